@@ -0,0 +1,101 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! Benchmarks for zune-imageprocs kernels
+//!
+//! Every kernel is run on 8 and 16 bit images, to catch regressions run
+//!
+//! ```text
+//! cargo bench --bench imageprocs -- --save-baseline main   # on a known good commit
+//! cargo bench --bench imageprocs                           # on the change
+//! cargo run --bin check_regressions -- main
+//! ```
+use std::time::Duration;
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use zune_image::core_filters::colorspace::grayscale::RgbToGrayScale;
+use zune_image::testing::synthetic_image;
+use zune_image::traits::OperationsTrait;
+use zune_imageprocs::box_blur::BoxBlur;
+use zune_imageprocs::convolve::Convolve;
+use zune_imageprocs::gaussian_blur::GaussianBlur;
+use zune_imageprocs::median::Median;
+use zune_imageprocs::sobel::Sobel;
+use zune_png::zune_core::bit_depth::BitDepth;
+use zune_png::zune_core::colorspace::ColorSpace;
+
+const WIDTH: usize = 1000;
+const HEIGHT: usize = 1000;
+
+/// Benchmark `operation` on an 8 and 16 bit image in `colorspace`
+fn bench_kernel(
+    c: &mut Criterion, name: &str, operation: &dyn OperationsTrait, colorspace: ColorSpace
+) {
+    let mut group = c.benchmark_group(format!("imageprocs: {name}"));
+    group.throughput(Throughput::Elements((WIDTH * HEIGHT) as u64));
+
+    for (depth, label) in [(BitDepth::Eight, "u8"), (BitDepth::Sixteen, "u16")] {
+        let image = synthetic_image(colorspace, depth, WIDTH, HEIGHT);
+
+        group.bench_function(label, |b| {
+            b.iter_batched(
+                || image.clone(),
+                |mut image| {
+                    operation.execute(&mut image).unwrap();
+                    image
+                },
+                BatchSize::LargeInput
+            )
+        });
+    }
+}
+
+fn bench_grayscale(c: &mut Criterion) {
+    bench_kernel(c, "grayscale", &RgbToGrayScale::default(), ColorSpace::RGB);
+}
+
+fn bench_box_blur(c: &mut Criterion) {
+    bench_kernel(c, "box blur", &BoxBlur::new(5), ColorSpace::Luma);
+}
+
+fn bench_gaussian_blur(c: &mut Criterion) {
+    bench_kernel(
+        c,
+        "gaussian blur",
+        &GaussianBlur::new(3.0),
+        ColorSpace::Luma
+    );
+}
+
+fn bench_convolve(c: &mut Criterion) {
+    // 3x3 sharpen
+    let weights = vec![0.0, -1.0, 0.0, -1.0, 5.0, -1.0, 0.0, -1.0, 0.0];
+    bench_kernel(
+        c,
+        "convolve 3x3",
+        &Convolve::new(weights, 1.0),
+        ColorSpace::Luma
+    );
+}
+
+fn bench_median(c: &mut Criterion) {
+    bench_kernel(c, "median", &Median::new(2), ColorSpace::Luma);
+}
+
+fn bench_sobel(c: &mut Criterion) {
+    bench_kernel(c, "sobel", &Sobel::new(), ColorSpace::Luma);
+}
+
+criterion_group!(name=benches;
+      config={
+      let c = Criterion::default();
+        c.measurement_time(Duration::from_secs(10))
+      };
+    targets=bench_grayscale, bench_box_blur, bench_gaussian_blur, bench_convolve, bench_median, bench_sobel);
+
+criterion_main!(benches);
@@ -0,0 +1,73 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! Micro benchmarks for the inflate match copy kernels, one group per
+//! offset/length class handled by `copy_match`
+
+use std::time::Duration;
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use zune_inflate::copy::{copy_match, MAX_COPY_SLOP};
+
+/// Number of matches copied per iteration
+const MATCHES: usize = 4096;
+
+fn bench_class(c: &mut Criterion, name: &str, offset: usize, length: usize) {
+    // space for the history, all matches and the slop of the last one
+    let mut buffer: Vec<u8> = (0..offset + MATCHES * length + MAX_COPY_SLOP)
+        .map(|x| (x % 251) as u8)
+        .collect();
+
+    let mut group = c.benchmark_group(format!("inflate copy: {name}"));
+    group.throughput(Throughput::Bytes((MATCHES * length) as u64));
+
+    group.bench_function(format!("offset {offset}, length {length}"), |b| {
+        b.iter(|| {
+            let mut dest_offset = offset;
+
+            for _ in 0..MATCHES {
+                copy_match(&mut buffer, dest_offset - offset, dest_offset, length);
+                dest_offset += length;
+            }
+            black_box(&buffer);
+        })
+    });
+}
+
+fn copy_rle(c: &mut Criterion) {
+    bench_class(c, "rle", 1, 258);
+}
+
+fn copy_small_offset(c: &mut Criterion) {
+    bench_class(c, "overlapping, small offset", 3, 258);
+}
+
+fn copy_medium_offset(c: &mut Criterion) {
+    bench_class(c, "8 byte chunks", 12, 258);
+}
+
+fn copy_short_match(c: &mut Criterion) {
+    bench_class(c, "short match", 64, 12);
+}
+
+fn copy_16_byte_chunks(c: &mut Criterion) {
+    bench_class(c, "16 byte chunks", 20, 258);
+}
+
+fn copy_long_match(c: &mut Criterion) {
+    bench_class(c, "32 byte chunks", 1024, 258);
+}
+
+criterion_group!(name=benches;
+      config={
+      let c = Criterion::default();
+        c.measurement_time(Duration::from_secs(5))
+      };
+    targets=copy_rle,copy_small_offset,copy_medium_offset,copy_short_match,copy_16_byte_chunks,copy_long_match);
+
+criterion_main!(benches);
@@ -0,0 +1,61 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! Fail if benchmarks got slower than a saved criterion baseline
+//!
+//! Usage: `check_regressions <baseline> [threshold]`, threshold defaults to `0.05` (5%)
+use std::process::ExitCode;
+
+use zune_benches::{criterion_dir, find_regressions};
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    let Some(baseline) = args.first() else {
+        eprintln!("Usage: check_regressions <baseline> [threshold]");
+        return ExitCode::FAILURE;
+    };
+    let threshold = match args.get(1).map(|x| x.parse::<f64>()) {
+        None => 0.05,
+        Some(Ok(threshold)) => threshold,
+        Some(Err(err)) => {
+            eprintln!("Invalid threshold: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match find_regressions(&criterion_dir(), baseline, threshold) {
+        Ok(regressions) if regressions.is_empty() => {
+            println!(
+                "No benchmark is more than {:.1}% slower than {baseline}",
+                threshold * 100.0
+            );
+            ExitCode::SUCCESS
+        }
+        Ok(regressions) => {
+            for regression in &regressions {
+                println!(
+                    "{}: {:.0}ns -> {:.0}ns ({:+.1}%)",
+                    regression.benchmark,
+                    regression.baseline_ns,
+                    regression.current_ns,
+                    regression.slowdown() * 100.0
+                );
+            }
+            println!(
+                "{} benchmarks regressed against {baseline}",
+                regressions.len()
+            );
+            ExitCode::FAILURE
+        }
+        Err(err) => {
+            eprintln!("{err}");
+            ExitCode::FAILURE
+        }
+    }
+}
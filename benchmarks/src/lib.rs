@@ -15,3 +15,104 @@ pub fn sample_path() -> PathBuf {
     // get parent path
     path.parent().unwrap().to_owned()
 }
+
+/// Directory criterion writes its results to
+///
+/// This respects `CARGO_TARGET_DIR`
+pub fn criterion_dir() -> PathBuf {
+    std::env::var_os("CARGO_TARGET_DIR")
+        .map_or_else(|| sample_path().join("target"), PathBuf::from)
+        .join("criterion")
+}
+
+/// A benchmark whose latest run is slower than the baseline
+#[derive(Debug, Clone)]
+pub struct Regression {
+    /// Benchmark path, e.g `imageprocs_ sobel/u8`
+    pub benchmark:   String,
+    /// Mean time of the baseline in nanoseconds
+    pub baseline_ns: f64,
+    /// Mean time of the latest run in nanoseconds
+    pub current_ns:  f64
+}
+
+impl Regression {
+    /// How much slower the latest run is, `0.1` is 10% slower
+    pub fn slowdown(&self) -> f64 {
+        self.current_ns / self.baseline_ns - 1.0
+    }
+}
+
+/// Compare the latest run of every benchmark against a saved baseline
+///
+/// Criterion stores results of a benchmark in `<criterion_dir>/<group>/<function>/`, with the
+/// latest run in `new/estimates.json` and baselines saved by `cargo bench -- --save-baseline <name>`
+/// in `<name>/estimates.json`.
+///
+/// Returns benchmarks whose mean time grew by more than `threshold` (e.g `0.05` for 5%),
+/// benchmarks missing from either run are skipped.
+///
+/// # Errors
+/// If the directory can't be read or an estimates file is invalid
+pub fn find_regressions(
+    criterion_dir: &Path, baseline: &str, threshold: f64
+) -> Result<Vec<Regression>, String> {
+    let mut regressions = vec![];
+    find_regressions_inner(
+        criterion_dir,
+        criterion_dir,
+        baseline,
+        threshold,
+        &mut regressions
+    )?;
+    regressions.sort_by(|a, b| a.benchmark.cmp(&b.benchmark));
+
+    Ok(regressions)
+}
+
+fn find_regressions_inner(
+    root: &Path, dir: &Path, baseline: &str, threshold: f64, regressions: &mut Vec<Regression>
+) -> Result<(), String> {
+    let entries = std::fs::read_dir(dir).map_err(|x| format!("Could not read {dir:?}: {x}"))?;
+
+    for entry in entries {
+        let path = entry.map_err(|x| x.to_string())?.path();
+
+        if !path.is_dir() {
+            continue;
+        }
+        let (current, saved) = (
+            path.join("new/estimates.json"),
+            path.join(baseline).join("estimates.json")
+        );
+
+        if current.exists() && saved.exists() {
+            let current_ns = mean_estimate(&current)?;
+            let baseline_ns = mean_estimate(&saved)?;
+
+            if current_ns > baseline_ns * (1.0 + threshold) {
+                let benchmark = path.strip_prefix(root).unwrap_or(&path);
+
+                regressions.push(Regression {
+                    benchmark: benchmark.to_string_lossy().to_string(),
+                    baseline_ns,
+                    current_ns
+                });
+            }
+        } else {
+            find_regressions_inner(root, &path, baseline, threshold, regressions)?;
+        }
+    }
+    Ok(())
+}
+
+/// Read the mean point estimate from a criterion `estimates.json` file
+fn mean_estimate(path: &Path) -> Result<f64, String> {
+    let contents = std::fs::read(path).map_err(|x| format!("Could not read {path:?}: {x}"))?;
+    let json: serde_json::Value =
+        serde_json::from_slice(&contents).map_err(|x| format!("Invalid json in {path:?}: {x}"))?;
+
+    json["mean"]["point_estimate"]
+        .as_f64()
+        .ok_or_else(|| format!("No mean estimate in {path:?}"))
+}
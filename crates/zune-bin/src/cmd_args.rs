@@ -11,11 +11,14 @@ use std::ffi::OsString;
 use clap::builder::PossibleValue;
 use clap::{value_parser, Arg, ArgAction, ArgGroup, Command, ValueEnum};
 
-use crate::cmd_args::arg_parsers::IColorSpace;
+use crate::cmd_args::arg_parsers::{parse_bytes, parse_color, parse_dimensions, IColorSpace};
 use crate::cmd_args::help_strings::{
-    AFTER_HELP, BOX_BLUR_HELP, BRIGHTEN_HELP, COLORSPACE_HELP, CROP_HELP, GAUSSIAN_BLUR_HELP,
-    THRESHOLD_HELP, TRANSPOSE_HELP
+    AFTER_HELP, ANALYZE_HELP, APPEND_HELP, BOX_BLUR_HELP, BRIGHTEN_HELP, COLORSPACE_HELP,
+    COMPARE_HELP, CROP_HELP, GAUSSIAN_BLUR_HELP, HASH_HELP, KEEP_HELP, MONTAGE_HELP,
+    SPRITESHEET_HELP, STACK_HELP, STRIP_HELP, THRESHOLD_HELP, TONEMAP_HELP, TRANSPOSE_HELP,
+    WATERMARK_HELP
 };
+use crate::cmd_parsers::chain::chain_help;
 
 pub mod arg_parsers;
 pub mod help_strings;
@@ -40,6 +43,29 @@ impl ValueEnum for MmapOptions {
         })
     }
 }
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum MetadataOptions {
+    All,
+    Exif,
+    Icc,
+    Text
+}
+
+impl ValueEnum for MetadataOptions {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[Self::All, Self::Exif, Self::Icc, Self::Text]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(match self {
+            Self::All => PossibleValue::new("all"),
+            Self::Exif => PossibleValue::new("exif"),
+            Self::Icc => PossibleValue::new("icc"),
+            Self::Text => PossibleValue::new("text")
+        })
+    }
+}
 #[rustfmt::skip]
 pub fn create_cmd_args() -> Command {
     let (options_args, option_group) = add_operations();
@@ -49,6 +75,7 @@ pub fn create_cmd_args() -> Command {
 
     Command::new("zune")
         .after_help(AFTER_HELP)
+        .subcommand_negates_reqs(true)
         .author("Caleb Etemesi")
         .version(env!("CARGO_PKG_VERSION"))
         .next_line_help(false)
@@ -56,10 +83,36 @@ pub fn create_cmd_args() -> Command {
         .arg(Arg::new("in")
             .short('i')
             .help("Input file to read data from")
+            .long_help("Input file to read data from\nTakes multiple files, e.g -i *.png, or can be repeated to process many files with the same operations, use --out-template to give each output its own name")
             .long("input")
+            .num_args(1..)
+            .action(ArgAction::Append)
+            .value_parser(value_parser!(OsString))
+            .required_unless_present_any(["from-zip", "watch"]))
+        .arg(Arg::new("from-zip")
+            .long("from-zip")
+            .value_name("archive:entry")
+            .help("Read the input from an entry of a ZIP or CBZ archive")
+            .long_help("Read the input from an entry of a ZIP or CBZ archive\nThe entry name is everything after the last `:`, e.g --from-zip comic.cbz:pages/01.png\nOnly stored and deflate compressed entries are supported")
             .action(ArgAction::Set)
             .value_parser(value_parser!(OsString))
-            .required(true))
+            .conflicts_with("in"))
+        .arg(Arg::new("watch")
+            .long("watch")
+            .value_name("DIR")
+            .help("Process files as they are added to or changed in DIR, until interrupted")
+            .long_help("Process files as they are added to or changed in DIR, until interrupted\nFiles already in DIR are processed on start, a file is picked up once its size and modification time stay the same between two scans so that files still being copied are skipped. Hidden files and outputs written to DIR are ignored\nRequires --out-template to name outputs")
+            .value_parser(value_parser!(OsString))
+            .conflicts_with_all(["in", "from-zip"])
+            .requires("out-template"))
+        .arg(Arg::new("watch-interval")
+            .long("watch-interval")
+            .value_name("ms")
+            .help_heading("ADVANCED")
+            .help("Time between scans of the --watch directory, in milliseconds")
+            .value_parser(value_parser!(u64).range(1..))
+            .default_value("1000")
+            .requires("watch"))
         .arg(Arg::new("out")
             .short('o')
             .long("out")
@@ -67,6 +120,27 @@ pub fn create_cmd_args() -> Command {
             .action(ArgAction::Append)
             .value_parser(value_parser!(OsString))
         )
+        .arg(Arg::new("chain")
+            .value_name("op name[:args]")
+            .help("Operations to run in order, e.g op blur:sigma=2 op resize:800x600 op grayscale")
+            .long_help(chain_help())
+            .num_args(1..)
+            .action(ArgAction::Append)
+            .value_parser(value_parser!(String)))
+        .arg(Arg::new("out-template")
+            .long("out-template")
+            .value_name("template")
+            .help("Name outputs of every input after a template, e.g '{stem}_{width}x{height}.{ext}'")
+            .long_help("Name outputs of every input after a template, e.g '{stem}_{width}x{height}.{ext}'\n{stem} is the input file name without its extension, {ext} its extension, {format} the preferred extension of the input format, {width} and {height} the output dimensions and {index} the position of the input, starting at 0\nThe output format is picked from the extension of the expanded name, use {{ and }} for literal braces")
+            .value_parser(value_parser!(String))
+            .conflicts_with("out"))
+        .arg(Arg::new("mipmaps")
+            .long("mipmaps")
+            .value_name("filter")
+            .help("Write a mipmap chain instead of a single image for every output")
+            .long_help("Write a mipmap chain instead of a single image for every output\nEach level is half the size of the previous one down to 1x1, -o out.png writes out-0.png (full size), out-1.png and so on.\n`linear` averages in linear light weighted by alpha, `box` averages stored values")
+            .requires("out")
+            .value_parser(["box", "linear"]))
         .arg(Arg::new("mmap")
             .long("mmap")
             .help_heading("ADVANCED")
@@ -74,6 +148,33 @@ pub fn create_cmd_args() -> Command {
             .help("Influence the use of memory maps")
             .long_help("Change use of memory maps and how they are used for decoding.\nMemory maps are preferred for large images to keep memory usage low.")
             .value_parser(value_parser!(MmapOptions)))
+        .arg(Arg::new("config")
+            .long("config")
+            .value_name("FILE")
+            .help_heading("ADVANCED")
+            .help("Read default options from FILE instead of ~/.config/zune/config.toml")
+            .long_help("Read default options from FILE instead of ~/.config/zune/config.toml\nThe config sets defaults for encoder options, thread counts, strictness and metadata policy, flags given on the command line take precedence, e.g\n\n  threads = 8\n  strict = true\n  [encode]\n  quality = 90\n  progressive = true\n  [metadata]\n  strip = [\"exif\", \"text\"]\n\nTop level keys are threads, strict, allow-partial, safe, max-memory, max-width and max-height, [encode] takes quality, effort, threads and progressive and [metadata] strip or keep\nSwitches turned on by the config are turned off with e.g --strict=false")
+            .value_parser(value_parser!(OsString)))
+        .arg(Arg::new("report-memory")
+            .long("report-memory")
+            .help_heading("ADVANCED")
+            .help("Report memory used by each decoder, operation and encoder")
+            .long_help("Report memory used by each decoder, operation and encoder\nRequires zune to be built with the `mem-stats` feature")
+            .action(ArgAction::SetTrue))
+        .arg(Arg::new("timings")
+            .long("timings")
+            .help_heading("ADVANCED")
+            .help("Report time spent in each decoding stage")
+            .long_help("Report time spent in each decoding stage, e.g header parsing, inflating and unfiltering\nOnly the png and jpeg decoders record stages")
+            .action(ArgAction::SetTrue))
+        .arg(Arg::new("report")
+            .long("report")
+            .value_name("format")
+            .help_heading("ADVANCED")
+            .help("Print a machine readable result for every input on stdout")
+            .long_help("Print a machine readable result for every input on stdout\nThe report lists the input, outputs written, dimensions, operations applied, time taken, warnings and error of every file, log output is disabled. Files after a failed one are still processed\nzune exits with 0 if all files succeeded, 1 if some failed and 2 on fatal errors, e.g invalid arguments")
+            .value_parser(["json"])
+            .conflicts_with_all(["probe", "dry-run", "watch", "stats", "timings", "report-memory", "view"]))
         .arg(Arg::new("all-yes")
             .long("yes")
             .short('y')
@@ -83,6 +184,49 @@ pub fn create_cmd_args() -> Command {
             .long("view")
             .help("View image effects after carrying out effects")
             .action(ArgAction::SetTrue))
+        .arg(Arg::new("dry-run")
+            .long("dry-run")
+            .help("Print the pipeline plan without processing pixels")
+            .long_help("Print the pipeline plan without processing pixels\nOnly image headers are decoded, the operations, predicted colorspaces and depths and encoders are printed")
+            .action(ArgAction::SetTrue))
+        .arg(Arg::new("stats")
+            .long("stats")
+            .help("Print per channel statistics of the processed image")
+            .long_help("Print per channel statistics of the processed image\nThe minimum, maximum, mean and standard deviation of every channel are computed after all operations ran, values are in the range of the image depth")
+            .action(ArgAction::SetTrue))
+        .arg(Arg::new("dump-stages")
+            .long("dump-stages")
+            .value_name("DIR")
+            .help_heading("ADVANCED")
+            .help("Write the image as PNG before and after every operation to DIR, for debugging")
+            .long_help("Write the image as PNG before and after every operation to DIR, for debugging\nFiles are named <image>-<stage>-<operation>.png, with multiple inputs each input gets a sub-directory named after it")
+            .value_parser(value_parser!(OsString)))
+        .arg(Arg::new("record-history")
+            .long("record-history")
+            .help_heading("ADVANCED")
+            .help("Record the operations and their parameters in the output metadata")
+            .long_help("Record the operations and their parameters in the output metadata\nThe history is stored as a text entry, written by formats supporting text metadata (e.g PNG tEXt chunks), and appended to if the input already carries one")
+            .action(ArgAction::SetTrue))
+        .arg(Arg::new("linear-light")
+            .long("linear-light")
+            .help_heading("ADVANCED")
+            .help("Run operations on linear light instead of sRGB encoded values")
+            .long_help("Run operations on linear light instead of sRGB encoded values\nBlurring and resizing in linear light keeps edges and fine detail from darkening, images are processed as floats and converted back to their original depth before encoding")
+            .action(ArgAction::SetTrue))
+        .arg(Arg::new("orientation")
+            .long("orientation")
+            .help_heading("ADVANCED")
+            .help("What to do with the exif orientation of inputs")
+            .long_help("What to do with the exif orientation of inputs\n`preserve` keeps pixels as stored and writes the orientation tag to formats supporting exif (JPEG and PNG), `apply` rotates pixels upright after decoding, before any operation, for viewers that ignore exif")
+            .value_parser(["preserve", "apply"])
+            .default_value("preserve"))
+        .arg(Arg::new("threads")
+            .long("threads")
+            .help_heading("ADVANCED")
+            .help("Maximum number of threads to use for decoding and image operations")
+            .long_help("Maximum number of threads to use for decoding and image operations\nThe budget is shared by all parallel work, 1 disables threading, defaults to the number of cores")
+            .value_name("N")
+            .value_parser(value_parser!(usize)))
         .arg(Arg::new("probe")
             .long("probe")
             .help("Probe file for details")
@@ -102,6 +246,189 @@ pub fn create_cmd_args() -> Command {
         .group(encode_group)
         .args(image_args)
         .group(image_args_group)
+        .subcommands(add_subcommands())
+}
+
+fn add_subcommands() -> Vec<Command> {
+    vec![Command::new("analyze")
+        .about("Show the deflate blocks of a compressed stream")
+        .long_about(ANALYZE_HELP)
+        .arg(
+            Arg::new("file")
+                .help("File holding the stream")
+                .required(true)
+                .value_parser(value_parser!(OsString))
+        )
+        .arg(
+            Arg::new("idat")
+                .long("idat")
+                .help("Analyze the image data of a png file")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("raw")
+                .long("raw")
+                .help("The file is a bare deflate stream instead of a zlib stream")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("idat")
+        ),
+        Command::new("compare")
+        .about("Compare two images")
+        .long_about(COMPARE_HELP)
+        .arg(
+            Arg::new("first")
+                .help("First image to compare")
+                .required(true)
+                .value_parser(value_parser!(OsString))
+        )
+        .arg(
+            Arg::new("second")
+                .help("Second image to compare")
+                .required(true)
+                .value_parser(value_parser!(OsString))
+        )
+        .arg(
+            Arg::new("visual")
+                .long("visual")
+                .help("Write an image highlighting the differences, requires --out")
+                .action(ArgAction::SetTrue)
+                .requires("out")
+        )
+        .arg(
+            Arg::new("out")
+                .short('o')
+                .long("out")
+                .help("File to write the visual diff to")
+                .value_parser(value_parser!(OsString))
+        )
+        .arg(
+            Arg::new("tolerance")
+                .long("tolerance")
+                .help("Maximum difference between two pixels before they are highlighted, used with --visual")
+                .default_value("0")
+                .value_parser(value_parser!(f32))
+        ),
+        Command::new("hash")
+        .about("Compute perceptual hashes of images")
+        .long_about(HASH_HELP)
+        .arg(
+            Arg::new("files")
+                .help("Images to hash")
+                .required(true)
+                .num_args(1..)
+                .value_parser(value_parser!(OsString))
+        )
+        .arg(
+            Arg::new("algorithm")
+                .long("algorithm")
+                .help("Hashing algorithm to use")
+                .default_value("perceptual")
+                .value_parser(["average", "difference", "perceptual"])
+        ),
+        Command::new("formats")
+        .about("List image formats supported by this build"),
+        Command::new("montage")
+        .about("Lay out multiple images in a grid")
+        .long_about(MONTAGE_HELP)
+        .arg(
+            Arg::new("files")
+                .help("Images to lay out, in row order")
+                .required(true)
+                .num_args(1..)
+                .value_parser(value_parser!(OsString))
+        )
+        .arg(
+            Arg::new("columns")
+                .long("columns")
+                .help("Number of columns, 0 makes the grid as square as possible")
+                .default_value("0")
+                .value_parser(value_parser!(usize))
+        )
+        .arg(
+            Arg::new("padding")
+                .long("padding")
+                .help("Pixels between cells and around the grid")
+                .default_value("0")
+                .value_parser(value_parser!(usize))
+        )
+        .arg(
+            Arg::new("background")
+                .long("background")
+                .help("Background color")
+                .default_value("#ffffff")
+                .value_parser(parse_color)
+        )
+        .arg(
+            Arg::new("out")
+                .short('o')
+                .long("out")
+                .help("File to write the montage to")
+                .required(true)
+                .value_parser(value_parser!(OsString))
+        ),
+        Command::new("spritesheet")
+        .about("Pack multiple images into a sprite sheet")
+        .long_about(SPRITESHEET_HELP)
+        .arg(
+            Arg::new("files")
+                .help("Images to pack")
+                .required(true)
+                .num_args(1..)
+                .value_parser(value_parser!(OsString))
+        )
+        .arg(
+            Arg::new("max-width")
+                .long("max-width")
+                .help("Maximum sheet width, 0 makes the sheet roughly square")
+                .default_value("0")
+                .value_parser(value_parser!(usize))
+        )
+        .arg(
+            Arg::new("padding")
+                .long("padding")
+                .help("Transparent pixels between images and around the sheet")
+                .default_value("0")
+                .value_parser(value_parser!(usize))
+        )
+        .arg(
+            Arg::new("manifest")
+                .long("manifest")
+                .help("File to write the JSON manifest to, defaults to the output with a .json extension")
+                .value_parser(value_parser!(OsString))
+        )
+        .arg(
+            Arg::new("out")
+                .short('o')
+                .long("out")
+                .help("File to write the sprite sheet to")
+                .required(true)
+                .value_parser(value_parser!(OsString))
+        ),
+        Command::new("stack")
+        .about("Merge multiple exposures into one image")
+        .long_about(STACK_HELP)
+        .arg(
+            Arg::new("files")
+                .help("Images to stack")
+                .required(true)
+                .num_args(1..)
+                .value_parser(value_parser!(OsString))
+        )
+        .arg(
+            Arg::new("mode")
+                .long("mode")
+                .help("How pixels are merged")
+                .default_value("mean")
+                .value_parser(["mean", "median", "max", "min"])
+        )
+        .arg(
+            Arg::new("out")
+                .short('o')
+                .long("out")
+                .help("File to write the stacked image to")
+                .required(true)
+                .value_parser(value_parser!(OsString))
+        ),]
 }
 
 fn add_logging_options() -> [Arg; 4] {
@@ -151,19 +478,46 @@ fn add_settings() -> Vec<Arg> {
             .help("Maximum height of images allowed")
             .default_value("37268")
             .value_parser(value_parser!(usize)),
-        Arg::new("strict")
+        Arg::new("max-dimensions")
+            .long("max-dimensions")
+            .help_heading(HELP_HEADING)
+            .value_name("WxH")
+            .help("Maximum width and height of images allowed, e.g 8192x8192")
+            .long_help("Maximum width and height of images allowed, e.g 8192x8192\nReplaces --max-width and --max-height, images exceeding either are rejected while reading headers")
+            .conflicts_with_all(["max-width", "max-height"])
+            .value_parser(parse_dimensions),
+        Arg::new("max-memory")
+            .long("max-memory")
+            .help_heading(HELP_HEADING)
+            .value_name("BYTES")
+            .help("Maximum memory a decoded image may use, accepts K, M and G suffixes")
+            .long_help("Maximum memory a decoded image may use, accepts K, M and G suffixes\nThe size is computed from the dimensions, channels and bit depth in the image headers, so images that would expand past it are rejected before decoding.\nThe default guards batch runs over untrusted files, raise it for very large images")
+            .default_value("1G")
+            .value_parser(parse_bytes),
+        switch(Arg::new("strict"))
             .long("strict")
             .help_heading(HELP_HEADING)
             .help("Treat most warnings as errors")
-            .action(ArgAction::SetTrue)
-            .default_value("false"),
-        Arg::new("safe")
+            .long_help("Treat most warnings as errors\nThis includes writing images in colorspaces the output format doesn't support, which are otherwise converted to the nearest supported colorspace"),
+        switch(Arg::new("allow-partial"))
+            .long("allow-partial")
+            .help_heading(HELP_HEADING)
+            .help("Decode the available part of truncated png and jpeg images instead of failing")
+            .long_help("Decode the available part of truncated png and jpeg images instead of failing\nParts of the image past the end of the file are black, even in strict mode"),
+        Arg::new("explain-errors")
+            .long("explain-errors")
+            .help_heading(HELP_HEADING)
+            .help("Print the bytes around the position decoding failed at")
+            .long_help("Print the bytes around the position decoding failed at\nA hexdump of BYTES before and after the failing byte (32 if not given) is printed with decoding errors, currently for png images")
+            .value_name("BYTES")
+            .num_args(0..=1)
+            .default_missing_value("32")
+            .value_parser(value_parser!(usize)),
+        switch(Arg::new("safe"))
             .long("safe")
             .help_heading(HELP_HEADING)
             .help("Do not use unsafe paths routines where possible")
             .long_help("Do not use unsafe paths routines where possible\nMainly used for debugging and testing purposes")
-            .action(ArgAction::SetTrue)
-            .default_value("false")
     ];
     // list them in order
     args.sort_unstable_by(|x, y| x.get_id().cmp(y.get_id()));
@@ -268,12 +622,33 @@ fn add_operations() -> (Vec<Arg>, ArgGroup) {
             .value_parser(value_parser!(usize))
             .help("Resize an image")
             .group(GROUP),
+        Arg::new("resize-premultiply")
+            .long("resize-premultiply")
+            .help_heading(HELP_HEADING)
+            .help("Premultiply colors by alpha while resizing, avoids dark fringes around transparent areas")
+            .action(ArgAction::SetTrue)
+            .requires("resize"),
+        Arg::new("resize-linear")
+            .long("resize-linear")
+            .help_heading(HELP_HEADING)
+            .help("Resize in linear light instead of sRGB, avoids darkened edges")
+            .action(ArgAction::SetTrue)
+            .requires("resize"),
         Arg::new("depth")
             .long("depth")
             .help_heading(HELP_HEADING)
             .help("Change image depth")
             .value_parser(value_parser!(u8))
             .group(GROUP),
+        Arg::new("dpi")
+            .long("dpi")
+            .value_name("x[,y]")
+            .help_heading(HELP_HEADING)
+            .help("Set the pixel density in dots per inch, written by encoders that store it")
+            .value_delimiter(',')
+            .num_args(1..=2)
+            .value_parser(value_parser!(f32))
+            .group(GROUP),
         Arg::new("auto-orient")
             .long("auto-orient")
             .help_heading(HELP_HEADING)
@@ -302,7 +677,185 @@ fn add_operations() -> (Vec<Arg>, ArgGroup) {
             .help_heading(HELP_HEADING)
             .allow_negative_numbers(true)
             .help("Adjust image brightness")
+            .value_parser(value_parser!(f32)),
+        Arg::new("vignette-correct")
+            .long("vignette-correct")
+            .value_name("strength")
+            .help_heading(HELP_HEADING)
+            .allow_negative_numbers(true)
+            .help("Brighten image corners to correct lens vignetting, negative values darken them")
             .value_parser(value_parser!(f32))
+            .group(GROUP),
+        Arg::new("channel-shift")
+            .long("channel-shift")
+            .value_names(["dx_r", "dy_r", "dx_b", "dy_b"])
+            .help_heading(HELP_HEADING)
+            .allow_negative_numbers(true)
+            .help("Shift red and blue channels by pixels to correct chromatic aberration")
+            .value_parser(value_parser!(f32))
+            .group(GROUP),
+        Arg::new("extent")
+            .long("extent")
+            .value_name("WxH")
+            .help_heading(HELP_HEADING)
+            .help("Place the image on a canvas of this size, cropping or padding it as needed")
+            .value_parser(parse_dimensions)
+            .group(GROUP),
+        Arg::new("extent-gravity")
+            .long("extent-gravity")
+            .value_name("gravity")
+            .help_heading(HELP_HEADING)
+            .help("Where the image is placed on the extent canvas, e.g north-west or south [default: center]")
+            .requires("extent")
+            .value_parser(value_parser!(String)),
+        Arg::new("extent-background")
+            .long("extent-background")
+            .value_name("color")
+            .help_heading(HELP_HEADING)
+            .help("Color of the extent canvas around the image (#rrggbb[aa] or r,g,b[,a]) [default: white]")
+            .requires("extent")
+            .value_parser(parse_color),
+        Arg::new("pixelate")
+            .long("pixelate")
+            .value_name("size")
+            .help_heading(HELP_HEADING)
+            .help("Replace blocks of size x size pixels with their mean, a mosaic effect")
+            .value_parser(value_parser!(usize))
+            .group(GROUP),
+        Arg::new("halftone")
+            .long("halftone")
+            .value_name("size")
+            .help_heading(HELP_HEADING)
+            .help("Ordered halftone with a size x size Bayer pattern, size is 2, 4, 8 or 16")
+            .value_parser(value_parser!(usize))
+            .group(GROUP),
+        Arg::new("halftone-levels")
+            .long("halftone-levels")
+            .value_name("levels")
+            .help_heading(HELP_HEADING)
+            .help("Number of levels every channel is reduced to by --halftone [default: 2]")
+            .requires("halftone")
+            .value_parser(value_parser!(usize)),
+        Arg::new("flatten-alpha")
+            .long("flatten-alpha")
+            .value_name("color")
+            .help_heading(HELP_HEADING)
+            .help("Composite transparent images onto a background color (#rrggbb or r,g,b), removing alpha")
+            .value_parser(parse_color)
+            .group(GROUP),
+        Arg::new("chroma-key")
+            .long("chroma-key")
+            .value_name("color")
+            .help_heading(HELP_HEADING)
+            .help("Make pixels close to a backdrop color (#rrggbb or r,g,b) transparent, e.g a green screen")
+            .value_parser(parse_color)
+            .group(GROUP),
+        Arg::new("chroma-key-tolerance")
+            .long("chroma-key-tolerance")
+            .value_name("distance")
+            .help_heading(HELP_HEADING)
+            .help("Chroma distance below which pixels become transparent, from 0.0 to about 1.0 [default: 0.15]")
+            .requires("chroma-key")
+            .value_parser(value_parser!(f32)),
+        Arg::new("chroma-key-softness")
+            .long("chroma-key-softness")
+            .value_name("distance")
+            .help_heading(HELP_HEADING)
+            .help("Width of the transition from transparent to opaque after the tolerance [default: 0.1]")
+            .requires("chroma-key")
+            .value_parser(value_parser!(f32)),
+        Arg::new("chroma-key-spill")
+            .long("chroma-key-spill")
+            .help_heading(HELP_HEADING)
+            .help("Remove backdrop color spill from the pixels that are kept")
+            .requires("chroma-key")
+            .action(ArgAction::SetTrue),
+        Arg::new("watermark")
+            .long("watermark")
+            .value_name("file[:gravity[:opacity]]")
+            .help_heading(HELP_HEADING)
+            .help("Draw an image over the input, e.g a logo")
+            .long_help(WATERMARK_HELP)
+            .value_parser(value_parser!(String))
+            .group(GROUP),
+        Arg::new("watermark-tile")
+            .long("watermark-tile")
+            .value_name("spacing")
+            .help_heading(HELP_HEADING)
+            .help("Repeat the watermark across the image with spacing pixels between copies")
+            .requires("watermark")
+            .value_parser(value_parser!(usize)),
+        Arg::new("watermark-margin")
+            .long("watermark-margin")
+            .value_name("pixels")
+            .help_heading(HELP_HEADING)
+            .help("Distance between the watermark and the edges it is placed against")
+            .requires("watermark")
+            .value_parser(value_parser!(usize)),
+        Arg::new("lut")
+            .long("lut")
+            .value_name("file")
+            .help_heading(HELP_HEADING)
+            .help("Color grade the image with a 3D LUT from an Adobe .cube file")
+            .value_parser(value_parser!(OsString))
+            .group(GROUP),
+        Arg::new("tonemap")
+            .long("tonemap")
+            .help_heading(HELP_HEADING)
+            .help("Tone map an HDR (float) image to 8 bit")
+            .long_help(TONEMAP_HELP)
+            .value_parser(["reinhard", "aces", "hable"])
+            .group(GROUP),
+        Arg::new("tonemap-exposure")
+            .long("tonemap-exposure")
+            .value_name("stops")
+            .help_heading(HELP_HEADING)
+            .help("Exposure adjustment in stops applied before tone mapping")
+            .allow_negative_numbers(true)
+            .requires("tonemap")
+            .value_parser(value_parser!(f32)),
+        Arg::new("tonemap-white")
+            .long("tonemap-white")
+            .value_name("white")
+            .help_heading(HELP_HEADING)
+            .help("Smallest value mapped to white when tone mapping")
+            .requires("tonemap")
+            .value_parser(value_parser!(f32)),
+        Arg::new("append-horizontal")
+            .long("append-horizontal")
+            .value_name("file")
+            .help_heading(HELP_HEADING)
+            .help("Append an image to the right of the input, can be repeated")
+            .long_help(APPEND_HELP)
+            .action(ArgAction::Append)
+            .value_parser(value_parser!(OsString))
+            .group(GROUP),
+        Arg::new("append-vertical")
+            .long("append-vertical")
+            .value_name("file")
+            .help_heading(HELP_HEADING)
+            .help("Append an image below the input, can be repeated")
+            .long_help(APPEND_HELP)
+            .action(ArgAction::Append)
+            .value_parser(value_parser!(OsString))
+            .group(GROUP),
+        Arg::new("append-scale")
+            .long("append-scale")
+            .help_heading(HELP_HEADING)
+            .help("Scale appended images to the height (or width) of the input instead of padding them")
+            .action(ArgAction::SetTrue),
+        Arg::new("append-spacing")
+            .long("append-spacing")
+            .value_name("pixels")
+            .help_heading(HELP_HEADING)
+            .help("Pixels between appended images")
+            .value_parser(value_parser!(usize)),
+        Arg::new("append-background")
+            .long("append-background")
+            .value_name("color")
+            .help_heading(HELP_HEADING)
+            .help("Color of padding and spacing around appended images (#rrggbb or r,g,b) [default: white]")
+            .value_parser(parse_color)
     ];
     args.sort_unstable_by(|x, y| x.get_id().cmp(y.get_id()));
 
@@ -340,16 +893,29 @@ fn add_encode_options() -> (Vec<Arg>, ArgGroup) {
             .help("Effort to put into encoding")
             .group(GROUP)
             .help_heading(HELP_HEADING),
-        Arg::new("progressive")
+        switch(Arg::new("progressive"))
             .long("progressive")
             .help("Encode images using progressive encoding where supported")
-            .action(ArgAction::SetTrue)
             .group(GROUP)
             .help_heading(HELP_HEADING),
         Arg::new("strip")
             .long("strip")
             .help("Strip metadata when encoding images (where supported)")
-            .action(ArgAction::SetTrue)
+            .long_help(STRIP_HELP)
+            .num_args(0..=1)
+            .default_missing_value("all")
+            .value_delimiter(',')
+            .action(ArgAction::Append)
+            .value_parser(value_parser!(MetadataOptions))
+            .group(GROUP)
+            .help_heading(HELP_HEADING),
+        Arg::new("keep")
+            .long("keep")
+            .help("Keep only the given metadata when encoding images")
+            .long_help(KEEP_HELP)
+            .value_delimiter(',')
+            .action(ArgAction::Append)
+            .value_parser(value_parser!(MetadataOptions))
             .group(GROUP)
             .help_heading(HELP_HEADING)
     ];
@@ -387,6 +953,20 @@ fn add_filters() -> (Vec<Arg>, ArgGroup) {
             .value_names(["sigma", "threshold"])
             .value_parser(value_parser!(f32))
             .group(GROUP),
+        Arg::new("denoise")
+            .long("denoise")
+            .help("Remove noise with non-local means, strength is about the noise deviation in 0..1 units")
+            .help_heading(GROUP)
+            .value_name("strength")
+            .value_parser(value_parser!(f32))
+            .group(GROUP),
+        Arg::new("smart-sharpen")
+            .long("smart-sharpen")
+            .help("Sharpen edges while leaving flat and noisy areas alone")
+            .help_heading(GROUP)
+            .value_names(["strength", "radius", "edge_threshold"])
+            .value_parser(value_parser!(f32))
+            .group(GROUP),
         Arg::new("statistic")
             .long("statistic")
             .help("Replace each pixel with corresponding statistic from the neighbourhood")
@@ -440,12 +1020,31 @@ fn add_filters() -> (Vec<Arg>, ArgGroup) {
 fn add_image_specific_settings() -> (Vec<Arg>, ArgGroup) {
     static GROUP: &str = "Image Format Settings";
 
-    let mut args = [Arg::new("jpeg-grayscale")
-        .long("jpeg-grayscale")
-        .help("Load jpeg images as grayscale")
-        .action(ArgAction::SetTrue)
-        .help_heading(GROUP)
-        .group(GROUP)];
+    let mut args = [
+        Arg::new("jpeg-grayscale")
+            .long("jpeg-grayscale")
+            .help("Load jpeg images as grayscale")
+            .action(ArgAction::SetTrue)
+            .help_heading(GROUP)
+            .group(GROUP),
+        Arg::new("thumbnail")
+            .long("thumbnail")
+            .value_name("SIZE")
+            .help("Load jpeg images at 1/2, 1/4 or 1/8 scale, keeping the largest side at least SIZE")
+            .long_help("Load jpeg images at 1/2, 1/4 or 1/8 scale, keeping the largest side at least SIZE\nThe full image is still decoded, but later operations only see the smaller image, combine it with --resize to get an exact size")
+            .value_parser(value_parser!(usize))
+            .help_heading(GROUP)
+            .group(GROUP),
+        Arg::new("crop-on-decode")
+            .long("crop-on-decode")
+            .help("Only load a region of jpeg images")
+            .long_help("Only load a region of jpeg images\nArguments are width height x y, like --crop, the region is taken before --thumbnail scaling")
+            .num_args(4)
+            .value_names(["width", "height", "x", "y"])
+            .value_parser(value_parser!(usize))
+            .help_heading(GROUP)
+            .group(GROUP)
+    ];
 
     let arg_group = ArgGroup::new(GROUP)
         .args(args.iter().map(|x| x.get_id()))
@@ -456,6 +1055,20 @@ fn add_image_specific_settings() -> (Vec<Arg>, ArgGroup) {
     (args.to_vec(), arg_group)
 }
 
+/// Make `arg` a switch that is turned on with `--flag` and set with `--flag=false`
+///
+/// The config file changes defaults, so switches it turns on must be
+/// possible to turn off again from the command line
+fn switch(arg: Arg) -> Arg {
+    arg.action(ArgAction::Set)
+        .value_name("bool")
+        .num_args(0..=1)
+        .require_equals(true)
+        .default_missing_value("true")
+        .default_value("false")
+        .value_parser(value_parser!(bool))
+}
+
 #[test]
 fn verify_cli() {
     create_cmd_args().debug_assert();
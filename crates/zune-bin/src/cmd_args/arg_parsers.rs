@@ -71,3 +71,61 @@ impl std::str::FromStr for IColorSpace {
         Err(format!("Invalid variant: {s}"))
     }
 }
+
+/// Parse dimensions given as `WxH`, e.g `1920x1080`
+pub fn parse_dimensions(s: &str) -> Result<(usize, usize), String> {
+    let error = || format!("Invalid dimensions {s}, expected WxH, e.g 1920x1080");
+
+    let (width, height) = s.split_once(['x', 'X']).ok_or_else(error)?;
+
+    let width = width.trim().parse::<usize>().map_err(|_| error())?;
+    let height = height.trim().parse::<usize>().map_err(|_| error())?;
+
+    Ok((width, height))
+}
+
+/// Parse a number of bytes with an optional `K`, `M` or `G` suffix,
+/// e.g `512M`, suffixes are powers of 1024
+pub fn parse_bytes(s: &str) -> Result<usize, String> {
+    let error = || format!("Invalid size {s}, expected a number of bytes, e.g 4096, 512M or 2G");
+
+    let s = s.trim();
+    let (number, shift) = match s.char_indices().last() {
+        Some((i, 'k' | 'K')) => (&s[..i], 10),
+        Some((i, 'm' | 'M')) => (&s[..i], 20),
+        Some((i, 'g' | 'G')) => (&s[..i], 30),
+        _ => (s, 0)
+    };
+    let number = number.trim().parse::<usize>().map_err(|_| error())?;
+
+    number.checked_mul(1 << shift).ok_or_else(error)
+}
+
+/// Parse a color given as `#rrggbb`, `#rrggbbaa` or `r,g,b[,a]`
+///
+/// Alpha defaults to 255 (opaque) when not given
+pub fn parse_color(s: &str) -> Result<[u8; 4], String> {
+    let error = || format!("Invalid color {s}, expected #rrggbb, #rrggbbaa or r,g,b[,a]");
+
+    let values: Vec<u8> = if let Some(hex) = s.strip_prefix('#') {
+        if !matches!(hex.len(), 6 | 8) || !hex.is_ascii() {
+            return Err(error());
+        }
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16))
+            .collect::<Result<_, _>>()
+            .map_err(|_| error())?
+    } else {
+        s.split(',')
+            .map(|x| x.trim().parse::<u8>())
+            .collect::<Result<_, _>>()
+            .map_err(|_| error())?
+    };
+
+    match values[..] {
+        [r, g, b] => Ok([r, g, b, 255]),
+        [r, g, b, a] => Ok([r, g, b, a]),
+        _ => Err(error())
+    }
+}
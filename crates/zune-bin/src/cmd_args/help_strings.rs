@@ -67,3 +67,139 @@ The implementation does not produce a true gaussian blur which involves convolvi
 a 2D kernel over the image as that is really slow, but we approximate it using very
 fast 1D box blurs.
 ";
+
+pub static ANALYZE_HELP: &str = "Show the deflate blocks of a compressed stream
+
+Walks the blocks of a zlib stream, or a bare deflate stream with --raw, and prints
+for each block its type (stored, static or dynamic Huffman codes), where it starts
+in bits, its compressed and uncompressed sizes, the number of literals and matches,
+the number of literal/length and offset codes in use and the bits spent on the
+block header describing them.
+
+With --idat the stream is taken from the IDAT chunks of a png file.
+
+Nothing is decompressed, this is meant for inspecting what an encoder produced.
+
+Example: zune analyze image.png --idat
+";
+
+pub static COMPARE_HELP: &str = "Compare two images
+
+Decodes both images and reports whether their pixels are identical.
+
+With --visual, an image is written to the --out file where pixels that differ
+by more than --tolerance are painted red and identical pixels are dimmed.
+
+Both images must have the same dimensions, depth and colorspace.
+
+Example: zune compare --visual a.png b.png -o diff.png
+";
+
+pub static HASH_HELP: &str = "Compute perceptual hashes of images
+
+Prints a 64 bit hash for every image, images that look alike have hashes
+differing in only a few bits, which is useful for finding duplicates.
+
+Supported algorithms are
+\taverage: Fastest, compares pixels to the mean brightness
+\tdifference: Compares brightness of neighbouring pixels
+\tperceptual: Uses the discrete cosine transform, most robust
+
+Example: zune hash --algorithm difference a.png b.jpg
+";
+
+pub static STRIP_HELP: &str = "Strip metadata when encoding images (where supported)
+
+Removes the given metadata categories from the image before it is encoded.
+Categories can be separated by commas or the flag can be repeated.
+
+Supported categories are
+\tall: Remove all metadata, the default if no category is given
+\texif: Exif fields, including GPS location
+\ticc: Embedded ICC color profiles
+\ttext: Textual key-value pairs, e.g png tEXt, zTXt and iTXt chunks
+
+Example: zune -i a.jpg --strip exif,text -o b.jpg
+";
+
+pub static KEEP_HELP: &str = "Keep only the given metadata when encoding images
+
+All other metadata categories are removed. When combined with --strip,
+categories given to --keep are preserved even if --strip would remove them.
+
+Accepts the same categories as --strip
+
+Example: zune -i a.jpg --keep icc -o b.jpg
+";
+
+pub static STACK_HELP: &str = "Merge multiple images of the same scene into one
+
+All images must have the same dimensions, depth and colorspace.
+
+Supported modes are
+\tmean: Average pixels, reduces noise
+\tmedian: Pick the middle pixel, reduces noise and removes outliers like satellites
+\tmax: Pick the brightest pixel, creates star trails
+\tmin: Pick the darkest pixel
+
+Example: zune stack --mode median frame1.png frame2.png frame3.png -o stacked.png
+";
+
+pub static MONTAGE_HELP: &str = "Lay out multiple images in a grid
+
+Every image is centered in a cell as large as the largest image, cells are
+separated by --padding pixels of the --background color.
+
+The output takes the colorspace and depth of the first image.
+
+Colors can be given as #rrggbb, #rrggbbaa or r,g,b[,a]
+
+Example: zune montage --columns 4 --padding 8 --background '#202020' *.png -o sheet.png
+";
+
+pub static SPRITESHEET_HELP: &str = "Pack multiple images into a sprite sheet
+
+Images are packed tallest first, each at the lowest free position it fits,
+into a transparent RGBA sheet no wider than --max-width, separated by --padding pixels.
+
+A JSON manifest lists the position and size of every input file in the sheet,
+it is written to --manifest, or next to the sheet with a .json extension.
+
+Example: zune spritesheet --padding 2 --max-width 1024 sprites/*.png -o atlas.png
+";
+
+pub static TONEMAP_HELP: &str = "Tone map an HDR (float) image to an 8 bit display image
+
+Operators
+  reinhard: x/(1+x), soft and never clips unless --tonemap-white is given
+  aces:     Fit of the ACES filmic curve, punchy contrast
+  hable:    Uncharted 2 filmic curve, white point defaults to 11.2
+
+Output is sRGB encoded.
+
+Example: zune -i in.hdr -o out.png --tonemap aces --tonemap-exposure -1
+";
+
+pub static WATERMARK_HELP: &str = "Draw an image over the input, e.g a logo
+
+The watermark is given as file[:gravity[:opacity]]
+  gravity: Where the watermark is placed, one of north-west, north, north-east,
+           west, center, east, south-west, south or south-east [default: south-east]
+  opacity: From 0.0 (invisible) to 1.0, multiplies the watermark alpha [default: 1.0]
+
+Use --watermark-margin to keep it away from the edges and --watermark-tile
+to repeat it across the whole image.
+
+Example: zune -i in.jpg -o out.jpg --watermark logo.png:south-east:0.5 --watermark-margin 16
+";
+
+pub static APPEND_HELP: &str = "Append images next to the input, e.g for before | after comparison strips
+
+--append-horizontal places images to the right of the input and --append-vertical
+below it, both can be repeated to append more images.
+
+Images of a different height (or width) are centered on a background color,
+use --append-scale to scale them to the height (or width) of the input instead.
+
+Example: zune -i before.jpg -o strip.jpg --append-horizontal after.jpg --append-spacing 8
+";
@@ -8,38 +8,66 @@
 
 use clap::ArgMatches;
 use zune_core::colorspace::ColorSpace;
-use zune_core::options::{DecoderOptions, EncoderOptions};
+use zune_core::options::{DecoderOptions, EncoderOptions, JpegDecodeOptions};
+use zune_image::metadata::MetadataCategory;
+
+use crate::cmd_args::MetadataOptions;
 
 pub mod global_options;
 
+pub mod chain;
 pub mod filters;
 pub mod operations;
 
 pub fn get_decoder_options(options: &ArgMatches) -> DecoderOptions {
-    let max_width = *options.get_one::<usize>("max-width").unwrap();
-    let max_height = *options.get_one::<usize>("max-height").unwrap();
+    let (max_width, max_height) = match options.get_one::<(usize, usize)>("max-dimensions") {
+        Some(dimensions) => *dimensions,
+        None => (
+            *options.get_one::<usize>("max-width").unwrap(),
+            *options.get_one::<usize>("max-height").unwrap()
+        )
+    };
+    let max_memory = *options.get_one::<usize>("max-memory").unwrap();
     let use_unsafe = !*options.get_one::<bool>("safe").unwrap();
     let strict_mode = *options.get_one::<bool>("strict").unwrap();
+    let allow_partial = *options.get_one::<bool>("allow-partial").unwrap();
+    let collect_timings = *options.get_one::<bool>("timings").unwrap_or(&false);
     let jpeg_grayscale = *options.get_one::<bool>("jpeg-grayscale").unwrap_or(&false);
+    // bytes around decoding failures are only kept when they will be printed
+    let error_context = *options.get_one::<usize>("explain-errors").unwrap_or(&0);
+
+    let mut jpeg_options = JpegDecodeOptions::new();
+
+    if let Some(size) = options.get_one::<usize>("thumbnail") {
+        jpeg_options = jpeg_options.set_min_size(*size);
+    }
+    if let Some(region) = options.get_many::<usize>("crop-on-decode") {
+        let region: Vec<usize> = region.copied().collect();
+        jpeg_options = jpeg_options.set_region(region[2], region[3], region[0], region[1]);
+    }
 
     let mut options = DecoderOptions::new_cmd()
         .set_max_height(max_height)
         .set_max_width(max_width)
+        .set_max_memory(max_memory)
         .set_use_unsafe(use_unsafe)
-        .set_strict_mode(strict_mode);
+        .set_strict_mode(strict_mode)
+        .set_allow_partial(allow_partial)
+        .set_collect_timings(collect_timings)
+        .set_error_context_size(error_context);
 
     if jpeg_grayscale {
         options = options.jpeg_set_out_colorspace(ColorSpace::Luma);
     }
-    options
+    options.jpeg_set_decode_options(jpeg_options)
 }
 
 pub fn get_encoder_options(options: &ArgMatches) -> EncoderOptions {
     let quality = *options.get_one::<u8>("quality").unwrap();
     let encode_threads = *options.get_one::<u8>("encode-threads").unwrap();
     let effort = *options.get_one::<u8>("effort").unwrap();
-    let progressive = options.contains_id("progressive");
-    let strip_metadata = options.contains_id("strip");
+    let progressive = options.get_flag("progressive");
+    let strip_metadata = get_stripped_metadata(options).len() == MetadataCategory::ALL.len();
 
     EncoderOptions::default()
         .set_quality(quality)
@@ -48,3 +76,39 @@ pub fn get_encoder_options(options: &ArgMatches) -> EncoderOptions {
         .set_strip_metadata(strip_metadata)
         .set_jpeg_encode_progressive(progressive)
 }
+
+/// Resolve `--strip` and `--keep` into the metadata categories that should be
+/// removed from images
+///
+/// Categories passed to `--keep` always survive, and `--keep` alone removes
+/// everything not explicitly kept
+pub fn get_stripped_metadata(options: &ArgMatches) -> Vec<MetadataCategory> {
+    let to_categories = |id: &str| -> Option<Vec<MetadataCategory>> {
+        let values = options.get_many::<MetadataOptions>(id)?;
+        let mut categories = vec![];
+
+        for value in values {
+            let expanded: &[MetadataCategory] = match value {
+                MetadataOptions::All => &MetadataCategory::ALL,
+                MetadataOptions::Exif => &[MetadataCategory::Exif],
+                MetadataOptions::Icc => &[MetadataCategory::Icc],
+                MetadataOptions::Text => &[MetadataCategory::Text]
+            };
+            categories.extend_from_slice(expanded);
+        }
+        Some(categories)
+    };
+    let keep = to_categories("keep");
+
+    let strip = match (to_categories("strip"), &keep) {
+        (Some(strip), _) => strip,
+        (None, Some(_)) => MetadataCategory::ALL.to_vec(),
+        (None, None) => vec![]
+    };
+    let keep = keep.unwrap_or_default();
+
+    MetadataCategory::ALL
+        .into_iter()
+        .filter(|x| strip.contains(x) && !keep.contains(x))
+        .collect()
+}
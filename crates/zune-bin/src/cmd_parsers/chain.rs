@@ -0,0 +1,699 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! Operation chains, e.g `op blur:sigma=2 op resize:800x600 op grayscale`
+//!
+//! Unlike one flag per operation, a chain runs operations in the order they
+//! are written and can repeat them.
+//!
+//! Each operation is written as `name[:arguments]` where arguments are separated
+//! by commas and are either positional, e.g `crop:100,100`, or named, e.g `crop:width=100,height=100`.
+//! Positional arguments come before named ones.
+use std::ffi::OsStr;
+use std::fmt::Write;
+use std::str::FromStr;
+
+use clap::ArgMatches;
+use zune_core::bit_depth::BitDepth;
+use zune_core::colorspace::ColorSpace;
+use zune_image::core_filters::colorspace::grayscale::LumaWeights;
+use zune_image::core_filters::colorspace::ColorspaceConv;
+use zune_image::core_filters::depth::Depth;
+use zune_image::core_filters::metadata::SetDpi;
+use zune_image::traits::OperationsTrait;
+use zune_imageprocs::auto_orient::AutoOrient;
+use zune_imageprocs::box_blur::BoxBlur;
+use zune_imageprocs::brighten::Brighten;
+use zune_imageprocs::composite::Gravity;
+use zune_imageprocs::contrast::Contrast;
+use zune_imageprocs::crop::Crop;
+use zune_imageprocs::exposure::Exposure;
+use zune_imageprocs::extent::Extent;
+use zune_imageprocs::flip::{Flip, VerticalFlip};
+use zune_imageprocs::flop::Flop;
+use zune_imageprocs::gamma::Gamma;
+use zune_imageprocs::gaussian_blur::GaussianBlur;
+use zune_imageprocs::halftone::Halftone;
+use zune_imageprocs::hsv_adjust::HsvAdjust;
+use zune_imageprocs::invert::Invert;
+use zune_imageprocs::median::Median;
+use zune_imageprocs::mirror::{Mirror, MirrorMode};
+use zune_imageprocs::non_local_means::NonLocalMeans;
+use zune_imageprocs::normalize::{Clamp, Normalize};
+use zune_imageprocs::pixelate::Pixelate;
+use zune_imageprocs::resize::{Resize, ResizeMethod};
+use zune_imageprocs::scharr::Scharr;
+use zune_imageprocs::sobel::Sobel;
+use zune_imageprocs::spatial::SpatialOps;
+use zune_imageprocs::spatial_ops::SpatialOperations;
+use zune_imageprocs::stretch_contrast::StretchContrast;
+use zune_imageprocs::threshold::{Threshold, ThresholdMethod};
+use zune_imageprocs::transpose::Transpose;
+use zune_imageprocs::unsharpen::Unsharpen;
+
+use crate::cmd_args::arg_parsers::{parse_color, parse_dimensions, IColorSpace};
+
+/// The type of value an argument takes
+#[derive(Copy, Clone, Debug)]
+enum Kind {
+    /// A floating point number
+    Number,
+    /// A non-negative integer
+    Integer,
+    /// Dimensions written as `WxH`
+    Dimensions,
+    /// A word, checked by the operation itself
+    Word
+}
+
+/// An argument of an operation
+struct Param {
+    name:    &'static str,
+    kind:    Kind,
+    /// Value used when the argument isn't given, `None` if it must be given
+    default: Option<&'static str>
+}
+
+const fn required(name: &'static str, kind: Kind) -> Param {
+    Param {
+        name,
+        kind,
+        default: None
+    }
+}
+
+const fn optional(name: &'static str, kind: Kind, default: &'static str) -> Param {
+    Param {
+        name,
+        kind,
+        default: Some(default)
+    }
+}
+
+/// A parsed argument value
+#[derive(Clone, Debug)]
+enum Value {
+    Number(f32),
+    Integer(usize),
+    Dimensions((usize, usize)),
+    Word(String)
+}
+
+/// Arguments of an operation, after they were checked against its parameters
+#[derive(Debug)]
+struct Arguments {
+    values: Vec<(&'static str, Value)>
+}
+
+impl Arguments {
+    fn get(&self, name: &str) -> &Value {
+        self.values
+            .iter()
+            .find(|(param, _)| *param == name)
+            .map(|(_, value)| value)
+            .expect("argument not declared by the operation")
+    }
+
+    fn number(&self, name: &str) -> f32 {
+        match self.get(name) {
+            Value::Number(value) => *value,
+            value => unreachable!("{name} is {value:?}, not a number")
+        }
+    }
+
+    fn integer(&self, name: &str) -> usize {
+        match self.get(name) {
+            Value::Integer(value) => *value,
+            value => unreachable!("{name} is {value:?}, not an integer")
+        }
+    }
+
+    fn dimensions(&self, name: &str) -> (usize, usize) {
+        match self.get(name) {
+            Value::Dimensions(value) => *value,
+            value => unreachable!("{name} is {value:?}, not dimensions")
+        }
+    }
+
+    fn word(&self, name: &str) -> &str {
+        match self.get(name) {
+            Value::Word(value) => value,
+            value => unreachable!("{name} is {value:?}, not a word")
+        }
+    }
+}
+
+/// An operation and the text it was parsed from
+pub type ChainedOperation = (String, Box<dyn OperationsTrait>);
+
+type Builder = fn(&Arguments) -> Result<Box<dyn OperationsTrait>, String>;
+
+/// An operation that can be used in a chain
+struct ChainOperation {
+    name:   &'static str,
+    params: &'static [Param],
+    build:  Builder
+}
+
+#[rustfmt::skip]
+static OPERATIONS: &[ChainOperation] = &[
+    ChainOperation {
+        name:   "blur",
+        params: &[required("sigma", Kind::Number)],
+        build:  |args| Ok(Box::new(GaussianBlur::new(args.number("sigma"))))
+    },
+    ChainOperation {
+        name:   "box-blur",
+        params: &[required("radius", Kind::Integer)],
+        build:  |args| Ok(Box::new(BoxBlur::new(args.integer("radius"))))
+    },
+    ChainOperation {
+        name:   "median-blur",
+        params: &[required("radius", Kind::Integer)],
+        build:  |args| Ok(Box::new(Median::new(args.integer("radius"))))
+    },
+    ChainOperation {
+        name:   "mean-blur",
+        params: &[required("radius", Kind::Integer)],
+        build:  |args| Ok(Box::new(SpatialOps::new(args.integer("radius"), SpatialOperations::Mean)))
+    },
+    ChainOperation {
+        name:   "unsharpen",
+        params: &[required("sigma", Kind::Number), optional("threshold", Kind::Integer, "0")],
+        build:  |args| {
+            let threshold = u16::try_from(args.integer("threshold"))
+                .map_err(|_| "threshold must be below 65536".to_string())?;
+            Ok(Box::new(Unsharpen::new(args.number("sigma"), threshold, 0)))
+        }
+    },
+    ChainOperation {
+        name:   "denoise",
+        params: &[required("strength", Kind::Number)],
+        build:  |args| Ok(Box::new(NonLocalMeans::new(args.number("strength"))))
+    },
+    ChainOperation {
+        name:   "sobel",
+        params: &[],
+        build:  |_| Ok(Box::new(Sobel::new()))
+    },
+    ChainOperation {
+        name:   "scharr",
+        params: &[],
+        build:  |_| Ok(Box::new(Scharr::new()))
+    },
+    ChainOperation {
+        name:   "resize",
+        params: &[required("size", Kind::Dimensions)],
+        build:  |args| {
+            let (width, height) = args.dimensions("size");
+            Ok(Box::new(Resize::new(width, height, ResizeMethod::Bilinear)))
+        }
+    },
+    ChainOperation {
+        name:   "extent",
+        params: &[
+            required("size", Kind::Dimensions),
+            optional("gravity", Kind::Word, "center"),
+            optional("background", Kind::Word, "#ffffff")
+        ],
+        build:  |args| {
+            let (width, height) = args.dimensions("size");
+            let gravity = Gravity::from_string_result(args.word("gravity"))?;
+            let background = parse_color(args.word("background"))?;
+            Ok(Box::new(Extent::new(width, height, gravity, background)))
+        }
+    },
+    ChainOperation {
+        name:   "pixelate",
+        params: &[required("size", Kind::Integer)],
+        build:  |args| Ok(Box::new(Pixelate::new(args.integer("size"))))
+    },
+    ChainOperation {
+        name:   "halftone",
+        params: &[optional("size", Kind::Integer, "4"), optional("levels", Kind::Integer, "2")],
+        build:  |args| {
+            Ok(Box::new(
+                Halftone::new(args.integer("size")).set_levels(args.integer("levels"))
+            ))
+        }
+    },
+    ChainOperation {
+        name:   "crop",
+        params: &[
+            required("width", Kind::Integer),
+            required("height", Kind::Integer),
+            optional("x", Kind::Integer, "0"),
+            optional("y", Kind::Integer, "0")
+        ],
+        build:  |args| {
+            Ok(Box::new(Crop::new(
+                args.integer("width"),
+                args.integer("height"),
+                args.integer("x"),
+                args.integer("y")
+            )))
+        }
+    },
+    ChainOperation {
+        name:   "grayscale",
+        params: &[optional("weights", Kind::Word, "bt601")],
+        build:  |args| {
+            let weights = LumaWeights::from_string_result(args.word("weights"))?;
+            Ok(Box::new(ColorspaceConv::new(ColorSpace::Luma).set_luma_weights(weights)))
+        }
+    },
+    ChainOperation {
+        name:   "colorspace",
+        params: &[required("colorspace", Kind::Word), optional("weights", Kind::Word, "bt601")],
+        build:  |args| {
+            let colorspace = IColorSpace::from_str(args.word("colorspace"))?;
+            let weights = LumaWeights::from_string_result(args.word("weights"))?;
+            Ok(Box::new(
+                ColorspaceConv::new(colorspace.to_colorspace()).set_luma_weights(weights)
+            ))
+        }
+    },
+    ChainOperation {
+        name:   "depth",
+        params: &[required("bits", Kind::Integer)],
+        build:  |args| {
+            let depth = match args.integer("bits") {
+                8 => BitDepth::Eight,
+                10 => BitDepth::Ten,
+                12 => BitDepth::Twelve,
+                16 => BitDepth::Sixteen,
+                32 => BitDepth::Float32,
+                bits => return Err(format!("Unknown depth {bits}, supported depths are 8, 10, 12, 16 and 32"))
+            };
+            Ok(Box::new(Depth::new(depth)))
+        }
+    },
+    ChainOperation {
+        name:   "flip",
+        params: &[],
+        build:  |_| Ok(Box::new(Flip::new()))
+    },
+    ChainOperation {
+        name:   "v-flip",
+        params: &[],
+        build:  |_| Ok(Box::new(VerticalFlip::new()))
+    },
+    ChainOperation {
+        name:   "flop",
+        params: &[],
+        build:  |_| Ok(Box::new(Flop::new()))
+    },
+    ChainOperation {
+        name:   "transpose",
+        params: &[],
+        build:  |_| Ok(Box::new(Transpose::new()))
+    },
+    ChainOperation {
+        name:   "mirror",
+        params: &[required("direction", Kind::Word)],
+        build:  |args| {
+            let direction = match args.word("direction") {
+                "north" => MirrorMode::North,
+                "south" => MirrorMode::South,
+                "east" => MirrorMode::East,
+                "west" => MirrorMode::West,
+                value => return Err(format!("Unknown mirror mode {value:?}"))
+            };
+            Ok(Box::new(Mirror::new(direction)))
+        }
+    },
+    ChainOperation {
+        name:   "auto-orient",
+        params: &[],
+        build:  |_| Ok(Box::new(AutoOrient))
+    },
+    ChainOperation {
+        name:   "dpi",
+        // y of zero means the same density as x
+        params: &[required("x", Kind::Number), optional("y", Kind::Number, "0")],
+        build:  |args| {
+            let x = args.number("x");
+            let y = if args.number("y") == 0.0 { x } else { args.number("y") };
+            Ok(Box::new(SetDpi::new(x, y)))
+        }
+    },
+    ChainOperation {
+        name:   "invert",
+        params: &[],
+        build:  |_| Ok(Box::new(Invert::new()))
+    },
+    ChainOperation {
+        name:   "brighten",
+        params: &[required("value", Kind::Number)],
+        build:  |args| Ok(Box::new(Brighten::new(args.number("value"))))
+    },
+    ChainOperation {
+        name:   "contrast",
+        params: &[required("value", Kind::Number)],
+        build:  |args| Ok(Box::new(Contrast::new(args.number("value"))))
+    },
+    ChainOperation {
+        name:   "gamma",
+        params: &[required("value", Kind::Number)],
+        build:  |args| Ok(Box::new(Gamma::new(args.number("value"))))
+    },
+    ChainOperation {
+        name:   "exposure",
+        params: &[required("value", Kind::Number)],
+        build:  |args| Ok(Box::new(Exposure::new(args.number("value"), 0.)))
+    },
+    ChainOperation {
+        name:   "huerotate",
+        params: &[required("degrees", Kind::Number)],
+        build:  |args| Ok(Box::new(HsvAdjust::new(args.number("degrees"), 1., 1.)))
+    },
+    ChainOperation {
+        name:   "saturate",
+        params: &[required("value", Kind::Number)],
+        build:  |args| Ok(Box::new(HsvAdjust::new(0., args.number("value"), 1.)))
+    },
+    ChainOperation {
+        name:   "lightness",
+        params: &[required("value", Kind::Number)],
+        build:  |args| Ok(Box::new(HsvAdjust::new(0., 1., args.number("value"))))
+    },
+    ChainOperation {
+        name:   "stretch-contrast",
+        params: &[required("lower", Kind::Number), required("upper", Kind::Number)],
+        build:  |args| Ok(Box::new(StretchContrast::new(args.number("lower"), args.number("upper"))))
+    },
+    ChainOperation {
+        name:   "threshold",
+        params: &[required("value", Kind::Number), optional("mode", Kind::Word, "binary")],
+        build:  |args| {
+            let method = ThresholdMethod::from_string_result(args.word("mode"))?;
+            Ok(Box::new(Threshold::new(args.number("value"), method)))
+        }
+    },
+    ChainOperation {
+        name:   "normalize",
+        params: &[optional("min", Kind::Number, "0"), optional("max", Kind::Number, "1")],
+        build:  |args| Ok(Box::new(Normalize::new(args.number("min"), args.number("max"))))
+    },
+    ChainOperation {
+        name:   "clamp",
+        params: &[optional("min", Kind::Number, "0"), optional("max", Kind::Number, "1")],
+        build:  |args| Ok(Box::new(Clamp::new(args.number("min"), args.number("max"))))
+    },
+];
+
+/// Parse `value` as an argument of kind `kind`
+fn parse_value(value: &str, kind: Kind) -> Result<Value, String> {
+    let value = value.trim();
+
+    match kind {
+        Kind::Number => value
+            .parse::<f32>()
+            .map(Value::Number)
+            .map_err(|_| format!("expected a number, got {value:?}")),
+        Kind::Integer => value
+            .parse::<usize>()
+            .map(Value::Integer)
+            .map_err(|_| format!("expected a non-negative integer, got {value:?}")),
+        Kind::Dimensions => parse_dimensions(value).map(Value::Dimensions),
+        Kind::Word => Ok(Value::Word(value.to_string()))
+    }
+}
+
+/// Check `arguments` against the parameters of `operation`
+fn parse_arguments(operation: &ChainOperation, arguments: &str) -> Result<Arguments, String> {
+    let mut given: Vec<Option<&str>> = vec![None; operation.params.len()];
+    let mut seen_named = false;
+
+    for (position, argument) in arguments
+        .split(',')
+        .filter(|x| !x.trim().is_empty())
+        .enumerate()
+    {
+        match argument.split_once('=') {
+            Some((key, value)) => {
+                seen_named = true;
+
+                let key = key.trim();
+                let index = operation
+                    .params
+                    .iter()
+                    .position(|param| param.name == key)
+                    .ok_or_else(|| format!("unknown argument {key:?}"))?;
+
+                if given[index].is_some() {
+                    return Err(format!("{key:?} is given twice"));
+                }
+                given[index] = Some(value);
+            }
+            None => {
+                if seen_named {
+                    return Err(format!(
+                        "positional argument {argument:?} after a named argument"
+                    ));
+                }
+                if position >= operation.params.len() {
+                    return Err(format!(
+                        "too many arguments, takes at most {}",
+                        operation.params.len()
+                    ));
+                }
+                given[position] = Some(argument);
+            }
+        }
+    }
+
+    let mut values = Vec::with_capacity(operation.params.len());
+
+    for (param, value) in operation.params.iter().zip(given) {
+        let value = value
+            .or(param.default)
+            .ok_or_else(|| format!("missing argument {:?}", param.name))?;
+        let value =
+            parse_value(value, param.kind).map_err(|err| format!("{}: {err}", param.name))?;
+
+        values.push((param.name, value));
+    }
+    Ok(Arguments { values })
+}
+
+/// Parse a single `name[:arguments]` operation
+fn parse_operation(spec: &str) -> Result<Box<dyn OperationsTrait>, String> {
+    let (name, arguments) = spec.split_once(':').unwrap_or((spec, ""));
+
+    let operation = OPERATIONS
+        .iter()
+        .find(|x| x.name == name.trim())
+        .ok_or_else(|| {
+            let names: Vec<&str> = OPERATIONS.iter().map(|x| x.name).collect();
+            format!(
+                "Unknown operation {name:?} in `op {spec}`, known operations are {}",
+                names.join(", ")
+            )
+        })?;
+
+    let arguments =
+        parse_arguments(operation, arguments).map_err(|err| format!("`op {spec}`: {err}"))?;
+
+    (operation.build)(&arguments).map_err(|err| format!("`op {spec}`: {err}"))
+}
+
+/// Parse a chain of `op name[:arguments]` tokens into operations
+///
+/// Returns the operations in the order they were written, each with the
+/// text that created it.
+pub fn parse_chain(tokens: &[String]) -> Result<Vec<ChainedOperation>, String> {
+    let mut operations = vec![];
+    let mut tokens = tokens.iter();
+
+    while let Some(token) = tokens.next() {
+        if token != "op" {
+            return Err(format!(
+                "Expected `op` before {token:?}, operations are chained as `op name[:arguments]`"
+            ));
+        }
+        let spec = tokens
+            .next()
+            .ok_or("`op` needs an operation, e.g op blur:sigma=2")?;
+
+        operations.push((format!("op {spec}"), parse_operation(spec)?));
+    }
+    Ok(operations)
+}
+
+/// Return the input files and the tokens of the operation chain, each token
+/// with its index on the command line
+///
+/// `-i` takes every value up to the next flag, so in `-i in.png op blur` it also
+/// takes `op blur`. Values of an `-i` from its first `op` on belong to the chain.
+pub fn split_inputs(args: &ArgMatches) -> (Vec<&OsStr>, Vec<(usize, String)>) {
+    let mut inputs = vec![];
+    let mut tokens = vec![];
+
+    if let (Some(occurrences), Some(mut indices)) =
+        (args.get_raw_occurrences("in"), args.indices_of("in"))
+    {
+        for occurrence in occurrences {
+            let mut in_chain = false;
+
+            for (value, index) in occurrence.zip(&mut indices) {
+                in_chain |= value == "op";
+
+                if in_chain {
+                    tokens.push((index, value.to_string_lossy().into_owned()));
+                } else {
+                    inputs.push(value);
+                }
+            }
+        }
+    }
+    if let (Some(values), Some(indices)) =
+        (args.get_many::<String>("chain"), args.indices_of("chain"))
+    {
+        tokens.extend(indices.zip(values.cloned()));
+    }
+    tokens.sort_by_key(|(index, _)| *index);
+
+    (inputs, tokens)
+}
+
+/// Describe the operations usable in a chain and their arguments
+pub fn chain_help() -> String {
+    let mut help = String::from(
+        "Run operations in the order they are written, e.g
+\tzune -i in.png -o out.png op blur:sigma=2 op resize:800x600 op grayscale
+
+Operations can be repeated, and run in the order they are written together
+with operations given with flags.
+Arguments are separated by commas, and are given in order or by name, e.g
+crop:100,100 and crop:width=100,height=100 are the same.
+
+Operations and their arguments, defaults are shown for optional ones
+"
+    );
+    for operation in OPERATIONS {
+        let params: Vec<String> = operation
+            .params
+            .iter()
+            .map(|param| match param.default {
+                Some(default) => format!("{}={default}", param.name),
+                None => param.name.to_string()
+            })
+            .collect();
+
+        if params.is_empty() {
+            let _ = writeln!(help, "\t{}", operation.name);
+        } else {
+            let _ = writeln!(help, "\t{}:{}", operation.name, params.join(","));
+        }
+    }
+    help
+}
+
+#[cfg(test)]
+fn operation(name: &str) -> &'static ChainOperation {
+    OPERATIONS.iter().find(|x| x.name == name).unwrap()
+}
+
+#[cfg(test)]
+fn test_matches(args: &[&str]) -> ArgMatches {
+    crate::cmd_args::create_cmd_args()
+        .try_get_matches_from(std::iter::once("zune").chain(args.iter().copied()))
+        .unwrap()
+}
+
+#[test]
+fn test_arguments_positional_and_named() {
+    let crop = operation("crop");
+
+    let arguments = parse_arguments(crop, "100,50,y=3").unwrap();
+    assert_eq!(arguments.integer("width"), 100);
+    assert_eq!(arguments.integer("height"), 50);
+    assert_eq!(arguments.integer("x"), 0);
+    assert_eq!(arguments.integer("y"), 3);
+
+    let arguments = parse_arguments(crop, "height=50, width=100").unwrap();
+    assert_eq!(arguments.integer("width"), 100);
+    assert_eq!(arguments.integer("height"), 50);
+}
+
+#[test]
+fn test_arguments_errors() {
+    let crop = operation("crop");
+
+    assert!(parse_arguments(crop, "100").unwrap_err().contains("height"));
+    assert!(parse_arguments(crop, "width=1,width=2")
+        .unwrap_err()
+        .contains("twice"));
+    assert!(parse_arguments(crop, "width=1,2")
+        .unwrap_err()
+        .contains("after a named"));
+    assert!(parse_arguments(crop, "1,2,3,4,5")
+        .unwrap_err()
+        .contains("too many"));
+    assert!(parse_arguments(crop, "1,2,depth=3")
+        .unwrap_err()
+        .contains("unknown argument"));
+    assert!(parse_arguments(operation("blur"), "sigma=big")
+        .unwrap_err()
+        .contains("expected a number"));
+}
+
+#[test]
+fn test_chain_order() {
+    let tokens: Vec<String> = ["op", "blur:2", "op", "grayscale", "op", "blur:sigma=1"]
+        .iter()
+        .map(|x| x.to_string())
+        .collect();
+
+    let operations = parse_chain(&tokens).unwrap();
+    let descriptions: Vec<&str> = operations.iter().map(|(x, _)| x.as_str()).collect();
+
+    assert_eq!(descriptions, ["op blur:2", "op grayscale", "op blur:sigma=1"]);
+}
+
+#[test]
+fn test_chain_errors() {
+    let error = |tokens: &[&str]| {
+        let tokens: Vec<String> = tokens.iter().map(|x| x.to_string()).collect();
+        parse_chain(&tokens).err().unwrap()
+    };
+
+    assert!(error(&["blur:2"]).contains("Expected `op`"));
+    assert!(error(&["op"]).contains("needs"));
+    assert!(error(&["op", "smudge"]).contains("Unknown operation"));
+}
+
+#[test]
+fn test_chain_after_inputs() {
+    let args = test_matches(&["-i", "a.png", "b.png", "op", "blur:2", "-o", "out.png"]);
+    let (inputs, tokens) = split_inputs(&args);
+
+    assert_eq!(inputs, ["a.png", "b.png"]);
+
+    let tokens: Vec<&str> = tokens.iter().map(|(_, x)| x.as_str()).collect();
+    assert_eq!(tokens, ["op", "blur:2"]);
+}
+
+#[test]
+fn test_chain_runs_in_written_order() {
+    use zune_image::image::Image;
+    use zune_image::pipelines::Pipeline;
+
+    let args = test_matches(&[
+        "-i", "in.png", "op", "blur:2", "--flip", "-o", "out.png", "op", "grayscale", "--invert"
+    ]);
+    let mut workflow = Pipeline::<Image>::new();
+
+    let descriptions = crate::workflow::add_operations(&args, &mut workflow).unwrap();
+
+    assert_eq!(
+        descriptions,
+        ["op blur:2", "flip", "op grayscale", "invert"]
+    );
+}
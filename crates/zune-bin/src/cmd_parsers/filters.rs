@@ -14,7 +14,9 @@ use zune_imageprocs::box_blur::BoxBlur;
 use zune_imageprocs::convolve::Convolve;
 use zune_imageprocs::gaussian_blur::GaussianBlur;
 use zune_imageprocs::median::Median;
+use zune_imageprocs::non_local_means::NonLocalMeans;
 use zune_imageprocs::scharr::Scharr;
+use zune_imageprocs::smart_sharpen::SmartSharpen;
 use zune_imageprocs::sobel::Sobel;
 use zune_imageprocs::spatial::SpatialOps;
 use zune_imageprocs::spatial_ops::SpatialOperations;
@@ -49,6 +51,19 @@ pub fn parse_options<T: IntoImage>(
 
         let unsharpen = Unsharpen::new(sigma_f32, threshold_u16 as u16, 0);
         workflow.add_operation(Box::new(unsharpen))
+    } else if argument == "denoise" {
+        let strength = *args.get_one::<f32>(argument).unwrap();
+        debug!("Added non-local means denoise with strength {}", strength);
+
+        workflow.add_operation(Box::new(NonLocalMeans::new(strength)));
+    } else if argument == "smart-sharpen" {
+        let values: Vec<f32> = args.get_many::<f32>(argument).unwrap().copied().collect();
+
+        debug!(
+            "Added smart sharpen filter with strength={}, radius={} and edge threshold={}",
+            values[0], values[1], values[2]
+        );
+        workflow.add_operation(Box::new(SmartSharpen::new(values[0], values[1], values[2])));
     } else if argument == "mean-blur" {
         let radius = *args.get_one::<usize>(argument).unwrap();
         debug!("Added mean blur filter with radius {}", radius);
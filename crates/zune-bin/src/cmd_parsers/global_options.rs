@@ -83,6 +83,11 @@ pub fn setup_logger(options: &ArgMatches) {
 
     simple_logger::init_with_level(log_level).unwrap();
 
+    if options.get_one::<String>("report").is_some() {
+        // the report is the only output on stdout, warnings are part of it
+        log::set_max_level(log::LevelFilter::Off);
+    }
+
     info!("Initialized logger");
     info!("Log level :{}", log_level);
 }
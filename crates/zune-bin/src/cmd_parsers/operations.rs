@@ -6,32 +6,49 @@
  * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
  */
 
+use std::ffi::OsString;
+
 use clap::ArgMatches;
 use log::debug;
 use zune_core::bit_depth::BitDepth;
 use zune_core::colorspace::ColorSpace;
 use zune_image::core_filters::colorspace::ColorspaceConv;
 use zune_image::core_filters::depth::Depth;
+use zune_image::core_filters::metadata::SetDpi;
+use zune_image::image::Image;
 use zune_image::pipelines::Pipeline;
 use zune_image::traits::IntoImage;
+use zune_imageprocs::auto_orient::AutoOrient;
 use zune_imageprocs::brighten::Brighten;
+use zune_imageprocs::channel_shift::ChannelShift;
+use zune_imageprocs::chroma_key::ChromaKey;
+use zune_imageprocs::composite::{Composite, Gravity};
+use zune_imageprocs::concat::{HStack, Harmonize, VStack};
 use zune_imageprocs::contrast::Contrast;
 use zune_imageprocs::crop::Crop;
 use zune_imageprocs::exposure::Exposure;
+use zune_imageprocs::extent::Extent;
+use zune_imageprocs::flatten_alpha::FlattenAlpha;
 use zune_imageprocs::flip::{Flip, VerticalFlip};
 use zune_imageprocs::flop::Flop;
 use zune_imageprocs::gamma::Gamma;
+use zune_imageprocs::halftone::Halftone;
 use zune_imageprocs::hsv_adjust::HsvAdjust;
 use zune_imageprocs::invert::Invert;
+use zune_imageprocs::lut3d::Lut3D;
 use zune_imageprocs::mirror::{Mirror, MirrorMode};
-use zune_imageprocs::resize::{Resize, ResizeMethod};
+use zune_imageprocs::pixelate::Pixelate;
+use zune_imageprocs::resize::{Resize, ResizeMethod, ResizeOptions};
 use zune_imageprocs::spatial::SpatialOps;
 use zune_imageprocs::spatial_ops::SpatialOperations;
 use zune_imageprocs::stretch_contrast::StretchContrast;
 use zune_imageprocs::threshold::{Threshold, ThresholdMethod};
+use zune_imageprocs::tonemap::{ToneMap, ToneMapOperator};
 use zune_imageprocs::transpose::Transpose;
+use zune_imageprocs::vignette::LensVignetteCorrect;
 
 use crate::cmd_args::arg_parsers::IColorSpace;
+use crate::cmd_parsers::get_decoder_options;
 
 pub fn parse_options<T: IntoImage>(
     workflow: &mut Pipeline<T>, argument: &str, args: &ArgMatches
@@ -148,21 +165,27 @@ pub fn parse_options<T: IntoImage>(
 
         let height = *values[1];
 
-        let func = Resize::new(width, height, ResizeMethod::Bilinear);
+        let options = ResizeOptions {
+            premultiply: args.get_flag("resize-premultiply"),
+            linearize:   args.get_flag("resize-linear")
+        };
+        let func = Resize::new(width, height, ResizeMethod::Bilinear).set_options(options);
 
         debug!(
-            "Added resize operation with width:{}, height:{}",
-            width, height
+            "Added resize operation with width:{}, height:{} and {:?}",
+            width, height, options
         );
         workflow.add_operation(Box::new(func));
     } else if argument == "depth" {
         let value = *args.get_one::<u8>(argument).unwrap();
         let depth = match value {
             8 => BitDepth::Eight,
+            10 => BitDepth::Ten,
+            12 => BitDepth::Twelve,
             16 => BitDepth::Sixteen,
             _ => {
                 return Err(format!(
-                    "Unknown depth value {value}, supported depths are 8 and 16"
+                    "Unknown depth value {value}, supported depths are 8, 10, 12 and 16"
                 ))
             }
         };
@@ -180,7 +203,13 @@ pub fn parse_options<T: IntoImage>(
         workflow.add_operation(Box::new(ColorspaceConv::new(colorspace)))
     } else if argument == "auto-orient" {
         debug!("Add auto orient operation");
-        //workflow.add_operation(Box::new(AutoOrient))
+        workflow.add_operation(Box::new(AutoOrient))
+    } else if argument == "dpi" {
+        let values: Vec<f32> = args.get_many::<f32>(argument).unwrap().copied().collect();
+        let (x, y) = (values[0], *values.get(1).unwrap_or(&values[0]));
+
+        debug!("Added set dpi operation with {x}x{y}");
+        workflow.add_operation(Box::new(SetDpi::new(x, y)));
     } else if argument == "exposure" {
         let exposure = *args.get_one::<f32>(argument).unwrap();
 
@@ -201,7 +230,155 @@ pub fn parse_options<T: IntoImage>(
         let value = *args.get_one::<f32>(argument).unwrap();
         workflow.add_operation(Box::new(HsvAdjust::new(0f32, 1f32, value)));
         debug!("Added lightness argument with value {}", value);
+    } else if argument == "vignette-correct" {
+        let value = *args.get_one::<f32>(argument).unwrap();
+        workflow.add_operation(Box::new(LensVignetteCorrect::new(value)));
+        debug!("Added vignette correction with strength {}", value);
+    } else if argument == "channel-shift" {
+        let values: Vec<f32> = args.get_many::<f32>(argument).unwrap().copied().collect();
+
+        workflow.add_operation(Box::new(ChannelShift::new(
+            values[0], values[1], values[2], values[3]
+        )));
+        debug!(
+            "Added channel shift with red=({},{}) and blue=({},{})",
+            values[0], values[1], values[2], values[3]
+        );
+    } else if argument == "flatten-alpha" {
+        let [r, g, b, _] = *args.get_one::<[u8; 4]>(argument).unwrap();
+        let background = [r, g, b].map(|x| f32::from(x) / 255.0);
+
+        workflow.add_operation(Box::new(FlattenAlpha::new(background)));
+        debug!("Added flatten alpha with background {:?}", [r, g, b]);
+    } else if argument == "chroma-key" {
+        let [r, g, b, _] = *args.get_one::<[u8; 4]>(argument).unwrap();
+        let color = [r, g, b].map(|x| f32::from(x) / 255.0);
+        let tolerance = *args.get_one::<f32>("chroma-key-tolerance").unwrap_or(&0.15);
+        let softness = *args.get_one::<f32>("chroma-key-softness").unwrap_or(&0.1);
+
+        let chroma_key = ChromaKey::new(color, tolerance, softness)
+            .set_spill_suppression(args.get_flag("chroma-key-spill"));
+
+        debug!("Added chroma key operation {chroma_key:?}");
+        workflow.add_operation(Box::new(chroma_key));
+    } else if argument == "watermark" {
+        let (file, gravity, opacity) = parse_watermark(args.get_one::<String>(argument).unwrap());
+        let overlay = Image::open_with_options(file, get_decoder_options(args))
+            .map_err(|x| format!("Could not open watermark {file:?}: {x:?}"))?;
+
+        let mut composite = Composite::new(overlay)
+            .set_gravity(gravity)
+            .set_opacity(opacity);
+
+        if let Some(margin) = args.get_one::<usize>("watermark-margin") {
+            composite = composite.set_margin(*margin, *margin);
+        }
+        if let Some(spacing) = args.get_one::<usize>("watermark-tile") {
+            composite = composite.set_tiled(true).set_spacing(*spacing, *spacing);
+        }
+        debug!("Added watermark {file:?} with gravity {gravity:?} and opacity {opacity}");
+        workflow.add_operation(Box::new(composite));
+    } else if argument == "extent" {
+        let (width, height) = *args.get_one::<(usize, usize)>(argument).unwrap();
+        let gravity = match args.get_one::<String>("extent-gravity") {
+            Some(gravity) => Gravity::from_string_result(gravity)?,
+            None => Gravity::Center
+        };
+        let background = *args
+            .get_one::<[u8; 4]>("extent-background")
+            .unwrap_or(&[255; 4]);
+
+        debug!("Added extent to {width}x{height} with gravity {gravity:?}");
+        workflow.add_operation(Box::new(Extent::new(width, height, gravity, background)));
+    } else if argument == "pixelate" {
+        let block_size = *args.get_one::<usize>(argument).unwrap();
+
+        debug!("Added pixelate with blocks of {block_size}x{block_size}");
+        workflow.add_operation(Box::new(Pixelate::new(block_size)));
+    } else if argument == "halftone" {
+        let matrix_size = *args.get_one::<usize>(argument).unwrap();
+        let levels = *args.get_one::<usize>("halftone-levels").unwrap_or(&2);
+
+        debug!("Added halftone with a {matrix_size}x{matrix_size} pattern and {levels} levels");
+        workflow.add_operation(Box::new(Halftone::new(matrix_size).set_levels(levels)));
+    } else if argument == "lut" {
+        let path = args.get_one::<OsString>(argument).unwrap();
+        let lut =
+            Lut3D::load_cube(path).map_err(|x| format!("Could not load LUT {path:?}: {x:?}"))?;
+
+        debug!("Added 3D LUT from {path:?}");
+        workflow.add_operation(Box::new(lut));
+    } else if argument == "tonemap" {
+        let operator =
+            ToneMapOperator::from_string_result(args.get_one::<String>(argument).unwrap())?;
+        let mut tonemap = ToneMap::new(operator);
+
+        if let Some(exposure) = args.get_one::<f32>("tonemap-exposure") {
+            tonemap = tonemap.set_exposure(*exposure);
+        }
+        if let Some(white) = args.get_one::<f32>("tonemap-white") {
+            tonemap = tonemap.set_white_point(*white);
+        }
+        debug!("Added tone map operation {tonemap:?}");
+        workflow.add_operation(Box::new(tonemap));
+    } else if argument == "append-horizontal" || argument == "append-vertical" {
+        let options = get_decoder_options(args);
+        let images = args
+            .get_many::<OsString>(argument)
+            .unwrap()
+            .map(|file| {
+                Image::open_with_options(file, options)
+                    .map_err(|x| format!("Could not open {file:?}: {x:?}"))
+            })
+            .collect::<Result<Vec<Image>, String>>()?;
+
+        let harmonize =
+            if args.get_flag("append-scale") { Harmonize::Scale } else { Harmonize::Pad };
+        let spacing = *args.get_one::<usize>("append-spacing").unwrap_or(&0);
+        let background = *args
+            .get_one::<[u8; 4]>("append-background")
+            .unwrap_or(&[255; 4]);
+
+        debug!("Appending {} images with {harmonize:?}", images.len());
+
+        if argument == "append-horizontal" {
+            let stack = HStack::new(images)
+                .set_harmonize(harmonize)
+                .set_spacing(spacing)
+                .set_background(background);
+            workflow.add_operation(Box::new(stack));
+        } else {
+            let stack = VStack::new(images)
+                .set_harmonize(harmonize)
+                .set_spacing(spacing)
+                .set_background(background);
+            workflow.add_operation(Box::new(stack));
+        }
     }
 
     Ok(())
 }
+
+/// Split a `file[:gravity[:opacity]]` watermark
+///
+/// Trailing parts that are not a gravity or opacity are kept
+/// in the file name, so paths may contain colons
+fn parse_watermark(spec: &str) -> (&str, Gravity, f32) {
+    let mut file = spec;
+    let mut gravity = Gravity::SouthEast;
+    let mut opacity = 1.0;
+
+    if let Some((rest, value)) = file.rsplit_once(':') {
+        if let Ok(value) = value.parse::<f32>() {
+            opacity = value;
+            file = rest;
+        }
+    }
+    if let Some((rest, value)) = file.rsplit_once(':') {
+        if let Ok(value) = Gravity::from_string_result(value) {
+            gravity = value;
+            file = rest;
+        }
+    }
+    (file, gravity, opacity)
+}
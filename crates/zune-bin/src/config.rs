@@ -0,0 +1,316 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! Default options read from a config file
+//!
+//! The file is `~/.config/zune/config.toml` (`%APPDATA%\zune\config.toml` on Windows, or
+//! `$XDG_CONFIG_HOME/zune/config.toml` when set), or the one given with `--config`.
+//! Its values replace the defaults of command line flags, so flags given on the
+//! command line always win, switches set in the file are turned off with e.g `--strict=false`.
+//!
+//! Values are strings, numbers, booleans or arrays of them, e.g
+//! ```toml
+//! threads = 8
+//! strict = true
+//! max-memory = "4G"
+//!
+//! [encode]
+//! quality = 90
+//! effort = 6
+//! threads = 2
+//! progressive = true
+//!
+//! [metadata]
+//! strip = ["exif", "text"]
+//! ```
+use std::env;
+use std::ffi::OsString;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+use clap::parser::ValueSource;
+use clap::{ArgAction, ArgMatches, Command};
+use log::info;
+use toml::{Table, Value};
+use zune_image::errors::ImageErrors;
+
+/// Keys understood in the config file and the id of the argument they set
+const KEYS: [(&str, &str); 13] = [
+    ("threads", "threads"),
+    ("strict", "strict"),
+    ("allow-partial", "allow-partial"),
+    ("safe", "safe"),
+    ("max-memory", "max-memory"),
+    ("max-width", "max-width"),
+    ("max-height", "max-height"),
+    ("encode.quality", "quality"),
+    ("encode.effort", "effort"),
+    ("encode.threads", "encode-threads"),
+    ("encode.progressive", "progressive"),
+    ("metadata.strip", "strip"),
+    ("metadata.keep", "keep")
+];
+
+/// A `key = value` entry of a config file
+#[derive(Debug, PartialEq)]
+struct ConfigEntry {
+    /// The key, prefixed with its tables, e.g `encode.quality`
+    key:    String,
+    /// The value, or every element of an array
+    values: Vec<String>
+}
+
+/// Return `cmd` with the defaults of its arguments replaced by the values of the
+/// config file
+///
+/// `matches` are the arguments parsed without a config, they name the config file and
+/// tell which arguments were given on the command line. A missing default config
+/// file is not an error.
+pub fn add_config_defaults(cmd: Command, matches: &ArgMatches) -> Result<Command, ImageErrors> {
+    let explicit = matches.get_one::<OsString>("config");
+
+    let Some(path) = explicit.map(PathBuf::from).or_else(default_config_path) else {
+        return Ok(cmd);
+    };
+    let contents = match std::fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(err) if explicit.is_none() && err.kind() == ErrorKind::NotFound => return Ok(cmd),
+        Err(err) => {
+            return Err(ImageErrors::GenericString(format!(
+                "Could not read config file {}: {err}",
+                path.display()
+            )))
+        }
+    };
+    info!("Reading default options from {}", path.display());
+
+    apply_config(cmd, matches, &contents)
+        .map_err(|err| ImageErrors::GenericString(format!("{}: {err}", path.display())))
+}
+
+fn default_config_path() -> Option<PathBuf> {
+    let dir = |var| {
+        env::var_os(var)
+            .filter(|x| !x.is_empty())
+            .map(PathBuf::from)
+    };
+
+    let config_dir = if cfg!(windows) {
+        dir("APPDATA")
+    } else {
+        dir("XDG_CONFIG_HOME").or_else(|| dir("HOME").map(|home| home.join(".config")))
+    };
+    config_dir.map(|x| x.join("zune").join("config.toml"))
+}
+
+fn on_command_line(matches: &ArgMatches, id: &str) -> bool {
+    matches.value_source(id) == Some(ValueSource::CommandLine)
+}
+
+fn apply_config(mut cmd: Command, matches: &ArgMatches, contents: &str) -> Result<Command, String> {
+    // --strip and --keep form one policy, a config must not add to the one of the command line
+    let metadata_given = on_command_line(matches, "strip") || on_command_line(matches, "keep");
+
+    for entry in parse_config(contents)? {
+        let Some((_, id)) = KEYS.iter().find(|(key, _)| *key == entry.key) else {
+            return Err(format!("unknown option `{}`", entry.key));
+        };
+        if on_command_line(matches, id) || (metadata_given && matches!(*id, "strip" | "keep")) {
+            continue;
+        }
+        let arg = cmd.get_arguments().find(|x| x.get_id() == id).unwrap();
+
+        if entry.values.len() != 1 && !matches!(arg.get_action(), ArgAction::Append) {
+            return Err(format!("`{}` takes a single value", entry.key));
+        }
+        // defaults are only parsed when matching, so parse them here where the
+        // key they came from is still known
+        let arg = arg.clone().default_values(entry.values);
+
+        Command::new("zune")
+            .no_binary_name(true)
+            .arg(arg.clone())
+            .try_get_matches_from(Vec::<OsString>::new())
+            .map_err(|err| {
+                let message = err.to_string();
+                let message = message.lines().next().unwrap_or_default();
+
+                format!("`{}`: {}", entry.key, message.trim_start_matches("error: "))
+            })?;
+
+        cmd = cmd.mut_arg(id, |_| arg);
+    }
+    Ok(cmd)
+}
+
+/// Parse `contents` into its `key = value` entries, keys of nested tables
+/// are joined with a `.`
+fn parse_config(contents: &str) -> Result<Vec<ConfigEntry>, String> {
+    let table = contents.parse::<Table>().map_err(|err| {
+        let message = err.message().trim_end();
+
+        match err.span() {
+            Some(span) => {
+                let line = contents[..span.start].matches('\n').count() + 1;
+                format!("line {line}: {message}")
+            }
+            None => message.to_string()
+        }
+    })?;
+    let mut entries = vec![];
+
+    flatten_table(table, "", &mut entries)?;
+
+    Ok(entries)
+}
+
+fn flatten_table(table: Table, prefix: &str, entries: &mut Vec<ConfigEntry>) -> Result<(), String> {
+    for (key, value) in table {
+        let key = match prefix.is_empty() {
+            true => key,
+            false => format!("{prefix}.{key}")
+        };
+        let values = match value {
+            Value::Table(table) => {
+                flatten_table(table, &key, entries)?;
+                continue;
+            }
+            Value::Array(values) => values
+                .into_iter()
+                .map(|x| value_to_string(&key, x))
+                .collect::<Result<_, _>>()?,
+            value => vec![value_to_string(&key, value)?]
+        };
+        entries.push(ConfigEntry { key, values });
+    }
+    Ok(())
+}
+
+/// Return a string, number or boolean as the text it would be on the command line
+fn value_to_string(key: &str, value: Value) -> Result<String, String> {
+    match value {
+        Value::String(string) => Ok(string),
+        Value::Integer(number) => Ok(number.to_string()),
+        Value::Float(number) => Ok(number.to_string()),
+        Value::Boolean(boolean) => Ok(boolean.to_string()),
+        _ => Err(format!(
+            "`{key}` must be a string, number, boolean or an array of them"
+        ))
+    }
+}
+
+#[test]
+fn test_parse_config() {
+    let contents = r#"
+        # defaults for every machine
+        threads = 8
+        max-memory = "4G" # quoted
+        encode.effort = 7
+        encode.quality = 9_0
+        encode.progressive = true
+
+        [metadata]
+        strip = [
+            'exif',
+            "text", # trailing comma
+        ]
+    "#;
+    let entries = parse_config(contents).unwrap();
+    let entry = |key: &str, values: &[&str]| ConfigEntry {
+        key:    key.to_string(),
+        values: values.iter().map(|x| x.to_string()).collect()
+    };
+
+    assert_eq!(
+        entries,
+        [
+            entry("encode.effort", &["7"]),
+            entry("encode.progressive", &["true"]),
+            entry("encode.quality", &["90"]),
+            entry("max-memory", &["4G"]),
+            entry("metadata.strip", &["exif", "text"]),
+            entry("threads", &["8"])
+        ]
+    );
+
+    assert!(parse_config("quality = high").is_err());
+    assert!(parse_config("quality = \"high").is_err());
+    assert!(parse_config("strip = [\"exif\"").is_err());
+    assert!(parse_config("[encode").is_err());
+    assert!(parse_config("quality").is_err());
+    assert!(parse_config("strip = [[\"exif\"]]").is_err());
+
+    let error = parse_config("threads = 8\n\nquality = high").unwrap_err();
+    assert!(error.starts_with("line 3: "), "{error}");
+}
+
+#[test]
+fn test_config_under_command_line() {
+    let cmd = crate::cmd_args::create_cmd_args();
+    let args = ["zune", "-i", "a.png", "--quality", "50", "--keep", "icc"];
+    let matches = cmd.clone().try_get_matches_from(args).unwrap();
+
+    let contents =
+        "strict = true\n[encode]\nquality = 90\neffort = 7\n[metadata]\nstrip = [\"exif\"]";
+    let matches = apply_config(cmd, &matches, contents)
+        .unwrap()
+        .try_get_matches_from(args)
+        .unwrap();
+
+    assert_eq!(matches.get_one::<u8>("quality"), Some(&50));
+    assert_eq!(matches.get_one::<u8>("effort"), Some(&7));
+    assert_eq!(matches.get_one::<bool>("strict"), Some(&true));
+    assert!(matches
+        .get_many::<crate::cmd_args::MetadataOptions>("strip")
+        .is_none());
+
+    let cmd = crate::cmd_args::create_cmd_args();
+    let matches = cmd
+        .clone()
+        .try_get_matches_from(["zune", "-i", "a.png"])
+        .unwrap();
+    let error = apply_config(cmd.clone(), &matches, "\n[encode]\nquality = 900").unwrap_err();
+    assert!(
+        error.starts_with("`encode.quality`: invalid value '900'"),
+        "{error}"
+    );
+
+    assert!(apply_config(cmd.clone(), &matches, "colour = true").is_err());
+    assert!(apply_config(cmd, &matches, "[encode]\neffort = [1, 2]").is_err());
+}
+
+#[test]
+fn test_switches_turn_off_config() {
+    let contents = "strict = true\nsafe = true\nallow-partial = true\nencode.progressive = true";
+
+    for (flag, id) in [
+        ("--strict=false", "strict"),
+        ("--safe=false", "safe"),
+        ("--allow-partial=false", "allow-partial"),
+        ("--progressive=false", "progressive")
+    ] {
+        let cmd = crate::cmd_args::create_cmd_args();
+        let args = ["zune", "-i", "a.png", flag];
+        let matches = cmd.clone().try_get_matches_from(args).unwrap();
+
+        let matches = apply_config(cmd, &matches, contents)
+            .unwrap()
+            .try_get_matches_from(args)
+            .unwrap();
+
+        assert!(!matches.get_flag(id), "{flag}");
+    }
+    // switches without a value are still turned on
+    let matches = crate::cmd_args::create_cmd_args()
+        .try_get_matches_from(["zune", "-i", "a.png", "--strict", "--progressive"])
+        .unwrap();
+
+    assert!(matches.get_flag("strict"));
+    assert!(matches.get_flag("progressive"));
+    assert!(!matches.get_flag("safe"));
+}
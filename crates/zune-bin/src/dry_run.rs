@@ -0,0 +1,93 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! Printing of pipeline plans for `--dry-run`
+use std::ffi::OsStr;
+use std::fs::File;
+use std::ops::Deref;
+
+use memmap2::Mmap;
+use zune_core::options::DecoderOptions;
+use zune_image::codecs::ImageFormat;
+use zune_image::errors::ImageErrors;
+use zune_image::pipelines::Pipeline;
+use zune_image::traits::IntoImage;
+
+/// Print what a pipeline would do to `in_file` without decoding any pixels
+///
+/// Only image headers are read, the colorspace and depth after each
+/// operation is predicted from the operations themselves.
+///
+/// `descriptions` contains the command line that created each operation
+/// in `workflow`, in the same order.
+///
+/// `in_memory` holds the input if it was not read from `in_file`, e.g
+/// when it came from an archive.
+pub fn print_plan<T: IntoImage>(
+    in_file: &OsStr, in_memory: Option<&[u8]>, workflow: &Pipeline<T>, descriptions: &[String],
+    out_files: &[&OsStr], options: DecoderOptions
+) -> Result<(), ImageErrors> {
+    let mmap;
+    let data = match in_memory {
+        Some(data) => data,
+        None => {
+            let file = File::open(in_file)?;
+            // Unsafety: Same as probe, we only read headers from the map
+            mmap = unsafe { Mmap::map(&file)? };
+            mmap.deref()
+        }
+    };
+
+    let (format, contents) = ImageFormat::guess_format(data)
+        .ok_or(ImageErrors::ImageDecoderNotIncluded(ImageFormat::Unknown))?;
+
+    let mut decoder = format.get_decoder_with_options(contents, options)?;
+    let metadata = decoder.read_headers()?.unwrap_or_default();
+
+    let (width, height) = metadata.get_dimensions();
+    let mut colorspace = decoder.out_colorspace();
+    let mut depth = metadata.get_depth();
+
+    println!("Plan for {in_file:?}");
+    println!(
+        "  decode:  {} ({width}x{height}) -> {colorspace:?}, {depth:?}",
+        decoder.name()
+    );
+
+    for (position, operation) in workflow.operations().iter().enumerate() {
+        colorspace = operation.output_colorspace(colorspace);
+        depth = operation.output_depth(depth);
+
+        let description = descriptions.get(position).map_or("", |x| x.as_str());
+
+        println!(
+            "  {:>2}. {} [{description}] -> {colorspace:?}, {depth:?}",
+            position + 1,
+            operation.name()
+        );
+    }
+
+    for (encoder, out_file) in workflow.encoders().iter().zip(out_files) {
+        let mut out_colorspace = colorspace;
+        let mut out_depth = depth;
+
+        if !encoder.supported_colorspaces().contains(&out_colorspace) {
+            out_colorspace = encoder.default_colorspace(out_colorspace);
+        }
+        if !encoder.supported_bit_depth().contains(&out_depth) {
+            out_depth = encoder.default_depth(out_depth);
+        }
+        println!(
+            "  encode:  {} to {out_file:?} -> {out_colorspace:?}, {out_depth:?}",
+            encoder.name()
+        );
+    }
+    println!();
+
+    Ok(())
+}
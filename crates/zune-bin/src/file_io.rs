@@ -6,7 +6,7 @@
  * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
  */
 
-use std::ffi::OsString;
+use std::ffi::{OsStr, OsString};
 use std::fs::File;
 use std::io::Read;
 use std::ops::Deref;
@@ -16,11 +16,13 @@ use memmap2::Mmap;
 use zune_core::options::DecoderOptions;
 use zune_image::errors::ImageErrors;
 use zune_image::image::Image;
-use zune_image::traits::IntoImage;
+use zune_image::traits::{IntoImage, OperationsTrait};
+use zune_zip::ZipArchive;
 
 pub struct ZuneFile {
     file_path: OsString,
     use_mmap:  bool,
+    contents:  Option<Vec<u8>>,
     options:   DecoderOptions
 }
 
@@ -29,13 +31,58 @@ impl ZuneFile {
         ZuneFile {
             file_path,
             use_mmap,
+            contents: None,
+            options
+        }
+    }
+    /// Create a file whose contents were already read, e.g from an archive
+    pub fn from_memory(
+        file_path: OsString, contents: Vec<u8>, options: DecoderOptions
+    ) -> ZuneFile {
+        ZuneFile {
+            file_path,
+            use_mmap: false,
+            contents: Some(contents),
             options
         }
     }
 }
 
+/// Read an entry of a ZIP archive given as `archive:entry`
+///
+/// The entry name is everything after the last `:`,
+/// so archive paths may contain colons but entry names may not
+pub fn read_zip_entry(spec: &OsStr) -> Result<Vec<u8>, ImageErrors> {
+    let spec = spec.to_string_lossy();
+
+    let (archive_path, entry) = spec.rsplit_once(':').ok_or_else(|| {
+        ImageErrors::GenericString(format!(
+            "Expected archive:entry for --from-zip but found {spec:?}"
+        ))
+    })?;
+    info!("Reading {entry:?} from archive {archive_path:?}");
+
+    let data = std::fs::read(archive_path)?;
+    let archive = ZipArchive::new(&data)
+        .map_err(|e| ImageErrors::GenericString(format!("{archive_path}: {e:?}")))?;
+
+    archive
+        .read_by_name(entry)
+        .map_err(|e| ImageErrors::GenericString(format!("{archive_path}: {e:?}")))
+}
+
 impl IntoImage for ZuneFile {
     fn into_image(self) -> Result<Image, ImageErrors> {
+        self.into_image_with(&[]).map(|(image, _)| image)
+    }
+
+    fn into_image_with(
+        self, operations: &[&dyn OperationsTrait]
+    ) -> Result<(Image, usize), ImageErrors> {
+        if let Some(contents) = self.contents {
+            info!("Reading {:?} from memory", self.file_path);
+            return Image::read_with(contents, self.options, operations);
+        }
         // read file
         let mut fd = File::open(self.file_path)?;
         let mmap = unsafe { Mmap::map(&fd)? };
@@ -55,6 +102,6 @@ impl IntoImage for ZuneFile {
             }
         };
 
-        Image::read(data, self.options)
+        Image::read_with(data, self.options, operations)
     }
 }
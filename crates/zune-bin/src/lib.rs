@@ -8,37 +8,73 @@
 
 use std::process::exit;
 
+use clap::ArgMatches;
 use log::error;
+use zune_image::errors::ImageErrors;
 
 use crate::cmd_args::MmapOptions;
+use crate::report::{print_fatal_report, EXIT_FATAL, EXIT_OK};
 use crate::workflow::create_and_exec_workflow_from_cmd;
 
 mod cmd_args;
 mod cmd_parsers;
+mod config;
+mod dry_run;
 mod file_io;
+mod out_template;
 mod probe_files;
+mod report;
 mod serde;
 mod show_gui;
+mod subcommands;
+mod watch;
 mod workflow;
 
+#[cfg(feature = "mem-stats")]
+#[global_allocator]
+static GLOBAL: zune_image::mem_stats::TrackingAllocator =
+    zune_image::mem_stats::TrackingAllocator::system();
+
 pub fn main() {
     let cmd = cmd_args::create_cmd_args();
-    let options = cmd.get_matches();
+    let options = cmd.clone().get_matches();
 
     cmd_parsers::global_options::setup_logger(&options);
 
+    // parse again with the defaults of the config file, flags given on the command line win
+    let options = match config::add_config_defaults(cmd, &options) {
+        Ok(cmd) => cmd.get_matches(),
+        Err(err) => exit_with_error(&options, &err)
+    };
+
     let parsed_opts = cmd_parsers::global_options::parse_options(&options);
 
-    let result = create_and_exec_workflow_from_cmd(&options, &parsed_opts);
+    let result = match options.subcommand() {
+        Some((name, sub_options)) => {
+            subcommands::run_subcommand(name, sub_options, &options).map(|()| EXIT_OK)
+        }
+        None => create_and_exec_workflow_from_cmd(&options, &parsed_opts)
+    };
 
-    if result.is_err() {
-        println!();
-        error!(
-            " Could not complete workflow, reason {:?}",
-            result.err().unwrap()
-        );
+    match result {
+        Ok(EXIT_OK) => (),
+        Ok(code) => exit(code),
+        Err(err) => exit_with_error(&options, &err)
+    }
+}
 
-        println!();
-        exit(-1);
+fn exit_with_error(options: &ArgMatches, err: &ImageErrors) -> ! {
+    if options.get_one::<String>("report").is_some() {
+        print_fatal_report(err);
+        exit(EXIT_FATAL);
     }
+    println!();
+    error!(" Could not complete workflow, reason {:?}", err);
+
+    if let Some(context) = err.context() {
+        println!("Bytes around offset {:#x}:\n{context}", context.offset());
+    }
+
+    println!();
+    exit(EXIT_FATAL);
 }
@@ -0,0 +1,164 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! Output file names for `--out-template`
+use std::ffi::OsStr;
+use std::path::Path;
+
+use zune_image::codecs::ImageFormat;
+
+/// Values substituted into an output template for one input
+pub struct TemplateVars<'a> {
+    /// The input file
+    pub in_file:    &'a OsStr,
+    /// Format of the input
+    pub format:     ImageFormat,
+    /// Position of the input in the command line, starting at zero
+    pub index:      usize,
+    /// Output width and height, not known before the pipeline runs
+    pub dimensions: Option<(usize, usize)>
+}
+
+/// Expand a template like `{stem}_{width}x{height}.{ext}` into a file name
+///
+/// Supported variables are
+/// - `{stem}`: input file name without extension
+/// - `{ext}`: input file extension
+/// - `{format}`: preferred extension of the input format, e.g `jpg`
+/// - `{width}`,`{height}`: dimensions of the output image
+/// - `{index}`: position of the input, starting at zero
+///
+/// `{{` and `}}` are a literal brace. Before the pipeline runs dimensions
+/// expand to `0`. The output format is chosen at that point, so the extension
+/// can't use `{width}` or `{height}`.
+pub fn expand_template(template: &str, vars: &TemplateVars) -> Result<String, String> {
+    let out = substitute(template, vars, vars.dimensions.unwrap_or((0, 0)))?;
+
+    if let Some(ext) = Path::new(template).extension().and_then(OsStr::to_str) {
+        if substitute(ext, vars, (0, 0))? != substitute(ext, vars, (1, 1))? {
+            return Err(format!(
+                "The extension of template {template:?} can't use {{width}} or {{height}}, the output format is chosen before they are known"
+            ));
+        }
+    }
+    Ok(out)
+}
+
+/// Expand `template` with `dimensions` as the output width and height
+fn substitute(
+    template: &str, vars: &TemplateVars, dimensions: (usize, usize)
+) -> Result<String, String> {
+    let path = Path::new(vars.in_file);
+    let (width, height) = dimensions;
+
+    let mut out = String::with_capacity(template.len());
+    let mut chars = template.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' => {
+                let mut name = String::new();
+
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some('{') if name.is_empty() => {
+                            name.push('{');
+                            break;
+                        }
+                        Some(c) => name.push(c),
+                        None => return Err(format!("Unclosed `{{` in template {template:?}"))
+                    }
+                }
+                match name.as_str() {
+                    "{" => out.push('{'),
+                    "stem" => out.push_str(&path.file_stem().unwrap_or_default().to_string_lossy()),
+                    "ext" => out.push_str(&path.extension().unwrap_or_default().to_string_lossy()),
+                    "format" => {
+                        out.push_str(vars.format.file_extensions().first().copied().unwrap_or(""))
+                    }
+                    "width" => out.push_str(&width.to_string()),
+                    "height" => out.push_str(&height.to_string()),
+                    "index" => out.push_str(&vars.index.to_string()),
+                    _ => {
+                        return Err(format!(
+                            "Unknown variable `{{{name}}}` in template {template:?}, expected one of stem, ext, format, width, height or index"
+                        ))
+                    }
+                }
+            }
+            '}' => {
+                // `}}` is a literal brace, a lone one is kept as is
+                if chars.clone().next() == Some('}') {
+                    chars.next();
+                }
+                out.push('}');
+            }
+            c => out.push(c)
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+fn test_vars(dimensions: Option<(usize, usize)>) -> TemplateVars<'static> {
+    TemplateVars {
+        in_file: OsStr::new("photos/cat.jpeg"),
+        format:  ImageFormat::JPEG,
+        index:   3,
+        dimensions
+    }
+}
+
+#[test]
+fn test_expand_variables() {
+    let vars = test_vars(Some((800, 600)));
+
+    assert_eq!(
+        expand_template("{stem}_{width}x{height}.{ext}", &vars).unwrap(),
+        "cat_800x600.jpeg"
+    );
+    assert_eq!(
+        expand_template("out/{index}-{stem}.{format}", &vars).unwrap(),
+        "out/3-cat.jpg"
+    );
+    // dimensions are zero before the pipeline runs
+    assert_eq!(
+        expand_template("{width}x{height}.png", &test_vars(None)).unwrap(),
+        "0x0.png"
+    );
+}
+
+#[test]
+fn test_expand_braces() {
+    let vars = test_vars(None);
+
+    assert_eq!(expand_template("{{stem}}.png", &vars).unwrap(), "{stem}.png");
+    assert_eq!(expand_template("a}b.png", &vars).unwrap(), "a}b.png");
+    assert!(expand_template("{stem.png", &vars)
+        .unwrap_err()
+        .contains("Unclosed"));
+    assert!(expand_template("{name}.png", &vars)
+        .unwrap_err()
+        .contains("Unknown variable"));
+}
+
+#[test]
+fn test_extension_without_dimensions() {
+    let vars = test_vars(Some((800, 600)));
+
+    assert!(expand_template("{stem}.{width}", &vars)
+        .unwrap_err()
+        .contains("extension"));
+    assert!(expand_template("{stem}.p{height}g", &vars).is_err());
+    // a literal brace is not a variable
+    assert_eq!(
+        expand_template("{stem}.{{width}}", &vars).unwrap(),
+        "cat.{width}"
+    );
+}
@@ -6,22 +6,33 @@
  * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
  */
 
+use std::ffi::{OsStr, OsString};
 use std::fs::File;
 use std::ops::Deref;
 use std::path::PathBuf;
 
 use clap::parser::ValueSource::CommandLine;
 use clap::ArgMatches;
+use log::error;
 use memmap2::Mmap;
 use zune_core::options::DecoderOptions;
 
+use crate::cmd_parsers::chain::split_inputs;
+use crate::file_io::read_zip_entry;
 use crate::serde::Metadata;
 
 /// Probe input files, extract metadata, and print to standard output.
 pub fn probe_input_files(args: &ArgMatches) {
     if let Some(view) = args.value_source("probe") {
         if view == CommandLine {
-            for in_file in args.get_raw("in").unwrap() {
+            if let Some(spec) = args.get_one::<OsString>("from-zip") {
+                match read_zip_entry(spec) {
+                    Ok(contents) => probe_contents(spec, contents.len() as u64, &contents),
+                    Err(e) => error!("Could not read {spec:?}: {e:?}")
+                }
+                return;
+            }
+            for in_file in split_inputs(args).0 {
                 if PathBuf::from(in_file).exists() {
                     let file = File::open(in_file).unwrap();
                     let file_size = file.metadata().unwrap().len();
@@ -29,29 +40,27 @@ pub fn probe_input_files(args: &ArgMatches) {
                     // will occur if you mess with the file
                     let mmap = unsafe { Mmap::map(&file).unwrap() };
 
-                    let file_contents = mmap.deref();
+                    probe_contents(in_file, file_size, mmap.deref());
+                }
+            }
+        }
+    }
+}
 
-                    if let Some((format, contents)) =
-                        zune_image::codecs::ImageFormat::guess_format(file_contents)
-                    {
-                        // set to high to remove restrictions.
-                        // We'll just be reading headers so it doesn't matter
-                        let options = DecoderOptions::new_cmd()
-                            .set_max_height(usize::MAX)
-                            .set_max_width(usize::MAX);
+fn probe_contents(in_file: &OsStr, file_size: u64, file_contents: &[u8]) {
+    if let Some((format, contents)) = zune_image::codecs::ImageFormat::guess_format(file_contents) {
+        // set to high to remove restrictions.
+        // We'll just be reading headers so it doesn't matter
+        let options = DecoderOptions::new_cmd()
+            .set_max_height(usize::MAX)
+            .set_max_width(usize::MAX);
 
-                        let mut decoder =
-                            format.get_decoder_with_options(contents, options).unwrap();
+        let mut decoder = format.get_decoder_with_options(contents, options).unwrap();
 
-                        if let Ok(Some(metadata)) = decoder.read_headers() {
-                            let real_metadata =
-                                Metadata::new(in_file.to_os_string(), file_size, &metadata);
+        if let Ok(Some(metadata)) = decoder.read_headers() {
+            let real_metadata = Metadata::new(in_file.to_os_string(), file_size, &metadata);
 
-                            println!("{}", serde_json::to_string_pretty(&real_metadata).unwrap());
-                        }
-                    }
-                }
-            }
+            println!("{}", serde_json::to_string_pretty(&real_metadata).unwrap());
         }
     }
 }
@@ -0,0 +1,131 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! Machine readable results of a run, printed with `--report json`
+//!
+//! Log output is disabled while reporting, so the report is the only thing on stdout
+//! and warnings that would have been logged are kept with the file they are about.
+use std::ffi::{OsStr, OsString};
+use std::time::Duration;
+
+use log::warn;
+use serde_json::{json, Value};
+use zune_image::errors::ImageErrors;
+
+/// Every file was processed
+pub const EXIT_OK: i32 = 0;
+/// Some files could not be processed, the others were
+pub const EXIT_FILES_FAILED: i32 = 1;
+/// Nothing could be processed, e.g because of invalid arguments
+pub const EXIT_FATAL: i32 = 2;
+
+/// What happened to one input file
+pub struct FileReport {
+    input:          OsString,
+    pub outputs:    Vec<OsString>,
+    pub dimensions: Option<(usize, usize)>,
+    pub operations: Vec<String>,
+    pub elapsed:    Duration,
+    warnings:       Vec<String>,
+    error:          Option<String>
+}
+
+impl FileReport {
+    pub fn new(input: &OsStr) -> FileReport {
+        FileReport {
+            input:      input.to_os_string(),
+            outputs:    vec![],
+            dimensions: None,
+            operations: vec![],
+            elapsed:    Duration::ZERO,
+            warnings:   vec![],
+            error:      None
+        }
+    }
+    /// Log `message` as a warning and record it for this file
+    pub fn warn(&mut self, message: String) {
+        warn!("{message}");
+        self.warnings.push(message);
+    }
+    /// Record the error that stopped processing this file
+    pub fn set_error(&mut self, error: &ImageErrors) {
+        self.error = Some(format!("{error:?}").trim_end().to_string());
+    }
+
+    pub const fn failed(&self) -> bool {
+        self.error.is_some()
+    }
+
+    fn to_json(&self) -> Value {
+        json!({
+            "input": self.input.to_string_lossy(),
+            "outputs": self.outputs.iter().map(|x| x.to_string_lossy()).collect::<Vec<_>>(),
+            "dimensions": self.dimensions.map(|(width, height)| json!({
+                "width": width,
+                "height": height
+            })),
+            "operations": self.operations,
+            "time_ms": self.elapsed.as_secs_f64() * 1000.0,
+            "warnings": self.warnings,
+            "error": self.error
+        })
+    }
+}
+
+/// The exit code of a run where `files` were processed
+pub fn exit_code(files: &[FileReport]) -> i32 {
+    if files.iter().any(FileReport::failed) {
+        EXIT_FILES_FAILED
+    } else {
+        EXIT_OK
+    }
+}
+
+/// Print the report of a run where `files` were processed
+pub fn print_report(files: &[FileReport]) {
+    let status = match exit_code(files) {
+        EXIT_OK => "ok",
+        _ => "failed"
+    };
+    print_json(&json!({
+        "status": status,
+        "files": files.iter().map(FileReport::to_json).collect::<Vec<_>>()
+    }));
+}
+
+/// Print the report of a run that stopped with `error` before processing files
+pub fn print_fatal_report(error: &ImageErrors) {
+    print_json(&json!({
+        "status": "fatal",
+        "error": format!("{error:?}").trim_end(),
+        "files": []
+    }));
+}
+
+fn print_json(value: &Value) {
+    println!("{}", serde_json::to_string_pretty(value).unwrap());
+}
+
+#[test]
+fn test_report_status() {
+    let mut converted = FileReport::new(OsStr::new("a.png"));
+    converted.dimensions = Some((3, 2));
+    converted.warn("a.png is truncated".to_string());
+
+    let json = converted.to_json();
+    assert_eq!(json["dimensions"]["width"], 3);
+    assert_eq!(json["warnings"][0], "a.png is truncated");
+    assert!(json["error"].is_null());
+
+    let mut failed = FileReport::new(OsStr::new("b.png"));
+    failed.set_error(&ImageErrors::GenericStr("broken"));
+
+    assert_eq!(exit_code(&[]), EXIT_OK);
+    assert_eq!(exit_code(&[converted]), EXIT_OK);
+    assert_eq!(exit_code(&[failed]), EXIT_FILES_FAILED);
+}
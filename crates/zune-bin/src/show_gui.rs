@@ -12,10 +12,11 @@ use std::io::Write;
 use std::time::UNIX_EPOCH;
 
 use zune_image::codecs::png::PngEncoder;
+use zune_image::errors::ImageErrors;
 use zune_image::image::Image;
 use zune_image::traits::EncoderTrait;
 
-pub fn open_in_default_app(image: &Image) {
+pub fn open_in_default_app(image: &Image) -> Result<(), ImageErrors> {
     let time = format!(
         "{}.png",
         std::time::SystemTime::now()
@@ -31,31 +32,28 @@ pub fn open_in_default_app(image: &Image) {
         .write(true)
         .truncate(true)
         .create(true)
-        .open(&path)
-        .unwrap();
+        .open(&path)?;
 
-    let data = PngEncoder::new().encode(image).unwrap();
-    file.write_all(&data).unwrap();
+    let data = PngEncoder::new().encode(image)?;
+    file.write_all(&data)?;
 
     #[cfg(target_os = "linux")]
     {
         std::process::Command::new("xdg-open")
-            .arg(path.to_str().unwrap())
-            .spawn()
-            .unwrap();
+            .arg(&path)
+            .spawn()?;
     }
     #[cfg(target_os = "windows")]
     {
         std::process::Command::new("start")
-            .arg(path.to_str().unwrap())
-            .spawn()
-            .unwrap();
+            .arg(&path)
+            .spawn()?;
     }
     #[cfg(target_os = "macos")]
     {
         std::process::Command::new("open")
-            .arg(path.to_str().unwrap())
-            .spawn()
-            .unwrap();
+            .arg(&path)
+            .spawn()?;
     }
+    Ok(())
 }
@@ -0,0 +1,40 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! Subcommands, these run instead of the normal decode-process-encode workflow
+use clap::ArgMatches;
+use zune_image::errors::ImageErrors;
+
+mod analyze;
+mod compare;
+mod formats;
+mod hash;
+mod montage;
+mod sprite_sheet;
+mod stack;
+
+/// Run the subcommand `name` with its arguments
+///
+/// `top_level` contains the arguments passed before the subcommand,
+/// and is used to configure things like decoder options
+pub(crate) fn run_subcommand(
+    name: &str, args: &ArgMatches, top_level: &ArgMatches
+) -> Result<(), ImageErrors> {
+    match name {
+        "analyze" => analyze::analyze(args),
+        "compare" => compare::compare(args, top_level),
+        "formats" => formats::formats(args),
+        "hash" => hash::hash(args, top_level),
+        "montage" => montage::montage(args, top_level),
+        "spritesheet" => sprite_sheet::sprite_sheet(args, top_level),
+        "stack" => stack::stack(args, top_level),
+        _ => Err(ImageErrors::GenericString(format!(
+            "Unknown subcommand {name}"
+        )))
+    }
+}
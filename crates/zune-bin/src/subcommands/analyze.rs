@@ -0,0 +1,117 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+use std::ffi::OsString;
+
+use clap::ArgMatches;
+use zune_image::errors::ImageErrors;
+use zune_inflate::analyze::{BlockInfo, BlockType};
+use zune_inflate::DeflateDecoder;
+
+const PNG_SIGNATURE: [u8; 8] = [137, 80, 78, 71, 13, 10, 26, 10];
+
+/// Concatenate the contents of the IDAT chunks of a png file, which form
+/// a single zlib stream
+fn png_idat(data: &[u8]) -> Result<Vec<u8>, ImageErrors> {
+    if !data.starts_with(&PNG_SIGNATURE) {
+        return Err(ImageErrors::GenericStr("Not a png file"));
+    }
+    let mut position = PNG_SIGNATURE.len();
+    let mut idat = vec![];
+
+    // length, type, contents and crc
+    while let Some(header) = data.get(position..position + 8) {
+        let length = u32::from_be_bytes(header[..4].try_into().unwrap()) as usize;
+        let contents = data
+            .get(position + 8..position + 8 + length)
+            .ok_or(ImageErrors::GenericStr("Truncated png chunk"))?;
+
+        if &header[4..] == b"IDAT" {
+            idat.extend_from_slice(contents);
+        }
+        position += 12 + length;
+    }
+    if idat.is_empty() {
+        return Err(ImageErrors::GenericStr("No IDAT chunks found"));
+    }
+    Ok(idat)
+}
+
+fn type_name(block: &BlockInfo) -> &'static str {
+    match block.block_type {
+        BlockType::Stored => "stored",
+        BlockType::Static => "static",
+        BlockType::Dynamic => "dynamic"
+    }
+}
+
+/// Print the deflate blocks of a zlib or raw deflate stream, or of png image data
+pub(crate) fn analyze(args: &ArgMatches) -> Result<(), ImageErrors> {
+    let file = args.get_one::<OsString>("file").unwrap();
+    let data = std::fs::read(file)?;
+
+    let stream = if args.get_flag("idat") { png_idat(&data)? } else { data };
+    let mut decoder = DeflateDecoder::new(&stream);
+
+    let blocks = if args.get_flag("raw") { decoder.analyze() } else { decoder.analyze_zlib() }
+        .map_err(|x| ImageErrors::GenericString(format!("Could not analyze {file:?}: {x:?}")))?;
+
+    println!(
+        "{:>5} {:<8} {:>10} {:>10} {:>10} {:>7} {:>9} {:>8} {:>7} {:>7} {:>7}",
+        "Block",
+        "Type",
+        "Bit",
+        "In bytes",
+        "Out bytes",
+        "Ratio",
+        "Literals",
+        "Matches",
+        "Litlen",
+        "Offset",
+        "Header"
+    );
+    for (position, block) in blocks.iter().enumerate() {
+        let in_bytes = block.compressed_bits as f64 / 8.0;
+        let ratio = block.uncompressed_size as f64 / in_bytes;
+
+        // used codes and the bits spent describing them, stored blocks have none
+        let (litlen, offset, header) = match &block.huffman {
+            Some(huffman) => (
+                huffman.used_litlen_codes().to_string(),
+                huffman.used_offset_codes().to_string(),
+                huffman.header_bits.to_string()
+            ),
+            None => ("-".into(), "-".into(), "-".into())
+        };
+        println!(
+            "{:>5} {:<8} {:>10} {:>10.1} {:>10} {:>7.2} {:>9} {:>8} {:>7} {:>7} {:>7}",
+            position,
+            type_name(block),
+            block.start_bit,
+            in_bytes,
+            block.uncompressed_size,
+            ratio,
+            block.literals,
+            block.matches,
+            litlen,
+            offset,
+            header
+        );
+    }
+    let in_bits: usize = blocks.iter().map(|x| x.compressed_bits).sum();
+    let out_bytes: usize = blocks.iter().map(|x| x.uncompressed_size).sum();
+
+    println!(
+        "{} blocks, {:.1} bytes in, {} bytes out, ratio {:.2}",
+        blocks.len(),
+        in_bits as f64 / 8.0,
+        out_bytes,
+        out_bytes as f64 * 8.0 / in_bits as f64
+    );
+    Ok(())
+}
@@ -0,0 +1,43 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+use std::ffi::OsString;
+
+use clap::ArgMatches;
+use log::info;
+use zune_image::errors::ImageErrors;
+use zune_image::image::Image;
+use zune_image::traits::OperationsTrait;
+use zune_imageprocs::diff_highlight::DiffHighlight;
+
+use crate::cmd_parsers::get_decoder_options;
+
+pub(crate) fn compare(args: &ArgMatches, top_level: &ArgMatches) -> Result<(), ImageErrors> {
+    let first = args.get_one::<OsString>("first").unwrap();
+    let second = args.get_one::<OsString>("second").unwrap();
+    let tolerance = *args.get_one::<f32>("tolerance").unwrap();
+
+    let options = get_decoder_options(top_level);
+
+    let reference = Image::open_with_options(first, options)?;
+    let mut image = Image::open_with_options(second, options)?;
+
+    if *args.get_one::<bool>("visual").unwrap() {
+        let out = args.get_one::<OsString>("out").unwrap();
+
+        DiffHighlight::new(&reference, tolerance).execute(&mut image)?;
+
+        info!("Writing visual diff to {:?}", out);
+        image.save(out)?;
+    } else if reference == image {
+        println!("{first:?} and {second:?} are identical");
+    } else {
+        println!("{first:?} and {second:?} differ");
+    }
+    Ok(())
+}
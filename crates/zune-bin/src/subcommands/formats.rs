@@ -0,0 +1,46 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+use clap::ArgMatches;
+use zune_image::errors::ImageErrors;
+use zune_image::registry::available_codecs;
+
+fn yes_no(value: bool) -> &'static str {
+    if value {
+        "yes"
+    } else {
+        "no"
+    }
+}
+
+/// Print codecs available in this build
+pub(crate) fn formats(_: &ArgMatches) -> Result<(), ImageErrors> {
+    println!(
+        "{:<10} {:<7} {:<7} {:<22} Encode colorspaces",
+        "Format", "Decode", "Encode", "Extensions"
+    );
+
+    for codec in available_codecs() {
+        let colorspaces = codec
+            .encode_colorspaces()
+            .iter()
+            .map(|x| format!("{x:?}"))
+            .collect::<Vec<String>>()
+            .join(",");
+
+        println!(
+            "{:<10} {:<7} {:<7} {:<22} {}",
+            codec.name(),
+            yes_no(codec.has_decoder()),
+            yes_no(codec.has_encoder()),
+            codec.extensions().join(","),
+            if colorspaces.is_empty() { "-" } else { &colorspaces }
+        );
+    }
+    Ok(())
+}
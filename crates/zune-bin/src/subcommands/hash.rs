@@ -0,0 +1,33 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+use std::ffi::OsString;
+
+use clap::ArgMatches;
+use zune_image::errors::ImageErrors;
+use zune_image::hash::HashAlgorithm;
+use zune_image::image::Image;
+
+use crate::cmd_parsers::get_decoder_options;
+
+pub(crate) fn hash(args: &ArgMatches, top_level: &ArgMatches) -> Result<(), ImageErrors> {
+    let algorithm = match args.get_one::<String>("algorithm").unwrap().as_str() {
+        "average" => HashAlgorithm::Average,
+        "difference" => HashAlgorithm::Difference,
+        "perceptual" => HashAlgorithm::Perceptual,
+        _ => unreachable!()
+    };
+    let options = get_decoder_options(top_level);
+
+    for file in args.get_many::<OsString>("files").unwrap() {
+        let image = Image::open_with_options(file, options)?;
+
+        println!("{:016x}  {:?}", algorithm.hash(&image)?, file);
+    }
+    Ok(())
+}
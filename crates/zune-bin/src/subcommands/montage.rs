@@ -0,0 +1,37 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+use std::ffi::OsString;
+
+use clap::ArgMatches;
+use log::info;
+use zune_image::errors::ImageErrors;
+use zune_image::image::Image;
+use zune_imageprocs::montage::Montage;
+
+use crate::cmd_parsers::get_decoder_options;
+
+pub(crate) fn montage(args: &ArgMatches, top_level: &ArgMatches) -> Result<(), ImageErrors> {
+    let out = args.get_one::<OsString>("out").unwrap();
+    let options = get_decoder_options(top_level);
+
+    let images = args
+        .get_many::<OsString>("files")
+        .unwrap()
+        .map(|file| Image::open_with_options(file, options))
+        .collect::<Result<Vec<Image>, ImageErrors>>()?;
+
+    let montage = Montage::new()
+        .set_columns(*args.get_one::<usize>("columns").unwrap())
+        .set_padding(*args.get_one::<usize>("padding").unwrap())
+        .set_background(*args.get_one::<[u8; 4]>("background").unwrap())
+        .build(&images)?;
+
+    info!("Writing montage of {} images to {:?}", images.len(), out);
+    montage.save(out)
+}
@@ -0,0 +1,114 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+use clap::ArgMatches;
+use log::info;
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+use zune_image::errors::ImageErrors;
+use zune_image::image::Image;
+use zune_imageprocs::sprite_sheet::{SpriteRect, SpriteSheet};
+
+use crate::cmd_parsers::get_decoder_options;
+
+/// The JSON manifest written next to the sprite sheet
+struct Manifest<'a> {
+    image:   &'a OsString,
+    width:   usize,
+    height:  usize,
+    sprites: Vec<Sprite<'a>>
+}
+
+/// Where a single input file ended up in the sheet
+struct Sprite<'a> {
+    file: &'a OsString,
+    rect: SpriteRect
+}
+
+impl Serialize for Manifest<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer
+    {
+        let mut state = serializer.serialize_struct("Manifest", 4)?;
+
+        state.serialize_field("image", &self.image.to_string_lossy())?;
+        state.serialize_field("width", &self.width)?;
+        state.serialize_field("height", &self.height)?;
+        state.serialize_field("sprites", &self.sprites)?;
+
+        state.end()
+    }
+}
+
+impl Serialize for Sprite<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer
+    {
+        let mut state = serializer.serialize_struct("Sprite", 5)?;
+
+        state.serialize_field("file", &self.file.to_string_lossy())?;
+        state.serialize_field("x", &self.rect.x)?;
+        state.serialize_field("y", &self.rect.y)?;
+        state.serialize_field("width", &self.rect.width)?;
+        state.serialize_field("height", &self.rect.height)?;
+
+        state.end()
+    }
+}
+
+pub(crate) fn sprite_sheet(args: &ArgMatches, top_level: &ArgMatches) -> Result<(), ImageErrors> {
+    let out = args.get_one::<OsString>("out").unwrap();
+    let manifest_file = match args.get_one::<OsString>("manifest") {
+        Some(file) => PathBuf::from(file),
+        None => Path::new(out).with_extension("json")
+    };
+    let options = get_decoder_options(top_level);
+
+    let files: Vec<&OsString> = args.get_many::<OsString>("files").unwrap().collect();
+    let images = files
+        .iter()
+        .map(|file| Image::open_with_options(file, options))
+        .collect::<Result<Vec<Image>, ImageErrors>>()?;
+
+    let (sheet, rects) = SpriteSheet::new()
+        .set_max_width(*args.get_one::<usize>("max-width").unwrap())
+        .set_padding(*args.get_one::<usize>("padding").unwrap())
+        .build(&images)?;
+
+    let (width, height) = sheet.dimensions();
+    let manifest = Manifest {
+        image: out,
+        width,
+        height,
+        sprites: files
+            .iter()
+            .zip(rects)
+            .map(|(file, rect)| Sprite { file, rect })
+            .collect()
+    };
+
+    info!(
+        "Writing {width}x{height} sprite sheet of {} images to {:?}",
+        images.len(),
+        out
+    );
+    sheet.save(out)?;
+
+    info!("Writing sprite sheet manifest to {:?}", manifest_file);
+    let json = serde_json::to_string_pretty(&manifest)
+        .map_err(|x| ImageErrors::GenericString(x.to_string()))?;
+
+    std::fs::write(manifest_file, json)?;
+
+    Ok(())
+}
@@ -0,0 +1,42 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+use std::ffi::OsString;
+
+use clap::ArgMatches;
+use log::info;
+use zune_image::errors::ImageErrors;
+use zune_image::image::Image;
+use zune_image::stack::StackMode;
+
+use crate::cmd_parsers::get_decoder_options;
+
+pub(crate) fn stack(args: &ArgMatches, top_level: &ArgMatches) -> Result<(), ImageErrors> {
+    let mode = match args.get_one::<String>("mode").unwrap().as_str() {
+        "mean" => StackMode::Mean,
+        "median" => StackMode::Median,
+        "max" => StackMode::Max,
+        "min" => StackMode::Min,
+        _ => unreachable!()
+    };
+    let out = args.get_one::<OsString>("out").unwrap();
+    let options = get_decoder_options(top_level);
+
+    let images = args
+        .get_many::<OsString>("files")
+        .unwrap()
+        .map(|file| Image::open_with_options(file, options))
+        .collect::<Result<Vec<Image>, ImageErrors>>()?;
+
+    info!("Stacking {} images using {:?}", images.len(), mode);
+
+    let image = Image::stack(&images, mode)?;
+
+    info!("Writing stacked image to {:?}", out);
+    image.save(out)
+}
@@ -0,0 +1,261 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! Hot folder processing for `--watch`
+//!
+//! The input directory is polled, new and changed files are run through
+//! the pipeline configured on the command line once they stop changing.
+use std::collections::{HashMap, HashSet};
+use std::ffi::{OsStr, OsString};
+use std::fs;
+use std::path::PathBuf;
+use std::thread::sleep;
+use std::time::{Duration, SystemTime};
+
+use clap::ArgMatches;
+use log::{error, info, warn};
+use zune_image::codecs::ImageFormat;
+use zune_image::errors::ImageErrors;
+
+use crate::cmd_parsers::global_options::CmdOptions;
+use crate::report::FileReport;
+use crate::workflow::process_file;
+
+/// Process files of the directory given to `--watch` as they appear or change
+///
+/// This only returns if the directory can't be read
+pub(crate) fn watch_directory(args: &ArgMatches, cmd_opts: &CmdOptions) -> Result<(), ImageErrors> {
+    let dir = args.get_one::<OsString>("watch").unwrap();
+    let interval = Duration::from_millis(*args.get_one::<u64>("watch-interval").unwrap());
+
+    if !fs::metadata(dir)?.is_dir() {
+        return Err(ImageErrors::GenericString(format!(
+            "{dir:?} is not a directory"
+        )));
+    }
+    info!("Watching {dir:?} for new files, press Ctrl+C to stop");
+
+    let mut watcher = Watcher::default();
+
+    loop {
+        watcher.scan(args, cmd_opts, dir)?;
+        sleep(interval);
+    }
+}
+
+/// Files seen by `--watch` across scans of the directory
+#[derive(Default)]
+struct Watcher {
+    /// Files and their state in the previous scan
+    previous:  HashMap<PathBuf, FileState>,
+    /// State of files when they were last processed
+    processed: HashMap<PathBuf, FileState>,
+    /// Everything we wrote, outputs may be written to the watched directory
+    outputs:   HashSet<PathBuf>,
+    /// Number of files processed so far
+    index:     usize
+}
+
+impl Watcher {
+    /// Scan `dir` once, processing files that didn't change since the previous scan
+    fn scan(
+        &mut self, args: &ArgMatches, cmd_opts: &CmdOptions, dir: &OsStr
+    ) -> Result<(), ImageErrors> {
+        let current = scan_directory(dir)?;
+
+        for (path, state) in &current {
+            if self.processed.get(path) == Some(state) || self.outputs.contains(path) {
+                continue;
+            }
+            // writers may still be copying the file, wait until it looks
+            // the same in two scans
+            if self.previous.get(path) != Some(state) {
+                continue;
+            }
+            info!("Processing {path:?}");
+
+            let mut written_files = HashSet::new();
+
+            match process_file(
+                args,
+                cmd_opts,
+                path.as_os_str(),
+                self.index,
+                None,
+                true,
+                &mut written_files,
+                &mut FileReport::new(path.as_os_str())
+            ) {
+                Ok(()) => self.index += 1,
+                Err(ImageErrors::ImageDecoderNotIncluded(ImageFormat::Unknown)) => {
+                    warn!("Skipping {path:?}, it is not a supported image")
+                }
+                Err(err) => error!("Could not process {path:?}, reason {err:?}")
+            }
+            self.outputs.extend(
+                written_files
+                    .iter()
+                    .filter_map(|x| fs::canonicalize(x).ok())
+            );
+            self.processed.insert(path.clone(), *state);
+        }
+        // deleted files are processed again if they come back
+        self.processed.retain(|path, _| current.contains_key(path));
+        self.previous = current;
+
+        Ok(())
+    }
+}
+
+/// Size and modification time of a file, a file is ready once
+/// these stop changing
+#[derive(Copy, Clone, PartialEq, Eq)]
+struct FileState {
+    len:      u64,
+    modified: Option<SystemTime>
+}
+
+/// Return the regular files of `dir` and their state, keyed by their canonical path
+///
+/// Hidden files are skipped, many tools copy into a hidden temporary file
+/// and rename it once done
+fn scan_directory(dir: &OsStr) -> Result<HashMap<PathBuf, FileState>, ImageErrors> {
+    let mut files = HashMap::new();
+
+    for entry in fs::read_dir(dir)? {
+        // files may be removed while we scan
+        let Ok(entry) = entry else {
+            continue;
+        };
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        if !metadata.is_file() {
+            continue;
+        }
+        let Ok(path) = fs::canonicalize(entry.path()) else {
+            continue;
+        };
+        let state = FileState {
+            len:      metadata.len(),
+            modified: metadata.modified().ok()
+        };
+        files.insert(path, state);
+    }
+    Ok(files)
+}
+
+#[cfg(test)]
+fn test_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("zune-watch-{name}-{}", std::process::id()));
+
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[cfg(test)]
+fn test_args(args: &[&OsStr]) -> ArgMatches {
+    crate::cmd_args::create_cmd_args()
+        .try_get_matches_from(std::iter::once(OsStr::new("zune")).chain(args.iter().copied()))
+        .unwrap()
+}
+
+#[cfg(test)]
+fn dir_entries(dir: &std::path::Path) -> Vec<OsString> {
+    let mut files: Vec<OsString> = fs::read_dir(dir)
+        .unwrap()
+        .map(|x| x.unwrap().file_name())
+        .collect();
+    files.sort();
+    files
+}
+
+#[test]
+fn test_watch_skips_outputs() {
+    use zune_core::colorspace::ColorSpace;
+    use zune_image::image::Image;
+
+    let dir = test_dir("outputs");
+    Image::fill(128_u8, ColorSpace::RGB, 4, 4)
+        .save(dir.join("a.ppm"))
+        .unwrap();
+
+    // outputs are written to the watched directory
+    let template = dir.join("{stem}_out.ppm");
+    let args = test_args(&[
+        OsStr::new("--watch"),
+        dir.as_os_str(),
+        OsStr::new("--out-template"),
+        template.as_os_str()
+    ]);
+    let cmd_opts = crate::cmd_parsers::global_options::parse_options(&args);
+    let mut watcher = Watcher::default();
+
+    for _ in 0..4 {
+        watcher.scan(&args, &cmd_opts, dir.as_os_str()).unwrap();
+    }
+    assert_eq!(watcher.index, 1);
+    assert_eq!(dir_entries(&dir), ["a.ppm", "a_out.ppm"]);
+
+    // a changed input is processed again
+    Image::fill(128_u8, ColorSpace::RGB, 8, 8)
+        .save(dir.join("a.ppm"))
+        .unwrap();
+
+    for _ in 0..4 {
+        watcher.scan(&args, &cmd_opts, dir.as_os_str()).unwrap();
+    }
+    assert_eq!(watcher.index, 2);
+    assert_eq!(dir_entries(&dir), ["a.ppm", "a_out.ppm"]);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_outputs_are_recorded() {
+    use zune_core::colorspace::ColorSpace;
+    use zune_image::image::Image;
+
+    let dir = test_dir("recorded");
+    let input = dir.join("a.ppm");
+    let output = dir.join("b.ppm");
+
+    Image::fill(128_u8, ColorSpace::RGB, 4, 4)
+        .save(&input)
+        .unwrap();
+
+    // without a template
+    let args = test_args(&[
+        OsStr::new("-i"),
+        input.as_os_str(),
+        OsStr::new("-o"),
+        output.as_os_str()
+    ]);
+    let cmd_opts = crate::cmd_parsers::global_options::parse_options(&args);
+    let mut written_files = HashSet::new();
+
+    process_file(
+        &args,
+        &cmd_opts,
+        input.as_os_str(),
+        0,
+        None,
+        false,
+        &mut written_files,
+        &mut FileReport::new(input.as_os_str())
+    )
+    .unwrap();
+
+    assert_eq!(written_files, HashSet::from([output.into_os_string()]));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
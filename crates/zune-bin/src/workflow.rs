@@ -6,134 +6,359 @@
  * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
  */
 
+use std::collections::HashSet;
+use std::ffi::{OsStr, OsString};
 use std::fs::File;
 use std::io::Read;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::string::String;
+use std::time::Instant;
 
 use clap::parser::ValueSource::CommandLine;
 use clap::ArgMatches;
-use log::{debug, error, info, warn};
+use log::{debug, info, warn};
+use zune_core::options::EncoderOptions;
+use zune_core::threads::set_num_threads;
 use zune_image::codecs::ImageFormat;
+use zune_image::core_filters::metadata::StripMetadata;
 use zune_image::errors::ImageErrors;
-use zune_image::pipelines::Pipeline;
+use zune_image::mipmaps::MipmapFilter;
+use zune_image::pipelines::{OrientationPolicy, Pipeline};
+use zune_image::statistics::Statistics;
 use zune_image::traits::IntoImage;
 
+use crate::cmd_parsers::chain::{parse_chain, split_inputs};
 use crate::cmd_parsers::global_options::CmdOptions;
-use crate::cmd_parsers::{get_decoder_options, get_encoder_options};
-use crate::file_io::ZuneFile;
+use crate::cmd_parsers::{get_decoder_options, get_encoder_options, get_stripped_metadata};
+use crate::dry_run::print_plan;
+use crate::file_io::{read_zip_entry, ZuneFile};
+use crate::out_template::{expand_template, TemplateVars};
 use crate::probe_files::probe_input_files;
+use crate::report::{exit_code, print_report, FileReport, EXIT_OK};
 use crate::show_gui::open_in_default_app;
+use crate::watch::watch_directory;
 use crate::MmapOptions;
 
+/// Process the inputs given on the command line
+///
+/// Returns the exit code of the run, see [`report`](crate::report)
 #[allow(unused_variables)]
-#[allow(clippy::unused_io_amount)] // yes it's what I want
 pub(crate) fn create_and_exec_workflow_from_cmd(
     args: &ArgMatches, cmd_opts: &CmdOptions
-) -> Result<(), ImageErrors> {
+) -> Result<i32, ImageErrors> {
     if let Some(view) = args.value_source("probe") {
         if view == CommandLine {
             probe_input_files(args);
-            return Ok(());
+            return Ok(EXIT_OK);
         }
     }
 
+    if let Some(threads) = args.get_one::<usize>("threads") {
+        debug!("Limiting threads to {threads}");
+        set_num_threads(*threads);
+    }
+
     info!("Creating workflows from input");
 
+    if args.value_source("watch") == Some(CommandLine) {
+        return watch_directory(args, cmd_opts).map(|()| EXIT_OK);
+    }
+
+    // an archive entry replaces the input files
+    let zip_entry = match args.get_one::<OsString>("from-zip") {
+        Some(spec) => Some(read_zip_entry(spec)?),
+        None => None
+    };
+    let inputs: Vec<&OsStr> = match args.get_raw("from-zip") {
+        Some(spec) => spec.collect(),
+        None => split_inputs(args).0
+    };
+    if inputs.is_empty() {
+        return Err(ImageErrors::GenericStr("No input files given"));
+    }
+
+    if inputs.len() > 1 && args.value_source("out") == Some(CommandLine) {
+        warn!("Every input is written to the same output files, use --out-template to name outputs after their input");
+    }
+    let report = args.get_one::<String>("report").is_some();
+
+    if report {
+        // invalid operations fail every file, report them once instead
+        add_operations(args, &mut Pipeline::<ZuneFile>::new())?;
+    }
+    // outputs written so far, to catch templates that give inputs the same name
+    let mut written_files = HashSet::new();
+    let mut reports = Vec::with_capacity(inputs.len());
+
+    for (index, in_file) in inputs.iter().copied().enumerate() {
+        let mut file_report = FileReport::new(in_file);
+        let start = Instant::now();
+
+        let result = process_file(
+            args,
+            cmd_opts,
+            in_file,
+            index,
+            zip_entry.as_deref(),
+            inputs.len() > 1,
+            &mut written_files,
+            &mut file_report
+        );
+        file_report.elapsed = start.elapsed();
+
+        if let Err(err) = result {
+            // when reporting, a failed file doesn't stop the others
+            if !report {
+                return Err(err);
+            }
+            file_report.set_error(&err);
+        }
+        reports.push(file_report);
+    }
+    if report {
+        print_report(&reports);
+    }
+
+    Ok(exit_code(&reports))
+}
+
+/// Run the pipeline configured on the command line on one input
+///
+/// `zip_entry` holds the contents of the input when it was read from an archive,
+/// `many_inputs` keeps stages of different inputs apart, `written_files`
+/// collects the outputs written by templates and `report` what happened to the input.
+#[allow(clippy::unused_io_amount, clippy::too_many_arguments)] // yes it's what I want
+pub(crate) fn process_file(
+    args: &ArgMatches, cmd_opts: &CmdOptions, in_file: &OsStr, index: usize,
+    zip_entry: Option<&[u8]>, many_inputs: bool, written_files: &mut HashSet<OsString>,
+    report: &mut FileReport
+) -> Result<(), ImageErrors> {
     let decoder_options = get_decoder_options(args);
+    let template = args.get_one::<String>("out-template");
     let mut buf = [0; 30];
 
-    for in_file in args.get_raw("in").unwrap() {
-        let mut workflow: Pipeline<ZuneFile> = Pipeline::new();
+    let mut workflow: Pipeline<ZuneFile> = Pipeline::new();
 
+    if let Some(contents) = zip_entry {
+        let length = contents.len().min(buf.len());
+        buf[..length].copy_from_slice(&contents[..length]);
+    } else {
         File::open(in_file)?.read(&mut buf)?;
+    }
 
-        add_operations(args, &mut workflow)?;
+    let mut descriptions = add_operations(args, &mut workflow)?;
 
-        let mmap_opt = cmd_opts.mmap;
-        let use_mmap = mmap_opt == MmapOptions::Auto || mmap_opt == MmapOptions::Always;
+    let stripped_metadata = get_stripped_metadata(args);
 
-        if let Some((format, _)) = ImageFormat::guess_format(&buf) {
-            if format.has_decoder() {
-                workflow.add_decoder(ZuneFile::new(
-                    in_file.to_os_string(),
-                    use_mmap,
-                    decoder_options
-                ))
+    if !stripped_metadata.is_empty() {
+        debug!("Stripping {:?} metadata", stripped_metadata);
+        workflow.add_operation(Box::new(StripMetadata::new(&stripped_metadata)));
+        descriptions.push(format!("strip {stripped_metadata:?}"));
+    }
+
+    let print_stats = args.value_source("stats") == Some(CommandLine);
+
+    if print_stats {
+        // last so that it sees the pixels that are written out
+        workflow.add_operation(Box::new(Statistics::new()));
+        descriptions.push("stats".to_string());
+    }
+    report.operations.clone_from(&descriptions);
+
+    if let Some(dir) = args.get_one::<OsString>("dump-stages") {
+        let mut dir = PathBuf::from(dir);
+
+        // keep stages of different inputs apart
+        if many_inputs {
+            dir.push(Path::new(in_file).file_name().unwrap_or(in_file));
+        }
+        debug!("Writing pipeline stages to {dir:?}");
+        workflow.set_intermediate_dump(dir, ImageFormat::PNG);
+    }
+
+    if args.value_source("linear-light") == Some(CommandLine) {
+        debug!("Processing in linear light");
+        workflow.process_in_linear_light(true);
+    }
+    if args.get_one::<String>("orientation").unwrap() == "apply" {
+        debug!("Applying exif orientation after decoding");
+        workflow.set_orientation_policy(OrientationPolicy::Apply);
+    }
+    // encoders reject colorspaces they can't write instead of converting them
+    workflow.set_strict_encoding(*args.get_one::<bool>("strict").unwrap());
+
+    if args.value_source("record-history") == Some(CommandLine) {
+        workflow.set_record_history(true);
+
+        for (position, description) in descriptions.iter().enumerate() {
+            workflow.set_operation_description(position, description.clone());
+        }
+    }
+
+    let mmap_opt = cmd_opts.mmap;
+    let use_mmap = mmap_opt == MmapOptions::Auto || mmap_opt == MmapOptions::Always;
+
+    let Some((format, _)) = ImageFormat::guess_format(&buf) else {
+        return Err(ImageErrors::ImageDecoderNotIncluded(ImageFormat::Unknown));
+    };
+    if !format.has_decoder() {
+        return Err(ImageErrors::ImageDecoderNotImplemented(format));
+    }
+    if let Some(contents) = zip_entry {
+        workflow.add_decoder(ZuneFile::from_memory(
+            in_file.to_os_string(),
+            contents.to_vec(),
+            decoder_options
+        ))
+    } else {
+        workflow.add_decoder(ZuneFile::new(
+            in_file.to_os_string(),
+            use_mmap,
+            decoder_options
+        ))
+    }
+
+    let options = get_encoder_options(args);
+    let mut encoded_files = vec![];
+
+    let mipmap_filter = match args.get_one::<String>("mipmaps").map(String::as_str) {
+        Some("box") => Some(MipmapFilter::Box),
+        Some(_) => Some(MipmapFilter::LinearBox),
+        None => None
+    };
+
+    let mut template_vars = TemplateVars {
+        in_file,
+        format,
+        index,
+        dimensions: None
+    };
+    // output dimensions are unknown until the pipeline ran, templated names
+    // are expanded again after that, here they only select the encoder, which
+    // expand_template keeps independent of the dimensions
+    let out_files: Vec<OsString> = match template {
+        Some(template) => vec![expand_template(template, &template_vars)?.into()],
+        None if args.value_source("out") == Some(CommandLine) => args
+            .get_raw("out")
+            .unwrap()
+            .map(OsStr::to_os_string)
+            .collect(),
+        None => vec![]
+    };
+
+    for out_file in &out_files {
+        if let Some(ext) = Path::new(out_file).extension() {
+            if let Some((encode_type, mut encoder)) =
+                ImageFormat::get_encoder_for_extension(ext.to_str().unwrap())
+            {
+                debug!("Treating {:?} as a {:?} format", out_file, encode_type);
+                encoder.set_options(options);
+                // mipmap levels are encoded after the pipeline runs
+                if mipmap_filter.is_none() {
+                    workflow.add_encoder(encoder);
+                }
+                encoded_files.push(out_file.as_os_str());
             } else {
-                return Err(ImageErrors::ImageDecoderNotImplemented(format));
+                report.warn(format!("Unknown or unsupported format {out_file:?}"));
             }
         } else {
-            return Err(ImageErrors::ImageDecoderNotIncluded(ImageFormat::Unknown));
-        }
-
-        let options = get_encoder_options(args);
-
-        if let Some(source) = args.value_source("out") {
-            if source == CommandLine {
-                for out_file in args.get_raw("out").unwrap() {
-                    if let Some(ext) = Path::new(out_file).extension() {
-                        if let Some((encode_type, mut encoder)) =
-                            ImageFormat::get_encoder_for_extension(ext.to_str().unwrap())
-                        {
-                            debug!("Treating {:?} as a {:?} format", out_file, encode_type);
-                            encoder.set_options(options);
-                            workflow.add_encoder(encoder);
-                        } else {
-                            error!("Unknown or unsupported format {:?}", out_file)
-                        }
-                    } else {
-                        error!("Could not determine extension from {:?}", out_file)
-                    }
-                }
-            }
+            report.warn(format!("Could not determine extension from {out_file:?}"));
+        }
+    }
+
+    if args.value_source("dry-run") == Some(CommandLine) {
+        print_plan(
+            in_file,
+            zip_entry,
+            &workflow,
+            &descriptions,
+            &encoded_files,
+            decoder_options
+        )?;
+        return Ok(());
+    }
+
+    workflow.advance_to_end()?;
+
+    report.dimensions = workflow.images().first().map(|x| x.dimensions());
+
+    if workflow
+        .images()
+        .iter()
+        .any(|x| x.metadata().is_truncated())
+    {
+        report.warn(format!(
+            "{in_file:?} is truncated, only part of the image was decoded"
+        ));
+    }
+    if args.value_source("report-memory") == Some(CommandLine) {
+        report_memory(&workflow);
+    }
+    if args.value_source("timings") == Some(CommandLine) {
+        report_timings(in_file, &workflow);
+    }
+    if print_stats {
+        report_statistics(in_file, &workflow);
+    }
+    let out_files = match template {
+        Some(template) => {
+            template_vars.dimensions = workflow.images().first().map(|x| x.dimensions());
+            vec![OsString::from(expand_template(template, &template_vars)?)]
         }
+        None => out_files.clone()
+    };
+    let results = workflow.get_results();
+    let mut curr_result_position = 0;
+
+    // write to output
 
-        workflow.advance_to_end()?;
-        let results = workflow.get_results();
-        let mut curr_result_position = 0;
-
-        // write to output
-
-        //  We support multiple format writes per invocation
-        // i.e it's perfectly valid to do -o a.ppm , -o a.png
-        if let Some(source) = args.value_source("out") {
-            if source == CommandLine {
-                for out_file in args.get_raw("out").unwrap() {
-                    //write to file
-                    if let Some(ext) = Path::new(out_file).extension() {
-                        if let Some((encode_type, _)) =
-                            ImageFormat::get_encoder_for_extension(ext.to_str().unwrap())
-                        {
-                            if encode_type.has_encoder()
-                                && results[curr_result_position].format() == encode_type
-                            {
-                                info!(
-                                    "Writing data as {:?} format to file {:?}",
-                                    results[curr_result_position].format(),
-                                    out_file
-                                );
-
-                                std::fs::write(out_file, results[curr_result_position].data())
-                                    .unwrap();
-
-                                curr_result_position += 1;
-                            } else {
-                                warn!("Ignoring {:?} file", out_file);
-                            }
-                        } else {
-                            warn!("Ignoring {:?} file", out_file);
+    //  We support multiple format writes per invocation
+    // i.e it's perfectly valid to do -o a.ppm , -o a.png
+    if let Some(filter) = mipmap_filter {
+        for out_file in &encoded_files {
+            write_mipmaps(&workflow, out_file, filter, options, written_files, report)?;
+        }
+    } else {
+        for out_file in &out_files {
+            //write to file
+            if let Some(ext) = Path::new(out_file).extension() {
+                if let Some((encode_type, _)) =
+                    ImageFormat::get_encoder_for_extension(ext.to_str().unwrap())
+                {
+                    if encode_type.has_encoder()
+                        && results[curr_result_position].format() == encode_type
+                    {
+                        info!(
+                            "Writing data as {:?} format to file {:?}",
+                            results[curr_result_position].format(),
+                            out_file
+                        );
+                        // every output is recorded, --watch skips them when they
+                        // are written to the watched directory
+                        if !written_files.insert(out_file.clone()) && template.is_some() {
+                            report.warn(format!(
+                                "{out_file:?} overwrites the output of a previous input"
+                            ));
                         }
+
+                        std::fs::write(out_file, results[curr_result_position].data())?;
+                        report.outputs.push(out_file.clone());
+
+                        curr_result_position += 1;
+                    } else {
+                        report.warn(format!("Ignoring {out_file:?} file"));
                     }
+                } else {
+                    report.warn(format!("Ignoring {out_file:?} file"));
                 }
             }
         }
+    }
 
-        if let Some(view) = args.value_source("view") {
-            if view == CommandLine {
-                for image in workflow.images() {
-                    open_in_default_app(image);
-                }
+    if let Some(view) = args.value_source("view") {
+        if view == CommandLine {
+            for image in workflow.images() {
+                open_in_default_app(image)?;
             }
         }
     }
@@ -141,10 +366,64 @@ pub(crate) fn create_and_exec_workflow_from_cmd(
     Ok(())
 }
 
+/// Write the mipmap levels of every image in `workflow`, `out.png`
+/// is written as `out-0.png` (full size), `out-1.png` and so on
+fn write_mipmaps<T: IntoImage>(
+    workflow: &Pipeline<T>, out_file: &OsStr, filter: MipmapFilter, options: EncoderOptions,
+    written_files: &mut HashSet<OsString>, report: &mut FileReport
+) -> Result<(), ImageErrors> {
+    let path = Path::new(out_file);
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = path.extension().unwrap_or_default().to_string_lossy();
+
+    let Some((_, mut encoder)) = ImageFormat::get_encoder_for_extension(&extension) else {
+        report.warn(format!("Ignoring {out_file:?} file"));
+        return Ok(());
+    };
+    encoder.set_options(options);
+
+    for (position, image) in workflow.images().iter().enumerate() {
+        // keep levels of multiple images apart
+        let prefix = if workflow.images().len() > 1 {
+            format!("{stem}-{position}")
+        } else {
+            stem.to_string()
+        };
+        for (level, mipmap) in image.generate_mipmaps(filter)?.iter().enumerate() {
+            let level_file = path.with_file_name(format!("{prefix}-{level}.{extension}"));
+            let (width, height) = mipmap.dimensions();
+
+            info!("Writing {width}x{height} mipmap level {level} to file {level_file:?}");
+            mipmap.save_with(&level_file, encoder.as_mut())?;
+            written_files.insert(level_file.clone().into_os_string());
+            report.outputs.push(level_file.into_os_string());
+        }
+    }
+    Ok(())
+}
+
+/// Add operations passed via the command line to `workflow`, in the order
+/// they were given
+///
+/// Returns the command line that created each added operation
 pub fn add_operations<T: IntoImage>(
     args: &ArgMatches, workflow: &mut Pipeline<T>
-) -> Result<(), String> {
-    for (_pos, id) in args.ids().enumerate() {
+) -> Result<Vec<String>, String> {
+    let mut descriptions = vec![];
+
+    let (_, chain) = split_inputs(args);
+    let tokens: Vec<String> = chain.iter().map(|(_, token)| token.clone()).collect();
+    // every chained operation is two tokens, `op` and its spec
+    let mut chained = chain
+        .iter()
+        .step_by(2)
+        .map(|(index, _)| *index)
+        .zip(parse_chain(&tokens)?)
+        .peekable();
+
+    let mut flags = vec![];
+
+    for id in args.ids() {
         if args.try_get_many::<clap::Id>(id.as_str()).is_ok() {
             // ignore groups
             continue;
@@ -158,10 +437,113 @@ pub fn add_operations<T: IntoImage>(
             // ignore things not passed via command line
             continue;
         }
+        flags.push((args.index_of(id.as_str()).unwrap_or(0), id.as_str()));
+    }
+    flags.sort_by_key(|(index, _)| *index);
+
+    let flags = flags.into_iter().map(|(index, id)| (index, Some(id)));
 
-        crate::cmd_parsers::operations::parse_options(workflow, id.as_str(), args)?;
-        crate::cmd_parsers::filters::parse_options(workflow, id.as_str(), args)?;
+    for (index, id) in flags.chain([(usize::MAX, None)]) {
+        // chained operations written before this flag
+        while let Some((_, (description, operation))) =
+            chained.next_if(|(position, _)| *position < index)
+        {
+            debug!("Added {description}");
+            workflow.add_operation(operation);
+            descriptions.push(description);
+        }
+        let Some(id) = id else {
+            break;
+        };
+        let previous_len = workflow.operations().len();
+
+        crate::cmd_parsers::operations::parse_options(workflow, id, args)?;
+        crate::cmd_parsers::filters::parse_options(workflow, id, args)?;
+
+        for _ in previous_len..workflow.operations().len() {
+            descriptions.push(describe_argument(args, id));
+        }
     }
 
-    Ok(())
+    Ok(descriptions)
+}
+
+/// Format an argument and its values as they were passed on the command line
+fn describe_argument(args: &ArgMatches, id: &str) -> String {
+    let mut description = id.to_string();
+
+    // flags carry no parameters worth displaying
+    if args.try_get_one::<bool>(id).is_err() {
+        for value in args.get_raw(id).into_iter().flatten() {
+            description.push(' ');
+            description.push_str(&value.to_string_lossy());
+        }
+    }
+    description
+}
+
+fn report_timings<T: IntoImage>(in_file: &OsStr, workflow: &Pipeline<T>) {
+    for image in workflow.images() {
+        let Some(timings) = image.metadata().decode_timings() else {
+            continue;
+        };
+        println!("Decode timings for {in_file:?}");
+        println!("{:<20} {:>12}", "Stage", "Time (ms)");
+
+        for (stage, duration) in timings.stages() {
+            println!("{:<20} {:>12.3}", stage, duration.as_secs_f64() * 1000.0);
+        }
+        println!(
+            "{:<20} {:>12.3}",
+            "total",
+            timings.total().as_secs_f64() * 1000.0
+        );
+    }
+}
+
+fn report_statistics<T: IntoImage>(in_file: &OsStr, workflow: &Pipeline<T>) {
+    for image in workflow.images() {
+        let Some(statistics) = image.metadata().statistics() else {
+            continue;
+        };
+        println!("Statistics for {in_file:?} ({:?})", image.colorspace());
+        println!(
+            "{:<10} {:>12} {:>12} {:>12} {:>12}",
+            "Channel", "Min", "Max", "Mean", "Std dev"
+        );
+        for (channel, stats) in statistics.iter().enumerate() {
+            println!(
+                "{:<10} {:>12.4} {:>12.4} {:>12.4} {:>12.4}",
+                channel, stats.min, stats.max, stats.mean, stats.std_dev
+            );
+        }
+    }
+}
+
+#[cfg(feature = "mem-stats")]
+fn report_memory<T: IntoImage>(workflow: &Pipeline<T>) {
+    use zune_image::mem_stats::max_usage;
+
+    const MB: f64 = 1024.0 * 1024.0;
+
+    println!(
+        "{:<30} {:>12} {:>12} {:>12}",
+        "Stage", "Peak MB", "Alloc MB", "Kept MB"
+    );
+
+    for usage in workflow.memory_usage() {
+        println!(
+            "{:<30} {:>12.2} {:>12.2} {:>12.2}",
+            usage.name,
+            usage.peak as f64 / MB,
+            usage.allocated as f64 / MB,
+            usage.retained as f64 / MB
+        );
+    }
+    println!("Peak memory in use: {:.2} MB", max_usage() as f64 / MB);
+}
+
+#[cfg(not(feature = "mem-stats"))]
+fn report_memory<T: IntoImage>(_: &Pipeline<T>) {
+    warn!("zune was built without the `mem-stats` feature, cannot report memory usage");
 }
@@ -36,6 +36,21 @@ pub enum BitDepth {
     ///
     /// Data is stored and processed in native endian.
     Sixteen,
+    /// 10 bit depth
+    ///
+    /// Images with such bit depth use [`u16`] to store values but only use
+    /// the range 0-1023, e.g 10 bit av1 and hevc content.
+    ///
+    /// Data is stored and processed in native endian.
+    Ten,
+    /// 12 bit depth
+    ///
+    /// Images with such bit depth use [`u16`] to store values but only use
+    /// the range 0-4095, e.g 12 bit av1 content and scientific pngs
+    /// with an `sBIT` chunk.
+    ///
+    /// Data is stored and processed in native endian.
+    Twelve,
     /// Floating point 32 bit data, range is 0.0 to 1.0
     ///
     /// Uses f32 to store data
@@ -94,6 +109,8 @@ impl BitDepth {
         {
             Self::Eight => (1 << 08) - 1,
             Self::Sixteen => u16::MAX,
+            Self::Ten => (1 << 10) - 1,
+            Self::Twelve => (1 << 12) - 1,
             Self::Float32 => 1,
             Self::Unknown => 0,
         }
@@ -116,13 +133,15 @@ impl BitDepth {
     /// assert_eq!(BitDepth::Eight.bit_type(),BitType::U8);
     ///
     /// assert_eq!(BitDepth::Sixteen.bit_type(),BitType::U16);
+    ///
+    /// assert_eq!(BitDepth::Ten.bit_type(),BitType::U16);
     /// ```
     ///
     /// See also [size_of](BitDepth::size_of)
     pub const fn bit_type(self) -> BitType {
         match self {
             Self::Eight => BitType::U8,
-            Self::Sixteen => BitType::U16,
+            Self::Sixteen | Self::Ten | Self::Twelve => BitType::U16,
             Self::Float32 => BitType::F32,
             Self::Unknown => panic!("Unknown bit type")
         }
@@ -145,7 +164,7 @@ impl BitDepth {
     pub const fn size_of(self) -> usize {
         match self {
             Self::Eight => core::mem::size_of::<u8>(),
-            Self::Sixteen => core::mem::size_of::<u16>(),
+            Self::Sixteen | Self::Ten | Self::Twelve => core::mem::size_of::<u16>(),
             Self::Float32 => core::mem::size_of::<f32>(),
             Self::Unknown => panic!("Unknown bit type")
         }
@@ -153,6 +172,43 @@ impl BitDepth {
     pub const fn bit_size(&self) -> usize {
         self.size_of() * 8
     }
+    /// Return the number of bits carrying information in each sample
+    ///
+    /// This differs from [bit_size](BitDepth::bit_size) for depths that
+    /// do not use the whole range of their storage type
+    ///
+    /// # Example
+    /// ```
+    /// use zune_core::bit_depth::BitDepth;
+    /// assert_eq!(BitDepth::Twelve.bit_size(),16);
+    /// assert_eq!(BitDepth::Twelve.significant_bits(),12);
+    /// ```
+    pub const fn significant_bits(&self) -> usize {
+        match self {
+            Self::Ten => 10,
+            Self::Twelve => 12,
+            _ => self.bit_size()
+        }
+    }
+    /// Return the depth whose samples carry `bits` significant bits
+    /// and are stored in a [`u16`]
+    ///
+    /// Returns `None` for values not representable by this enum
+    ///
+    /// # Example
+    /// ```
+    /// use zune_core::bit_depth::BitDepth;
+    /// assert_eq!(BitDepth::from_u16_bits(10),Some(BitDepth::Ten));
+    /// assert_eq!(BitDepth::from_u16_bits(9),None);
+    /// ```
+    pub const fn from_u16_bits(bits: u8) -> Option<BitDepth> {
+        match bits {
+            10 => Some(Self::Ten),
+            12 => Some(Self::Twelve),
+            16 => Some(Self::Sixteen),
+            _ => None
+        }
+    }
 }
 
 /// Byte endianness of returned samples
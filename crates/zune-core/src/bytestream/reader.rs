@@ -9,6 +9,7 @@
 use core::cmp::min;
 
 use crate::bytestream::traits::ZReaderTrait;
+use crate::diagnostics::ByteContext;
 
 const ERROR_MSG: &str = "No more bytes";
 
@@ -283,6 +284,15 @@ impl<T: ZReaderTrait> ZByteReader<T> {
             }
         }
     }
+    /// Return up to `window` bytes before and after `offset`, counted from
+    /// the start of the stream
+    ///
+    /// Used to show where decoding failed, see [`ByteContext`]
+    pub fn byte_context(&self, offset: usize, window: usize) -> ByteContext {
+        let data = self.stream.get_slice(0..self.len()).unwrap_or_default();
+
+        ByteContext::new(data, offset, window)
+    }
     /// Return the remaining unread bytes in this byte reader
     pub fn remaining_bytes(&self) -> &[u8] {
         debug_assert!(self.position <= self.len());
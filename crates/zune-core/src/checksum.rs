@@ -0,0 +1,38 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! Checksum routines shared by decoders and encoders
+//!
+//! This module contains implementations of the two checksums
+//! commonly found in image formats
+//!
+//! - [CRC-32](crc32): Used by png chunks and gzip streams
+//! - [Adler-32](adler32): Used by zlib streams
+//!
+//! Both checksums expose an incremental API, the state is the checksum
+//! of all data seen so far and can be updated with more data as it
+//! becomes available
+//!
+//! # Example
+//! ```
+//! use zune_core::checksum::{adler32, crc32};
+//!
+//! let mut crc = crc32::INITIAL;
+//! crc32::update(&mut crc, b"1234");
+//! crc32::update(&mut crc, b"56789");
+//! // same as computing it in one go
+//! assert_eq!(crc, crc32::crc32(b"123456789"));
+//! assert_eq!(crc, 0xCBF4_3926);
+//!
+//! let mut adler = adler32::INITIAL;
+//! adler32::update(&mut adler, b"Wikipedia");
+//! assert_eq!(adler, 0x11E6_0398);
+//! ```
+pub mod adler32;
+pub mod crc32;
+mod crc_tables;
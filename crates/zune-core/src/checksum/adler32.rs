@@ -0,0 +1,117 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! Adler-32 checksum
+//!
+//! This is the checksum used by zlib streams, as defined by [RFC 1950]
+//!
+//! With the `simd-adler32` feature, the checksum is computed by the vectorized
+//! [simd-adler32] crate, otherwise a scalar implementation is used which defers
+//! the modulo reductions to once every [`NMAX`] bytes, the largest amount of data
+//! that can be summed before the accumulators may overflow.
+//!
+//! [simd-adler32]: https://crates.io/crates/simd-adler32
+//! [RFC 1950]: https://www.rfc-editor.org/rfc/rfc1950
+const MOD_ADLER: u32 = 65521;
+
+/// Largest number of bytes that can be processed before `b` may overflow
+/// a `u32`, see zlib's adler32.c
+const NMAX: usize = 5552;
+
+/// Initial state for an incremental Adler-32 computation
+pub const INITIAL: u32 = 1;
+
+/// Update an Adler-32 checksum with more data
+///
+/// `state` is the checksum of all data seen so far,
+/// or [`INITIAL`] if nothing has been seen
+///
+/// # Example
+/// ```
+/// use zune_core::checksum::adler32;
+///
+/// let mut adler = adler32::INITIAL;
+/// adler32::update(&mut adler, b"hello ");
+/// adler32::update(&mut adler, b"world");
+///
+/// assert_eq!(adler, adler32::adler32(b"hello world"));
+/// ```
+pub fn update(state: &mut u32, data: &[u8]) {
+    #[cfg(feature = "simd-adler32")]
+    {
+        let mut hasher = simd_adler32::Adler32::from_checksum(*state);
+        hasher.write(data);
+        *state = hasher.finish();
+    }
+    #[cfg(not(feature = "simd-adler32"))]
+    {
+        update_scalar(state, data);
+    }
+}
+
+/// Portable Adler-32, used when the `simd-adler32` feature is disabled
+#[cfg_attr(feature = "simd-adler32", allow(dead_code))]
+fn update_scalar(state: &mut u32, data: &[u8]) {
+    let mut a = *state & 0xFFFF;
+    let mut b = *state >> 16;
+
+    for block in data.chunks(NMAX) {
+        for byte in block {
+            a += u32::from(*byte);
+            b += a;
+        }
+        a %= MOD_ADLER;
+        b %= MOD_ADLER;
+    }
+    *state = (b << 16) | a;
+}
+
+/// Calculate the Adler-32 checksum of `data`
+pub fn adler32(data: &[u8]) -> u32 {
+    let mut state = INITIAL;
+    update(&mut state, data);
+    state
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use crate::checksum::adler32::{adler32, update, update_scalar, INITIAL};
+
+    #[test]
+    fn test_adler_known_values() {
+        assert_eq!(adler32(b""), 1);
+        assert_eq!(adler32(b"Wikipedia"), 0x11E6_0398);
+        // large enough to need several reductions of the worst case input
+        assert_eq!(adler32(&vec![255_u8; 100_000]), 0x149A_302C);
+    }
+
+    #[test]
+    fn test_adler_incremental() {
+        let data = vec![255_u8; 20_000];
+
+        let mut state = INITIAL;
+        for chunk in data.chunks(4999) {
+            update(&mut state, chunk);
+        }
+        assert_eq!(state, adler32(&data));
+    }
+
+    #[test]
+    fn test_adler_scalar_matches() {
+        let data: alloc::vec::Vec<u8> = (0..70_000_u32).map(|x| (x * 7 + x / 13) as u8).collect();
+
+        for length in [0, 1, 15, 16, 17, 5552, 5553, data.len()] {
+            let mut scalar = INITIAL;
+            update_scalar(&mut scalar, &data[..length]);
+
+            assert_eq!(scalar, adler32(&data[..length]), "length {length}");
+        }
+    }
+}
@@ -0,0 +1,112 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! CRC-32 checksum
+//!
+//! This is the CRC used by png, gzip and zip, with the reversed
+//! polynomial `0xEDB88320`.
+//!
+//! The implementation uses the slice by 8 technique, processing
+//! eight bytes per iteration
+use crate::checksum::crc_tables::{CRC32_SLICE1_TABLE, CRC32_SLICE8_TABLE};
+
+/// Initial state for an incremental CRC-32 computation
+pub const INITIAL: u32 = 0;
+
+/// Update a CRC-32 checksum with more data
+///
+/// `state` is the checksum of all data seen so far,
+/// or [`INITIAL`] if nothing has been seen
+///
+/// # Example
+/// ```
+/// use zune_core::checksum::crc32;
+///
+/// let mut crc = crc32::INITIAL;
+/// crc32::update(&mut crc, b"hello ");
+/// crc32::update(&mut crc, b"world");
+///
+/// assert_eq!(crc, crc32::crc32(b"hello world"));
+/// ```
+pub fn update(state: &mut u32, data: &[u8]) {
+    *state = !crc32_slice8(data, !*state);
+}
+
+/// Calculate the CRC-32 checksum of `data`
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut state = INITIAL;
+    update(&mut state, data);
+    state
+}
+
+/// Calculate crc for a data and an initial crc value
+///
+/// This works on the raw crc register, i.e the caller is responsible for the
+/// pre and post conditioning (inverting) of the crc value
+#[allow(clippy::identity_op, clippy::zero_prefixed_literal)]
+fn crc32_slice8(data: &[u8], mut crc: u32) -> u32 {
+    // main loop
+    for chunk in data.chunks_exact(8) {
+        let chunk_loaded = u64::from_le_bytes(chunk.try_into().unwrap());
+
+        let v1 = (chunk_loaded & u64::from(u32::MAX)) as u32;
+        let v2 = (chunk_loaded >> 32) as u32;
+
+        crc = CRC32_SLICE8_TABLE[0x700 + (((crc ^ v1) >> 00) & 0xFF) as usize]
+            ^ CRC32_SLICE8_TABLE[0x600 + (((crc ^ v1) >> 08) & 0xFF) as usize]
+            ^ CRC32_SLICE8_TABLE[0x500 + (((crc ^ v1) >> 16) & 0xFF) as usize]
+            ^ CRC32_SLICE8_TABLE[0x400 + (((crc ^ v1) >> 24) & 0xFF) as usize]
+            ^ CRC32_SLICE8_TABLE[0x300 + (((v2 >> 00) & 0xFF) as usize)]
+            ^ CRC32_SLICE8_TABLE[0x200 + (((v2 >> 08) & 0xFF) as usize)]
+            ^ CRC32_SLICE8_TABLE[0x100 + (((v2 >> 16) & 0xFF) as usize)]
+            ^ CRC32_SLICE8_TABLE[0x000 + (((v2 >> 24) & 0xFF) as usize)];
+    }
+    // handle remainder
+    crc32_slice1(data.chunks_exact(8).remainder(), crc)
+}
+
+/// A smaller CRC function used to handle data that doesn't fit
+/// into the slice by 8 loop
+fn crc32_slice1(data: &[u8], mut crc: u32) -> u32 {
+    for datum in data {
+        crc = (crc >> 8) ^ CRC32_SLICE1_TABLE[((crc & 0xFF) ^ u32::from(*datum)) as usize];
+    }
+    crc
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use crate::checksum::crc32::{crc32, crc32_slice1, crc32_slice8, update, INITIAL};
+
+    #[test]
+    fn test_crc_same() {
+        let data: Vec<u8> = (0..1000_u32).map(|x| (x * 7 + x / 3) as u8).collect();
+
+        let crc_simple = crc32_slice1(&data, 0);
+        let crc_table8 = crc32_slice8(&data, 0);
+
+        assert_eq!(
+            crc_simple, crc_table8,
+            "CRC {crc_simple} {crc_table8} do not match"
+        );
+    }
+
+    #[test]
+    fn test_crc_incremental() {
+        let data: Vec<u8> = (0..1000_u32).map(|x| (x * 13) as u8).collect();
+
+        let mut state = INITIAL;
+        for chunk in data.chunks(13) {
+            update(&mut state, chunk);
+        }
+        assert_eq!(state, crc32(&data));
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+}
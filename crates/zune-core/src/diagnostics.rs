@@ -0,0 +1,124 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! Bytes around decoding failures
+//!
+//! The png and gif decoders, when built with their `diagnostics` feature, can attach
+//! the bytes surrounding the position decoding failed at to their errors, so that
+//! corrupt files can be inspected without opening a hex editor.
+//!
+//! [`ByteContext`] holds those bytes and renders them like `hexdump -C`, marking the
+//! failing byte
+//!
+//! ```text
+//! 00000000  89 50 4e 47 0d 0a 1a 0a  00 00 00 0d 49 48 44 52  |.PNG........IHDR|
+//!                                             ^^
+//! ```
+//!
+//! The number of bytes kept is set with [`DecoderOptions::set_error_context_size`],
+//! nothing is attached unless it is set.
+//!
+//! # Example
+//! ```
+//! use zune_core::diagnostics::ByteContext;
+//!
+//! let data = b"\x89PNG\r\n\x1a\n\x00\x00\x00\x0dIHDR";
+//! let context = ByteContext::new(data, 11, 32);
+//!
+//! assert_eq!(context.offset(), 11);
+//! assert_eq!(context.bytes(), data);
+//! println!("{context}");
+//! ```
+//!
+//! [`DecoderOptions::set_error_context_size`]: crate::options::DecoderOptions::set_error_context_size
+use alloc::vec::Vec;
+use core::fmt;
+
+/// Number of bytes rendered per line
+const BYTES_PER_LINE: usize = 16;
+
+/// A window of bytes around the position a decoder failed at
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ByteContext {
+    start:  usize,
+    offset: usize,
+    bytes:  Vec<u8>
+}
+
+impl ByteContext {
+    /// Copy up to `window` bytes before and after `offset` from `data`
+    ///
+    /// The window is widened to start at a multiple of 16 bytes, so that
+    /// rendered lines line up with file offsets.
+    ///
+    /// `offset` may be past the end of `data`, e.g when the data ended early
+    pub fn new(data: &[u8], offset: usize, window: usize) -> ByteContext {
+        let start = offset.saturating_sub(window) / BYTES_PER_LINE * BYTES_PER_LINE;
+        let start = start.min(data.len());
+        let end = offset.saturating_add(window + 1).min(data.len());
+
+        ByteContext {
+            start,
+            offset,
+            bytes: data[start..end].to_vec()
+        }
+    }
+    /// Return the position of the failing byte in the input
+    pub const fn offset(&self) -> usize {
+        self.offset
+    }
+    /// Return the position of the first byte of the window in the input
+    pub const fn start(&self) -> usize {
+        self.start
+    }
+    /// Return the bytes in the window
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+impl fmt::Display for ByteContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (line, chunk) in self.bytes.chunks(BYTES_PER_LINE).enumerate() {
+            let line_start = self.start + line * BYTES_PER_LINE;
+
+            write!(f, "{line_start:08x} ")?;
+
+            for column in 0..BYTES_PER_LINE {
+                // an extra space splits the line in two halves
+                if column % 8 == 0 {
+                    write!(f, " ")?;
+                }
+                match chunk.get(column) {
+                    Some(byte) => write!(f, "{byte:02x} ")?,
+                    None => write!(f, "   ")?
+                }
+            }
+            write!(f, " |")?;
+
+            for byte in chunk {
+                let c =
+                    if byte.is_ascii_graphic() || *byte == b' ' { char::from(*byte) } else { '.' };
+                write!(f, "{c}")?;
+            }
+            writeln!(f, "|")?;
+
+            if (line_start..line_start + chunk.len()).contains(&self.offset) {
+                let column = self.offset - line_start;
+                // address, space and the half separators before the byte
+                let indent = 10 + column * 3 + usize::from(column >= 8);
+
+                writeln!(f, "{:indent$}^^", "")?;
+            }
+        }
+        if self.offset >= self.start + self.bytes.len() {
+            writeln!(f, "offset {:#x} is past the end of the data", self.offset)?;
+        }
+        Ok(())
+    }
+}
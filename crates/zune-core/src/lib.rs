@@ -12,9 +12,14 @@
 //! It currently contains
 //!
 //! - A bytestream reader and writer with endian aware reads and writes
+//! - CRC-32 and Adler-32 checksums
 //! - Colorspace and bit depth information shared by images
 //! - Image decoder and encoder options
 //! - A simple enum type to hold image decoding results.
+//! - A thread budget shared by crates that use threads (requires `std`)
+//! - Checked arithmetic for image buffer sizes
+//! - Timings of decoding stages
+//! - Bytes around decoding failures, for error reports
 //!
 //! This library is `#[no_std]` with `alloc` feature needed for defining `Vec`
 //! which we need for storing decoded  bytes.
@@ -38,7 +43,13 @@ pub use log;
 
 pub mod bit_depth;
 pub mod bytestream;
+pub mod checksum;
 pub mod colorspace;
+pub mod diagnostics;
 pub mod options;
 pub mod result;
 mod serde;
+#[cfg(feature = "std")]
+pub mod threads;
+pub mod timings;
+pub mod utils;
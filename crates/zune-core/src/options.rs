@@ -6,7 +6,7 @@
 //! All supported options are put into one _Options to allow for global configurations
 //! options e.g the same  `DecoderOption` can be reused for all other decoders
 //!
-pub use decoder::DecoderOptions;
+pub use decoder::{DecoderOptions, JpegDecodeOptions, PngCrcPolicy, PngStrictness};
 pub use encoder::EncoderOptions;
 
 mod decoder;
@@ -27,8 +27,15 @@ fn decoder_strict_mode() -> DecoderFlags {
         zune_use_sse41:            true,
         png_add_alpha_channel:     false,
         png_strip_16_bit_to_8_bit: false,
+        png_keep_raw_samples:      false,
+        png_rescale_sbit:          false,
+        png_sbit_depth:            false,
+        png_composite_background:  false,
         png_decode_animated:       true,
-        jxl_decode_animated:       true
+        png_threaded_adam7:        false,
+        jxl_decode_animated:       true,
+        allow_partial:             false,
+        collect_timings:           false
     }
 }
 
@@ -53,8 +60,15 @@ fn fast_options() -> DecoderFlags {
 
         png_add_alpha_channel:     false,
         png_strip_16_bit_to_8_bit: false,
+        png_keep_raw_samples:      false,
+        png_rescale_sbit:          false,
+        png_sbit_depth:            false,
+        png_composite_background:  false,
         png_decode_animated:       true,
-        jxl_decode_animated:       true
+        png_threaded_adam7:        false,
+        jxl_decode_animated:       true,
+        allow_partial:             false,
+        collect_timings:           false
     }
 }
 
@@ -80,9 +94,16 @@ fn cmd_options() -> DecoderFlags {
 
         png_add_alpha_channel:     false,
         png_strip_16_bit_to_8_bit: false,
+        png_keep_raw_samples:      false,
+        png_rescale_sbit:          false,
+        png_sbit_depth:            false,
+        png_composite_background:  false,
 
         png_decode_animated: true,
-        jxl_decode_animated: true
+        png_threaded_adam7:  true,
+        jxl_decode_animated: true,
+        allow_partial:       false,
+        collect_timings:     false
     }
 }
 
@@ -121,9 +142,264 @@ pub struct DecoderFlags {
     zune_use_neon:                bool,
     /// Whether the png decoder should strip 16 bit to 8 bit
     png_strip_16_bit_to_8_bit:    bool,
+    /// Whether the png decoder should keep raw values of 1, 2 and 4 bit samples
+    png_keep_raw_samples:         bool,
+    /// Whether the png decoder should rescale samples of images with an sBIT chunk
+    png_rescale_sbit:             bool,
+    /// Whether 16 bit png images with 10 or 12 significant bits decode to those depths
+    png_sbit_depth:               bool,
+    /// Whether the png decoder should blend transparent pixels onto the background color
+    png_composite_background:     bool,
     /// Decode all frames for an animated images
     png_decode_animated:          bool,
-    jxl_decode_animated:          bool
+    /// Decode Adam7 passes of interlaced png images on multiple threads
+    png_threaded_adam7:           bool,
+    jxl_decode_animated:          bool,
+    /// Whether decoders should return the rows decoded before the input ended
+    /// instead of an error when the image is truncated
+    allow_partial:                bool,
+    /// Whether decoders should record the time spent in each decoding stage
+    collect_timings:              bool
+}
+
+/// Classes of png errors that should be treated as fatal
+///
+/// Errors not in the set are tolerated by the png decoder, which
+/// logs a warning and continues decoding, this allows images with common real-world
+/// damage to still be decoded.
+///
+/// Flags can be combined via `|`
+///
+/// # Example
+/// ```
+/// use zune_core::options::{DecoderOptions, PngStrictness};
+/// // only treat corrupt critical chunks as errors
+/// let options = DecoderOptions::default().png_set_strictness(PngStrictness::CRITICAL_CRC);
+/// // or
+/// let options = DecoderOptions::default()
+///     .png_set_strictness(PngStrictness::CRITICAL_CRC | PngStrictness::ZLIB_HEADER);
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PngStrictness(u8);
+
+impl PngStrictness {
+    /// CRC mismatch on a critical chunk (`IHDR`,`PLTE`,`IDAT`,`IEND`..)
+    pub const CRITICAL_CRC: PngStrictness = PngStrictness(1 << 0);
+    /// CRC mismatch on an ancillary chunk (`tEXt`,`gAMA`,`eXIf`...)
+    pub const ANCILLARY_CRC: PngStrictness = PngStrictness(1 << 1);
+    /// Bytes present after the `IEND` chunk
+    pub const TRAILING_DATA: PngStrictness = PngStrictness(1 << 2);
+    /// zlib header whose `FCHECK` bits are wrong
+    pub const ZLIB_HEADER: PngStrictness = PngStrictness(1 << 3);
+
+    /// Treat every error class as fatal
+    pub const STRICT: PngStrictness = PngStrictness(0b1111);
+    /// Only treat a corrupt critical chunk as fatal
+    ///
+    /// This mirrors what browsers tolerate
+    pub const PERMISSIVE: PngStrictness = PngStrictness::CRITICAL_CRC;
+    /// Do not treat any error class as fatal
+    pub const NONE: PngStrictness = PngStrictness(0);
+
+    /// Return the raw bits of the set
+    pub const fn bits(self) -> u8 {
+        self.0
+    }
+    /// Create a set from raw bits, unknown bits are dropped
+    pub const fn from_bits_truncate(bits: u8) -> PngStrictness {
+        PngStrictness(bits & Self::STRICT.0)
+    }
+    /// Return true if all flags in `other` are in this set
+    pub const fn contains(self, other: PngStrictness) -> bool {
+        self.0 & other.0 == other.0
+    }
+    /// Return a set with flags from both `self` and `other`
+    #[must_use]
+    pub const fn union(self, other: PngStrictness) -> PngStrictness {
+        PngStrictness(self.0 | other.0)
+    }
+    /// Return `self` with flags in `other` removed
+    #[must_use]
+    pub const fn difference(self, other: PngStrictness) -> PngStrictness {
+        PngStrictness(self.0 & !other.0)
+    }
+}
+
+impl core::ops::BitOr for PngStrictness {
+    type Output = PngStrictness;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        self.union(rhs)
+    }
+}
+
+impl core::ops::BitOrAssign for PngStrictness {
+    fn bitor_assign(&mut self, rhs: Self) {
+        *self = self.union(rhs);
+    }
+}
+
+/// Which png chunks have their CRC confirmed
+///
+/// Confirming CRCs means hashing every byte of the file, for small images this
+/// can be a noticeable part of decoding time, policies other than [`All`](Self::All)
+/// trade robustness for speed.
+///
+/// Mismatches found are reported according to [`PngStrictness`]
+///
+/// # Example
+/// ```
+/// use zune_core::options::{DecoderOptions, PngCrcPolicy};
+/// // trust metadata, but make sure the pixels are intact
+/// let options = DecoderOptions::default().png_set_crc_policy(PngCrcPolicy::CriticalOnly);
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PngCrcPolicy {
+    /// Confirm the CRC of every chunk
+    All,
+    /// Confirm the CRC of critical chunks (`IHDR`,`PLTE`,`IDAT`,`IEND`..),
+    /// ancillary chunks are not checked
+    CriticalOnly,
+    /// Confirm the CRC of the first `n` chunks, later chunks are not checked
+    ///
+    /// This catches truncated downloads and files that aren't pngs early on,
+    /// without paying for the image data.
+    FirstChunks(usize)
+}
+
+impl PngCrcPolicy {
+    /// Return true if a chunk should have its CRC confirmed
+    ///
+    /// # Arguments
+    /// - `position`: Position of the chunk in the file, starting at zero for `IHDR`
+    /// - `critical`: Whether the chunk is critical
+    pub const fn should_confirm(self, position: usize, critical: bool) -> bool {
+        match self {
+            PngCrcPolicy::All => true,
+            PngCrcPolicy::CriticalOnly => critical,
+            PngCrcPolicy::FirstChunks(n) => position < n
+        }
+    }
+}
+
+/// Reduced size decoding for jpeg images
+///
+/// Thumbnailers rarely need every pixel, jpeg decoders can output an image
+/// scaled by 1/2, 1/4 or 1/8 and a region of the image.
+///
+/// zune-jpeg has no scaled IDCT yet, the full image is still decoded and then
+/// cropped and box averaged, so this does not make decoding faster. It only means
+/// later stages work on the smaller image.
+///
+/// The region is applied first, in full image coordinates, and the region is then scaled,
+/// scaled dimensions are rounded up.
+///
+/// # Example
+/// ```
+/// use zune_core::options::{DecoderOptions, JpegDecodeOptions};
+/// // largest side at least 256 pixels, for a thumbnail
+/// let jpeg_options = JpegDecodeOptions::new().set_min_size(256);
+/// let options = DecoderOptions::default().jpeg_set_decode_options(jpeg_options);
+///
+/// assert_eq!(jpeg_options.output_dimensions(4000, 3000), Some((500, 375)));
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct JpegDecodeOptions {
+    scale:    usize,
+    min_size: Option<usize>,
+    region:   Option<(usize, usize, usize, usize)>
+}
+
+impl Default for JpegDecodeOptions {
+    fn default() -> Self {
+        JpegDecodeOptions::new()
+    }
+}
+
+impl JpegDecodeOptions {
+    /// Supported scale denominators
+    pub const SCALES: [usize; 4] = [1, 2, 4, 8];
+
+    /// Create options that decode the full image
+    pub const fn new() -> JpegDecodeOptions {
+        JpegDecodeOptions {
+            scale:    1,
+            min_size: None,
+            region:   None
+        }
+    }
+    /// Scale the output by `1/denominator`
+    ///
+    /// Denominators that aren't one of [`SCALES`](Self::SCALES) are
+    /// rounded down to one
+    #[must_use]
+    pub const fn set_scale(mut self, denominator: usize) -> Self {
+        self.scale = match denominator {
+            0 | 1 => 1,
+            2 | 3 => 2,
+            4..=7 => 4,
+            _ => 8
+        };
+        self
+    }
+    /// Pick the smallest scale whose largest side is still at least `size` pixels
+    ///
+    /// This overrides [`set_scale`](Self::set_scale)
+    #[must_use]
+    pub const fn set_min_size(mut self, size: usize) -> Self {
+        self.min_size = Some(size);
+        self
+    }
+    /// Only decode `width` x `height` pixels starting at `x`, `y`
+    ///
+    /// Regions extending past the image are clipped to it
+    #[must_use]
+    pub const fn set_region(mut self, x: usize, y: usize, width: usize, height: usize) -> Self {
+        self.region = Some((x, y, width, height));
+        self
+    }
+    /// Return true if these options decode the full image
+    pub const fn is_full_image(&self) -> bool {
+        self.scale == 1 && self.min_size.is_none() && self.region.is_none()
+    }
+
+    /// Return the region decoded from an image of `width` x `height`
+    /// as `(x, y, width, height)`
+    ///
+    /// Returns `None` if the region is empty or outside the image
+    pub fn output_region(
+        &self, width: usize, height: usize
+    ) -> Option<(usize, usize, usize, usize)> {
+        let (x, y, region_width, region_height) = self.region.unwrap_or((0, 0, width, height));
+
+        let region_width = region_width.min(width.saturating_sub(x));
+        let region_height = region_height.min(height.saturating_sub(y));
+
+        (region_width > 0 && region_height > 0).then_some((x, y, region_width, region_height))
+    }
+    /// Return the scale denominator used for an image of `width` x `height`
+    pub fn scale(&self, width: usize, height: usize) -> usize {
+        let Some(min_size) = self.min_size else {
+            return self.scale;
+        };
+        let (_, _, width, height) = self.output_region(width, height).unwrap_or_default();
+        let largest = width.max(height);
+
+        Self::SCALES
+            .into_iter()
+            .rev()
+            .find(|scale| largest.div_ceil(*scale) >= min_size)
+            .unwrap_or(1)
+    }
+    /// Return the dimensions of the decoded image for an image of `width` x `height`
+    ///
+    /// Returns `None` if the region is empty or outside the image
+    pub fn output_dimensions(&self, width: usize, height: usize) -> Option<(usize, usize)> {
+        let (_, _, region_width, region_height) = self.output_region(width, height)?;
+        let scale = self.scale(width, height);
+
+        Some((region_width.div_ceil(scale), region_height.div_ceil(scale)))
+    }
 }
 
 /// Decoder options
@@ -137,14 +413,28 @@ pub struct DecoderOptions {
     ///
     /// - Default value: 16384
     /// - Respected by: `all decoders`
-    max_width:      usize,
+    max_width:          usize,
     /// Maximum height for which decoders will not
     /// try to decode images larger than the
     /// specified height
     ///
     /// - Default value: 16384
     /// - Respected by: `all decoders`
-    max_height:     usize,
+    max_height:         usize,
+    /// Maximum number of bytes a decoded frame may occupy
+    ///
+    /// This is checked against the dimensions, colorspace and depth
+    /// in the image headers before any pixels are decoded.
+    ///
+    /// - Default value: `usize::MAX`, no limit
+    /// - Respected by: `zune-image`
+    max_memory:         usize,
+    /// Number of bytes before and after the failure position
+    /// kept in decoding errors
+    ///
+    /// - Default value: 0, no context
+    /// - Respected by: `png`, `gif`, when built with their `diagnostics` feature
+    error_context:      usize,
     /// Output colorspace
     ///
     /// The jpeg decoder allows conversion to a separate colorspace
@@ -155,7 +445,30 @@ pub struct DecoderOptions {
     ///
     /// - Default value: `ColorSpace::RGB`
     /// - Respected by: `jpeg`
-    out_colorspace: ColorSpace,
+    out_colorspace:     ColorSpace,
+    /// Output colorspace for png images
+    ///
+    /// The png decoder can expand gray images to RGB and add or strip
+    /// the alpha channel while un-filtering scanlines.
+    ///
+    /// - Default value: `None`, the decoder picks the image colorspace
+    /// - Respected by: `png`
+    png_out_colorspace: Option<ColorSpace>,
+    /// Classes of png errors that are fatal
+    ///
+    /// - Default value: Everything except `PngStrictness::TRAILING_DATA`
+    /// - Respected by: `png`
+    png_strictness:     PngStrictness,
+    /// Which png chunks have their CRC confirmed
+    ///
+    /// - Default value: `PngCrcPolicy::All`
+    /// - Respected by: `png`
+    png_crc_policy:     PngCrcPolicy,
+    /// Reduced size decoding for jpeg images
+    ///
+    /// - Default value: Decode the full image
+    /// - Respected by: `jpeg` (via `zune-image`)
+    jpeg_decode:        JpegDecodeOptions,
 
     /// Maximum number of scans allowed
     /// for progressive jpeg images
@@ -223,6 +536,19 @@ impl DecoderOptions {
         self.max_height
     }
 
+    /// Get the maximum number of bytes a decoded frame may occupy
+    pub const fn get_max_memory(&self) -> usize {
+        self.max_memory
+    }
+
+    /// Get the number of bytes kept on each side of the position
+    /// decoding failed at
+    ///
+    /// See [`set_error_context_size`](Self::set_error_context_size)
+    pub const fn get_error_context_size(&self) -> usize {
+        self.error_context
+    }
+
     /// Return true whether the decoder should be in strict mode
     /// And reject most errors
     pub fn get_strict_mode(&self) -> bool {
@@ -262,6 +588,36 @@ impl DecoderOptions {
         self
     }
 
+    /// Set the maximum number of bytes a decoded frame may occupy
+    ///
+    /// Images whose headers describe a larger frame are rejected before
+    /// decoding, which guards against small files that expand to huge images.
+    /// Unlike [`set_max_width`](Self::set_max_width) and [`set_max_height`](Self::set_max_height),
+    /// this accounts for the number of components and the bit depth
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes`: The maximum size of a decoded frame in bytes
+    ///
+    /// returns: DecoderOptions
+    pub fn set_max_memory(mut self, bytes: usize) -> Self {
+        self.max_memory = bytes;
+        self
+    }
+
+    /// Set the number of bytes kept on each side of the position decoding
+    /// failed at, see [`ByteContext`](crate::diagnostics::ByteContext)
+    ///
+    /// Decoders built with their `diagnostics` feature attach these bytes
+    /// to errors, zero attaches none and leaves errors unwrapped.
+    ///
+    /// - Default value: 0
+    /// - Respected by: `png`, `gif`
+    pub const fn set_error_context_size(mut self, bytes: usize) -> Self {
+        self.error_context = bytes;
+        self
+    }
+
     /// Whether the routines can use unsafe platform specific
     /// intrinsics when necessary
     ///
@@ -291,6 +647,10 @@ impl DecoderOptions {
     /// This reduces the error tolerance level for the decoders and invalid
     /// samples will be rejected by the decoder
     ///
+    /// Data after the png `IEND` chunk is still accepted in strict mode,
+    /// use [`png_set_strictness`](Self::png_set_strictness) with
+    /// `PngStrictness::STRICT` to reject it too
+    ///
     /// # Arguments
     ///
     /// * `yes`:
@@ -301,6 +661,50 @@ impl DecoderOptions {
         self.flags.jpg_error_on_non_conformance = yes;
         self.flags.png_confirm_crc = yes;
         self.flags.inflate_confirm_adler = yes;
+        self.png_strictness = if yes {
+            PngStrictness::STRICT.difference(PngStrictness::TRAILING_DATA)
+        } else {
+            PngStrictness::PERMISSIVE
+        };
+        self
+    }
+
+    /// Get whether decoders salvage truncated images
+    ///
+    /// See [`set_allow_partial`](Self::set_allow_partial)
+    pub const fn get_allow_partial(&self) -> bool {
+        self.flags.allow_partial
+    }
+    /// Set whether decoders should salvage truncated images
+    ///
+    /// When the input ends before the whole image is decoded, decoders return
+    /// the rows reconstructed so far with the rest of the image left black,
+    /// instead of returning an error, even in strict mode.
+    /// Decoders report this via an `is_truncated` method.
+    ///
+    /// - Default value: `false`
+    /// - Respected by: `png`, `jpeg`
+    pub const fn set_allow_partial(mut self, yes: bool) -> Self {
+        self.flags.allow_partial = yes;
+        self
+    }
+
+    /// Get whether decoders record timings of their decoding stages
+    ///
+    /// See [`set_collect_timings`](Self::set_collect_timings)
+    pub const fn get_collect_timings(&self) -> bool {
+        self.flags.collect_timings
+    }
+    /// Set whether decoders should record the time spent in each decoding stage
+    ///
+    /// Timings are retrieved with the decoder's `timings` method after
+    /// decoding, see [`DecodeTimings`](crate::timings::DecodeTimings).
+    /// Without the `std` feature no time is recorded.
+    ///
+    /// - Default value: `false`
+    /// - Respected by: `png`, `jpeg`
+    pub const fn set_collect_timings(mut self, yes: bool) -> Self {
+        self.flags.collect_timings = yes;
         self
     }
 
@@ -397,6 +801,104 @@ impl DecoderOptions {
         self.flags.png_strip_16_bit_to_8_bit
     }
 
+    /// Set whether the png decoder should keep the raw values of samples
+    /// smaller than a byte
+    ///
+    /// Grayscale images with 1, 2 or 4 bits per sample are always
+    /// unpacked to one byte per sample. By default samples are scaled to the full
+    /// 8 bit range, e.g 2 bit samples `0..=3` become `0, 85, 170, 255`. When set, they keep
+    /// their stored values, which is useful when samples are measurements or labels
+    /// rather than intensities.
+    ///
+    /// Palette indices are never scaled.
+    ///
+    /// - Default value: `false`
+    pub const fn png_set_keep_raw_samples(mut self, yes: bool) -> Self {
+        self.flags.png_keep_raw_samples = yes;
+        self
+    }
+
+    /// Return whether the png decoder keeps the raw values of samples smaller than a byte
+    ///
+    /// See [`png_set_keep_raw_samples`](Self::png_set_keep_raw_samples)
+    pub const fn png_get_keep_raw_samples(&self) -> bool {
+        self.flags.png_keep_raw_samples
+    }
+
+    /// Set whether the png decoder should rescale samples of images with an
+    /// `sBIT` chunk to the full range of the image depth
+    ///
+    /// The png specification requires encoders to scale samples with fewer significant
+    /// bits to the full range, yet some scanners and scientific tools store e.g 12 bit
+    /// data as is in a 16 bit png, which then looks almost black. When set, samples
+    /// are assumed to hold values in the range given by the `sBIT` chunk and are scaled to
+    /// the 8 or 16 bit range, e.g 12 bit samples `0..=4095` become `0..=65535`.
+    ///
+    /// Images with samples outside that range were already scaled by their encoder and
+    /// are left as is.
+    ///
+    /// - Default value: `false`
+    pub const fn png_set_rescale_significant_bits(mut self, yes: bool) -> Self {
+        self.flags.png_rescale_sbit = yes;
+        self
+    }
+
+    /// Return whether the png decoder rescales samples of images with an `sBIT` chunk
+    ///
+    /// See [`png_set_rescale_significant_bits`](Self::png_set_rescale_significant_bits)
+    pub const fn png_get_rescale_significant_bits(&self) -> bool {
+        self.flags.png_rescale_sbit
+    }
+
+    /// Set whether `zune-image` should decode 16 bit png images whose `sBIT` chunk
+    /// gives every channel 10 or 12 significant bits as
+    /// [`BitDepth::Ten`](crate::bit_depth::BitDepth::Ten) or
+    /// [`BitDepth::Twelve`](crate::bit_depth::BitDepth::Twelve) images
+    ///
+    /// Samples are shifted back to their original range, e.g `0..=4095` for 12 bits.
+    /// Not every image operation handles those depths, so by default such images
+    /// decode as 16 bit images with samples in the full 16 bit range.
+    ///
+    /// Has no effect when samples are rescaled, see
+    /// [`png_set_rescale_significant_bits`](Self::png_set_rescale_significant_bits)
+    ///
+    /// - Default value: `false`
+    pub const fn png_set_decode_significant_depth(mut self, yes: bool) -> Self {
+        self.flags.png_sbit_depth = yes;
+        self
+    }
+
+    /// Return whether `zune-image` decodes 16 bit png images with 10 or 12 significant
+    /// bits to those depths
+    ///
+    /// See [`png_set_decode_significant_depth`](Self::png_set_decode_significant_depth)
+    pub const fn png_get_decode_significant_depth(&self) -> bool {
+        self.flags.png_sbit_depth
+    }
+
+    /// Set whether the png decoder should composite images with an alpha channel
+    /// onto their background color
+    ///
+    /// When set, pixels are blended onto the color of the `bKGD` chunk, or white if
+    /// the image has none, and the alpha channel is dropped, so an RGBA image decodes to RGB
+    /// and a luma with alpha image to luma. This is what viewers and thumbnailers
+    /// usually want, and it is done while expanding scanlines so no extra pass is needed.
+    ///
+    /// Animated images are not composited, since frames have to be blended onto each other.
+    ///
+    /// - Default value: `false`
+    pub const fn png_set_composite_background(mut self, yes: bool) -> Self {
+        self.flags.png_composite_background = yes;
+        self
+    }
+
+    /// Return whether the png decoder composites images onto their background color
+    ///
+    /// See [`png_set_composite_background`](Self::png_set_composite_background)
+    pub const fn png_get_composite_background(&self) -> bool {
+        self.flags.png_composite_background
+    }
+
     /// Return whether `zune-image` should decode animated images or
     /// whether we should just decode the first frame only
     pub const fn png_decode_animated(&self) -> bool {
@@ -408,6 +910,83 @@ impl DecoderOptions {
         self.flags.png_decode_animated = yes;
         self
     }
+
+    /// Return whether the png decoder should decode the seven
+    /// Adam7 passes of interlaced images on multiple threads
+    pub const fn png_get_threaded_adam7(&self) -> bool {
+        self.flags.png_threaded_adam7
+    }
+    /// Set whether the png decoder should decode the seven
+    /// Adam7 passes of interlaced images on multiple threads
+    ///
+    /// The image is still inflated once, but since passes are independent
+    /// afterwards, un-filtering and de-interlacing them is done in parallel.
+    ///
+    /// This requires the `threads` feature of `zune-png`, otherwise it is ignored
+    ///
+    /// Threads are taken from the shared budget in `zune_core::threads`
+    pub const fn png_set_threaded_adam7(mut self, yes: bool) -> Self {
+        self.flags.png_threaded_adam7 = yes;
+        self
+    }
+
+    /// Get the colorspace the png decoder was asked to output
+    ///
+    /// Returns `None` if the image colorspace should be used
+    pub const fn png_get_out_colorspace(&self) -> Option<ColorSpace> {
+        self.png_out_colorspace
+    }
+    /// Set the colorspace the png decoder should output
+    ///
+    /// The conversion is done in the same pass as scanline un-filtering, so it
+    /// is cheaper than converting the decoded image afterwards.
+    ///
+    /// Supported conversions are between `Luma`, `LumaA`, `RGB` and `RGBA`
+    /// where gray is expanded to RGB and alpha is either added (fully opaque)
+    /// or stripped. Conversions the decoder cannot do (e.g. RGB to Luma) are ignored
+    /// and the image colorspace is returned, so one should always check
+    /// `PngDecoder::get_colorspace`.
+    ///
+    /// Equivalent to libpng's `png_set_gray_to_rgb`, `png_set_add_alpha`
+    /// and `png_set_strip_alpha` transforms
+    #[must_use]
+    pub const fn png_set_out_colorspace(mut self, colorspace: ColorSpace) -> Self {
+        self.png_out_colorspace = Some(colorspace);
+        self
+    }
+
+    /// Get the classes of errors the png decoder treats as fatal
+    pub const fn png_get_strictness(&self) -> PngStrictness {
+        self.png_strictness
+    }
+    /// Set the classes of errors the png decoder treats as fatal
+    ///
+    /// Errors not in `strictness` are logged as warnings and decoding continues,
+    /// use [`PngStrictness::PERMISSIVE`] to decode damaged images the way browsers
+    /// do and [`PngStrictness::STRICT`] to reject anything non-conforming.
+    ///
+    /// CRC classes only apply when CRC confirmation is enabled via
+    /// [`png_set_confirm_crc`](Self::png_set_confirm_crc)
+    #[must_use]
+    pub const fn png_set_strictness(mut self, strictness: PngStrictness) -> Self {
+        self.png_strictness = strictness;
+        self
+    }
+
+    /// Get which chunks the png decoder confirms the CRC of
+    pub const fn png_get_crc_policy(&self) -> PngCrcPolicy {
+        self.png_crc_policy
+    }
+    /// Set which chunks the png decoder confirms the CRC of
+    ///
+    /// This also enables CRC confirmation, use
+    /// [`png_set_confirm_crc`](Self::png_set_confirm_crc) to skip CRCs entirely
+    #[must_use]
+    pub const fn png_set_crc_policy(mut self, policy: PngCrcPolicy) -> Self {
+        self.png_crc_policy = policy;
+        self.flags.png_confirm_crc = true;
+        self
+    }
 }
 
 /// JPEG specific options
@@ -440,6 +1019,16 @@ impl DecoderOptions {
         self.out_colorspace = colorspace;
         self
     }
+    /// Get the scale and region jpeg images are decoded at
+    pub const fn jpeg_get_decode_options(&self) -> JpegDecodeOptions {
+        self.jpeg_decode
+    }
+    /// Set the scale and region jpeg images are decoded at
+    #[must_use]
+    pub const fn jpeg_set_decode_options(mut self, options: JpegDecodeOptions) -> Self {
+        self.jpeg_decode = options;
+        self
+    }
 }
 
 /// Intrinsics support
@@ -645,13 +1234,19 @@ impl DecoderOptions {
 impl Default for DecoderOptions {
     fn default() -> Self {
         Self {
-            out_colorspace: ColorSpace::RGB,
-            max_width:      1 << 14,
-            max_height:     1 << 14,
-            max_scans:      100,
-            deflate_limit:  1 << 30,
-            flags:          decoder_strict_mode(),
-            endianness:     ByteEndian::BE
+            out_colorspace:     ColorSpace::RGB,
+            png_out_colorspace: None,
+            png_strictness:     PngStrictness::STRICT.difference(PngStrictness::TRAILING_DATA),
+            png_crc_policy:     PngCrcPolicy::All,
+            jpeg_decode:        JpegDecodeOptions::new(),
+            max_width:          1 << 14,
+            max_height:         1 << 14,
+            max_memory:         usize::MAX,
+            error_context:      0,
+            max_scans:          100,
+            deflate_limit:      1 << 30,
+            flags:              decoder_strict_mode(),
+            endianness:         ByteEndian::BE
         }
     }
 }
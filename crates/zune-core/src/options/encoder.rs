@@ -6,7 +6,7 @@
  * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
  */
 
-use crate::bit_depth::BitDepth;
+use crate::bit_depth::{BitDepth, ByteEndian};
 use crate::colorspace::ColorSpace;
 
 /// Encoder options that are flags
@@ -31,7 +31,8 @@ pub struct EncoderOptions {
     depth:       BitDepth,
     num_threads: u8,
     effort:      u8,
-    flags:       EncoderFlags
+    flags:       EncoderFlags,
+    endianness:  ByteEndian
 }
 
 impl Default for EncoderOptions {
@@ -44,7 +45,8 @@ impl Default for EncoderOptions {
             depth:       BitDepth::Eight,
             num_threads: 4,
             effort:      4,
-            flags:       EncoderFlags::default()
+            flags:       EncoderFlags::default(),
+            endianness:  ByteEndian::BE
         }
     }
 }
@@ -183,7 +185,33 @@ impl EncoderOptions {
     /// The default value is false, and encoders that respect this try to preserve as much
     /// data as possible from one image to another
     pub const fn strip_metadata(&self) -> bool {
-        !self.flags.image_strip_metadata
+        self.flags.image_strip_metadata
+    }
+
+    /// Set the byte order of samples that span more than one byte
+    ///
+    /// This is used for raw outputs of 16 bit images, e.g `Image::to_u8`,
+    /// where one may want native little endian samples instead of big endian ones.
+    ///
+    /// Formats whose specification mandates a byte order, like PNG and PPM,
+    /// ignore this and always use their required order.
+    ///
+    /// Default is [`ByteEndian::BE`]
+    ///
+    /// # Arguments
+    ///
+    /// * `endian`: The byte order to write samples in
+    ///
+    /// returns: EncoderOptions
+    pub fn set_byte_endian(mut self, endian: ByteEndian) -> Self {
+        self.endianness = endian;
+        self
+    }
+
+    /// Get the byte order in which samples that span more than one byte
+    /// will be written
+    pub const fn get_byte_endian(&self) -> ByteEndian {
+        self.endianness
     }
 }
 
@@ -9,13 +9,29 @@
 //! Decoding results for images
 use alloc::vec::Vec;
 
+fn expand_indices(indices: &[u8], palette: &[[u8; 4]]) -> Vec<u8> {
+    indices
+        .iter()
+        .flat_map(|x| palette.get(usize::from(*x)).copied().unwrap_or([0; 4]))
+        .collect()
+}
+
 /// A simple enum that can hold decode
 /// results of most images
 #[non_exhaustive]
 pub enum DecodingResult {
     U8(Vec<u8>),
     U16(Vec<u16>),
-    F32(Vec<f32>)
+    /// Floating point samples, e.g. from HDR formats, nominally in `0.0..=1.0`
+    F32(Vec<f32>),
+    /// Palette indices and the palette they index into
+    ///
+    /// Palette entries are RGBA, formats without palette transparency
+    /// should set alpha to 255
+    Indexed {
+        indices: Vec<u8>,
+        palette: Vec<[u8; 4]>
+    }
 }
 
 impl DecodingResult {
@@ -69,4 +85,151 @@ impl DecodingResult {
             _ => None
         }
     }
+
+    /// Return the contents if the enum stores `Vec<f32>` or otherwise
+    /// return `None`.
+    ///
+    /// # Example
+    /// ```
+    /// use zune_core::result::DecodingResult;
+    /// let data = DecodingResult::F32(vec![0.0;100]);
+    /// assert!(data.f32().is_some());
+    /// ```
+    pub fn f32(self) -> Option<Vec<f32>> {
+        match self {
+            DecodingResult::F32(data) => Some(data),
+            _ => None
+        }
+    }
+
+    /// Apply `func` to every 8 bit sample
+    ///
+    /// For [`Indexed`](DecodingResult::Indexed) results, `func` is applied to the
+    /// palette entries, which is the same as applying it to every pixel.
+    /// Other variants are returned unchanged.
+    ///
+    /// # Example
+    /// ```
+    /// use zune_core::result::DecodingResult;
+    /// let data = DecodingResult::U8(vec![10;100]).map_u8(|x| 255 - x);
+    /// assert_eq!(data.u8().unwrap()[0], 245);
+    /// ```
+    #[must_use]
+    pub fn map_u8<F: FnMut(u8) -> u8>(self, mut func: F) -> DecodingResult {
+        match self {
+            DecodingResult::U8(mut data) => {
+                data.iter_mut().for_each(|x| *x = func(*x));
+                DecodingResult::U8(data)
+            }
+            DecodingResult::Indexed {
+                indices,
+                mut palette
+            } => {
+                palette.iter_mut().flatten().for_each(|x| *x = func(*x));
+                DecodingResult::Indexed { indices, palette }
+            }
+            result => result
+        }
+    }
+
+    /// Apply `func` to every 16 bit sample, other variants are returned unchanged
+    #[must_use]
+    pub fn map_u16<F: FnMut(u16) -> u16>(self, mut func: F) -> DecodingResult {
+        match self {
+            DecodingResult::U16(mut data) => {
+                data.iter_mut().for_each(|x| *x = func(*x));
+                DecodingResult::U16(data)
+            }
+            result => result
+        }
+    }
+
+    /// Apply `func` to every float sample, other variants are returned unchanged
+    #[must_use]
+    pub fn map_f32<F: FnMut(f32) -> f32>(self, mut func: F) -> DecodingResult {
+        match self {
+            DecodingResult::F32(mut data) => {
+                data.iter_mut().for_each(|x| *x = func(*x));
+                DecodingResult::F32(data)
+            }
+            result => result
+        }
+    }
+
+    /// Replace palette indices with the RGBA palette entries they point to
+    ///
+    /// Indices outside the palette become transparent black.
+    /// Other variants are returned unchanged.
+    ///
+    /// # Example
+    /// ```
+    /// use zune_core::result::DecodingResult;
+    /// let data = DecodingResult::Indexed {
+    ///     indices: vec![1, 0],
+    ///     palette: vec![[0, 0, 0, 255], [255, 0, 0, 255]]
+    /// };
+    /// assert_eq!(data.expand_palette().u8().unwrap(), [255, 0, 0, 255, 0, 0, 0, 255]);
+    /// ```
+    #[must_use]
+    pub fn expand_palette(self) -> DecodingResult {
+        match self {
+            DecodingResult::Indexed { indices, palette } => {
+                DecodingResult::U8(expand_indices(&indices, &palette))
+            }
+            result => result
+        }
+    }
+
+    /// Return the samples as 8 bit values, converting from other types
+    ///
+    /// - 16 bit samples keep their most significant byte
+    /// - Float samples are clamped to `0.0..=1.0` and scaled
+    /// - Indexed results are expanded to RGBA via [`expand_palette`](Self::expand_palette)
+    ///
+    /// # Example
+    /// ```
+    /// use zune_core::result::DecodingResult;
+    /// let data = DecodingResult::U16(vec![65535, 257]);
+    /// assert_eq!(data.as_u8_lossy(), [255, 1]);
+    /// ```
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn as_u8_lossy(&self) -> Vec<u8> {
+        match self {
+            DecodingResult::U8(data) => data.clone(),
+            DecodingResult::U16(data) => data.iter().map(|x| (x >> 8) as u8).collect(),
+            DecodingResult::F32(data) => data
+                .iter()
+                .map(|x| (x.clamp(0.0, 1.0) * 255.0 + 0.5) as u8)
+                .collect(),
+            DecodingResult::Indexed { indices, palette } => expand_indices(indices, palette)
+        }
+    }
+
+    /// Return the samples as 16 bit values, converting from other types
+    ///
+    /// - 8 bit samples are scaled to the full 16 bit range, i.e. `255` becomes `65535`
+    /// - Float samples are clamped to `0.0..=1.0` and scaled
+    /// - Indexed results are expanded to RGBA first
+    ///
+    /// # Example
+    /// ```
+    /// use zune_core::result::DecodingResult;
+    /// let data = DecodingResult::F32(vec![1.0, 0.0, 2.0]);
+    /// assert_eq!(data.as_u16_lossy(), [65535, 0, 65535]);
+    /// ```
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn as_u16_lossy(&self) -> Vec<u16> {
+        match self {
+            DecodingResult::U16(data) => data.clone(),
+            DecodingResult::F32(data) => data
+                .iter()
+                .map(|x| (x.clamp(0.0, 1.0) * 65535.0 + 0.5) as u16)
+                .collect(),
+            result => result
+                .as_u8_lossy()
+                .iter()
+                .map(|x| u16::from(*x) * 257)
+                .collect()
+        }
+    }
 }
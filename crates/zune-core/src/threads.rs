@@ -0,0 +1,286 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! A thread budget shared by the zune crates
+//!
+//! Crates that do work on multiple threads (e.g Adam7 de-interlacing in `zune-png`,
+//! per channel filters in `zune-imageprocs`) run it through a [`ThreadPool`], so
+//! they all draw from one process wide budget instead of each spawning as many
+//! threads as there are cores and oversubscribing the machine.
+//!
+//! - [`set_num_threads`] sets the global budget, by default it's the available parallelism.
+//! - [`with_num_threads`] overrides it for everything run inside a closure on the current thread.
+//! - [`ThreadPool::set_num_threads`] overrides it for a single call.
+//!
+//! Work is run on scoped threads, so it can borrow from the caller, and the calling
+//! thread takes part in the work. When the budget is used up, e.g by a nested call
+//! from inside a worker, the work simply runs on the calling thread.
+//!
+//! # Example
+//! ```
+//! use zune_core::threads::{with_num_threads, ThreadPool};
+//!
+//! let mut rows = vec![vec![1_u8; 10]; 4];
+//!
+//! // double every row, on at most two threads
+//! with_num_threads(2, || {
+//!     ThreadPool::new().for_each(rows.iter_mut(), |row| row.iter_mut().for_each(|x| *x *= 2));
+//! });
+//! assert!(rows.iter().flatten().all(|x| *x == 2));
+//! ```
+use core::cell::Cell;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::vec::Vec;
+
+/// Global thread budget, zero means use the available parallelism
+static NUM_THREADS: AtomicUsize = AtomicUsize::new(0);
+
+/// Number of worker threads currently running in all pools
+static ACTIVE_WORKERS: AtomicUsize = AtomicUsize::new(0);
+
+std::thread_local! {
+    /// Budget set by [`with_num_threads`] for the current thread
+    static THREAD_OVERRIDE: Cell<Option<usize>> = const { Cell::new(None) };
+}
+
+/// Set the maximum number of threads used by parallel work in the zune crates
+///
+/// The count includes the calling thread, so `1` disables threading,
+/// `0` resets it to the available parallelism.
+pub fn set_num_threads(num_threads: usize) {
+    NUM_THREADS.store(num_threads, Ordering::Relaxed);
+}
+
+/// Return the maximum number of threads used by parallel work
+///
+/// This is the value of [`set_num_threads`], or the available parallelism if it wasn't set
+pub fn get_num_threads() -> usize {
+    resolve(NUM_THREADS.load(Ordering::Relaxed))
+}
+
+/// Run `func` with a different thread budget for all parallel work it does on this thread
+///
+/// The previous budget is restored once `func` returns
+pub fn with_num_threads<R>(num_threads: usize, func: impl FnOnce() -> R) -> R {
+    /// Restores the previous override, even if `func` panics
+    struct Restore(Option<usize>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            THREAD_OVERRIDE.with(|x| x.set(self.0));
+        }
+    }
+    let _restore = Restore(THREAD_OVERRIDE.with(|x| x.replace(Some(num_threads))));
+
+    func()
+}
+
+/// Convert a thread count to an actual count, zero means available parallelism
+fn resolve(num_threads: usize) -> usize {
+    if num_threads == 0 {
+        std::thread::available_parallelism().map_or(1, |x| x.get())
+    } else {
+        num_threads
+    }
+}
+
+/// Runs work on scoped threads, limited by the shared thread budget
+///
+/// The pool doesn't keep threads around, it's a cheap handle and can be
+/// created wherever it's needed.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct ThreadPool {
+    num_threads: Option<usize>
+}
+
+impl ThreadPool {
+    /// Create a pool using the current thread budget
+    pub const fn new() -> ThreadPool {
+        ThreadPool { num_threads: None }
+    }
+
+    /// Override the thread budget for work run by this pool
+    ///
+    /// `1` runs everything on the calling thread, `0` uses the available parallelism
+    #[must_use]
+    pub const fn set_num_threads(mut self, num_threads: usize) -> ThreadPool {
+        self.num_threads = Some(num_threads);
+        self
+    }
+
+    /// Return the maximum number of threads, including the caller,
+    /// this pool will use
+    pub fn num_threads(&self) -> usize {
+        let num_threads = self
+            .num_threads
+            .or_else(|| THREAD_OVERRIDE.with(Cell::get))
+            .unwrap_or_else(|| NUM_THREADS.load(Ordering::Relaxed));
+
+        resolve(num_threads)
+    }
+
+    /// Call `func` on every item, possibly on multiple threads, and
+    /// return the results in the order of the items
+    pub fn map<T, R, F>(&self, items: impl IntoIterator<Item = T>, func: F) -> Vec<R>
+    where
+        T: Send,
+        R: Send,
+        F: Fn(T) -> R + Sync
+    {
+        let items: Vec<T> = items.into_iter().collect();
+        let workers = Workers::acquire(self.num_threads(), items.len().saturating_sub(1));
+
+        if workers.count == 0 {
+            return items.into_iter().map(func).collect();
+        }
+        let queue = Mutex::new(items.into_iter().enumerate());
+
+        // every thread takes items from the queue until it's empty
+        let run = || {
+            let mut results = Vec::new();
+
+            loop {
+                let next = queue.lock().unwrap_or_else(|x| x.into_inner()).next();
+
+                match next {
+                    Some((position, item)) => results.push((position, func(item))),
+                    None => return results
+                }
+            }
+        };
+        let mut results = std::thread::scope(|s| {
+            let handles: Vec<_> = (0..workers.count).map(|_| s.spawn(run)).collect();
+
+            let mut results = run();
+
+            for handle in handles {
+                match handle.join() {
+                    Ok(worker_results) => results.extend(worker_results),
+                    Err(panic) => std::panic::resume_unwind(panic)
+                }
+            }
+            results
+        });
+        results.sort_unstable_by_key(|(position, _)| *position);
+
+        results.into_iter().map(|(_, result)| result).collect()
+    }
+
+    /// Call `func` on every item, possibly on multiple threads
+    pub fn for_each<T, F>(&self, items: impl IntoIterator<Item = T>, func: F)
+    where
+        T: Send,
+        F: Fn(T) + Sync
+    {
+        self.map(items, func);
+    }
+
+    /// Call `func` on every item, possibly on multiple threads,
+    /// returning the first error in item order
+    ///
+    /// All items are processed even if some of them fail
+    pub fn try_for_each<T, E, F>(
+        &self, items: impl IntoIterator<Item = T>, func: F
+    ) -> Result<(), E>
+    where
+        T: Send,
+        E: Send,
+        F: Fn(T) -> Result<(), E> + Sync
+    {
+        self.map(items, func).into_iter().collect()
+    }
+}
+
+/// Worker threads taken from the global budget, returned when dropped
+struct Workers {
+    count: usize
+}
+
+impl Workers {
+    /// Take up to `wanted` workers, keeping the total number of threads
+    /// (workers plus the calling thread) within `num_threads`
+    fn acquire(num_threads: usize, wanted: usize) -> Workers {
+        let limit = num_threads.saturating_sub(1);
+        let mut active = ACTIVE_WORKERS.load(Ordering::Relaxed);
+
+        loop {
+            let count = wanted.min(limit.saturating_sub(active));
+
+            if count == 0 {
+                return Workers { count: 0 };
+            }
+            match ACTIVE_WORKERS.compare_exchange_weak(
+                active,
+                active + count,
+                Ordering::AcqRel,
+                Ordering::Relaxed
+            ) {
+                Ok(_) => return Workers { count },
+                Err(current) => active = current
+            }
+        }
+    }
+}
+
+impl Drop for Workers {
+    fn drop(&mut self) {
+        if self.count > 0 {
+            ACTIVE_WORKERS.fetch_sub(self.count, Ordering::AcqRel);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::vec::Vec;
+
+    use super::ThreadPool;
+
+    #[test]
+    fn map_keeps_order() {
+        let results = ThreadPool::new()
+            .set_num_threads(4)
+            .map(0..100_usize, |x| x * 2);
+
+        assert_eq!(results, (0..100).map(|x| x * 2).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn single_thread_runs_on_caller() {
+        let caller = std::thread::current().id();
+
+        ThreadPool::new()
+            .set_num_threads(1)
+            .for_each(0..10, |_| assert_eq!(std::thread::current().id(), caller));
+    }
+
+    #[test]
+    fn threads_are_bounded() {
+        let running = AtomicUsize::new(0);
+        let max_running = AtomicUsize::new(0);
+
+        ThreadPool::new().set_num_threads(3).for_each(0..50, |_| {
+            let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+            max_running.fetch_max(now, Ordering::SeqCst);
+            std::thread::sleep(std::time::Duration::from_millis(1));
+            running.fetch_sub(1, Ordering::SeqCst);
+        });
+        assert!(max_running.load(Ordering::SeqCst) <= 3);
+    }
+
+    #[test]
+    fn try_for_each_returns_first_error() {
+        let result = ThreadPool::new()
+            .set_num_threads(4)
+            .try_for_each(0..20, |x| if x % 7 == 6 { Err(x) } else { Ok(()) });
+
+        assert_eq!(result, Err(6));
+    }
+}
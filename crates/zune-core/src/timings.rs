@@ -0,0 +1,174 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! Decoding stage timings
+//!
+//! When [`DecoderOptions::set_collect_timings`] is enabled, decoders record how long
+//! each stage of decoding (header parsing, inflating, unfiltering ...) took into a
+//! [`DecodeTimings`], which they expose via a `timings` method.
+//!
+//! Measuring time requires the `std` feature, without it stages are still
+//! listed but report a zero duration.
+//!
+//! # Example
+//! ```
+//! use zune_core::timings::{DecodeTimings, StageTimer};
+//!
+//! let mut timings = DecodeTimings::default();
+//!
+//! let timer = StageTimer::start(true);
+//! // do some work
+//! timings.record("headers", timer);
+//!
+//! assert_eq!(timings.stages()[0].0, "headers");
+//! ```
+//!
+//! [`DecoderOptions::set_collect_timings`]: crate::options::DecoderOptions::set_collect_timings
+use alloc::vec::Vec;
+use core::fmt;
+use core::time::Duration;
+
+/// A running measurement of a single decoding stage
+///
+/// Created by [`StageTimer::start`] and consumed by [`DecodeTimings::record`]
+#[derive(Copy, Clone, Debug)]
+pub struct StageTimer {
+    #[cfg(feature = "std")]
+    start:   Option<std::time::Instant>,
+    enabled: bool
+}
+
+impl StageTimer {
+    /// Start measuring a stage
+    ///
+    /// If `enabled` is false, no time is read and recording the
+    /// timer does nothing, so decoders can unconditionally time stages.
+    pub fn start(enabled: bool) -> StageTimer {
+        StageTimer {
+            #[cfg(feature = "std")]
+            start: enabled.then(std::time::Instant::now),
+            enabled
+        }
+    }
+    /// Return the time elapsed since the timer was started
+    ///
+    /// This is zero for disabled timers or when the `std` feature is off
+    pub fn elapsed(&self) -> Duration {
+        #[cfg(feature = "std")]
+        {
+            if let Some(start) = self.start {
+                return start.elapsed();
+            }
+        }
+        Duration::ZERO
+    }
+}
+
+/// Time spent in each stage of decoding an image
+///
+/// Stages are kept in the order they were first recorded, stages
+/// recorded more than once, e.g per scanline or per frame, are summed.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DecodeTimings {
+    stages: Vec<(&'static str, Duration)>
+}
+
+impl DecodeTimings {
+    /// Add the time measured by `timer` to `stage`
+    ///
+    /// Does nothing if the timer is disabled
+    pub fn record(&mut self, stage: &'static str, timer: StageTimer) {
+        if timer.enabled {
+            self.add(stage, timer.elapsed());
+        }
+    }
+    /// Add `duration` to `stage`, creating the stage if it doesn't exist
+    pub fn add(&mut self, stage: &'static str, duration: Duration) {
+        match self.stages.iter_mut().find(|(name, _)| *name == stage) {
+            Some((_, total)) => *total += duration,
+            None => self.stages.push((stage, duration))
+        }
+    }
+    /// Add all stages of `other` to this timings
+    pub fn extend(&mut self, other: &DecodeTimings) {
+        for (stage, duration) in &other.stages {
+            self.add(stage, *duration);
+        }
+    }
+    /// Return the recorded stages and their durations in the order they were first recorded
+    pub fn stages(&self) -> &[(&'static str, Duration)] {
+        &self.stages
+    }
+    /// Return the time spent in `stage`, if it was recorded
+    pub fn get(&self, stage: &str) -> Option<Duration> {
+        self.stages
+            .iter()
+            .find(|(name, _)| *name == stage)
+            .map(|(_, duration)| *duration)
+    }
+    /// Return the sum of all stages
+    pub fn total(&self) -> Duration {
+        self.stages.iter().map(|(_, duration)| *duration).sum()
+    }
+    /// Return true if no stage was recorded
+    pub fn is_empty(&self) -> bool {
+        self.stages.is_empty()
+    }
+    /// Remove all recorded stages
+    pub fn clear(&mut self) {
+        self.stages.clear();
+    }
+}
+
+impl fmt::Display for DecodeTimings {
+    /// Write one `stage: duration` line per stage followed by the total
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self
+            .stages
+            .iter()
+            .map(|(name, _)| name.len())
+            .max()
+            .unwrap_or(0)
+            .max("total".len());
+
+        for (stage, duration) in &self.stages {
+            writeln!(f, "{stage:<width$} : {duration:?}")?;
+        }
+        write!(f, "{:<width$} : {:?}", "total", self.total())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    use crate::timings::{DecodeTimings, StageTimer};
+
+    #[test]
+    fn test_stages_are_summed() {
+        let mut timings = DecodeTimings::default();
+        timings.add("inflate", Duration::from_millis(2));
+        timings.add("unfilter", Duration::from_millis(1));
+        timings.add("inflate", Duration::from_millis(3));
+
+        assert_eq!(timings.stages().len(), 2);
+        assert_eq!(timings.stages()[0].0, "inflate");
+        assert_eq!(timings.get("inflate"), Some(Duration::from_millis(5)));
+        assert_eq!(timings.total(), Duration::from_millis(6));
+    }
+
+    #[test]
+    fn test_disabled_timer_records_nothing() {
+        let mut timings = DecodeTimings::default();
+        timings.record("headers", StageTimer::start(false));
+        assert!(timings.is_empty());
+
+        timings.record("headers", StageTimer::start(true));
+        assert!(timings.get("headers").is_some());
+    }
+}
@@ -0,0 +1,65 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! Checked arithmetic for image sizes
+//!
+//! Dimensions come from untrusted headers, and `width * height * components`
+//! easily overflows a `usize` on 32 bit targets, wrapping to a small value
+//! that under-allocates the output. Decoders should compute buffer sizes with
+//! these helpers and reject images whose size can't be represented.
+use crate::bit_depth::BitDepth;
+
+/// Return the number of bytes needed to store an image, or `None` if it overflows a `usize`
+///
+/// # Arguments
+/// - width, height: Image dimensions
+/// - components: Number of components per pixel, e.g 3 for RGB
+/// - depth: Bit depth of each component, `BitDepth::Unknown` returns `None`
+///
+/// # Example
+/// ```
+/// use zune_core::bit_depth::BitDepth;
+/// use zune_core::utils::checked_image_size;
+///
+/// assert_eq!(checked_image_size(10, 10, 3, BitDepth::Sixteen), Some(600));
+/// assert_eq!(checked_image_size(usize::MAX / 2, 3, 1, BitDepth::Eight), None);
+/// ```
+pub fn checked_image_size(
+    width: usize, height: usize, components: usize, depth: BitDepth
+) -> Option<usize> {
+    if depth == BitDepth::Unknown {
+        return None;
+    }
+    width
+        .checked_mul(height)?
+        .checked_mul(components)?
+        .checked_mul(depth.size_of())
+}
+
+/// Return the number of bytes in a single row of an image, or `None` if it overflows a `usize`
+///
+/// Rows of packed images (depths less than 8 bits) are rounded up to whole bytes
+///
+/// # Arguments
+/// - width: Number of pixels in the row
+/// - components: Number of components per pixel
+/// - bits: Number of bits in each component
+///
+/// # Example
+/// ```
+/// use zune_core::utils::checked_row_size;
+///
+/// // 10 pixels of 1 bit gray need two bytes
+/// assert_eq!(checked_row_size(10, 1, 1), Some(2));
+/// assert_eq!(checked_row_size(10, 4, 16), Some(80));
+/// ```
+pub fn checked_row_size(width: usize, components: usize, bits: usize) -> Option<usize> {
+    let bits = width.checked_mul(components)?.checked_mul(bits)?;
+
+    Some(bits / 8 + usize::from(bits % 8 != 0))
+}
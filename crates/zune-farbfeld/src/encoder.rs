@@ -136,6 +136,23 @@ impl<'a> FarbFeldEncoder<'a> {
         Ok(())
     }
 
+    /// Encode only the image header
+    ///
+    /// The header is followed by the RGBA samples in big endian,
+    /// this allows writing an image in pieces without holding the
+    /// whole encoded image in memory
+    pub fn encode_header(&self) -> Result<Vec<u8>, FarbFeldEncoderErrors> {
+        let mut out = vec![0; FARBFELD_HEADER_SIZE];
+        let mut stream = ZByteWriter::new(&mut out);
+
+        self.encode_headers(&mut stream)?;
+
+        let position = stream.position();
+        out.truncate(position);
+
+        Ok(out)
+    }
+
     /// Encode the contents returning a vector containing
     /// encoded contents or an error if anything occurs
     pub fn encode(&self) -> Result<Vec<u8>, FarbFeldEncoderErrors> {
@@ -1,9 +1,18 @@
+use zune_core::bit_depth::BitDepth;
 use zune_core::bytestream::{ZByteReader, ZReaderTrait};
 use zune_core::log::trace;
 use zune_core::options::DecoderOptions;
+use zune_core::utils::checked_image_size;
 
 use crate::errors::GifDecoderErrors;
 
+/// Largest number of codes in an LZW code table
+const MAX_CODES: usize = 4096;
+
+/// A GIF decoder
+///
+/// Frames are decoded one at a time via [`decode_frame`](Self::decode_frame), each
+/// one composited over the previous ones into an RGBA canvas of the image dimensions.
 pub struct GifDecoder<T: ZReaderTrait> {
     stream:       ZByteReader<T>,
     options:      DecoderOptions,
@@ -14,14 +23,120 @@ pub struct GifDecoder<T: ZReaderTrait> {
     ratio:        u8,
     read_headers: bool,
     _background:  u16, // current b
-    pal:          [[u8; 4]; 256]
+    pal:          [[u8; 4]; 256],
+    /// The composited image, in RGBA
+    canvas:       Vec<u8>,
+    /// Number of frames decoded so far
+    frame_index:  usize,
+    /// How the previous frame is disposed of before the next one is drawn
+    dispose:      Option<Disposal>
+}
+
+/// Disposal of a frame, from its graphic control extension
+enum Disposal {
+    /// Clear the frame area to transparent
+    Background {
+        left:   usize,
+        top:    usize,
+        width:  usize,
+        height: usize
+    },
+    /// Restore the canvas to what it was before the frame was drawn
+    Previous(Vec<u8>)
+}
+
+/// Details from a graphic control extension, applying to the next frame
+#[derive(Copy, Clone, Default)]
+struct GraphicControl {
+    disposal:    u8,
+    transparent: Option<u8>
 }
 
 impl<T: ZReaderTrait> GifDecoder<T> {
+    /// Create a new decoder that will decode `data` with default options
+    pub fn new(data: T) -> GifDecoder<T> {
+        GifDecoder::new_with_options(data, DecoderOptions::default())
+    }
+    /// Create a new decoder that will decode `data` with the specified options
+    pub fn new_with_options(data: T, options: DecoderOptions) -> GifDecoder<T> {
+        GifDecoder {
+            stream: ZByteReader::new(data),
+            options,
+            width: 0,
+            height: 0,
+            flags: 0,
+            bgindex: 0,
+            ratio: 0,
+            read_headers: false,
+            _background: 0,
+            pal: [[0; 4]; 256],
+            canvas: vec![],
+            frame_index: 0,
+            dispose: None
+        }
+    }
+
     pub fn decode_headers(&mut self) -> Result<(), GifDecoderErrors> {
         if self.read_headers {
             return Ok(());
         }
+        self.decode_headers_inner()
+            .map_err(|err| self.with_context(err))
+    }
+
+    /// Return the image width and height, or `None` if the headers
+    /// haven't been decoded
+    pub fn get_dimensions(&self) -> Option<(usize, usize)> {
+        if !self.read_headers {
+            return None;
+        }
+        Some((self.width, self.height))
+    }
+
+    /// Decode the first frame of the image, returning RGBA pixels
+    ///
+    /// # Errors
+    /// If the image is corrupt or has no frames
+    pub fn decode(&mut self) -> Result<Vec<u8>, GifDecoderErrors> {
+        self.decode_frame()?
+            .ok_or(GifDecoderErrors::Static("Image has no frames"))
+    }
+
+    /// Decode the next frame of the image
+    ///
+    /// # Returns
+    /// - `Ok(Some(pixels))`: The RGBA canvas after drawing the frame
+    /// - `Ok(None)`: There are no more frames
+    ///
+    /// # Errors
+    /// If the frame is corrupt, with [`TruncatedFrame`](GifDecoderErrors::TruncatedFrame)
+    /// when the stream ends inside it
+    pub fn decode_frame(&mut self) -> Result<Option<Vec<u8>>, GifDecoderErrors> {
+        self.decode_headers()?;
+
+        self.decode_frame_inner()
+            .map_err(|err| self.with_context(err))
+    }
+
+    /// Attach the bytes around the current stream position to `err`
+    ///
+    /// This is a no-op without the `diagnostics` feature
+    #[allow(clippy::unused_self)]
+    fn with_context(&self, err: GifDecoderErrors) -> GifDecoderErrors {
+        #[cfg(feature = "diagnostics")]
+        {
+            let window = self.options.get_error_context_size();
+
+            if window != 0 && err.context().is_none() {
+                let context = self.stream.byte_context(self.stream.get_position(), window);
+
+                return GifDecoderErrors::WithContext(Box::new(err), context);
+            }
+        }
+        err
+    }
+
+    fn decode_headers_inner(&mut self) -> Result<(), GifDecoderErrors> {
         if !test_gif(&mut self.stream) {
             return Err(GifDecoderErrors::NotAGif);
         }
@@ -47,9 +162,17 @@ impl<T: ZReaderTrait> GifDecoder<T> {
                 self.height
             ));
         }
+        // frames are decoded to RGBA, make sure that fits in a usize
+        if checked_image_size(self.width, self.height, 4, BitDepth::Eight).is_none() {
+            return Err(GifDecoderErrors::Static(
+                "Image dimensions are too large to decode on this platform"
+            ));
+        }
         // check if we have a global palette
         if (self.flags & 0x80) > 0 {
-            self.parse_colortable(2 << (self.flags & 7), usize::MAX)?;
+            let mut pal = self.pal;
+            self.parse_colortable(2 << (self.flags & 7), &mut pal)?;
+            self.pal = pal;
         }
         trace!("Image width  :{}", self.width);
         trace!("Image height :{}", self.height);
@@ -58,25 +181,329 @@ impl<T: ZReaderTrait> GifDecoder<T> {
 
         Ok(())
     }
-    fn parse_colortable(&mut self, num_entries: usize, transp: usize) -> Result<(), &'static str> {
+
+    fn decode_frame_inner(&mut self) -> Result<Option<Vec<u8>>, GifDecoderErrors> {
+        let mut control = GraphicControl::default();
+
+        loop {
+            // a missing trailer is common, treat the end of the stream as one
+            let Ok(block) = self.stream.get_u8_err() else {
+                return Ok(None);
+            };
+            match block {
+                // extension
+                0x21 => {
+                    let label = self.stream.get_u8_err()?;
+
+                    if label == 0xF9 {
+                        control = self.parse_graphic_control()?;
+                    } else {
+                        self.skip_sub_blocks()?;
+                    }
+                }
+                // image descriptor
+                0x2C => return self.decode_image(control).map(Some),
+                // trailer
+                0x3B => return Ok(None),
+                _ => return Err(GifDecoderErrors::Static("Unknown block in gif stream"))
+            }
+        }
+    }
+
+    fn parse_graphic_control(&mut self) -> Result<GraphicControl, GifDecoderErrors> {
+        let length = usize::from(self.stream.get_u8_err()?);
+
+        if length != 4 {
+            return Err(GifDecoderErrors::Static("Bad graphic control extension length"));
+        }
+        let packed = self.stream.get_u8_err()?;
+        let _delay = self.stream.get_u16_le_err()?;
+        let index = self.stream.get_u8_err()?;
+
+        let control = GraphicControl {
+            disposal:    (packed >> 2) & 7,
+            transparent: (packed & 1 == 1).then_some(index)
+        };
+        self.skip_sub_blocks()?;
+
+        Ok(control)
+    }
+
+    /// Skip data sub-blocks up to and including the block terminator
+    fn skip_sub_blocks(&mut self) -> Result<(), GifDecoderErrors> {
+        loop {
+            let length = usize::from(self.stream.get_u8_err()?);
+
+            if length == 0 {
+                return Ok(());
+            }
+            if !self.stream.has(length) {
+                return Err(GifDecoderErrors::InsufficientData(
+                    length,
+                    self.stream.remaining()
+                ));
+            }
+            self.stream.skip(length);
+        }
+    }
+
+    fn decode_image(&mut self, control: GraphicControl) -> Result<Vec<u8>, GifDecoderErrors> {
+        let frame_index = self.frame_index;
+        let truncated = |_| GifDecoderErrors::TruncatedFrame { frame_index };
+
+        let left = usize::from(self.stream.get_u16_le_err().map_err(truncated)?);
+        let top = usize::from(self.stream.get_u16_le_err().map_err(truncated)?);
+        let width = usize::from(self.stream.get_u16_le_err().map_err(truncated)?);
+        let height = usize::from(self.stream.get_u16_le_err().map_err(truncated)?);
+        let packed = self.stream.get_u8_err().map_err(truncated)?;
+
+        // the canvas passed the dimension limits, frames must fit in it before
+        // anything of their size is allocated
+        if left + width > self.width || top + height > self.height {
+            return Err(GifDecoderErrors::Static("Frame is outside the logical screen"));
+        }
+        let palette = if packed & 0x80 != 0 {
+            let mut local = [[0; 4]; 256];
+            self.parse_colortable(2 << (packed & 7), &mut local)
+                .map_err(|_| GifDecoderErrors::TruncatedFrame { frame_index })?;
+            local
+        } else if self.flags & 0x80 != 0 {
+            self.pal
+        } else {
+            return Err(GifDecoderErrors::MissingColorTable);
+        };
+        let interlaced = packed & 0x40 != 0;
+
+        let min_code_size = self.stream.get_u8_err().map_err(truncated)?;
+
+        if !(1..=11).contains(&min_code_size) {
+            return Err(GifDecoderErrors::Static("Invalid LZW minimum code size"));
+        }
+        // gather the image data sub-blocks
+        let mut data = vec![];
+
+        loop {
+            let length = usize::from(self.stream.get_u8_err().map_err(truncated)?);
+
+            if length == 0 {
+                break;
+            }
+            let block = self.stream.get(length).map_err(truncated)?;
+            data.extend_from_slice(block);
+        }
+        let mut indices = vec![0; width * height];
+
+        let decoded = lzw_decode(&data, min_code_size, &mut indices)?;
+
+        if decoded < indices.len() {
+            return Err(GifDecoderErrors::TruncatedFrame { frame_index });
+        }
+        self.draw_frame(
+            &indices,
+            &palette,
+            (left, top, width, height),
+            interlaced,
+            control
+        );
+        self.frame_index += 1;
+
+        Ok(self.canvas.clone())
+    }
+
+    /// Composite a frame of palette indices into the canvas
+    fn draw_frame(
+        &mut self, indices: &[u8], palette: &[[u8; 4]; 256],
+        (left, top, width, height): (usize, usize, usize, usize), interlaced: bool,
+        control: GraphicControl
+    ) {
+        if self.canvas.is_empty() {
+            self.canvas = vec![0; self.width * self.height * 4];
+        }
+        // dispose of the previous frame
+        match self.dispose.take() {
+            Some(Disposal::Background {
+                left,
+                top,
+                width,
+                height
+            }) => {
+                for row in self.canvas.chunks_exact_mut(self.width * 4).skip(top).take(height) {
+                    let start = (left * 4).min(row.len());
+                    let end = ((left + width) * 4).min(row.len());
+                    row[start..end].fill(0);
+                }
+            }
+            Some(Disposal::Previous(canvas)) => self.canvas = canvas,
+            None => ()
+        }
+        self.dispose = match control.disposal {
+            2 => Some(Disposal::Background {
+                left,
+                top,
+                width,
+                height
+            }),
+            3 => Some(Disposal::Previous(self.canvas.clone())),
+            _ => None
+        };
+
+        for (i, row) in indices.chunks_exact(width.max(1)).enumerate() {
+            let y = top + if interlaced { interlaced_row(i, height) } else { i };
+
+            if y >= self.height {
+                continue;
+            }
+            for (x, index) in row.iter().enumerate() {
+                let x = left + x;
+
+                if x >= self.width || Some(*index) == control.transparent {
+                    continue;
+                }
+                // palette entries are stored in BGRA order
+                let [b, g, r, a] = palette[usize::from(*index)];
+                let position = (y * self.width + x) * 4;
+
+                self.canvas[position..position + 4].copy_from_slice(&[r, g, b, a]);
+            }
+        }
+    }
+
+    fn parse_colortable(
+        &mut self, num_entries: usize, pal: &mut [[u8; 4]; 256]
+    ) -> Result<(), GifDecoderErrors> {
         if !self.stream.has(num_entries * 3) {
-            return Err("Not enough bytes for palette");
-        }
-        self.pal
-            .iter_mut()
-            .take(num_entries)
-            .enumerate()
-            .for_each(|(pos, x)| {
-                // weird order
-                x[2] = self.stream.get_u8();
-                x[1] = self.stream.get_u8();
-                x[0] = self.stream.get_u8();
-                x[3] = if transp == pos { 0 } else { 255 }
-            });
+            return Err(GifDecoderErrors::InsufficientData(
+                num_entries * 3,
+                self.stream.remaining()
+            ));
+        }
+        pal.iter_mut().take(num_entries).for_each(|x| {
+            // weird order
+            x[2] = self.stream.get_u8();
+            x[1] = self.stream.get_u8();
+            x[0] = self.stream.get_u8();
+            x[3] = 255;
+        });
         Ok(())
     }
 }
 
+/// Map the `i`th stored row of an interlaced frame to its row in the frame
+fn interlaced_row(i: usize, height: usize) -> usize {
+    // rows are stored in four passes, every 8th row starting at 0, every 8th
+    // starting at 4, every 4th starting at 2 and every 2nd starting at 1
+    let passes = [(0, 8), (4, 8), (2, 4), (1, 2)];
+    let mut i = i;
+
+    for (start, step) in passes {
+        let rows = height.saturating_sub(start).div_ceil(step);
+
+        if i < rows {
+            return start + i * step;
+        }
+        i -= rows;
+    }
+    i
+}
+
+/// Decode LZW compressed `data` into `output`
+///
+/// Returns the number of indices written, decoding stops at the end of
+/// information code, when `data` runs out or when `output` is full.
+fn lzw_decode(data: &[u8], min_code_size: u8, output: &mut [u8]) -> Result<usize, GifDecoderErrors> {
+    let clear_code = 1_usize << min_code_size;
+    let end_code = clear_code + 1;
+
+    // each code is the code of its prefix string followed by a byte
+    let mut prefix = [0_u16; MAX_CODES];
+    let mut suffix = [0_u8; MAX_CODES];
+    // first byte and length of the string for each code
+    let mut first = [0_u8; MAX_CODES];
+    let mut lengths = [0_u16; MAX_CODES];
+
+    for code in 0..clear_code {
+        suffix[code] = code as u8;
+        first[code] = code as u8;
+        lengths[code] = 1;
+    }
+    let mut code_size = usize::from(min_code_size) + 1;
+    let mut next_code = clear_code + 2;
+    let mut previous: Option<usize> = None;
+
+    let mut bit_buffer = 0_u32;
+    let mut bits = 0;
+    let mut bytes = data.iter();
+    let mut position = 0;
+
+    while position < output.len() {
+        while bits < code_size {
+            let Some(byte) = bytes.next() else {
+                return Ok(position);
+            };
+            bit_buffer |= u32::from(*byte) << bits;
+            bits += 8;
+        }
+        let code = (bit_buffer & ((1 << code_size) - 1)) as usize;
+        bit_buffer >>= code_size;
+        bits -= code_size;
+
+        if code == clear_code {
+            code_size = usize::from(min_code_size) + 1;
+            next_code = clear_code + 2;
+            previous = None;
+            continue;
+        }
+        if code == end_code {
+            break;
+        }
+        let bad_code = || GifDecoderErrors::BadLzwCode {
+            code: code as u16,
+            position
+        };
+        match previous {
+            None if code < clear_code => (),
+            None => return Err(bad_code()),
+            Some(previous) => {
+                // the new entry is the previous string followed by the first
+                // byte of this one, which for a code not yet in the table is
+                // the previous string's first byte
+                let string_first = if code < next_code {
+                    first[code]
+                } else if code == next_code {
+                    first[previous]
+                } else {
+                    return Err(bad_code());
+                };
+                if next_code < MAX_CODES {
+                    prefix[next_code] = previous as u16;
+                    suffix[next_code] = string_first;
+                    first[next_code] = first[previous];
+                    lengths[next_code] = lengths[previous] + 1;
+                    next_code += 1;
+
+                    if next_code == 1 << code_size && code_size < 12 {
+                        code_size += 1;
+                    }
+                }
+            }
+        }
+        // write the string backwards, truncating it if it overflows the output
+        let length = usize::from(lengths[code]);
+        let end = (position + length).min(output.len());
+        let mut current = code;
+
+        for i in (position..position + length).rev() {
+            if i < end {
+                output[i] = suffix[current];
+            }
+            current = usize::from(prefix[current]);
+        }
+        position = end;
+        previous = Some(code);
+    }
+    Ok(position)
+}
+
 fn test_gif<T: ZReaderTrait>(buffer: &mut ZByteReader<T>) -> bool {
     if buffer.get_u8() != b'G'
         || buffer.get_u8() != b'I'
@@ -1,33 +1,124 @@
-use core::fmt::Debug;
+use core::fmt::{Debug, Display};
 use std::fmt::Formatter;
 
+use zune_core::diagnostics::ByteContext;
+
+/// Errors possible during gif decoding
 pub enum GifDecoderErrors {
     /// File is not a gif
     NotAGif,
     /// A generic error
     Static(&'static str),
     /// To large dimensions for width or height
-    TooLargeDimensions(&'static str, usize, usize)
+    TooLargeDimensions(&'static str, usize, usize),
+    /// The stream doesn't have enough bytes
+    ///
+    /// # Arguments
+    /// - 1st argument is the number of bytes we expected
+    /// - 2nd argument is number of bytes actually left
+    InsufficientData(usize, usize),
+    /// An LZW code that is not yet in the code table
+    BadLzwCode {
+        /// The code encountered
+        code:     u16,
+        /// Position of the code in the output stream
+        position: usize
+    },
+    /// A frame has no local color table and the image
+    /// has no global color table
+    MissingColorTable,
+    /// The stream ended before the frame was fully decoded
+    TruncatedFrame {
+        /// Index of the truncated frame, starting from zero
+        frame_index: usize
+    },
+    /// An error reading the image
+    Io(std::io::Error),
+    /// An error with the bytes around the position it occurred at
+    ///
+    /// Only returned when the crate is built with the `diagnostics` feature
+    WithContext(Box<GifDecoderErrors>, ByteContext)
 }
+
+impl GifDecoderErrors {
+    /// Return the bytes around the position decoding failed at, if known
+    ///
+    /// This requires the `diagnostics` feature
+    pub fn context(&self) -> Option<&ByteContext> {
+        match self {
+            Self::WithContext(_, context) => Some(context),
+            _ => None
+        }
+    }
+    /// Return the error without the bytes around its position
+    pub fn without_context(&self) -> &GifDecoderErrors {
+        match self {
+            Self::WithContext(err, _) => err,
+            err => err
+        }
+    }
+}
+
 impl Debug for GifDecoderErrors {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
             GifDecoderErrors::NotAGif => {
-                writeln!(f, "Not a gif, magic bytes didn't match")
+                write!(f, "Not a gif, magic bytes didn't match")
             }
             GifDecoderErrors::Static(v) => {
-                writeln!(f, "{}", v)
+                write!(f, "{}", v)
             }
             GifDecoderErrors::TooLargeDimensions(a, b, c) => {
-                writeln!(
+                write!(
                     f,
                     "Too large dimensions for {a} expected less than {b} but found  {c}"
                 )
             }
+            GifDecoderErrors::InsufficientData(expected, found) => {
+                write!(
+                    f,
+                    "Insufficient data required {expected} but remaining stream has {found}"
+                )
+            }
+            GifDecoderErrors::BadLzwCode { code, position } => {
+                write!(f, "Bad LZW code {code} at position {position}")
+            }
+            GifDecoderErrors::MissingColorTable => {
+                write!(f, "Frame has no local or global color table")
+            }
+            GifDecoderErrors::TruncatedFrame { frame_index } => {
+                write!(f, "Frame {frame_index} is truncated")
+            }
+            GifDecoderErrors::Io(err) => {
+                write!(f, "I/O error: {err}")
+            }
+            GifDecoderErrors::WithContext(err, _) => write!(f, "{err:?}")
+        }
+    }
+}
+
+impl Display for GifDecoderErrors {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl std::error::Error for GifDecoderErrors {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::WithContext(err, _) => err.source(),
+            _ => None
         }
     }
 }
 
+impl From<std::io::Error> for GifDecoderErrors {
+    fn from(value: std::io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
 impl From<&'static str> for GifDecoderErrors {
     fn from(value: &'static str) -> Self {
         Self::Static(value)
@@ -0,0 +1,156 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+use std::fs::read;
+use std::path::Path;
+
+use zune_gif::{GifDecoder, GifDecoderErrors};
+
+fn open(name: &str) -> Vec<u8> {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("../../test-images/gif")
+        .join(name);
+    read(path).unwrap()
+}
+
+const BLACK: [u8; 4] = [0, 0, 0, 255];
+const RED: [u8; 4] = [255, 0, 0, 255];
+const GREEN: [u8; 4] = [0, 255, 0, 255];
+const BLUE: [u8; 4] = [0, 0, 255, 255];
+
+#[test]
+fn test_decode_first_frame() {
+    let data = open("rgb_4x3.gif");
+    let mut decoder = GifDecoder::new(&data);
+
+    let pixels = decoder.decode().unwrap();
+
+    let expected = [
+        RED, RED, RED, RED, GREEN, GREEN, GREEN, GREEN, BLUE, BLACK, BLUE, BLACK
+    ];
+    assert_eq!(decoder.get_dimensions(), Some((4, 3)));
+    assert_eq!(pixels, expected.concat());
+}
+
+#[test]
+fn test_frame_outside_screen() {
+    let data = open("rgb_4x3.gif");
+    // the image descriptor follows the 4 color global table
+    assert_eq!(data[25], 0x2C);
+
+    // a frame far larger than the 4x3 screen
+    let mut huge = data.clone();
+    huge[30..34].copy_from_slice(&[0xFF; 4]);
+    assert!(GifDecoder::new(&huge).decode().is_err());
+
+    // a frame of the screen size moved right by a pixel
+    let mut shifted = data.clone();
+    shifted[26] = 1;
+    assert!(GifDecoder::new(&shifted).decode().is_err());
+}
+
+#[test]
+fn test_decode_full_code_table() {
+    // enough noise to grow codes to 12 bits and fill the code table
+    let data = open("noise_128x128.gif");
+    let pixels = GifDecoder::new(&data).decode().unwrap();
+
+    let palette = [BLACK, RED, GREEN, BLUE];
+    let mut state = 0x2545_F491_u32;
+
+    for (i, pixel) in pixels.chunks_exact(4).enumerate() {
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        let index = if i % 7 == 0 { 1 } else { (state >> 8) & 3 };
+
+        assert_eq!(pixel, palette[index as usize], "pixel {i}");
+    }
+}
+
+#[test]
+fn test_decode_frames() {
+    let data = open("two_frames.gif");
+    let mut decoder = GifDecoder::new(&data);
+
+    decoder.decode_frame().unwrap().unwrap();
+    let second = decoder.decode_frame().unwrap().unwrap();
+    assert!(decoder.decode_frame().unwrap().is_none());
+
+    // the second frame is drawn at (1,1), with index 0 transparent
+    let expected = [
+        RED, RED, RED, RED, GREEN, GREEN, BLUE, GREEN, BLUE, BLUE, BLUE, BLACK
+    ];
+    assert_eq!(second, expected.concat());
+}
+
+#[test]
+fn test_missing_color_table() {
+    let data = open("no_color_table.gif");
+    let err = GifDecoder::new(&data).decode().unwrap_err();
+
+    assert!(matches!(err, GifDecoderErrors::MissingColorTable));
+}
+
+#[test]
+fn test_bad_lzw_code() {
+    let data = open("bad_lzw_code.gif");
+    let err = GifDecoder::new(&data).decode().unwrap_err();
+
+    assert!(matches!(
+        err,
+        GifDecoderErrors::BadLzwCode {
+            code:     7,
+            position: 0
+        }
+    ));
+}
+
+#[test]
+fn test_truncated_frame() {
+    let data = open("truncated_second_frame.gif");
+    let mut decoder = GifDecoder::new(&data);
+
+    decoder.decode_frame().unwrap();
+    let err = decoder.decode_frame().unwrap_err();
+
+    assert!(matches!(
+        err,
+        GifDecoderErrors::TruncatedFrame { frame_index: 1 }
+    ));
+    assert_eq!(err.to_string(), "Frame 1 is truncated");
+}
+
+#[test]
+fn test_not_a_gif() {
+    let err = GifDecoder::new(b"GIF80a".as_slice())
+        .decode_headers()
+        .unwrap_err();
+
+    assert_eq!(err.to_string(), "Not a gif, magic bytes didn't match");
+}
+
+#[test]
+#[cfg(feature = "diagnostics")]
+fn test_error_context() {
+    use zune_core::options::DecoderOptions;
+
+    let data = open("truncated_second_frame.gif");
+
+    let options = DecoderOptions::default().set_error_context_size(8);
+    let mut decoder = GifDecoder::new_with_options(&data, options);
+    decoder.decode_frame().unwrap();
+    let err = decoder.decode_frame().unwrap_err();
+
+    let context = err.context().expect("No context for error");
+    assert_eq!(context.bytes(), &data[context.start()..][..context.bytes().len()]);
+    assert!(matches!(
+        err.without_context(),
+        GifDecoderErrors::TruncatedFrame { frame_index: 1 }
+    ));
+}
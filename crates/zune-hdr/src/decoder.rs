@@ -314,7 +314,7 @@ where
             .take(self.height)
         {
             if self.width < 8 || self.width > 0x7fff {
-                self.decompress(&mut scanline, self.width as i32)?;
+                self.decompress(&mut scanline, 0)?;
                 convert_scanline(&scanline, out_scanline);
                 continue;
             }
@@ -325,7 +325,7 @@ where
                 // undo byte read
                 self.buf.rewind(1);
 
-                self.decompress(&mut scanline, self.width as i32)?;
+                self.decompress(&mut scanline, 0)?;
                 convert_scanline(&scanline, out_scanline);
                 continue;
             }
@@ -343,7 +343,8 @@ where
                 scanline[0] = 2;
                 scanline[3] = i;
 
-                self.decompress(&mut scanline, self.width as i32)?;
+                // the first pixel was read above
+                self.decompress(&mut scanline, 1)?;
                 convert_scanline(&scanline, out_scanline);
                 continue;
             }
@@ -392,41 +393,41 @@ where
         Ok(())
     }
 
-    fn decompress(&mut self, scanline: &mut [u8], mut width: i32) -> Result<(), HdrDecodeErrors> {
+    /// Decode a flat or old style run length encoded scanline, starting at pixel `start`
+    fn decompress(&mut self, scanline: &mut [u8], start: usize) -> Result<(), HdrDecodeErrors> {
         let mut shift = 0;
-        let mut scanline_offset = 0;
+        let mut position = start;
 
-        while width > 0 {
+        while position < self.width {
             if !self.buf.has(4) {
                 // not enough bytes for below
                 // panic.
                 return Err(HdrDecodeErrors::Generic("Not enough bytes"));
             }
-
-            scanline[0] = self.buf.get_u8();
-            scanline[1] = self.buf.get_u8();
-            scanline[2] = self.buf.get_u8();
-            scanline[3] = self.buf.get_u8();
-
-            if scanline[0] == 1 && scanline[1] == 1 && scanline[2] == 1 {
-                let run = scanline[3];
-
-                let mut i = i32::from(run) << shift;
-
-                while width > 0 && scanline_offset > 4 && i > 0 {
-                    scanline.copy_within(scanline_offset - 4..scanline_offset, 4);
-                    scanline_offset += 4;
-                    i -= 1;
-                    width -= 4;
+            let pixel = [
+                self.buf.get_u8(),
+                self.buf.get_u8(),
+                self.buf.get_u8(),
+                self.buf.get_u8()
+            ];
+
+            if pixel[0..3] == [1, 1, 1] {
+                // repeat the previous pixel, consecutive runs hold
+                // more significant bytes of the count
+                if position == 0 {
+                    return Err(HdrDecodeErrors::Generic("Run with no pixel to repeat"));
                 }
-                shift += 8;
+                let run = usize::from(pixel[3]) << shift;
+                let end = position.saturating_add(run).min(self.width);
 
-                if shift > 16 {
-                    break;
+                for i in position..end {
+                    scanline.copy_within((position - 1) * 4..position * 4, i * 4);
                 }
+                position = end;
+                shift = (shift + 8).min(24);
             } else {
-                scanline_offset += 4;
-                width -= 1;
+                scanline[position * 4..position * 4 + 4].copy_from_slice(&pixel);
+                position += 1;
                 shift = 0;
             }
         }
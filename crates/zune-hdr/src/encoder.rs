@@ -297,7 +297,8 @@ fn floor(num: f32) -> f32 {
         /* handle infinities and nan */
         return num;
     }
-    let n = num as u64;
+    // signed, an unsigned cast saturates negative numbers to zero
+    let n = num as i64;
     let d = n as f32;
 
     if d == num || num >= 0.0 {
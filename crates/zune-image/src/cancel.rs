@@ -0,0 +1,158 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! Cancelling long running pipelines
+//!
+//! A [`CancellationToken`] is a cheap, cloneable flag shared between the thread running a
+//! pipeline and whoever wants to stop it, e.g a GUI cancel button or a server
+//! dropping a request.
+//!
+//! [`Pipeline`](crate::pipelines::Pipeline) checks its token between decoding, every operation
+//! and every encoder, and makes it the [current](current) token while operations run, so
+//! long running kernels (e.g blurs) can stop at row granularity by polling [`is_cancelled`].
+//!
+//! # Example
+//! ```
+//! use zune_core::colorspace::ColorSpace;
+//! use zune_image::cancel::CancellationToken;
+//! use zune_image::errors::ImageErrors;
+//! use zune_image::image::Image;
+//! use zune_image::pipelines::Pipeline;
+//!
+//! let token = CancellationToken::new();
+//!
+//! let mut pipeline = Pipeline::<Image>::new();
+//! pipeline.chain_image(Image::fill(0_u8, ColorSpace::RGB, 10, 10));
+//! pipeline.set_cancellation_token(token.clone());
+//!
+//! // e.g from another thread
+//! token.cancel();
+//!
+//! assert!(matches!(pipeline.advance_to_end(), Err(ImageErrors::Cancelled)));
+//! ```
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use crate::errors::ImageErrors;
+
+std::thread_local! {
+    /// Token made current by [`CancellationToken::enter`] on this thread
+    static CURRENT: RefCell<Option<CancellationToken>> = const { RefCell::new(None) };
+}
+
+/// A flag used to ask running work to stop
+///
+/// Clones share the same flag, cancelling one cancels all of them.
+/// Once cancelled, a token stays cancelled.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>
+}
+
+impl CancellationToken {
+    /// Create a new token that hasn't been cancelled
+    pub fn new() -> CancellationToken {
+        CancellationToken::default()
+    }
+
+    /// Ask all work observing this token to stop
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Return true if [`cancel`](Self::cancel) was called on this token or a clone of it
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Return [`ImageErrors::Cancelled`] if the token was cancelled
+    pub fn check(&self) -> Result<(), ImageErrors> {
+        if self.is_cancelled() {
+            return Err(ImageErrors::Cancelled);
+        }
+        Ok(())
+    }
+
+    /// Make this the current token of this thread until the returned scope is dropped
+    ///
+    /// Work run on other threads, e.g by a thread pool, doesn't see it, and
+    /// should enter the token captured with [`current`] itself.
+    #[must_use = "the token is only current until the scope is dropped"]
+    pub fn enter(&self) -> CancellationScope {
+        let previous = CURRENT.with(|x| x.replace(Some(self.clone())));
+
+        CancellationScope { previous }
+    }
+}
+
+/// Restores the previously current token when dropped
+///
+/// See [`CancellationToken::enter`]
+pub struct CancellationScope {
+    previous: Option<CancellationToken>
+}
+
+impl Drop for CancellationScope {
+    fn drop(&mut self) {
+        CURRENT.with(|x| x.replace(self.previous.take()));
+    }
+}
+
+/// Return the current token of this thread, if any
+pub fn current() -> Option<CancellationToken> {
+    CURRENT.with(|x| x.borrow().clone())
+}
+
+/// Return true if the current token of this thread was cancelled
+///
+/// This is cheap enough to be called once per row by kernels
+pub fn is_cancelled() -> bool {
+    CURRENT.with(|x| {
+        x.borrow()
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled)
+    })
+}
+
+/// Return [`ImageErrors::Cancelled`] if the current token of this thread was cancelled
+///
+/// Operations whose kernels stopped early because of [`is_cancelled`] call this
+/// so the partially processed image isn't mistaken for a result.
+pub fn check() -> Result<(), ImageErrors> {
+    if is_cancelled() {
+        return Err(ImageErrors::Cancelled);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cancel::{current, is_cancelled, CancellationToken};
+
+    #[test]
+    fn test_scopes_nest() {
+        let outer = CancellationToken::new();
+        let inner = CancellationToken::new();
+
+        assert!(current().is_none());
+        {
+            let _outer = outer.enter();
+            {
+                let _inner = inner.enter();
+                inner.cancel();
+                assert!(is_cancelled());
+            }
+            assert!(!is_cancelled());
+            outer.clone().cancel();
+            assert!(is_cancelled());
+        }
+        assert!(current().is_none());
+        assert!(!is_cancelled());
+    }
+}
@@ -31,10 +31,14 @@
 //!
 #![allow(unused_imports, unused_variables, non_camel_case_types, dead_code)]
 
-use std::io::Cursor;
+use std::ffi::OsString;
+use std::fs::File;
+use std::io::{BufWriter, Cursor, Write};
 use std::path::Path;
 
+use zune_core::bit_depth::BitDepth;
 use zune_core::bytestream::{ZByteReader, ZReaderTrait};
+use zune_core::colorspace::ColorSpace;
 use zune_core::log::trace;
 use zune_core::options::{DecoderOptions, EncoderOptions};
 
@@ -43,7 +47,9 @@ use crate::codecs::ImageFormat::JPEG_XL;
 use crate::errors::ImgEncodeErrors::ImageEncodeErrors;
 use crate::errors::{ImageErrors, ImgEncodeErrors};
 use crate::image::Image;
-use crate::traits::{DecoderTrait, EncoderTrait};
+use crate::metadata::ImageMetadata;
+use crate::registry;
+use crate::traits::{DecoderTrait, EncoderTrait, OperationsTrait};
 
 pub mod bmp;
 mod exr;
@@ -97,6 +103,10 @@ pub enum ImageFormat {
     HDR,
     /// Windows Bitmap Files
     BMP,
+    /// A format registered at runtime by a downstream crate, identified by its name
+    ///
+    /// See [`registry::register_decoder`](crate::registry::register_decoder)
+    External(&'static str),
     /// Any unknown format
     Unknown
 }
@@ -108,7 +118,11 @@ impl ImageFormat {
         self.get_encoder().is_some()
     }
 
+    /// Return true if the library was compiled with a decoder for this format
     pub fn has_decoder(self) -> bool {
+        if let ImageFormat::External(name) = self {
+            return registry::external_has_decoder(name);
+        }
         #[cfg(feature = "jpeg-xl")]
         {
             // for jpeg-xl we  know we have a decoder when the header can be parsed
@@ -122,6 +136,46 @@ impl ImageFormat {
         }
         return self.get_decoder::<&[u8]>(&[]).is_ok();
     }
+
+    /// Return colorspaces the encoder for this format can write
+    ///
+    /// Images in other colorspaces are converted to the encoder's default
+    /// colorspace before encoding.
+    ///
+    /// Returns an empty slice if the format has no encoder
+    pub fn supported_colorspaces_for_encode(self) -> &'static [ColorSpace] {
+        self.get_encoder()
+            .map(|encoder| encoder.supported_colorspaces())
+            .unwrap_or(&[])
+    }
+
+    /// Return bit depths the encoder for this format can write
+    ///
+    /// Returns an empty slice if the format has no encoder
+    pub fn supported_bit_depths_for_encode(self) -> &'static [BitDepth] {
+        self.get_encoder()
+            .map(|encoder| encoder.supported_bit_depth())
+            .unwrap_or(&[])
+    }
+
+    /// Return file extensions commonly used by this format, without the leading dot
+    ///
+    /// The first extension is the preferred one
+    pub fn file_extensions(self) -> &'static [&'static str] {
+        match self {
+            ImageFormat::JPEG => &["jpg", "jpeg"],
+            ImageFormat::PNG => &["png"],
+            ImageFormat::PPM => &["ppm", "pam", "pgm", "pbm", "pfm"],
+            ImageFormat::PSD => &["psd"],
+            ImageFormat::Farbfeld => &["ff"],
+            ImageFormat::QOI => &["qoi"],
+            ImageFormat::JPEG_XL => &["jxl"],
+            ImageFormat::HDR => &["hdr"],
+            ImageFormat::BMP => &["bmp"],
+            ImageFormat::External(name) => registry::external_extensions(name),
+            ImageFormat::Unknown => &[]
+        }
+    }
     pub fn get_decoder<'a, T>(&self, data: T) -> Result<Box<dyn DecoderTrait<T> + 'a>, ImageErrors>
     where
         T: ZReaderTrait + 'a
@@ -251,6 +305,8 @@ impl ImageFormat {
                     Err(ImageErrors::ImageDecoderNotIncluded(*self))
                 }
             }
+            ImageFormat::External(name) => registry::external_decoder(name, &data, options)
+                .ok_or(ImageErrors::ImageDecoderNotIncluded(*self)),
             ImageFormat::Unknown => Err(ImageErrors::ImageDecoderNotImplemented(*self))
         }
     }
@@ -332,6 +388,19 @@ impl ImageFormat {
                     None
                 }
             }
+            Self::Farbfeld => {
+                #[cfg(feature = "farbfeld")]
+                {
+                    Some(Box::new(
+                        codecs::farbfeld::FarbFeldEncoder::new_with_options(options)
+                    ))
+                }
+                #[cfg(not(feature = "farbfeld"))]
+                {
+                    None
+                }
+            }
+            Self::External(name) => registry::external_encoder(name, options),
             // all encoders not implemented default to none
             _ => None
         }
@@ -423,7 +492,10 @@ impl ImageFormat {
                     None
                 }
             }
-            _ => None
+            extension => {
+                let format = registry::external_format_for_extension(extension)?;
+                Some((format, format.get_encoder()?))
+            }
         }
     }
 }
@@ -501,9 +573,51 @@ impl Image {
     /// Ok::<(),ImageErrors>(())
     /// ```
     pub fn save_to<P: AsRef<Path>>(&self, file: P, format: ImageFormat) -> Result<(), ImageErrors> {
-        let contents = self.write_to_vec(format)?;
-        std::fs::write(file, contents)?;
-        Ok(())
+        let Some(mut encoder) = format.get_encoder() else {
+            return Err(ImageErrors::EncodeErrors(ImgEncodeErrors::NoEncoderForFormat(format)));
+        };
+        self.save_with(file, encoder.as_mut())
+    }
+
+    /// Save an image to a file using an already configured encoder
+    ///
+    /// The image is encoded to a temporary file next to `file`, which replaces
+    /// `file` once encoding succeeded, so a failed encode leaves neither an
+    /// empty nor a partially written file behind.
+    ///
+    /// # Arguments
+    ///
+    /// * `file`: The file path to which the image will be saved
+    /// * `encoder`: The encoder to use, with its options already set
+    pub fn save_with<P: AsRef<Path>>(
+        &self, file: P, encoder: &mut dyn EncoderTrait
+    ) -> Result<(), ImageErrors> {
+        let file = file.as_ref();
+
+        let mut temp_name = OsString::from(".");
+        temp_name.push(file.file_name().unwrap_or_default());
+        temp_name.push(format!(".{}.tmp", std::process::id()));
+        let temp = file.with_file_name(temp_name);
+
+        let result = (|| -> Result<(), ImageErrors> {
+            // encoders that can stream write straight to the file
+            let mut writer = BufWriter::new(File::create(&temp)?);
+
+            encoder.encode_to(self, &mut writer)?;
+            writer.flush()?;
+            Ok(())
+        })();
+
+        match result {
+            Ok(()) => std::fs::rename(&temp, file).map_err(|err| {
+                let _ = std::fs::remove_file(&temp);
+                err.into()
+            }),
+            Err(err) => {
+                let _ = std::fs::remove_file(&temp);
+                Err(err)
+            }
+        }
     }
 
     /// Encode an image returning a vector containing the result
@@ -545,6 +659,40 @@ impl Image {
         }
     }
 
+    /// Encode an image and write the result to `writer`
+    ///
+    /// Encoders that can produce output in pieces write it as they go instead
+    /// of holding the whole encoded image in memory, see
+    /// [`EncoderTrait::encode_to`]
+    ///
+    /// # Arguments
+    ///
+    /// * `writer`: Where encoded bytes are written to, it is not buffered
+    /// * `format`: The format to use for encoding, it's an error if the
+    ///   relevant encoder is not present
+    ///
+    /// # Examples
+    ///
+    /// - Encode an image to PPM in memory, needs the ppm format to be enabled
+    /// ```
+    /// use zune_core::colorspace::ColorSpace;
+    /// use zune_image::codecs::ImageFormat;
+    /// use zune_image::image::Image;
+    ///
+    /// let image = Image::fill::<u8>(0,ColorSpace::RGB,10,10);
+    /// let mut output = vec![];
+    /// image.write_to(&mut output,ImageFormat::PPM).unwrap();
+    /// ```
+    pub fn write_to<W: Write>(&self, writer: &mut W, format: ImageFormat) -> Result<(), ImageErrors> {
+        if let Some(mut encoder) = format.get_encoder() {
+            encoder.encode_to(self, writer)
+        } else {
+            Err(ImageErrors::EncodeErrors(
+                crate::errors::ImgEncodeErrors::NoEncoderForFormat(format)
+            ))
+        }
+    }
+
     /// Open an encoded file for which the library has a configured decoder for it
     ///
     /// # Note
@@ -601,7 +749,25 @@ impl Image {
     /// // create a simple ppm p5 grayscale format
     /// let image = Image::read(b"P5 1 1 255 1",DecoderOptions::default());
     ///```
+    ///
+    /// # Limits
+    /// When [`DecoderOptions::set_max_memory`] is set, headers are read first
+    /// and images whose decoded frame would be larger are rejected before
+    /// any pixels are decoded
     pub fn read<T>(src: T, options: DecoderOptions) -> Result<Image, ImageErrors>
+    where
+        T: ZReaderTrait
+    {
+        Self::read_with(src, options, &[]).map(|(image, _)| image)
+    }
+    /// Open a new file from memory with the configured options, running the leading
+    /// `operations` that have interleaved kernels while decoding
+    ///
+    /// Returns the image and how many of `operations` ran on it,
+    /// see [`DecoderTrait::decode_with`]
+    pub fn read_with<T>(
+        src: T, options: DecoderOptions, operations: &[&dyn OperationsTrait]
+    ) -> Result<(Image, usize), ImageErrors>
     where
         T: ZReaderTrait
     {
@@ -609,10 +775,20 @@ impl Image {
 
         if let Some(format) = decoder {
             let mut image_decoder = format.0.get_decoder_with_options(format.1, options)?;
+
+            if options.get_max_memory() != usize::MAX {
+                if let Some(metadata) = image_decoder.read_headers()? {
+                    check_memory_limit(&metadata, options.get_max_memory())?;
+                }
+            }
             // save format
-            let mut image = image_decoder.decode()?;
+            let (mut image, ran) = image_decoder.decode_with(operations)?;
             image.metadata.format = Some(format.0);
-            Ok(image)
+
+            if options.get_collect_timings() {
+                image.metadata.timings = Some(image_decoder.timings());
+            }
+            Ok((image, ran))
         } else {
             Err(ImageErrors::ImageDecoderNotImplemented(
                 ImageFormat::Unknown
@@ -620,6 +796,24 @@ impl Image {
         }
     }
 }
+/// Return an error if a frame described by `metadata` needs more than `limit` bytes
+fn check_memory_limit(metadata: &ImageMetadata, limit: usize) -> Result<(), ImageErrors> {
+    let (width, height) = metadata.get_dimensions();
+
+    let size = width
+        .checked_mul(height)
+        .and_then(|x| x.checked_mul(metadata.get_colorspace().num_components()))
+        .and_then(|x| x.checked_mul(metadata.get_depth().size_of()))
+        .unwrap_or(usize::MAX);
+
+    if size > limit {
+        return Err(ImageErrors::ImageDecodeErrors(format!(
+            "Image of {width}x{height} needs {size} bytes when decoded, more than the limit of {limit} bytes"
+        )));
+    }
+    Ok(())
+}
+
 /// Guess the format of an image based on it's magic bytes
 ///
 /// # Arguments
@@ -677,6 +871,10 @@ where
             }
         }
     }
+    // formats registered by downstream crates
+    if let Some(format) = registry::external_format_for_magic(reader.remaining_bytes()) {
+        return Some((format, reader.consume()));
+    }
 
     None
 }
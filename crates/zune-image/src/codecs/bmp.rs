@@ -18,18 +18,25 @@ use crate::codecs::ImageFormat;
 use crate::errors::ImageErrors;
 use crate::image::Image;
 use crate::metadata::ImageMetadata;
-use crate::traits::DecoderTrait;
+use crate::pipelines::fused_image;
+use crate::traits::{DecoderTrait, OperationsTrait};
 
 impl<T> DecoderTrait<T> for BmpDecoder<T>
 where
     T: ZReaderTrait
 {
     fn decode(&mut self) -> Result<Image, ImageErrors> {
+        DecoderTrait::decode_with(self, &[]).map(|(image, _)| image)
+    }
+
+    fn decode_with(
+        &mut self, operations: &[&dyn OperationsTrait]
+    ) -> Result<(Image, usize), ImageErrors> {
         let pixels = self.decode()?;
         let (width, height) = self.get_dimensions().unwrap();
         let colorspace = self.get_colorspace().unwrap();
 
-        Ok(Image::from_u8(&pixels, width, height, colorspace))
+        fused_image(pixels, width, height, colorspace, operations)
     }
 
     fn dimensions(&self) -> Option<(usize, usize)> {
@@ -11,7 +11,9 @@
 //!
 //! This uses the delegate library [`zune-farbfeld`](zune_farbfeld)
 //! for encoding and decoding images
-use zune_core::bit_depth::BitDepth;
+use std::io::Write;
+
+use zune_core::bit_depth::{BitDepth, ByteEndian};
 use zune_core::bytestream::ZReaderTrait;
 use zune_core::colorspace::ColorSpace;
 use zune_core::options::EncoderOptions;
@@ -106,7 +108,7 @@ impl EncoderTrait for FarbFeldEncoder {
 
         assert_eq!(image.depth(), BitDepth::Sixteen);
 
-        let data = &image.to_u8()[0];
+        let data = &image.to_u8_native()[0];
 
         let encoder_options = zune_farbfeld::FarbFeldEncoder::new(data, options);
 
@@ -117,6 +119,21 @@ impl EncoderTrait for FarbFeldEncoder {
         Ok(data)
     }
 
+    fn encode_inner_to(
+        &mut self, image: &Image, writer: &mut dyn Write
+    ) -> Result<(), ImageErrors> {
+        let options = create_options_for_encoder(self.options, image);
+
+        assert_eq!(image.depth(), BitDepth::Sixteen);
+
+        let header = zune_farbfeld::FarbFeldEncoder::new(&[], options)
+            .encode_header()
+            .map_err(<FarbFeldEncoderErrors as Into<ImgEncodeErrors>>::into)?;
+
+        writer.write_all(&header)?;
+        image.frames_ref()[0].write_interleaved(image.colorspace(), ByteEndian::BE, writer)
+    }
+
     fn supported_colorspaces(&self) -> &'static [ColorSpace] {
         &[ColorSpace::RGBA]
     }
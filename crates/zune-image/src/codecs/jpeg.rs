@@ -14,23 +14,31 @@
 //!
 //! The decoder and encoder both support metadata extraction and saving.
 //!
-use jpeg_encoder::{ColorType, EncodingError};
+use jpeg_encoder::{ColorType, Density, EncodingError};
 use zune_core::bit_depth::BitDepth;
 use zune_core::bytestream::ZReaderTrait;
 use zune_core::colorspace::ColorSpace;
 use zune_core::log::warn;
-use zune_core::options::EncoderOptions;
+use zune_core::options::{EncoderOptions, JpegDecodeOptions};
+use zune_core::timings::{DecodeTimings, StageTimer};
 use zune_jpeg::errors::DecodeErrors;
 pub use zune_jpeg::{ImageInfo, JpegDecoder};
 
 use crate::codecs::{create_options_for_encoder, ImageFormat};
 use crate::errors::{ImageErrors, ImgEncodeErrors};
 use crate::image::Image;
-use crate::metadata::ImageMetadata;
-use crate::traits::{DecodeInto, DecoderTrait, EncoderTrait};
+use crate::metadata::{DensityUnit, ImageMetadata, PhysicalDimensions};
+use crate::pipelines::fused_image;
+use crate::traits::{DecodeInto, DecoderTrait, EncoderTrait, OperationsTrait};
 
 impl<T: ZReaderTrait> DecoderTrait<T> for zune_jpeg::JpegDecoder<T> {
     fn decode(&mut self) -> Result<Image, crate::errors::ImageErrors> {
+        DecoderTrait::decode_with(self, &[]).map(|(image, _)| image)
+    }
+
+    fn decode_with(
+        &mut self, operations: &[&dyn OperationsTrait]
+    ) -> Result<(Image, usize), ImageErrors> {
         let metadata = self.read_headers()?.unwrap();
 
         let pixels = self
@@ -40,14 +48,37 @@ impl<T: ZReaderTrait> DecoderTrait<T> for zune_jpeg::JpegDecoder<T> {
         let colorspace = self.get_output_colorspace().unwrap();
         let (width, height) = self.dimensions().unwrap();
 
-        let mut image = Image::from_u8(&pixels, width, height, colorspace);
+        let decode_options = self.get_options().jpeg_get_decode_options();
+
+        let timer = StageTimer::start(self.get_options().get_collect_timings());
+        let (mut image, ran) = if decode_options.is_full_image() {
+            fused_image(pixels, width, height, colorspace, operations)?
+        } else {
+            let (pixels, width, height) = reduce_pixels(
+                &pixels,
+                (width, height),
+                colorspace.num_components(),
+                decode_options
+            )?;
+            fused_image(pixels, width, height, colorspace, operations)?
+        };
+        self.timings_mut().record("deinterleave", timer);
+
+        // operations that ran may have changed the colorspace
+        let colorspace = image.colorspace();
+
         image.metadata = metadata;
-        image.metadata.colorspace = self.get_output_colorspace().unwrap();
-        Ok(image)
+        image.metadata.colorspace = colorspace;
+        image.metadata.truncated = self.is_truncated();
+        Ok((image, ran))
     }
 
     fn dimensions(&self) -> Option<(usize, usize)> {
-        self.dimensions().map(|dims| (dims.0, dims.1))
+        let (width, height) = self.dimensions()?;
+
+        self.get_options()
+            .jpeg_get_decode_options()
+            .output_dimensions(width, height)
     }
 
     fn out_colorspace(&self) -> ColorSpace {
@@ -62,7 +93,9 @@ impl<T: ZReaderTrait> DecoderTrait<T> for zune_jpeg::JpegDecoder<T> {
         self.decode_headers()
             .map_err(<DecodeErrors as Into<ImageErrors>>::into)?;
 
-        let (width, height) = self.dimensions().unwrap();
+        let (width, height) = <Self as DecoderTrait<T>>::dimensions(self).ok_or(
+            ImageErrors::ImageDecodeErrors("jpg: decode region is outside the image".to_string())
+        )?;
 
         let mut metadata = ImageMetadata {
             format: Some(ImageFormat::JPEG),
@@ -79,9 +112,92 @@ impl<T: ZReaderTrait> DecoderTrait<T> for zune_jpeg::JpegDecoder<T> {
                 metadata.parse_raw_exif(exif)
             }
         }
+        metadata.icc_chunk = self.icc_profile();
+
+        if let Some(info) = self.info() {
+            let unit = match info.density_unit {
+                1 => DensityUnit::Inch,
+                2 => DensityUnit::Centimeter,
+                _ => DensityUnit::Unknown
+            };
+            let (x, y) = (info.x_density, info.y_density);
+            // a square aspect ratio, what most encoders write, says nothing
+            let unknown_square = unit == DensityUnit::Unknown && x == y;
+
+            if x != 0 && y != 0 && !unknown_square {
+                metadata.physical_dimensions =
+                    Some(PhysicalDimensions::new(f32::from(x), f32::from(y), unit));
+            }
+        }
 
         Ok(Some(metadata))
     }
+
+    fn timings(&self) -> DecodeTimings {
+        self.timings().clone()
+    }
+}
+
+/// Crop and downscale interleaved `pixels` of an image with `dimensions` as
+/// requested by `options`
+///
+/// zune-jpeg has no scaled IDCT and always decodes the full image, so this is done
+/// right after decoding, before the image is handed to anything else. Downscaling
+/// averages `scale` x `scale` blocks, it costs an extra pass over the decoded pixels.
+fn reduce_pixels(
+    pixels: &[u8], dimensions: (usize, usize), components: usize, options: JpegDecodeOptions
+) -> Result<(Vec<u8>, usize, usize), ImageErrors> {
+    let (width, height) = dimensions;
+
+    let (x, y, region_width, region_height) =
+        options
+            .output_region(width, height)
+            .ok_or(ImageErrors::ImageDecodeErrors(
+                "jpg: decode region is outside the image".to_string()
+            ))?;
+    let scale = options.scale(width, height);
+
+    let out_width = region_width.div_ceil(scale);
+    let out_height = region_height.div_ceil(scale);
+
+    let mut output = vec![0; out_width * out_height * components];
+    let mut sums = vec![0_u32; out_width * components];
+
+    for (out_y, out_row) in output.chunks_exact_mut(out_width * components).enumerate() {
+        sums.fill(0);
+
+        let start_y = y + out_y * scale;
+        let end_y = (start_y + scale).min(y + region_height);
+
+        for in_y in start_y..end_y {
+            let row_start = (in_y * width + x) * components;
+            let row = &pixels[row_start..row_start + region_width * components];
+
+            for (in_x, pixel) in row.chunks_exact(components).enumerate() {
+                let sum = &mut sums[(in_x / scale) * components..][..components];
+
+                for (sum, sample) in sum.iter_mut().zip(pixel) {
+                    *sum += u32::from(*sample);
+                }
+            }
+        }
+        let block_height = end_y - start_y;
+
+        for (out_x, (out, sum)) in out_row
+            .chunks_exact_mut(components)
+            .zip(sums.chunks_exact(components))
+            .enumerate()
+        {
+            // blocks at the right and bottom edges may be partial
+            let block_width = (region_width - out_x * scale).min(scale);
+            let count = (block_width * block_height) as u32;
+
+            for (out, sum) in out.iter_mut().zip(sum) {
+                *out = ((sum + count / 2) / count) as u8;
+            }
+        }
+    }
+    Ok((output, out_width, out_height))
 }
 
 impl From<zune_jpeg::errors::DecodeErrors> for ImageErrors {
@@ -155,6 +271,28 @@ impl EncoderTrait for JpegEncoder {
             encoder.set_progressive(options.jpeg_encode_progressive());
             encoder.set_optimized_huffman_tables(options.jpeg_optimized_huffman_tables());
 
+            if let Some(dims) = image.metadata.physical_dimensions() {
+                let to_u16 = |density: f32| density.round().clamp(1.0, 65535.0) as u16;
+                // JFIF stores whole pixels per inch or per centimeter
+                let density = if dims.unit == DensityUnit::Inch {
+                    Some(Density::Inch {
+                        x: to_u16(dims.x_density),
+                        y: to_u16(dims.y_density)
+                    })
+                } else {
+                    dims.density_in(DensityUnit::Centimeter)
+                        .map(|(x, y)| Density::Centimeter {
+                            x: to_u16(x),
+                            y: to_u16(y)
+                        })
+                };
+                match density {
+                    Some(density) => encoder.set_density(density),
+                    // jpeg-encoder can't write an aspect ratio
+                    None => warn!("Pixel aspect ratio can't be stored in jpeg, ignoring it")
+                }
+            }
+
             #[cfg(feature = "metadata")]
             {
                 use exif::experimental::Writer;
@@ -181,6 +319,12 @@ impl EncoderTrait for JpegEncoder {
                 }
             }
 
+            if !options.strip_metadata() {
+                if let Some(icc) = image.metadata.icc_chunk() {
+                    encoder.add_icc_profile(icc)?;
+                }
+            }
+
             encoder.encode(pixels, width as u16, height as u16, colorspace)?;
 
             Ok(encoded_data)
@@ -221,6 +365,10 @@ impl EncoderTrait for JpegEncoder {
     fn set_options(&mut self, options: EncoderOptions) {
         self.options = Some(options)
     }
+
+    fn supports_exif(&self) -> bool {
+        cfg!(feature = "metadata")
+    }
 }
 
 /// Match the library colorspace to jpeg color type
@@ -261,3 +409,94 @@ where
         Ok(self.output_buffer_size().unwrap())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use zune_core::colorspace::ColorSpace;
+    use zune_core::options::{DecoderOptions, JpegDecodeOptions};
+
+    use crate::codecs::jpeg::reduce_pixels;
+    use crate::codecs::ImageFormat;
+    use crate::image::Image;
+    use crate::metadata::{DensityUnit, PhysicalDimensions};
+
+    #[test]
+    fn test_density_round_trip() {
+        let mut image = Image::fill(100_u8, ColorSpace::RGB, 16, 16);
+
+        let read_back = |image: &Image| {
+            let jpeg = image.write_to_vec(ImageFormat::JPEG).unwrap();
+            Image::read(&jpeg, DecoderOptions::default())
+                .unwrap()
+                .metadata()
+                .physical_dimensions()
+        };
+        assert_eq!(read_back(&image), None);
+
+        image
+            .metadata_mut()
+            .set_physical_dimensions(PhysicalDimensions::from_dpi(300.0, 150.0));
+        assert_eq!(
+            read_back(&image),
+            Some(PhysicalDimensions::from_dpi(300.0, 150.0))
+        );
+
+        // other units are stored per centimeter
+        image
+            .metadata_mut()
+            .set_physical_dimensions(PhysicalDimensions::new(11811.0, 5906.0, DensityUnit::Meter));
+
+        let dims = read_back(&image).unwrap();
+        assert_eq!(dims.unit, DensityUnit::Centimeter);
+        assert_eq!((dims.x_density, dims.y_density), (118.0, 59.0));
+    }
+
+    #[test]
+    fn test_reduce_pixels() {
+        // 3x2 gray image, scaled by 2 with partial blocks on the right
+        let pixels = [10, 20, 30, 30, 40, 50];
+        let options = JpegDecodeOptions::new().set_scale(2);
+
+        let (output, width, height) = reduce_pixels(&pixels, (3, 2), 1, options).unwrap();
+
+        assert_eq!((width, height), (2, 1));
+        assert_eq!(output, [25, 40]);
+
+        let options = JpegDecodeOptions::new().set_region(1, 1, 5, 5);
+        let (output, width, height) = reduce_pixels(&pixels, (3, 2), 1, options).unwrap();
+
+        assert_eq!((width, height), (2, 1));
+        assert_eq!(output, [40, 50]);
+
+        let options = JpegDecodeOptions::new().set_region(3, 0, 1, 1);
+        assert!(reduce_pixels(&pixels, (3, 2), 1, options).is_err());
+    }
+
+    #[test]
+    fn test_reduced_decode() {
+        let image = Image::fill(100_u8, ColorSpace::RGB, 40, 24);
+        let jpeg = image.write_to_vec(ImageFormat::JPEG).unwrap();
+
+        let decode = |options: JpegDecodeOptions| {
+            let options = DecoderOptions::default().jpeg_set_decode_options(options);
+            Image::read(&jpeg, options).unwrap()
+        };
+
+        let scaled = decode(JpegDecodeOptions::new().set_scale(4));
+        assert_eq!(scaled.dimensions(), (10, 6));
+        assert_eq!(scaled.metadata().get_dimensions(), (10, 6));
+
+        let region = decode(
+            JpegDecodeOptions::new()
+                .set_region(8, 8, 16, 8)
+                .set_scale(2)
+        );
+        assert_eq!(region.dimensions(), (8, 4));
+
+        let thumbnail = decode(JpegDecodeOptions::new().set_min_size(12));
+        assert_eq!(thumbnail.dimensions(), (20, 12));
+        assert!(thumbnail.flatten_to_u8()[0]
+            .iter()
+            .all(|x| x.abs_diff(100) <= 2));
+    }
+}
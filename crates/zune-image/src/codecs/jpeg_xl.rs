@@ -68,7 +68,7 @@ impl EncoderTrait for JxlEncoder {
     fn encode_inner(&mut self, image: &Image) -> Result<Vec<u8>, ImageErrors> {
         let options = create_options_for_encoder(self.options, image);
 
-        let data = &image.to_u8()[0];
+        let data = &image.to_u8_native()[0];
 
         let encoder = JxlSimpleEncoder::new(data, options);
 
@@ -98,7 +98,9 @@ impl EncoderTrait for JxlEncoder {
 
     fn default_depth(&self, depth: BitDepth) -> BitDepth {
         match depth {
-            BitDepth::Sixteen | BitDepth::Float32 => BitDepth::Sixteen,
+            BitDepth::Ten | BitDepth::Twelve | BitDepth::Sixteen | BitDepth::Float32 => {
+                BitDepth::Sixteen
+            }
             _ => BitDepth::Eight
         }
     }
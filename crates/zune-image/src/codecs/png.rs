@@ -17,6 +17,7 @@ use zune_core::colorspace::ColorSpace;
 use zune_core::log::warn;
 use zune_core::options::EncoderOptions;
 use zune_core::result::DecodingResult;
+use zune_core::timings::{DecodeTimings, StageTimer};
 pub use zune_png::*;
 
 use crate::codecs::{create_options_for_encoder, ImageFormat};
@@ -24,14 +25,21 @@ use crate::errors::ImageErrors;
 use crate::errors::ImageErrors::ImageDecodeErrors;
 use crate::frame::Frame;
 use crate::image::Image;
-use crate::metadata::ImageMetadata;
-use crate::traits::{DecoderTrait, EncoderTrait};
+use crate::metadata::{DensityUnit, ImageMetadata};
+use crate::pipelines::fused_image;
+use crate::traits::{DecoderTrait, EncoderTrait, OperationsTrait};
 
 impl<T> DecoderTrait<T> for PngDecoder<T>
 where
     T: ZReaderTrait
 {
     fn decode(&mut self) -> Result<Image, ImageErrors> {
+        DecoderTrait::decode_with(self, &[]).map(|(image, _)| image)
+    }
+
+    fn decode_with(
+        &mut self, operations: &[&dyn OperationsTrait]
+    ) -> Result<(Image, usize), ImageErrors> {
         let metadata = self.read_headers()?.unwrap();
 
         let depth = self.get_depth().unwrap();
@@ -70,7 +78,9 @@ where
                         )?;
                         let duration = f64::from(frame.delay_num) / f64::from(frame.delay_denom);
                         // then build a frame from that
+                        let timer = StageTimer::start(self.get_options().get_collect_timings());
                         let im_frame = Frame::from_u8(&output, colorspace, usize::from(frame.delay_num),usize::from(frame.delay_denom));
+                        self.timings_mut().record("deinterleave", timer);
                         output_frames.push(im_frame);
                     }
                     _ => return Err(ImageDecodeErrors("The current image is an  Animated PNG but has a depth of 16, such an image isn't supported".to_string()))
@@ -78,22 +88,38 @@ where
             }
             let mut image = Image::new_frames(output_frames, depth, width, height, colorspace);
             image.metadata = metadata;
+            image.metadata.truncated = self.is_truncated();
 
-            Ok(image)
+            Ok((image, 0))
         } else {
-            let pixels = self
-                .decode()
-                .map_err(<error::PngDecodeErrors as Into<ImageErrors>>::into)?;
+            let pixels = self.decode().map_err(|err| located_error(self, err))?;
 
-            let mut image = match pixels {
-                DecodingResult::U8(data) => Image::from_u8(&data, width, height, colorspace),
-                DecodingResult::U16(data) => Image::from_u16(&data, width, height, colorspace),
+            let timer = StageTimer::start(self.get_options().get_collect_timings());
+            let (mut image, ran) = match pixels {
+                DecodingResult::U8(data) => {
+                    fused_image(data, width, height, colorspace, operations)?
+                }
+                DecodingResult::U16(data) => match significant_depth(self) {
+                    Some(depth) => {
+                        // 10 and 12 bit samples were scaled to 16 bits by the encoder, undo that
+                        let shift = 16 - depth.significant_bits();
+                        let data: Vec<u16> = data.iter().map(|x| x >> shift).collect();
+
+                        (Image::from_u16(&data, width, height, colorspace), 0)
+                    }
+                    None => (Image::from_u16(&data, width, height, colorspace), 0)
+                },
                 _ => unreachable!()
             };
+            self.timings_mut().record("deinterleave", timer);
+            // operations that ran may have changed the colorspace
+            let colorspace = image.colorspace();
             // metadata
             image.metadata = metadata;
+            image.metadata.colorspace = colorspace;
+            image.metadata.truncated = self.is_truncated();
 
-            Ok(image)
+            Ok((image, ran))
         }
     }
     fn dimensions(&self) -> Option<(usize, usize)> {
@@ -109,11 +135,10 @@ where
     }
 
     fn read_headers(&mut self) -> Result<Option<ImageMetadata>, crate::errors::ImageErrors> {
-        self.decode_headers()
-            .map_err(<error::PngDecodeErrors as Into<ImageErrors>>::into)?;
+        self.decode_headers().map_err(|err| located_error(self, err))?;
 
         let (width, height) = self.get_dimensions().unwrap();
-        let depth = self.get_depth().unwrap();
+        let depth = significant_depth(self).unwrap_or(self.get_depth().unwrap());
 
         let mut metadata = ImageMetadata {
             format: Some(ImageFormat::PNG),
@@ -122,26 +147,89 @@ where
             width: width,
             height: height,
             default_gamma: self.get_info().unwrap().gamma,
+            significant_bits: self.get_info().unwrap().significant_bits.clone(),
             ..Default::default()
         };
+        let info = self.get_info().unwrap();
+
         #[cfg(feature = "metadata")]
         {
-            let info = self.get_info().unwrap();
             // see if we have an exif chunk
             if let Some(exif) = &info.exif {
                 metadata.parse_raw_exif(exif)
             }
         }
+        metadata.icc_chunk = info.icc_profile.clone();
+        metadata.physical_dimensions = info.physical_dimensions.map(|dims| {
+            let unit = if dims.unit_is_meter { DensityUnit::Meter } else { DensityUnit::Unknown };
+
+            crate::metadata::PhysicalDimensions::new(
+                dims.x_pixels_per_unit as f32,
+                dims.y_pixels_per_unit as f32,
+                unit
+            )
+        });
+
+        let text = info.text_chunk.iter().map(|x| (&x.keyword, &x.text));
+        let ztxt = info.ztxt_chunk.iter().map(|x| (&x.keyword, &x.text));
+        let itxt = info.itxt_chunk.iter().map(|x| (&x.keyword, &x.text));
+
+        for (key, value) in text.chain(ztxt).chain(itxt) {
+            metadata.add_text_chunk(
+                String::from_utf8_lossy(key).to_string(),
+                String::from_utf8_lossy(value).to_string()
+            );
+        }
 
         Ok(Some(metadata))
     }
+
+    fn timings(&self) -> DecodeTimings {
+        self.timings().clone()
+    }
+}
+
+/// Return the depth of 16 bit images holding 10 or 12 bit samples, if the caller
+/// asked to decode them to that depth
+///
+/// Not every image operation handles such depths, so this is opt in
+fn significant_depth<T: ZReaderTrait>(decoder: &PngDecoder<T>) -> Option<BitDepth> {
+    if decoder.get_options().png_get_decode_significant_depth() {
+        decoder.get_significant_depth()
+    } else {
+        None
+    }
+}
+
+/// Convert `err` returned by `decoder`, adding where in the stream it occurred
+fn located_error<T: ZReaderTrait>(
+    decoder: &PngDecoder<T>, err: error::PngDecodeErrors
+) -> ImageErrors {
+    let location = decoder.error_location();
+    let err = ImageErrors::from(err);
+
+    match (err, location) {
+        (ImageErrors::ImageDecodeErrors(err), Some(location)) => {
+            ImageErrors::ImageDecodeErrors(format!("{}, at {location}", err.trim_end()))
+        }
+        (ImageErrors::ImageDecodeErrorsWithContext(err, context), Some(location)) => {
+            ImageErrors::ImageDecodeErrorsWithContext(
+                format!("{}, at {location}", err.trim_end()),
+                context
+            )
+        }
+        (err, _) => err
+    }
 }
 
 impl From<zune_png::error::PngDecodeErrors> for ImageErrors {
     fn from(from: zune_png::error::PngDecodeErrors) -> Self {
         let err = format!("png: {from:?}");
 
-        ImageErrors::ImageDecodeErrors(err)
+        match from.context() {
+            Some(context) => ImageErrors::ImageDecodeErrorsWithContext(err, context.clone()),
+            None => ImageErrors::ImageDecodeErrors(err)
+        }
     }
 }
 
@@ -175,6 +263,30 @@ impl EncoderTrait for PngEncoder {
 
         let mut buf = std::io::Cursor::new(vec![]);
 
+        if let Some(gamma) = image.metadata.default_gamma() {
+            encoder.set_gamma(gamma);
+        }
+        if let Some(dims) = image.metadata.physical_dimensions() {
+            // png only stores pixels per meter, or the aspect ratio
+            let (x, y) = dims
+                .density_in(DensityUnit::Meter)
+                .unwrap_or((dims.x_density, dims.y_density));
+
+            encoder.set_physical_dimensions(PhysicalDimensions {
+                x_pixels_per_unit: x.round() as u32,
+                y_pixels_per_unit: y.round() as u32,
+                unit_is_meter:     dims.unit != DensityUnit::Unknown
+            });
+        }
+        if !options.strip_metadata() {
+            for (key, value) in image.metadata.text_chunks() {
+                encoder.add_text_chunk(key, value);
+            }
+            if let Some(icc) = image.metadata.icc_chunk() {
+                encoder.set_icc_profile(icc);
+            }
+        }
+
         #[cfg(feature = "metadata")]
         {
             use exif::experimental::Writer;
@@ -212,7 +324,12 @@ impl EncoderTrait for PngEncoder {
     }
 
     fn supported_bit_depth(&self) -> &'static [BitDepth] {
-        &[BitDepth::Eight, BitDepth::Sixteen]
+        &[
+            BitDepth::Eight,
+            BitDepth::Ten,
+            BitDepth::Twelve,
+            BitDepth::Sixteen
+        ]
     }
 
     fn default_depth(&self, depth: BitDepth) -> BitDepth {
@@ -224,4 +341,108 @@ impl EncoderTrait for PngEncoder {
     fn set_options(&mut self, opts: EncoderOptions) {
         self.options = Some(opts)
     }
+    fn supports_exif(&self) -> bool {
+        cfg!(feature = "metadata")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use zune_core::bit_depth::BitDepth;
+    use zune_core::colorspace::ColorSpace;
+    use zune_core::options::DecoderOptions;
+
+    use crate::codecs::ImageFormat;
+    use crate::image::Image;
+    use crate::metadata::{DensityUnit, PhysicalDimensions};
+
+    #[test]
+    fn test_twelve_bit_round_trip() {
+        let pixels: Vec<u16> = (0..4096).collect();
+        let mut image = Image::from_u16(&pixels, 64, 64, ColorSpace::Luma);
+        image.set_depth(BitDepth::Twelve);
+
+        let png = image.write_to_vec(ImageFormat::PNG).unwrap();
+        let options = DecoderOptions::default().png_set_decode_significant_depth(true);
+        let decoded = Image::read(&png, options).unwrap();
+
+        assert_eq!(decoded.depth(), BitDepth::Twelve);
+        assert_eq!(decoded.metadata().get_significant_bits(), Some(&[12][..]));
+        assert_eq!(
+            decoded.channels_ref(false)[0]
+                .reinterpret_as::<u16>()
+                .unwrap(),
+            &pixels
+        );
+    }
+
+    #[test]
+    fn test_twelve_bit_decodes_as_sixteen() {
+        let pixels: Vec<u16> = (0..4096).collect();
+        let mut image = Image::from_u16(&pixels, 64, 64, ColorSpace::Luma);
+        image.set_depth(BitDepth::Twelve);
+
+        let png = image.write_to_vec(ImageFormat::PNG).unwrap();
+        let decoded = Image::read(&png, DecoderOptions::default()).unwrap();
+
+        // operations assume 16 bit samples fill the whole range
+        assert_eq!(decoded.depth(), BitDepth::Sixteen);
+        assert_eq!(decoded.metadata().get_significant_bits(), Some(&[12][..]));
+        assert_eq!(
+            decoded.channels_ref(false)[0]
+                .reinterpret_as::<u16>()
+                .unwrap()
+                .last(),
+            Some(&u16::MAX)
+        );
+    }
+
+    #[test]
+    fn test_metadata_round_trip() {
+        let mut image = Image::fill(128_u8, ColorSpace::RGB, 8, 8);
+        let icc: Vec<u8> = (0..=255).cycle().take(3000).collect();
+
+        image.metadata_mut().set_default_gamma(0.45455);
+        image.metadata_mut().set_icc_chunk(icc.clone());
+        image
+            .metadata_mut()
+            .add_text_chunk("Title".to_string(), "Archive".to_string());
+        image
+            .metadata_mut()
+            .set_physical_dimensions(PhysicalDimensions::from_dpi(300.0, 300.0));
+
+        let png = image.write_to_vec(ImageFormat::PNG).unwrap();
+        let decoded = Image::read(&png, DecoderOptions::default()).unwrap();
+        let metadata = decoded.metadata();
+
+        assert_eq!(metadata.default_gamma(), Some(0.45455));
+        assert_eq!(metadata.icc_chunk(), Some(&icc[..]));
+        assert_eq!(
+            metadata.text_chunks(),
+            &[("Title".to_string(), "Archive".to_string())]
+        );
+        // stored per meter
+        let dims = metadata.physical_dimensions().unwrap();
+        let (x, y) = dims.dpi().unwrap();
+
+        assert_eq!(dims.unit, DensityUnit::Meter);
+        assert_eq!((dims.x_density, dims.y_density), (11811.0, 11811.0));
+        assert!((x - 300.0).abs() < 0.01 && (y - 300.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_error_location_in_message() {
+        let image = Image::fill(7_u8, ColorSpace::RGB, 8, 8);
+        let mut png = image.write_to_vec(ImageFormat::PNG).unwrap();
+        // corrupt the bit depth in IHDR
+        png[24] = 3;
+
+        let Err(err) = Image::read(&png, DecoderOptions::default()) else {
+            panic!("Decoded a png with a bit depth of 3");
+        };
+        assert!(
+            format!("{err:?}").contains("in the IHDR chunk starting at 0x8"),
+            "{err:?}"
+        );
+    }
 }
@@ -8,7 +8,9 @@
 
 #![cfg(feature = "ppm")]
 //! Represents a PPM and PAL image encoder
-use zune_core::bit_depth::BitDepth;
+use std::io::Write;
+
+use zune_core::bit_depth::{BitDepth, ByteEndian};
 use zune_core::bytestream::ZReaderTrait;
 use zune_core::colorspace::ColorSpace;
 use zune_core::options::EncoderOptions;
@@ -19,7 +21,8 @@ use crate::codecs::{create_options_for_encoder, ImageFormat};
 use crate::errors::{ImageErrors, ImgEncodeErrors};
 use crate::image::Image;
 use crate::metadata::ImageMetadata;
-use crate::traits::{DecoderTrait, EncoderTrait};
+use crate::pipelines::fused_image;
+use crate::traits::{DecoderTrait, EncoderTrait, OperationsTrait};
 
 #[derive(Copy, Clone, Default)]
 pub struct PPMEncoder {
@@ -45,7 +48,7 @@ impl EncoderTrait for PPMEncoder {
     fn encode_inner(&mut self, image: &Image) -> Result<Vec<u8>, ImageErrors> {
         let options = create_options_for_encoder(self.options, image);
 
-        let data = &image.to_u8()[0];
+        let data = &image.to_u8_native()[0];
 
         let ppm_encoder = PPMEnc::new(data, options);
 
@@ -56,6 +59,20 @@ impl EncoderTrait for PPMEncoder {
         Ok(data)
     }
 
+    fn encode_inner_to(
+        &mut self, image: &Image, writer: &mut dyn Write
+    ) -> Result<(), ImageErrors> {
+        let options = create_options_for_encoder(self.options, image);
+
+        let header = PPMEnc::new(&[], options)
+            .encode_header()
+            .map_err(<PPMEncodeErrors as Into<ImgEncodeErrors>>::into)?;
+
+        writer.write_all(&header)?;
+        // samples follow the header as is, 16 bit ones in big endian
+        image.frames_ref()[0].write_interleaved(image.colorspace(), ByteEndian::BE, writer)
+    }
+
     fn supported_colorspaces(&self) -> &'static [ColorSpace] {
         &[
             ColorSpace::RGB,  // p7
@@ -78,7 +95,9 @@ impl EncoderTrait for PPMEncoder {
     /// Float32 types, they are converted to Float16 types
     fn default_depth(&self, depth: BitDepth) -> BitDepth {
         match depth {
-            BitDepth::Float32 | BitDepth::Sixteen => BitDepth::Sixteen,
+            BitDepth::Float32 | BitDepth::Ten | BitDepth::Twelve | BitDepth::Sixteen => {
+                BitDepth::Sixteen
+            }
             _ => BitDepth::Eight
         }
     }
@@ -92,23 +111,29 @@ where
     T: ZReaderTrait
 {
     fn decode(&mut self) -> Result<Image, ImageErrors> {
+        DecoderTrait::decode_with(self, &[]).map(|(image, _)| image)
+    }
+
+    fn decode_with(
+        &mut self, operations: &[&dyn OperationsTrait]
+    ) -> Result<(Image, usize), ImageErrors> {
         let pixels = self.decode()?;
 
         let depth = self.get_bit_depth().unwrap();
         let (width, height) = self.get_dimensions().unwrap();
         let colorspace = self.get_colorspace().unwrap();
 
-        let mut image = match pixels {
-            DecodingResult::U8(data) => Image::from_u8(&data, width, height, colorspace),
-            DecodingResult::U16(data) => Image::from_u16(&data, width, height, colorspace),
-            DecodingResult::F32(data) => Image::from_f32(&data, width, height, colorspace),
+        let (mut image, ran) = match pixels {
+            DecodingResult::U8(data) => fused_image(data, width, height, colorspace, operations)?,
+            DecodingResult::U16(data) => (Image::from_u16(&data, width, height, colorspace), 0),
+            DecodingResult::F32(data) => (Image::from_f32(&data, width, height, colorspace), 0),
             _ => unreachable!()
         };
 
         // set metadata details
         image.metadata.format = Some(ImageFormat::PPM);
 
-        Ok(image)
+        Ok((image, ran))
     }
 
     fn dimensions(&self) -> Option<(usize, usize)> {
@@ -21,13 +21,20 @@ use crate::codecs::{create_options_for_encoder, ImageFormat};
 use crate::errors::{ImageErrors, ImgEncodeErrors};
 use crate::image::Image;
 use crate::metadata::ImageMetadata;
-use crate::traits::{DecodeInto, DecoderTrait, EncoderTrait};
+use crate::pipelines::fused_image;
+use crate::traits::{DecodeInto, DecoderTrait, EncoderTrait, OperationsTrait};
 
 impl<T> DecoderTrait<T> for QoiDecoder<T>
 where
     T: ZReaderTrait
 {
     fn decode(&mut self) -> Result<Image, ImageErrors> {
+        DecoderTrait::decode_with(self, &[]).map(|(image, _)| image)
+    }
+
+    fn decode_with(
+        &mut self, operations: &[&dyn OperationsTrait]
+    ) -> Result<(Image, usize), ImageErrors> {
         let pixels = self.decode()?;
         // safe because these are none when we haven't decoded.
         let colorspace = self.get_colorspace().unwrap();
@@ -35,12 +42,12 @@ where
 
         let depth = self.get_bit_depth();
 
-        let mut image = Image::from_u8(&pixels, width, height, colorspace);
+        let (mut image, ran) = fused_image(pixels, width, height, colorspace, operations)?;
 
         // set metadata details
         image.metadata.format = Some(ImageFormat::QOI);
 
-        Ok(image)
+        Ok((image, ran))
     }
 
     fn dimensions(&self) -> Option<(usize, usize)> {
@@ -104,7 +111,7 @@ impl EncoderTrait for QoiEncoder {
     fn encode_inner(&mut self, image: &Image) -> Result<Vec<u8>, ImageErrors> {
         let options = create_options_for_encoder(self.options, image);
 
-        let data = &image.to_u8()[0];
+        let data = &image.to_u8_native()[0];
 
         let mut qoi_encoder = zune_qoi::QoiEncoder::new(data, options);
 
@@ -0,0 +1,522 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! Conversions between planar and interleaved pixel buffers
+//!
+//! The library stores every channel in its own plane (`RRRR`,`GGGG`,`BBBB`),
+//! while most other libraries exchange interleaved buffers (`RGBRGBRGB`) whose rows
+//! may be padded, e.g OpenCV `Mat`s with a step or GPU staging buffers with a pitch.
+//!
+//! [`interleave`] and [`deinterleave_with_stride`] convert between the two, handling
+//! the row padding so that callers don't need per row loops.
+//!
+//! Strides are in elements of the buffer type, not bytes, a byte pitch of a
+//! `u16` buffer must be divided by two.
+//!
+//! The last row of an interleaved buffer doesn't need padding, some APIs
+//! omit it.
+//!
+//! Images can also be built straight from such buffers, see
+//! [`Image::from_slice_with_stride`] and [`Image::from_raw_parts`].
+//!
+//! # Example
+//! ```
+//! use zune_core::colorspace::ColorSpace;
+//! use zune_image::convert::{deinterleave_with_stride, interleave};
+//! use zune_image::errors::ImageErrors;
+//! use zune_image::image::Image;
+//!
+//! // a 2x2 RGB image
+//! let image = Image::from_u8(&[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12], 2, 2, ColorSpace::RGB);
+//!
+//! let channels = image.frames_ref()[0].channels_ref(ColorSpace::RGB, false);
+//! let planes = channels
+//!     .iter()
+//!     .map(|x| x.reinterpret_as::<u8>())
+//!     .collect::<Result<Vec<_>, _>>()?;
+//!
+//! // rows of 2 pixels padded to 8 bytes
+//! let mut padded = vec![0; 16];
+//! interleave(&planes, 2, 8, &mut padded)?;
+//! assert_eq!(padded, [1, 2, 3, 4, 5, 6, 0, 0, 7, 8, 9, 10, 11, 12, 0, 0]);
+//!
+//! // and back
+//! let (mut r, mut g, mut b) = (vec![0; 4], vec![0; 4], vec![0; 4]);
+//! deinterleave_with_stride(&padded, 2, 8, &mut [&mut r, &mut g, &mut b])?;
+//! assert_eq!(r, [1, 4, 7, 10]);
+//! # Ok::<(),ImageErrors>(())
+//! ```
+use std::mem::size_of;
+
+use bytemuck::Pod;
+use zune_core::bit_depth::{BitDepth, BitType};
+use zune_core::colorspace::ColorSpace;
+
+use crate::channel::Channel;
+use crate::errors::ImageErrors;
+use crate::image::Image;
+
+/// Layout of a pixel in a buffer owned by another library
+///
+/// Used by [`Image::from_slice_with_stride`] and [`Image::from_raw_parts`].
+/// 16 bit and float samples are in native endian.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum PixelFormat {
+    /// 8 bit grayscale
+    Luma8,
+    /// 8 bit grayscale with alpha
+    LumaA8,
+    /// 8 bit red, green, blue
+    Rgb8,
+    /// 8 bit red, green, blue, alpha
+    Rgba8,
+    /// 8 bit blue, green, red, e.g Windows DIBs
+    Bgr8,
+    /// 8 bit blue, green, red, alpha
+    Bgra8,
+    /// 8 bit blue, green, red and an unused byte, e.g most screen capture APIs
+    ///
+    /// The unused byte is dropped, images are [`ColorSpace::BGR`]
+    Bgrx8,
+    /// 8 bit red, green, blue and an unused byte
+    ///
+    /// The unused byte is dropped, images are [`ColorSpace::RGB`]
+    Rgbx8,
+    /// 8 bit alpha, red, green, blue
+    Argb8,
+    /// 16 bit grayscale
+    Luma16,
+    /// 16 bit red, green, blue
+    Rgb16,
+    /// 16 bit red, green, blue, alpha
+    Rgba16,
+    /// Float grayscale
+    LumaF32,
+    /// Float red, green, blue
+    RgbF32,
+    /// Float red, green, blue, alpha
+    RgbaF32
+}
+
+impl PixelFormat {
+    /// Colorspace of images made from this format
+    pub const fn colorspace(self) -> ColorSpace {
+        match self {
+            Self::Luma8 | Self::Luma16 | Self::LumaF32 => ColorSpace::Luma,
+            Self::LumaA8 => ColorSpace::LumaA,
+            Self::Rgb8 | Self::Rgbx8 | Self::Rgb16 | Self::RgbF32 => ColorSpace::RGB,
+            Self::Rgba8 | Self::Rgba16 | Self::RgbaF32 => ColorSpace::RGBA,
+            Self::Bgr8 | Self::Bgrx8 => ColorSpace::BGR,
+            Self::Bgra8 => ColorSpace::BGRA,
+            Self::Argb8 => ColorSpace::ARGB
+        }
+    }
+
+    /// Depth of images made from this format
+    pub const fn depth(self) -> BitDepth {
+        match self {
+            Self::Luma16 | Self::Rgb16 | Self::Rgba16 => BitDepth::Sixteen,
+            Self::LumaF32 | Self::RgbF32 | Self::RgbaF32 => BitDepth::Float32,
+            _ => BitDepth::Eight
+        }
+    }
+
+    /// Number of samples in a pixel, including unused ones
+    pub const fn samples_per_pixel(self) -> usize {
+        match self {
+            Self::Bgrx8 | Self::Rgbx8 => 4,
+            _ => self.colorspace().num_components()
+        }
+    }
+
+    /// Number of bytes in a pixel, including unused samples
+    pub const fn bytes_per_pixel(self) -> usize {
+        self.samples_per_pixel() * self.depth().size_of()
+    }
+}
+
+/// Check the layout of a foreign buffer, returning the number of bytes it spans
+fn buffer_len(
+    width: usize, height: usize, stride: usize, format: PixelFormat
+) -> Result<usize, ImageErrors> {
+    if width == 0 || height == 0 {
+        return Err(ImageErrors::GenericStr("Image dimensions cannot be zero"));
+    }
+    let overflow = || ImageErrors::GenericStr("Image dimensions overflow a usize");
+    let row = width
+        .checked_mul(format.bytes_per_pixel())
+        .ok_or_else(overflow)?;
+
+    if stride < row {
+        return Err(ImageErrors::GenericString(format!(
+            "Stride {stride} is smaller than a row of {width} {format:?} pixels ({row} bytes)"
+        )));
+    }
+    // the last row needs no padding
+    stride
+        .checked_mul(height - 1)
+        .and_then(|x| x.checked_add(row))
+        .ok_or_else(overflow)
+}
+
+/// Fill planar channels from bytes of interleaved pixels whose rows are `stride` bytes apart
+///
+/// The first `channels.len()` samples of every pixel are kept.
+fn deinterleave_bytes<T: Pod>(
+    pixels: &[u8], width: usize, stride: usize, format: PixelFormat, channels: &mut [&mut [T]]
+) {
+    let pixel_size = format.bytes_per_pixel();
+
+    // reinterpreting only works on aligned buffers without unused samples,
+    // otherwise fall back to copying samples one by one
+    if format.samples_per_pixel() == channels.len() && stride.is_multiple_of(size_of::<T>()) {
+        let whole_samples = pixels.len() / size_of::<T>() * size_of::<T>();
+
+        if let Ok(samples) = bytemuck::try_cast_slice::<u8, T>(&pixels[..whole_samples]) {
+            let stride = stride / size_of::<T>();
+
+            if deinterleave_with_stride(samples, width, stride, channels).is_ok() {
+                return;
+            }
+        }
+    }
+    for (y, row) in pixels.chunks(stride).enumerate() {
+        let row = &row[..width * pixel_size];
+
+        for (x, pixel) in row.chunks_exact(pixel_size).enumerate() {
+            for (c, channel) in channels.iter_mut().enumerate() {
+                let sample = &pixel[c * size_of::<T>()..(c + 1) * size_of::<T>()];
+
+                channel[y * width + x] = bytemuck::pod_read_unaligned(sample);
+            }
+        }
+    }
+}
+
+/// Constructors from buffers owned by other libraries
+impl Image {
+    /// Create an image from interleaved pixels whose rows are `stride` bytes apart
+    ///
+    /// This wraps buffers from frame grabbers, screen capture APIs and other libraries
+    /// as they are, rows may be padded and channels in BGR order, and the last row
+    /// doesn't need padding. The pixels are copied, the buffer isn't kept.
+    ///
+    /// # Arguments
+    /// - pixels: The interleaved buffer
+    /// - width, height: Image dimensions in pixels
+    /// - stride: Distance between the start of two rows, in bytes,
+    ///   at least `width * format.bytes_per_pixel()`
+    /// - format: Layout of a pixel
+    ///
+    /// # Errors
+    /// If a dimension is zero, the stride is smaller than a row or `pixels` is too small
+    ///
+    /// # Example
+    /// ```
+    /// use zune_core::colorspace::ColorSpace;
+    /// use zune_image::convert::PixelFormat;
+    /// use zune_image::image::Image;
+    ///
+    /// // a 2x2 BGRX capture with rows padded to 12 bytes
+    /// let capture = [
+    ///     3, 2, 1, 0, 6, 5, 4, 0, 0, 0, 0, 0, //
+    ///     9, 8, 7, 0, 12, 11, 10, 0
+    /// ];
+    /// let image = Image::from_slice_with_stride(&capture, 2, 2, 12, PixelFormat::Bgrx8).unwrap();
+    ///
+    /// assert_eq!(image.colorspace(), ColorSpace::BGR);
+    /// assert_eq!(image.flatten_to_u8()[0], [3, 2, 1, 6, 5, 4, 9, 8, 7, 12, 11, 10]);
+    /// ```
+    pub fn from_slice_with_stride(
+        pixels: &[u8], width: usize, height: usize, stride: usize, format: PixelFormat
+    ) -> Result<Image, ImageErrors> {
+        let expected = buffer_len(width, height, stride, format)?;
+
+        if pixels.len() < expected {
+            return Err(ImageErrors::GenericString(format!(
+                "Buffer of length {} too small for a {width}x{height} image with a stride of {stride}, expected at least {expected}",
+                pixels.len()
+            )));
+        }
+        let pixels = &pixels[..expected];
+        let colorspace = format.colorspace();
+        let depth = format.depth();
+        let plane_size = width * height * depth.size_of();
+
+        let mut channels = vec![
+            Channel::new_with_bit_type(plane_size, depth.bit_type());
+            colorspace.num_components()
+        ];
+
+        match depth.bit_type() {
+            BitType::U8 => {
+                let mut planes = channels
+                    .iter_mut()
+                    .map(Channel::reinterpret_as_mut::<u8>)
+                    .collect::<Result<Vec<_>, _>>()?;
+                deinterleave_bytes(pixels, width, stride, format, &mut planes);
+            }
+            BitType::U16 => {
+                let mut planes = channels
+                    .iter_mut()
+                    .map(Channel::reinterpret_as_mut::<u16>)
+                    .collect::<Result<Vec<_>, _>>()?;
+                deinterleave_bytes(pixels, width, stride, format, &mut planes);
+            }
+            BitType::F32 => {
+                let mut planes = channels
+                    .iter_mut()
+                    .map(Channel::reinterpret_as_mut::<f32>)
+                    .collect::<Result<Vec<_>, _>>()?;
+                deinterleave_bytes(pixels, width, stride, format, &mut planes);
+            }
+            d => {
+                return Err(ImageErrors::ImageOperationNotImplemented(
+                    "from_slice_with_stride",
+                    d
+                ))
+            }
+        }
+        Ok(Image::new(channels, depth, width, height, colorspace))
+    }
+
+    /// Create an image from a pointer to interleaved pixels whose rows are `stride` bytes apart
+    ///
+    /// This is [`from_slice_with_stride`](Self::from_slice_with_stride) for buffers
+    /// handed over through FFI, prefer it when a slice is available.
+    ///
+    /// # Errors
+    /// If `ptr` is null, a dimension is zero, the stride is smaller than a row
+    /// or the dimensions overflow
+    ///
+    /// # Safety
+    /// `ptr` must be valid for reads of `stride * (height - 1) + width * format.bytes_per_pixel()`
+    /// bytes, and the memory must not be written to during the call.
+    pub unsafe fn from_raw_parts(
+        ptr: *const u8, width: usize, height: usize, stride: usize, format: PixelFormat
+    ) -> Result<Image, ImageErrors> {
+        if ptr.is_null() {
+            return Err(ImageErrors::GenericStr("Null pixel pointer"));
+        }
+        let length = buffer_len(width, height, stride, format)?;
+        // Safety: the caller guarantees `ptr` is readable for `length` bytes
+        let pixels = unsafe { std::slice::from_raw_parts(ptr, length) };
+
+        Image::from_slice_with_stride(pixels, width, height, stride, format)
+    }
+}
+
+/// Check plane and buffer sizes, returning the number of rows
+fn confirm_layout(
+    planes: &[usize], width: usize, stride: usize, interleaved: usize
+) -> Result<usize, ImageErrors> {
+    let Some(plane_len) = planes.first().copied() else {
+        return Err(ImageErrors::GenericStr("Expected at least one channel"));
+    };
+    if planes.iter().any(|x| *x != plane_len) {
+        return Err(ImageErrors::GenericStr("Channels have different lengths"));
+    }
+    if width == 0 || plane_len % width != 0 {
+        return Err(ImageErrors::GenericString(format!(
+            "Channel length {plane_len} is not a multiple of the width {width}"
+        )));
+    }
+    let row = width * planes.len();
+
+    if stride < row {
+        return Err(ImageErrors::GenericString(format!(
+            "Stride {stride} is smaller than a row of {width} pixels with {} channels ({row})",
+            planes.len()
+        )));
+    }
+    let height = plane_len / width;
+    // the last row needs no padding
+    let expected = stride * height.saturating_sub(1) + row * usize::from(height > 0);
+
+    if interleaved < expected {
+        return Err(ImageErrors::GenericString(format!(
+            "Interleaved buffer of length {interleaved} too small, expected at least {expected}"
+        )));
+    }
+    Ok(height)
+}
+
+/// Interleave planar channels into a buffer whose rows are `stride` elements apart
+///
+/// Padding between rows is left untouched.
+///
+/// # Arguments
+/// - channels: One plane per channel, all of the same length
+/// - width: Image width in pixels
+/// - stride: Distance between the start of two rows of `out`, in elements,
+///   at least `width * channels.len()`
+/// - out: The interleaved buffer
+///
+/// # Errors
+/// If channels have different lengths, their length is not a multiple of `width`,
+/// the stride is smaller than a row or `out` is too small
+pub fn interleave<T: Copy>(
+    channels: &[&[T]], width: usize, stride: usize, out: &mut [T]
+) -> Result<(), ImageErrors> {
+    let lengths: Vec<usize> = channels.iter().map(|x| x.len()).collect();
+    let height = confirm_layout(&lengths, width, stride, out.len())?;
+    let components = channels.len();
+
+    for (y, row) in out.chunks_mut(stride).take(height).enumerate() {
+        let start = y * width;
+        let row = &mut row[..width * components];
+
+        if let [channel] = channels {
+            row.copy_from_slice(&channel[start..start + width]);
+            continue;
+        }
+        for (c, channel) in channels.iter().enumerate() {
+            let values = &channel[start..start + width];
+
+            for (pixel, value) in row.chunks_exact_mut(components).zip(values) {
+                pixel[c] = *value;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Separate a buffer of interleaved pixels whose rows are `stride` elements apart
+/// into planar channels
+///
+/// Padding between rows is ignored.
+///
+/// # Arguments
+/// - interleaved: The interleaved buffer
+/// - width: Image width in pixels
+/// - stride: Distance between the start of two rows of `interleaved`, in elements,
+///   at least `width * channels.len()`
+/// - channels: One plane per channel, all of the same length, the number of
+///   channels is the number of components of a pixel
+///
+/// # Errors
+/// If channels have different lengths, their length is not a multiple of `width`,
+/// the stride is smaller than a row or `interleaved` is too small
+pub fn deinterleave_with_stride<T: Copy>(
+    interleaved: &[T], width: usize, stride: usize, channels: &mut [&mut [T]]
+) -> Result<(), ImageErrors> {
+    let lengths: Vec<usize> = channels.iter().map(|x| x.len()).collect();
+    let height = confirm_layout(&lengths, width, stride, interleaved.len())?;
+    let components = channels.len();
+
+    for (y, row) in interleaved.chunks(stride).take(height).enumerate() {
+        let start = y * width;
+        let row = &row[..width * components];
+
+        if let [channel] = channels {
+            channel[start..start + width].copy_from_slice(row);
+            continue;
+        }
+        for (c, channel) in channels.iter_mut().enumerate() {
+            let values = &mut channel[start..start + width];
+
+            for (value, pixel) in values.iter_mut().zip(row.chunks_exact(components)) {
+                *value = pixel[c];
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use zune_core::colorspace::ColorSpace;
+
+    use crate::convert::{deinterleave_with_stride, interleave, PixelFormat};
+    use crate::image::Image;
+
+    #[test]
+    fn test_round_trip_u16() {
+        let luma = [1_u16, 2, 3, 4, 5, 6];
+        let alpha = [10_u16, 20, 30, 40, 50, 60];
+        // 3x2 luma alpha image with a stride of 8, the last row is unpadded
+        let mut interleaved = vec![0_u16; 14];
+
+        interleave(&[&luma, &alpha], 3, 8, &mut interleaved).unwrap();
+        assert_eq!(
+            interleaved,
+            [1, 10, 2, 20, 3, 30, 0, 0, 4, 40, 5, 50, 6, 60]
+        );
+
+        let (mut new_luma, mut new_alpha) = ([0; 6], [0; 6]);
+        deinterleave_with_stride(&interleaved, 3, 8, &mut [&mut new_luma, &mut new_alpha]).unwrap();
+        assert_eq!((new_luma, new_alpha), (luma, alpha));
+    }
+
+    #[test]
+    fn test_single_channel() {
+        let mut out = [9_u8; 5];
+        interleave(&[&[1, 2, 3, 4]], 2, 3, &mut out).unwrap();
+        assert_eq!(out, [1, 2, 9, 3, 4]);
+    }
+
+    #[test]
+    fn test_invalid_layouts() {
+        let plane = [0_u8; 6];
+        let mut out = [0_u8; 32];
+        // stride smaller than a row
+        assert!(interleave(&[&plane, &plane], 3, 5, &mut out).is_err());
+        // length not a multiple of the width
+        assert!(interleave(&[&plane], 4, 4, &mut out).is_err());
+        // mismatched channels
+        assert!(interleave(&[&plane, &plane[..3]], 3, 6, &mut out).is_err());
+        // too small output
+        assert!(interleave(&[&plane, &plane], 3, 8, &mut out[..13]).is_err());
+    }
+
+    #[test]
+    fn test_foreign_buffers() {
+        // 3x2 RGB16 with rows padded to 20 bytes
+        let samples: Vec<u16> = (1..=18).collect();
+        let mut bytes = vec![0_u8; 40];
+
+        for (y, row) in samples.chunks(9).enumerate() {
+            for (x, sample) in row.iter().enumerate() {
+                let start = y * 20 + x * 2;
+                bytes[start..start + 2].copy_from_slice(&sample.to_ne_bytes());
+            }
+        }
+        let expected = Image::from_u16(&samples, 3, 2, ColorSpace::RGB);
+
+        let image = Image::from_slice_with_stride(&bytes, 3, 2, 20, PixelFormat::Rgb16).unwrap();
+        assert_eq!(image.eq_exact(&expected), Ok(()));
+
+        // the same pixels at an odd address are copied sample by sample
+        let mut unaligned = [0_u8; 41];
+        unaligned[1..].copy_from_slice(&bytes);
+        let image =
+            Image::from_slice_with_stride(&unaligned[1..], 3, 2, 20, PixelFormat::Rgb16).unwrap();
+        assert_eq!(image.eq_exact(&expected), Ok(()));
+
+        // and through a pointer
+        let image =
+            unsafe { Image::from_raw_parts(bytes.as_ptr(), 3, 2, 20, PixelFormat::Rgb16) }.unwrap();
+        assert_eq!(image.eq_exact(&expected), Ok(()));
+    }
+
+    #[test]
+    fn test_invalid_foreign_buffers() {
+        let pixels = [0_u8; 16];
+
+        // stride smaller than a row
+        assert!(Image::from_slice_with_stride(&pixels, 2, 2, 7, PixelFormat::Bgra8).is_err());
+        // too small buffer
+        assert!(Image::from_slice_with_stride(&pixels, 2, 3, 6, PixelFormat::Rgb8).is_err());
+        // zero dimensions
+        assert!(Image::from_slice_with_stride(&pixels, 0, 2, 6, PixelFormat::Rgb8).is_err());
+        assert!(unsafe {
+            Image::from_raw_parts(std::ptr::null(), 2, 2, 8, PixelFormat::Bgra8).is_err()
+        });
+    }
+}
@@ -11,3 +11,6 @@
 //! running of images
 pub mod colorspace;
 pub mod depth;
+pub mod metadata;
+pub mod orientation;
+pub mod transfer;
@@ -18,19 +18,22 @@
 //! and then from the intermediate color converts it to the desired colorspace
 //!
 use zune_core::bit_depth::BitType;
-use zune_core::colorspace::{ColorSpace, ALL_COLORSPACES};
+use zune_core::colorspace::ColorSpace;
 
 use crate::core_filters::colorspace::conversion_functions::{
     convert_adding_opaque_alpha, convert_cmyk_to_rgb, convert_hsl_to_rgb, convert_hsv_to_rgb,
     convert_luma_to_rgb, convert_rgb_bgr, convert_rgb_to_argb, convert_rgb_to_cmyk,
     convert_rgb_to_grayscale, convert_rgb_to_hsl, convert_rgb_to_hsv,
-    convert_rgba_to_argb_or_vice_versa, pop_channel
+    convert_rgb_to_ycbcr, convert_rgba_to_argb_or_vice_versa, convert_ycbcr_to_rgb, pop_channel
 };
+pub(crate) use crate::core_filters::colorspace::conversion_functions::flatten_alpha;
+use crate::core_filters::colorspace::grayscale::{LumaWeights, RgbToGrayScale};
 use crate::errors::ImageErrors;
 use crate::image::Image;
 use crate::traits::OperationsTrait;
 
-mod grayscale;
+pub mod expand;
+pub mod grayscale;
 //mod rgb_to_hsl;
 mod rgb_to_xyb;
 
@@ -39,6 +42,25 @@ mod rgb_to_cmyk;
 mod rgb_to_hsl;
 mod rgb_to_hsv;
 mod tests;
+mod ycbcr;
+
+/// Colorspaces [`ColorspaceConv`] can convert images from
+///
+/// YCCK is a decoder internal colorspace, decoders convert it to RGB
+/// before handing out images so it cannot be converted from.
+pub static CONVERTIBLE_COLORSPACES: [ColorSpace; 11] = [
+    ColorSpace::RGB,
+    ColorSpace::RGBA,
+    ColorSpace::LumaA,
+    ColorSpace::Luma,
+    ColorSpace::CMYK,
+    ColorSpace::BGRA,
+    ColorSpace::BGR,
+    ColorSpace::ARGB,
+    ColorSpace::HSL,
+    ColorSpace::HSV,
+    ColorSpace::YCbCr
+];
 
 /// Colorspace conversion filter
 ///
@@ -46,15 +68,31 @@ mod tests;
 /// bit depth, there exists multiple mappings with different colorspace
 ///
 ///
+/// Conversions to grayscale and between RGB and YCbCr weigh red, green and blue
+/// according to [`LumaWeights`], BT.601 by default, see [`set_luma_weights`](Self::set_luma_weights).
+/// YCbCr is full range, as produced by JPEG decoders.
+///
 /// This filter can also be accessed via
 /// [`image.convert_color()`](crate::image::Image::convert_color)
 pub struct ColorspaceConv {
-    to: ColorSpace
+    to:           ColorSpace,
+    luma_weights: LumaWeights
 }
 
 impl ColorspaceConv {
     pub fn new(to: ColorSpace) -> ColorspaceConv {
-        ColorspaceConv { to }
+        ColorspaceConv {
+            to,
+            luma_weights: LumaWeights::default()
+        }
+    }
+    /// Set the luma coefficients used by grayscale and YCbCr conversions
+    ///
+    /// e.g. [`LumaWeights::Bt709`] for high definition content
+    #[must_use]
+    pub fn set_luma_weights(mut self, weights: LumaWeights) -> ColorspaceConv {
+        self.luma_weights = weights;
+        self
     }
 }
 impl OperationsTrait for ColorspaceConv {
@@ -73,14 +111,15 @@ impl OperationsTrait for ColorspaceConv {
         match from {
             ColorSpace::RGB => match self.to {
                 ColorSpace::RGBA => convert_adding_opaque_alpha(image)?,
-                ColorSpace::Luma => convert_rgb_to_grayscale(image, self.to, self.to.has_alpha())?,
-                ColorSpace::LumaA => convert_rgb_to_grayscale(image, self.to, self.to.has_alpha())?,
+                ColorSpace::Luma => convert_rgb_to_grayscale(image, self.to, self.luma_weights)?,
+                ColorSpace::LumaA => convert_rgb_to_grayscale(image, self.to, self.luma_weights)?,
                 ColorSpace::CMYK => convert_rgb_to_cmyk(image)?,
                 ColorSpace::BGR => convert_rgb_bgr(from, self.to, image)?,
                 ColorSpace::BGRA => convert_rgb_bgr(from, self.to, image)?,
                 ColorSpace::ARGB => convert_rgb_to_argb(image)?,
                 ColorSpace::HSL => convert_rgb_to_hsl(image)?,
                 ColorSpace::HSV => convert_rgb_to_hsv(image)?,
+                ColorSpace::YCbCr => convert_rgb_to_ycbcr(image, self.luma_weights)?,
                 color => {
                     let msg = format!("Unsupported/unknown mapping from RGB to {color:?}");
                     return Err(ImageErrors::GenericString(msg));
@@ -91,10 +130,11 @@ impl OperationsTrait for ColorspaceConv {
                 ColorSpace::BGR => convert_rgb_bgr(from, self.to, image)?,
                 ColorSpace::BGRA => convert_rgb_bgr(from, self.to, image)?,
                 ColorSpace::ARGB => convert_rgba_to_argb_or_vice_versa(image)?,
-                ColorSpace::LumaA => convert_rgb_to_grayscale(image, self.to, self.to.has_alpha())?,
-                ColorSpace::Luma => convert_rgb_to_grayscale(image, self.to, self.to.has_alpha())?,
+                ColorSpace::LumaA => convert_rgb_to_grayscale(image, self.to, self.luma_weights)?,
+                ColorSpace::Luma => convert_rgb_to_grayscale(image, self.to, self.luma_weights)?,
                 ColorSpace::HSV => convert_rgb_to_hsv(image)?,
                 ColorSpace::HSL => convert_rgb_to_hsl(image)?,
+                ColorSpace::YCbCr => convert_rgb_to_ycbcr(image, self.luma_weights)?,
                 ColorSpace::CMYK => {
                     // drop alpha
                     pop_channel(image);
@@ -108,11 +148,11 @@ impl OperationsTrait for ColorspaceConv {
             ColorSpace::Luma => match self.to {
                 ColorSpace::RGB => convert_luma_to_rgb(image, self.to)?,
                 ColorSpace::LumaA => convert_adding_opaque_alpha(image)?,
-                color => {
-                    // convert to rgb
+                _ => {
+                    // convert to rgb, then to the desired colorspace
                     convert_luma_to_rgb(image, ColorSpace::RGB)?;
                     image.set_colorspace(ColorSpace::RGB);
-                    image.convert_color(color)?;
+                    self.execute_impl(image)?;
                 }
             },
 
@@ -120,11 +160,11 @@ impl OperationsTrait for ColorspaceConv {
                 ColorSpace::RGB => convert_luma_to_rgb(image, self.to)?,
                 ColorSpace::RGBA => convert_luma_to_rgb(image, self.to)?,
                 ColorSpace::Luma => pop_channel(image),
-                color => {
-                    // convert to rgba
+                _ => {
+                    // convert to rgba, then to the desired colorspace
                     convert_luma_to_rgb(image, ColorSpace::RGBA)?;
                     image.set_colorspace(ColorSpace::RGBA);
-                    image.convert_color(color)?;
+                    self.execute_impl(image)?;
                 }
             },
             ColorSpace::CMYK => {
@@ -132,14 +172,14 @@ impl OperationsTrait for ColorspaceConv {
                 convert_cmyk_to_rgb(image, ColorSpace::RGB)?;
                 image.set_colorspace(ColorSpace::RGB);
                 // convert to desired colorspace
-                image.convert_color(self.to)?;
+                self.execute_impl(image)?;
             }
             ColorSpace::BGR => {
                 // first convert to rgb
                 convert_rgb_bgr(from, ColorSpace::RGB, image)?;
                 // then convert to desired color
                 image.set_colorspace(ColorSpace::RGB);
-                image.convert_color(self.to)?;
+                self.execute_impl(image)?;
             }
             ColorSpace::BGRA => {
                 // BGRA and RGBA are similar with difference being only the R and B are swapped
@@ -148,28 +188,35 @@ impl OperationsTrait for ColorspaceConv {
 
                 // then use RGBA conversions
                 image.set_colorspace(ColorSpace::RGBA);
-                image.convert_color(self.to)?;
+                self.execute_impl(image)?;
             }
 
             ColorSpace::ARGB => {
                 // convert to RGBA
                 convert_rgba_to_argb_or_vice_versa(image)?;
                 image.set_colorspace(ColorSpace::RGBA);
-                image.convert_color(self.to)?;
+                self.execute_impl(image)?;
             }
             ColorSpace::HSL => {
                 // convert to rgb
                 convert_hsl_to_rgb(image)?;
                 image.set_colorspace(ColorSpace::RGB);
                 // convert to desired colorspace
-                image.convert_color(self.to)?;
+                self.execute_impl(image)?;
             }
             ColorSpace::HSV => {
                 // convert to rgb
                 convert_hsv_to_rgb(image)?;
                 image.set_colorspace(ColorSpace::RGB);
                 // convert to desired colorspace
-                image.convert_color(self.to)?;
+                self.execute_impl(image)?;
+            }
+            ColorSpace::YCbCr => {
+                // convert to rgb with the same weights
+                convert_ycbcr_to_rgb(image, self.luma_weights)?;
+                image.set_colorspace(ColorSpace::RGB);
+                // convert to desired colorspace
+                self.execute_impl(image)?;
             }
 
             color => {
@@ -184,10 +231,34 @@ impl OperationsTrait for ColorspaceConv {
     }
 
     fn supported_colorspaces(&self) -> &'static [ColorSpace] {
-        &ALL_COLORSPACES
+        &CONVERTIBLE_COLORSPACES
     }
 
     fn supported_types(&self) -> &'static [BitType] {
         &[BitType::U16, BitType::U8, BitType::F32]
     }
+
+    fn output_colorspace(&self, _: ColorSpace) -> ColorSpace {
+        self.to
+    }
+
+    fn interleaved_colorspace(&self, input: ColorSpace) -> Option<ColorSpace> {
+        // only grayscale conversions have an interleaved kernel
+        let to_grayscale = matches!(input, ColorSpace::RGB | ColorSpace::RGBA)
+            && (self.to == ColorSpace::Luma
+                || (self.to == ColorSpace::LumaA && input.has_alpha()));
+
+        (input == self.to || to_grayscale).then_some(self.to)
+    }
+
+    fn execute_interleaved(
+        &self, pixels: &mut Vec<u8>, colorspace: ColorSpace
+    ) -> Result<(), ImageErrors> {
+        if colorspace == self.to {
+            return Ok(());
+        }
+        RgbToGrayScale::new(self.luma_weights)
+            .set_preserve_alpha(self.to.has_alpha())
+            .execute_interleaved(pixels, colorspace)
+    }
 }
@@ -1,23 +1,23 @@
 use zune_core::bit_depth::{BitDepth, BitType};
 use zune_core::colorspace::ColorSpace;
-use zune_core::log::warn;
 
 use crate::channel::Channel;
-use crate::core_filters::colorspace::grayscale::{
-    rgb_to_grayscale_f32, rgb_to_grayscale_u16, rgb_to_grayscale_u8
-};
+use crate::core_filters::colorspace::grayscale::{LumaWeights, RgbToGrayScale};
 use crate::core_filters::colorspace::rgb_to_cmyk;
 use crate::core_filters::colorspace::rgb_to_hsl::{hsl_to_rgb, rgb_to_hsl};
 use crate::core_filters::colorspace::rgb_to_hsv::{hsv_to_rgb, rgb_to_hsv};
+use crate::core_filters::colorspace::ycbcr::{rgb_to_ycbcr, ycbcr_to_rgb};
 use crate::errors::ImageErrors;
 use crate::image::Image;
+use crate::metadata::AlphaState;
+use crate::traits::OperationsTrait;
 
 pub fn convert_adding_opaque_alpha(image: &mut Image) -> Result<(), ImageErrors> {
     let old_len = image.channels_ref(true)[0].len();
 
-    let bit_type = image.depth().bit_type();
+    let depth = image.depth();
 
-    let new_channel = match bit_type {
+    let new_channel = match depth.bit_type() {
         BitType::U8 => {
             let mut channel = Channel::new_with_length::<u8>(old_len);
             channel.fill(255_u8).unwrap();
@@ -25,7 +25,7 @@ pub fn convert_adding_opaque_alpha(image: &mut Image) -> Result<(), ImageErrors>
         }
         BitType::U16 => {
             let mut channel = Channel::new_with_length::<u16>(old_len);
-            channel.fill(65535_u16).unwrap();
+            channel.fill(depth.max_value()).unwrap();
             channel
         }
         BitType::F32 => {
@@ -54,115 +54,15 @@ pub fn convert_adding_opaque_alpha(image: &mut Image) -> Result<(), ImageErrors>
 }
 
 pub fn convert_rgb_to_grayscale(
-    image: &mut Image, to: ColorSpace, preserve_alpha: bool
+    image: &mut Image, to: ColorSpace, weights: LumaWeights
 ) -> Result<(), ImageErrors> {
-    let im_colorspace = image.colorspace();
-
-    if im_colorspace == ColorSpace::Luma || im_colorspace == ColorSpace::LumaA {
-        warn!("Image already in grayscale skipping this operation");
-        return Ok(());
-    }
-
-    let (width, height) = image.dimensions();
-    let size = width * height * image.depth().size_of();
-
-    let colorspace = image.colorspace();
-    let depth = image.depth();
-    let max_value = image.depth().max_value();
+    RgbToGrayScale::new(weights)
+        .set_preserve_alpha(to.has_alpha())
+        .execute_impl(image)?;
 
-    let mut out_colorspace = ColorSpace::Unknown;
-
-    for frame in image.frames_mut() {
-        let channel = frame.channels_ref(colorspace, !preserve_alpha);
-
-        match depth.bit_type() {
-            BitType::U8 => {
-                let r = channel[0].reinterpret_as::<u8>().unwrap();
-                let g = channel[1].reinterpret_as::<u8>().unwrap();
-                let b = channel[2].reinterpret_as::<u8>().unwrap();
-                let mut out = Channel::new_with_length::<u8>(size);
-
-                rgb_to_grayscale_u8(
-                    r,
-                    g,
-                    b,
-                    out.reinterpret_as_mut::<u8>().unwrap(),
-                    max_value as u8
-                );
-
-                if preserve_alpha && colorspace.has_alpha() {
-                    frame.set_channels(vec![out, channel[3].clone()]);
-                    out_colorspace = ColorSpace::LumaA;
-                } else if to.has_alpha() {
-                    // add alpha channel
-                    let mut alpha_out = Channel::new_with_length::<u8>(size);
-                    alpha_out.reinterpret_as_mut::<u8>().unwrap().fill(u8::MAX);
-                    frame.set_channels(vec![out, alpha_out]);
-                    out_colorspace = ColorSpace::Luma;
-                } else {
-                    frame.set_channels(vec![out]);
-                    out_colorspace = ColorSpace::Luma;
-                }
-            }
-            BitType::U16 => {
-                let r = channel[0].reinterpret_as::<u16>().unwrap();
-                let g = channel[1].reinterpret_as::<u16>().unwrap();
-                let b = channel[2].reinterpret_as::<u16>().unwrap();
-                let mut out = Channel::new_with_length::<u16>(size);
-
-                rgb_to_grayscale_u16(r, g, b, out.reinterpret_as_mut::<u16>().unwrap(), max_value);
-
-                if preserve_alpha && colorspace.has_alpha() {
-                    frame.set_channels(vec![out, channel[3].clone()]);
-                    out_colorspace = ColorSpace::LumaA;
-                } else if to.has_alpha() {
-                    // add alpha channel
-                    let mut alpha_out = Channel::new_with_length::<u16>(size);
-                    alpha_out
-                        .reinterpret_as_mut::<u16>()
-                        .unwrap()
-                        .fill(u16::MAX);
-                    frame.set_channels(vec![out, alpha_out]);
-                    out_colorspace = ColorSpace::Luma;
-                } else {
-                    frame.set_channels(vec![out]);
-                    out_colorspace = ColorSpace::Luma;
-                }
-            }
-            BitType::F32 => {
-                let r = channel[0].reinterpret_as::<f32>().unwrap();
-                let g = channel[1].reinterpret_as::<f32>().unwrap();
-                let b = channel[2].reinterpret_as::<f32>().unwrap();
-                let mut out = Channel::new_with_length::<f32>(size);
-
-                rgb_to_grayscale_f32(
-                    r,
-                    g,
-                    b,
-                    out.reinterpret_as_mut::<f32>().unwrap(),
-                    max_value as f32
-                );
-
-                if preserve_alpha && colorspace.has_alpha() {
-                    frame.set_channels(vec![out, channel[3].clone()]);
-                    out_colorspace = ColorSpace::LumaA;
-                } else if to.has_alpha() {
-                    // add alpha channel
-                    let mut alpha_out = Channel::new_with_length::<f32>(size);
-                    alpha_out.reinterpret_as_mut::<f32>().unwrap().fill(1.0);
-                    frame.set_channels(vec![out, alpha_out]);
-                    out_colorspace = ColorSpace::Luma;
-                } else {
-                    frame.set_channels(vec![out]);
-                    out_colorspace = ColorSpace::Luma;
-                }
-            }
-            d => return Err(ImageErrors::ImageOperationNotImplemented("colorspace", d))
-        }
+    if to.has_alpha() && !image.colorspace().has_alpha() {
+        convert_adding_opaque_alpha(image)?;
     }
-
-    assert_ne!(out_colorspace, ColorSpace::Unknown);
-
     Ok(())
 }
 
@@ -194,6 +94,14 @@ pub fn convert_rgb_bgr(
 pub fn convert_rgb_to_cmyk(image: &mut Image) -> Result<(), ImageErrors> {
     let depth = image.depth();
 
+    if matches!(depth, BitDepth::Ten | BitDepth::Twelve) {
+        // the u16 routines expect the full 16 bit range
+        image.convert_depth(BitDepth::Sixteen)?;
+        convert_rgb_to_cmyk(image)?;
+        // restore original bit depth
+        return image.convert_depth(depth);
+    }
+
     for frame in image.frames_mut() {
         let channels = frame.channels_vec();
 
@@ -254,6 +162,14 @@ pub fn convert_rgb_to_cmyk(image: &mut Image) -> Result<(), ImageErrors> {
 pub fn convert_cmyk_to_rgb(image: &mut Image, to: ColorSpace) -> Result<(), ImageErrors> {
     let depth = image.depth();
 
+    if matches!(depth, BitDepth::Ten | BitDepth::Twelve) {
+        // the u16 routines expect the full 16 bit range
+        image.convert_depth(BitDepth::Sixteen)?;
+        convert_cmyk_to_rgb(image, to)?;
+        // restore original bit depth
+        return image.convert_depth(depth);
+    }
+
     for frame in image.frames_mut() {
         let channels = frame.channels_vec();
 
@@ -393,6 +309,53 @@ pub fn convert_hsv_to_rgb(image: &mut Image) -> Result<(), ImageErrors> {
 
     Ok(())
 }
+pub fn convert_rgb_to_ycbcr(image: &mut Image, weights: LumaWeights) -> Result<(), ImageErrors> {
+    image.convert_color(ColorSpace::RGB)?;
+
+    // preserve original depth
+    let orig_depth = image.depth();
+    image.convert_depth(BitDepth::Float32)?;
+
+    for frame in image.frames_mut() {
+        let channels = frame.channels_vec();
+        let (r, rest) = channels.split_at_mut(1);
+        let (g, b) = rest.split_at_mut(1);
+
+        rgb_to_ycbcr(
+            r[0].reinterpret_as_mut()?,
+            g[0].reinterpret_as_mut()?,
+            b[0].reinterpret_as_mut()?,
+            weights
+        );
+    }
+    // restore original bit depth
+    image.convert_depth(orig_depth)?;
+
+    Ok(())
+}
+pub fn convert_ycbcr_to_rgb(image: &mut Image, weights: LumaWeights) -> Result<(), ImageErrors> {
+    assert_eq!(image.colorspace(), ColorSpace::YCbCr);
+    // preserve original depth
+    let orig_depth = image.depth();
+    image.convert_depth(BitDepth::Float32)?;
+
+    for frame in image.frames_mut() {
+        let channels = frame.channels_vec();
+        let (y, rest) = channels.split_at_mut(1);
+        let (cb, cr) = rest.split_at_mut(1);
+
+        ycbcr_to_rgb(
+            y[0].reinterpret_as_mut()?,
+            cb[0].reinterpret_as_mut()?,
+            cr[0].reinterpret_as_mut()?,
+            weights
+        );
+    }
+    // restore original bit depth
+    image.convert_depth(orig_depth)?;
+
+    Ok(())
+}
 pub fn pop_channel(image: &mut Image) {
     // contain the alpha channel
     for frame in image.frames_mut() {
@@ -401,11 +364,19 @@ pub fn pop_channel(image: &mut Image) {
 }
 pub fn convert_rgb_to_argb(image: &mut Image) -> Result<(), ImageErrors> {
     convert_adding_opaque_alpha(image)?;
+    let to_argb = image.colorspace() == ColorSpace::RGBA;
     // swap
     for frame in image.frames_mut() {
         // switch the channels now
-        let a_channel = frame.channels_vec().pop().unwrap();
-        frame.channels_vec().insert(0, a_channel);
+        let channels = frame.channels_vec();
+
+        if to_argb {
+            let a_channel = channels.pop().unwrap();
+            channels.insert(0, a_channel);
+        } else {
+            let a_channel = channels.remove(0);
+            channels.push(a_channel);
+        }
     }
     Ok(())
 }
@@ -415,11 +386,19 @@ pub fn convert_rgba_to_argb_or_vice_versa(image: &mut Image) -> Result<(), Image
         image.colorspace(),
         ColorSpace::RGBA | ColorSpace::ARGB
     ));
+    let to_argb = image.colorspace() == ColorSpace::RGBA;
     // swap
     for frame in image.frames_mut() {
         // switch the channels now
-        let a_channel = frame.channels_vec().pop().unwrap();
-        frame.channels_vec().insert(0, a_channel);
+        let channels = frame.channels_vec();
+
+        if to_argb {
+            let a_channel = channels.pop().unwrap();
+            channels.insert(0, a_channel);
+        } else {
+            let a_channel = channels.remove(0);
+            channels.push(a_channel);
+        }
     }
     Ok(())
 }
@@ -456,3 +435,57 @@ pub fn convert_luma_to_rgb(
     }
     Ok(())
 }
+
+/// Composite an image with alpha over a white background and remove the alpha channel
+///
+/// The alpha channel must be the last channel, `to` is the colorspace of the remaining channels
+pub fn flatten_alpha(image: &mut Image, to: ColorSpace) -> Result<(), ImageErrors> {
+    let premultiplied = image.metadata.is_premultiplied_alpha();
+    let depth = image.depth();
+    let bit_type = depth.bit_type();
+
+    for frame in image.frames_mut() {
+        let channels = frame.channels_vec();
+        let alpha = channels.pop().unwrap();
+
+        for channel in channels.iter_mut() {
+            match bit_type {
+                BitType::U8 => {
+                    let alpha = alpha.reinterpret_as::<u8>()?;
+                    for (c, a) in channel.reinterpret_as_mut::<u8>()?.iter_mut().zip(alpha) {
+                        let (c32, a32) = (u32::from(*c), u32::from(*a));
+                        let color = if premultiplied { c32 * 255 } else { c32 * a32 };
+                        *c = ((color + 255 * (255 - a32) + 127) / 255).min(255) as u8;
+                    }
+                }
+                BitType::U16 => {
+                    let alpha = alpha.reinterpret_as::<u16>()?;
+                    // e.g 65535 for 16 bit images, 1023 for 10 bit images
+                    let max = u64::from(depth.max_value());
+
+                    for (c, a) in channel.reinterpret_as_mut::<u16>()?.iter_mut().zip(alpha) {
+                        let (c64, a64) = (u64::from(*c), u64::from(*a).min(max));
+                        let color = if premultiplied { c64 * max } else { c64 * a64 };
+                        *c = ((color + max * (max - a64) + max / 2) / max).min(max) as u16;
+                    }
+                }
+                BitType::F32 => {
+                    let alpha = alpha.reinterpret_as::<f32>()?;
+                    for (c, a) in channel.reinterpret_as_mut::<f32>()?.iter_mut().zip(alpha) {
+                        let color = if premultiplied { *c } else { *c * a };
+                        *c = color + (1.0 - a);
+                    }
+                }
+                _ => {
+                    return Err(ImageErrors::GenericStr(
+                        "Unsupported bit depth for flattening alpha"
+                    ))
+                }
+            }
+        }
+    }
+    image.set_colorspace(to);
+    image.metadata.set_alpha(AlphaState::NonPreMultiplied);
+
+    Ok(())
+}
@@ -0,0 +1,223 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! Lossless colorspace expansion
+//!
+//! These are the inverse of [`RgbToGrayScale`](crate::core_filters::colorspace::grayscale::RgbToGrayScale)
+//! and of dropping alpha, they let a pipeline bring images of different layouts
+//! to a single one (e.g RGBA) before stages or encoders that need it.
+use zune_core::bit_depth::BitType;
+use zune_core::colorspace::ColorSpace;
+use zune_core::log::warn;
+
+use crate::channel::Channel;
+use crate::errors::ImageErrors;
+use crate::image::Image;
+use crate::traits::OperationsTrait;
+
+/// Convert a grayscale image to RGB by replicating the luma channel
+///
+/// [`Luma`](ColorSpace::Luma) images become [`RGB`](ColorSpace::RGB) and
+/// [`LumaA`](ColorSpace::LumaA) images become [`RGBA`](ColorSpace::RGBA), alpha is kept.
+///
+/// Images already in color are left untouched.
+///
+/// # Example
+/// ```
+/// use zune_core::colorspace::ColorSpace;
+/// use zune_image::core_filters::colorspace::expand::GrayToRgb;
+/// use zune_image::image::Image;
+/// use zune_image::traits::OperationsTrait;
+///
+/// let mut image = Image::from_u8(&[10, 200], 1, 1, ColorSpace::LumaA);
+/// GrayToRgb::new().execute(&mut image).unwrap();
+///
+/// assert_eq!(image.colorspace(), ColorSpace::RGBA);
+/// assert_eq!(image.flatten_to_u8()[0], [10, 10, 10, 200]);
+/// ```
+#[derive(Copy, Clone, Debug, Default)]
+pub struct GrayToRgb;
+
+impl GrayToRgb {
+    /// Create a new grayscale to RGB conversion
+    #[must_use]
+    pub fn new() -> GrayToRgb {
+        GrayToRgb
+    }
+}
+
+impl OperationsTrait for GrayToRgb {
+    fn name(&self) -> &'static str {
+        "Grayscale to RGB"
+    }
+
+    fn execute_impl(&self, image: &mut Image) -> Result<(), ImageErrors> {
+        let colorspace = image.colorspace();
+
+        if !colorspace.is_grayscale() {
+            warn!("Image already in color skipping this operation");
+            return Ok(());
+        }
+        for frame in image.frames_mut() {
+            let channels = frame.channels_vec();
+            let luma = channels[0].clone();
+
+            channels.insert(1, luma.clone());
+            channels.insert(1, luma);
+        }
+        image.set_colorspace(self.output_colorspace(colorspace));
+
+        Ok(())
+    }
+
+    fn supported_colorspaces(&self) -> &'static [ColorSpace] {
+        &[
+            ColorSpace::Luma,
+            ColorSpace::LumaA,
+            ColorSpace::RGB,
+            ColorSpace::RGBA,
+            ColorSpace::BGR,
+            ColorSpace::BGRA,
+            ColorSpace::ARGB
+        ]
+    }
+
+    fn supported_types(&self) -> &'static [BitType] {
+        &[BitType::U8, BitType::U16, BitType::F32]
+    }
+
+    fn output_colorspace(&self, input: ColorSpace) -> ColorSpace {
+        match input {
+            ColorSpace::Luma => ColorSpace::RGB,
+            ColorSpace::LumaA => ColorSpace::RGBA,
+            _ => input
+        }
+    }
+}
+
+/// Add an alpha channel with the same value for every pixel
+///
+/// [`Luma`](ColorSpace::Luma) becomes [`LumaA`](ColorSpace::LumaA),
+/// [`RGB`](ColorSpace::RGB) becomes [`RGBA`](ColorSpace::RGBA) and
+/// [`BGR`](ColorSpace::BGR) becomes [`BGRA`](ColorSpace::BGRA).
+///
+/// Images that already have alpha are left untouched.
+///
+/// # Example
+/// ```
+/// use zune_core::colorspace::ColorSpace;
+/// use zune_image::core_filters::colorspace::expand::AddAlpha;
+/// use zune_image::image::Image;
+/// use zune_image::traits::OperationsTrait;
+///
+/// let mut image = Image::from_u8(&[1, 2, 3], 1, 1, ColorSpace::RGB);
+/// AddAlpha::new(0.5).execute(&mut image).unwrap();
+///
+/// assert_eq!(image.colorspace(), ColorSpace::RGBA);
+/// assert_eq!(image.flatten_to_u8()[0], [1, 2, 3, 128]);
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub struct AddAlpha {
+    value: f32
+}
+
+impl AddAlpha {
+    /// Create a new operation adding an alpha channel
+    ///
+    /// # Arguments
+    /// - value: The alpha of every pixel, from 0.0 (transparent) to 1.0 (opaque),
+    ///   it is scaled to the image depth
+    #[must_use]
+    pub fn new(value: f32) -> AddAlpha {
+        AddAlpha { value }
+    }
+}
+
+impl Default for AddAlpha {
+    /// An opaque alpha channel
+    fn default() -> Self {
+        AddAlpha::new(1.0)
+    }
+}
+
+impl OperationsTrait for AddAlpha {
+    fn name(&self) -> &'static str {
+        "Add alpha"
+    }
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn execute_impl(&self, image: &mut Image) -> Result<(), ImageErrors> {
+        let colorspace = image.colorspace();
+
+        if colorspace.has_alpha() {
+            warn!("Image already has alpha skipping this operation");
+            return Ok(());
+        }
+        if !(0.0..=1.0).contains(&self.value) {
+            return Err(ImageErrors::GenericString(format!(
+                "Alpha value {} is outside the range 0.0..=1.0",
+                self.value
+            )));
+        }
+        let (width, height) = image.dimensions();
+        let depth = image.depth();
+        let size = width * height * depth.size_of();
+        let scaled = (self.value * f32::from(depth.max_value())).round();
+
+        let alpha = match depth.bit_type() {
+            BitType::U8 => {
+                let mut channel = Channel::new_with_length::<u8>(size);
+                channel.fill(scaled as u8)?;
+                channel
+            }
+            BitType::U16 => {
+                let mut channel = Channel::new_with_length::<u16>(size);
+                channel.fill(scaled as u16)?;
+                channel
+            }
+            BitType::F32 => {
+                let mut channel = Channel::new_with_length::<f32>(size);
+                channel.fill(self.value)?;
+                channel
+            }
+            d => return Err(ImageErrors::ImageOperationNotImplemented(self.name(), d))
+        };
+
+        for frame in image.frames_mut() {
+            frame.push(alpha.clone());
+        }
+        image.set_colorspace(self.output_colorspace(colorspace));
+
+        Ok(())
+    }
+
+    fn supported_colorspaces(&self) -> &'static [ColorSpace] {
+        &[
+            ColorSpace::Luma,
+            ColorSpace::LumaA,
+            ColorSpace::RGB,
+            ColorSpace::RGBA,
+            ColorSpace::BGR,
+            ColorSpace::BGRA,
+            ColorSpace::ARGB
+        ]
+    }
+
+    fn supported_types(&self) -> &'static [BitType] {
+        &[BitType::U8, BitType::U16, BitType::F32]
+    }
+
+    fn output_colorspace(&self, input: ColorSpace) -> ColorSpace {
+        match input {
+            ColorSpace::Luma => ColorSpace::LumaA,
+            ColorSpace::RGB => ColorSpace::RGBA,
+            ColorSpace::BGR => ColorSpace::BGRA,
+            _ => input
+        }
+    }
+}
@@ -6,27 +6,320 @@
  * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
  */
 
+//! RGB to grayscale conversion
+//!
+//! Grayscale is a weighted sum of the red, green and blue channels,
+//! the weights depend on the standard the image was encoded for, see [`LumaWeights`].
+use zune_core::bit_depth::BitType;
+use zune_core::colorspace::ColorSpace;
+use zune_core::log::warn;
+
+use crate::channel::Channel;
 use crate::core_filters::colorspace::grayscale::scalar::{
-    convert_rgb_to_grayscale_scalar, convert_rgb_to_grayscale_scalar_f32,
-    convert_rgb_to_grayscale_scalar_u16
+    convert_rgb_to_grayscale_scalar, convert_rgb_to_grayscale_scalar_f32
 };
+use crate::errors::ImageErrors;
+use crate::image::Image;
+use crate::traits::OperationsTrait;
 
 mod avx2;
 mod scalar;
 mod sse41;
 
-pub fn rgb_to_grayscale_u16(r: &[u16], g: &[u16], b: &[u16], out: &mut [u16], max_value: u16) {
-    convert_rgb_to_grayscale_scalar_u16(r, g, b, out, max_value);
+/// Luma coefficients used to weigh red, green and blue channels
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum LumaWeights {
+    /// ITU-R BT.601, `0.299R + 0.587G + 0.114B`
+    ///
+    /// Used by JPEG and standard definition video
+    #[default]
+    Bt601,
+    /// ITU-R BT.709, `0.2126R + 0.7152G + 0.0722B`
+    ///
+    /// Shares primaries with sRGB, used by high definition video
+    Bt709,
+    /// ITU-R BT.2020, `0.2627R + 0.6780G + 0.0593B`
+    ///
+    /// Used by ultra high definition and HDR video
+    Bt2020,
+    /// Equal weights, `(R + G + B) / 3`
+    Average
+}
+
+impl LumaWeights {
+    /// Parse weights from their name, one of `bt601`, `bt709`, `bt2020` or `average`
+    pub fn from_string_result(input: &str) -> Result<Self, String> {
+        match input {
+            "bt601" => Ok(Self::Bt601),
+            "bt709" => Ok(Self::Bt709),
+            "bt2020" => Ok(Self::Bt2020),
+            "average" => Ok(Self::Average),
+            _ => Err(format!(
+                "Unknown luma weights {input:?}, accepted values are bt601, bt709, bt2020 and average"
+            ))
+        }
+    }
+    /// Red, green and blue coefficients, in that order
+    pub const fn coefficients(self) -> [f32; 3] {
+        match self {
+            LumaWeights::Bt601 => [0.2989, 0.5870, 0.1140],
+            LumaWeights::Bt709 => [0.2126, 0.7152, 0.0722],
+            LumaWeights::Bt2020 => [0.2627, 0.6780, 0.0593],
+            LumaWeights::Average => [1.0 / 3.0; 3]
+        }
+    }
+}
+
+/// Convert an image to grayscale
+///
+/// The red, green and blue channels are found according to the image colorspace,
+/// so RGB, BGR and ARGB layouts (with or without alpha) are all handled.
+///
+/// If alpha is preserved, images with alpha become [`LumaA`](ColorSpace::LumaA), otherwise
+/// the alpha channel is dropped and the image becomes [`Luma`](ColorSpace::Luma), which
+/// is also how to convert RGBX images where the fourth channel is padding.
+///
+/// Images already in grayscale are left untouched, alpha included.
+///
+/// # Example
+/// ```
+/// use zune_core::colorspace::ColorSpace;
+/// use zune_image::core_filters::colorspace::grayscale::{LumaWeights, RgbToGrayScale};
+/// use zune_image::image::Image;
+/// use zune_image::traits::OperationsTrait;
+///
+/// let mut image = Image::fill(128_u16, ColorSpace::BGRA, 10, 10);
+/// RgbToGrayScale::new(LumaWeights::Bt709).execute(&mut image).unwrap();
+///
+/// assert_eq!(image.colorspace(), ColorSpace::LumaA);
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub struct RgbToGrayScale {
+    weights:        LumaWeights,
+    preserve_alpha: bool
+}
+
+impl RgbToGrayScale {
+    /// Create a new grayscale conversion using `weights`, alpha is preserved
+    #[must_use]
+    pub fn new(weights: LumaWeights) -> RgbToGrayScale {
+        RgbToGrayScale {
+            weights,
+            preserve_alpha: true
+        }
+    }
+    /// Whether to keep the alpha channel of color images
+    ///
+    /// When false, alpha is dropped and the output is always [`Luma`](ColorSpace::Luma)
+    #[must_use]
+    pub fn set_preserve_alpha(mut self, yes: bool) -> RgbToGrayScale {
+        self.preserve_alpha = yes;
+        self
+    }
+}
+
+impl Default for RgbToGrayScale {
+    fn default() -> Self {
+        RgbToGrayScale::new(LumaWeights::default())
+    }
+}
+
+/// Indices of the red, green and blue channels of a colorspace
+fn rgb_positions(colorspace: ColorSpace) -> Option<[usize; 3]> {
+    match colorspace {
+        ColorSpace::RGB | ColorSpace::RGBA => Some([0, 1, 2]),
+        ColorSpace::BGR | ColorSpace::BGRA => Some([2, 1, 0]),
+        ColorSpace::ARGB => Some([1, 2, 3]),
+        _ => None
+    }
+}
+
+impl OperationsTrait for RgbToGrayScale {
+    fn name(&self) -> &'static str {
+        "RGB to Grayscale"
+    }
+
+    fn execute_impl(&self, image: &mut Image) -> Result<(), ImageErrors> {
+        let colorspace = image.colorspace();
+
+        if colorspace.is_grayscale() {
+            warn!("Image already in grayscale skipping this operation");
+            return Ok(());
+        }
+        let [r_pos, g_pos, b_pos] =
+            rgb_positions(colorspace).ok_or(ImageErrors::UnsupportedColorspace(
+                colorspace,
+                self.name(),
+                self.supported_colorspaces()
+            ))?;
+        let alpha_pos = colorspace.alpha_position().filter(|_| self.preserve_alpha);
+
+        let (width, height) = image.dimensions();
+        let depth = image.depth();
+        let size = width * height * depth.size_of();
+        let max_value = depth.max_value();
+
+        for frame in image.frames_mut() {
+            let channels = frame.channels_vec();
+            let (r, g, b) = (&channels[r_pos], &channels[g_pos], &channels[b_pos]);
+
+            let out = match depth.bit_type() {
+                BitType::U8 => {
+                    let mut out = Channel::new_with_length::<u8>(size);
+                    rgb_to_grayscale_u8(
+                        r.reinterpret_as()?,
+                        g.reinterpret_as()?,
+                        b.reinterpret_as()?,
+                        out.reinterpret_as_mut()?,
+                        max_value as u8,
+                        self.weights
+                    );
+                    out
+                }
+                BitType::U16 => {
+                    let mut out = Channel::new_with_length::<u16>(size);
+                    convert_rgb_to_grayscale_scalar::<u16>(
+                        r.reinterpret_as()?,
+                        g.reinterpret_as()?,
+                        b.reinterpret_as()?,
+                        out.reinterpret_as_mut()?,
+                        max_value,
+                        self.weights.coefficients()
+                    );
+                    out
+                }
+                BitType::F32 => {
+                    let mut out = Channel::new_with_length::<f32>(size);
+                    rgb_to_grayscale_f32(
+                        r.reinterpret_as()?,
+                        g.reinterpret_as()?,
+                        b.reinterpret_as()?,
+                        out.reinterpret_as_mut()?,
+                        f32::from(max_value),
+                        self.weights
+                    );
+                    out
+                }
+                d => return Err(ImageErrors::ImageOperationNotImplemented(self.name(), d))
+            };
+
+            match alpha_pos {
+                Some(pos) => {
+                    let alpha = channels.swap_remove(pos);
+                    *channels = vec![out, alpha];
+                }
+                None => *channels = vec![out]
+            }
+        }
+        image.set_colorspace(self.output_colorspace(colorspace));
+
+        Ok(())
+    }
+
+    fn supported_colorspaces(&self) -> &'static [ColorSpace] {
+        &[
+            ColorSpace::RGB,
+            ColorSpace::RGBA,
+            ColorSpace::BGR,
+            ColorSpace::BGRA,
+            ColorSpace::ARGB,
+            ColorSpace::Luma,
+            ColorSpace::LumaA
+        ]
+    }
+
+    fn supported_types(&self) -> &'static [BitType] {
+        &[BitType::U8, BitType::U16, BitType::F32]
+    }
+
+    fn output_colorspace(&self, input: ColorSpace) -> ColorSpace {
+        if input.is_grayscale() {
+            input
+        } else if input.has_alpha() && self.preserve_alpha {
+            ColorSpace::LumaA
+        } else {
+            ColorSpace::Luma
+        }
+    }
+
+    fn interleaved_colorspace(&self, input: ColorSpace) -> Option<ColorSpace> {
+        (input.is_grayscale() || rgb_positions(input).is_some())
+            .then(|| self.output_colorspace(input))
+    }
+
+    fn execute_interleaved(
+        &self, pixels: &mut Vec<u8>, colorspace: ColorSpace
+    ) -> Result<(), ImageErrors> {
+        if colorspace.is_grayscale() {
+            return Ok(());
+        }
+        let positions = rgb_positions(colorspace).ok_or(ImageErrors::UnsupportedColorspace(
+            colorspace,
+            self.name(),
+            self.supported_colorspaces()
+        ))?;
+        let alpha_pos = colorspace.alpha_position().filter(|_| self.preserve_alpha);
+
+        rgb_to_grayscale_interleaved_u8(
+            pixels,
+            colorspace.num_components(),
+            positions,
+            alpha_pos,
+            self.weights
+        );
+        Ok(())
+    }
+}
+
+/// Convert interleaved pixels with `components` channels to grayscale in place
+///
+/// Each pixel becomes its gray value, followed by the sample at `alpha_pos` if any
+fn rgb_to_grayscale_interleaved_u8(
+    pixels: &mut Vec<u8>, components: usize, [r_pos, g_pos, b_pos]: [usize; 3],
+    alpha_pos: Option<usize>, weights: LumaWeights
+) {
+    let count = pixels.len() / components;
+
+    let mut planes = vec![0_u8; count * 4];
+    let (rgb, gray) = planes.split_at_mut(count * 3);
+    let (r, gb) = rgb.split_at_mut(count);
+    let (g, b) = gb.split_at_mut(count);
+
+    for (i, pixel) in pixels.chunks_exact(components).enumerate() {
+        r[i] = pixel[r_pos];
+        g[i] = pixel[g_pos];
+        b[i] = pixel[b_pos];
+    }
+    // go through the planar kernel so both paths give the same values
+    rgb_to_grayscale_u8(r, g, b, gray, u8::MAX, weights);
+
+    match alpha_pos {
+        Some(pos) => {
+            // pixel i is written to 2i and 2i + 1, which were read before
+            for (i, value) in gray.iter().enumerate() {
+                pixels[2 * i + 1] = pixels[i * components + pos];
+                pixels[2 * i] = *value;
+            }
+            pixels.truncate(count * 2);
+        }
+        None => {
+            pixels.truncate(count);
+            pixels.copy_from_slice(gray);
+        }
+    }
 }
 
-pub fn rgb_to_grayscale_u8(r: &[u8], g: &[u8], b: &[u8], out: &mut [u8], max_value: u8) {
+fn rgb_to_grayscale_u8(
+    r: &[u8], g: &[u8], b: &[u8], out: &mut [u8], max_value: u8, weights: LumaWeights
+) {
     #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
     {
+        // the SIMD kernels have BT.601 weights baked in
         #[cfg(feature = "avx2")]
         {
             use crate::grayscale::avx2::convert_rgb_to_grayscale_u8_avx2;
 
-            if is_x86_feature_detected!("avx2") {
+            if weights == LumaWeights::Bt601 && is_x86_feature_detected!("avx2") {
                 unsafe {
                     return convert_rgb_to_grayscale_u8_avx2(r, g, b, out);
                 }
@@ -37,18 +330,20 @@ pub fn rgb_to_grayscale_u8(r: &[u8], g: &[u8], b: &[u8], out: &mut [u8], max_val
         {
             use crate::grayscale::sse41::convert_rgb_to_grayscale_u8_sse41;
 
-            if is_x86_feature_detected!("sse4.1") {
+            if weights == LumaWeights::Bt601 && is_x86_feature_detected!("sse4.1") {
                 unsafe {
                     return convert_rgb_to_grayscale_u8_sse41(r, g, b, out);
                 }
             }
         }
     }
-    convert_rgb_to_grayscale_scalar(r, g, b, out, max_value);
+    convert_rgb_to_grayscale_scalar(r, g, b, out, max_value, weights.coefficients());
 }
 
-pub fn rgb_to_grayscale_f32(r: &[f32], g: &[f32], b: &[f32], out: &mut [f32], max_value: f32) {
-    convert_rgb_to_grayscale_scalar_f32(r, g, b, out, max_value);
+fn rgb_to_grayscale_f32(
+    r: &[f32], g: &[f32], b: &[f32], out: &mut [f32], max_value: f32, weights: LumaWeights
+) {
+    convert_rgb_to_grayscale_scalar_f32(r, g, b, out, max_value, weights.coefficients());
 }
 
 #[cfg(feature = "benchmarks")]
@@ -99,6 +394,7 @@ mod benchmarks {
     #[bench]
     fn convert_rgb_to_grayscale_scalar_bench(b: &mut test::Bencher) {
         use crate::grayscale::scalar::convert_rgb_to_grayscale_scalar;
+        use crate::grayscale::LumaWeights;
         let width = 800;
         let height = 800;
         let dimensions = width * height;
@@ -109,7 +405,14 @@ mod benchmarks {
 
         let mut c4 = vec![255; dimensions];
         b.iter(|| {
-            convert_rgb_to_grayscale_scalar(&c1, &c2, &c3, &mut c4, 255);
+            convert_rgb_to_grayscale_scalar(
+                &c1,
+                &c2,
+                &c3,
+                &mut c4,
+                255,
+                LumaWeights::Bt601.coefficients()
+            );
         });
     }
 
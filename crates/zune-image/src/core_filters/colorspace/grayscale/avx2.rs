@@ -15,6 +15,7 @@ use std::arch::x86::*;
 use std::arch::x86_64::*;
 
 use crate::grayscale::scalar::convert_rgb_to_grayscale_scalar;
+use crate::grayscale::LumaWeights;
 
 #[target_feature(enable = "avx2")]
 #[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
@@ -83,6 +84,13 @@ pub(crate) unsafe fn convert_rgb_to_grayscale_u8_avx2(r: &[u8], g: &[u8], b: &[u
         let c2 = &g[c_start..];
         let c3 = &b[c_start..];
 
-        convert_rgb_to_grayscale_scalar(c1, c2, c3, &mut gr[start..], 255);
+        convert_rgb_to_grayscale_scalar(
+            c1,
+            c2,
+            c3,
+            &mut gr[start..],
+            255,
+            LumaWeights::Bt601.coefficients()
+        );
     }
 }
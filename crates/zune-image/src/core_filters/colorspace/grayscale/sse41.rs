@@ -15,6 +15,7 @@ use std::arch::x86::*;
 use std::arch::x86_64::*;
 
 use crate::grayscale::scalar::convert_rgb_to_grayscale_scalar;
+use crate::grayscale::LumaWeights;
 
 #[target_feature(enable = "sse4.1")]
 #[allow(
@@ -81,6 +82,13 @@ pub(crate) unsafe fn convert_rgb_to_grayscale_u8_sse41(
         let c2 = &g[c_start..];
         let c3 = &b[c_start..];
 
-        convert_rgb_to_grayscale_scalar(c1, c2, c3, &mut gr[start..], 255);
+        convert_rgb_to_grayscale_scalar(
+            c1,
+            c2,
+            c3,
+            &mut gr[start..],
+            255,
+            LumaWeights::Bt601.coefficients()
+        );
     }
 }
@@ -3,6 +3,8 @@
 use nanorand::Rng;
 use zune_core::colorspace::ColorSpace;
 
+use crate::core_filters::colorspace::expand::{AddAlpha, GrayToRgb};
+use crate::core_filters::colorspace::grayscale::{LumaWeights, RgbToGrayScale};
 use crate::core_filters::colorspace::ColorspaceConv;
 use crate::image::Image;
 use crate::traits::OperationsTrait;
@@ -85,6 +87,7 @@ fn single_tests(u8_im: &Image, u16_im: &Image, f32_im: &Image) {
     test_helper(u8_im, u16_im, f32_im, ColorSpace::ARGB);
     test_helper(u8_im, u16_im, f32_im, ColorSpace::HSL);
     test_helper(u8_im, u16_im, f32_im, ColorSpace::HSV);
+    test_helper(u8_im, u16_im, f32_im, ColorSpace::YCbCr);
 }
 #[test]
 fn test_rgb_to_other_colors() {
@@ -118,6 +121,12 @@ fn test_hsl_to_other_colors() {
     let [u8_im, u16_im, f32_im] = create_image(ColorSpace::HSL);
     single_tests(&u8_im, &u16_im, &f32_im);
 }
+#[test]
+fn test_ycbcr_to_other_colors() {
+    let [u8_im, u16_im, f32_im] = create_image(ColorSpace::YCbCr);
+    single_tests(&u8_im, &u16_im, &f32_im);
+}
+
 #[test]
 fn test_luma_to_other_colors() {
     let [u8_im, u16_im, f32_im] = create_image(ColorSpace::Luma);
@@ -129,3 +138,180 @@ fn test_luma_a_to_other_colors() {
     let [u8_im, u16_im, f32_im] = create_image(ColorSpace::LumaA);
     single_tests(&u8_im, &u16_im, &f32_im);
 }
+
+#[test]
+fn test_grayscale_layouts_agree() {
+    let (r, g, b, a) = (200_u8, 100, 50, 77);
+
+    let images = [
+        Image::from_u8(&[r, g, b, a], 1, 1, ColorSpace::RGBA),
+        Image::from_u8(&[b, g, r, a], 1, 1, ColorSpace::BGRA),
+        Image::from_u8(&[a, r, g, b], 1, 1, ColorSpace::ARGB)
+    ];
+    for mut image in images {
+        let colorspace = image.colorspace();
+        RgbToGrayScale::default().execute(&mut image).unwrap();
+
+        assert_eq!(image.colorspace(), ColorSpace::LumaA);
+        assert_eq!(image.flatten_to_u8()[0], [124, a], "{colorspace:?}");
+    }
+}
+
+#[test]
+fn test_grayscale_drop_alpha() {
+    // RGBX, the last channel is padding
+    let mut image = Image::from_u8(&[200, 100, 50, 0], 1, 1, ColorSpace::RGBA);
+    RgbToGrayScale::default()
+        .set_preserve_alpha(false)
+        .execute(&mut image)
+        .unwrap();
+
+    assert_eq!(image.colorspace(), ColorSpace::Luma);
+    assert_eq!(image.flatten_to_u8()[0], [124]);
+}
+
+#[test]
+fn test_grayscale_luma_a_untouched() {
+    let mut image = Image::from_u8(&[10, 20, 30, 40], 2, 1, ColorSpace::LumaA);
+    let expected = image.clone();
+
+    RgbToGrayScale::default().execute(&mut image).unwrap();
+    assert!(image == expected);
+}
+
+#[test]
+fn test_grayscale_weights_u16() {
+    for (weights, expected) in [
+        (LumaWeights::Bt601, 19588),
+        (LumaWeights::Bt709, 13933),
+        (LumaWeights::Bt2020, 17216),
+        (LumaWeights::Average, 21845)
+    ] {
+        let mut image = Image::from_u16(&[u16::MAX, 0, 0], 1, 1, ColorSpace::RGB);
+        RgbToGrayScale::new(weights).execute(&mut image).unwrap();
+
+        let luma = image.frames_ref()[0].channels_ref(ColorSpace::Luma, false)[0]
+            .reinterpret_as::<u16>()
+            .unwrap()[0];
+        assert!(luma.abs_diff(expected) <= 1, "{weights:?} gave {luma}");
+    }
+}
+
+#[test]
+fn test_rgb_to_ycbcr_weights() {
+    for (weights, y, cr) in [
+        (LumaWeights::Bt601, 76, 255),
+        (LumaWeights::Bt709, 54, 255),
+        (LumaWeights::Bt2020, 66, 255)
+    ] {
+        let mut image = Image::from_u8(&[255, 0, 0], 1, 1, ColorSpace::RGB);
+        ColorspaceConv::new(ColorSpace::YCbCr)
+            .set_luma_weights(weights)
+            .execute(&mut image)
+            .unwrap();
+
+        let pixel = &image.flatten_to_u8()[0];
+        assert_eq!(image.colorspace(), ColorSpace::YCbCr);
+        assert!(
+            pixel[0].abs_diff(y) <= 1 && pixel[2].abs_diff(cr) <= 1,
+            "{weights:?}: {pixel:?}"
+        );
+
+        // converting back with the same weights recovers the color
+        ColorspaceConv::new(ColorSpace::RGB)
+            .set_luma_weights(weights)
+            .execute(&mut image)
+            .unwrap();
+        let pixel = &image.flatten_to_u8()[0];
+        assert!(
+            pixel[0] >= 253 && pixel[1] <= 2 && pixel[2] <= 2,
+            "{weights:?}: {pixel:?}"
+        );
+    }
+}
+
+#[test]
+fn test_ycbcr_matches_jpeg_decoder() {
+    use zune_core::options::DecoderOptions;
+    use zune_jpeg::JpegDecoder;
+
+    use crate::traits::DecoderTrait;
+
+    let mut file = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    file.pop();
+    file.pop();
+    let actual_file = file.join("test-images/jpeg/2029.jpg");
+    let data = std::fs::read(&actual_file).unwrap();
+
+    // the decoder's own YCbCr -> RGB conversion
+    let rgb = Image::read(&data, DecoderOptions::default()).unwrap();
+
+    let opts = DecoderOptions::new_fast().jpeg_set_out_colorspace(ColorSpace::YCbCr);
+    let mut decoder: Box<dyn DecoderTrait<&Vec<u8>>> =
+        Box::new(JpegDecoder::new_with_options(&data, opts));
+    let mut im = decoder.decode().unwrap();
+
+    assert_eq!(im.colorspace(), ColorSpace::YCbCr);
+    im.convert_color(ColorSpace::RGB).unwrap();
+
+    let expected = &rgb.flatten_to_u8()[0];
+    let actual = &im.flatten_to_u8()[0];
+    // integer and floating point conversions round differently
+    assert!(expected
+        .iter()
+        .zip(actual)
+        .all(|(a, b)| a.abs_diff(*b) <= 3));
+}
+
+#[test]
+fn test_argb_rgba_round_trip() {
+    let mut image = Image::from_u8(&[1, 2, 3, 4], 1, 1, ColorSpace::ARGB);
+
+    image.convert_color(ColorSpace::RGBA).unwrap();
+    assert_eq!(image.flatten_to_u8()[0], [2, 3, 4, 1]);
+
+    image.convert_color(ColorSpace::ARGB).unwrap();
+    assert_eq!(image.flatten_to_u8()[0], [1, 2, 3, 4]);
+}
+
+#[test]
+fn test_gray_to_rgb_layouts() {
+    let mut image = Image::from_u8(&[3, 70, 255, 0], 2, 1, ColorSpace::LumaA);
+    GrayToRgb::new().execute(&mut image).unwrap();
+
+    assert_eq!(image.colorspace(), ColorSpace::RGBA);
+    assert_eq!(image.flatten_to_u8()[0], [3, 3, 3, 70, 255, 255, 255, 0]);
+
+    let mut image = Image::from_u8(&[3, 70], 2, 1, ColorSpace::Luma);
+    GrayToRgb::new().execute(&mut image).unwrap();
+
+    assert_eq!(image.colorspace(), ColorSpace::RGB);
+    assert_eq!(image.flatten_to_u8()[0], [3, 3, 3, 70, 70, 70]);
+}
+
+#[test]
+fn test_add_alpha_layouts() {
+    for (from, to) in [
+        (ColorSpace::Luma, ColorSpace::LumaA),
+        (ColorSpace::RGB, ColorSpace::RGBA),
+        (ColorSpace::BGR, ColorSpace::BGRA)
+    ] {
+        let mut image = Image::fill(9_u16, from, 3, 2);
+        AddAlpha::new(0.0).execute(&mut image).unwrap();
+
+        assert_eq!(image.colorspace(), to);
+        let alpha = &image.frames_ref()[0].channels_ref(to, false)[to.num_components() - 1];
+        assert!(alpha
+            .reinterpret_as::<u16>()
+            .unwrap()
+            .iter()
+            .all(|x| *x == 0));
+    }
+    // images with alpha are untouched and out of range values are rejected
+    let mut image = Image::fill(9_u8, ColorSpace::ARGB, 3, 2);
+    AddAlpha::default().execute(&mut image).unwrap();
+    assert_eq!(image.colorspace(), ColorSpace::ARGB);
+
+    let mut image = Image::fill(9_u8, ColorSpace::Luma, 3, 2);
+    assert!(AddAlpha::new(2.0).execute(&mut image).is_err());
+}
@@ -0,0 +1,92 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! Full range RGB <-> YCbCr conversion
+//!
+//! Luma is the weighted sum given by [`LumaWeights`], and chroma the scaled blue and red
+//! differences from it, centered at `0.5`. With [`LumaWeights::Bt601`] this is the
+//! JFIF matrix JPEG decoders use.
+use crate::core_filters::colorspace::grayscale::LumaWeights;
+
+/// Convert RGB samples in `0.0..=1.0` to YCbCr in place
+pub fn rgb_to_ycbcr(r_y: &mut [f32], g_cb: &mut [f32], b_cr: &mut [f32], weights: LumaWeights) {
+    let [kr, kg, kb] = weights.coefficients();
+    let cb_scale = 0.5 / (1.0 - kb);
+    let cr_scale = 0.5 / (1.0 - kr);
+
+    r_y.iter_mut()
+        .zip(g_cb.iter_mut())
+        .zip(b_cr.iter_mut())
+        .for_each(|((r, g), b)| {
+            let y = kr * *r + kg * *g + kb * *b;
+
+            *g = (*b - y) * cb_scale + 0.5;
+            *b = (*r - y) * cr_scale + 0.5;
+            *r = y;
+        });
+}
+
+/// Convert YCbCr samples in `0.0..=1.0` to RGB in place, the inverse of [`rgb_to_ycbcr`]
+pub fn ycbcr_to_rgb(y_r: &mut [f32], cb_g: &mut [f32], cr_b: &mut [f32], weights: LumaWeights) {
+    let [kr, kg, kb] = weights.coefficients();
+    let cb_scale = 2.0 * (1.0 - kb);
+    let cr_scale = 2.0 * (1.0 - kr);
+
+    y_r.iter_mut()
+        .zip(cb_g.iter_mut())
+        .zip(cr_b.iter_mut())
+        .for_each(|((y, cb), cr)| {
+            let r = *y + (*cr - 0.5) * cr_scale;
+            let b = *y + (*cb - 0.5) * cb_scale;
+
+            *cb = (*y - kr * r - kb * b) / kg;
+            *y = r;
+            *cr = b;
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core_filters::colorspace::grayscale::LumaWeights;
+    use crate::core_filters::colorspace::ycbcr::{rgb_to_ycbcr, ycbcr_to_rgb};
+
+    #[test]
+    fn test_ycbcr_round_trip() {
+        let weights = [
+            LumaWeights::Bt601,
+            LumaWeights::Bt709,
+            LumaWeights::Bt2020,
+            LumaWeights::Average
+        ];
+        let rgb = [
+            [1.0, 0.0, 0.0],
+            [0.2, 0.9, 0.4],
+            [0.0, 0.0, 1.0],
+            [0.5, 0.5, 0.5]
+        ];
+
+        for weights in weights {
+            let mut r = rgb.map(|x| x[0]);
+            let mut g = rgb.map(|x| x[1]);
+            let mut b = rgb.map(|x| x[2]);
+
+            rgb_to_ycbcr(&mut r, &mut g, &mut b, weights);
+            // chroma stays in range, and grays have none
+            assert!(g.iter().chain(&b).all(|x| (0.0..=1.0001).contains(x)));
+            assert!((g[3] - 0.5).abs() < 1e-4 && (b[3] - 0.5).abs() < 1e-4);
+
+            ycbcr_to_rgb(&mut r, &mut g, &mut b, weights);
+
+            for (i, pixel) in rgb.iter().enumerate() {
+                for (a, e) in [r[i], g[i], b[i]].iter().zip(pixel) {
+                    assert!((a - e).abs() < 1e-4, "{weights:?}: {a} != {e}");
+                }
+            }
+        }
+    }
+}
@@ -15,6 +15,8 @@
 //!|BitDepth         |native type    |range      |
 //!|-----------------|---------------|-----------|
 //!|BitDepth::Eight  | [`u8`]        |0   - 255  |
+//!|BitDepth::Ten    | [`u16`]       |0   - 1023 |
+//!|BitDepth::Twelve | [`u16`]       |0   - 4095 |
 //!|BitDepth::Sixteen| [`u16`]       |0   - 65535|
 //!|BitDepth::F32    | [`f32`]       |0.0 - 1.0  |
 //!  
@@ -26,8 +28,24 @@
 //! when moving from `BitDepth::Eight` to `BitDepth::F32`, the library will automatically
 //! divide all pixels by `255.0` after converting them to f32's
 //!
+//! Depths sharing a native type are also rescaled, i.e moving from
+//! `BitDepth::Ten` to `BitDepth::Sixteen` maps `1023` to `65535`, the samples
+//! are never assumed to use the full range of their storage type.
+//!
+//! Integer conversions round to the nearest value, and samples above the
+//! maximum of their depth are treated as that maximum.
+//!
+//! This full range scaling keeps what samples mean relative to black and white,
+//! other policies, e.g keeping sample values as they are for measurement data,
+//! can be selected with [`DepthScale`].
+//!
+use std::mem::size_of;
+
+use bytemuck::Pod;
 use zune_core::bit_depth::{BitDepth, BitType};
 use zune_core::log::trace;
+#[cfg(feature = "threads")]
+use zune_core::threads::ThreadPool;
 
 use crate::channel::Channel;
 use crate::errors::ImageErrors;
@@ -45,7 +63,7 @@ use crate::traits::OperationsTrait;
 ///  - `to`: A mutable reference to pixels in 8 bit format where we will
 /// write our pixels
 /// - `max_value`: Maximum value we expect this pixel to store.
-#[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
+#[allow(clippy::cast_possible_truncation)]
 pub(crate) fn depth_u16_to_u8(from: &[u16], to: &mut [u8], max_value: u16) {
     if max_value == u16::MAX {
         // divide by 257, this clamps it to 0..255
@@ -54,13 +72,12 @@ pub(crate) fn depth_u16_to_u8(from: &[u16], to: &mut [u8], max_value: u16) {
             *new = new_val;
         }
     } else {
-        //okay do scaling
-        let max = 1.0 / f32::from(max_value);
-        let scale = 255.0;
+        // okay do scaling, rounding to the nearest value
+        let max = u32::from(max_value);
 
         for (old, new) in from.iter().zip(to.iter_mut()) {
-            let new_val = ((f32::from(*old) * max) * scale) as u8;
-            *new = new_val;
+            let new_val = (u32::from(*old).min(max) * 255 + max / 2) / max;
+            *new = new_val as u8;
         }
     }
 }
@@ -76,28 +93,212 @@ pub(crate) fn depth_u16_to_u8(from: &[u16], to: &mut [u8], max_value: u16) {
 ///  - `to`: A mutable reference to pixels in 8 bit format where we will
 /// write our pixels
 /// - `max_value`: Maximum value we expect this pixel to store.
-#[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
+#[allow(clippy::cast_possible_truncation)]
 pub(crate) fn depth_u8_to_u16(from: &[u8], to: &mut [u16], max_value: u16) {
-    // okay do scaling
-    let max = 1.0 / 255.0;
-    let scale = f32::from(max_value);
+    // okay do scaling, rounding to the nearest value
+    let max = u32::from(max_value);
 
     for (old, new) in from.iter().zip(to.iter_mut()) {
-        let new_val = ((f32::from(*old) * max) * scale) as u16;
-        *new = new_val;
+        let new_val = (u32::from(*old) * max + 127) / 255;
+        *new = new_val as u16;
+    }
+}
+
+/// Rescale u16 pixels in place from one maximum value to another
+///
+/// This converts between depths stored in a [`u16`], e.g 10 bit
+/// and 16 bit images, mapping `from_max` to `to_max` and darkest to zero.
+///
+/// # Arguments
+/// - `data`: Pixels to rescale
+/// - `from_max`: Maximum value the pixels currently store
+/// - `to_max`: Maximum value the pixels should store
+#[allow(clippy::cast_possible_truncation)]
+pub(crate) fn depth_u16_to_u16(data: &mut [u16], from_max: u16, to_max: u16) {
+    let from_max = u32::from(from_max);
+    let to_max = u32::from(to_max);
+
+    for pixel in data.iter_mut() {
+        let new_val = (u32::from(*pixel).min(from_max) * to_max + from_max / 2) / from_max;
+        *pixel = new_val as u16;
+    }
+}
+
+/// How sample values are mapped from one depth to another
+///
+/// Ranges below are those of the depths, e.g `0..=1023` for 10 bit and `0.0..=1.0` for float
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum DepthScale {
+    /// Map the range of the old depth to the range of the new one,
+    /// e.g 8 to 16 bit multiplies by 257 and 16 to 8 bit divides by 257, rounding
+    ///
+    /// Black stays black and white stays white.
+    #[default]
+    FullRange,
+    /// Shift samples between integer depths, replicating the high bits into
+    /// the new low bits when widening, e.g 8 to 10 bit is `(x << 2) | (x >> 6)`
+    ///
+    /// Narrowing drops the low bits without rounding, this matches what most
+    /// hardware and other libraries do. Conversions to or from float use [`DepthScale::FullRange`].
+    BitShift,
+    /// Keep sample values as they are, e.g `200` in 8 bit stays `200` in 16 bit
+    ///
+    /// Values that don't fit the new depth saturate at its maximum, and floats
+    /// are not clamped. This preserves statistics like the mean of measurement data,
+    /// but changes what the samples look like.
+    Preserve,
+    /// Map `black..=white`, in units of the old depth, to the range of the new depth,
+    /// clamping samples outside it
+    ///
+    /// This stretches data that only uses part of its range, e.g a 16 bit sensor
+    /// whose values lie in `1000..=5000`.
+    Range {
+        /// Sample value mapped to zero
+        black: f32,
+        /// Sample value mapped to the maximum of the new depth
+        white: f32
     }
 }
 
+impl DepthScale {
+    /// Map a sample in units of `from` to units of `to`, without rounding or clamping
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn scale(self, value: f64, from: BitDepth, to: BitDepth) -> f64 {
+        let from_max = f64::from(from.max_value());
+        let to_max = f64::from(to.max_value());
+
+        match self {
+            DepthScale::BitShift
+                if from.bit_type() != BitType::F32 && to.bit_type() != BitType::F32 =>
+            {
+                let from_bits = from.significant_bits();
+                let to_bits = to.significant_bits();
+                let value = value.min(from_max) as u32;
+
+                if to_bits <= from_bits {
+                    return f64::from(value >> (from_bits - to_bits));
+                }
+                // repeat the sample until it fills the new depth, then drop the excess
+                let (mut widened, mut filled) = (0_u64, 0);
+
+                while filled < to_bits {
+                    widened = (widened << from_bits) | u64::from(value);
+                    filled += from_bits;
+                }
+                (widened >> (filled - to_bits)) as f64
+            }
+            DepthScale::FullRange | DepthScale::BitShift => value.min(from_max) / from_max * to_max,
+            DepthScale::Preserve => value,
+            DepthScale::Range { black, white } => {
+                let (black, white) = (f64::from(black), f64::from(white));
+
+                (value - black) / (white - black) * to_max
+            }
+        }
+    }
+}
+
+/// Native types of depths, created from scaled values
+trait FromScaled: Pod {
+    fn from_scaled(value: f64, max: f64, clamp: bool) -> Self;
+}
+
+impl FromScaled for u8 {
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn from_scaled(value: f64, max: f64, _: bool) -> Self {
+        value.round().clamp(0.0, max) as u8
+    }
+}
+
+impl FromScaled for u16 {
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn from_scaled(value: f64, max: f64, _: bool) -> Self {
+        value.round().clamp(0.0, max) as u16
+    }
+}
+
+impl FromScaled for f32 {
+    #[allow(clippy::cast_possible_truncation)]
+    fn from_scaled(value: f64, max: f64, clamp: bool) -> Self {
+        if clamp {
+            value.clamp(0.0, max) as f32
+        } else {
+            value as f32
+        }
+    }
+}
+
+/// Convert a channel from depth `from` to `to` with a [`DepthScale`]
+///
+/// Integer samples go through a lookup table, so the scale is computed once per value
+fn scale_channel<T: FromScaled>(
+    channel: &Channel, from: BitDepth, to: BitDepth, scale: DepthScale
+) -> Result<Channel, ImageErrors> {
+    let to_max = f64::from(to.max_value());
+    let map = |value: f64| {
+        T::from_scaled(
+            scale.scale(value, from, to),
+            to_max,
+            scale != DepthScale::Preserve
+        )
+    };
+    let samples: Vec<T> = match from.bit_type() {
+        BitType::U8 => {
+            let table: Vec<T> = (0..=u8::MAX).map(|x| map(f64::from(x))).collect();
+
+            channel
+                .reinterpret_as::<u8>()?
+                .iter()
+                .map(|x| table[usize::from(*x)])
+                .collect()
+        }
+        BitType::U16 => {
+            let table: Vec<T> = (0..=u16::MAX).map(|x| map(f64::from(x))).collect();
+
+            channel
+                .reinterpret_as::<u16>()?
+                .iter()
+                .map(|x| table[usize::from(*x)])
+                .collect()
+        }
+        BitType::F32 => channel
+            .reinterpret_as::<f32>()?
+            .iter()
+            .map(|x| map(f64::from(*x)))
+            .collect(),
+        d => return Err(ImageErrors::ImageOperationNotImplemented("depth", d))
+    };
+    let mut new_channel = Channel::new_with_capacity::<T>(samples.len() * size_of::<T>());
+    new_channel.extend(&samples);
+
+    Ok(new_channel)
+}
+
 /// Change the image's bit depth from it's initial
 /// value to the one specified by this operation.
+///
+/// Samples are mapped with [`DepthScale::FullRange`] unless another
+/// scale is set with [`set_scale`](Depth::set_scale).
 #[derive(Copy, Clone)]
 pub struct Depth {
-    depth: BitDepth
+    depth: BitDepth,
+    scale: DepthScale
 }
 
 impl Depth {
     pub fn new(depth: BitDepth) -> Depth {
-        Depth { depth }
+        Depth {
+            depth,
+            scale: DepthScale::FullRange
+        }
+    }
+    /// Set how samples are mapped to the new depth
+    ///
+    /// Default is [`DepthScale::FullRange`]
+    #[must_use]
+    pub fn set_scale(mut self, scale: DepthScale) -> Depth {
+        self.scale = scale;
+        self
     }
 }
 
@@ -109,14 +310,47 @@ impl OperationsTrait for Depth {
     fn execute_impl(&self, image: &mut Image) -> Result<(), ImageErrors> {
         let image_depth = image.depth();
 
-        if image_depth == self.depth {
+        // a range stretches samples even within a depth
+        let stretches = matches!(self.scale, DepthScale::Range { .. });
+
+        if image_depth == self.depth && !stretches {
             trace!("Image depth already matches requested, no-op");
             return Ok(());
         }
 
-        for channel in image.channels_mut(false) {
-            match (image_depth, self.depth) {
-                (BitDepth::Eight, BitDepth::Sixteen) => {
+        if image_depth == BitDepth::Unknown || self.depth == BitDepth::Unknown {
+            let msg = format!(
+                "Unknown depth conversion from {:?} to {:?}",
+                image_depth, self.depth
+            );
+            return Err(ImageErrors::GenericString(msg));
+        }
+        if let DepthScale::Range { black, white } = self.scale {
+            if !(black.is_finite() && white.is_finite() && black < white) {
+                return Err(ImageErrors::GenericString(format!(
+                    "Invalid depth range, black ({black}) should be less than white ({white})"
+                )));
+            }
+        }
+
+        let convert = |channel: &mut Channel| {
+            if self.scale != DepthScale::FullRange {
+                *channel = match self.depth.bit_type() {
+                    BitType::U8 => {
+                        scale_channel::<u8>(channel, image_depth, self.depth, self.scale)?
+                    }
+                    BitType::U16 => {
+                        scale_channel::<u16>(channel, image_depth, self.depth, self.scale)?
+                    }
+                    BitType::F32 => {
+                        scale_channel::<f32>(channel, image_depth, self.depth, self.scale)?
+                    }
+                    d => return Err(ImageErrors::ImageOperationNotImplemented(self.name(), d))
+                };
+                return Ok(());
+            }
+            match (image_depth.bit_type(), self.depth.bit_type()) {
+                (BitType::U8, BitType::U16) => {
                     let old_data = channel.reinterpret_as().unwrap();
                     let mut new_channel = Channel::new_with_length::<u16>(old_data.len() * 2);
 
@@ -127,7 +361,7 @@ impl OperationsTrait for Depth {
                     *channel = new_channel;
                 }
 
-                (BitDepth::Sixteen, BitDepth::Eight) => {
+                (BitType::U16, BitType::U8) => {
                     let old_data = channel.reinterpret_as::<u16>().unwrap();
                     let mut new_channel = Channel::new_with_length::<u8>(channel.len() / 2);
 
@@ -137,7 +371,13 @@ impl OperationsTrait for Depth {
 
                     *channel = new_channel;
                 }
-                (BitDepth::Float32, BitDepth::Eight) => {
+                (BitType::U16, BitType::U16) => {
+                    // same storage, e.g 10 bit to 16 bit, rescale in place
+                    let data = channel.reinterpret_as_mut::<u16>().unwrap();
+
+                    depth_u16_to_u16(data, image_depth.max_value(), self.depth.max_value());
+                }
+                (BitType::F32, BitType::U8) => {
                     let old_data = channel.reinterpret_as::<f32>().unwrap();
                     let mut new_channel = Channel::new_with_length::<u8>(channel.len() / 4);
 
@@ -150,20 +390,22 @@ impl OperationsTrait for Depth {
 
                     *channel = new_channel;
                 }
-                (BitDepth::Float32, BitDepth::Sixteen) => {
+                (BitType::F32, BitType::U16) => {
                     let old_data = channel.reinterpret_as::<f32>().unwrap();
                     let mut new_channel = Channel::new_with_length::<u16>(channel.len() / 2);
 
                     let new_channel_raw = new_channel.reinterpret_as_mut::<u16>().unwrap();
 
-                    // scale by multiplying with 65535
+                    // scale by multiplying with the max value, e.g 65535 for 16 bit
+                    let max = f32::from(self.depth.max_value());
+
                     for (old_chan, new_chan) in old_data.iter().zip(new_channel_raw.iter_mut()) {
-                        *new_chan = (65535.0 * old_chan).clamp(0.0, 65535.0) as u16;
+                        *new_chan = (max * old_chan).round().clamp(0.0, max) as u16;
                     }
 
                     *channel = new_channel;
                 }
-                (BitDepth::Eight, BitDepth::Float32) => {
+                (BitType::U8, BitType::F32) => {
                     let old_data = channel.reinterpret_as::<u8>().unwrap();
                     let mut new_channel = Channel::new_with_length::<f32>(old_data.len() * 4);
 
@@ -177,14 +419,14 @@ impl OperationsTrait for Depth {
 
                     *channel = new_channel;
                 }
-                (BitDepth::Sixteen, BitDepth::Float32) => {
+                (BitType::U16, BitType::F32) => {
                     let old_data = channel.reinterpret_as::<u16>().unwrap();
                     let mut new_channel = Channel::new_with_length::<f32>(old_data.len() * 4);
 
                     let new_channel_raw = new_channel.reinterpret_as_mut::<f32>().unwrap();
 
-                    // scale by dividing with 65535
-                    let recip = 1.0 / 65535.0;
+                    // scale by dividing with the max value, e.g 65535 for 16 bit
+                    let recip = 1.0 / f32::from(image_depth.max_value());
 
                     for (old_chan, new_chan) in old_data.iter().zip(new_channel_raw.iter_mut()) {
                         *new_chan = f32::from(*old_chan) * recip;
@@ -202,7 +444,17 @@ impl OperationsTrait for Depth {
                     return Err(ImageErrors::GenericString(msg));
                 }
             }
-        }
+            Ok(())
+        };
+        #[cfg(feature = "threads")]
+        ThreadPool::new().try_for_each(image.channels_mut(false), convert)?;
+
+        #[cfg(not(feature = "threads"))]
+        image
+            .channels_mut(false)
+            .into_iter()
+            .try_for_each(convert)?;
+
         trace!("Image depth changed to {:?}", self.depth);
 
         image.set_depth(self.depth);
@@ -212,4 +464,162 @@ impl OperationsTrait for Depth {
     fn supported_types(&self) -> &'static [BitType] {
         &[BitType::U8, BitType::U16, BitType::F32]
     }
+
+    fn output_depth(&self, _: BitDepth) -> BitDepth {
+        self.depth
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use zune_core::bit_depth::BitDepth;
+    use zune_core::colorspace::ColorSpace;
+
+    use crate::core_filters::depth::{Depth, DepthScale};
+    use crate::image::Image;
+    use crate::traits::OperationsTrait;
+
+    fn convert(pixels: &[u16], from: BitDepth, to: BitDepth) -> Image {
+        let mut image = Image::from_u16(pixels, pixels.len(), 1, ColorSpace::Luma);
+        image.set_depth(from);
+
+        Depth::new(to).execute(&mut image).unwrap();
+        assert_eq!(image.depth(), to);
+        image
+    }
+
+    fn u16_pixels(image: &Image) -> Vec<u16> {
+        image.channels_ref(false)[0]
+            .reinterpret_as::<u16>()
+            .unwrap()
+            .to_vec()
+    }
+
+    #[test]
+    fn test_ten_bit_uses_its_range() {
+        let image = convert(&[0, 512, 1023], BitDepth::Ten, BitDepth::Eight);
+        let channel = &image.channels_ref(false)[0];
+        assert_eq!(channel.reinterpret_as::<u8>().unwrap(), &[0, 128, 255]);
+
+        let image = convert(&[0, 512, 1023], BitDepth::Ten, BitDepth::Sixteen);
+        assert_eq!(u16_pixels(&image), &[0, 32800, 65535]);
+
+        let image = convert(&[0, 4095], BitDepth::Twelve, BitDepth::Float32);
+        let channel = &image.channels_ref(false)[0];
+        assert_eq!(channel.reinterpret_as::<f32>().unwrap(), &[0.0, 1.0]);
+    }
+
+    #[test]
+    fn test_u16_depths_round_trip() {
+        let ten: Vec<u16> = (0..1024).collect();
+        let twelve: Vec<u16> = (0..4096).collect();
+
+        for (pixels, depth) in [(&ten, BitDepth::Ten), (&twelve, BitDepth::Twelve)] {
+            for other in [BitDepth::Twelve, BitDepth::Sixteen, BitDepth::Float32] {
+                if other == depth {
+                    continue;
+                }
+                let mut image = convert(pixels, depth, other);
+                Depth::new(depth).execute(&mut image).unwrap();
+
+                assert_eq!(&u16_pixels(&image), pixels, "{depth:?} -> {other:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_eight_bit_round_trip() {
+        let pixels: Vec<u8> = (0..=255).collect();
+
+        for depth in [BitDepth::Ten, BitDepth::Twelve, BitDepth::Sixteen] {
+            let mut image = Image::from_u8(&pixels, pixels.len(), 1, ColorSpace::Luma);
+
+            Depth::new(depth).execute(&mut image).unwrap();
+            assert_eq!(
+                u16_pixels(&image).last(),
+                Some(&depth.max_value()),
+                "{depth:?}"
+            );
+            Depth::new(BitDepth::Eight).execute(&mut image).unwrap();
+
+            let channel = &image.channels_ref(false)[0];
+            assert_eq!(channel.reinterpret_as::<u8>().unwrap(), &pixels);
+        }
+    }
+
+    fn scale(pixels: &[u16], from: BitDepth, to: BitDepth, scale: DepthScale) -> Vec<f32> {
+        let mut image = Image::from_u16(pixels, pixels.len(), 1, ColorSpace::Luma);
+        image.set_depth(from);
+
+        image.convert_depth_with_scale(to, scale).unwrap();
+        // compare as floats whatever the depth
+        image
+            .convert_depth_with_scale(BitDepth::Float32, DepthScale::Preserve)
+            .unwrap();
+        image.channels_ref(false)[0]
+            .reinterpret_as::<f32>()
+            .unwrap()
+            .to_vec()
+    }
+
+    #[test]
+    fn test_depth_scales() {
+        let (ten, sixteen) = (BitDepth::Ten, BitDepth::Sixteen);
+        let pixels = [0, 128, 255];
+
+        assert_eq!(
+            scale(&pixels, sixteen, BitDepth::Eight, DepthScale::Preserve),
+            [0.0, 128.0, 255.0]
+        );
+        // replicated bits, and dropped on the way back
+        let shifted = scale(
+            &[0, 128, 255, 514],
+            BitDepth::Twelve,
+            ten,
+            DepthScale::BitShift
+        );
+        assert_eq!(shifted, [0.0, 32.0, 63.0, 128.0]);
+
+        let mut image = Image::from_u8(&[0, 128, 255], 3, 1, ColorSpace::Luma);
+        image
+            .convert_depth_with_scale(ten, DepthScale::BitShift)
+            .unwrap();
+        assert_eq!(u16_pixels(&image), [0, 514, 1023]);
+        image
+            .convert_depth_with_scale(sixteen, DepthScale::BitShift)
+            .unwrap();
+        assert_eq!(u16_pixels(&image), [0, 32928, 65535]);
+
+        // values saturate, statistics of samples in range are kept
+        let mut image = Image::from_u16(&[300, 40], 2, 1, ColorSpace::Luma);
+        image
+            .convert_depth_with_scale(BitDepth::Eight, DepthScale::Preserve)
+            .unwrap();
+        let channel = &image.channels_ref(false)[0];
+        assert_eq!(channel.reinterpret_as::<u8>().unwrap(), &[255, 40]);
+
+        let range = DepthScale::Range {
+            black: 1000.0,
+            white: 2000.0
+        };
+        assert_eq!(
+            scale(&[500, 1000, 1500, 3000], sixteen, BitDepth::Eight, range),
+            [0.0, 0.0, 128.0, 255.0]
+        );
+        // within the same depth
+        assert_eq!(scale(&[1500], sixteen, sixteen, range), [32768.0]);
+        let inverted = DepthScale::Range {
+            black: 2.0,
+            white: 1.0
+        };
+        assert!(Image::from_u8(&[1], 1, 1, ColorSpace::Luma)
+            .convert_depth_with_scale(sixteen, inverted)
+            .is_err());
+    }
+
+    #[test]
+    fn test_out_of_range_samples_saturate() {
+        let image = convert(&[2000, u16::MAX], BitDepth::Ten, BitDepth::Sixteen);
+        assert_eq!(u16_pixels(&image), &[65535, 65535]);
+    }
 }
@@ -0,0 +1,123 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+//! Metadata editing
+//!
+//! This removes selected metadata categories from an image
+//! so that encoders do not carry them over to the output, and sets
+//! metadata that doesn't change pixels
+use zune_core::bit_depth::BitType;
+use zune_core::log::trace;
+
+use crate::errors::ImageErrors;
+use crate::image::Image;
+use crate::metadata::{MetadataCategory, PhysicalDimensions};
+use crate::traits::OperationsTrait;
+
+/// Remove metadata categories from an image
+///
+/// Pixels are left untouched, only the image metadata is modified
+///
+/// # Example
+/// ```
+/// use zune_core::colorspace::ColorSpace;
+/// use zune_image::core_filters::metadata::StripMetadata;
+/// use zune_image::image::Image;
+/// use zune_image::metadata::MetadataCategory;
+/// use zune_image::traits::OperationsTrait;
+///
+/// let mut image = Image::fill(0_u8, ColorSpace::RGB, 10, 10);
+/// image.metadata_mut().set_icc_chunk(vec![0; 10]);
+///
+/// StripMetadata::new(&MetadataCategory::ALL).execute(&mut image).unwrap();
+/// assert!(image.metadata().icc_chunk().is_none());
+/// ```
+pub struct StripMetadata {
+    categories: Vec<MetadataCategory>
+}
+
+impl StripMetadata {
+    /// Create a new operation which removes metadata in `categories`
+    pub fn new(categories: &[MetadataCategory]) -> StripMetadata {
+        StripMetadata {
+            categories: categories.to_vec()
+        }
+    }
+}
+
+impl OperationsTrait for StripMetadata {
+    fn name(&self) -> &'static str {
+        "Strip metadata"
+    }
+
+    fn execute_impl(&self, image: &mut Image) -> Result<(), ImageErrors> {
+        for category in &self.categories {
+            trace!("Removing {:?} metadata", category);
+            image.metadata_mut().strip(*category);
+        }
+        Ok(())
+    }
+
+    fn supported_types(&self) -> &'static [BitType] {
+        &[BitType::U8, BitType::U16, BitType::F32]
+    }
+}
+
+/// Set the pixel density of an image, in pixels per inch
+///
+/// Pixels are left untouched, encoders which can store the density
+/// write it to the output
+///
+/// # Example
+/// ```
+/// use zune_core::colorspace::ColorSpace;
+/// use zune_image::core_filters::metadata::SetDpi;
+/// use zune_image::image::Image;
+/// use zune_image::traits::OperationsTrait;
+///
+/// let mut image = Image::fill(0_u8, ColorSpace::RGB, 10, 10);
+///
+/// SetDpi::new(300.0, 300.0).execute(&mut image).unwrap();
+/// assert_eq!(image.metadata().physical_dimensions().unwrap().dpi(), Some((300.0, 300.0)));
+/// ```
+pub struct SetDpi {
+    x_dpi: f32,
+    y_dpi: f32
+}
+
+impl SetDpi {
+    /// Create a new operation which sets the horizontal and vertical density
+    /// to `x_dpi` and `y_dpi` pixels per inch
+    pub fn new(x_dpi: f32, y_dpi: f32) -> SetDpi {
+        SetDpi { x_dpi, y_dpi }
+    }
+}
+
+impl OperationsTrait for SetDpi {
+    fn name(&self) -> &'static str {
+        "Set DPI"
+    }
+
+    fn execute_impl(&self, image: &mut Image) -> Result<(), ImageErrors> {
+        let valid = |dpi: f32| dpi.is_finite() && dpi > 0.0;
+
+        if !valid(self.x_dpi) || !valid(self.y_dpi) {
+            return Err(ImageErrors::GenericString(format!(
+                "DPI must be positive, got {}x{}",
+                self.x_dpi, self.y_dpi
+            )));
+        }
+        image
+            .metadata_mut()
+            .set_physical_dimensions(PhysicalDimensions::from_dpi(self.x_dpi, self.y_dpi));
+        Ok(())
+    }
+
+    fn supported_types(&self) -> &'static [BitType] {
+        &[BitType::U8, BitType::U16, BitType::F32]
+    }
+}
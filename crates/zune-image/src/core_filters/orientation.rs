@@ -0,0 +1,200 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! Exif orientation
+//!
+//! Cameras usually store pixels the way the sensor read them and record how the
+//! image should be turned for display in the exif orientation tag.
+//!
+//! [`ApplyOrientation`] moves the pixels so that the image is upright and resets
+//! the tag, which is what viewers that ignore exif need. Keeping the pixels and the tag
+//! untouched is lossless and cheaper, see [`OrientationPolicy`](crate::pipelines::OrientationPolicy)
+//! for choosing between the two in a pipeline.
+use zune_core::bit_depth::BitType;
+use zune_core::log::{trace, warn};
+
+use crate::channel::Channel;
+use crate::errors::ImageErrors;
+use crate::image::Image;
+use crate::traits::OperationsTrait;
+
+/// Rotate and mirror an image so that it is upright, according to its exif orientation
+///
+/// The orientation tag is then set to 1 (upright), so that viewers honouring it
+/// do not turn the image a second time.
+///
+/// This is a no-op for images without an orientation tag, or if the `metadata`
+/// feature is not enabled.
+///
+/// # Example
+/// ```
+/// use zune_core::colorspace::ColorSpace;
+/// use zune_image::core_filters::orientation::ApplyOrientation;
+/// use zune_image::image::Image;
+/// use zune_image::traits::OperationsTrait;
+///
+/// let mut image = Image::fill(0_u8, ColorSpace::RGB, 20, 10);
+/// ApplyOrientation::new().execute(&mut image).unwrap();
+/// ```
+#[derive(Copy, Clone, Debug, Default)]
+pub struct ApplyOrientation;
+
+impl ApplyOrientation {
+    /// Create a new orientation operation
+    #[must_use]
+    pub fn new() -> ApplyOrientation {
+        ApplyOrientation
+    }
+}
+
+impl OperationsTrait for ApplyOrientation {
+    fn name(&self) -> &'static str {
+        "Apply orientation"
+    }
+
+    fn execute_impl(&self, image: &mut Image) -> Result<(), ImageErrors> {
+        let Some(orientation) = image.metadata().exif_orientation() else {
+            return Ok(());
+        };
+        match orientation {
+            1 => return Ok(()),
+            2..=8 => {}
+            _ => {
+                warn!("Unknown exif orientation {orientation}, ignoring it");
+                return Ok(());
+            }
+        }
+        trace!("Applying exif orientation {orientation}");
+
+        let (width, height) = image.dimensions();
+        let depth = image.depth().bit_type();
+
+        for channel in image.channels_mut(false) {
+            let mut new_channel =
+                Channel::new_with_length_and_type(channel.len(), channel.get_type_id());
+
+            match depth {
+                BitType::U8 => reorient::<u8>(
+                    orientation,
+                    width,
+                    height,
+                    channel.reinterpret_as()?,
+                    new_channel.reinterpret_as_mut()?
+                ),
+                BitType::U16 => reorient::<u16>(
+                    orientation,
+                    width,
+                    height,
+                    channel.reinterpret_as()?,
+                    new_channel.reinterpret_as_mut()?
+                ),
+                BitType::F32 => reorient::<f32>(
+                    orientation,
+                    width,
+                    height,
+                    channel.reinterpret_as()?,
+                    new_channel.reinterpret_as_mut()?
+                ),
+                d => return Err(ImageErrors::ImageOperationNotImplemented(self.name(), d))
+            }
+            *channel = new_channel;
+        }
+        // orientations 5 to 8 swap rows and columns
+        if orientation >= 5 {
+            image.set_dimensions(height, width);
+        }
+        image.metadata_mut().set_exif_orientation(1);
+
+        Ok(())
+    }
+
+    fn supported_types(&self) -> &'static [BitType] {
+        &[BitType::U8, BitType::U16, BitType::F32]
+    }
+}
+
+/// Write `input`, stored with the exif `orientation`, upright into `output`
+///
+/// `width` and `height` are the dimensions of `input`
+fn reorient<T: Copy>(orientation: u16, width: usize, height: usize, input: &[T], output: &mut [T]) {
+    let out_width = if orientation >= 5 { height } else { width };
+
+    for (y, row) in output.chunks_exact_mut(out_width).enumerate() {
+        for (x, pixel) in row.iter_mut().enumerate() {
+            let (in_x, in_y) = match orientation {
+                // mirrored horizontally
+                2 => (width - 1 - x, y),
+                // rotated 180 degrees
+                3 => (width - 1 - x, height - 1 - y),
+                // mirrored vertically
+                4 => (x, height - 1 - y),
+                // transposed
+                5 => (y, x),
+                // needs a 90 degree clockwise rotation
+                6 => (y, height - 1 - x),
+                // transversed
+                7 => (width - 1 - y, height - 1 - x),
+                // needs a 90 degree anti-clockwise rotation
+                8 => (width - 1 - y, x),
+                _ => (x, y)
+            };
+            *pixel = input[in_y * width + in_x];
+        }
+    }
+}
+
+#[cfg(all(test, feature = "metadata"))]
+mod tests {
+    use exif::{Field, In, Tag, Value};
+    use zune_core::colorspace::ColorSpace;
+
+    use crate::core_filters::orientation::ApplyOrientation;
+    use crate::image::Image;
+    use crate::traits::OperationsTrait;
+
+    /// A 3x2 image with pixels numbered in storage order, tagged with `orientation`
+    fn tagged_image(orientation: u16) -> Image {
+        let mut image = Image::from_u8(&[1, 2, 3, 4, 5, 6], 3, 2, ColorSpace::Luma);
+
+        image.metadata_mut().exif = Some(vec![Field {
+            tag:     Tag::Orientation,
+            ifd_num: In::PRIMARY,
+            value:   Value::Short(vec![orientation])
+        }]);
+        image
+    }
+
+    #[test]
+    fn test_orientations_are_upright() {
+        // stored pixels   1 2 3
+        //                 4 5 6
+        let expected: [((usize, usize), [u8; 6]); 8] = [
+            ((3, 2), [1, 2, 3, 4, 5, 6]),
+            ((3, 2), [3, 2, 1, 6, 5, 4]),
+            ((3, 2), [6, 5, 4, 3, 2, 1]),
+            ((3, 2), [4, 5, 6, 1, 2, 3]),
+            ((2, 3), [1, 4, 2, 5, 3, 6]),
+            ((2, 3), [4, 1, 5, 2, 6, 3]),
+            ((2, 3), [6, 3, 5, 2, 4, 1]),
+            ((2, 3), [3, 6, 2, 5, 1, 4])
+        ];
+        for (orientation, (dimensions, pixels)) in (1..).zip(expected) {
+            let mut image = tagged_image(orientation);
+
+            ApplyOrientation::new().execute(&mut image).unwrap();
+
+            assert_eq!(image.dimensions(), dimensions, "orientation {orientation}");
+            assert_eq!(
+                image.flatten_to_u8()[0],
+                pixels,
+                "orientation {orientation}"
+            );
+            assert_eq!(image.metadata().exif_orientation(), Some(1));
+        }
+    }
+}
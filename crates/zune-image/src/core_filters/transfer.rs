@@ -0,0 +1,384 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! sRGB transfer function conversions
+//!
+//! Most images store sRGB encoded values, which are roughly perceptually uniform
+//! but not proportional to light intensity, so averaging them (e.g when blurring or resizing)
+//! darkens edges between bright and dark areas.
+//!
+//! This module contains the sRGB transfer functions, [`TransferLut`] which evaluates them
+//! quickly, and the [`ToLinearLight`] and [`FromLinearLight`] operations which move whole
+//! images between encoded values and linear light.
+//!
+//! Images in linear light are stored as floats and their metadata carries
+//! [`ColorCharacteristics::Linear`], so operations that linearize internally
+//! can tell when the work is already done.
+//!
+//! # Example
+//! ```
+//! use zune_core::bit_depth::BitDepth;
+//! use zune_core::colorspace::ColorSpace;
+//! use zune_image::core_filters::transfer::{FromLinearLight, ToLinearLight};
+//! use zune_image::errors::ImageErrors;
+//! use zune_image::image::Image;
+//! use zune_image::traits::OperationsTrait;
+//!
+//! let mut image = Image::from_u8(&[0, 128, 255], 3, 1, ColorSpace::Luma);
+//!
+//! ToLinearLight::new().execute(&mut image)?;
+//! assert_eq!(image.depth(), BitDepth::Float32);
+//!
+//! FromLinearLight::new(BitDepth::Eight).execute(&mut image)?;
+//! assert_eq!(image.flatten_to_u8()[0], [0, 128, 255]);
+//! # Ok::<(),ImageErrors>(())
+//! ```
+use zune_core::bit_depth::{BitDepth, BitType};
+use zune_core::colorspace::{ColorCharacteristics, ColorSpace};
+use zune_core::log::trace;
+
+use crate::channel::Channel;
+use crate::errors::ImageErrors;
+use crate::image::Image;
+use crate::traits::OperationsTrait;
+
+/// Number of entries in the transfer function tables
+const LUT_SIZE: usize = 4096;
+
+/// Decode an sRGB encoded value to linear light
+#[must_use]
+pub fn srgb_to_linear(x: f32) -> f32 {
+    if x <= 0.040_45 {
+        x / 12.92
+    } else {
+        ((x + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Encode a linear light value with the sRGB transfer function
+#[must_use]
+pub fn linear_to_srgb(x: f32) -> f32 {
+    if x <= 0.003_130_8 {
+        x * 12.92
+    } else {
+        1.055 * x.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Colorspaces whose channels are sRGB encoded and can be linearized
+#[must_use]
+pub const fn is_srgb(colorspace: ColorSpace) -> bool {
+    matches!(
+        colorspace,
+        ColorSpace::RGB
+            | ColorSpace::RGBA
+            | ColorSpace::BGR
+            | ColorSpace::BGRA
+            | ColorSpace::ARGB
+            | ColorSpace::Luma
+            | ColorSpace::LumaA
+    )
+}
+
+/// Return true if the image metadata marks its pixels as linear light
+#[must_use]
+pub fn is_linear_light(image: &Image) -> bool {
+    image.metadata().get_color_trc() == Some(ColorCharacteristics::Linear)
+}
+
+/// A transfer function sampled over `[0,1]`, values in between entries
+/// are linearly interpolated
+///
+/// This is several times faster than calling the function for every pixel,
+/// with an error well below what 16 bit images can represent
+pub struct TransferLut {
+    table: Vec<f32>,
+    func:  fn(f32) -> f32
+}
+
+impl TransferLut {
+    /// Sample `func`, which must map `0.0` to `0.0`
+    #[allow(clippy::cast_precision_loss)]
+    pub fn new(func: fn(f32) -> f32) -> TransferLut {
+        let table = (0..=LUT_SIZE)
+            .map(|x| func(x as f32 / LUT_SIZE as f32))
+            .collect();
+
+        TransferLut { table, func }
+    }
+    /// A table decoding sRGB values to linear light
+    pub fn srgb_decode() -> TransferLut {
+        TransferLut::new(srgb_to_linear)
+    }
+    /// A table encoding linear light to sRGB values
+    pub fn srgb_encode() -> TransferLut {
+        TransferLut::new(linear_to_srgb)
+    }
+
+    /// Evaluate the function at `value`, negative values map to zero
+    #[allow(
+        clippy::cast_precision_loss,
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss
+    )]
+    pub fn apply(&self, value: f32) -> f32 {
+        if value <= 0.0 {
+            return 0.0;
+        }
+        if value >= 1.0 {
+            // out of range values (e.g in float images) use the exact function
+            return (self.func)(value);
+        }
+        let position = value * LUT_SIZE as f32;
+        let index = position as usize;
+        let fraction = position - index as f32;
+
+        self.table[index] + (self.table[index + 1] - self.table[index]) * fraction
+    }
+}
+
+/// Run the color channels of a float image through `lut`, leaving alpha untouched
+///
+/// Premultiplied colors are divided by alpha before the transfer function
+/// and multiplied back after
+fn transfer(image: &mut Image, lut: &TransferLut) -> Result<(), ImageErrors> {
+    let colorspace = image.colorspace();
+    let alpha_position = colorspace.alpha_position();
+    let premultiplied = image.metadata().is_premultiplied_alpha();
+
+    for frame in image.frames_mut() {
+        let channels = frame.channels_mut(colorspace, false);
+
+        let alpha = match alpha_position {
+            Some(position) if premultiplied => {
+                Some(channels[position].reinterpret_as::<f32>()?.to_vec())
+            }
+            _ => None
+        };
+
+        for (position, channel) in channels.iter_mut().enumerate() {
+            if Some(position) == alpha_position {
+                continue;
+            }
+            let values = channel.reinterpret_as_mut::<f32>()?;
+
+            if let Some(alpha) = &alpha {
+                for (x, a) in values.iter_mut().zip(alpha) {
+                    *x = if *a > 0.0 { lut.apply(*x / a) * a } else { 0.0 };
+                }
+            } else {
+                for x in values {
+                    *x = lut.apply(*x);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Round a float channel in `0.0..=1.0` to an integer depth
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn quantize(channel: &Channel, depth: BitDepth) -> Result<Channel, ImageErrors> {
+    let values = channel.reinterpret_as::<f32>()?;
+    let max_value = f32::from(depth.max_value());
+
+    let channel = match depth.bit_type() {
+        BitType::U8 => {
+            let mut channel = Channel::new_with_length::<u8>(values.len());
+
+            for (out, x) in channel.reinterpret_as_mut::<u8>()?.iter_mut().zip(values) {
+                *out = (x * max_value).round().clamp(0.0, max_value) as u8;
+            }
+            channel
+        }
+        BitType::U16 => {
+            let mut channel = Channel::new_with_length::<u16>(values.len() * 2);
+
+            for (out, x) in channel.reinterpret_as_mut::<u16>()?.iter_mut().zip(values) {
+                *out = (x * max_value).round().clamp(0.0, max_value) as u16;
+            }
+            channel
+        }
+        d => {
+            return Err(ImageErrors::ImageOperationNotImplemented(
+                "From linear light",
+                d
+            ))
+        }
+    };
+    Ok(channel)
+}
+
+/// Convert an image to linear light
+///
+/// The image is converted to [`BitDepth::Float32`] and, if its colorspace
+/// is sRGB encoded (RGB and luma variants, see [`is_srgb`]), its color channels
+/// are decoded to linear light and the metadata is marked with [`ColorCharacteristics::Linear`].
+///
+/// Images already marked as linear are only converted to float,
+/// other colorspaces are converted to float and left as is.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct ToLinearLight;
+
+impl ToLinearLight {
+    /// Create a new linear light conversion
+    #[must_use]
+    pub fn new() -> ToLinearLight {
+        ToLinearLight
+    }
+}
+
+impl OperationsTrait for ToLinearLight {
+    fn name(&self) -> &'static str {
+        "To linear light"
+    }
+
+    fn execute_impl(&self, image: &mut Image) -> Result<(), ImageErrors> {
+        image.convert_depth(BitDepth::Float32)?;
+
+        if !is_srgb(image.colorspace()) || is_linear_light(image) {
+            trace!("Image is not sRGB encoded, skipping linearization");
+            return Ok(());
+        }
+        transfer(image, &TransferLut::srgb_decode())?;
+
+        image
+            .metadata_mut()
+            .set_color_trc(ColorCharacteristics::Linear);
+
+        Ok(())
+    }
+
+    fn supported_types(&self) -> &'static [BitType] {
+        &[BitType::U8, BitType::U16, BitType::F32]
+    }
+
+    fn output_depth(&self, _: BitDepth) -> BitDepth {
+        BitDepth::Float32
+    }
+}
+
+/// Convert an image from linear light back to sRGB encoded values
+/// in a specific depth
+///
+/// This undoes [`ToLinearLight`], images that are not marked as linear light
+/// are only converted to `depth`.
+///
+/// Unlike [`Depth`](crate::core_filters::depth::Depth), floats are rounded to the
+/// nearest integer, so a round trip through linear light returns the original values
+#[derive(Copy, Clone, Debug)]
+pub struct FromLinearLight {
+    depth: BitDepth
+}
+
+impl FromLinearLight {
+    /// Create a new conversion from linear light
+    ///
+    /// # Arguments
+    /// - depth: Depth of the image after conversion
+    #[must_use]
+    pub fn new(depth: BitDepth) -> FromLinearLight {
+        FromLinearLight { depth }
+    }
+}
+
+impl OperationsTrait for FromLinearLight {
+    fn name(&self) -> &'static str {
+        "From linear light"
+    }
+
+    fn execute_impl(&self, image: &mut Image) -> Result<(), ImageErrors> {
+        if is_srgb(image.colorspace()) && is_linear_light(image) {
+            image.convert_depth(BitDepth::Float32)?;
+            transfer(image, &TransferLut::srgb_encode())?;
+
+            image
+                .metadata_mut()
+                .set_color_trc(ColorCharacteristics::sRGB);
+        } else {
+            trace!("Image is not in linear light, only converting depth");
+        }
+
+        if image.depth() != BitDepth::Float32 || self.depth == BitDepth::Float32 {
+            return image.convert_depth(self.depth);
+        }
+        let colorspace = image.colorspace();
+
+        for frame in image.frames_mut() {
+            for channel in frame.channels_mut(colorspace, false) {
+                *channel = quantize(channel, self.depth)?;
+            }
+        }
+        image.set_depth(self.depth);
+
+        Ok(())
+    }
+
+    fn supported_types(&self) -> &'static [BitType] {
+        &[BitType::U8, BitType::U16, BitType::F32]
+    }
+
+    fn output_depth(&self, _: BitDepth) -> BitDepth {
+        self.depth
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use zune_core::bit_depth::BitDepth;
+    use zune_core::colorspace::{ColorCharacteristics, ColorSpace};
+
+    use crate::core_filters::transfer::{
+        linear_to_srgb, srgb_to_linear, FromLinearLight, ToLinearLight, TransferLut
+    };
+    use crate::image::Image;
+    use crate::traits::OperationsTrait;
+
+    #[test]
+    fn test_lut_matches_function() {
+        let decode = TransferLut::srgb_decode();
+        let encode = TransferLut::srgb_encode();
+
+        for x in (0..=1000).map(|x| x as f32 / 1000.0) {
+            assert!((decode.apply(x) - srgb_to_linear(x)).abs() < 1e-5);
+            assert!((encode.apply(x) - linear_to_srgb(x)).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn test_round_trip_u8() {
+        let pixels: Vec<u8> = (0..=255).collect();
+        let mut image = Image::from_u8(&pixels, 256, 1, ColorSpace::Luma);
+
+        ToLinearLight::new().execute(&mut image).unwrap();
+        assert_eq!(
+            image.metadata().get_color_trc(),
+            Some(ColorCharacteristics::Linear)
+        );
+        // middle gray is about a fifth of the light of white
+        let linear = image.flatten_to_u8();
+        assert_eq!(linear[0][128], 55);
+
+        FromLinearLight::new(BitDepth::Eight)
+            .execute(&mut image)
+            .unwrap();
+        assert_eq!(image.depth(), BitDepth::Eight);
+        assert_eq!(image.flatten_to_u8()[0], pixels);
+    }
+
+    #[test]
+    fn test_alpha_untouched() {
+        let mut image = Image::from_u8(&[128, 128], 1, 1, ColorSpace::LumaA);
+
+        ToLinearLight::new().execute(&mut image).unwrap();
+
+        let channels = image.frames_ref()[0].channels_ref(ColorSpace::LumaA, false);
+        let alpha = channels[1].reinterpret_as::<f32>().unwrap()[0];
+        assert!((alpha - 128.0 / 255.0).abs() < 1e-6);
+    }
+}
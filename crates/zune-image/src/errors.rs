@@ -13,6 +13,7 @@ use std::io::Error;
 
 use zune_core::bit_depth::BitType;
 use zune_core::colorspace::ColorSpace;
+use zune_core::diagnostics::ByteContext;
 
 use crate::channel::ChannelErrors;
 use crate::codecs::ImageFormat;
@@ -23,6 +24,9 @@ use crate::codecs::ImageFormat;
 /// all decoding,processing and encoding errors possible
 pub enum ImageErrors {
     ImageDecodeErrors(String),
+    /// A decoding error with the bytes around the position it occurred at,
+    /// see [`ImageErrors::context`]
+    ImageDecodeErrorsWithContext(String, ByteContext),
     DimensionsMisMatch(usize, usize),
     UnsupportedColorspace(ColorSpace, &'static str, &'static [ColorSpace]),
     NoImageForOperations,
@@ -37,7 +41,9 @@ pub enum ImageErrors {
     ImageDecoderNotIncluded(ImageFormat),
     ImageDecoderNotImplemented(ImageFormat),
     IoError(std::io::Error),
-    ImageOperationNotImplemented(&'static str, BitType)
+    ImageOperationNotImplemented(&'static str, BitType),
+    /// The work was stopped by a [`CancellationToken`](crate::cancel::CancellationToken)
+    Cancelled
 }
 
 /// Errors that may occur during image operations
@@ -70,7 +76,7 @@ pub enum ImgEncodeErrors {
 impl Debug for ImageErrors {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::ImageDecodeErrors(err) => {
+            Self::ImageDecodeErrors(err) | Self::ImageDecodeErrorsWithContext(err, _) => {
                 writeln!(f, "{err}")
             }
 
@@ -132,6 +138,23 @@ impl Debug for ImageErrors {
                     op_type, depth
                 )
             }
+            ImageErrors::Cancelled => {
+                writeln!(f, "Processing was cancelled")
+            }
+        }
+    }
+}
+
+impl ImageErrors {
+    /// Return the bytes around the position decoding failed at, if the decoder
+    /// reported them
+    ///
+    /// This requires the `diagnostics` feature, and is currently
+    /// supported by the png decoder
+    pub fn context(&self) -> Option<&ByteContext> {
+        match self {
+            Self::ImageDecodeErrorsWithContext(_, context) => Some(context),
+            _ => None
         }
     }
 }
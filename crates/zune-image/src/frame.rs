@@ -12,14 +12,20 @@
 #![allow(dead_code)]
 
 use std::any::TypeId;
+use std::io::Write;
 
 use bytemuck::Pod;
+use zune_core::bit_depth::ByteEndian;
 use zune_core::colorspace::ColorSpace;
 
 use crate::channel::{Channel, ChannelErrors};
 use crate::deinterleave::{deinterleave_f32, deinterleave_u16, deinterleave_u8};
+use crate::errors::ImageErrors;
 use crate::traits::ZuneInts;
 
+/// Number of pixels interleaved at a time by [`Frame::write_interleaved`]
+const WRITE_CHUNK_PIXELS: usize = 4096;
+
 /// A single image frame
 ///
 /// This represents a simple image frame which contains a group
@@ -233,11 +239,13 @@ impl Frame {
     pub fn channels_mut(&mut self, colorspace: ColorSpace, ignore_alpha: bool) -> &mut [Channel] {
         // check if alpha channel is present in colorspace
         if ignore_alpha && colorspace.has_alpha() {
-            // do not take the last one,
-            // we assume the last one contains the alpha channel
-            // in it.
-            // TODO: Is this a bad assumption
-            &mut self.channels[0..colorspace.num_components() - 1]
+            let alpha_position = colorspace.alpha_position().unwrap();
+            if alpha_position == 0 {
+                // cover ARGB
+                &mut self.channels[1..colorspace.num_components()]
+            } else {
+                &mut self.channels[0..colorspace.num_components() - 1]
+            }
         } else {
             &mut self.channels[0..colorspace.num_components()]
         }
@@ -412,76 +420,7 @@ impl Frame {
     /// # Panics
     /// If channel isn't storing the u16 as it's internal  type
     pub fn u16_to_native_endian(&self, colorspace: ColorSpace) -> Vec<u8> {
-        // confirm all channels are in u16
-        for channel in &self.channels {
-            if channel.get_type_id() != TypeId::of::<u16>() {
-                panic!("Wrong type ID, expected u16 but got another type");
-                // wrong type id, that's an error
-                //return Err(ImageErrors::WrongTypeId(channel.get_type_id(), U16_TYPE_ID));
-            }
-        }
-        let length = self.channels[0].len() * colorspace.num_components();
-
-        let mut out_pixel = vec![0_u8; length];
-
-        match colorspace.num_components() {
-            // reinterpret as u16 first then native endian
-            1 => self.channels[0]
-                .reinterpret_as::<u16>()
-                .unwrap()
-                .iter()
-                .zip(out_pixel.chunks_exact_mut(2))
-                .for_each(|(x, y)| y.copy_from_slice(&x.to_ne_bytes())),
-
-            2 => {
-                let luma_channel = self.channels[0].reinterpret_as::<u16>().unwrap();
-                let alpha_channel = self.channels[1].reinterpret_as::<u16>().unwrap();
-
-                for ((out, luma), alpha) in out_pixel
-                    .chunks_exact_mut(4)
-                    .zip(luma_channel)
-                    .zip(alpha_channel)
-                {
-                    out[0..2].copy_from_slice(&luma.to_ne_bytes());
-                    out[2..4].copy_from_slice(&alpha.to_ne_bytes());
-                }
-            }
-            3 => {
-                let c1 = self.channels[0].reinterpret_as::<u16>().unwrap();
-                let c2 = self.channels[1].reinterpret_as::<u16>().unwrap();
-                let c3 = self.channels[2].reinterpret_as::<u16>().unwrap();
-
-                for (((out, first), second), third) in
-                    out_pixel.chunks_exact_mut(6).zip(c1).zip(c2).zip(c3)
-                {
-                    out[0..2].copy_from_slice(&first.to_ne_bytes());
-                    out[2..4].copy_from_slice(&second.to_ne_bytes());
-                    out[4..6].copy_from_slice(&third.to_ne_bytes());
-                }
-            }
-            4 => {
-                let c1 = self.channels[0].reinterpret_as::<u16>().unwrap();
-                let c2 = self.channels[1].reinterpret_as::<u16>().unwrap();
-                let c3 = self.channels[2].reinterpret_as::<u16>().unwrap();
-                let c4 = self.channels[3].reinterpret_as::<u16>().unwrap();
-
-                for ((((out, first), second), third), fourth) in out_pixel
-                    .chunks_exact_mut(8)
-                    .zip(c1)
-                    .zip(c2)
-                    .zip(c3)
-                    .zip(c4)
-                {
-                    out[0..2].copy_from_slice(&first.to_ne_bytes());
-                    out[2..4].copy_from_slice(&second.to_ne_bytes());
-                    out[4..6].copy_from_slice(&third.to_ne_bytes());
-                    out[6..8].copy_from_slice(&fourth.to_ne_bytes());
-                }
-            }
-            // panics, all the way down
-            _ => unreachable!(),
-        }
-        out_pixel
+        self.u16_to_bytes(colorspace, u16::to_ne_bytes)
     }
 
     /// convert `u16` channels  to big endian
@@ -494,8 +433,37 @@ impl Frame {
     ///
     /// # Panics
     /// If channel isn't storing the u16 as it's internal  type
-
     pub fn u16_to_big_endian(&self, colorspace: ColorSpace) -> Vec<u8> {
+        self.u16_to_bytes(colorspace, u16::to_be_bytes)
+    }
+
+    /// convert `u16` channels  to little endian
+    ///
+    ///  # Arguments
+    /// - Colorspace of the image
+    ///
+    /// # Returns
+    ///  - A vector with each two bytes representing a u16 value but
+    ///
+    /// # Panics
+    /// If channel isn't storing the u16 as it's internal  type
+    pub fn u16_to_little_endian(&self, colorspace: ColorSpace) -> Vec<u8> {
+        self.u16_to_bytes(colorspace, u16::to_le_bytes)
+    }
+
+    /// convert `u16` channels  to the byte order specified by `endian`
+    ///
+    /// # Panics
+    /// If channel isn't storing the u16 as it's internal  type
+    pub fn u16_to_endian(&self, colorspace: ColorSpace, endian: ByteEndian) -> Vec<u8> {
+        match endian {
+            ByteEndian::LE => self.u16_to_little_endian(colorspace),
+            ByteEndian::BE => self.u16_to_big_endian(colorspace),
+        }
+    }
+
+    /// Interleave `u16` channels, converting each sample to bytes via `to_bytes`
+    fn u16_to_bytes(&self, colorspace: ColorSpace, to_bytes: fn(u16) -> [u8; 2]) -> Vec<u8> {
         // confirm all channels are in u16
         for channel in &self.channels {
             if channel.get_type_id() != TypeId::of::<u16>() {
@@ -509,13 +477,13 @@ impl Frame {
         let mut out_pixel = vec![0_u8; length];
 
         match colorspace.num_components() {
-            // reinterpret as u16 first then native endian
+            // reinterpret as u16 first then convert
             1 => self.channels[0]
                 .reinterpret_as::<u16>()
                 .unwrap()
                 .iter()
                 .zip(out_pixel.chunks_exact_mut(2))
-                .for_each(|(x, y)| y.copy_from_slice(&x.to_be_bytes())),
+                .for_each(|(x, y)| y.copy_from_slice(&to_bytes(*x))),
 
             2 => {
                 let luma_channel = self.channels[0].reinterpret_as::<u16>().unwrap();
@@ -526,8 +494,8 @@ impl Frame {
                     .zip(luma_channel)
                     .zip(alpha_channel)
                 {
-                    out[0..2].copy_from_slice(&luma.to_be_bytes());
-                    out[2..4].copy_from_slice(&alpha.to_be_bytes());
+                    out[0..2].copy_from_slice(&to_bytes(*luma));
+                    out[2..4].copy_from_slice(&to_bytes(*alpha));
                 }
             }
             3 => {
@@ -538,9 +506,9 @@ impl Frame {
                 for (((out, first), second), third) in
                     out_pixel.chunks_exact_mut(6).zip(c1).zip(c2).zip(c3)
                 {
-                    out[0..2].copy_from_slice(&first.to_be_bytes());
-                    out[2..4].copy_from_slice(&second.to_be_bytes());
-                    out[4..6].copy_from_slice(&third.to_be_bytes());
+                    out[0..2].copy_from_slice(&to_bytes(*first));
+                    out[2..4].copy_from_slice(&to_bytes(*second));
+                    out[4..6].copy_from_slice(&to_bytes(*third));
                 }
             }
             4 => {
@@ -556,10 +524,10 @@ impl Frame {
                     .zip(c3)
                     .zip(c4)
                 {
-                    out[0..2].copy_from_slice(&first.to_be_bytes());
-                    out[2..4].copy_from_slice(&second.to_be_bytes());
-                    out[4..6].copy_from_slice(&third.to_be_bytes());
-                    out[6..8].copy_from_slice(&fourth.to_be_bytes());
+                    out[0..2].copy_from_slice(&to_bytes(*first));
+                    out[2..4].copy_from_slice(&to_bytes(*second));
+                    out[4..6].copy_from_slice(&to_bytes(*third));
+                    out[6..8].copy_from_slice(&to_bytes(*fourth));
                 }
             }
             // panics, all the way down
@@ -567,6 +535,60 @@ impl Frame {
         }
         out_pixel
     }
+    /// Write interleaved samples to `writer` in small pieces, without
+    /// flattening the whole frame first
+    ///
+    /// This is for encoders writing raw pixels to a stream, `u16` samples
+    /// are written in `endian` byte order.
+    ///
+    /// # Errors
+    /// If channels don't store `u8` or `u16` samples or `writer` fails
+    pub fn write_interleaved<W: Write + ?Sized>(
+        &self, colorspace: ColorSpace, endian: ByteEndian, writer: &mut W,
+    ) -> Result<(), ImageErrors> {
+        let type_id = self.channels[0].get_type_id();
+
+        if type_id == TypeId::of::<u8>() {
+            self.write_samples::<u8, 1, W>(colorspace, |x| [x], writer)
+        } else if type_id == TypeId::of::<u16>() {
+            let to_bytes = match endian {
+                ByteEndian::LE => u16::to_le_bytes,
+                ByteEndian::BE => u16::to_be_bytes,
+            };
+            self.write_samples::<u16, 2, W>(colorspace, to_bytes, writer)
+        } else {
+            Err(ImageErrors::WrongTypeId(TypeId::of::<u16>(), type_id))
+        }
+    }
+
+    /// Interleave samples of type `T`, each taking `N` bytes, and write them to `writer`
+    fn write_samples<T: Copy + Default + 'static, const N: usize, W: Write + ?Sized>(
+        &self, colorspace: ColorSpace, to_bytes: impl Fn(T) -> [u8; N], writer: &mut W,
+    ) -> Result<(), ImageErrors> {
+        let channels = self.channels[..colorspace.num_components()]
+            .iter()
+            .map(Channel::reinterpret_as::<T>)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let pixel_bytes = channels.len() * N;
+        let pixels = channels[0].len();
+        let mut buffer = vec![0_u8; WRITE_CHUNK_PIXELS.min(pixels) * pixel_bytes];
+
+        for start in (0..pixels).step_by(WRITE_CHUNK_PIXELS) {
+            let end = (start + WRITE_CHUNK_PIXELS).min(pixels);
+
+            for (position, channel) in channels.iter().enumerate() {
+                for (pixel, sample) in buffer
+                    .chunks_exact_mut(pixel_bytes)
+                    .zip(&channel[start..end])
+                {
+                    pixel[position * N..(position + 1) * N].copy_from_slice(&to_bytes(*sample));
+                }
+            }
+            writer.write_all(&buffer[..(end - start) * pixel_bytes])?;
+        }
+        Ok(())
+    }
     /// Overwrite the current image channels with new channels
     ///
     /// # Argument
@@ -597,6 +619,17 @@ mod tests {
         assert_eq!(&frame_data, &[80, 195]);
     }
 
+    #[test]
+    fn test_conversion_to_explicit_endian() {
+        let mut channel = Channel::new::<u16>();
+        channel.push(50000_u16);
+
+        let frame = Frame::new(vec![channel]);
+
+        assert_eq!(&frame.u16_to_little_endian(ColorSpace::Luma), &[80, 195]);
+        assert_eq!(&frame.u16_to_big_endian(ColorSpace::Luma), &[195, 80]);
+    }
+
     #[test]
     fn test_flatten_grayscale_to_rgba() {
         let mut channel = Channel::new::<u8>();
@@ -609,4 +642,26 @@ mod tests {
         let reference = [10, 10, 10, 255, 20, 20, 20, 255, 20, 20, 20, 255];
         assert_eq!(&out, &reference);
     }
+
+    #[test]
+    fn test_write_interleaved() {
+        use zune_core::bit_depth::ByteEndian;
+
+        // more pixels than are interleaved at a time
+        let luma: Vec<u16> = (0..5000).collect();
+        let alpha: Vec<u16> = (0..5000).rev().collect();
+
+        let mut channels = vec![Channel::new::<u16>(), Channel::new::<u16>()];
+        channels[0].extend::<u16>(&luma);
+        channels[1].extend::<u16>(&alpha);
+
+        let frame = Frame::new(channels);
+
+        let mut out = vec![];
+        frame
+            .write_interleaved(ColorSpace::LumaA, ByteEndian::BE, &mut out)
+            .unwrap();
+
+        assert_eq!(out, frame.u16_to_big_endian(ColorSpace::LumaA));
+    }
 }
@@ -0,0 +1,271 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! Perceptual image hashes
+//!
+//! This module contains functions to compute 64-bit fingerprints
+//! of images, images which look alike produce hashes with a small
+//! [hamming distance](hamming_distance) between them, which makes them useful
+//! for finding duplicates or near duplicates
+//!
+//! The following hashes are supported
+//!
+//! - [Average hash](average_hash): Fastest, but sensitive to gamma and color histogram changes
+//! - [Difference hash](difference_hash): Tracks gradients, almost as fast as the average hash but more accurate
+//! - [Perceptual hash](perceptual_hash): Uses a discrete cosine transform, slowest but the most
+//!   robust to changes in gamma, contrast and light compression
+//!
+//! All hashes only consider the first frame of an animated image.
+//!
+//! # Example
+//! ```
+//! use zune_core::colorspace::ColorSpace;
+//! use zune_image::errors::ImageErrors;
+//! use zune_image::hash::{hamming_distance, perceptual_hash};
+//! use zune_image::image::Image;
+//!
+//! let a = Image::fill::<u8>(128, ColorSpace::RGB, 100, 100);
+//! let b = Image::fill::<u8>(128, ColorSpace::RGB, 200, 200);
+//!
+//! let distance = hamming_distance(perceptual_hash(&a)?, perceptual_hash(&b)?);
+//! // a distance below 10 usually means the images are similar
+//! assert!(distance < 10);
+//! # Ok::<(),ImageErrors>(())
+//! ```
+use std::f32::consts::PI;
+
+use zune_core::bit_depth::BitDepth;
+use zune_core::colorspace::ColorSpace;
+
+use crate::errors::ImageErrors;
+use crate::image::Image;
+
+/// Size of the grayscale image the DCT is computed on
+const DCT_SIZE: usize = 32;
+/// Size of the low frequency coefficients used in the perceptual hash
+const HASH_SIZE: usize = 8;
+
+/// The algorithm used for hashing images
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum HashAlgorithm {
+    /// Average hash, see [`average_hash`]
+    Average,
+    /// Difference hash, see [`difference_hash`]
+    Difference,
+    /// DCT based perceptual hash, see [`perceptual_hash`]
+    Perceptual
+}
+
+impl HashAlgorithm {
+    /// Compute the hash of an image using this algorithm
+    pub fn hash(self, image: &Image) -> Result<u64, ImageErrors> {
+        match self {
+            HashAlgorithm::Average => average_hash(image),
+            HashAlgorithm::Difference => difference_hash(image),
+            HashAlgorithm::Perceptual => perceptual_hash(image)
+        }
+    }
+}
+
+/// Return the number of bits that differ between two hashes
+///
+/// The smaller the distance, the more similar the images are, a distance of
+/// zero means the images are perceptually identical
+pub const fn hamming_distance(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+/// Compute the average hash of an image
+///
+/// The image is reduced to an 8x8 grayscale image and each bit is set
+/// if the corresponding pixel is brighter than the mean of all pixels
+pub fn average_hash(image: &Image) -> Result<u64, ImageErrors> {
+    let pixels = grayscale_thumbnail(image, HASH_SIZE, HASH_SIZE)?;
+
+    let mean = pixels.iter().sum::<f32>() / pixels.len() as f32;
+
+    Ok(pack_bits(pixels.iter().map(|x| *x > mean)))
+}
+
+/// Compute the difference hash of an image
+///
+/// The image is reduced to a 9x8 grayscale image and each bit is set
+/// if a pixel is darker than the pixel on its right
+pub fn difference_hash(image: &Image) -> Result<u64, ImageErrors> {
+    let pixels = grayscale_thumbnail(image, HASH_SIZE + 1, HASH_SIZE)?;
+
+    let bits = pixels
+        .chunks_exact(HASH_SIZE + 1)
+        .flat_map(|row| row.windows(2).map(|x| x[0] < x[1]));
+
+    Ok(pack_bits(bits))
+}
+
+/// Compute the perceptual hash of an image
+///
+/// The image is reduced to a 32x32 grayscale image, a 2D discrete cosine transform
+/// is computed and the lowest 8x8 frequencies are kept, each bit is then set if its
+/// coefficient is greater than the median of the coefficients
+pub fn perceptual_hash(image: &Image) -> Result<u64, ImageErrors> {
+    let pixels = grayscale_thumbnail(image, DCT_SIZE, DCT_SIZE)?;
+
+    let coefficients = dct_2d_low_frequencies(&pixels);
+
+    // the DC term is excluded from the median since it is
+    // the average brightness and usually dwarfs everything else
+    let mut sorted = coefficients[1..].to_vec();
+    sorted.sort_unstable_by(f32::total_cmp);
+    let median = sorted[sorted.len() / 2];
+
+    Ok(pack_bits(coefficients.iter().map(|x| *x > median)))
+}
+
+/// Pack the first 64 booleans into an integer, with the first
+/// item being the most significant bit
+fn pack_bits(bits: impl Iterator<Item = bool>) -> u64 {
+    bits.take(64)
+        .fold(0, |hash, bit| (hash << 1) | u64::from(bit))
+}
+
+/// Convert the first frame of an image into grayscale and downscale it
+/// to `width` x `height` using an area average
+fn grayscale_thumbnail(
+    image: &Image, out_width: usize, out_height: usize
+) -> Result<Vec<f32>, ImageErrors> {
+    let first_frame = image.frames.first().ok_or(ImageErrors::NoImageBuffer)?;
+
+    let (width, height) = image.dimensions();
+
+    if width == 0 || height == 0 {
+        return Err(ImageErrors::GenericStr(
+            "Cannot hash an image with zero dimensions"
+        ));
+    }
+
+    let mut gray = Image {
+        frames:   vec![first_frame.clone()],
+        metadata: image.metadata.clone()
+    };
+    gray.convert_depth(BitDepth::Float32)?;
+    gray.convert_color(ColorSpace::Luma)?;
+
+    let pixels = gray.frames[0].channels_ref(ColorSpace::Luma, true)[0].reinterpret_as::<f32>()?;
+
+    let mut output = vec![0.0; out_width * out_height];
+
+    for (oy, out_row) in output.chunks_exact_mut(out_width).enumerate() {
+        let y_start = oy * height / out_height;
+        let y_end = ((oy + 1) * height / out_height).max(y_start + 1);
+
+        for (ox, out_px) in out_row.iter_mut().enumerate() {
+            let x_start = ox * width / out_width;
+            let x_end = ((ox + 1) * width / out_width).max(x_start + 1);
+
+            let mut sum = 0.0;
+
+            for row in pixels.chunks_exact(width).take(y_end).skip(y_start) {
+                sum += row[x_start..x_end].iter().sum::<f32>();
+            }
+            *out_px = sum / ((y_end - y_start) * (x_end - x_start)) as f32;
+        }
+    }
+    Ok(output)
+}
+
+/// Compute a DCT-II of a `DCT_SIZE` x `DCT_SIZE` image returning only the
+/// top left `HASH_SIZE` x `HASH_SIZE` coefficients in row major order
+fn dct_2d_low_frequencies(pixels: &[f32]) -> Vec<f32> {
+    // cos_table[k][n] = cos(pi/N * (n + 0.5) * k)
+    let mut cos_table = [[0.0_f32; DCT_SIZE]; HASH_SIZE];
+
+    for (k, row) in cos_table.iter_mut().enumerate() {
+        for (n, value) in row.iter_mut().enumerate() {
+            *value = (PI / DCT_SIZE as f32 * (n as f32 + 0.5) * k as f32).cos();
+        }
+    }
+
+    // transform rows, keeping only the low frequencies
+    let mut rows = vec![0.0; DCT_SIZE * HASH_SIZE];
+
+    for (in_row, out_row) in pixels
+        .chunks_exact(DCT_SIZE)
+        .zip(rows.chunks_exact_mut(HASH_SIZE))
+    {
+        for (out, cosines) in out_row.iter_mut().zip(cos_table.iter()) {
+            *out = in_row.iter().zip(cosines).map(|(a, b)| a * b).sum();
+        }
+    }
+
+    // then transform columns
+    let mut output = vec![0.0; HASH_SIZE * HASH_SIZE];
+
+    for (k, cosines) in cos_table.iter().enumerate() {
+        for x in 0..HASH_SIZE {
+            output[k * HASH_SIZE + x] = cosines
+                .iter()
+                .enumerate()
+                .map(|(n, c)| rows[n * HASH_SIZE + x] * c)
+                .sum();
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use zune_core::colorspace::ColorSpace;
+
+    use crate::hash::{hamming_distance, HashAlgorithm};
+    use crate::image::Image;
+
+    fn gradient(width: usize, height: usize, invert: bool) -> Image {
+        let mut pixels = vec![0_u8; width * height];
+
+        for (y, row) in pixels.chunks_exact_mut(width).enumerate() {
+            for (x, px) in row.iter_mut().enumerate() {
+                // a bright block on a horizontal gradient
+                let value =
+                    if x < width / 4 && y < height / 2 { 255 } else { (x * 200 / width) as u8 };
+                *px = if invert { 255 - value } else { value };
+            }
+        }
+        Image::from_u8(&pixels, width, height, ColorSpace::Luma)
+    }
+
+    #[test]
+    fn test_hash_resized_images_are_similar() {
+        let small = gradient(64, 64, false);
+        let large = gradient(300, 200, false);
+
+        for algorithm in [
+            HashAlgorithm::Average,
+            HashAlgorithm::Difference,
+            HashAlgorithm::Perceptual
+        ] {
+            let a = algorithm.hash(&small).unwrap();
+            let b = algorithm.hash(&large).unwrap();
+            assert!(hamming_distance(a, b) < 10, "{algorithm:?}");
+        }
+    }
+
+    #[test]
+    fn test_hash_inverted_images_differ() {
+        let image = gradient(64, 64, false);
+        let inverted = gradient(64, 64, true);
+
+        for algorithm in [
+            HashAlgorithm::Average,
+            HashAlgorithm::Difference,
+            HashAlgorithm::Perceptual
+        ] {
+            let a = algorithm.hash(&image).unwrap();
+            let b = algorithm.hash(&inverted).unwrap();
+            assert!(hamming_distance(a, b) > 20, "{algorithm:?}");
+        }
+    }
+}
@@ -14,17 +14,22 @@ use std::fmt::Debug;
 use std::mem::size_of;
 
 use bytemuck::{Pod, Zeroable};
-use zune_core::bit_depth::BitDepth;
+use zune_core::bit_depth::{BitDepth, BitType, ByteEndian};
 use zune_core::colorspace::ColorSpace;
+use zune_core::options::EncoderOptions;
 
 use crate::channel::{Channel, ChannelErrors};
 use crate::core_filters::colorspace::ColorspaceConv;
-use crate::core_filters::depth::Depth;
+use crate::core_filters::depth::{Depth, DepthScale};
 use crate::deinterleave::{deinterleave_f32, deinterleave_u16, deinterleave_u8};
 use crate::errors::ImageErrors;
 use crate::frame::Frame;
 use crate::metadata::ImageMetadata;
+use crate::mipmaps::{generate_mipmaps, MipmapFilter};
+use crate::stack::{stack_images, StackMode};
+use crate::tiles::{image_tiles, image_tiles_mut, Tiles, TilesMut};
 use crate::traits::{OperationsTrait, ZuneInts};
+use crate::view::{ImageView, ImageViewMut};
 
 /// Maximum supported color channels
 pub const MAX_CHANNELS: usize = 4;
@@ -171,22 +176,30 @@ impl Image {
     /// Convert image to a byte representation interleaving
     /// image pixels where necessary
     ///
-    /// # Note
-    /// For images using anything larger than 8 bit,
-    /// u8 as native endian is used
-    /// i.e RGB data looks like `[R,R,G,G,G,B,B]`
-    #[allow(dead_code)]
-    pub(crate) fn to_u8(&self) -> Vec<Vec<u8>> {
-        let colorspace = self.colorspace();
-        if self.metadata.get_depth() == BitDepth::Eight {
-            self.flatten_frames::<u8>()
-        } else if self.metadata.get_depth() == BitDepth::Sixteen {
-            self.frames_ref()
-                .iter()
-                .map(|z| z.u16_to_native_endian(colorspace))
-                .collect()
+    /// Samples that span more than one byte are stored in the byte order
+    /// set by [`EncoderOptions::set_byte_endian`], e.g for 16 bit images
+    /// `ByteEndian::BE` produces the order expected by PNG and PPM while
+    /// `ByteEndian::LE` produces the native order on most machines.
+    ///
+    /// This is useful when dumping raw pixels, encoders of formats with a
+    /// defined byte order ignore the option.
+    ///
+    /// # Returns
+    /// A vector of bytes for each frame in the image
+    ///
+    /// [`EncoderOptions::set_byte_endian`]: zune_core::options::EncoderOptions::set_byte_endian
+    pub fn to_u8(&self, options: EncoderOptions) -> Vec<Vec<u8>> {
+        self.to_u8_endian(options.get_byte_endian())
+    }
+    /// Convert image to bytes with 16 bit samples in native endian
+    ///
+    /// The format libraries take samples this way and write them in
+    /// the order their format requires
+    pub(crate) fn to_u8_native(&self) -> Vec<Vec<u8>> {
+        if cfg!(target_endian = "little") {
+            self.to_u8_endian(ByteEndian::LE)
         } else {
-            todo!("Unimplemented")
+            self.to_u8_endian(ByteEndian::BE)
         }
     }
     pub fn flatten_to_u8(&self) -> Vec<Vec<u8>> {
@@ -198,15 +211,18 @@ impl Image {
             im_clone.flatten_frames::<u8>()
         }
     }
-    #[allow(dead_code)]
     pub(crate) fn to_u8_be(&self) -> Vec<Vec<u8>> {
+        self.to_u8_endian(ByteEndian::BE)
+    }
+
+    fn to_u8_endian(&self, endian: ByteEndian) -> Vec<Vec<u8>> {
         let colorspace = self.colorspace();
         if self.metadata.get_depth() == BitDepth::Eight {
             self.flatten_frames::<u8>()
-        } else if self.metadata.get_depth() == BitDepth::Sixteen {
+        } else if self.metadata.get_depth().bit_type() == BitType::U16 {
             self.frames_ref()
                 .iter()
-                .map(|z| z.u16_to_big_endian(colorspace))
+                .map(|z| z.u16_to_endian(colorspace, endian))
                 .collect()
         } else {
             todo!("Unimplemented")
@@ -573,6 +589,47 @@ impl Image {
         }
         Ok(())
     }
+    /// Iterate over the image in tiles of `tile_width` by `tile_height` pixels
+    ///
+    /// Tiles of every frame are returned left to right, top to bottom, tiles on the right
+    /// and bottom edges are smaller when the dimensions aren't a multiple of the tile size.
+    /// Each tile is a view of one frame, see the [tiles](crate::tiles) module
+    ///
+    /// # Returns
+    ///  - Ok(Tiles): Iterator over the tiles
+    ///  - Err(ImageErrors): A tile dimension is zero, or the image depth isn't supported
+    pub fn tiles(&self, tile_width: usize, tile_height: usize) -> Result<Tiles<'_>, ImageErrors> {
+        image_tiles(self, tile_width, tile_height)
+    }
+    /// Iterate over the image in mutable tiles of `tile_width` by `tile_height` pixels
+    ///
+    /// Tiles don't overlap, so they can be processed in parallel,
+    /// see [`tiles`](Self::tiles) for the order and size of tiles
+    pub fn tiles_mut(
+        &mut self, tile_width: usize, tile_height: usize
+    ) -> Result<TilesMut<'_>, ImageErrors> {
+        image_tiles_mut(self, tile_width, tile_height)
+    }
+    /// Borrow the `width` by `height` region whose top left pixel is at `(x, y)`
+    ///
+    /// See the [view](crate::view) module
+    ///
+    /// # Returns
+    ///  - Ok(ImageView): The view
+    ///  - Err(ImageErrors): The region isn't inside the image
+    pub fn view(
+        &self, x: usize, y: usize, width: usize, height: usize
+    ) -> Result<ImageView<'_>, ImageErrors> {
+        ImageView::new(self, x, y, width, height)
+    }
+    /// Borrow the `width` by `height` region whose top left pixel is at `(x, y)` mutably
+    ///
+    /// Operations run on the region with [`OperationsTrait::execute_view`]
+    pub fn view_mut(
+        &mut self, x: usize, y: usize, width: usize, height: usize
+    ) -> Result<ImageViewMut<'_>, ImageErrors> {
+        ImageViewMut::new(self, x, y, width, height)
+    }
 }
 
 /// Image conversion routines
@@ -592,6 +649,82 @@ impl Image {
     pub fn convert_depth(&mut self, to: BitDepth) -> Result<(), ImageErrors> {
         Depth::new(to).execute(self)
     }
+    /// Convert an image from one depth to another, mapping samples with `scale`
+    ///
+    /// [`convert_depth`](Self::convert_depth) uses [`DepthScale::FullRange`], use this
+    /// when samples should keep their values, e.g for measurement data.
+    ///
+    /// # Arguments
+    /// - to: The bit-depth to convert the image into
+    /// - scale: How samples are mapped to the new depth
+    ///
+    /// # Example
+    /// ```
+    /// use zune_core::bit_depth::BitDepth;
+    /// use zune_core::colorspace::ColorSpace;
+    /// use zune_image::core_filters::depth::DepthScale;
+    /// use zune_image::image::Image;
+    ///
+    /// let mut image = Image::from_u8(&[200], 1, 1, ColorSpace::Luma);
+    /// image.convert_depth_with_scale(BitDepth::Sixteen, DepthScale::Preserve).unwrap();
+    ///
+    /// assert_eq!(image.channels_ref(false)[0].reinterpret_as::<u16>().unwrap(), &[200]);
+    /// ```
+    pub fn convert_depth_with_scale(
+        &mut self, to: BitDepth, scale: DepthScale
+    ) -> Result<(), ImageErrors> {
+        Depth::new(to).set_scale(scale).execute(self)
+    }
+    /// Merge multiple images of the same scene into one
+    ///
+    /// Images must have the same dimensions, colorspace, depth and number of frames,
+    /// metadata is copied from the first image.
+    ///
+    /// # Arguments
+    /// - images: The images to stack
+    /// - mode: How pixels at the same position are merged
+    ///
+    /// # Example
+    /// ```
+    /// use zune_core::colorspace::ColorSpace;
+    /// use zune_image::image::Image;
+    /// use zune_image::stack::StackMode;
+    ///
+    /// let exposures = [
+    ///     Image::fill(10_u8, ColorSpace::RGB, 100, 100),
+    ///     Image::fill(20_u8, ColorSpace::RGB, 100, 100),
+    ///     Image::fill(90_u8, ColorSpace::RGB, 100, 100)
+    /// ];
+    /// let stacked = Image::stack(&exposures, StackMode::Median).unwrap();
+    /// assert!(stacked == exposures[1]);
+    /// ```
+    pub fn stack(images: &[Image], mode: StackMode) -> Result<Image, ImageErrors> {
+        stack_images(images, mode)
+    }
+    /// Generate a mipmap chain, halving the image until it is 1x1
+    ///
+    /// The first level is a copy of the image, each following level is half
+    /// the width and height of the previous one (rounded down, but never below 1).
+    /// Levels keep the colorspace, depth, frames and metadata of the image.
+    ///
+    /// # Arguments
+    /// - filter: How pixels are combined when halving
+    ///
+    /// # Example
+    /// ```
+    /// use zune_core::colorspace::ColorSpace;
+    /// use zune_image::image::Image;
+    /// use zune_image::mipmaps::MipmapFilter;
+    ///
+    /// let image = Image::fill(100_u8, ColorSpace::RGB, 16, 4);
+    /// let levels = image.generate_mipmaps(MipmapFilter::LinearBox).unwrap();
+    ///
+    /// let sizes: Vec<_> = levels.iter().map(|x| x.dimensions()).collect();
+    /// assert_eq!(sizes, [(16, 4), (8, 2), (4, 1), (2, 1), (1, 1)]);
+    /// ```
+    pub fn generate_mipmaps(&self, filter: MipmapFilter) -> Result<Vec<Image>, ImageErrors> {
+        generate_mipmaps(self, filter)
+    }
 }
 
 pub(crate) fn checked_mul(
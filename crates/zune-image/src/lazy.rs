@@ -0,0 +1,237 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! Images that are decoded on first use
+//!
+//! A [`LazyImage`] keeps the encoded bytes of an image and the information in its
+//! headers, pixels are only decoded when they are first asked for. Tools that look at
+//! many files but only process some of them (e.g sort by dimensions, find images of a
+//! format, validate headers) can decide using the headers and skip decoding the rest.
+//!
+//! A [`Pipeline`](crate::pipelines::Pipeline) of lazy images with no operations and
+//! no encoders skips decoding entirely, the headers are available via
+//! [`Pipeline::metadata`](crate::pipelines::Pipeline::metadata).
+use std::path::Path;
+
+use zune_core::bit_depth::BitDepth;
+use zune_core::colorspace::ColorSpace;
+use zune_core::options::DecoderOptions;
+
+use crate::codecs::ImageFormat;
+use crate::errors::ImageErrors;
+use crate::image::Image;
+use crate::metadata::ImageMetadata;
+use crate::traits::{IntoImage, OperationsTrait};
+
+/// An encoded image that is decoded the first time its pixels are needed
+///
+/// # Example
+/// ```
+/// use zune_core::options::DecoderOptions;
+/// use zune_image::lazy::LazyImage;
+///
+/// // a 2x1 grayscale ppm
+/// let mut image = LazyImage::new(b"P5 2 1 255 \x00\xff".to_vec(), DecoderOptions::default()).unwrap();
+///
+/// // headers are read without decoding
+/// assert_eq!(image.dimensions(), (2, 1));
+/// assert!(!image.is_decoded());
+///
+/// // pixels are decoded on first use
+/// assert_eq!(image.image().unwrap().flatten_to_u8()[0], [0, 255]);
+/// assert!(image.is_decoded());
+/// ```
+pub struct LazyImage {
+    data:     Vec<u8>,
+    format:   ImageFormat,
+    options:  DecoderOptions,
+    metadata: ImageMetadata,
+    image:    Option<Image>
+}
+
+impl LazyImage {
+    /// Read the headers of an encoded image, without decoding its pixels
+    ///
+    /// Formats whose decoder cannot read headers on their own are decoded immediately.
+    ///
+    /// # Arguments
+    /// - data: The encoded image
+    /// - options: Options used to read the headers and, later, to decode the image
+    ///
+    /// # Errors
+    /// If the format isn't recognised or the headers are invalid
+    pub fn new(data: Vec<u8>, options: DecoderOptions) -> Result<LazyImage, ImageErrors> {
+        let (format, _) = ImageFormat::guess_format(data.as_slice()).ok_or(
+            ImageErrors::ImageDecoderNotImplemented(ImageFormat::Unknown)
+        )?;
+
+        let headers = format
+            .get_decoder_with_options(data.as_slice(), options)?
+            .read_headers()?;
+
+        let needs_decode = headers.is_none();
+
+        let mut lazy = LazyImage {
+            data,
+            format,
+            options,
+            metadata: headers.unwrap_or_default(),
+            image: None
+        };
+        if needs_decode {
+            // the decoder can't read headers alone, decode to find out
+            let image = lazy.decode()?;
+            lazy.metadata = image.metadata().clone();
+            lazy.image = Some(image);
+        }
+        lazy.metadata.format = Some(format);
+
+        Ok(lazy)
+    }
+    /// Read a file and its headers, without decoding its pixels
+    ///
+    /// See [`LazyImage::new`]
+    pub fn open<P: AsRef<Path>>(
+        file: P, options: DecoderOptions
+    ) -> Result<LazyImage, ImageErrors> {
+        LazyImage::new(std::fs::read(file)?, options)
+    }
+
+    fn decode(&self) -> Result<Image, ImageErrors> {
+        self.decode_with(&[]).map(|(image, _)| image)
+    }
+
+    fn decode_with(
+        &self, operations: &[&dyn OperationsTrait]
+    ) -> Result<(Image, usize), ImageErrors> {
+        let (mut image, ran) = self
+            .format
+            .get_decoder_with_options(self.data.as_slice(), self.options)?
+            .decode_with(operations)?;
+        image.metadata.format = Some(self.format);
+
+        Ok((image, ran))
+    }
+
+    /// Return the format of the encoded image
+    pub const fn format(&self) -> ImageFormat {
+        self.format
+    }
+    /// Return the encoded bytes of the image
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+    /// Return the image metadata
+    ///
+    /// Before decoding this is what the headers contain, after decoding
+    /// it is the metadata of the decoded image
+    pub fn metadata(&self) -> &ImageMetadata {
+        match &self.image {
+            Some(image) => image.metadata(),
+            None => &self.metadata
+        }
+    }
+    /// Return the image width and height
+    pub const fn dimensions(&self) -> (usize, usize) {
+        self.metadata.get_dimensions()
+    }
+    /// Return the colorspace the image will be decoded to
+    pub const fn colorspace(&self) -> ColorSpace {
+        self.metadata.get_colorspace()
+    }
+    /// Return the bit depth the image will be decoded to
+    pub const fn depth(&self) -> BitDepth {
+        self.metadata.get_depth()
+    }
+    /// Return true if the pixels have been decoded
+    pub const fn is_decoded(&self) -> bool {
+        self.image.is_some()
+    }
+    /// Return the decoded image, decoding it if this is the first use
+    ///
+    /// # Errors
+    /// If decoding fails, in which case a later call tries again
+    pub fn image(&mut self) -> Result<&Image, ImageErrors> {
+        self.image_mut().map(|x| &*x)
+    }
+    /// Return the decoded image as a mutable reference, decoding it if this is the first use
+    ///
+    /// Changes to the image are not written back to the encoded bytes
+    pub fn image_mut(&mut self) -> Result<&mut Image, ImageErrors> {
+        if self.image.is_none() {
+            self.image = Some(self.decode()?);
+        }
+        Ok(self.image.as_mut().unwrap())
+    }
+}
+
+impl IntoImage for LazyImage {
+    fn into_image(self) -> Result<Image, ImageErrors> {
+        match self.image {
+            Some(image) => Ok(image),
+            None => self.decode()
+        }
+    }
+
+    fn into_image_with(
+        self, operations: &[&dyn OperationsTrait]
+    ) -> Result<(Image, usize), ImageErrors> {
+        match self.image {
+            Some(image) => Ok((image, 0)),
+            None => self.decode_with(operations)
+        }
+    }
+
+    fn headers(&self) -> Option<&ImageMetadata> {
+        Some(self.metadata())
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "ppm")]
+mod tests {
+    use zune_core::colorspace::ColorSpace;
+    use zune_core::options::DecoderOptions;
+
+    use crate::core_filters::colorspace::ColorspaceConv;
+    use crate::lazy::LazyImage;
+    use crate::pipelines::Pipeline;
+
+    /// A ppm with valid headers but missing pixels
+    const TRUNCATED: &[u8] = b"P6 300 200 255\n\x00\x00";
+
+    #[test]
+    fn test_headers_without_decoding() {
+        let mut image = LazyImage::new(TRUNCATED.to_vec(), DecoderOptions::default()).unwrap();
+
+        assert_eq!(image.dimensions(), (300, 200));
+        assert_eq!(image.colorspace(), ColorSpace::RGB);
+        assert!(image.image().is_err());
+        assert!(!image.is_decoded());
+    }
+
+    #[test]
+    fn test_pipeline_skips_decoding() {
+        let lazy = || LazyImage::new(TRUNCATED.to_vec(), DecoderOptions::default()).unwrap();
+
+        // only headers are needed, so the broken pixels are never seen
+        let mut pipeline = Pipeline::new();
+        pipeline.add_decoder(lazy());
+        pipeline.advance_to_end().unwrap();
+
+        assert!(pipeline.images().is_empty());
+        assert_eq!(pipeline.metadata()[0].get_dimensions(), (300, 200));
+
+        // operations need pixels
+        let mut pipeline = Pipeline::new();
+        pipeline.add_decoder(lazy());
+        pipeline.add_operation(Box::new(ColorspaceConv::new(ColorSpace::Luma)));
+
+        assert!(pipeline.advance_to_end().is_err());
+    }
+}
@@ -94,18 +94,32 @@
 )]
 extern crate core;
 
+pub mod cancel;
 pub mod channel;
 pub mod codecs;
+pub mod convert;
 pub mod core_filters;
 mod deinterleave;
 pub mod errors;
 pub mod frame;
+pub mod hash;
 pub mod image;
+pub mod lazy;
+pub mod mem_stats;
 mod mempool;
 pub mod metadata;
+pub mod mipmaps;
 mod ops;
 pub mod pipelines;
+pub mod registry;
 mod serde;
+pub mod stack;
+pub mod statistics;
 mod tests;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+pub mod tiles;
 pub mod traits;
 pub mod utils;
+pub mod view;
+pub mod zune_raw;
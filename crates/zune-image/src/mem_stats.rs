@@ -0,0 +1,167 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+#![cfg(feature = "mem-stats")]
+//! Allocation statistics
+//!
+//! This provides an allocator that keeps track of how much memory
+//! is in use, which [`Pipeline`](crate::pipelines::Pipeline) uses to report
+//! memory usage for each decoder, operation and encoder it runs.
+//!
+//! The library cannot install a global allocator, that is left to the binary,
+//! without it every statistic reported will be zero.
+//!
+//! # Example
+//! ```no_run
+//! use zune_image::mem_stats::TrackingAllocator;
+//!
+//! #[global_allocator]
+//! static GLOBAL: TrackingAllocator = TrackingAllocator::system();
+//! ```
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+static TOTAL: AtomicUsize = AtomicUsize::new(0);
+static MAX: AtomicUsize = AtomicUsize::new(0);
+
+/// An allocator wrapper that counts bytes allocated through it
+pub struct TrackingAllocator<A = System> {
+    inner: A
+}
+
+impl TrackingAllocator<System> {
+    /// Track allocations made by the system allocator
+    pub const fn system() -> TrackingAllocator<System> {
+        TrackingAllocator { inner: System }
+    }
+}
+
+impl<A> TrackingAllocator<A> {
+    /// Track allocations made by `inner`
+    pub const fn new(inner: A) -> TrackingAllocator<A> {
+        TrackingAllocator { inner }
+    }
+}
+
+fn record_alloc(size: usize) {
+    let current = CURRENT.fetch_add(size, Ordering::Relaxed) + size;
+    PEAK.fetch_max(current, Ordering::Relaxed);
+    MAX.fetch_max(current, Ordering::Relaxed);
+    TOTAL.fetch_add(size, Ordering::Relaxed);
+}
+
+fn record_dealloc(size: usize) {
+    CURRENT.fetch_sub(size, Ordering::Relaxed);
+}
+
+unsafe impl<A: GlobalAlloc> GlobalAlloc for TrackingAllocator<A> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = self.inner.alloc(layout);
+        if !ptr.is_null() {
+            record_alloc(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        self.inner.dealloc(ptr, layout);
+        record_dealloc(layout.size());
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = self.inner.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            record_alloc(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = self.inner.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            // count it as a fresh allocation, for a moment both
+            // buffers may be alive
+            record_alloc(new_size);
+            record_dealloc(layout.size());
+        }
+        new_ptr
+    }
+}
+
+/// Bytes currently allocated via [`TrackingAllocator`]
+pub fn current_usage() -> usize {
+    CURRENT.load(Ordering::Relaxed)
+}
+
+/// Highest number of bytes allocated at once since program start
+/// or the last call to [`reset_peak`]
+pub fn peak_usage() -> usize {
+    PEAK.load(Ordering::Relaxed)
+}
+
+/// Highest number of bytes allocated at once since program start
+///
+/// Unlike [`peak_usage`], this is not affected by [`reset_peak`]
+pub fn max_usage() -> usize {
+    MAX.load(Ordering::Relaxed)
+}
+
+/// Total number of bytes ever allocated, including freed ones
+pub fn total_allocated() -> usize {
+    TOTAL.load(Ordering::Relaxed)
+}
+
+/// Reset the peak usage to the memory currently in use
+pub fn reset_peak() {
+    PEAK.store(CURRENT.load(Ordering::Relaxed), Ordering::Relaxed);
+}
+
+/// Memory used by a single pipeline stage
+#[derive(Copy, Clone, Debug)]
+pub struct MemoryUsage {
+    /// Name of the decoder, operation or encoder
+    pub name:      &'static str,
+    /// Highest number of bytes the stage had allocated at once,
+    /// on top of what was in use before it started
+    pub peak:      usize,
+    /// Total number of bytes allocated by the stage
+    pub allocated: usize,
+    /// Bytes still held once the stage finished
+    pub retained:  isize
+}
+
+/// Measures memory usage between [`start`](Self::start) and [`finish`](Self::finish)
+pub(crate) struct StageTracker {
+    name:    &'static str,
+    current: usize,
+    total:   usize
+}
+
+impl StageTracker {
+    pub(crate) fn start(name: &'static str) -> StageTracker {
+        reset_peak();
+        StageTracker {
+            name,
+            current: current_usage(),
+            total: total_allocated()
+        }
+    }
+
+    pub(crate) fn finish(self) -> MemoryUsage {
+        let current = current_usage();
+
+        MemoryUsage {
+            name:      self.name,
+            peak:      peak_usage().saturating_sub(self.current),
+            allocated: total_allocated().saturating_sub(self.total),
+            retained:  current as isize - self.current as isize
+        }
+    }
+}
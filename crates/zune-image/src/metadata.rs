@@ -13,8 +13,10 @@
 
 use zune_core::bit_depth::BitDepth;
 use zune_core::colorspace::{ColorCharacteristics, ColorSpace};
+use zune_core::timings::DecodeTimings;
 
 use crate::codecs::ImageFormat;
+use crate::statistics::ChannelStatistics;
 
 mod exif;
 
@@ -24,7 +26,77 @@ mod exif;
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum AlphaState {
     PreMultiplied,
-    NonPreMultiplied,
+    NonPreMultiplied
+}
+
+/// Unit of the densities of [`PhysicalDimensions`]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum DensityUnit {
+    /// The densities only give the pixel aspect ratio
+    Unknown,
+    /// Pixels per inch
+    Inch,
+    /// Pixels per centimeter
+    Centimeter,
+    /// Pixels per meter
+    Meter
+}
+
+/// The physical size of the pixels of an image
+///
+/// Read from PNG `pHYs` chunks and JPEG JFIF headers, encoders that
+/// can store it write it back
+///
+/// # Example
+/// ```
+/// use zune_image::metadata::{DensityUnit, PhysicalDimensions};
+///
+/// let dims = PhysicalDimensions::new(11811.0, 11811.0, DensityUnit::Meter);
+/// let (x, _) = dims.dpi().unwrap();
+///
+/// assert_eq!(x.round(), 300.0);
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PhysicalDimensions {
+    /// Horizontal pixels per unit
+    pub x_density: f32,
+    /// Vertical pixels per unit
+    pub y_density: f32,
+    pub unit:      DensityUnit
+}
+
+impl PhysicalDimensions {
+    /// Create physical dimensions of `x_density` and `y_density` pixels per `unit`
+    pub const fn new(x_density: f32, y_density: f32, unit: DensityUnit) -> PhysicalDimensions {
+        PhysicalDimensions {
+            x_density,
+            y_density,
+            unit
+        }
+    }
+    /// Create physical dimensions of `x_dpi` and `y_dpi` pixels per inch
+    pub const fn from_dpi(x_dpi: f32, y_dpi: f32) -> PhysicalDimensions {
+        PhysicalDimensions::new(x_dpi, y_dpi, DensityUnit::Inch)
+    }
+
+    /// Return the horizontal and vertical densities in `unit`, or none if this only
+    /// gives the aspect ratio, or `unit` is [`DensityUnit::Unknown`]
+    pub fn density_in(&self, unit: DensityUnit) -> Option<(f32, f32)> {
+        let meters_per_unit = |unit| match unit {
+            DensityUnit::Unknown => None,
+            DensityUnit::Inch => Some(0.0254),
+            DensityUnit::Centimeter => Some(0.01),
+            DensityUnit::Meter => Some(1.0)
+        };
+        let scale = meters_per_unit(unit)? / meters_per_unit(self.unit)?;
+
+        Some((self.x_density * scale, self.y_density * scale))
+    }
+    /// Return the horizontal and vertical densities in pixels per inch, or none
+    /// if this only gives the aspect ratio
+    pub fn dpi(&self) -> Option<(f32, f32)> {
+        self.density_in(DensityUnit::Inch)
+    }
 }
 
 /// Image metadata
@@ -36,16 +108,46 @@ pub enum AlphaState {
 pub struct ImageMetadata {
     // REMEMBER: If you add a field here add it's serialization
     // to mod file
-    pub(crate) color_trc: Option<ColorCharacteristics>,
+    pub(crate) color_trc:     Option<ColorCharacteristics>,
     pub(crate) default_gamma: Option<f32>,
-    pub(crate) width: usize,
-    pub(crate) height: usize,
-    pub(crate) colorspace: ColorSpace,
-    pub(crate) depth: BitDepth,
-    pub(crate) format: Option<ImageFormat>,
-    pub(crate) alpha: AlphaState,
+    pub(crate) width:         usize,
+    pub(crate) height:        usize,
+    pub(crate) colorspace:    ColorSpace,
+    pub(crate) depth:         BitDepth,
+    pub(crate) significant_bits: Option<Vec<u8>>,
+    pub(crate) format:        Option<ImageFormat>,
+    pub(crate) alpha:         AlphaState,
     #[cfg(feature = "metadata")]
-    pub(crate) exif: Option<Vec<::exif::Field>>,
+    pub(crate) exif:          Option<Vec<::exif::Field>>,
+    pub(crate) icc_chunk:     Option<Vec<u8>>,
+    pub(crate) text_chunks:   Vec<(String, String)>,
+    pub(crate) physical_dimensions: Option<PhysicalDimensions>,
+    pub(crate) truncated:     bool,
+    pub(crate) statistics:    Option<Vec<ChannelStatistics>>,
+    pub(crate) timings:       Option<DecodeTimings>
+}
+
+/// Categories of metadata an image may carry alongside its pixels
+///
+/// This is used to select which metadata should be removed
+/// from an image, see [`ImageMetadata::strip`]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum MetadataCategory {
+    /// Exif fields, including GPS information
+    Exif,
+    /// Embedded ICC color profile
+    Icc,
+    /// Textual key-value pairs, e.g png tEXt, zTXt and iTXt chunks
+    Text
+}
+
+impl MetadataCategory {
+    /// All supported metadata categories
+    pub const ALL: [MetadataCategory; 3] = [
+        MetadataCategory::Exif,
+        MetadataCategory::Icc,
+        MetadataCategory::Text
+    ];
 }
 
 impl Default for ImageMetadata {
@@ -57,10 +159,17 @@ impl Default for ImageMetadata {
             height: 0,
             colorspace: ColorSpace::Unknown,
             depth: BitDepth::default(),
+            significant_bits: None,
             format: None,
             alpha: AlphaState::NonPreMultiplied,
             #[cfg(feature = "metadata")]
             exif: None,
+            icc_chunk: None,
+            text_chunks: vec![],
+            physical_dimensions: None,
+            truncated: false,
+            statistics: None,
+            timings: None
         }
     }
 }
@@ -100,6 +209,40 @@ impl ImageMetadata {
             return None;
         }
     }
+    /// Return the exif orientation of the image, from 1 (upright) to 8
+    ///
+    /// Returns `None` if the image has no orientation tag or the
+    /// metadata feature isn't enabled
+    pub fn exif_orientation(&self) -> Option<u16> {
+        #[cfg(feature = "metadata")]
+        {
+            let field = self
+                .exif
+                .as_ref()?
+                .iter()
+                .find(|field| field.tag == ::exif::Tag::Orientation)?;
+
+            field.value.get_uint(0).and_then(|x| u16::try_from(x).ok())
+        }
+        #[cfg(not(feature = "metadata"))]
+        {
+            None
+        }
+    }
+    /// Set the exif orientation of the image, if it has an orientation tag
+    ///
+    /// This is a no-op if the metadata feature isn't enabled
+    #[allow(unused_variables)]
+    pub fn set_exif_orientation(&mut self, orientation: u16) {
+        #[cfg(feature = "metadata")]
+        {
+            let fields = self.exif.iter_mut().flatten();
+
+            for field in fields.filter(|field| field.tag == ::exif::Tag::Orientation) {
+                field.value = ::exif::Value::Short(vec![orientation]);
+            }
+        }
+    }
     /// Get image dimensions as a tuple of width and height
     ///  
     /// # Example
@@ -163,6 +306,18 @@ impl ImageMetadata {
     pub fn set_depth(&mut self, depth: BitDepth) {
         self.depth = depth;
     }
+    /// Get the number of significant bits of each channel as stored
+    /// in the file the image was decoded from
+    ///
+    /// This is set for png images with an `sBIT` chunk, e.g a 16 bit png holding
+    /// 12 bit scans reports `[12, 12, 12]` for its RGB channels
+    pub fn get_significant_bits(&self) -> Option<&[u8]> {
+        self.significant_bits.as_deref()
+    }
+    /// Set the number of significant bits of each channel
+    pub fn set_significant_bits(&mut self, bits: Option<Vec<u8>>) {
+        self.significant_bits = bits;
+    }
     /// Set the default gamma for this image
     ///
     /// This is gamma that will be used to convert this image
@@ -179,6 +334,11 @@ impl ImageMetadata {
     pub fn set_default_gamma(&mut self, gamma: f32) {
         self.default_gamma = Some(gamma);
     }
+    /// Return the gamma set by the decoder or with [`set_default_gamma`](Self::set_default_gamma),
+    /// or none if it isn't known
+    pub const fn default_gamma(&self) -> Option<f32> {
+        self.default_gamma
+    }
 
     /// Get the image for which this metadata was fetched from
     ///
@@ -197,4 +357,88 @@ impl ImageMetadata {
     pub fn set_alpha(&mut self, alpha_state: AlphaState) {
         self.alpha = alpha_state;
     }
+
+    /// Return true if the image was decoded from truncated input
+    ///
+    /// Decoders only salvage truncated images when
+    /// [`DecoderOptions::set_allow_partial`](zune_core::options::DecoderOptions::set_allow_partial)
+    /// is set, parts of the image past the end of the input are black
+    pub const fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// Return the time spent in each stage of decoding the image or none
+    /// if timings were not collected
+    ///
+    /// Timings are set by [`Image::read`](crate::image::Image::read) when
+    /// [`DecoderOptions::set_collect_timings`](zune_core::options::DecoderOptions::set_collect_timings)
+    /// is set
+    pub const fn decode_timings(&self) -> Option<&DecodeTimings> {
+        self.timings.as_ref()
+    }
+
+    /// Return the embedded ICC profile of the image or none if it
+    /// doesn't exist
+    pub fn icc_chunk(&self) -> Option<&[u8]> {
+        self.icc_chunk.as_deref()
+    }
+    /// Set the ICC profile that should be carried with the image
+    pub fn set_icc_chunk(&mut self, icc: Vec<u8>) {
+        self.icc_chunk = Some(icc);
+    }
+
+    /// Return textual metadata of the image as key-value pairs
+    pub fn text_chunks(&self) -> &[(String, String)] {
+        &self.text_chunks
+    }
+    /// Add a textual key-value pair to the image metadata
+    pub fn add_text_chunk(&mut self, key: String, value: String) {
+        self.text_chunks.push((key, value));
+    }
+
+    /// Return the physical size of the pixels of the image or none if
+    /// it isn't known
+    pub const fn physical_dimensions(&self) -> Option<PhysicalDimensions> {
+        self.physical_dimensions
+    }
+    /// Set the physical size of the pixels of the image, see also
+    /// [`SetDpi`](crate::core_filters::metadata::SetDpi)
+    pub fn set_physical_dimensions(&mut self, dimensions: PhysicalDimensions) {
+        self.physical_dimensions = Some(dimensions);
+    }
+
+    /// Return per channel statistics of the image or none if they
+    /// were not computed
+    ///
+    /// These are set by the [`Statistics`](crate::statistics::Statistics) operation
+    pub fn statistics(&self) -> Option<&[ChannelStatistics]> {
+        self.statistics.as_deref()
+    }
+    /// Set per channel statistics of the image
+    pub fn set_statistics(&mut self, statistics: Vec<ChannelStatistics>) {
+        self.statistics = Some(statistics);
+    }
+
+    /// Remove all metadata belonging to `category` from the image
+    ///
+    /// # Example
+    /// ```
+    /// use zune_image::metadata::{ImageMetadata, MetadataCategory};
+    /// let mut meta = ImageMetadata::default();
+    /// meta.set_icc_chunk(vec![0; 10]);
+    /// meta.strip(MetadataCategory::Icc);
+    /// assert!(meta.icc_chunk().is_none());
+    /// ```
+    pub fn strip(&mut self, category: MetadataCategory) {
+        match category {
+            MetadataCategory::Exif => {
+                #[cfg(feature = "metadata")]
+                {
+                    self.exif = None;
+                }
+            }
+            MetadataCategory::Icc => self.icc_chunk = None,
+            MetadataCategory::Text => self.text_chunks.clear()
+        }
+    }
 }
@@ -0,0 +1,236 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! Mipmap generation
+//!
+//! A mipmap chain is a sequence of images each half the size of the previous one,
+//! down to a single pixel. Texture pipelines upload the whole chain so the GPU can
+//! sample from the level closest to the size a texture is drawn at.
+//!
+//! See [`Image::generate_mipmaps`] for the entry point.
+use zune_core::bit_depth::BitDepth;
+use zune_core::colorspace::ColorSpace;
+
+use crate::channel::Channel;
+use crate::core_filters::transfer::{is_linear_light, is_srgb, linear_to_srgb, srgb_to_linear};
+use crate::errors::ImageErrors;
+use crate::image::Image;
+
+/// How pixels are combined when halving an image
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum MipmapFilter {
+    /// Average each 2x2 block of stored values
+    Box,
+    /// Average each 2x2 block in linear light, weighting colors by alpha.
+    ///
+    /// This keeps fine detail from darkening and transparent pixels from
+    /// bleeding into their neighbours. Colorspaces other than RGB and luma
+    /// are averaged like [`MipmapFilter::Box`]
+    LinearBox
+}
+
+/// How the float copy of the image differs from the stored image
+#[derive(Copy, Clone)]
+struct Conversion {
+    colorspace:    ColorSpace,
+    alpha:         Option<usize>,
+    /// The input has premultiplied alpha
+    premultiplied: bool,
+    /// Colors are multiplied by alpha while filtering
+    weighted:      bool,
+    /// Colors are in linear light while filtering
+    linear:        bool
+}
+
+impl Conversion {
+    /// Multiply or divide color channels by alpha, dividing by a zero alpha gives zero
+    fn weigh(&self, image: &mut Image, divide: bool) -> Result<(), ImageErrors> {
+        let Some(alpha_position) = self.alpha else {
+            return Ok(());
+        };
+        for frame in image.frames_mut() {
+            let channels = frame.channels_mut(self.colorspace, false);
+            let alpha = channels[alpha_position].reinterpret_as::<f32>()?.to_vec();
+
+            for (position, channel) in channels.iter_mut().enumerate() {
+                if position == alpha_position {
+                    continue;
+                }
+                for (color, a) in channel.reinterpret_as_mut::<f32>()?.iter_mut().zip(&alpha) {
+                    if !divide {
+                        *color *= a;
+                    } else if *a > 0.0 {
+                        *color /= a;
+                    } else {
+                        *color = 0.0;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Run color channels through a transfer function, leaving alpha untouched
+    fn transfer(&self, image: &mut Image, func: fn(f32) -> f32) -> Result<(), ImageErrors> {
+        for frame in image.frames_mut() {
+            for (position, channel) in frame
+                .channels_mut(self.colorspace, false)
+                .iter_mut()
+                .enumerate()
+            {
+                if Some(position) == self.alpha {
+                    continue;
+                }
+                for x in channel.reinterpret_as_mut::<f32>()? {
+                    *x = func(x.max(0.0));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Convert a float image of stored values to the space filtering happens in
+    fn forward(&self, image: &mut Image) -> Result<(), ImageErrors> {
+        if self.premultiplied {
+            self.weigh(image, true)?;
+        }
+        if self.linear {
+            self.transfer(image, srgb_to_linear)?;
+        }
+        if self.weighted {
+            self.weigh(image, false)?;
+        }
+        Ok(())
+    }
+
+    /// Undo [`Conversion::forward`]
+    fn backward(&self, image: &mut Image) -> Result<(), ImageErrors> {
+        if self.weighted {
+            self.weigh(image, true)?;
+        }
+        if self.linear {
+            self.transfer(image, linear_to_srgb)?;
+        }
+        if self.premultiplied {
+            self.weigh(image, false)?;
+        }
+        Ok(())
+    }
+}
+
+/// Average a channel down to new dimensions, every output pixel is the
+/// mean of the block of input pixels it covers
+#[allow(clippy::cast_precision_loss)]
+fn downsample(
+    channel: &Channel, width: usize, height: usize, new_width: usize, new_height: usize
+) -> Result<Channel, ImageErrors> {
+    let input = channel.reinterpret_as::<f32>()?;
+    let mut output =
+        Channel::new_with_length::<f32>(new_width * new_height * core::mem::size_of::<f32>());
+
+    for (y, out_row) in output
+        .reinterpret_as_mut::<f32>()?
+        .chunks_exact_mut(new_width)
+        .enumerate()
+    {
+        let (y0, y1) = (y * height / new_height, (y + 1) * height / new_height);
+
+        for (x, out) in out_row.iter_mut().enumerate() {
+            let (x0, x1) = (x * width / new_width, (x + 1) * width / new_width);
+
+            let sum: f32 = input[y0 * width..y1 * width]
+                .chunks_exact(width)
+                .map(|row| row[x0..x1].iter().sum::<f32>())
+                .sum();
+
+            *out = sum / ((y1 - y0) * (x1 - x0)) as f32;
+        }
+    }
+    Ok(output)
+}
+
+pub(crate) fn generate_mipmaps(
+    image: &Image, filter: MipmapFilter
+) -> Result<Vec<Image>, ImageErrors> {
+    let colorspace = image.colorspace();
+    let depth = image.depth();
+    let alpha = colorspace.alpha_position();
+    let premultiplied = alpha.is_some() && image.metadata().is_premultiplied_alpha();
+    let linear = filter == MipmapFilter::LinearBox;
+
+    let conversion = Conversion {
+        colorspace,
+        alpha,
+        premultiplied,
+        weighted: alpha.is_some() && (linear || premultiplied),
+        linear: linear && is_srgb(colorspace) && !is_linear_light(image)
+    };
+    // filtering runs on a float copy, so rounding errors don't add up between levels
+    let mut work = image.clone();
+    work.convert_depth(BitDepth::Float32)?;
+    conversion.forward(&mut work)?;
+
+    let (mut width, mut height) = image.dimensions();
+    let mut levels = vec![image.clone()];
+
+    while (width > 1 || height > 1) && width.min(height) > 0 {
+        let (new_width, new_height) = ((width / 2).max(1), (height / 2).max(1));
+
+        for frame in work.frames_mut() {
+            for channel in frame.channels_mut(colorspace, false) {
+                *channel = downsample(channel, width, height, new_width, new_height)?;
+            }
+        }
+        (width, height) = (new_width, new_height);
+        work.set_dimensions(width, height);
+
+        let mut level = work.clone();
+        conversion.backward(&mut level)?;
+        level.convert_depth(depth)?;
+        levels.push(level);
+    }
+    Ok(levels)
+}
+
+#[cfg(test)]
+mod tests {
+    use zune_core::colorspace::ColorSpace;
+
+    use crate::image::Image;
+    use crate::mipmaps::MipmapFilter;
+
+    #[test]
+    fn test_mipmap_sizes() {
+        let image = Image::fill(0_u16, ColorSpace::LumaA, 5, 3);
+        let levels = image.generate_mipmaps(MipmapFilter::Box).unwrap();
+
+        let sizes: Vec<_> = levels.iter().map(Image::dimensions).collect();
+        assert_eq!(sizes, [(5, 3), (2, 1), (1, 1)]);
+        assert!(levels.iter().all(|x| x.colorspace() == ColorSpace::LumaA));
+    }
+
+    #[test]
+    fn test_mipmap_filters() {
+        // a black and white checkerboard
+        let pixels: Vec<u8> = (0..16)
+            .map(|x| if (x + x / 4) % 2 == 0 { 0 } else { 255 })
+            .collect();
+        let image = Image::from_u8(&pixels, 4, 4, ColorSpace::Luma);
+
+        let level = |filter| image.generate_mipmaps(filter).unwrap()[2].flatten_to_u8()[0][0];
+        assert_eq!(level(MipmapFilter::Box), 127);
+        // half the light of white is brighter than half the stored value
+        assert_eq!(level(MipmapFilter::LinearBox), 187);
+
+        // transparent black doesn't darken opaque red
+        let image = Image::from_u8(&[255, 0, 0, 255, 0, 0, 0, 0], 2, 1, ColorSpace::RGBA);
+        let levels = image.generate_mipmaps(MipmapFilter::LinearBox).unwrap();
+
+        assert_eq!(levels[1].flatten_to_u8()[0], [254, 0, 0, 127]);
+    }
+}
@@ -6,15 +6,49 @@
 //! Pipelines, Batch image processing support
 //!
 #![allow(unused_variables)]
+use std::path::{Path, PathBuf};
 use std::time::Instant;
 
+use zune_core::bit_depth::{BitDepth, BitType};
+use zune_core::colorspace::ColorSpace;
 use zune_core::log::Level::Trace;
-use zune_core::log::{log_enabled, trace, Level};
+use zune_core::log::{log_enabled, trace, warn, Level};
 
+use crate::cancel::CancellationToken;
 use crate::codecs::ImageFormat;
+use crate::core_filters::orientation::ApplyOrientation;
+use crate::core_filters::transfer::{FromLinearLight, ToLinearLight};
 use crate::errors::ImageErrors;
 use crate::image::Image;
-use crate::traits::{EncoderTrait, IntoImage, OperationsTrait};
+#[cfg(feature = "mem-stats")]
+use crate::mem_stats::{MemoryUsage, StageTracker};
+use crate::metadata::ImageMetadata;
+use crate::traits::{EncoderTrait, IntoImage, LightSpace, OperationsTrait};
+
+/// Key of the text metadata entry holding the processing history
+///
+/// See [`Pipeline::set_record_history`]
+pub const HISTORY_KEY: &str = "zune-image history";
+
+/// What a pipeline does with the exif orientation of decoded images
+///
+/// See [`Pipeline::set_orientation_policy`]
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum OrientationPolicy {
+    /// Keep pixels as stored and the orientation tag as is, encoders
+    /// that write exif (e.g JPEG and PNG) carry it over to the output
+    ///
+    /// This is lossless and suits archival, but viewers that ignore
+    /// exif show the image turned.
+    #[default]
+    Preserve,
+    /// Rotate and mirror pixels so that images are upright before any operation
+    /// runs, and reset the orientation tag
+    ///
+    /// This suits outputs displayed by browsers and other viewers that may ignore exif,
+    /// and operations like crop then see the image the way it is displayed.
+    Apply
+}
 
 #[derive(Copy, Clone, Debug)]
 enum PipelineState {
@@ -78,7 +112,17 @@ pub struct Pipeline<T: IntoImage> {
     image:         Vec<Image>,
     operations:    Vec<Box<dyn OperationsTrait>>,
     encode:        Vec<Box<dyn EncoderTrait>>,
-    encode_result: Vec<EncodeResult>
+    encode_result: Vec<EncodeResult>,
+    dump:          Option<(PathBuf, ImageFormat)>,
+    history:       bool,
+    descriptions:  Vec<Option<String>>,
+    linear_light:  bool,
+    fused:         usize,
+    strict_encode: bool,
+    orientation:   OrientationPolicy,
+    cancel:        Option<CancellationToken>,
+    #[cfg(feature = "mem-stats")]
+    memory_usage:  Vec<MemoryUsage>
 }
 
 impl<T> Pipeline<T>
@@ -89,12 +133,22 @@ where
     #[allow(clippy::new_without_default)]
     pub fn new() -> Pipeline<T> {
         Pipeline {
-            image:         vec![],
-            state:         Some(PipelineState::Initialized),
-            decode:        None,
-            operations:    vec![],
-            encode:        vec![],
-            encode_result: vec![]
+            image: vec![],
+            state: Some(PipelineState::Initialized),
+            decode: None,
+            operations: vec![],
+            encode: vec![],
+            encode_result: vec![],
+            dump: None,
+            history: false,
+            descriptions: vec![],
+            linear_light: false,
+            fused: 0,
+            strict_encode: false,
+            orientation: OrientationPolicy::Preserve,
+            cancel: None,
+            #[cfg(feature = "mem-stats")]
+            memory_usage: vec![]
         }
     }
     /// Add a single encoder for this image
@@ -126,6 +180,165 @@ where
     pub fn add_operation(&mut self, operations: Box<dyn OperationsTrait>) {
         self.operations.push(operations);
     }
+    /// Write images to `dir` in `format` before the first operation and after every
+    /// operation, to find out which stage of a pipeline went wrong
+    ///
+    /// Files are named `<image>-<stage>-<operation>.<extension>`, e.g `0-02-gaussian-blur.png`
+    /// is the first image after the second operation, and `0-00-input.png` is the image
+    /// before any operation ran.
+    ///
+    /// The directory is created if it doesn't exist, and images are converted
+    /// to a depth and colorspace the encoder supports if needed.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use zune_image::codecs::ImageFormat;
+    /// use zune_image::core_filters::depth::Depth;
+    /// use zune_image::image::Image;
+    /// use zune_image::pipelines::Pipeline;
+    ///
+    /// let mut pipeline = Pipeline::<Image>::new();
+    /// pipeline.set_intermediate_dump("stages", ImageFormat::PNG);
+    /// pipeline.add_operation(Box::new(Depth::new(zune_core::bit_depth::BitDepth::Sixteen)));
+    /// ```
+    pub fn set_intermediate_dump<P: AsRef<Path>>(&mut self, dir: P, format: ImageFormat) {
+        self.dump = Some((dir.as_ref().to_path_buf(), format));
+    }
+    /// Record the operations ran on every image in its metadata, so that outputs
+    /// describe how they were made
+    ///
+    /// After all operations run, a text entry keyed [`HISTORY_KEY`] is added to the
+    /// image metadata, containing the library version followed by one line per
+    /// operation, e.g
+    ///
+    /// ```text
+    /// zune-image 0.4.12
+    /// 1. Brighten: brighten 0.2
+    /// 2. Transpose
+    /// ```
+    /// If the image already has a history (e.g it was made by an earlier run), the new
+    /// record is appended to it.
+    ///
+    /// Encoders that support text metadata (e.g PNG) write it with the image
+    /// unless metadata is stripped.
+    ///
+    /// Operation parameters are recorded when they are set with
+    /// [`set_operation_description`](Self::set_operation_description)
+    pub fn set_record_history(&mut self, yes: bool) {
+        self.history = yes;
+    }
+    /// Run operations on linear light instead of sRGB encoded values
+    ///
+    /// Filters that average pixels, e.g blurs and resizing, are only correct on values
+    /// proportional to light, averaging sRGB encoded values darkens edges and fine detail.
+    ///
+    /// When set, images are converted to linear light floats before the first operation
+    /// and back to sRGB in their original depth after the last one.
+    /// Operations returning [`LightSpace::Encoded`] from
+    /// [`OperationsTrait::light_space`] are given sRGB encoded values (still as floats), and the
+    /// image is moved back to linear light before the next operation returning [`LightSpace::Linear`].
+    ///
+    /// Only RGB and luma colorspaces are linearized, other colorspaces are processed
+    /// as stored, in float.
+    ///
+    /// Default is false
+    pub fn process_in_linear_light(&mut self, yes: bool) {
+        self.linear_light = yes;
+    }
+    /// Set whether encoders should reject images in colorspaces they can't write
+    ///
+    /// By default images are converted to the nearest supported colorspace
+    /// with a warning, see [`EncoderTrait::encode_with_conversion`]
+    ///
+    /// Default is false
+    pub fn set_strict_encoding(&mut self, yes: bool) {
+        self.strict_encode = yes;
+    }
+    /// Set what to do with the exif orientation of images
+    ///
+    /// With [`OrientationPolicy::Apply`] images are made upright before the first operation,
+    /// with [`OrientationPolicy::Preserve`] the orientation tag is left for encoders to
+    /// write, and a warning is logged for encoders that cannot store it.
+    ///
+    /// Orientation is only known with the `metadata` feature.
+    ///
+    /// Default is [`OrientationPolicy::Preserve`]
+    pub fn set_orientation_policy(&mut self, policy: OrientationPolicy) {
+        self.orientation = policy;
+    }
+    /// Set a token that stops the pipeline once cancelled
+    ///
+    /// The token is checked before decoding, before every operation and before every encoder,
+    /// and [`advance`](Self::advance) returns [`ImageErrors::Cancelled`] when it was cancelled.
+    /// Operations run with the token [current](crate::cancel::current), so long running
+    /// kernels (e.g blurs) stop within a row of it being cancelled.
+    ///
+    /// A cancelled pipeline cannot be resumed, images may be left partially processed.
+    pub fn set_cancellation_token(&mut self, token: CancellationToken) {
+        self.cancel = Some(token);
+    }
+    /// Describe the parameters of the operation at `position` (counting from zero
+    /// in order of addition), used when recording the processing history
+    pub fn set_operation_description(&mut self, position: usize, description: String) {
+        if self.descriptions.len() <= position {
+            self.descriptions.resize(position + 1, None);
+        }
+        self.descriptions[position] = Some(description);
+    }
+    /// Return the operations queued in this pipeline, in order of execution
+    pub fn operations(&self) -> &[Box<dyn OperationsTrait>] {
+        &self.operations
+    }
+    /// Return the names of the queued operations, in order of execution
+    pub fn operation_names(&self) -> Vec<&'static str> {
+        self.operations.iter().map(|x| x.name()).collect()
+    }
+    /// Insert an operation at `position`, shifting the operations after it
+    ///
+    /// Descriptions set with [`set_operation_description`](Self::set_operation_description)
+    /// move along with their operations.
+    ///
+    /// Changes only take effect if the operations haven't ran yet
+    ///
+    /// # Panics
+    /// If `position` is greater than the number of operations
+    pub fn insert_operation(&mut self, position: usize, operation: Box<dyn OperationsTrait>) {
+        self.operations.insert(position, operation);
+
+        if position < self.descriptions.len() {
+            self.descriptions.insert(position, None);
+        }
+    }
+    /// Remove and return the operation at `position`, shifting the operations after it
+    ///
+    /// # Panics
+    /// If `position` is out of bounds
+    pub fn remove_operation(&mut self, position: usize) -> Box<dyn OperationsTrait> {
+        if position < self.descriptions.len() {
+            self.descriptions.remove(position);
+        }
+        self.operations.remove(position)
+    }
+    /// Move the operation at `from` so that it ends up at `to`,
+    /// shifting the operations in between
+    ///
+    /// # Panics
+    /// If `from` or `to` are out of bounds
+    pub fn move_operation(&mut self, from: usize, to: usize) {
+        assert!(to < self.operations.len(), "Cannot move operation to {to}");
+
+        let description = self.descriptions.get_mut(from).and_then(Option::take);
+        let operation = self.remove_operation(from);
+        self.insert_operation(to, operation);
+
+        if let Some(description) = description {
+            self.set_operation_description(to, description);
+        }
+    }
+    /// Return the encoders queued in this pipeline, in order of execution
+    pub fn encoders(&self) -> &[Box<dyn EncoderTrait>] {
+        &self.encode
+    }
     /// Add an image to this chain.
     pub fn chain_image(&mut self, image: Image) {
         self.image.push(image);
@@ -177,6 +390,20 @@ where
     pub fn images(&self) -> &[Image] {
         self.image.as_ref()
     }
+    /// Return the metadata of the images in the pipeline
+    ///
+    /// If the decoder hasn't run, or was skipped since nothing needed pixels, this
+    /// includes the metadata the decoder knows from the image headers,
+    /// see [`IntoImage::headers`]
+    pub fn metadata(&self) -> Vec<&ImageMetadata> {
+        let pending = self.decode.as_ref().and_then(T::headers);
+
+        self.image
+            .iter()
+            .map(Image::metadata)
+            .chain(pending)
+            .collect()
+    }
     /// Return all images in the workflow as mutable references
     pub fn images_mut(&mut self) -> &mut [Image] {
         self.image.as_mut()
@@ -198,8 +425,9 @@ where
                     let start = Instant::now();
                     // do the actual decode
                     if self.decode.is_none() {
-                        // we have an image, no need to decode a new one
-                        if self.image.is_empty() {
+                        // we have an image from chain_image, no need to decode a new one,
+                        // without one there is nothing to run operations on
+                        if !self.image.is_empty() {
                             trace!("Image already present, no need to decode");
                             // move to the next state
                             self.state = state.next();
@@ -208,18 +436,54 @@ where
                         }
                         return Err(ImageErrors::NoImageForOperations);
                     }
+                    let has_headers = self.decode.as_ref().and_then(T::headers).is_some();
+
+                    if has_headers && self.operations.is_empty() && self.encode.is_empty() {
+                        // nothing needs pixels, the headers are all that can be looked at
+                        trace!("No operations or encoders, skipping decoding");
+                        self.state = None;
+
+                        return Ok(());
+                    }
 
                     if log_enabled!(Trace) {
                         println!();
                         trace!("Current state: {:?}\n", state);
                     }
 
+                    self.check_cancelled()?;
+
                     let decode_op = self.decode.take().unwrap();
 
-                    let img = decode_op.into_image()?;
+                    #[cfg(feature = "mem-stats")]
+                    let tracker = StageTracker::start("Decode");
+
+                    // point operations at the start run over the decoder's interleaved
+                    // output, sparing a pass over the channels for each of them
+                    let operations: Vec<&dyn OperationsTrait> =
+                        if self.linear_light || self.dump.is_some() {
+                            vec![]
+                        } else {
+                            self.operations.iter().map(AsRef::as_ref).collect()
+                        };
+                    let (img, fused) = decode_op.into_image_with(&operations)?;
 
+                    if fused > 0 {
+                        trace!(
+                            "Ran {} while decoding",
+                            operations[..fused]
+                                .iter()
+                                .map(|x| x.name())
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        );
+                    }
+                    self.fused = fused;
                     self.image.push(img);
 
+                    #[cfg(feature = "mem-stats")]
+                    self.memory_usage.push(tracker.finish());
+
                     let stop = Instant::now();
 
                     self.state = state.next();
@@ -235,23 +499,85 @@ where
                         println!();
                         trace!("Current state: {:?}\n", state);
                     }
+                    let _scope = self.cancel.as_ref().map(CancellationToken::enter);
+
+                    let images = self.image.len();
+
+                    for (position, image) in self.image.iter_mut().enumerate() {
+                        if self.orientation == OrientationPolicy::Apply {
+                            ApplyOrientation::new().execute(image)?;
+                        }
+                        if let Some((dir, format)) = &self.dump {
+                            dump_stage(image, dir, *format, position, 0, "input")?;
+                        }
+                        // the decoded image is the last one, the operations it
+                        // ran while decoding are skipped
+                        let fused = if position + 1 == images { self.fused } else { 0 };
+
+                        // the image is kept in floats while in linear light, so the depth
+                        // it returns to is the one the operations would have produced
+                        let depth = self
+                            .operations
+                            .iter()
+                            .skip(fused)
+                            .fold(image.depth(), |depth, operation| operation.output_depth(depth));
+
+                        if self.linear_light {
+                            ToLinearLight::new().execute(image)?;
+                        }
+                        for (stage, operation) in self.operations.iter().enumerate().skip(fused) {
+                            crate::cancel::check()?;
 
-                    for image in self.image.iter_mut() {
-                        for operation in &self.operations {
                             let operation_name = operation.name();
 
+                            if self.linear_light {
+                                match operation.light_space() {
+                                    LightSpace::Linear => ToLinearLight::new().execute(image)?,
+                                    LightSpace::Encoded => {
+                                        FromLinearLight::new(BitDepth::Float32).execute(image)?;
+                                    }
+                                    LightSpace::Any => {}
+                                }
+                            }
+
                             trace!("Running {}", operation_name);
 
                             let start = Instant::now();
 
+                            #[cfg(feature = "mem-stats")]
+                            let tracker = StageTracker::start(operation_name);
+
                             operation.execute(image)?;
 
+                            #[cfg(feature = "mem-stats")]
+                            self.memory_usage.push(tracker.finish());
+
                             let stop = Instant::now();
 
                             trace!(
                                 "Finished running `{operation_name}` in {} ms",
                                 (stop - start).as_millis()
                             );
+
+                            if let Some((dir, format)) = &self.dump {
+                                dump_stage(
+                                    image,
+                                    dir,
+                                    *format,
+                                    position,
+                                    stage + 1,
+                                    operation_name
+                                )?;
+                            }
+                        }
+                        // the last operation may have stopped early
+                        crate::cancel::check()?;
+
+                        if self.linear_light {
+                            FromLinearLight::new(depth).execute(image)?;
+                        }
+                        if self.history && !self.operations.is_empty() {
+                            record_history(image, &self.operations, &self.descriptions);
                         }
                         self.state = state.next();
                     }
@@ -270,11 +596,31 @@ where
                         for encoder in self.encode.iter_mut() {
                             let encoder_name = encoder.name();
 
+                            if let Some(token) = &self.cancel {
+                                token.check()?;
+                            }
                             trace!("Running {}", encoder_name);
 
+                            if let Some(orientation) = image.metadata().exif_orientation() {
+                                if orientation != 1 && !encoder.supports_exif() {
+                                    warn!("{encoder_name} cannot store the exif orientation ({orientation}) of the image, it will be displayed turned, apply the orientation to keep it upright");
+                                }
+                            }
+
                             let start = Instant::now();
 
-                            let result = encoder.encode_to_result(image)?;
+                            #[cfg(feature = "mem-stats")]
+                            let tracker = StageTracker::start(encoder_name);
+
+                            let result = EncodeResult {
+                                data:   encoder
+                                    .encode_with_conversion(image, self.strict_encode)?,
+                                format: encoder.format()
+                            };
+
+                            #[cfg(feature = "mem-stats")]
+                            self.memory_usage.push(tracker.finish());
+
                             self.encode_result.push(result);
                             let stop = Instant::now();
 
@@ -316,7 +662,479 @@ where
         }
         Ok(())
     }
+    /// Return [`ImageErrors::Cancelled`] if the cancellation token was cancelled
+    fn check_cancelled(&self) -> Result<(), ImageErrors> {
+        match &self.cancel {
+            Some(token) => token.check(),
+            None => Ok(())
+        }
+    }
     pub fn get_results(&self) -> &[EncodeResult] {
         &self.encode_result
     }
+    /// Return memory usage of each decoder, operation and encoder
+    /// that has been run, in order of execution
+    ///
+    /// Numbers are only meaningful if [`TrackingAllocator`](crate::mem_stats::TrackingAllocator)
+    /// is the global allocator
+    #[cfg(feature = "mem-stats")]
+    pub fn memory_usage(&self) -> &[MemoryUsage] {
+        &self.memory_usage
+    }
+}
+
+/// Return the colorspace 8 bit pixels in `colorspace` are in after running
+/// `operations` through their interleaved kernels, or `None` if one of them can't
+pub(crate) fn interleaved_output(
+    colorspace: ColorSpace, operations: &[&dyn OperationsTrait]
+) -> Option<ColorSpace> {
+    operations
+        .iter()
+        .try_fold(colorspace, |colorspace, operation| {
+            let supported = operation.supported_colorspaces().contains(&colorspace)
+                && operation.supported_types().contains(&BitType::U8);
+
+            supported
+                .then(|| operation.interleaved_colorspace(colorspace))
+                .flatten()
+        })
+}
+
+/// Return how many of the first `operations` can run through their interleaved kernels
+/// on 8 bit pixels in `colorspace`
+fn interleaved_run(colorspace: ColorSpace, operations: &[&dyn OperationsTrait]) -> usize {
+    let mut colorspace = colorspace;
+
+    operations
+        .iter()
+        .take_while(
+            |operation| match interleaved_output(colorspace, &[**operation]) {
+                Some(output) => {
+                    colorspace = output;
+                    true
+                }
+                None => false
+            }
+        )
+        .count()
+}
+
+/// Run `operations` on interleaved 8 bit `pixels` in `colorspace` through their
+/// interleaved kernels, returning the colorspace the pixels are in afterwards
+pub(crate) fn execute_interleaved(
+    pixels: &mut Vec<u8>, colorspace: ColorSpace, operations: &[&dyn OperationsTrait]
+) -> Result<ColorSpace, ImageErrors> {
+    let output = interleaved_output(colorspace, operations).ok_or(ImageErrors::GenericStr(
+        "Operations have no interleaved kernels for the image colorspace"
+    ))?;
+    let length = pixels.len() / colorspace.num_components();
+    let mut colorspace = colorspace;
+
+    for operation in operations {
+        operation.execute_interleaved(pixels, colorspace)?;
+        // checked by interleaved_output
+        colorspace = operation.interleaved_colorspace(colorspace).unwrap();
+    }
+    if pixels.len() != length * output.num_components() {
+        return Err(ImageErrors::DimensionsMisMatch(
+            length * output.num_components(),
+            pixels.len()
+        ));
+    }
+    Ok(output)
+}
+
+/// Create an image from interleaved 8 bit `pixels`, running the leading `operations`
+/// that have interleaved kernels over them before they are split into channels
+///
+/// Returns the image and how many of `operations` ran,
+/// for decoders implementing [`DecoderTrait::decode_with`]
+///
+/// [`DecoderTrait::decode_with`]: crate::traits::DecoderTrait::decode_with
+pub(crate) fn fused_image(
+    mut pixels: Vec<u8>, width: usize, height: usize, colorspace: ColorSpace,
+    operations: &[&dyn OperationsTrait]
+) -> Result<(Image, usize), ImageErrors> {
+    let run = interleaved_run(colorspace, operations);
+    let colorspace = execute_interleaved(&mut pixels, colorspace, &operations[..run])?;
+
+    Ok((Image::from_u8(&pixels, width, height, colorspace), run))
+}
+
+/// Append the operations ran on `image` to its history text metadata
+fn record_history(
+    image: &mut Image, operations: &[Box<dyn OperationsTrait>], descriptions: &[Option<String>]
+) {
+    let mut record = format!("zune-image {}", env!("CARGO_PKG_VERSION"));
+
+    for (stage, operation) in operations.iter().enumerate() {
+        record.push_str(&format!("\n{}. {}", stage + 1, operation.name()));
+
+        if let Some(Some(description)) = descriptions.get(stage) {
+            record.push_str(&format!(": {description}"));
+        }
+    }
+    let text_chunks = &mut image.metadata.text_chunks;
+
+    if let Some((_, history)) = text_chunks.iter_mut().find(|(key, _)| key == HISTORY_KEY) {
+        history.push('\n');
+        history.push_str(&record);
+    } else {
+        text_chunks.push((HISTORY_KEY.to_string(), record));
+    }
+}
+
+/// Write `image` after `stage` of the pipeline to `dir`
+fn dump_stage(
+    image: &Image, dir: &Path, format: ImageFormat, position: usize, stage: usize, name: &str
+) -> Result<(), ImageErrors> {
+    // operation names have spaces and capitals, keep file names simple
+    let name: String = name
+        .chars()
+        .map(
+            |x| {
+                if x.is_ascii_alphanumeric() {
+                    x.to_ascii_lowercase()
+                } else {
+                    '-'
+                }
+            }
+        )
+        .collect();
+    let extension = format.file_extensions().first().unwrap_or(&"bin");
+    let path = dir.join(format!("{position}-{stage:02}-{name}.{extension}"));
+
+    trace!("Writing stage {stage} to {path:?}");
+
+    std::fs::create_dir_all(dir)?;
+    image.save_to(path, format)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    use zune_core::bit_depth::{BitDepth, BitType};
+    use zune_core::colorspace::ColorSpace;
+    use zune_core::options::DecoderOptions;
+
+    use crate::cancel::CancellationToken;
+    use crate::codecs::ImageFormat;
+    use crate::core_filters::colorspace::grayscale::{LumaWeights, RgbToGrayScale};
+    use crate::core_filters::colorspace::ColorspaceConv;
+    use crate::core_filters::depth::Depth;
+    use crate::errors::ImageErrors;
+    use crate::image::Image;
+    use crate::lazy::LazyImage;
+    use crate::pipelines::{Pipeline, HISTORY_KEY};
+    use crate::testing::synthetic_image;
+    use crate::traits::{LightSpace, OperationsTrait};
+
+    #[test]
+    #[cfg(feature = "ppm")]
+    fn test_intermediate_dump() {
+        let dir = std::env::temp_dir().join(format!("zune-dump-{}", std::process::id()));
+
+        let mut pipeline = Pipeline::<Image>::new();
+        pipeline.chain_image(Image::fill(100_u8, ColorSpace::RGB, 4, 4));
+        pipeline.set_intermediate_dump(&dir, ImageFormat::PPM);
+        pipeline.add_operation(Box::new(ColorspaceConv::new(ColorSpace::Luma)));
+        pipeline.add_operation(Box::new(Depth::new(BitDepth::Sixteen)));
+        pipeline.advance_to_end().unwrap();
+
+        let mut files: Vec<String> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|x| x.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        files.sort();
+
+        let luma = Image::open(dir.join(&files[1])).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(
+            files,
+            [
+                "0-00-input.ppm",
+                "0-01-colorspace-conversion.ppm",
+                "0-02-depth.ppm"
+            ]
+        );
+        assert_eq!(luma.colorspace(), ColorSpace::Luma);
+    }
+
+    #[test]
+    #[cfg(feature = "png")]
+    fn test_history_round_trip() {
+        let mut pipeline = Pipeline::<Image>::new();
+        pipeline.chain_image(Image::fill(100_u8, ColorSpace::RGB, 4, 4));
+        pipeline.set_record_history(true);
+        pipeline.add_operation(Box::new(ColorspaceConv::new(ColorSpace::Luma)));
+        pipeline.add_operation(Box::new(Depth::new(BitDepth::Sixteen)));
+        pipeline.set_operation_description(1, "depth 16".to_string());
+        pipeline.advance_to_end().unwrap();
+
+        let encoded = pipeline.images()[0].write_to_vec(ImageFormat::PNG).unwrap();
+        let decoded = Image::read(encoded, Default::default()).unwrap();
+
+        let expected = format!(
+            "zune-image {}\n1. Colorspace conversion\n2. Depth: depth 16",
+            env!("CARGO_PKG_VERSION")
+        );
+        assert_eq!(
+            decoded.metadata().text_chunks(),
+            [(HISTORY_KEY.to_string(), expected.clone())]
+        );
+
+        // a second run appends to the existing history
+        let mut pipeline = Pipeline::<Image>::new();
+        pipeline.chain_image(decoded);
+        pipeline.set_record_history(true);
+        pipeline.add_operation(Box::new(Depth::new(BitDepth::Eight)));
+        pipeline.advance_to_end().unwrap();
+
+        let history = &pipeline.images()[0].metadata().text_chunks()[0].1;
+        assert_eq!(
+            *history,
+            format!(
+                "{expected}\nzune-image {}\n1. Depth",
+                env!("CARGO_PKG_VERSION")
+            )
+        );
+    }
+
+    #[test]
+    fn test_pipeline_without_decoder() {
+        // images added via chain_image are used as they are
+        let mut pipeline = Pipeline::<Image>::new();
+        pipeline.chain_image(Image::fill(100_u8, ColorSpace::RGB, 4, 4));
+        pipeline.add_operation(Box::new(Depth::new(BitDepth::Sixteen)));
+        pipeline.advance_to_end().unwrap();
+
+        assert_eq!(pipeline.images()[0].depth(), BitDepth::Sixteen);
+
+        // with no image and no decoder there is nothing to work on
+        let mut pipeline = Pipeline::<Image>::new();
+        pipeline.add_operation(Box::new(Depth::new(BitDepth::Sixteen)));
+
+        assert!(matches!(
+            pipeline.advance_to_end(),
+            Err(ImageErrors::NoImageForOperations)
+        ));
+    }
+
+    #[test]
+    fn test_modify_operations() {
+        let mut pipeline = Pipeline::<Image>::new();
+        pipeline.chain_image(Image::fill(100_u8, ColorSpace::RGB, 4, 4));
+        pipeline.set_record_history(true);
+        pipeline.add_operation(Box::new(Depth::new(BitDepth::Sixteen)));
+        pipeline.add_operation(Box::new(Depth::new(BitDepth::Eight)));
+        pipeline.set_operation_description(0, "depth 16".to_string());
+
+        pipeline.insert_operation(1, Box::new(ColorspaceConv::new(ColorSpace::Luma)));
+        assert_eq!(
+            pipeline.operation_names(),
+            ["Depth", "Colorspace conversion", "Depth"]
+        );
+        // descriptions follow their operations
+        pipeline.move_operation(0, 2);
+        assert_eq!(pipeline.remove_operation(0).name(), "Colorspace conversion");
+        pipeline.insert_operation(0, Box::new(ColorspaceConv::new(ColorSpace::Luma)));
+
+        pipeline.advance_to_end().unwrap();
+
+        let image = &pipeline.images()[0];
+        assert_eq!(image.colorspace(), ColorSpace::Luma);
+        assert_eq!(image.depth(), BitDepth::Sixteen);
+        assert!(image.metadata().text_chunks()[0]
+            .1
+            .ends_with("1. Colorspace conversion\n2. Depth\n3. Depth: depth 16"));
+    }
+
+    /// Averages the two pixels of a 2x1 float luma image
+    struct Average(LightSpace);
+
+    impl OperationsTrait for Average {
+        fn name(&self) -> &'static str {
+            "Average"
+        }
+
+        fn execute_impl(&self, image: &mut Image) -> Result<(), ImageErrors> {
+            let channel = &mut image.frames_mut()[0].channels_mut(ColorSpace::Luma, false)[0];
+            let values = channel.reinterpret_as_mut::<f32>()?;
+            let mean = (values[0] + values[1]) / 2.0;
+            values.fill(mean);
+            Ok(())
+        }
+
+        fn supported_types(&self) -> &'static [BitType] {
+            &[BitType::F32]
+        }
+
+        fn light_space(&self) -> LightSpace {
+            self.0
+        }
+    }
+
+    /// Adds one to every sample, recording whether the interleaved kernel ran
+    struct AddOne(Rc<Cell<bool>>);
+
+    impl OperationsTrait for AddOne {
+        fn name(&self) -> &'static str {
+            "Add one"
+        }
+
+        fn execute_impl(&self, image: &mut Image) -> Result<(), ImageErrors> {
+            for channel in image.channels_mut(false) {
+                for sample in channel.reinterpret_as_mut::<u8>()? {
+                    *sample = sample.wrapping_add(1);
+                }
+            }
+            Ok(())
+        }
+
+        fn supported_types(&self) -> &'static [BitType] {
+            &[BitType::U8]
+        }
+
+        fn interleaved_colorspace(&self, input: ColorSpace) -> Option<ColorSpace> {
+            Some(input)
+        }
+
+        fn execute_interleaved(
+            &self, pixels: &mut Vec<u8>, _: ColorSpace
+        ) -> Result<(), ImageErrors> {
+            self.0.set(true);
+
+            for sample in pixels {
+                *sample = sample.wrapping_add(1);
+            }
+            Ok(())
+        }
+    }
+
+    /// Cancels its token, like a user pressing cancel while it runs
+    struct Cancel(CancellationToken);
+
+    impl OperationsTrait for Cancel {
+        fn name(&self) -> &'static str {
+            "Cancel"
+        }
+
+        fn execute_impl(&self, _: &mut Image) -> Result<(), ImageErrors> {
+            self.0.cancel();
+            Ok(())
+        }
+
+        fn supported_types(&self) -> &'static [BitType] {
+            &[BitType::U8, BitType::U16, BitType::F32]
+        }
+    }
+
+    #[test]
+    fn test_cancellation() {
+        let token = CancellationToken::new();
+
+        let mut pipeline = Pipeline::<Image>::new();
+        pipeline.chain_image(Image::fill(100_u8, ColorSpace::RGB, 4, 4));
+        pipeline.set_cancellation_token(token.clone());
+        pipeline.add_operation(Box::new(Cancel(token.clone())));
+        pipeline.add_operation(Box::new(Depth::new(BitDepth::Sixteen)));
+
+        assert!(matches!(
+            pipeline.advance_to_end(),
+            Err(ImageErrors::Cancelled)
+        ));
+        assert!(token.is_cancelled());
+        // operations after the cancel didn't run
+        assert_eq!(pipeline.images()[0].depth(), BitDepth::Eight);
+    }
+
+    #[test]
+    #[cfg(feature = "ppm")]
+    fn test_operations_while_decoding() {
+        let image = synthetic_image(ColorSpace::RGB, BitDepth::Eight, 101, 67);
+
+        let mut expected = image.clone();
+        AddOne(Rc::default()).execute(&mut expected).unwrap();
+        RgbToGrayScale::new(LumaWeights::Bt709)
+            .execute(&mut expected)
+            .unwrap();
+
+        let mut ppm = b"P6 101 67 255\n".to_vec();
+        ppm.extend_from_slice(&image.flatten_to_u8()[0]);
+
+        let lazy = || LazyImage::new(ppm.clone(), DecoderOptions::default()).unwrap();
+
+        let ran_interleaved = Rc::new(Cell::new(false));
+
+        let mut pipeline = Pipeline::<LazyImage>::new();
+        pipeline.add_decoder(lazy());
+        pipeline.add_operation(Box::new(AddOne(ran_interleaved.clone())));
+        pipeline.add_operation(Box::new(RgbToGrayScale::new(LumaWeights::Bt709)));
+        pipeline.advance_to_end().unwrap();
+
+        assert!(ran_interleaved.get());
+        assert_eq!(pipeline.images()[0].colorspace(), ColorSpace::Luma);
+        assert_eq!(pipeline.images()[0].eq_exact(&expected), Ok(()));
+
+        // images that were already decoded run on channels
+        let ran_interleaved = Rc::new(Cell::new(false));
+
+        let mut pipeline = Pipeline::<Image>::new();
+        pipeline.chain_image(image);
+        pipeline.add_operation(Box::new(AddOne(ran_interleaved.clone())));
+        pipeline.add_operation(Box::new(RgbToGrayScale::new(LumaWeights::Bt709)));
+        pipeline.advance_to_end().unwrap();
+
+        assert!(!ran_interleaved.get());
+        assert_eq!(pipeline.images()[0].eq_exact(&expected), Ok(()));
+
+        // operations after the first without an interleaved kernel run on channels
+        let ran_interleaved = Rc::new(Cell::new(false));
+
+        let mut pipeline = Pipeline::<LazyImage>::new();
+        pipeline.add_decoder(lazy());
+        pipeline.add_operation(Box::new(AddOne(ran_interleaved.clone())));
+        pipeline.add_operation(Box::new(Depth::new(BitDepth::Sixteen)));
+        pipeline.advance_to_end().unwrap();
+
+        assert!(ran_interleaved.get());
+        assert_eq!(pipeline.images()[0].depth(), BitDepth::Sixteen);
+    }
+
+    #[test]
+    fn test_linear_light() {
+        let run = |space| {
+            let mut pipeline = Pipeline::<Image>::new();
+            pipeline.chain_image(Image::from_u8(&[0, 254], 2, 1, ColorSpace::Luma));
+            pipeline.process_in_linear_light(true);
+            pipeline.add_operation(Box::new(Average(space)));
+            pipeline.advance_to_end().unwrap();
+
+            let image = &pipeline.images()[0];
+            assert_eq!(image.depth(), BitDepth::Eight);
+            image.flatten_to_u8()[0][0]
+        };
+        // half the light of a bright pixel is brighter than half of its encoded value
+        assert_eq!(run(LightSpace::Linear), 187);
+        assert_eq!(run(LightSpace::Encoded), 127);
+    }
+
+    #[test]
+    fn test_linear_light_with_depth() {
+        let mut pipeline = Pipeline::<Image>::new();
+        pipeline.chain_image(Image::from_u8(&[0, 254], 2, 1, ColorSpace::Luma));
+        pipeline.process_in_linear_light(true);
+        pipeline.add_operation(Box::new(Depth::new(BitDepth::Sixteen)));
+        pipeline.add_operation(Box::new(Average(LightSpace::Linear)));
+        pipeline.advance_to_end().unwrap();
+
+        let image = &pipeline.images()[0];
+        assert_eq!(image.depth(), BitDepth::Sixteen);
+        // 187 scaled to 16 bits, give or take rounding
+        let value = image.flatten_frames::<u16>()[0][0];
+        assert!((value / 257).abs_diff(187) <= 1, "{value}");
+    }
 }
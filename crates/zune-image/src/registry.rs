@@ -0,0 +1,492 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! Runtime discovery of available codecs
+//!
+//! Which decoders and encoders exist depends on the cargo features the library
+//! was compiled with, this module allows applications to query that at runtime
+//! instead of mirroring the feature list.
+//!
+//! It also allows downstream crates to register their own decoders and encoders,
+//! see [`register_decoder`] and [`register_encoder`], registered formats are
+//! identified by [`ImageFormat::External`] and are picked up by [`Image::open`](crate::image::Image::open),
+//! [`Image::save`](crate::image::Image::save) and anything built on top of them.
+//!
+//! # Example
+//! - List formats that can be written
+//! ```
+//! use zune_image::registry::available_codecs;
+//!
+//! for codec in available_codecs().iter().filter(|x| x.has_encoder()) {
+//!     println!("{} ({})", codec.name(), codec.extensions().join(","));
+//! }
+//! ```
+use std::marker::PhantomData;
+use std::sync::RwLock;
+
+use zune_core::bit_depth::BitDepth;
+use zune_core::bytestream::ZReaderTrait;
+use zune_core::colorspace::ColorSpace;
+use zune_core::options::{DecoderOptions, EncoderOptions};
+
+use crate::codecs::ImageFormat;
+use crate::errors::ImageErrors;
+use crate::image::Image;
+use crate::metadata::ImageMetadata;
+use crate::traits::{DecoderTrait, EncoderTrait};
+
+/// A function creating a decoder for an externally registered format
+///
+/// The decoder receives the whole encoded image
+pub type DecoderConstructor = fn(Vec<u8>, DecoderOptions) -> Box<dyn DecoderTrait<Vec<u8>>>;
+
+/// A function creating an encoder for an externally registered format
+pub type EncoderConstructor = fn(EncoderOptions) -> Box<dyn EncoderTrait>;
+
+struct ExternalFormat {
+    name:        &'static str,
+    magic_bytes: Vec<&'static [u8]>,
+    extensions:  &'static [&'static str],
+    decoder:     Option<DecoderConstructor>,
+    encoder:     Option<EncoderConstructor>
+}
+
+static EXTERNAL_FORMATS: RwLock<Vec<ExternalFormat>> = RwLock::new(Vec::new());
+
+fn with_external_format<F: FnOnce(&mut ExternalFormat)>(name: &'static str, f: F) {
+    let mut formats = EXTERNAL_FORMATS
+        .write()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+
+    if let Some(format) = formats.iter_mut().find(|x| x.name == name) {
+        f(format);
+    } else {
+        let mut format = ExternalFormat {
+            name,
+            magic_bytes: vec![],
+            extensions: &[],
+            decoder: None,
+            encoder: None
+        };
+        f(&mut format);
+        formats.push(format);
+    }
+}
+
+fn find_external_format<R, F: Fn(&ExternalFormat) -> Option<R>>(f: F) -> Option<R> {
+    EXTERNAL_FORMATS
+        .read()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+        .iter()
+        .find_map(f)
+}
+
+/// Register a decoder for images starting with `magic_bytes`
+///
+/// Formats are identified by `name`, registering a decoder and an encoder
+/// with the same name makes them one format. A format may have multiple magic bytes,
+/// each call adds one and replaces the decoder constructor.
+///
+/// Built-in formats are probed first, so an external decoder cannot override a
+/// built-in one.
+///
+/// # Arguments
+/// - name: The name of the format
+/// - magic_bytes: Bytes every image of this format starts with
+/// - constructor: Function creating the decoder
+///
+/// # Returns
+/// The format identifying the registered codec
+///
+/// # Example
+/// ```
+/// use zune_core::colorspace::ColorSpace;
+/// use zune_core::options::DecoderOptions;
+/// use zune_image::errors::ImageErrors;
+/// use zune_image::image::Image;
+/// use zune_image::registry::register_decoder;
+/// use zune_image::traits::DecoderTrait;
+///
+/// // a format storing a single gray pixel after the magic bytes
+/// struct PixelDecoder(Vec<u8>);
+///
+/// impl DecoderTrait<Vec<u8>> for PixelDecoder {
+///     fn decode(&mut self) -> Result<Image, ImageErrors> {
+///         Ok(Image::fill::<u8>(self.0[5], ColorSpace::Luma, 1, 1))
+///     }
+///     fn dimensions(&self) -> Option<(usize, usize)> {
+///         Some((1, 1))
+///     }
+///     fn out_colorspace(&self) -> ColorSpace {
+///         ColorSpace::Luma
+///     }
+///     fn name(&self) -> &'static str {
+///         "pixel"
+///     }
+/// }
+///
+/// register_decoder("pixel", b"PIXEL", |data, _| Box::new(PixelDecoder(data)));
+///
+/// let image = Image::read(b"PIXEL\x2A", DecoderOptions::default())?;
+/// assert_eq!(image.dimensions(), (1, 1));
+/// # Ok::<(),ImageErrors>(())
+/// ```
+pub fn register_decoder(
+    name: &'static str, magic_bytes: &'static [u8], constructor: DecoderConstructor
+) -> ImageFormat {
+    with_external_format(name, |format| {
+        if !format.magic_bytes.contains(&magic_bytes) {
+            format.magic_bytes.push(magic_bytes);
+        }
+        format.decoder = Some(constructor);
+    });
+    ImageFormat::External(name)
+}
+
+/// Register an encoder for files ending with one of `extensions`
+///
+/// Formats are identified by `name`, see [`register_decoder`].
+///
+/// Built-in formats take precedence when an extension is shared.
+///
+/// # Arguments
+/// - name: The name of the format
+/// - extensions: File extensions of the format, without the leading dot
+/// - constructor: Function creating the encoder
+///
+/// # Returns
+/// The format identifying the registered codec, the encoder's [`format`](EncoderTrait::format)
+/// method should return it
+pub fn register_encoder(
+    name: &'static str, extensions: &'static [&'static str], constructor: EncoderConstructor
+) -> ImageFormat {
+    with_external_format(name, |format| {
+        format.extensions = extensions;
+        format.encoder = Some(constructor);
+    });
+    ImageFormat::External(name)
+}
+
+/// Bridges a decoder for an external format to the reader
+/// type the caller used
+struct ExternalDecoder<T> {
+    inner:   Box<dyn DecoderTrait<Vec<u8>>>,
+    _marker: PhantomData<T>
+}
+
+impl<T: ZReaderTrait> DecoderTrait<T> for ExternalDecoder<T> {
+    fn decode(&mut self) -> Result<Image, ImageErrors> {
+        self.inner.decode()
+    }
+
+    fn dimensions(&self) -> Option<(usize, usize)> {
+        self.inner.dimensions()
+    }
+
+    fn out_colorspace(&self) -> ColorSpace {
+        self.inner.out_colorspace()
+    }
+
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    fn is_experimental(&self) -> bool {
+        self.inner.is_experimental()
+    }
+
+    fn read_headers(&mut self) -> Result<Option<ImageMetadata>, ImageErrors> {
+        self.inner.read_headers()
+    }
+}
+
+/// Create a decoder for an external format
+///
+/// External decoders take ownership of their input, so this copies `data`
+pub(crate) fn external_decoder<'a, T: ZReaderTrait + 'a>(
+    name: &'static str, data: &T, options: DecoderOptions
+) -> Option<Box<dyn DecoderTrait<T> + 'a>> {
+    let constructor = find_external_format(|x| if x.name == name { x.decoder } else { None })?;
+    let bytes = data.get_slice(0..data.get_len()).unwrap_or(&[]).to_vec();
+
+    Some(Box::new(ExternalDecoder {
+        inner:   constructor(bytes, options),
+        _marker: PhantomData
+    }))
+}
+
+/// Create an encoder for an external format
+pub(crate) fn external_encoder(
+    name: &'static str, options: EncoderOptions
+) -> Option<Box<dyn EncoderTrait>> {
+    let constructor = find_external_format(|x| if x.name == name { x.encoder } else { None })?;
+
+    Some(constructor(options))
+}
+
+pub(crate) fn external_has_decoder(name: &'static str) -> bool {
+    find_external_format(|x| (x.name == name).then_some(x.decoder.is_some())).unwrap_or(false)
+}
+
+pub(crate) fn external_extensions(name: &'static str) -> &'static [&'static str] {
+    find_external_format(|x| (x.name == name).then_some(x.extensions)).unwrap_or(&[])
+}
+
+/// Find the external format whose magic bytes `header` starts with
+pub(crate) fn external_format_for_magic(header: &[u8]) -> Option<ImageFormat> {
+    find_external_format(|x| {
+        let matches = x.decoder.is_some() && x.magic_bytes.iter().any(|m| header.starts_with(m));
+        matches.then_some(ImageFormat::External(x.name))
+    })
+}
+
+/// Find the external format with an encoder for `extension`
+pub(crate) fn external_format_for_extension(extension: &str) -> Option<ImageFormat> {
+    find_external_format(|x| {
+        let matches = x.encoder.is_some() && x.extensions.contains(&extension);
+        matches.then_some(ImageFormat::External(x.name))
+    })
+}
+
+/// All formats the library knows of, regardless of whether
+/// their features are enabled or not
+pub const KNOWN_FORMATS: [ImageFormat; 9] = [
+    ImageFormat::JPEG,
+    ImageFormat::PNG,
+    ImageFormat::PPM,
+    ImageFormat::PSD,
+    ImageFormat::Farbfeld,
+    ImageFormat::QOI,
+    ImageFormat::JPEG_XL,
+    ImageFormat::HDR,
+    ImageFormat::BMP
+];
+
+/// Information about a codec available in this build
+#[derive(Clone, Debug)]
+pub struct CodecInfo {
+    format:             ImageFormat,
+    name:               &'static str,
+    description:        &'static str,
+    extensions:         &'static [&'static str],
+    has_decoder:        bool,
+    has_encoder:        bool,
+    encode_colorspaces: &'static [ColorSpace],
+    encode_depths:      &'static [BitDepth]
+}
+
+impl CodecInfo {
+    fn from_format(format: ImageFormat) -> CodecInfo {
+        let (name, description) = match format {
+            ImageFormat::JPEG => ("jpeg", "Joint Photographic Experts Group"),
+            ImageFormat::PNG => ("png", "Portable Network Graphics"),
+            ImageFormat::PPM => ("ppm", "Portable Pixel Map image"),
+            ImageFormat::PSD => ("psd", "Photoshop document"),
+            ImageFormat::Farbfeld => ("farbfeld", "Farbfeld"),
+            ImageFormat::QOI => ("qoi", "Quite Okay Image"),
+            ImageFormat::JPEG_XL => ("jpeg-xl", "JPEG XL"),
+            ImageFormat::HDR => ("hdr", "Radiance HDR"),
+            ImageFormat::BMP => ("bmp", "Windows Bitmap"),
+            ImageFormat::External(name) => (name, "Externally registered format"),
+            ImageFormat::Unknown => ("unknown", "Unknown format")
+        };
+
+        CodecInfo {
+            format,
+            name,
+            description,
+            extensions: format.file_extensions(),
+            has_decoder: format.has_decoder(),
+            has_encoder: format.has_encoder(),
+            encode_colorspaces: format.supported_colorspaces_for_encode(),
+            encode_depths: format.supported_bit_depths_for_encode()
+        }
+    }
+    /// The image format this codec handles
+    pub const fn format(&self) -> ImageFormat {
+        self.format
+    }
+    /// Short name of the codec, matching the cargo feature that enables it
+    pub const fn name(&self) -> &'static str {
+        self.name
+    }
+    /// Human readable description of the format
+    pub const fn description(&self) -> &'static str {
+        self.description
+    }
+    /// File extensions used by this format, without the leading dot
+    pub const fn extensions(&self) -> &'static [&'static str] {
+        self.extensions
+    }
+    /// Whether images of this format can be decoded
+    pub const fn has_decoder(&self) -> bool {
+        self.has_decoder
+    }
+    /// Whether images can be encoded to this format
+    pub const fn has_encoder(&self) -> bool {
+        self.has_encoder
+    }
+    /// Colorspaces the encoder can write, empty if there is no encoder
+    pub const fn encode_colorspaces(&self) -> &'static [ColorSpace] {
+        self.encode_colorspaces
+    }
+    /// Bit depths the encoder can write, empty if there is no encoder
+    pub const fn encode_depths(&self) -> &'static [BitDepth] {
+        self.encode_depths
+    }
+}
+
+/// Return information about every codec that has either a decoder or
+/// an encoder in this build, followed by externally registered codecs
+pub fn available_codecs() -> Vec<CodecInfo> {
+    let external: Vec<ImageFormat> = EXTERNAL_FORMATS
+        .read()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+        .iter()
+        .map(|x| ImageFormat::External(x.name))
+        .collect();
+
+    KNOWN_FORMATS
+        .iter()
+        .chain(external.iter())
+        .map(|format| CodecInfo::from_format(*format))
+        .filter(|codec| codec.has_decoder() || codec.has_encoder())
+        .collect()
+}
+
+/// Return information about the codec handling `format`, or `None` if
+/// the library was compiled without a decoder and an encoder for it
+pub fn codec_info(format: ImageFormat) -> Option<CodecInfo> {
+    let info = CodecInfo::from_format(format);
+
+    if info.has_decoder() || info.has_encoder() {
+        Some(info)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use zune_core::bit_depth::BitDepth;
+    use zune_core::colorspace::ColorSpace;
+    use zune_core::options::DecoderOptions;
+
+    use crate::codecs::ImageFormat;
+    use crate::errors::ImageErrors;
+    use crate::image::Image;
+    use crate::registry::{available_codecs, codec_info, register_decoder, register_encoder};
+    use crate::traits::{DecoderTrait, EncoderTrait};
+
+    #[test]
+    #[cfg(feature = "png")]
+    fn test_png_capabilities() {
+        let info = codec_info(ImageFormat::PNG).unwrap();
+
+        assert!(info.has_decoder());
+        assert!(info.has_encoder());
+        assert!(info.extensions().contains(&"png"));
+        assert!(info.encode_colorspaces().contains(&ColorSpace::RGBA));
+    }
+
+    #[test]
+    #[cfg(feature = "psd")]
+    fn test_decoder_only_format() {
+        let info = codec_info(ImageFormat::PSD).unwrap();
+
+        assert!(info.has_decoder());
+        assert!(!info.has_encoder());
+        assert!(info.encode_colorspaces().is_empty());
+    }
+
+    /// Stores luma pixels after a magic string, width and height are a byte each
+    struct TinyCodec(Vec<u8>);
+
+    impl DecoderTrait<Vec<u8>> for TinyCodec {
+        fn decode(&mut self) -> Result<Image, ImageErrors> {
+            let (width, height) = self.dimensions().unwrap();
+            let pixels = self.0[6..].to_vec();
+
+            Ok(Image::from_u8(&pixels, width, height, ColorSpace::Luma))
+        }
+
+        fn dimensions(&self) -> Option<(usize, usize)> {
+            Some((usize::from(self.0[4]), usize::from(self.0[5])))
+        }
+
+        fn out_colorspace(&self) -> ColorSpace {
+            ColorSpace::Luma
+        }
+
+        fn name(&self) -> &'static str {
+            "tiny"
+        }
+    }
+
+    impl EncoderTrait for TinyCodec {
+        fn name(&self) -> &'static str {
+            "tiny"
+        }
+
+        fn encode_inner(&mut self, image: &Image) -> Result<Vec<u8>, ImageErrors> {
+            let (width, height) = image.dimensions();
+            let mut out = b"TINY".to_vec();
+
+            out.extend_from_slice(&[width as u8, height as u8]);
+            out.extend_from_slice(&image.flatten_to_u8()[0]);
+            Ok(out)
+        }
+
+        fn supported_colorspaces(&self) -> &'static [ColorSpace] {
+            &[ColorSpace::Luma]
+        }
+
+        fn format(&self) -> ImageFormat {
+            ImageFormat::External("tiny")
+        }
+
+        fn supported_bit_depth(&self) -> &'static [BitDepth] {
+            &[BitDepth::Eight]
+        }
+
+        fn default_depth(&self, _: BitDepth) -> BitDepth {
+            BitDepth::Eight
+        }
+    }
+
+    #[test]
+    fn test_external_codec_roundtrip() {
+        let format = register_decoder("tiny", b"TINY", |data, _| Box::new(TinyCodec(data)));
+        register_encoder("tiny", &["tiny"], |_| Box::new(TinyCodec(vec![])));
+
+        assert_eq!(format, ImageFormat::External("tiny"));
+        assert!(format.has_decoder());
+        assert!(format.has_encoder());
+        assert_eq!(format.file_extensions(), &["tiny"]);
+        assert!(available_codecs().iter().any(|x| x.format() == format));
+
+        let image = Image::fill::<u8>(42, ColorSpace::Luma, 3, 2);
+        let encoded = image.write_to_vec(format).unwrap();
+        let decoded = Image::read(encoded, DecoderOptions::default()).unwrap();
+
+        assert_eq!(decoded.metadata().get_image_format(), Some(format));
+        assert_eq!(decoded.dimensions(), (3, 2));
+        assert_eq!(decoded.flatten_to_u8()[0], vec![42; 6]);
+
+        let (extension_format, _) = ImageFormat::get_encoder_for_extension("tiny").unwrap();
+        assert_eq!(extension_format, format);
+    }
+
+    #[test]
+    fn test_unknown_is_not_listed() {
+        assert!(codec_info(ImageFormat::Unknown).is_none());
+        assert!(available_codecs()
+            .iter()
+            .all(|codec| codec.format() != ImageFormat::Unknown));
+    }
+}
@@ -14,23 +14,27 @@ use serde::ser::SerializeStruct;
 use serde::{Serialize, Serializer};
 
 use crate::codecs::ImageFormat;
-use crate::metadata::ImageMetadata;
+use crate::metadata::{ImageMetadata, PhysicalDimensions};
+use crate::statistics::ChannelStatistics;
 
 impl Serialize for ImageMetadata {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer
     {
-        const STRUCT_FIELDS: usize = 7;
+        const STRUCT_FIELDS: usize = 15;
         let mut state = serializer.serialize_struct("Metadata", STRUCT_FIELDS)?;
 
         state.serialize_field("width", &self.width)?;
         state.serialize_field("height", &self.height)?;
         state.serialize_field("colorspace", &self.colorspace)?;
         state.serialize_field("depth", &self.depth)?;
+        state.serialize_field("significant_bits", &self.significant_bits)?;
         state.serialize_field("format", &self.format)?;
         state.serialize_field("color_transfer_characteristics", &self.color_trc)?;
         state.serialize_field("gamma_value", &self.default_gamma)?;
+        state.serialize_field("truncated", &self.truncated)?;
+        state.serialize_field("physical_dimensions", &self.physical_dimensions)?;
 
         let mut fields = BTreeMap::new();
         if let Some(ex) = &self.exif {
@@ -58,6 +62,30 @@ impl Serialize for ImageMetadata {
         } else {
             state.serialize_field("exif", &fields)?;
         }
+        state.serialize_field(
+            "icc_profile_size",
+            &self.icc_chunk.as_ref().map(|x| x.len())
+        )?;
+
+        let text: BTreeMap<&String, &String> =
+            self.text_chunks.iter().map(|(k, v)| (k, v)).collect();
+
+        if text.is_empty() {
+            state.serialize_field::<Option<BTreeMap<&String, &String>>>("text", &None)?;
+        } else {
+            state.serialize_field("text", &text)?;
+        }
+        state.serialize_field("statistics", &self.statistics)?;
+
+        // stage name to seconds
+        let timings: Option<BTreeMap<&str, f64>> = self.timings.as_ref().map(|timings| {
+            timings
+                .stages()
+                .iter()
+                .map(|(stage, duration)| (*stage, duration.as_secs_f64()))
+                .collect()
+        });
+        state.serialize_field("decode_timings", &timings)?;
 
         state.end()
     }
@@ -69,6 +97,41 @@ impl Serialize for ImageFormat {
     where
         S: Serializer
     {
+        if let ImageFormat::External(name) = self {
+            return serializer.serialize_str(name);
+        }
         serializer.serialize_str(&format!("{:?}", self))
     }
 }
+
+impl Serialize for PhysicalDimensions {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer
+    {
+        let mut state = serializer.serialize_struct("PhysicalDimensions", 4)?;
+
+        state.serialize_field("x_density", &self.x_density)?;
+        state.serialize_field("y_density", &self.y_density)?;
+        state.serialize_field("unit", &format!("{:?}", self.unit))?;
+        state.serialize_field("dpi", &self.dpi())?;
+
+        state.end()
+    }
+}
+
+impl Serialize for ChannelStatistics {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer
+    {
+        let mut state = serializer.serialize_struct("ChannelStatistics", 4)?;
+
+        state.serialize_field("min", &self.min)?;
+        state.serialize_field("max", &self.max)?;
+        state.serialize_field("mean", &self.mean)?;
+        state.serialize_field("std_dev", &self.std_dev)?;
+
+        state.end()
+    }
+}
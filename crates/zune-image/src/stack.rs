@@ -0,0 +1,230 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! Image stacking
+//!
+//! Stacking merges multiple exposures of the same scene into one image, the classic
+//! use is astrophotography where averaging many noisy frames increases the signal to noise
+//! ratio, while a median stack also removes transient things like satellites and hot pixels.
+//!
+//! See [`Image::stack`] for the entry point.
+use bytemuck::Pod;
+use zune_core::bit_depth::BitType;
+
+use crate::channel::Channel;
+use crate::errors::ImageErrors;
+use crate::frame::Frame;
+use crate::image::Image;
+
+/// How pixels at the same position in stacked images are merged
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum StackMode {
+    /// The average of all pixels, reduces noise
+    Mean,
+    /// The middle pixel, reduces noise and removes outliers.
+    ///
+    /// For an even number of images, this is the average of the two middle pixels
+    Median,
+    /// The brightest pixel, creates star trails
+    Max,
+    /// The darkest pixel
+    Min
+}
+
+macro_rules! reduce_int {
+    ($name:ident, $int:ty, $acc:ty) => {
+        #[allow(clippy::cast_possible_truncation)]
+        fn $name(mode: StackMode, values: &mut [$int]) -> $int {
+            let len = values.len() as $acc;
+
+            match mode {
+                StackMode::Mean => {
+                    let sum: $acc = values.iter().map(|x| <$acc>::from(*x)).sum();
+                    // round to nearest
+                    ((sum + len / 2) / len) as $int
+                }
+                StackMode::Median => {
+                    let mid = values.len() / 2;
+                    values.sort_unstable();
+
+                    if values.len() % 2 == 1 {
+                        values[mid]
+                    } else {
+                        let sum = <$acc>::from(values[mid - 1]) + <$acc>::from(values[mid]);
+                        sum.div_ceil(2) as $int
+                    }
+                }
+                StackMode::Max => values.iter().copied().max().unwrap_or_default(),
+                StackMode::Min => values.iter().copied().min().unwrap_or_default()
+            }
+        }
+    };
+}
+
+// accumulators are wide enough for u32::MAX images
+reduce_int!(reduce_u8, u8, u64);
+reduce_int!(reduce_u16, u16, u64);
+
+#[allow(clippy::cast_possible_truncation)]
+fn reduce_f32(mode: StackMode, values: &mut [f32]) -> f32 {
+    match mode {
+        StackMode::Mean => {
+            let sum: f64 = values.iter().map(|x| f64::from(*x)).sum();
+            (sum / values.len() as f64) as f32
+        }
+        StackMode::Median => {
+            let mid = values.len() / 2;
+            values.sort_unstable_by(f32::total_cmp);
+
+            if values.len() % 2 == 1 {
+                values[mid]
+            } else {
+                (values[mid - 1] + values[mid]) / 2.0
+            }
+        }
+        StackMode::Max => values.iter().copied().fold(f32::MIN, f32::max),
+        StackMode::Min => values.iter().copied().fold(f32::MAX, f32::min)
+    }
+}
+
+/// Merge the same channel of every image, calling `reduce` with the
+/// samples at each pixel position
+fn stack_channel<T: Pod + Default>(
+    channels: &[&Channel], reduce: impl Fn(&mut [T]) -> T
+) -> Result<Channel, ImageErrors> {
+    let inputs = channels
+        .iter()
+        .map(|x| x.reinterpret_as::<T>())
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut out = Channel::new_with_length::<T>(channels[0].len());
+    let mut scratch = vec![T::default(); inputs.len()];
+
+    for (i, pixel) in out.reinterpret_as_mut::<T>()?.iter_mut().enumerate() {
+        for (sample, input) in scratch.iter_mut().zip(&inputs) {
+            *sample = input[i];
+        }
+        *pixel = reduce(&mut scratch);
+    }
+    Ok(out)
+}
+
+pub(crate) fn stack_images(images: &[Image], mode: StackMode) -> Result<Image, ImageErrors> {
+    let first = images
+        .first()
+        .ok_or(ImageErrors::GenericStr("No images to stack"))?;
+
+    for image in &images[1..] {
+        if image.dimensions() != first.dimensions() {
+            let (w, h) = image.dimensions();
+            let (fw, fh) = first.dimensions();
+
+            return Err(ImageErrors::GenericString(format!(
+                "Cannot stack a {w}x{h} image with a {fw}x{fh} image"
+            )));
+        }
+        if image.colorspace() != first.colorspace() || image.depth() != first.depth() {
+            return Err(ImageErrors::GenericString(format!(
+                "Cannot stack a {:?} {:?} image with a {:?} {:?} image, convert them first",
+                image.colorspace(),
+                image.depth(),
+                first.colorspace(),
+                first.depth()
+            )));
+        }
+        if image.frames_len() != first.frames_len() {
+            return Err(ImageErrors::GenericStr(
+                "Cannot stack images with a different number of frames"
+            ));
+        }
+    }
+    let colorspace = first.colorspace();
+    let depth = first.depth();
+
+    let mut frames = Vec::with_capacity(first.frames_len());
+
+    for frame in 0..first.frames_len() {
+        let mut channels = vec![];
+
+        for channel in 0..colorspace.num_components() {
+            let inputs: Vec<&Channel> = images
+                .iter()
+                .map(|x| &x.frames_ref()[frame].channels_ref(colorspace, false)[channel])
+                .collect();
+
+            let stacked = match depth.bit_type() {
+                BitType::U8 => stack_channel(&inputs, |x| reduce_u8(mode, x))?,
+                BitType::U16 => stack_channel(&inputs, |x| reduce_u16(mode, x))?,
+                BitType::F32 => stack_channel(&inputs, |x| reduce_f32(mode, x))?,
+                d => return Err(ImageErrors::ImageOperationNotImplemented("stack", d))
+            };
+            channels.push(stacked);
+        }
+        frames.push(Frame::new(channels));
+    }
+    let (width, height) = first.dimensions();
+
+    let mut image = Image::new_frames(frames, depth, width, height, colorspace);
+    image.metadata = first.metadata.clone();
+
+    Ok(image)
+}
+
+#[cfg(test)]
+mod tests {
+    use zune_core::colorspace::ColorSpace;
+
+    use crate::image::Image;
+    use crate::stack::StackMode;
+
+    fn first_pixel_u16(image: &Image) -> u16 {
+        image.frames_ref()[0].channels_ref(ColorSpace::Luma, false)[0]
+            .reinterpret_as::<u16>()
+            .unwrap()[0]
+    }
+
+    #[test]
+    fn test_stack_u16_modes() {
+        // sums overflow u16, and the even count exercises the median average
+        let images: Vec<Image> = [60000_u16, 50000, 40000, 10]
+            .iter()
+            .map(|x| Image::fill(*x, ColorSpace::Luma, 3, 2))
+            .collect();
+
+        for (mode, expected) in [
+            (StackMode::Mean, 37503),
+            (StackMode::Median, 45000),
+            (StackMode::Max, 60000),
+            (StackMode::Min, 10)
+        ] {
+            let stacked = Image::stack(&images, mode).unwrap();
+            assert_eq!(first_pixel_u16(&stacked), expected, "{mode:?}");
+        }
+    }
+
+    #[test]
+    fn test_stack_f32_mean() {
+        let images = [
+            Image::fill(0.25_f32, ColorSpace::RGB, 4, 4),
+            Image::fill(0.75_f32, ColorSpace::RGB, 4, 4)
+        ];
+        let stacked = Image::stack(&images, StackMode::Mean).unwrap();
+
+        assert!(stacked == Image::fill(0.5_f32, ColorSpace::RGB, 4, 4));
+    }
+
+    #[test]
+    fn test_stack_mismatch() {
+        let images = [
+            Image::fill(0_u8, ColorSpace::RGB, 4, 4),
+            Image::fill(0_u8, ColorSpace::RGB, 4, 5)
+        ];
+        assert!(Image::stack(&images, StackMode::Max).is_err());
+        assert!(Image::stack(&[], StackMode::Max).is_err());
+    }
+}
@@ -0,0 +1,206 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! Per channel image statistics
+//!
+//! [`channel_statistics`] computes the minimum, maximum, mean and standard
+//! deviation of every channel of an image, the [`Statistics`] operation does the same
+//! inside a pipeline and stores the results on the image metadata, where they can be read
+//! back with [`ImageMetadata::statistics`](crate::metadata::ImageMetadata::statistics)
+//!
+//! Values are in the range of the image depth, e.g `0..=255` for 8 bit images,
+//! and cover all frames of an animated image.
+//!
+//! # Example
+//! ```
+//! use zune_core::colorspace::ColorSpace;
+//! use zune_image::errors::ImageErrors;
+//! use zune_image::image::Image;
+//! use zune_image::statistics::Statistics;
+//! use zune_image::traits::OperationsTrait;
+//!
+//! let mut image = Image::from_u8(&[0, 10, 20, 30], 2, 2, ColorSpace::Luma);
+//! Statistics::new().execute(&mut image)?;
+//!
+//! let stats = image.metadata().statistics().unwrap();
+//! assert_eq!(stats[0].max, 30.0);
+//! assert_eq!(stats[0].mean, 15.0);
+//! # Ok::<(),ImageErrors>(())
+//! ```
+use zune_core::bit_depth::BitType;
+
+use crate::channel::Channel;
+use crate::errors::ImageErrors;
+use crate::image::Image;
+use crate::traits::OperationsTrait;
+
+/// Statistics of a single channel
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ChannelStatistics {
+    /// Smallest value in the channel
+    pub min:     f64,
+    /// Largest value in the channel
+    pub max:     f64,
+    /// Average of all values in the channel
+    pub mean:    f64,
+    /// Population standard deviation of the channel values
+    pub std_dev: f64
+}
+
+/// Compute statistics of every channel of an image
+///
+/// The result contains one entry per channel in the order of
+/// the image colorspace, alpha included
+///
+/// # Errors
+/// If the image depth is not supported or the image has no pixels
+pub fn channel_statistics(image: &Image) -> Result<Vec<ChannelStatistics>, ImageErrors> {
+    let colorspace = image.colorspace();
+    let bit_type = image.depth().bit_type();
+
+    let channels = |c: usize| {
+        image
+            .frames_ref()
+            .iter()
+            .map(move |frame| &frame.channels_ref(colorspace, false)[c])
+    };
+
+    (0..colorspace.num_components())
+        .map(|c| match bit_type {
+            BitType::U8 => statistics::<u8>(channels(c)),
+            BitType::U16 => statistics::<u16>(channels(c)),
+            BitType::F32 => statistics::<f32>(channels(c)),
+            d => Err(ImageErrors::ImageOperationNotImplemented("Statistics", d))
+        })
+        .collect()
+}
+
+/// Compute statistics of the same channel across frames
+///
+/// The mean is found first so that the deviation can be summed
+/// in a second pass, which keeps it exact for large images
+fn statistics<'a, T>(
+    channels: impl Iterator<Item = &'a Channel> + Clone
+) -> Result<ChannelStatistics, ImageErrors>
+where
+    T: Copy + Default + 'static + Into<f64>
+{
+    let mut min = f64::INFINITY;
+    let mut max = f64::NEG_INFINITY;
+    let mut sum = 0.0;
+    let mut count = 0_usize;
+
+    for channel in channels.clone() {
+        let values = channel.reinterpret_as::<T>()?;
+
+        for value in values.iter().map(|x| (*x).into()) {
+            min = min.min(value);
+            max = max.max(value);
+            sum += value;
+        }
+        count += values.len();
+    }
+    if count == 0 {
+        return Err(ImageErrors::GenericStr(
+            "Cannot compute statistics of an empty image"
+        ));
+    }
+    let mean = sum / count as f64;
+    let mut deviation = 0.0;
+
+    for channel in channels {
+        let values = channel.reinterpret_as::<T>()?;
+
+        for value in values.iter().map(|x| (*x).into()) {
+            deviation += (value - mean) * (value - mean);
+        }
+    }
+
+    Ok(ChannelStatistics {
+        min,
+        max,
+        mean,
+        std_dev: (deviation / count as f64).sqrt()
+    })
+}
+
+/// Compute per channel statistics and store them on the image
+///
+/// This does not modify pixels, the results are stored in the image
+/// metadata and can be read with [`ImageMetadata::statistics`](crate::metadata::ImageMetadata::statistics)
+/// after the operation runs.
+///
+/// Results describe the pixels at the time the operation ran, operations
+/// executed after it do not update them.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Statistics;
+
+impl Statistics {
+    /// Create a new statistics operation
+    #[must_use]
+    pub fn new() -> Statistics {
+        Statistics
+    }
+}
+
+impl OperationsTrait for Statistics {
+    fn name(&self) -> &'static str {
+        "Statistics"
+    }
+
+    fn execute_impl(&self, image: &mut Image) -> Result<(), ImageErrors> {
+        let statistics = channel_statistics(image)?;
+
+        image.metadata_mut().set_statistics(statistics);
+
+        Ok(())
+    }
+
+    fn supported_types(&self) -> &'static [BitType] {
+        &[BitType::U8, BitType::U16, BitType::F32]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use zune_core::colorspace::ColorSpace;
+
+    use crate::image::Image;
+    use crate::statistics::{channel_statistics, Statistics};
+    use crate::traits::OperationsTrait;
+
+    #[test]
+    fn test_statistics_rgb() {
+        let pixels = [0, 100, 255, 10, 100, 255, 20, 100, 255, 30, 100, 255];
+        let image = Image::from_u8(&pixels, 2, 2, ColorSpace::RGB);
+
+        let stats = channel_statistics(&image).unwrap();
+
+        assert_eq!(stats.len(), 3);
+        assert_eq!(
+            (stats[0].min, stats[0].max, stats[0].mean),
+            (0.0, 30.0, 15.0)
+        );
+        assert!((stats[0].std_dev - 125.0_f64.sqrt()).abs() < 1e-9);
+        // constant channels have no deviation
+        assert_eq!((stats[1].mean, stats[1].std_dev), (100.0, 0.0));
+        assert_eq!((stats[2].min, stats[2].max), (255.0, 255.0));
+    }
+
+    #[test]
+    fn test_statistics_stored_on_image() {
+        let mut image = Image::fill::<u16>(1000, ColorSpace::LumaA, 4, 4);
+        assert!(image.metadata().statistics().is_none());
+
+        Statistics::new().execute(&mut image).unwrap();
+
+        let stats = image.metadata().statistics().unwrap();
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats[1].mean, 1000.0);
+    }
+}
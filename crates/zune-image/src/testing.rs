@@ -0,0 +1,624 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! Utilities for testing image operations
+//!
+//! This requires the `testing` feature, enable it in `dev-dependencies`
+//! so the helpers stay out of release builds
+//!
+//! This contains three tools
+//!
+//! - [`OperationChecker`]: Runs an operation over a matrix of synthetic images in
+//!   every colorspace, bit depth and a few awkward dimensions, catching panics and
+//!   checking the output image is consistent with what the operation claims to produce.
+//! - [`compare_with_golden`]: Compares an image with a reference stored on disk,
+//!   used to catch unintended changes in an operation's output.
+//! - [`Image::eq_exact`] and [`Image::eq_within_tolerance`]: Compare two images sample
+//!   by sample, describing where and by how much they differ.
+//!
+//! # Example
+//! ```
+//! use zune_image::core_filters::depth::Depth;
+//! use zune_core::bit_depth::BitDepth;
+//! use zune_image::testing::OperationChecker;
+//!
+//! let failures = OperationChecker::new().run(&Depth::new(BitDepth::Sixteen));
+//! assert!(failures.is_empty(), "{failures:#?}");
+//! ```
+use std::fmt::{Display, Formatter};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::Path;
+
+use zune_core::bit_depth::{BitDepth, BitType};
+use zune_core::colorspace::ColorSpace;
+
+use crate::channel::Channel;
+use crate::image::Image;
+use crate::pipelines::{fused_image, interleaved_output};
+use crate::traits::OperationsTrait;
+
+/// Colorspaces test images are generated in
+pub const TEST_COLORSPACES: [ColorSpace; 12] = [
+    ColorSpace::Luma,
+    ColorSpace::LumaA,
+    ColorSpace::RGB,
+    ColorSpace::RGBA,
+    ColorSpace::BGR,
+    ColorSpace::BGRA,
+    ColorSpace::ARGB,
+    ColorSpace::YCbCr,
+    ColorSpace::YCCK,
+    ColorSpace::CMYK,
+    ColorSpace::HSL,
+    ColorSpace::HSV
+];
+
+/// Bit depths test images are generated in
+pub const TEST_DEPTHS: [BitDepth; 3] = [BitDepth::Eight, BitDepth::Sixteen, BitDepth::Float32];
+
+/// Dimensions of test images, (width, height)
+///
+/// These are deliberately odd, single pixel and non-square sizes which tend to
+/// trip SIMD tails and row/column mixups.
+pub const TEST_DIMENSIONS: [(usize, usize); 4] = [(1, 1), (3, 2), (17, 9), (31, 64)];
+
+/// Create a deterministic image for testing
+///
+/// Pixels follow a pattern that has both gradients and high frequency
+/// changes, and every channel differs from the others.
+///
+/// Float images are in the range `0.0..=1.0`
+pub fn synthetic_image(
+    colorspace: ColorSpace, depth: BitDepth, width: usize, height: usize
+) -> Image {
+    let components = colorspace.num_components();
+
+    let values: Vec<u8> = (0..height)
+        .flat_map(|y| (0..width).map(move |x| (x, y)))
+        .flat_map(|(x, y)| (0..components).map(move |c| (x, y, c)))
+        .map(|(x, y, c)| ((x * 31 + y * 17 + c * 71 + (x * y) % 13) % 256) as u8)
+        .collect();
+
+    match depth {
+        BitDepth::Sixteen => {
+            let pixels: Vec<u16> = values.iter().map(|x| u16::from(*x) * 257).collect();
+            Image::from_u16(&pixels, width, height, colorspace)
+        }
+        BitDepth::Float32 => {
+            let pixels: Vec<f32> = values.iter().map(|x| f32::from(*x) / 255.0).collect();
+            Image::from_f32(&pixels, width, height, colorspace)
+        }
+        _ => Image::from_u8(&values, width, height, colorspace)
+    }
+}
+
+/// A problem found by [`OperationChecker`]
+#[derive(Clone, Debug)]
+pub struct OperationFailure {
+    /// Name of the operation
+    pub operation:  &'static str,
+    /// Colorspace of the input image
+    pub colorspace: ColorSpace,
+    /// Bit depth of the input image
+    pub depth:      BitDepth,
+    /// Dimensions of the input image
+    pub dimensions: (usize, usize),
+    /// What went wrong
+    pub reason:     String
+}
+
+impl Display for OperationFailure {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} on {:?} {:?} {}x{}: {}",
+            self.operation,
+            self.colorspace,
+            self.depth,
+            self.dimensions.0,
+            self.dimensions.1,
+            self.reason
+        )
+    }
+}
+
+/// Run an operation over every combination of colorspace, depth and dimensions
+///
+/// For each combination, the checker
+///
+/// - Catches panics, an operation should return an error instead of panicking.
+/// - Confirms the output colorspace and depth match [`OperationsTrait::output_colorspace`]
+///   and [`OperationsTrait::output_depth`]
+/// - Confirms the output image has non-zero dimensions
+/// - Confirms operations with an interleaved kernel give the same result through it,
+///   see [`OperationsTrait::interleaved_colorspace`]
+/// - Optionally confirms the operation is idempotent, i.e running it twice gives the same
+///   result as running it once.
+///
+/// Errors are not failures, since an operation may not support an input, but an
+/// operation returning an error for a colorspace and depth it claims to support is.
+#[derive(Clone, Debug)]
+pub struct OperationChecker {
+    colorspaces:       Vec<ColorSpace>,
+    depths:            Vec<BitDepth>,
+    dimensions:        Vec<(usize, usize)>,
+    check_idempotence: bool
+}
+
+impl Default for OperationChecker {
+    fn default() -> Self {
+        OperationChecker {
+            colorspaces:       TEST_COLORSPACES.to_vec(),
+            depths:            TEST_DEPTHS.to_vec(),
+            dimensions:        TEST_DIMENSIONS.to_vec(),
+            check_idempotence: false
+        }
+    }
+}
+
+impl OperationChecker {
+    /// Create a checker using all test colorspaces, depths and dimensions
+    pub fn new() -> OperationChecker {
+        OperationChecker::default()
+    }
+    /// Only test images in the given colorspaces
+    #[must_use]
+    pub fn set_colorspaces(mut self, colorspaces: &[ColorSpace]) -> OperationChecker {
+        self.colorspaces = colorspaces.to_vec();
+        self
+    }
+    /// Only test images with the given depths
+    #[must_use]
+    pub fn set_depths(mut self, depths: &[BitDepth]) -> OperationChecker {
+        self.depths = depths.to_vec();
+        self
+    }
+    /// Only test images with the given dimensions
+    #[must_use]
+    pub fn set_dimensions(mut self, dimensions: &[(usize, usize)]) -> OperationChecker {
+        self.dimensions = dimensions.to_vec();
+        self
+    }
+    /// Check that running the operation twice gives the same result as running it once
+    #[must_use]
+    pub fn set_check_idempotence(mut self, yes: bool) -> OperationChecker {
+        self.check_idempotence = yes;
+        self
+    }
+
+    /// Run `operation` over all configured combinations, returning every failure found
+    pub fn run(&self, operation: &dyn OperationsTrait) -> Vec<OperationFailure> {
+        let mut failures = vec![];
+
+        for colorspace in &self.colorspaces {
+            for depth in &self.depths {
+                for dimensions in &self.dimensions {
+                    let image = synthetic_image(*colorspace, *depth, dimensions.0, dimensions.1);
+
+                    if let Err(reason) = self.check_one(operation, image) {
+                        failures.push(OperationFailure {
+                            operation: operation.name(),
+                            colorspace: *colorspace,
+                            depth: *depth,
+                            dimensions: *dimensions,
+                            reason
+                        });
+                    }
+                }
+            }
+        }
+        failures
+    }
+
+    fn check_one(&self, operation: &dyn OperationsTrait, mut image: Image) -> Result<(), String> {
+        let (colorspace, depth) = (image.colorspace(), image.depth());
+
+        let supported = operation.supported_colorspaces().contains(&colorspace)
+            && operation.supported_types().contains(&depth.bit_type());
+
+        let input = image.clone();
+
+        match execute_catching_panics(operation, &mut image)? {
+            Ok(()) => (),
+            Err(err) if supported => return Err(format!("Returned an error: {err:?}")),
+            Err(_) => return Ok(())
+        }
+        if image.colorspace() != operation.output_colorspace(colorspace) {
+            return Err(format!(
+                "Expected colorspace {:?} but image is in {:?}",
+                operation.output_colorspace(colorspace),
+                image.colorspace()
+            ));
+        }
+        if image.depth() != operation.output_depth(depth) {
+            return Err(format!(
+                "Expected depth {:?} but image is in {:?}",
+                operation.output_depth(depth),
+                image.depth()
+            ));
+        }
+        let (width, height) = image.dimensions();
+
+        if width == 0 || height == 0 {
+            return Err(format!("Output has zero dimensions {width}x{height}"));
+        }
+
+        if depth == BitDepth::Eight && interleaved_output(colorspace, &[operation]).is_some() {
+            let pixels = input.flatten_to_u8().swap_remove(0);
+            let (width, height) = input.dimensions();
+
+            let (interleaved, _) = catch_unwind(AssertUnwindSafe(|| {
+                fused_image(pixels, width, height, colorspace, &[operation])
+            }))
+            .map_err(|_| "Interleaved kernel panicked".to_string())?
+            .map_err(|err| format!("Interleaved kernel returned an error: {err:?}"))?;
+
+            interleaved
+                .eq_exact(&image)
+                .map_err(|err| format!("Interleaved kernel differs, {err}"))?;
+        }
+        if self.check_idempotence {
+            let mut again = image.clone();
+
+            if let Err(err) = execute_catching_panics(operation, &mut again)? {
+                return Err(format!("Second run returned an error: {err:?}"));
+            }
+            if again != image {
+                return Err("Operation is not idempotent".to_string());
+            }
+        }
+        Ok(())
+    }
+}
+
+fn execute_catching_panics(
+    operation: &dyn OperationsTrait, image: &mut Image
+) -> Result<Result<(), crate::errors::ImageErrors>, String> {
+    catch_unwind(AssertUnwindSafe(|| operation.execute(image))).map_err(|err| {
+        let message = err
+            .downcast_ref::<String>()
+            .cloned()
+            .or_else(|| err.downcast_ref::<&str>().map(|x| x.to_string()))
+            .unwrap_or_default();
+
+        format!("Panicked: {message}")
+    })
+}
+
+/// Environment variable which when set makes [`compare_with_golden`]
+/// (re)write reference files instead of comparing against them
+pub const BLESS_ENV_VAR: &str = "ZUNE_BLESS_GOLDEN";
+
+fn golden_bytes(image: &Image) -> Vec<u8> {
+    let (width, height) = image.dimensions();
+
+    let mut out = format!(
+        "zune-golden {width} {height} {:?} {:?}\n",
+        image.colorspace(),
+        image.depth()
+    )
+    .into_bytes();
+
+    for channel in image.channels_ref(false) {
+        // safety: any bit pattern is a valid u8
+        out.extend_from_slice(unsafe { channel.alias() });
+    }
+    out
+}
+
+/// Compare an image against a golden file
+///
+/// The file stores the image header followed by raw, native endian channel data of
+/// every frame, comparisons are exact, so goldens of float images should be avoided
+/// since results may differ slightly between platforms.
+///
+/// When the [`BLESS_ENV_VAR`] environment variable is set, the file is written instead.
+///
+/// # Errors
+/// If the file doesn't exist, can't be read or written or differs from the image,
+/// the error describes the difference
+pub fn compare_with_golden<P: AsRef<Path>>(image: &Image, path: P) -> Result<(), String> {
+    let path = path.as_ref();
+    let actual = golden_bytes(image);
+
+    if std::env::var_os(BLESS_ENV_VAR).is_some() {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|x| x.to_string())?;
+        }
+        return std::fs::write(path, actual).map_err(|x| x.to_string());
+    }
+
+    let expected = std::fs::read(path).map_err(|x| {
+        format!("Could not read golden file {path:?}: {x}, run with {BLESS_ENV_VAR}=1 to create it")
+    })?;
+
+    if expected == actual {
+        return Ok(());
+    }
+
+    let header = |x: &[u8]| {
+        let end = x.iter().position(|x| *x == b'\n').unwrap_or(x.len());
+        String::from_utf8_lossy(&x[..end]).to_string()
+    };
+    let (expected_header, actual_header) = (header(&expected), header(&actual));
+
+    if expected_header != actual_header {
+        return Err(format!(
+            "Image differs from {path:?}, expected `{expected_header}` but found `{actual_header}`"
+        ));
+    }
+    let differing = expected.iter().zip(&actual).filter(|(a, b)| a != b).count();
+
+    Err(format!(
+        "Image differs from {path:?}, {differing} of {} bytes differ",
+        actual.len()
+    ))
+}
+
+/// How two images differ, returned by [`Image::eq_exact`] and
+/// [`Image::eq_within_tolerance`]
+///
+/// Values are ordered as `(self, other)`
+#[derive(Clone, Debug, PartialEq)]
+pub enum ImageMismatch {
+    /// The images have different dimensions
+    Dimensions((usize, usize), (usize, usize)),
+    /// The images are in different colorspaces
+    Colorspace(ColorSpace, ColorSpace),
+    /// The images have different bit depths
+    Depth(BitDepth, BitDepth),
+    /// The images have a different number of frames
+    Frames(usize, usize),
+    /// Some samples differ by more than the tolerance
+    Pixels(PixelMismatch)
+}
+
+/// Samples of two images of the same layout that differ by more than the tolerance
+#[derive(Clone, Debug, PartialEq)]
+pub struct PixelMismatch {
+    /// Frame of the first differing sample
+    pub frame:             usize,
+    /// Position of the first differing pixel, as `(x, y)`
+    pub position:          (usize, usize),
+    /// Channel of the first differing sample, in colorspace order
+    pub channel:           usize,
+    /// Values of the first differing sample in both images
+    pub values:            (f32, f32),
+    /// Number of samples that differ by more than the tolerance
+    pub differing_samples: usize,
+    /// Number of pixels with at least one differing sample
+    pub differing_pixels:  usize,
+    /// Largest difference between two samples, infinite if a sample is `NaN`
+    pub max_difference:    f32,
+    /// Number of samples compared
+    pub total_samples:     usize
+}
+
+impl Display for ImageMismatch {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ImageMismatch::Dimensions(a, b) => {
+                write!(f, "Dimensions differ, {}x{} vs {}x{}", a.0, a.1, b.0, b.1)
+            }
+            ImageMismatch::Colorspace(a, b) => write!(f, "Colorspaces differ, {a:?} vs {b:?}"),
+            ImageMismatch::Depth(a, b) => write!(f, "Depths differ, {a:?} vs {b:?}"),
+            ImageMismatch::Frames(a, b) => write!(f, "Frame counts differ, {a} vs {b}"),
+            ImageMismatch::Pixels(pixels) => write!(
+                f,
+                "{} of {} samples in {} pixels differ, the first at frame {} ({}, {}) channel {}: {} vs {}, largest difference {}",
+                pixels.differing_samples,
+                pixels.total_samples,
+                pixels.differing_pixels,
+                pixels.frame,
+                pixels.position.0,
+                pixels.position.1,
+                pixels.channel,
+                pixels.values.0,
+                pixels.values.1,
+                pixels.max_difference
+            )
+        }
+    }
+}
+
+impl std::error::Error for ImageMismatch {}
+
+impl Image {
+    /// Check that two images are identical, sample for sample
+    ///
+    /// Unlike `==`, this also compares dimensions, colorspace and depth, and
+    /// describes the difference, e.g for `assert!` messages in tests
+    ///
+    /// # Example
+    /// ```
+    /// use zune_core::colorspace::ColorSpace;
+    /// use zune_image::image::Image;
+    /// use zune_image::testing::ImageMismatch;
+    ///
+    /// let a = Image::from_u8(&[1, 2, 3], 3, 1, ColorSpace::Luma);
+    /// let b = Image::from_u8(&[1, 2, 4], 3, 1, ColorSpace::Luma);
+    ///
+    /// let Err(ImageMismatch::Pixels(pixels)) = a.eq_exact(&b) else {
+    ///     panic!("images should differ");
+    /// };
+    /// assert_eq!(pixels.position, (2, 0));
+    /// ```
+    ///
+    /// # Errors
+    /// How the images differ
+    pub fn eq_exact(&self, other: &Image) -> Result<(), ImageMismatch> {
+        self.eq_within_tolerance(other, 0.0)
+    }
+
+    /// Check that two images have the same layout and no samples differ by more
+    /// than `max_per_channel_diff`
+    ///
+    /// The tolerance is in units of the image depth, e.g `1.0` allows 8 and 16 bit
+    /// samples to be off by one and float samples to be off by `1.0`, so float images
+    /// usually need a small fraction. `NaN` samples only equal other `NaN` samples.
+    ///
+    /// # Errors
+    /// How the images differ, pixel mismatches cover every frame
+    pub fn eq_within_tolerance(
+        &self, other: &Image, max_per_channel_diff: f32
+    ) -> Result<(), ImageMismatch> {
+        if self.dimensions() != other.dimensions() {
+            return Err(ImageMismatch::Dimensions(
+                self.dimensions(),
+                other.dimensions()
+            ));
+        }
+        if self.colorspace() != other.colorspace() {
+            return Err(ImageMismatch::Colorspace(
+                self.colorspace(),
+                other.colorspace()
+            ));
+        }
+        if self.depth() != other.depth() {
+            return Err(ImageMismatch::Depth(self.depth(), other.depth()));
+        }
+        if self.frames_len() != other.frames_len() {
+            return Err(ImageMismatch::Frames(self.frames_len(), other.frames_len()));
+        }
+        match self.depth().bit_type() {
+            BitType::U8 => compare_samples::<u8>(self, other, max_per_channel_diff),
+            BitType::U16 => compare_samples::<u16>(self, other, max_per_channel_diff),
+            BitType::F32 => compare_samples::<f32>(self, other, max_per_channel_diff),
+            // unknown depths have no samples to compare
+            _ => Ok(())
+        }
+    }
+}
+
+/// Compare samples of two images with the same layout, stored as `T`
+fn compare_samples<T: Copy + Default + Into<f32> + 'static>(
+    a: &Image, b: &Image, tolerance: f32
+) -> Result<(), ImageMismatch> {
+    let colorspace = a.colorspace();
+    let width = a.dimensions().0.max(1);
+
+    let mut mismatch: Option<PixelMismatch> = None;
+    let mut total_samples = 0;
+
+    for (frame, (frame_a, frame_b)) in a.frames_ref().iter().zip(b.frames_ref()).enumerate() {
+        let channels_a = samples_of::<T>(frame_a.channels_ref(colorspace, false));
+        let channels_b = samples_of::<T>(frame_b.channels_ref(colorspace, false));
+
+        let pixels = channels_a.first().map_or(0, |x| x.len());
+        total_samples += pixels * channels_a.len();
+
+        for pixel in 0..pixels {
+            let mut pixel_differs = false;
+
+            for (channel, (x, y)) in channels_a.iter().zip(&channels_b).enumerate() {
+                let (x, y): (f32, f32) = (x[pixel].into(), y[pixel].into());
+
+                let difference = if x.is_nan() && y.is_nan() {
+                    0.0
+                } else if x.is_nan() || y.is_nan() {
+                    f32::INFINITY
+                } else {
+                    (x - y).abs()
+                };
+                if difference <= tolerance {
+                    continue;
+                }
+                let entry = mismatch.get_or_insert(PixelMismatch {
+                    frame,
+                    position: (pixel % width, pixel / width),
+                    channel,
+                    values: (x, y),
+                    differing_samples: 0,
+                    differing_pixels: 0,
+                    max_difference: 0.0,
+                    total_samples: 0
+                });
+                entry.differing_samples += 1;
+                entry.differing_pixels += usize::from(!pixel_differs);
+                entry.max_difference = entry.max_difference.max(difference);
+
+                pixel_differs = true;
+            }
+        }
+    }
+    match mismatch {
+        Some(mismatch) => Err(ImageMismatch::Pixels(PixelMismatch {
+            total_samples,
+            ..mismatch
+        })),
+        None => Ok(())
+    }
+}
+
+/// Samples of each channel, channels hold the type of the image depth
+fn samples_of<T: Default + 'static>(channels: &[Channel]) -> Vec<&[T]> {
+    channels
+        .iter()
+        .map(|x| x.reinterpret_as::<T>().unwrap())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use zune_core::colorspace::ColorSpace;
+
+    use crate::image::Image;
+    use crate::testing::ImageMismatch;
+
+    #[test]
+    fn test_eq_exact_reports_first_difference() {
+        let a = Image::from_u16(&[0, 0, 0, 10, 20, 30], 2, 1, ColorSpace::RGB);
+        let b = Image::from_u16(&[0, 0, 0, 10, 25, 40], 2, 1, ColorSpace::RGB);
+
+        assert_eq!(a.eq_exact(&a.clone()), Ok(()));
+
+        let Err(ImageMismatch::Pixels(pixels)) = a.eq_exact(&b) else {
+            panic!("Expected a pixel mismatch");
+        };
+        assert_eq!(pixels.position, (1, 0));
+        assert_eq!(pixels.channel, 1);
+        assert_eq!(pixels.values, (20.0, 25.0));
+        assert_eq!(pixels.differing_samples, 2);
+        assert_eq!(pixels.differing_pixels, 1);
+        assert_eq!(pixels.max_difference, 10.0);
+        assert_eq!(pixels.total_samples, 6);
+    }
+
+    #[test]
+    fn test_eq_within_tolerance() {
+        let a = Image::from_f32(&[0.5, f32::NAN, 1.0], 3, 1, ColorSpace::Luma);
+        let b = Image::from_f32(&[0.51, f32::NAN, 1.0], 3, 1, ColorSpace::Luma);
+
+        assert!(a.eq_exact(&b).is_err());
+        assert_eq!(a.eq_within_tolerance(&b, 0.02), Ok(()));
+
+        let c = Image::from_f32(&[0.5, 0.0, 1.0], 3, 1, ColorSpace::Luma);
+        assert!(a.eq_within_tolerance(&c, 1.0).is_err());
+    }
+
+    #[test]
+    fn test_eq_layout_mismatch() {
+        let luma = Image::fill(0_u8, ColorSpace::Luma, 2, 2);
+
+        assert_eq!(
+            luma.eq_exact(&Image::fill(0_u8, ColorSpace::Luma, 2, 3)),
+            Err(ImageMismatch::Dimensions((2, 2), (2, 3)))
+        );
+        assert_eq!(
+            luma.eq_exact(&Image::fill(0_u16, ColorSpace::Luma, 2, 2)),
+            Err(ImageMismatch::Depth(
+                zune_core::bit_depth::BitDepth::Eight,
+                zune_core::bit_depth::BitDepth::Sixteen
+            ))
+        );
+        assert_eq!(
+            luma.eq_exact(&Image::fill(0_u8, ColorSpace::RGB, 2, 2)),
+            Err(ImageMismatch::Colorspace(ColorSpace::Luma, ColorSpace::RGB))
+        );
+    }
+}
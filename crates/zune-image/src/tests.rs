@@ -46,3 +46,232 @@ fn test_fractal() {
         .unwrap();
     image.save_to("a.ppm", ImageFormat::PPM).unwrap()
 }
+
+#[test]
+#[cfg(feature = "ppm")]
+fn test_max_memory() {
+    use zune_core::options::DecoderOptions;
+
+    use crate::image::Image;
+
+    // a 2x2 grayscale image needs four bytes
+    let ppm: &[u8] = b"P5 2 2 255 \x01\x02\x03\x04";
+    let options = DecoderOptions::default().set_max_memory(3);
+
+    assert!(Image::read(ppm, options).is_err());
+    assert!(Image::read(ppm, options.set_max_memory(4)).is_ok());
+}
+
+#[test]
+#[cfg(feature = "png")]
+fn test_decode_timings() {
+    use zune_core::colorspace::ColorSpace;
+    use zune_core::options::DecoderOptions;
+
+    use crate::codecs::png::PngEncoder;
+    use crate::image::Image;
+    use crate::traits::EncoderTrait;
+
+    let image = Image::fill(10_u8, ColorSpace::RGB, 16, 16);
+    let png = PngEncoder::new().encode(&image).unwrap();
+
+    let decoded = Image::read(&png, DecoderOptions::default()).unwrap();
+    assert!(decoded.metadata().decode_timings().is_none());
+
+    let options = DecoderOptions::default().set_collect_timings(true);
+    let decoded = Image::read(&png, options).unwrap();
+    let stages: Vec<&str> = decoded
+        .metadata()
+        .decode_timings()
+        .unwrap()
+        .stages()
+        .iter()
+        .map(|(stage, _)| *stage)
+        .collect();
+
+    assert_eq!(stages, ["headers", "inflate", "unfilter", "deinterleave"]);
+}
+
+/// An encoder writing raw 8 bit RGB pixels
+struct RawRgbEncoder;
+
+impl crate::traits::EncoderTrait for RawRgbEncoder {
+    fn name(&self) -> &'static str {
+        "raw-rgb"
+    }
+
+    fn encode_inner(
+        &mut self, image: &crate::image::Image
+    ) -> Result<Vec<u8>, crate::errors::ImageErrors> {
+        Ok(image.flatten_to_u8().remove(0))
+    }
+
+    fn supported_colorspaces(&self) -> &'static [zune_core::colorspace::ColorSpace] {
+        &[zune_core::colorspace::ColorSpace::RGB]
+    }
+
+    fn format(&self) -> ImageFormat {
+        ImageFormat::Unknown
+    }
+
+    fn supported_bit_depth(&self) -> &'static [zune_core::bit_depth::BitDepth] {
+        &[zune_core::bit_depth::BitDepth::Eight]
+    }
+
+    fn default_depth(&self, _: zune_core::bit_depth::BitDepth) -> zune_core::bit_depth::BitDepth {
+        zune_core::bit_depth::BitDepth::Eight
+    }
+}
+
+#[test]
+fn test_encode_with_conversion() {
+    use zune_core::colorspace::ColorSpace;
+
+    use crate::image::Image;
+    use crate::traits::EncoderTrait;
+
+    // opaque red and fully transparent black
+    let rgba = Image::from_u8(&[255, 0, 0, 255, 0, 0, 0, 0], 2, 1, ColorSpace::RGBA);
+    let luma = Image::from_u8(&[10, 20], 2, 1, ColorSpace::Luma);
+
+    let mut encoder = RawRgbEncoder;
+    // transparent pixels are flattened against white
+    assert_eq!(
+        encoder.encode_with_conversion(&rgba, false).unwrap(),
+        [255, 0, 0, 255, 255, 255]
+    );
+    assert_eq!(
+        encoder.encode_with_conversion(&luma, false).unwrap(),
+        [10, 10, 10, 20, 20, 20]
+    );
+    assert!(encoder.encode_with_conversion(&rgba, true).is_err());
+}
+
+#[test]
+fn test_encode_to_writer() {
+    use zune_core::colorspace::ColorSpace;
+
+    use crate::image::Image;
+    use crate::traits::EncoderTrait;
+
+    // the default implementation writes what encode returns
+    let luma = Image::from_u8(&[10, 20], 2, 1, ColorSpace::Luma);
+    let mut output = vec![];
+
+    RawRgbEncoder.encode_to(&luma, &mut output).unwrap();
+    assert_eq!(output, RawRgbEncoder.encode(&luma).unwrap());
+}
+
+#[test]
+#[cfg(all(feature = "ppm", feature = "farbfeld"))]
+fn test_streaming_encoders() {
+    use zune_core::colorspace::ColorSpace;
+
+    use crate::codecs::farbfeld::FarbFeldEncoder;
+    use crate::codecs::ppm::PPMEncoder;
+    use crate::image::Image;
+    use crate::traits::EncoderTrait;
+
+    let image = Image::from_fn::<u16, _>(97, 61, ColorSpace::RGBA, |x, y, px| {
+        px[0] = (x * 600) as u16;
+        px[1] = (y * 1000) as u16;
+        px[2] = (x * y) as u16;
+        px[3] = 40000;
+    });
+    let encoders: [Box<dyn EncoderTrait>; 2] = [
+        Box::new(PPMEncoder::new()),
+        Box::new(FarbFeldEncoder::new())
+    ];
+
+    for mut encoder in encoders {
+        for image in [image.clone(), Image::fill(7_u8, ColorSpace::RGB, 97, 61)] {
+            let mut output = vec![];
+            encoder.encode_to(&image, &mut output).unwrap();
+
+            assert_eq!(
+                output,
+                encoder.encode(&image).unwrap(),
+                "{}",
+                encoder.name()
+            );
+        }
+    }
+}
+
+/// An encoder whose encoding always fails
+struct FailingEncoder;
+
+impl crate::traits::EncoderTrait for FailingEncoder {
+    fn name(&self) -> &'static str {
+        "failing"
+    }
+
+    fn encode_inner(
+        &mut self, _: &crate::image::Image
+    ) -> Result<Vec<u8>, crate::errors::ImageErrors> {
+        Err(crate::errors::ImageErrors::GenericStr("Encoding failed"))
+    }
+
+    fn supported_colorspaces(&self) -> &'static [zune_core::colorspace::ColorSpace] {
+        &[zune_core::colorspace::ColorSpace::RGB]
+    }
+
+    fn format(&self) -> ImageFormat {
+        ImageFormat::Unknown
+    }
+
+    fn supported_bit_depth(&self) -> &'static [zune_core::bit_depth::BitDepth] {
+        &[zune_core::bit_depth::BitDepth::Eight]
+    }
+
+    fn default_depth(&self, _: zune_core::bit_depth::BitDepth) -> zune_core::bit_depth::BitDepth {
+        zune_core::bit_depth::BitDepth::Eight
+    }
+}
+
+#[test]
+fn test_failed_save_keeps_file() {
+    use zune_core::colorspace::ColorSpace;
+
+    use crate::image::Image;
+
+    let dir = std::env::temp_dir().join(format!("zune-save-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("image.raw");
+    std::fs::write(&file, b"old").unwrap();
+
+    let rgb = Image::fill(10_u8, ColorSpace::RGB, 2, 1);
+    assert!(rgb.save_with(&file, &mut FailingEncoder).is_err());
+
+    // neither the old file nor a temporary one is touched
+    assert_eq!(std::fs::read(&file).unwrap(), b"old");
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+
+    rgb.save_with(&file, &mut RawRgbEncoder).unwrap();
+
+    assert_eq!(std::fs::read(&file).unwrap(), [10; 6]);
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_to_u8_byte_order() {
+    use zune_core::bit_depth::ByteEndian;
+    use zune_core::colorspace::ColorSpace;
+    use zune_core::options::EncoderOptions;
+
+    use crate::image::Image;
+
+    let image = Image::from_u16(&[0x0102, 0x0304], 2, 1, ColorSpace::Luma);
+
+    // big endian is the default, as PNG and PPM use it
+    assert_eq!(image.to_u8(EncoderOptions::default())[0], [1, 2, 3, 4]);
+
+    let options = EncoderOptions::default().set_byte_endian(ByteEndian::LE);
+    assert_eq!(image.to_u8(options)[0], [2, 1, 4, 3]);
+
+    // 8 bit images have no byte order
+    let image = Image::from_u8(&[1, 2], 2, 1, ColorSpace::Luma);
+    assert_eq!(image.to_u8(options)[0], [1, 2]);
+}
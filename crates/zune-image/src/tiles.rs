@@ -0,0 +1,290 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! Iterating over an image in tiles
+//!
+//! Tiled algorithms work on a small rectangle of an image at a time, which keeps their
+//! working set in cache and gives every thread of a pool independent work.
+//!
+//! [`Image::tiles`] and [`Image::tiles_mut`] split every frame into tiles of the requested
+//! size, left to right and top to bottom. Tiles on the right and bottom edges are smaller
+//! when the image dimensions aren't a multiple of the tile size.
+//!
+//! Tiles are [views](crate::view) of a single frame, they borrow rows of the image's
+//! channels, nothing is copied. Mutable tiles don't overlap so they can be sent to
+//! different threads, and operations run on them with
+//! [`OperationsTrait::execute_view`](crate::traits::OperationsTrait::execute_view).
+//!
+//! # Example
+//! ```
+//! use zune_core::colorspace::ColorSpace;
+//! use zune_image::image::Image;
+//!
+//! let mut image = Image::fill(0_u8, ColorSpace::RGB, 100, 50);
+//!
+//! // make every tile as bright as its area
+//! for mut tile in image.tiles_mut(64, 32).unwrap() {
+//!     let (width, height) = tile.dimensions();
+//!
+//!     tile.for_each_row_mut(false, |row: &mut [u8]| row.fill((width * height / 64) as u8))
+//!         .unwrap();
+//! }
+//! let tiles: Vec<_> = image.tiles(64, 32).unwrap().collect();
+//!
+//! assert_eq!(tiles.len(), 4);
+//! assert_eq!(tiles[3].position(), (64, 32));
+//! assert_eq!(tiles[3].dimensions(), (36, 18));
+//! assert_eq!(tiles[3].row::<u8>(0, 0, 0).unwrap()[0], 10);
+//! ```
+use crate::errors::ImageErrors;
+use crate::image::Image;
+use crate::view::{channel_bytes_mut, ImageView, ImageViewMut, ViewFrame};
+
+/// Iterator over the tiles of an image, returned by [`Image::tiles`]
+pub struct Tiles<'a> {
+    tiles: std::vec::IntoIter<ImageView<'a>>
+}
+
+impl<'a> Iterator for Tiles<'a> {
+    type Item = ImageView<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.tiles.next()
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.tiles.size_hint()
+    }
+}
+
+impl<'a> ExactSizeIterator for Tiles<'a> {}
+
+/// Iterator over the mutable tiles of an image, returned by [`Image::tiles_mut`]
+pub struct TilesMut<'a> {
+    tiles: std::vec::IntoIter<ImageViewMut<'a>>
+}
+
+impl<'a> Iterator for TilesMut<'a> {
+    type Item = ImageViewMut<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.tiles.next()
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.tiles.size_hint()
+    }
+}
+
+impl<'a> ExactSizeIterator for TilesMut<'a> {}
+
+fn check_tile_size(tile_width: usize, tile_height: usize) -> Result<(), ImageErrors> {
+    if tile_width == 0 || tile_height == 0 {
+        return Err(ImageErrors::GenericString(format!(
+            "Tile dimensions must be non-zero, got {tile_width}x{tile_height}"
+        )));
+    }
+    Ok(())
+}
+
+pub(crate) fn image_tiles(
+    image: &Image, tile_width: usize, tile_height: usize
+) -> Result<Tiles<'_>, ImageErrors> {
+    check_tile_size(tile_width, tile_height)?;
+
+    let (width, height) = image.dimensions();
+    let mut tiles = vec![];
+
+    for index in 0..image.frames_len() {
+        for y in (0..height).step_by(tile_height) {
+            for x in (0..width).step_by(tile_width) {
+                tiles.push(ImageView::with_frames(
+                    image,
+                    index..index + 1,
+                    x,
+                    y,
+                    tile_width.min(width - x),
+                    tile_height.min(height - y)
+                )?);
+            }
+        }
+    }
+    Ok(Tiles {
+        tiles: tiles.into_iter()
+    })
+}
+
+pub(crate) fn image_tiles_mut(
+    image: &mut Image, tile_width: usize, tile_height: usize
+) -> Result<TilesMut<'_>, ImageErrors> {
+    check_tile_size(tile_width, tile_height)?;
+
+    let (width, height) = image.dimensions();
+    let colorspace = image.colorspace();
+    let depth = image.depth();
+    let size = depth.size_of();
+    let mut tiles = vec![];
+
+    if width == 0 || height == 0 {
+        return Ok(TilesMut {
+            tiles: tiles.into_iter()
+        });
+    }
+    let columns = width.div_ceil(tile_width);
+    // (frame, position, dimensions) of every tile and the rows they borrow
+    let mut places = vec![];
+    let mut frames = vec![];
+
+    for (index, frame) in image.frames.iter_mut().enumerate() {
+        let first_tile = frames.len();
+
+        for y in (0..height).step_by(tile_height) {
+            for x in (0..width).step_by(tile_width) {
+                let dimensions = (tile_width.min(width - x), tile_height.min(height - y));
+
+                places.push((index, (x, y), dimensions));
+                frames.push(ViewFrame {
+                    numerator:   frame.numerator,
+                    denominator: frame.denominator,
+                    rows:        vec![]
+                });
+            }
+        }
+        // hand out pieces of every row to the tiles they fall in, channel by channel,
+        // so each tile ends up with its rows in channel order
+        for channel in frame.channels_mut(colorspace, false) {
+            let channel = channel_bytes_mut(channel, depth)?;
+
+            for (y, row) in channel.chunks_exact_mut(width * size).take(height).enumerate() {
+                let tile_row = first_tile + (y / tile_height) * columns;
+
+                for (column, piece) in row.chunks_mut(tile_width * size).enumerate() {
+                    frames[tile_row + column].rows.push(piece);
+                }
+            }
+        }
+    }
+    for ((index, position, dimensions), frame) in places.into_iter().zip(frames) {
+        tiles.push(ImageViewMut::from_frames(
+            &image.metadata,
+            index,
+            position,
+            dimensions,
+            vec![frame]
+        ));
+    }
+    Ok(TilesMut {
+        tiles: tiles.into_iter()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use zune_core::colorspace::ColorSpace;
+
+    use crate::image::Image;
+
+    #[test]
+    fn test_tiles_cover_image() {
+        let (width, height) = (37, 21);
+        let pixels: Vec<u16> = (0..width * height)
+            .flat_map(|i| [((i / width) * 100 + i % width) as u16, 7])
+            .collect();
+        let image = Image::from_u16(&pixels, width, height, ColorSpace::LumaA);
+        let mut seen = vec![0; width * height];
+
+        for tile in image.tiles(8, 5).unwrap() {
+            let (x, y) = tile.position();
+            let (w, h) = tile.dimensions();
+
+            assert_eq!(tile.num_channels(), 2);
+            assert_eq!((tile.frames_len(), tile.first_frame()), (1, 0));
+            assert_eq!((w, h), (8.min(width - x), 5.min(height - y)));
+
+            for row in 0..h {
+                let expected: Vec<u16> = (x..x + w).map(|i| ((y + row) * 100 + i) as u16).collect();
+
+                assert_eq!(tile.row::<u16>(0, 0, row).unwrap(), expected);
+                assert!(tile
+                    .row::<u16>(0, 1, row)
+                    .unwrap()
+                    .iter()
+                    .all(|p| *p == 7));
+
+                seen[(y + row) * width + x..][..w]
+                    .iter_mut()
+                    .for_each(|s| *s += 1);
+            }
+        }
+        assert!(seen.iter().all(|s| *s == 1));
+    }
+
+    #[test]
+    fn test_tiles_mut_match_tiles() {
+        let mut image = Image::fill(0_u8, ColorSpace::RGB, 10, 7);
+
+        for (i, mut tile) in image.tiles_mut(4, 3).unwrap().enumerate() {
+            for c in 0..tile.num_channels() {
+                for y in 0..tile.dimensions().1 {
+                    tile.row_mut::<u8>(0, c, y)
+                        .unwrap()
+                        .fill(i as u8 * 3 + c as u8);
+                }
+            }
+        }
+        let tiles = image.tiles(4, 3).unwrap();
+        assert_eq!(tiles.len(), 9);
+
+        for (i, tile) in tiles.enumerate() {
+            for c in 0..3 {
+                for y in 0..tile.dimensions().1 {
+                    let row = tile.row::<u8>(0, c, y).unwrap();
+                    assert!(row.iter().all(|p| *p == i as u8 * 3 + c as u8));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_tiles_mut_are_views() {
+        let mut image = Image::fill(0_u16, ColorSpace::Luma, 9, 4);
+        let expected = image.clone();
+
+        // tiles are views, so they can be sent to other threads
+        std::thread::scope(|scope| {
+            for mut tile in image.tiles_mut(4, 4).unwrap() {
+                scope.spawn(move || {
+                    let value = tile.position().0 as u16;
+                    tile.for_each_row_mut(false, |row: &mut [u16]| row.fill(value))
+                        .unwrap();
+                });
+            }
+        });
+        let row = image.view(0, 3, 9, 1).unwrap().row::<u16>(0, 0, 0).unwrap();
+        assert_eq!(row, [0, 0, 0, 0, 4, 4, 4, 4, 8]);
+
+        // copies of a tile cover only the tile
+        let mut tile = image.tiles_mut(4, 4).unwrap().nth(1).unwrap();
+        tile.copy_from(&expected.view(4, 0, 4, 4).unwrap().to_image().unwrap())
+            .unwrap();
+        let row = image.view(0, 3, 9, 1).unwrap().row::<u16>(0, 0, 0).unwrap();
+        assert_eq!(row, [0, 0, 0, 0, 0, 0, 0, 0, 8]);
+    }
+
+    #[test]
+    fn test_tiles_errors() {
+        let mut image = Image::fill(0_u8, ColorSpace::RGB, 10, 7);
+
+        assert!(image.tiles(0, 4).is_err());
+        assert!(image.tiles_mut(4, 0).is_err());
+
+        let tile = image.tiles(4, 4).unwrap().next().unwrap();
+        assert!(tile.row::<u16>(0, 0, 0).is_err());
+
+        let mut tile = image.tiles_mut(4, 4).unwrap().next().unwrap();
+        assert!(tile.row_mut::<f32>(0, 0, 0).is_err());
+    }
+}
@@ -14,20 +14,25 @@
 //! - encoding: `EncoderTrait`: Implementing this means the image can be saved to a certain format
 //!
 #![allow(unused_variables)]
+use std::borrow::Cow;
+use std::io::Write;
+
 use zune_core::bit_depth::{BitDepth, BitType};
 use zune_core::bytestream::ZReaderTrait;
 use zune_core::colorspace::{ColorSpace, ALL_COLORSPACES};
 use zune_core::log::{trace, warn};
 use zune_core::options::EncoderOptions;
+use zune_core::timings::DecodeTimings;
 
 use crate::codecs::ImageFormat;
-use crate::core_filters::colorspace::ColorspaceConv;
+use crate::core_filters::colorspace::{flatten_alpha, ColorspaceConv};
 use crate::core_filters::depth::Depth;
-use crate::errors::{ImageErrors, ImageOperationsErrors};
+use crate::errors::{ImageErrors, ImageOperationsErrors, ImgEncodeErrors};
 use crate::image::Image;
 use crate::metadata::AlphaState::NonPreMultiplied;
 use crate::metadata::{AlphaState, ImageMetadata};
 use crate::pipelines::EncodeResult;
+use crate::view::ImageViewMut;
 
 /// Encapsulates an image decoder.
 ///
@@ -87,6 +92,43 @@ pub trait DecoderTrait<T: ZReaderTrait> {
     fn read_headers(&mut self) -> Result<Option<ImageMetadata>, crate::errors::ImageErrors> {
         Ok(None)
     }
+    /// Return the time spent in each stage of decoding
+    ///
+    /// Stages are only recorded when
+    /// [`DecoderOptions::set_collect_timings`](zune_core::options::DecoderOptions::set_collect_timings)
+    /// is set, decoders that don't record timings return no stages
+    fn timings(&self) -> DecodeTimings {
+        DecodeTimings::default()
+    }
+
+    /// Decode the image, running the leading `operations` that have interleaved kernels
+    /// over the decoded pixels before they are split into channels
+    ///
+    /// Returns the image and how many of `operations` ran on it. Decoders that don't
+    /// produce interleaved 8 bit pixels decode as [`decode`](Self::decode) and run none.
+    ///
+    /// See [`OperationsTrait::interleaved_colorspace`]
+    fn decode_with(
+        &mut self, _operations: &[&dyn OperationsTrait]
+    ) -> Result<(Image, usize), ImageErrors> {
+        Ok((self.decode()?, 0))
+    }
+}
+
+/// Values an operation expects pixels to hold when a pipeline
+/// processes images in linear light
+///
+/// See [`Pipeline::process_in_linear_light`](crate::pipelines::Pipeline::process_in_linear_light)
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum LightSpace {
+    /// The operation works on whatever values the image holds
+    Any,
+    /// The operation averages or interpolates pixels and is only correct
+    /// on values proportional to light, e.g blurring and resizing
+    Linear,
+    /// The operation expects sRGB encoded values, e.g thresholds and curves
+    /// whose parameters are chosen on perceptual values
+    Encoded
 }
 
 /// This encapsulates an image operation.
@@ -125,6 +167,22 @@ pub trait OperationsTrait {
     /// the right things
     fn supported_types(&self) -> &'static [BitType];
 
+    /// Return the colorspace an image in `input` colorspace will be in
+    /// after this operation is executed
+    ///
+    /// This allows one to plan a pipeline without touching any pixels,
+    /// operations that change the image colorspace must override this
+    fn output_colorspace(&self, input: ColorSpace) -> ColorSpace {
+        input
+    }
+    /// Return the bit depth an image with `input` depth will be in after this
+    /// operation is executed
+    ///
+    /// Operations that change the image depth must override this
+    fn output_depth(&self, input: BitDepth) -> BitDepth {
+        input
+    }
+
     /// Execute an operation
     ///
     /// This does come common error checking operations, e.g
@@ -142,35 +200,11 @@ pub trait OperationsTrait {
     ///
     /// [`execute_impl`]: Self::execute_impl
     fn execute(&self, image: &mut Image) -> Result<(), ImageErrors> {
-        // Confirm colorspace
-        let colorspace = image.colorspace();
-
-        let supported = self
-            .supported_colorspaces()
-            .iter()
-            .any(|x| *x == colorspace);
-
-        if !supported {
-            return Err(ImageErrors::UnsupportedColorspace(
-                colorspace,
-                self.name(),
-                self.supported_colorspaces()
-            ));
-        }
         // if image.metadata.alpha != self.alpha_state()
         // {
         //     PremultiplyAlpha::new(self.alpha_state());
         // }
-        // check we support the bit depth
-        let bit_type = image.metadata.get_depth().bit_type();
-
-        let supported = self.supported_types().iter().any(|x| *x == bit_type);
-
-        if !supported {
-            return Err(ImageErrors::OperationsError(
-                ImageOperationsErrors::UnsupportedType(self.name(), bit_type)
-            ));
-        }
+        confirm_support(self, image.colorspace(), image.depth().bit_type())?;
 
         confirm_invariants(image)?;
 
@@ -181,6 +215,47 @@ pub trait OperationsTrait {
 
         Ok(())
     }
+    /// Execute an operation on the region of an image covered by `view`
+    ///
+    /// This checks that this operation supports the image colorspace and depth,
+    /// before calling [`execute_view_impl`]
+    ///
+    /// # Example
+    /// ```
+    /// use zune_core::bit_depth::BitDepth;
+    /// use zune_core::colorspace::ColorSpace;
+    /// use zune_image::core_filters::depth::Depth;
+    /// use zune_image::image::Image;
+    /// use zune_image::traits::OperationsTrait;
+    ///
+    /// let mut image = Image::fill(10_u8, ColorSpace::RGB, 100, 100);
+    /// let mut view = image.view_mut(0, 0, 50, 50).unwrap();
+    ///
+    /// // operations that change the depth of an image can't run on a region
+    /// assert!(Depth::new(BitDepth::Sixteen).execute_view(&mut view).is_err());
+    /// ```
+    ///
+    /// [`execute_view_impl`]: Self::execute_view_impl
+    fn execute_view(&self, view: &mut ImageViewMut) -> Result<(), ImageErrors> {
+        confirm_support(self, view.colorspace(), view.depth().bit_type())?;
+
+        self.execute_view_impl(view)
+    }
+    /// Execute an operation on a region of an image
+    ///
+    /// The default copies the region into a new image, executes the operation on it and
+    /// copies the result back. Operations that can work on the rows of the region in place,
+    /// e.g point operations, should override this to skip the copies.
+    ///
+    /// Operations that change the dimensions, colorspace or depth of an image fail on views.
+    fn execute_view_impl(&self, view: &mut ImageViewMut) -> Result<(), ImageErrors> {
+        let mut region = view.to_image()?;
+
+        self.execute_impl(&mut region)?;
+        confirm_invariants(&region)?;
+
+        view.copy_from(&region)
+    }
     /// Alpha state for which the image operation works in
     ///
     /// Most image expect a premultiplied alpha state to work correctly
@@ -190,6 +265,46 @@ pub trait OperationsTrait {
         AlphaState::PreMultiplied
     }
 
+    /// Values this operation expects pixels in when a pipeline
+    /// processes images in linear light
+    ///
+    /// Operations blending neighbouring pixels should return [`LightSpace::Linear`],
+    /// operations whose parameters refer to encoded values [`LightSpace::Encoded`].
+    /// The default, [`LightSpace::Any`], runs on whatever the image holds
+    fn light_space(&self) -> LightSpace {
+        LightSpace::Any
+    }
+
+    /// Return the colorspace interleaved 8 bit pixels in `input` colorspace will be in
+    /// after [`execute_interleaved`], or `None` if the operation has no interleaved kernel
+    /// for that colorspace
+    ///
+    /// Pipelines run the operations at their start that return `Some` over the interleaved
+    /// pixels decoders produce, before they are split into channels (see
+    /// [`DecoderTrait::decode_with`]), so point operations (whose output pixel only depends
+    /// on the same input pixel) may override this.
+    ///
+    /// [`execute_interleaved`]: Self::execute_interleaved
+    fn interleaved_colorspace(&self, _input: ColorSpace) -> Option<ColorSpace> {
+        None
+    }
+
+    /// Run the operation on interleaved 8 bit `pixels` in `colorspace`
+    ///
+    /// This is only called for colorspaces [`interleaved_colorspace`] accepts and must give
+    /// the same result as [`execute`], operations changing the number of channels resize `pixels`
+    ///
+    /// [`interleaved_colorspace`]: Self::interleaved_colorspace
+    /// [`execute`]: Self::execute
+    fn execute_interleaved(
+        &self, _pixels: &mut Vec<u8>, colorspace: ColorSpace
+    ) -> Result<(), ImageErrors> {
+        Err(ImageErrors::GenericString(format!(
+            "{} has no interleaved kernel for {colorspace:?}",
+            self.name()
+        )))
+    }
+
     /// Clone the image and execute the operation on it, returning
     /// a new image instead of modifying the existing one
     ///
@@ -202,6 +317,51 @@ pub trait OperationsTrait {
     }
 }
 
+/// Return the colorspace among RGB, RGBA, Luma and LumaA supported by an encoder that
+/// represents `from` with the least loss
+///
+/// Losing color costs more than losing alpha, which costs more than adding channels
+fn nearest_colorspace(from: ColorSpace, supported: &[ColorSpace]) -> Option<ColorSpace> {
+    let has_color = !from.is_grayscale();
+    let has_alpha = from.has_alpha();
+
+    [
+        ColorSpace::RGB,
+        ColorSpace::RGBA,
+        ColorSpace::Luma,
+        ColorSpace::LumaA
+    ]
+    .into_iter()
+    .filter(|x| supported.contains(x))
+    .min_by_key(|to| {
+        let (color, alpha) = (!to.is_grayscale(), to.has_alpha());
+
+        4 * usize::from(has_color && !color)
+            + 2 * usize::from(has_alpha && !alpha)
+            + usize::from(color && !has_color)
+            + usize::from(alpha && !has_alpha)
+    })
+}
+
+/// Return an error if `operation` doesn't support `colorspace` or `bit_type`
+fn confirm_support<T: OperationsTrait + ?Sized>(
+    operation: &T, colorspace: ColorSpace, bit_type: BitType
+) -> Result<(), ImageErrors> {
+    if !operation.supported_colorspaces().contains(&colorspace) {
+        return Err(ImageErrors::UnsupportedColorspace(
+            colorspace,
+            operation.name(),
+            operation.supported_colorspaces()
+        ));
+    }
+    if !operation.supported_types().contains(&bit_type) {
+        return Err(ImageErrors::OperationsError(
+            ImageOperationsErrors::UnsupportedType(operation.name(), bit_type)
+        ));
+    }
+    Ok(())
+}
+
 /// Confirm that image invariants have been respected across image
 /// operations
 fn confirm_invariants(image: &Image) -> Result<(), ImageErrors> {
@@ -287,62 +447,105 @@ pub trait EncoderTrait {
     /// is recommended to have the image in a format that can be encoded
     /// directly to prevent such
     fn encode(&mut self, image: &Image) -> Result<Vec<u8>, ImageErrors> {
-        // confirm things hold themselves
-        confirm_invariants(image)?;
+        let image = prepare_for_encoder(self, image)?;
 
-        // check colorspace is correct.
-        let colorspace = image.colorspace();
-        let supported_colorspaces = self.supported_colorspaces();
+        self.encode_inner(&image)
+    }
 
-        // deal convert bit depths
-        let depth = image.depth();
+    /// Encode and write to `writer`
+    ///
+    /// This is the streaming counterpart of [`encode_inner`](Self::encode_inner),
+    /// the default implementation encodes to a `Vec<u8>` and writes it out, encoders
+    /// that can produce output in pieces should override it so that large images
+    /// go to files or sockets without holding the whole encoded image in memory.
+    ///
+    /// # Arguments
+    /// - image: An image in a colorspace and depth the encoder supports
+    /// - writer: Where encoded bytes are written to
+    fn encode_inner_to(
+        &mut self, image: &Image, writer: &mut dyn Write
+    ) -> Result<(), ImageErrors> {
+        let data = self.encode_inner(image)?;
+        writer.write_all(&data)?;
 
-        if image.is_animated() && !self.supports_animated_images() {
-            warn!("The current image is animated but the encoder ({:?}) doesn't support animated images, this will only encode the first frame",self.name());
-        }
-        if !supported_colorspaces.contains(&colorspace)
-            || !self.supported_bit_depth().contains(&depth)
-            || image.metadata.alpha != NonPreMultiplied
-        {
-            let mut image_clone = image.clone();
-
-            if !supported_colorspaces.contains(&colorspace) {
-                // get default colorspace
-                let default_colorspace = self.default_colorspace(colorspace);
-                let image_format = self.format();
-
-                trace!("Image is in {colorspace:?} colorspace,converting it to {default_colorspace:?} which is the default configured colorspace of {image_format:?}");
-                // try converting  it to a supported colorspace
-                let converter = ColorspaceConv::new(default_colorspace);
-
-                converter.execute(&mut image_clone)?
-            }
-            let image_depth = image.depth();
-
-            if !self.supported_bit_depth().contains(&depth) {
-                trace!(
-                    "Image depth is in {:?}, but {} encoder supports {:?}",
-                    image.depth(),
-                    self.name(),
-                    self.supported_bit_depth()
-                );
-                trace!(
-                    "Converting image to a depth of {:?}",
-                    self.default_depth(image_depth)
-                );
-
-                let depth = Depth::new(self.default_depth(image_depth));
-
-                depth.execute(&mut image_clone)?;
-            }
+        Ok(())
+    }
 
-            // confirm again we didn't mess up
-            confirm_invariants(&image_clone)?;
+    /// Encode the image into the specified format and write it to `writer`
+    ///
+    /// This does the same conversions as [`encode`](Self::encode) and then calls
+    /// [`encode_inner_to`](Self::encode_inner_to), writing as the encoder produces output
+    /// where the encoder supports it.
+    ///
+    /// The writer is not buffered, wrap files and sockets in a [`std::io::BufWriter`]
+    ///
+    /// # Arguments
+    /// - image: The image to encode
+    /// - writer: Where encoded bytes are written to
+    ///
+    /// # Errors
+    /// Any error from encoding or from `writer`, in which case some bytes may
+    /// have been written already
+    fn encode_to(&mut self, image: &Image, writer: &mut dyn Write) -> Result<(), ImageErrors> {
+        let image = prepare_for_encoder(self, image)?;
+
+        self.encode_inner_to(&image, writer)
+    }
 
-            self.encode_inner(&image_clone)
+    /// Encode an image, converting it to the nearest colorspace the encoder supports
+    ///
+    /// Unlike [`encode`](Self::encode), which converts unsupported colorspaces to
+    /// [`default_colorspace`](Self::default_colorspace), this picks the supported colorspace
+    /// that loses the least information, preferring to keep color over alpha and
+    /// never adding more channels than needed, e.g RGBA becomes RGB for an encoder
+    /// without alpha support and Luma becomes RGB for an encoder only writing color images.
+    ///
+    /// When alpha is removed, the image is composited over a white background
+    /// instead of dropping the alpha channel, so transparent areas don't show
+    /// whatever color they stored.
+    ///
+    /// A warning is logged for every conversion.
+    ///
+    /// # Arguments
+    /// - image: The image to encode
+    /// - strict: If true, return an error for unsupported colorspaces instead of converting
+    ///
+    /// # Errors
+    /// In strict mode, if the image colorspace is not supported by the encoder,
+    /// and any error from [`encode`](Self::encode)
+    fn encode_with_conversion(
+        &mut self, image: &Image, strict: bool
+    ) -> Result<Vec<u8>, ImageErrors> {
+        let colorspace = image.colorspace();
+        let supported_colorspaces = self.supported_colorspaces();
+
+        if supported_colorspaces.contains(&colorspace) {
+            return self.encode(image);
+        }
+        if strict {
+            return Err(
+                ImgEncodeErrors::UnsupportedColorspace(colorspace, supported_colorspaces).into()
+            );
+        }
+        let Some(target) = nearest_colorspace(colorspace, supported_colorspaces) else {
+            // nothing close, let encode fall back to the default colorspace
+            return self.encode(image);
+        };
+        warn!(
+            "{} cannot encode {colorspace:?} images, converting to {target:?}",
+            self.name()
+        );
+        let mut image_clone = image.clone();
+
+        if colorspace.has_alpha() && !target.has_alpha() {
+            let with_alpha =
+                if target.is_grayscale() { ColorSpace::LumaA } else { ColorSpace::RGBA };
+            ColorspaceConv::new(with_alpha).execute(&mut image_clone)?;
+            flatten_alpha(&mut image_clone, target)?;
         } else {
-            self.encode_inner(image)
+            ColorspaceConv::new(target).execute(&mut image_clone)?;
         }
+        self.encode(&image_clone)
     }
     /// Return the image format for which this
     /// encoder will encode the format in
@@ -410,6 +613,75 @@ pub trait EncoderTrait {
     fn supports_animated_images(&self) -> bool {
         false
     }
+
+    /// Return true if the encoder writes the exif metadata of images, including
+    /// the orientation tag
+    fn supports_exif(&self) -> bool {
+        false
+    }
+}
+
+/// Convert `image` to a colorspace and depth supported by `encoder`
+///
+/// The image is only cloned when a conversion is needed
+fn prepare_for_encoder<'a, E: EncoderTrait + ?Sized>(
+    encoder: &E, image: &'a Image
+) -> Result<Cow<'a, Image>, ImageErrors> {
+    // confirm things hold themselves
+    confirm_invariants(image)?;
+
+    // check colorspace is correct.
+    let colorspace = image.colorspace();
+    let supported_colorspaces = encoder.supported_colorspaces();
+
+    // deal convert bit depths
+    let depth = image.depth();
+
+    if image.is_animated() && !encoder.supports_animated_images() {
+        warn!("The current image is animated but the encoder ({:?}) doesn't support animated images, this will only encode the first frame",encoder.name());
+    }
+    if supported_colorspaces.contains(&colorspace)
+        && encoder.supported_bit_depth().contains(&depth)
+        && image.metadata.alpha == NonPreMultiplied
+    {
+        return Ok(Cow::Borrowed(image));
+    }
+    let mut image_clone = image.clone();
+
+    if !supported_colorspaces.contains(&colorspace) {
+        // get default colorspace
+        let default_colorspace = encoder.default_colorspace(colorspace);
+        let image_format = encoder.format();
+
+        trace!("Image is in {colorspace:?} colorspace,converting it to {default_colorspace:?} which is the default configured colorspace of {image_format:?}");
+        // try converting  it to a supported colorspace
+        let converter = ColorspaceConv::new(default_colorspace);
+
+        converter.execute(&mut image_clone)?
+    }
+    let image_depth = image.depth();
+
+    if !encoder.supported_bit_depth().contains(&depth) {
+        trace!(
+            "Image depth is in {:?}, but {} encoder supports {:?}",
+            image.depth(),
+            encoder.name(),
+            encoder.supported_bit_depth()
+        );
+        trace!(
+            "Converting image to a depth of {:?}",
+            encoder.default_depth(image_depth)
+        );
+
+        let depth = Depth::new(encoder.default_depth(image_depth));
+
+        depth.execute(&mut image_clone)?;
+    }
+
+    // confirm again we didn't mess up
+    confirm_invariants(&image_clone)?;
+
+    Ok(Cow::Owned(image_clone))
 }
 
 /// Trait that encapsulates supported
@@ -457,6 +729,83 @@ impl ZuneInts<f32> for f32 {
     }
 }
 
+/// Unsigned integer sample types, `u8` and `u16`
+///
+/// Kernels written once against this trait support both depths, so an operation
+/// can't end up handling 8 bit images only.
+///
+/// Sums and fixed point products of samples are carried out in the wider
+/// [`Accumulator`](UnsignedSample::Accumulator), which can't overflow for
+/// values that fit in half its bits.
+pub trait UnsignedSample:
+    ZuneInts<Self> + Copy + Default + Ord + bytemuck::Pod + Send + Sync + 'static
+{
+    /// The bit type of channels holding this sample
+    const BIT_TYPE: BitType;
+    /// The number of values this sample can take, e.g the length of its histogram
+    const LEVELS: usize;
+
+    /// An unsigned integer at least twice as wide as this sample
+    type Accumulator: SampleAccumulator + From<Self> + From<u16>;
+
+    /// Convert an accumulated value back, saturating at the maximum sample value
+    fn saturate(value: Self::Accumulator) -> Self;
+
+    /// Return this sample as an index, e.g into a histogram
+    fn to_index(self) -> usize;
+}
+
+/// Integer operations needed by [`UnsignedSample::Accumulator`]
+pub trait SampleAccumulator:
+    Copy
+    + Ord
+    + core::ops::Add<Output = Self>
+    + core::ops::Sub<Output = Self>
+    + core::ops::Mul<Output = Self>
+    + core::ops::Div<Output = Self>
+    + core::ops::Shl<u32, Output = Self>
+    + core::ops::Shr<u32, Output = Self>
+{
+    /// Size of this type in bits
+    const BITS: u32;
+
+    /// Convert a non-negative float, truncating the fractional part
+    fn from_f64(value: f64) -> Self;
+}
+
+macro_rules! unsigned_sample {
+    ($sample:tt, $accumulator:tt, $bit_type:expr) => {
+        impl UnsignedSample for $sample {
+            const BIT_TYPE: BitType = $bit_type;
+            const LEVELS: usize = 1 << $sample::BITS;
+
+            type Accumulator = $accumulator;
+
+            #[inline(always)]
+            fn saturate(value: $accumulator) -> $sample {
+                value.min($accumulator::from($sample::MAX)) as $sample
+            }
+            #[inline(always)]
+            fn to_index(self) -> usize {
+                usize::from(self)
+            }
+        }
+
+        impl SampleAccumulator for $accumulator {
+            const BITS: u32 = $accumulator::BITS;
+
+            #[inline(always)]
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            fn from_f64(value: f64) -> $accumulator {
+                value as $accumulator
+            }
+        }
+    };
+}
+
+unsigned_sample!(u8, u32, BitType::U8);
+unsigned_sample!(u16, u64, BitType::U16);
+
 /// Trait that encapsulates image decoders that
 /// can write data as raw native endian into
 /// a buffer of u8
@@ -479,6 +828,27 @@ pub trait DecodeInto {
 pub trait IntoImage {
     /// Consumes this and returns an image
     fn into_image(self) -> Result<Image, ImageErrors>;
+
+    /// Consumes this and returns an image, running the leading `operations` that have
+    /// interleaved kernels while decoding, see [`DecoderTrait::decode_with`]
+    ///
+    /// Returns the image and how many of `operations` ran on it
+    fn into_image_with(
+        self, _operations: &[&dyn OperationsTrait]
+    ) -> Result<(Image, usize), ImageErrors>
+    where
+        Self: Sized
+    {
+        Ok((self.into_image()?, 0))
+    }
+
+    /// Return the image metadata if it is known without decoding
+    ///
+    /// Pipelines with no operations and no encoders skip decoding sources
+    /// that return `Some`, see [`LazyImage`](crate::lazy::LazyImage)
+    fn headers(&self) -> Option<&ImageMetadata> {
+        None
+    }
 }
 
 impl IntoImage for Image {
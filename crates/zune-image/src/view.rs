@@ -0,0 +1,584 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! Borrowed rectangular regions of an image
+//!
+//! An [`ImageView`] or [`ImageViewMut`] refers to a rectangle of every channel of an
+//! image without copying it. Views from [`Image::view`] cover every frame, the tiles
+//! returned by [`Image::tiles`] cover one. Rows of a view are slices into the image's
+//! channels.
+//!
+//! Operations run on a region with [`OperationsTrait::execute_view`]. Those that don't
+//! override [`OperationsTrait::execute_view_impl`] run on a copy of the region which is
+//! written back afterwards, point operations work on the rows in place.
+//!
+//! # Example
+//! ```
+//! use zune_core::colorspace::ColorSpace;
+//! use zune_image::image::Image;
+//!
+//! let mut image = Image::fill(10_u8, ColorSpace::Luma, 8, 8);
+//!
+//! let mut view = image.view_mut(2, 4, 3, 2).unwrap();
+//! view.for_each_row_mut(false, |row: &mut [u8]| row.fill(200)).unwrap();
+//!
+//! let pixels = image.channels_ref(false)[0].reinterpret_as::<u8>().unwrap();
+//! assert_eq!(&pixels[4 * 8..5 * 8], &[10, 10, 200, 200, 200, 10, 10, 10]);
+//! ```
+//!
+//! [`OperationsTrait::execute_view`]: crate::traits::OperationsTrait::execute_view
+//! [`OperationsTrait::execute_view_impl`]: crate::traits::OperationsTrait::execute_view_impl
+use std::any::TypeId;
+use std::mem::size_of;
+use std::ops::Range;
+
+use bytemuck::Pod;
+use zune_core::bit_depth::{BitDepth, BitType};
+use zune_core::colorspace::ColorSpace;
+
+use crate::channel::{Channel, ChannelErrors};
+use crate::errors::ImageErrors;
+use crate::frame::Frame;
+use crate::image::Image;
+use crate::metadata::ImageMetadata;
+
+/// Return an error if the `width` by `height` region at `(x, y)` isn't inside `image`
+fn check_region(
+    image: &Image, x: usize, y: usize, width: usize, height: usize
+) -> Result<(), ImageErrors> {
+    let (image_width, image_height) = image.dimensions();
+
+    let fits_x = x.checked_add(width).is_some_and(|end| end <= image_width);
+    let fits_y = y.checked_add(height).is_some_and(|end| end <= image_height);
+
+    if !fits_x || !fits_y {
+        return Err(ImageErrors::GenericString(format!(
+            "Region of {width}x{height} at ({x}, {y}) is outside the {image_width}x{image_height} image"
+        )));
+    }
+    Ok(())
+}
+
+/// Return an error if `T` isn't the type of samples of `depth`
+fn check_type<T: 'static>(depth: BitDepth) -> Result<(), ImageErrors> {
+    let expected = match depth.bit_type() {
+        BitType::U8 => TypeId::of::<u8>(),
+        BitType::U16 => TypeId::of::<u16>(),
+        BitType::F32 => TypeId::of::<f32>(),
+        d => return Err(ImageErrors::ImageOperationNotImplemented("view", d))
+    };
+    if expected != TypeId::of::<T>() {
+        return Err(ChannelErrors::DifferentType(expected, TypeId::of::<T>()).into());
+    }
+    Ok(())
+}
+
+/// Return the samples of `channel` as bytes, the channel must hold samples of `depth`
+pub(crate) fn channel_bytes(channel: &Channel, depth: BitDepth) -> Result<&[u8], ImageErrors> {
+    match depth.bit_type() {
+        BitType::U8 => Ok(channel.reinterpret_as::<u8>()?),
+        BitType::U16 => Ok(bytemuck::cast_slice(channel.reinterpret_as::<u16>()?)),
+        BitType::F32 => Ok(bytemuck::cast_slice(channel.reinterpret_as::<f32>()?)),
+        d => Err(ImageErrors::ImageOperationNotImplemented("view", d))
+    }
+}
+
+/// Return the samples of `channel` as mutable bytes, see [`channel_bytes`]
+pub(crate) fn channel_bytes_mut(
+    channel: &mut Channel, depth: BitDepth
+) -> Result<&mut [u8], ImageErrors> {
+    match depth.bit_type() {
+        BitType::U8 => Ok(channel.reinterpret_as_mut::<u8>()?),
+        BitType::U16 => Ok(bytemuck::cast_slice_mut(channel.reinterpret_as_mut::<u16>()?)),
+        BitType::F32 => Ok(bytemuck::cast_slice_mut(channel.reinterpret_as_mut::<f32>()?)),
+        d => Err(ImageErrors::ImageOperationNotImplemented("view", d))
+    }
+}
+
+/// The rows of a frame covered by a view
+#[derive(Clone)]
+pub(crate) struct ViewFrame<R> {
+    pub(crate) numerator:   usize,
+    pub(crate) denominator: usize,
+    /// Rows of every channel as bytes, all rows of the first channel come first
+    pub(crate) rows:        Vec<R>
+}
+
+/// A rectangular region of an image
+///
+/// See the [view](crate::view) module
+#[derive(Clone)]
+pub struct ImageView<'a> {
+    metadata:    &'a ImageMetadata,
+    first_frame: usize,
+    x:           usize,
+    y:           usize,
+    width:       usize,
+    height:      usize,
+    frames:      Vec<ViewFrame<&'a [u8]>>
+}
+
+impl<'a> ImageView<'a> {
+    /// Create a view of the `width` by `height` region of `image` whose top left pixel is at `(x, y)`
+    ///
+    /// # Returns
+    ///  - Ok(ImageView): The view
+    ///  - Err(ImageErrors): The region isn't inside the image
+    pub fn new(
+        image: &'a Image, x: usize, y: usize, width: usize, height: usize
+    ) -> Result<ImageView<'a>, ImageErrors> {
+        check_region(image, x, y, width, height)?;
+
+        ImageView::with_frames(image, 0..image.frames_len(), x, y, width, height)
+    }
+    /// Create a view of a region in `frames` of `image`, the region must be inside the image
+    pub(crate) fn with_frames(
+        image: &'a Image, frames: Range<usize>, x: usize, y: usize, width: usize, height: usize
+    ) -> Result<ImageView<'a>, ImageErrors> {
+        let stride = image.dimensions().0;
+        let size = image.depth().size_of();
+        let first_frame = frames.start;
+        let mut view_frames = Vec::with_capacity(frames.len());
+
+        for frame in &image.frames[frames] {
+            let mut rows = Vec::with_capacity(image.colorspace().num_components() * height);
+
+            for channel in frame.channels_ref(image.colorspace(), false) {
+                let samples = channel_bytes(channel, image.depth())?;
+
+                rows.extend(
+                    (y..y + height).map(|row| &samples[(row * stride + x) * size..][..width * size])
+                );
+            }
+            view_frames.push(ViewFrame {
+                numerator: frame.numerator,
+                denominator: frame.denominator,
+                rows
+            });
+        }
+        Ok(ImageView {
+            metadata: &image.metadata,
+            first_frame,
+            x,
+            y,
+            width,
+            height,
+            frames: view_frames
+        })
+    }
+    /// The `(x, y)` position of the top left pixel of this view in the image
+    pub const fn position(&self) -> (usize, usize) {
+        (self.x, self.y)
+    }
+    /// The `(width, height)` of this view
+    pub const fn dimensions(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+    /// The colorspace of the viewed image
+    pub const fn colorspace(&self) -> ColorSpace {
+        self.metadata.get_colorspace()
+    }
+    /// The depth of the viewed image
+    pub const fn depth(&self) -> BitDepth {
+        self.metadata.get_depth()
+    }
+    /// The number of channels of the viewed image, this includes alpha
+    pub const fn num_channels(&self) -> usize {
+        self.colorspace().num_components()
+    }
+    /// The number of frames in this view
+    pub fn frames_len(&self) -> usize {
+        self.frames.len()
+    }
+    /// The index in the image of the first frame in this view
+    ///
+    /// Views created by [`Image::view`] start at the first frame, tiles hold a single frame
+    pub const fn first_frame(&self) -> usize {
+        self.first_frame
+    }
+    /// Return row `y` of `channel` in `frame`, `y` is relative to the top of the view
+    /// and `frame` to its first frame
+    ///
+    /// # Returns
+    ///  - Ok(&[T]): The `width` samples of the row
+    ///  - Err(ImageErrors): The channel is not of type `T`
+    ///
+    /// # Panics
+    /// If `frame`, `channel` or `y` is out of bounds
+    pub fn row<T: Pod>(
+        &self, frame: usize, channel: usize, y: usize
+    ) -> Result<&'a [T], ImageErrors> {
+        check_type::<T>(self.depth())?;
+        assert!(
+            y < self.height,
+            "Row {y} out of bounds for a view of height {}",
+            self.height
+        );
+        Ok(bytemuck::cast_slice(self.frames[frame].rows[channel * self.height + y]))
+    }
+    /// Copy the region into a new image
+    ///
+    /// The new image has the frames of this view and the metadata of the viewed image
+    pub fn to_image(&self) -> Result<Image, ImageErrors> {
+        let mut frames = Vec::with_capacity(self.frames_len());
+
+        for (index, frame) in self.frames.iter().enumerate() {
+            let mut channels = Vec::with_capacity(self.num_channels());
+
+            for channel in 0..self.num_channels() {
+                channels.push(match self.depth().bit_type() {
+                    BitType::U8 => self.copy_channel::<u8>(index, channel)?,
+                    BitType::U16 => self.copy_channel::<u16>(index, channel)?,
+                    BitType::F32 => self.copy_channel::<f32>(index, channel)?,
+                    d => return Err(ImageErrors::ImageOperationNotImplemented("view", d))
+                });
+            }
+            frames.push(Frame {
+                channels,
+                numerator: frame.numerator,
+                denominator: frame.denominator
+            });
+        }
+        let mut image = Image {
+            frames,
+            metadata: self.metadata.clone()
+        };
+        image.metadata.set_dimensions(self.width, self.height);
+
+        Ok(image)
+    }
+
+    fn copy_channel<T: Pod + Default>(
+        &self, frame: usize, channel: usize
+    ) -> Result<Channel, ImageErrors> {
+        let mut out = Channel::new_with_capacity::<T>(self.width * self.height * size_of::<T>());
+
+        for y in 0..self.height {
+            out.extend(self.row::<T>(frame, channel, y)?);
+        }
+        Ok(out)
+    }
+}
+
+/// A mutable rectangular region of an image
+///
+/// See the [view](crate::view) module
+pub struct ImageViewMut<'a> {
+    metadata:    &'a ImageMetadata,
+    first_frame: usize,
+    x:           usize,
+    y:           usize,
+    width:       usize,
+    height:      usize,
+    frames:      Vec<ViewFrame<&'a mut [u8]>>
+}
+
+impl<'a> ImageViewMut<'a> {
+    /// Create a mutable view of the `width` by `height` region of `image` whose top left
+    /// pixel is at `(x, y)`
+    ///
+    /// # Returns
+    ///  - Ok(ImageViewMut): The view
+    ///  - Err(ImageErrors): The region isn't inside the image
+    pub fn new(
+        image: &'a mut Image, x: usize, y: usize, width: usize, height: usize
+    ) -> Result<ImageViewMut<'a>, ImageErrors> {
+        check_region(image, x, y, width, height)?;
+
+        let (stride, _) = image.dimensions();
+        let colorspace = image.colorspace();
+        let depth = image.depth();
+        let size = depth.size_of();
+        let mut frames = Vec::with_capacity(image.frames_len());
+
+        for frame in &mut image.frames {
+            let (numerator, denominator) = (frame.numerator, frame.denominator);
+            let mut rows = Vec::with_capacity(colorspace.num_components() * height);
+
+            for channel in frame.channels_mut(colorspace, false) {
+                let samples = channel_bytes_mut(channel, depth)?;
+
+                if stride == 0 {
+                    // the region is empty, but still has `height` rows
+                    rows.extend((0..height).map(|_| <&mut [u8]>::default()));
+                    continue;
+                }
+                rows.extend(
+                    samples
+                        .chunks_exact_mut(stride * size)
+                        .skip(y)
+                        .take(height)
+                        .map(|row| &mut row[x * size..(x + width) * size])
+                );
+            }
+            frames.push(ViewFrame {
+                numerator,
+                denominator,
+                rows
+            });
+        }
+        Ok(ImageViewMut::from_frames(
+            &image.metadata,
+            0,
+            (x, y),
+            (width, height),
+            frames
+        ))
+    }
+    /// Create a view from rows borrowed from the frames of an image, starting at `first_frame`
+    pub(crate) fn from_frames(
+        metadata: &'a ImageMetadata, first_frame: usize, (x, y): (usize, usize),
+        (width, height): (usize, usize), frames: Vec<ViewFrame<&'a mut [u8]>>
+    ) -> ImageViewMut<'a> {
+        ImageViewMut {
+            metadata,
+            first_frame,
+            x,
+            y,
+            width,
+            height,
+            frames
+        }
+    }
+    /// Borrow this view immutably
+    pub fn as_view(&self) -> ImageView<'_> {
+        let frames = self
+            .frames
+            .iter()
+            .map(|frame| ViewFrame {
+                numerator:   frame.numerator,
+                denominator: frame.denominator,
+                rows:        frame.rows.iter().map(|row| &**row).collect()
+            })
+            .collect();
+
+        ImageView {
+            metadata: self.metadata,
+            first_frame: self.first_frame,
+            x: self.x,
+            y: self.y,
+            width: self.width,
+            height: self.height,
+            frames
+        }
+    }
+    /// The `(x, y)` position of the top left pixel of this view in the image
+    pub const fn position(&self) -> (usize, usize) {
+        (self.x, self.y)
+    }
+    /// The `(width, height)` of this view
+    pub const fn dimensions(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+    /// The colorspace of the viewed image
+    pub const fn colorspace(&self) -> ColorSpace {
+        self.metadata.get_colorspace()
+    }
+    /// The depth of the viewed image
+    pub const fn depth(&self) -> BitDepth {
+        self.metadata.get_depth()
+    }
+    /// The number of channels of the viewed image, this includes alpha
+    pub const fn num_channels(&self) -> usize {
+        self.colorspace().num_components()
+    }
+    /// The number of frames in this view
+    pub fn frames_len(&self) -> usize {
+        self.frames.len()
+    }
+    /// The index in the image of the first frame in this view, see [`ImageView::first_frame`]
+    pub const fn first_frame(&self) -> usize {
+        self.first_frame
+    }
+    /// Return row `y` of `channel` in `frame`, see [`ImageView::row`]
+    pub fn row<T: Pod>(
+        &self, frame: usize, channel: usize, y: usize
+    ) -> Result<&[T], ImageErrors> {
+        check_type::<T>(self.depth())?;
+        assert!(
+            y < self.height,
+            "Row {y} out of bounds for a view of height {}",
+            self.height
+        );
+        Ok(bytemuck::cast_slice(self.frames[frame].rows[channel * self.height + y]))
+    }
+    /// Return row `y` of `channel` in `frame` mutably, `y` is relative to the top of the view
+    /// and `frame` to its first frame
+    ///
+    /// # Returns
+    ///  - Ok(&mut [T]): The `width` samples of the row
+    ///  - Err(ImageErrors): The channel is not of type `T`
+    ///
+    /// # Panics
+    /// If `frame`, `channel` or `y` is out of bounds
+    pub fn row_mut<T: Pod>(
+        &mut self, frame: usize, channel: usize, y: usize
+    ) -> Result<&mut [T], ImageErrors> {
+        check_type::<T>(self.depth())?;
+        assert!(
+            y < self.height,
+            "Row {y} out of bounds for a view of height {}",
+            self.height
+        );
+        Ok(bytemuck::cast_slice_mut(self.frames[frame].rows[channel * self.height + y]))
+    }
+    /// Call `func` on every row of the view, in every channel of every frame
+    ///
+    /// # Arguments
+    /// - ignore_alpha: Skip rows of the alpha channel
+    /// - func: Function called with the `width` samples of each row
+    pub fn for_each_row_mut<T, F>(
+        &mut self, ignore_alpha: bool, mut func: F
+    ) -> Result<(), ImageErrors>
+    where
+        T: Pod,
+        F: FnMut(&mut [T])
+    {
+        check_type::<T>(self.depth())?;
+
+        let alpha = match ignore_alpha {
+            true => self.colorspace().alpha_position(),
+            false => None
+        };
+        let height = self.height;
+
+        for frame in &mut self.frames {
+            for (index, row) in frame.rows.iter_mut().enumerate() {
+                if Some(index / height) != alpha {
+                    func(bytemuck::cast_slice_mut(row));
+                }
+            }
+        }
+        Ok(())
+    }
+    /// Copy the region into a new image, see [`ImageView::to_image`]
+    pub fn to_image(&self) -> Result<Image, ImageErrors> {
+        self.as_view().to_image()
+    }
+    /// Overwrite the region with the pixels of `image`
+    ///
+    /// `image` must have the dimensions of the view and the colorspace, depth and number
+    /// of frames of the view, e.g it was returned by [`to_image`](Self::to_image)
+    /// and then modified.
+    pub fn copy_from(&mut self, image: &Image) -> Result<(), ImageErrors> {
+        if image.dimensions() != self.dimensions() {
+            let (width, height) = image.dimensions();
+            return Err(ImageErrors::DimensionsMisMatch(
+                width * height,
+                self.width * self.height
+            ));
+        }
+        if image.colorspace() != self.colorspace()
+            || image.depth() != self.depth()
+            || image.frames_len() != self.frames_len()
+        {
+            return Err(ImageErrors::GenericString(format!(
+                "Cannot copy a {:?} {:?} image of {} frames into a view of a {:?} {:?} image of {} frames",
+                image.colorspace(),
+                image.depth(),
+                image.frames_len(),
+                self.colorspace(),
+                self.depth(),
+                self.frames_len()
+            )));
+        }
+        if self.width == 0 || self.height == 0 {
+            return Ok(());
+        }
+        let row_size = self.width * self.depth().size_of();
+
+        for (frame, view_frame) in image.frames_ref().iter().zip(&mut self.frames) {
+            for (source, rows) in frame
+                .channels_ref(image.colorspace(), false)
+                .iter()
+                .zip(view_frame.rows.chunks_mut(self.height))
+            {
+                let source = channel_bytes(source, image.depth())?;
+
+                for (row, source) in rows.iter_mut().zip(source.chunks_exact(row_size)) {
+                    row.copy_from_slice(source);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use zune_core::bit_depth::BitDepth;
+    use zune_core::colorspace::ColorSpace;
+
+    use crate::image::Image;
+
+    fn gradient(width: usize, height: usize) -> Image {
+        let pixels: Vec<u16> = (0..width * height)
+            .flat_map(|i| [(i * 3) as u16, (i * 5) as u16, 1000])
+            .collect();
+        Image::from_u16(&pixels, width, height, ColorSpace::RGB)
+    }
+
+    #[test]
+    fn test_view_rows_and_copies() {
+        let image = gradient(11, 6);
+        let view = image.view(3, 2, 4, 3).unwrap();
+
+        assert_eq!(view.row::<u16>(0, 0, 0).unwrap(), &[75, 78, 81, 84]);
+        assert_eq!(view.row::<u16>(0, 1, 2).unwrap(), &[235, 240, 245, 250]);
+        assert!(view.row::<u8>(0, 0, 0).is_err());
+
+        let region = view.to_image().unwrap();
+        assert_eq!(region.dimensions(), (4, 3));
+        assert_eq!(region.depth(), BitDepth::Sixteen);
+        assert_eq!(
+            region.channels_ref(false)[0]
+                .reinterpret_as::<u16>()
+                .unwrap()[..4],
+            [75, 78, 81, 84]
+        );
+    }
+
+    #[test]
+    fn test_view_mut_copy_from() {
+        let mut image = gradient(11, 6);
+        let expected = image.clone();
+
+        let mut view = image.view_mut(5, 1, 6, 5).unwrap();
+        let mut region = view.to_image().unwrap();
+
+        region.channels_mut(false)[2]
+            .reinterpret_as_mut::<u16>()
+            .unwrap()
+            .fill(0);
+        view.copy_from(&region).unwrap();
+        assert_eq!(view.row::<u16>(0, 2, 4).unwrap(), &[0; 6]);
+        assert_eq!(view.row::<u16>(0, 1, 4).unwrap()[0], 5 * (5 * 11 + 5));
+        // copying the original back restores the image
+        view.copy_from(&expected.view(5, 1, 6, 5).unwrap().to_image().unwrap())
+            .unwrap();
+
+        assert!(image == expected);
+
+        let mut view = image.view_mut(5, 1, 6, 5).unwrap();
+        assert!(view.copy_from(&gradient(6, 4)).is_err());
+        assert!(view
+            .copy_from(&Image::fill(0_u8, ColorSpace::RGB, 6, 5))
+            .is_err());
+    }
+
+    #[test]
+    fn test_view_bounds() {
+        let mut image = gradient(11, 6);
+
+        assert!(image.view(0, 0, 11, 6).is_ok());
+        assert!(image.view(10, 5, 1, 1).is_ok());
+        assert!(image.view(10, 5, 2, 1).is_err());
+        assert!(image.view_mut(0, 6, 1, 1).is_err());
+        assert!(image.view_mut(usize::MAX, 0, 2, 1).is_err());
+    }
+}
@@ -0,0 +1,595 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! A compact internal format for caching images between runs
+//!
+//! [`Image::to_zune_raw`] dumps an image as stored in memory, planar, in its own depth
+//! and colorspace, along with its metadata, and [`Image::from_zune_raw`] loads it back
+//! exactly. There is no pixel conversion or filtering, so it is much cheaper
+//! than encoding to an image format, and unlike them nothing is lost.
+//!
+//! It is meant for caching intermediate pipeline results, not for exchanging
+//! images, other tools can't read it.
+//!
+//! # Layout
+//! All integers are little endian.
+//!
+//! | Field                | Size            | Notes                                      |
+//! |----------------------|-----------------|--------------------------------------------|
+//! | magic                | 8               | `ZUNERAW\0`                                |
+//! | version              | 2               | currently `2`                              |
+//! | flags                | 1               | bit 0 set if planes are zlib compressed    |
+//! | colorspace           | 1               | see `COLORSPACES`                          |
+//! | depth                | 1               | see `DEPTHS`                               |
+//! | alpha                | 1               | `0` not premultiplied, `1` premultiplied   |
+//! | width, height        | 8 + 8           |                                            |
+//! | color trc            | 1               | `0` unset, `1` sRGB, `2` linear            |
+//! | gamma                | 1 + 4           | presence byte then `f32`                   |
+//! | format               | 1               | `0` unset, else see `FORMATS`              |
+//! | truncated            | 1               |                                            |
+//! | significant bits     | blob            |                                            |
+//! | icc profile          | blob            |                                            |
+//! | exif                 | blob            | a TIFF structure, as stored in PNG `eXIf`  |
+//! | text chunks          | 4 + 2 blobs each| count then key and value, UTF-8            |
+//! | physical dimensions  | 1 + 1 + 4 + 4   | presence, unit code, x and y `f32`, v2 on  |
+//! | frames               | 4               | count of frames that follow                |
+//!
+//! A blob is a presence byte followed, if present, by a 4 byte length and the bytes.
+//!
+//! Every frame is its delay numerator and denominator (8 bytes each), its channel count
+//! (4 bytes), then for each channel the 8 byte size of the stored plane followed by the plane.
+//! Plane samples are little endian, zlib compressed when the flag is set.
+//!
+//! Statistics and decode timings are not stored, they describe a single run.
+//!
+//! Codes are only ever appended to, so files written by older versions stay readable.
+use std::mem::size_of;
+
+use zune_core::bit_depth::{BitDepth, BitType};
+use zune_core::bytestream::ZByteReader;
+use zune_core::colorspace::{ColorCharacteristics, ColorSpace};
+
+use crate::channel::Channel;
+use crate::codecs::ImageFormat;
+use crate::errors::ImageErrors;
+use crate::frame::Frame;
+use crate::image::Image;
+use crate::metadata::{AlphaState, DensityUnit, ImageMetadata, PhysicalDimensions};
+
+/// First bytes of every zune raw file
+pub const ZUNE_RAW_MAGIC: [u8; 8] = *b"ZUNERAW\0";
+
+/// Version written by [`Image::to_zune_raw`]
+const VERSION: u16 = 2;
+
+/// Flag set when planes are zlib compressed
+const FLAG_ZLIB: u8 = 1;
+
+/// Colorspaces, indexed by their code
+const COLORSPACES: [ColorSpace; 13] = [
+    ColorSpace::Unknown,
+    ColorSpace::RGB,
+    ColorSpace::RGBA,
+    ColorSpace::YCbCr,
+    ColorSpace::Luma,
+    ColorSpace::LumaA,
+    ColorSpace::YCCK,
+    ColorSpace::CMYK,
+    ColorSpace::BGR,
+    ColorSpace::BGRA,
+    ColorSpace::ARGB,
+    ColorSpace::HSL,
+    ColorSpace::HSV
+];
+
+/// Depths, indexed by their code
+const DEPTHS: [BitDepth; 5] = [
+    BitDepth::Eight,
+    BitDepth::Sixteen,
+    BitDepth::Ten,
+    BitDepth::Twelve,
+    BitDepth::Float32
+];
+
+/// Units of physical dimensions, indexed by their code
+const DENSITY_UNITS: [DensityUnit; 4] = [
+    DensityUnit::Unknown,
+    DensityUnit::Inch,
+    DensityUnit::Centimeter,
+    DensityUnit::Meter
+];
+
+/// Source formats, indexed by their code minus one, zero means no format
+///
+/// Formats registered at runtime are not stored, their names can't be restored
+const FORMATS: [ImageFormat; 9] = [
+    ImageFormat::JPEG,
+    ImageFormat::PNG,
+    ImageFormat::PPM,
+    ImageFormat::PSD,
+    ImageFormat::Farbfeld,
+    ImageFormat::QOI,
+    ImageFormat::JPEG_XL,
+    ImageFormat::HDR,
+    ImageFormat::BMP
+];
+
+impl Image {
+    /// Dump the image to the zune raw format
+    ///
+    /// This keeps the depth, colorspace, planar layout and metadata of the image,
+    /// see the [module docs](crate::zune_raw) for the layout.
+    ///
+    /// # Arguments
+    /// - compress: Zlib compress planes, smaller output at the cost of some speed.
+    ///   This requires the `zune-raw-zlib` feature
+    ///
+    /// # Example
+    /// ```
+    /// use zune_core::colorspace::ColorSpace;
+    /// use zune_image::image::Image;
+    ///
+    /// let image = Image::fill(128_u16, ColorSpace::RGB, 20, 20);
+    ///
+    /// let raw = image.to_zune_raw(false).unwrap();
+    /// let loaded = Image::from_zune_raw(&raw).unwrap();
+    ///
+    /// assert_eq!(loaded.depth(), image.depth());
+    /// assert!(loaded == image);
+    /// ```
+    pub fn to_zune_raw(&self, compress: bool) -> Result<Vec<u8>, ImageErrors> {
+        if compress && cfg!(not(feature = "zune-raw-zlib")) {
+            return Err(ImageErrors::GenericStr(
+                "Compressing zune raw planes requires the `zune-raw-zlib` feature"
+            ));
+        }
+        let metadata = &self.metadata;
+        let mut out = Vec::new();
+
+        out.extend_from_slice(&ZUNE_RAW_MAGIC);
+        out.extend_from_slice(&VERSION.to_le_bytes());
+        out.push(if compress { FLAG_ZLIB } else { 0 });
+        out.push(code_of(&COLORSPACES, metadata.colorspace, "colorspace")?);
+        out.push(code_of(&DEPTHS, metadata.depth, "depth")?);
+        out.push(u8::from(metadata.alpha == AlphaState::PreMultiplied));
+        out.extend_from_slice(&(metadata.width as u64).to_le_bytes());
+        out.extend_from_slice(&(metadata.height as u64).to_le_bytes());
+
+        out.push(match metadata.color_trc {
+            None => 0,
+            Some(ColorCharacteristics::sRGB) => 1,
+            Some(ColorCharacteristics::Linear) => 2
+        });
+        out.push(u8::from(metadata.default_gamma.is_some()));
+        out.extend_from_slice(&metadata.default_gamma.unwrap_or(0.0).to_le_bytes());
+        out.push(
+            metadata
+                .format
+                .and_then(|format| FORMATS.iter().position(|x| *x == format))
+                .map_or(0, |position| position as u8 + 1)
+        );
+        out.push(u8::from(metadata.truncated));
+
+        write_blob(&mut out, metadata.significant_bits.as_deref())?;
+        write_blob(&mut out, metadata.icc_chunk.as_deref())?;
+        write_blob(&mut out, raw_exif(metadata).as_deref())?;
+
+        out.extend_from_slice(&to_u32(metadata.text_chunks.len())?.to_le_bytes());
+
+        for (key, value) in &metadata.text_chunks {
+            write_blob(&mut out, Some(key.as_bytes()))?;
+            write_blob(&mut out, Some(value.as_bytes()))?;
+        }
+
+        let dims = metadata.physical_dimensions;
+
+        out.push(u8::from(dims.is_some()));
+        out.push(code_of(
+            &DENSITY_UNITS,
+            dims.map_or(DensityUnit::Unknown, |x| x.unit),
+            "unit"
+        )?);
+        out.extend_from_slice(&dims.map_or(0.0, |x| x.x_density).to_le_bytes());
+        out.extend_from_slice(&dims.map_or(0.0, |x| x.y_density).to_le_bytes());
+
+        out.extend_from_slice(&to_u32(self.frames.len())?.to_le_bytes());
+
+        let bit_type = metadata.depth.bit_type();
+
+        for frame in &self.frames {
+            out.extend_from_slice(&(frame.numerator as u64).to_le_bytes());
+            out.extend_from_slice(&(frame.denominator as u64).to_le_bytes());
+            out.extend_from_slice(&to_u32(frame.channels.len())?.to_le_bytes());
+
+            for channel in &frame.channels {
+                let plane = plane_to_le_bytes(channel, bit_type)?;
+                let plane = if compress { compress_plane(&plane) } else { plane };
+
+                out.extend_from_slice(&(plane.len() as u64).to_le_bytes());
+                out.extend_from_slice(&plane);
+            }
+        }
+        Ok(out)
+    }
+
+    /// Load an image written by [`to_zune_raw`](Self::to_zune_raw)
+    ///
+    /// Reading compressed planes requires the `zune-raw-zlib` feature, and
+    /// exif is only restored with the `metadata` feature.
+    pub fn from_zune_raw(data: &[u8]) -> Result<Image, ImageErrors> {
+        let mut stream = ZByteReader::new(data);
+
+        if stream.get_fixed_bytes_or_err::<8>().map_err(truncated)? != ZUNE_RAW_MAGIC {
+            return Err(ImageErrors::ImageDecodeErrors(
+                "Not a zune raw file, bad magic bytes".to_string()
+            ));
+        }
+        let version = stream.get_u16_le_err().map_err(truncated)?;
+
+        if version > VERSION {
+            return Err(ImageErrors::ImageDecodeErrors(format!(
+                "Unsupported zune raw version {version}, the newest supported is {VERSION}"
+            )));
+        }
+        let flags = stream.get_u8_err().map_err(truncated)?;
+        let colorspace = from_code(&COLORSPACES, read_u8(&mut stream)?, "colorspace")?;
+        let depth = from_code(&DEPTHS, read_u8(&mut stream)?, "depth")?;
+        let alpha = match read_u8(&mut stream)? {
+            0 => AlphaState::NonPreMultiplied,
+            _ => AlphaState::PreMultiplied
+        };
+        let width = to_usize(stream.get_u64_le_err().map_err(truncated)?)?;
+        let height = to_usize(stream.get_u64_le_err().map_err(truncated)?)?;
+
+        let mut metadata = ImageMetadata::default();
+
+        metadata.set_dimensions(width, height);
+        metadata.set_colorspace(colorspace);
+        metadata.set_depth(depth);
+        metadata.set_alpha(alpha);
+
+        metadata.color_trc = match read_u8(&mut stream)? {
+            0 => None,
+            1 => Some(ColorCharacteristics::sRGB),
+            2 => Some(ColorCharacteristics::Linear),
+            code => return Err(unknown_code(code, "color trc"))
+        };
+        let has_gamma = read_u8(&mut stream)? != 0;
+        let gamma = f32::from_le_bytes(stream.get_fixed_bytes_or_err().map_err(truncated)?);
+
+        metadata.default_gamma = has_gamma.then_some(gamma);
+        metadata.format = match read_u8(&mut stream)? {
+            0 => None,
+            code => Some(from_code(&FORMATS, code - 1, "format")?)
+        };
+        metadata.truncated = read_u8(&mut stream)? != 0;
+        metadata.significant_bits = read_blob(&mut stream)?;
+        metadata.icc_chunk = read_blob(&mut stream)?;
+
+        #[allow(unused_variables)]
+        if let Some(exif) = read_blob(&mut stream)? {
+            #[cfg(feature = "metadata")]
+            metadata.parse_raw_exif(&exif);
+        }
+
+        let num_text = stream.get_u32_le_err().map_err(truncated)?;
+
+        for _ in 0..num_text {
+            let key = read_string(&mut stream)?;
+            let value = read_string(&mut stream)?;
+
+            metadata.text_chunks.push((key, value));
+        }
+
+        if version >= 2 {
+            let has_dims = read_u8(&mut stream)? != 0;
+            let unit = from_code(&DENSITY_UNITS, read_u8(&mut stream)?, "unit")?;
+            let x = f32::from_le_bytes(stream.get_fixed_bytes_or_err().map_err(truncated)?);
+            let y = f32::from_le_bytes(stream.get_fixed_bytes_or_err().map_err(truncated)?);
+
+            metadata.physical_dimensions = has_dims.then_some(PhysicalDimensions::new(x, y, unit));
+        }
+
+        let bit_type = depth.bit_type();
+        let plane_size = width
+            .checked_mul(height)
+            .and_then(|x| x.checked_mul(depth.size_of()))
+            .ok_or_else(|| {
+                ImageErrors::ImageDecodeErrors(format!(
+                    "Image dimensions {width}x{height} are too large"
+                ))
+            })?;
+
+        let num_frames = stream.get_u32_le_err().map_err(truncated)?;
+        let mut frames = Vec::new();
+
+        for _ in 0..num_frames {
+            let numerator = to_usize(stream.get_u64_le_err().map_err(truncated)?)?;
+            let denominator = to_usize(stream.get_u64_le_err().map_err(truncated)?)?;
+            let num_channels = stream.get_u32_le_err().map_err(truncated)?;
+
+            let mut channels = Vec::new();
+
+            for _ in 0..num_channels {
+                let stored = to_usize(stream.get_u64_le_err().map_err(truncated)?)?;
+                let stored = stream.get(stored).map_err(truncated)?;
+
+                let plane = if flags & FLAG_ZLIB != 0 {
+                    decompress_plane(stored, plane_size)?
+                } else {
+                    stored.to_vec()
+                };
+                if plane.len() != plane_size {
+                    return Err(ImageErrors::ImageDecodeErrors(format!(
+                        "Plane has {} bytes, expected {plane_size} for a {width}x{height} {depth:?} image",
+                        plane.len()
+                    )));
+                }
+                channels.push(plane_from_le_bytes(&plane, bit_type)?);
+            }
+            frames.push(Frame::new_with_duration(channels, numerator, denominator));
+        }
+        Ok(Image { frames, metadata })
+    }
+}
+
+/// Return the code of `value`, its position in `codes`
+fn code_of<T: PartialEq + core::fmt::Debug>(
+    codes: &[T], value: T, what: &str
+) -> Result<u8, ImageErrors> {
+    codes
+        .iter()
+        .position(|x| *x == value)
+        .map(|x| x as u8)
+        .ok_or_else(|| {
+            ImageErrors::GenericString(format!("The {what} {value:?} has no zune raw code"))
+        })
+}
+
+/// Return the value with `code`, its position in `codes`
+fn from_code<T: Copy>(codes: &[T], code: u8, what: &str) -> Result<T, ImageErrors> {
+    codes
+        .get(usize::from(code))
+        .copied()
+        .ok_or_else(|| unknown_code(code, what))
+}
+
+fn unknown_code(code: u8, what: &str) -> ImageErrors {
+    ImageErrors::ImageDecodeErrors(format!("Unknown zune raw {what} code {code}"))
+}
+
+fn truncated(_: &'static str) -> ImageErrors {
+    ImageErrors::ImageDecodeErrors("Truncated zune raw file".to_string())
+}
+
+fn read_u8(stream: &mut ZByteReader<&[u8]>) -> Result<u8, ImageErrors> {
+    stream.get_u8_err().map_err(truncated)
+}
+
+fn to_u32(value: usize) -> Result<u32, ImageErrors> {
+    u32::try_from(value).map_err(|_| ImageErrors::GenericStr("Too many items for zune raw"))
+}
+
+fn to_usize(value: u64) -> Result<usize, ImageErrors> {
+    usize::try_from(value).map_err(|_| {
+        ImageErrors::ImageDecodeErrors(format!("Value {value} does not fit in this platform"))
+    })
+}
+
+fn write_blob(out: &mut Vec<u8>, blob: Option<&[u8]>) -> Result<(), ImageErrors> {
+    match blob {
+        Some(blob) => {
+            out.push(1);
+            out.extend_from_slice(&to_u32(blob.len())?.to_le_bytes());
+            out.extend_from_slice(blob);
+        }
+        None => out.push(0)
+    }
+    Ok(())
+}
+
+fn read_blob(stream: &mut ZByteReader<&[u8]>) -> Result<Option<Vec<u8>>, ImageErrors> {
+    if read_u8(stream)? == 0 {
+        return Ok(None);
+    }
+    let length = stream.get_u32_le_err().map_err(truncated)? as usize;
+
+    stream
+        .get(length)
+        .map(|x| Some(x.to_vec()))
+        .map_err(truncated)
+}
+
+fn read_string(stream: &mut ZByteReader<&[u8]>) -> Result<String, ImageErrors> {
+    let bytes = read_blob(stream)?.unwrap_or_default();
+
+    String::from_utf8(bytes)
+        .map_err(|_| ImageErrors::ImageDecodeErrors("Text chunk is not UTF-8".to_string()))
+}
+
+/// Exif of the image as a TIFF structure
+#[cfg(feature = "metadata")]
+fn raw_exif(metadata: &ImageMetadata) -> Option<Vec<u8>> {
+    use exif::experimental::Writer;
+
+    let fields = metadata.exif.as_ref()?;
+    let mut writer = Writer::new();
+    let mut buf = std::io::Cursor::new(Vec::new());
+
+    for field in fields {
+        writer.push_field(field);
+    }
+    match writer.write(&mut buf, false) {
+        Ok(()) => Some(buf.into_inner()),
+        Err(err) => {
+            zune_core::log::warn!("Writing exif failed {:?}", err);
+            None
+        }
+    }
+}
+
+#[cfg(not(feature = "metadata"))]
+fn raw_exif(_: &ImageMetadata) -> Option<Vec<u8>> {
+    None
+}
+
+fn plane_to_le_bytes(channel: &Channel, bit_type: BitType) -> Result<Vec<u8>, ImageErrors> {
+    Ok(match bit_type {
+        BitType::U8 => channel.reinterpret_as::<u8>()?.to_vec(),
+        BitType::U16 => channel
+            .reinterpret_as::<u16>()?
+            .iter()
+            .flat_map(|x| x.to_le_bytes())
+            .collect(),
+        BitType::F32 => channel
+            .reinterpret_as::<f32>()?
+            .iter()
+            .flat_map(|x| x.to_le_bytes())
+            .collect(),
+        d => return Err(ImageErrors::ImageOperationNotImplemented("zune raw", d))
+    })
+}
+
+fn plane_from_le_bytes(plane: &[u8], bit_type: BitType) -> Result<Channel, ImageErrors> {
+    let mut channel = Channel::new_with_bit_type(plane.len(), bit_type);
+
+    match bit_type {
+        BitType::U8 => channel.reinterpret_as_mut::<u8>()?.copy_from_slice(plane),
+        BitType::U16 => {
+            let samples = plane.chunks_exact(size_of::<u16>());
+
+            for (out, sample) in channel.reinterpret_as_mut::<u16>()?.iter_mut().zip(samples) {
+                *out = u16::from_le_bytes([sample[0], sample[1]]);
+            }
+        }
+        BitType::F32 => {
+            let samples = plane.chunks_exact(size_of::<f32>());
+
+            for (out, sample) in channel.reinterpret_as_mut::<f32>()?.iter_mut().zip(samples) {
+                *out = f32::from_le_bytes([sample[0], sample[1], sample[2], sample[3]]);
+            }
+        }
+        d => return Err(ImageErrors::ImageOperationNotImplemented("zune raw", d))
+    }
+    Ok(channel)
+}
+
+#[cfg(feature = "zune-raw-zlib")]
+fn compress_plane(plane: &[u8]) -> Vec<u8> {
+    use zune_inflate::{DeflateEncodeOptions, DeflateEncoder, DeflateEncodingStrategy};
+
+    // caching favours speed over size
+    let options = DeflateEncodeOptions::new(DeflateEncodingStrategy::Fast);
+
+    DeflateEncoder::new_with_options(plane, options).encode_zlib()
+}
+
+#[cfg(not(feature = "zune-raw-zlib"))]
+fn compress_plane(_: &[u8]) -> Vec<u8> {
+    unreachable!("compression is rejected without the zune-raw-zlib feature")
+}
+
+#[cfg(feature = "zune-raw-zlib")]
+fn decompress_plane(stored: &[u8], plane_size: usize) -> Result<Vec<u8>, ImageErrors> {
+    use zune_inflate::{DeflateDecoder, DeflateOptions};
+
+    let options = DeflateOptions::default()
+        .set_limit(plane_size)
+        .set_size_hint(plane_size);
+
+    DeflateDecoder::new_with_options(stored, options)
+        .decode_zlib()
+        .map_err(|err| ImageErrors::ImageDecodeErrors(format!("{err:?}")))
+}
+
+#[cfg(not(feature = "zune-raw-zlib"))]
+fn decompress_plane(_: &[u8], _: usize) -> Result<Vec<u8>, ImageErrors> {
+    Err(ImageErrors::GenericStr(
+        "Reading compressed zune raw planes requires the `zune-raw-zlib` feature"
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use zune_core::bit_depth::BitDepth;
+    use zune_core::colorspace::{ColorCharacteristics, ColorSpace};
+
+    use crate::codecs::ImageFormat;
+    use crate::frame::Frame;
+    use crate::image::Image;
+    use crate::metadata::{AlphaState, PhysicalDimensions};
+    use crate::testing::synthetic_image;
+
+    #[test]
+    fn test_round_trip() {
+        for depth in [BitDepth::Eight, BitDepth::Sixteen, BitDepth::Float32] {
+            let mut image = synthetic_image(ColorSpace::RGBA, depth, 13, 7);
+            let metadata = image.metadata_mut();
+
+            metadata.set_alpha(AlphaState::PreMultiplied);
+            metadata.set_color_trc(ColorCharacteristics::Linear);
+            metadata.set_default_gamma(1.8);
+            metadata.set_icc_chunk(vec![1, 2, 3]);
+            metadata.add_text_chunk("key".to_string(), "value".to_string());
+            metadata.set_physical_dimensions(PhysicalDimensions::from_dpi(300.0, 150.0));
+            metadata.format = Some(ImageFormat::PNG);
+
+            let raw = image.to_zune_raw(false).unwrap();
+            let loaded = Image::from_zune_raw(&raw).unwrap();
+
+            assert_eq!(loaded.eq_exact(&image), Ok(()));
+
+            let (expected, found) = (image.metadata(), loaded.metadata());
+            assert_eq!(found.alpha, expected.alpha);
+            assert_eq!(found.color_trc, expected.color_trc);
+            assert_eq!(found.default_gamma, expected.default_gamma);
+            assert_eq!(found.icc_chunk, expected.icc_chunk);
+            assert_eq!(found.text_chunks, expected.text_chunks);
+            assert_eq!(found.physical_dimensions, expected.physical_dimensions);
+            assert_eq!(found.format, expected.format);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "zune-raw-zlib")]
+    fn test_compressed_frames() {
+        let frame = |value| Frame::from_u8(&[value; 3 * 16 * 16], ColorSpace::RGB, 2, 3);
+        let image = Image::new_frames(
+            vec![frame(10), frame(200)],
+            BitDepth::Eight,
+            16,
+            16,
+            ColorSpace::RGB
+        );
+
+        let raw = image.to_zune_raw(true).unwrap();
+        let loaded = Image::from_zune_raw(&raw).unwrap();
+
+        assert!(raw.len() < image.to_zune_raw(false).unwrap().len());
+        assert_eq!(loaded.eq_exact(&image), Ok(()));
+        assert_eq!(loaded.frames_ref()[1].numerator, 2);
+        assert_eq!(loaded.frames_ref()[1].denominator, 3);
+    }
+
+    #[test]
+    fn test_corrupt_input() {
+        let image = synthetic_image(ColorSpace::Luma, BitDepth::Sixteen, 5, 5);
+        let raw = image.to_zune_raw(false).unwrap();
+
+        for length in [0, 4, 20, raw.len() - 1] {
+            assert!(Image::from_zune_raw(&raw[..length]).is_err());
+        }
+        let mut bad_depth = raw.clone();
+        bad_depth[12] = 200;
+        assert!(Image::from_zune_raw(&bad_depth).is_err());
+
+        let mut bad_magic = raw;
+        bad_magic[0] = b'X';
+        assert!(Image::from_zune_raw(&bad_magic).is_err());
+    }
+}
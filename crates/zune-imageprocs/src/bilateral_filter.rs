@@ -11,6 +11,8 @@
 //!  A description can be found [here](https://homepages.inf.ed.ac.uk/rbf/CVonline/LOCAL_COPIES/MANDUCHI1/Bilateral_Filtering.html)
 //!
 use zune_core::bit_depth::BitType;
+#[cfg(feature = "threads")]
+use zune_core::threads::ThreadPool;
 use zune_image::channel::Channel;
 use zune_image::errors::ImageErrors;
 use zune_image::image::Image;
@@ -91,45 +93,30 @@ impl OperationsTrait for BilateralFilter {
 
         #[cfg(feature = "threads")]
         {
-            std::thread::scope(|s| {
-                let mut t_results = vec![];
-                for channel in image.channels_mut(true) {
-                    let result = s.spawn(|| {
-                        let mut new_channel =
-                            Channel::new_with_bit_type(channel.len(), depth.bit_type());
-                        match depth.bit_type() {
-                            BitType::U8 => bilateral_filter_int::<u8>(
-                                channel.reinterpret_as()?,
-                                new_channel.reinterpret_as_mut()?,
-                                w,
-                                h,
-                                &coeffs
-                            ),
-                            BitType::U16 => bilateral_filter_int::<u16>(
-                                channel.reinterpret_as()?,
-                                new_channel.reinterpret_as_mut()?,
-                                w,
-                                h,
-                                &coeffs
-                            ),
+            ThreadPool::new().try_for_each(image.channels_mut(true), |channel| {
+                let mut new_channel = Channel::new_with_bit_type(channel.len(), depth.bit_type());
+                match depth.bit_type() {
+                    BitType::U8 => bilateral_filter_int::<u8>(
+                        channel.reinterpret_as()?,
+                        new_channel.reinterpret_as_mut()?,
+                        w,
+                        h,
+                        &coeffs
+                    ),
+                    BitType::U16 => bilateral_filter_int::<u16>(
+                        channel.reinterpret_as()?,
+                        new_channel.reinterpret_as_mut()?,
+                        w,
+                        h,
+                        &coeffs
+                    ),
 
-                            d => {
-                                return Err(ImageErrors::ImageOperationNotImplemented(
-                                    self.name(),
-                                    d
-                                ));
-                            }
-                        }
-                        *channel = new_channel;
-                        Ok(())
-                    });
-                    t_results.push(result);
+                    d => {
+                        return Err(ImageErrors::ImageOperationNotImplemented(self.name(), d));
+                    }
                 }
-
-                t_results
-                    .into_iter()
-                    .map(|x| x.join().unwrap())
-                    .collect::<Result<Vec<()>, ImageErrors>>()
+                *channel = new_channel;
+                Ok(())
             })?;
         }
 
@@ -15,13 +15,19 @@ use std::f32;
 
 use zune_core::bit_depth::BitType;
 use zune_core::log::{trace, warn};
+#[cfg(feature = "threads")]
+use zune_core::threads::ThreadPool;
+use zune_image::cancel;
+#[cfg(feature = "threads")]
+use zune_image::cancel::CancellationToken;
 use zune_image::errors::ImageErrors;
 use zune_image::image::Image;
-use zune_image::traits::OperationsTrait;
+use zune_image::traits::{LightSpace, OperationsTrait, UnsignedSample};
 
 use crate::mathops::{compute_mod_u32, fastdiv_u32};
 use crate::traits::NumOps;
 use crate::transpose;
+use crate::utils::match_unsigned;
 
 /// Perform a box blur
 ///
@@ -60,62 +66,44 @@ impl OperationsTrait for BoxBlur {
         #[cfg(feature = "threads")]
         {
             trace!("Running box blur in multithreaded mode");
-            std::thread::scope(|s| {
-                let mut errors = vec![];
-                // blur each channel on a separate thread
-                for channel in image.channels_mut(false) {
-                    let result = s.spawn(|| match depth.bit_type() {
-                        BitType::U16 => {
-                            let mut scratch_space = vec![0; width * height];
-                            let data = channel.reinterpret_as_mut::<u16>()?;
-                            box_blur_u16(data, &mut scratch_space, width, height, self.radius);
-                            Ok(())
-                        }
-                        BitType::U8 => {
-                            let mut scratch_space = vec![0; width * height];
-                            let data = channel.reinterpret_as_mut::<u8>()?;
-                            box_blur_u8(data, &mut scratch_space, width, height, self.radius);
-                            Ok(())
-                        }
-
-                        BitType::F32 => {
-                            let mut scratch_space = vec![0.0; width * height];
-                            let data = channel.reinterpret_as_mut::<f32>()?;
-                            box_blur_f32(data, &mut scratch_space, width, height, self.radius);
-                            Ok(())
-                        }
-                        d => return Err(ImageErrors::ImageOperationNotImplemented("box_blur", d))
-                    });
-                    errors.push(result);
-                }
-                errors
-                    .into_iter()
-                    .map(|x| x.join().unwrap())
-                    .collect::<Result<Vec<()>, ImageErrors>>()
+            // workers don't see the cancellation token of this thread
+            let token = cancel::current();
+
+            ThreadPool::new().try_for_each(image.channels_mut(false), |channel| {
+                let _scope = token.as_ref().map(CancellationToken::enter);
+
+                match_unsigned!(
+                    depth.bit_type(),
+                    T => {
+                        let mut scratch_space = vec![T::default(); width * height];
+                        let data = channel.reinterpret_as_mut::<T>()?;
+                        box_blur_unsigned(data, &mut scratch_space, width, height, self.radius);
+                        Ok(())
+                    },
+                    BitType::F32 => {
+                        let mut scratch_space = vec![0.0; width * height];
+                        let data = channel.reinterpret_as_mut::<f32>()?;
+                        box_blur_f32(data, &mut scratch_space, width, height, self.radius);
+                        Ok(())
+                    }
+                    d => return Err(ImageErrors::ImageOperationNotImplemented("box_blur", d))
+                )
             })?;
         }
         #[cfg(not(feature = "threads"))]
         {
             trace!("Running box blur in single threaded mode");
 
-            match depth.bit_type() {
-                BitType::U16 => {
-                    let mut scratch_space = vec![0; width * height];
+            match_unsigned!(
+                depth.bit_type(),
+                T => {
+                    let mut scratch_space = vec![T::default(); width * height];
 
                     for channel in image.get_channels_mut(false) {
-                        let data = channel.reinterpret_as_mut::<u16>()?;
-                        box_blur_u16(data, &mut scratch_space, width, height, self.radius);
+                        let data = channel.reinterpret_as_mut::<T>()?;
+                        box_blur_unsigned(data, &mut scratch_space, width, height, self.radius);
                     }
-                }
-                BitType::U8 => {
-                    let mut scratch_space = vec![0; width * height];
-
-                    for channel in image.get_channels_mut(false) {
-                        let data = channel.reinterpret_as_mut::<u8>()?;
-                        box_blur_u8(data, &mut scratch_space, width, height, self.radius);
-                    }
-                }
-
+                },
                 BitType::F32 => {
                     let mut scratch_space = vec![0.0; width * height];
 
@@ -125,49 +113,60 @@ impl OperationsTrait for BoxBlur {
                     }
                 }
                 d => return Err(ImageErrors::ImageOperationNotImplemented("box_blur", d))
-            }
+            );
         }
 
-        Ok(())
+        cancel::check()
     }
     fn supported_types(&self) -> &'static [BitType] {
         &[BitType::U8, BitType::U16, BitType::F32]
     }
+
+    fn light_space(&self) -> LightSpace {
+        LightSpace::Linear
+    }
 }
 
-pub fn box_blur_u16(
-    in_out_image: &mut [u16], scratch_space: &mut [u16], width: usize, height: usize,
-    mut radius: usize
-) {
+/// Blur a single channel of unsigned samples
+///
+/// # Arguments
+/// - in_out_image: A single image channel, blurred samples are stored in the same buffer
+/// - scratch_space: Buffer used to store intermediate samples, must have the same length
+///   as `in_out_image`
+/// - width,height: Dimensions of the image
+/// - radius: The radius of the blur, even radii are bumped to the next odd one
+pub fn box_blur_unsigned<T>(
+    in_out_image: &mut [T], scratch_space: &mut [T], width: usize, height: usize, mut radius: usize
+) where
+    T: UnsignedSample + NumOps<T>,
+    u32: From<T>
+{
     if width == 0 || radius <= 1 {
         warn!("Box blur with radius less than or equal to 1 does nothing");
         return;
     }
     if (radius % 2) == 0 {
+        // evn radius are annoying, generates wrong values, just bump it to the next odd one
         radius += 1;
     }
     box_blur_inner(in_out_image, scratch_space, width, radius);
-    transpose::transpose_u16(scratch_space, in_out_image, width, height);
+    transpose::transpose_unsigned(scratch_space, in_out_image, width, height);
     box_blur_inner(in_out_image, scratch_space, height, radius);
-    transpose::transpose_u16(scratch_space, in_out_image, height, width);
+    transpose::transpose_unsigned(scratch_space, in_out_image, height, width);
 }
 
+#[deprecated(since = "0.4.13", note = "use `box_blur_unsigned`")]
+pub fn box_blur_u16(
+    in_out_image: &mut [u16], scratch_space: &mut [u16], width: usize, height: usize, radius: usize
+) {
+    box_blur_unsigned(in_out_image, scratch_space, width, height, radius);
+}
+
+#[deprecated(since = "0.4.13", note = "use `box_blur_unsigned`")]
 pub fn box_blur_u8(
-    in_out_image: &mut [u8], scratch_space: &mut [u8], width: usize, height: usize,
-    mut radius: usize
+    in_out_image: &mut [u8], scratch_space: &mut [u8], width: usize, height: usize, radius: usize
 ) {
-    if width == 0 || radius <= 1 {
-        warn!("Box blur with radius less than or equal to 1 does nothing");
-        return;
-    }
-    if (radius % 2) == 0 {
-        // evn radius are annoying, generates wrong values, just bump it to the next odd one
-        radius += 1;
-    }
-    box_blur_inner(in_out_image, scratch_space, width, radius);
-    transpose::transpose_u8(scratch_space, in_out_image, width, height);
-    box_blur_inner(in_out_image, scratch_space, height, radius);
-    transpose::transpose_u8(scratch_space, in_out_image, height, width);
+    box_blur_unsigned(in_out_image, scratch_space, width, height, radius);
 }
 
 pub fn box_blur_f32(
@@ -230,6 +229,10 @@ where
         .chunks_exact(width)
         .zip(out_image.chunks_exact_mut(width))
     {
+        // stop early once the pipeline is cancelled, the operation reports it
+        if cancel::is_cancelled() {
+            return;
+        }
         let half_radius = (diameter + 1) / 2;
 
         let mut accumulator: u32 = stride_in[..half_radius].iter().map(|x| u32::from(*x)).sum();
@@ -316,6 +319,10 @@ pub(crate) fn box_blur_f32_inner(
         .chunks_exact(width)
         .zip(out_image.chunks_exact_mut(width))
     {
+        // see box_blur_inner
+        if cancel::is_cancelled() {
+            return;
+        }
         let half_radius = (diameter + 1) / 2;
 
         let mut accumulator: f32 = stride_in[..half_radius].iter().copied().sum();
@@ -370,7 +377,7 @@ pub(crate) fn box_blur_f32_inner(
 mod benchmarks {
     extern crate test;
 
-    use crate::box_blur::{box_blur_u16, box_blur_u8};
+    use crate::box_blur::box_blur_unsigned;
 
     #[bench]
     fn bench_box_blur_u16(b: &mut test::Bencher) {
@@ -378,11 +385,11 @@ mod benchmarks {
         let height = 800;
         let radius = 10;
         let dimensions = width * height;
-        let mut in_vec = vec![255; dimensions];
+        let mut in_vec = vec![255_u16; dimensions];
         let mut scratch_space = vec![0; dimensions];
 
         b.iter(|| {
-            box_blur_u16(&mut in_vec, &mut scratch_space, width, height, radius);
+            box_blur_unsigned(&mut in_vec, &mut scratch_space, width, height, radius);
         });
     }
 
@@ -392,11 +399,11 @@ mod benchmarks {
         let height = 800;
         let radius = 10;
         let dimensions = width * height;
-        let mut in_vec = vec![255; dimensions];
+        let mut in_vec = vec![255_u8; dimensions];
         let mut scratch_space = vec![0; dimensions];
 
         b.iter(|| {
-            box_blur_u8(&mut in_vec, &mut scratch_space, width, height, radius);
+            box_blur_unsigned(&mut in_vec, &mut scratch_space, width, height, radius);
         });
     }
 }
@@ -28,6 +28,7 @@ use zune_core::colorspace::ColorSpace;
 use zune_image::errors::ImageErrors;
 use zune_image::image::Image;
 use zune_image::traits::OperationsTrait;
+use zune_image::view::ImageViewMut;
 
 use crate::traits::NumOps;
 
@@ -99,6 +100,23 @@ impl OperationsTrait for Brighten {
         }
         Ok(())
     }
+
+    fn execute_view_impl(&self, view: &mut ImageViewMut) -> Result<(), ImageErrors> {
+        let max_val = view.depth().max_value();
+
+        match view.depth().bit_type() {
+            BitType::U8 => {
+                let max_val = u8::try_from(max_val.clamp(0, 255)).unwrap();
+                view.for_each_row_mut(true, |row| brighten(row, self.value, max_val))
+            }
+            BitType::U16 => view.for_each_row_mut(true, |row| brighten(row, self.value, max_val)),
+            BitType::F32 => view.for_each_row_mut(true, |row| {
+                brighten_f32(row, self.value, f32::from(max_val));
+            }),
+            d => Err(ImageErrors::ImageOperationNotImplemented(self.name(), d))
+        }
+    }
+
     fn supported_colorspaces(&self) -> &'static [ColorSpace] {
         &[
             ColorSpace::RGBA,
@@ -111,6 +129,43 @@ impl OperationsTrait for Brighten {
     fn supported_types(&self) -> &'static [BitType] {
         &[BitType::U8, BitType::U16, BitType::F32]
     }
+
+    fn interleaved_colorspace(&self, input: ColorSpace) -> Option<ColorSpace> {
+        Some(input)
+    }
+
+    fn execute_interleaved(
+        &self, pixels: &mut Vec<u8>, colorspace: ColorSpace
+    ) -> Result<(), ImageErrors> {
+        brighten_interleaved_u8(pixels, colorspace, self.value);
+        Ok(())
+    }
+}
+
+/// Brighten interleaved 8 bit pixels of `colorspace`, leaving alpha untouched
+///
+/// # Arguments
+///
+/// * `pixels`: Interleaved pixels, operates in place
+/// * `colorspace`: Colorspace of the pixels, used to find the alpha channel
+/// * `value`: Value to increase the channel values with, must be between -1 and 1, where 1 stands for maximum brightness
+///   and -1 for darkness
+#[allow(clippy::cast_possible_truncation)]
+pub fn brighten_interleaved_u8(pixels: &mut [u8], colorspace: ColorSpace, value: f32) {
+    // every possible sample through the planar kernel, so both agree
+    let mut lut: [u8; 256] = core::array::from_fn(|x| x as u8);
+    brighten(&mut lut, value, u8::MAX);
+
+    let components = colorspace.num_components();
+    let alpha = colorspace.alpha_position();
+
+    for pixel in pixels.chunks_exact_mut(components) {
+        for (position, sample) in pixel.iter_mut().enumerate() {
+            if Some(position) != alpha {
+                *sample = lut[usize::from(*sample)];
+            }
+        }
+    }
 }
 /// Brighten operation
 ///
@@ -0,0 +1,151 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! Shift red and blue channels relative to green
+//!
+//! Lenses bend light of different wavelengths by different amounts (chromatic aberration),
+//! which shows up as red and blue fringes at edges, shifting the red and blue channels back
+//! into alignment with green removes simple, uniform fringing.
+//!
+//! Shifts can be fractional, samples are bilinearly interpolated and pixels shifted
+//! in from outside the image replicate the image edge.
+use zune_core::bit_depth::BitType;
+use zune_core::colorspace::ColorSpace;
+use zune_image::errors::ImageErrors;
+use zune_image::image::Image;
+use zune_image::traits::OperationsTrait;
+
+use crate::warp::{warp, BorderMode};
+
+/// Shift the red and blue channels of an RGB image
+///
+/// # Alpha channel
+/// - Alpha channel is ignored
+///
+/// # Example
+/// Move red half a pixel to the left and blue half a pixel to the right
+/// ```
+/// use zune_core::colorspace::ColorSpace;
+/// use zune_image::image::Image;
+/// use zune_image::traits::OperationsTrait;
+/// use zune_imageprocs::channel_shift::ChannelShift;
+///
+/// let mut image = Image::fill(100_u8, ColorSpace::RGB, 100, 100);
+///
+/// ChannelShift::new(-0.5, 0.0, 0.5, 0.0)
+///     .execute(&mut image)
+///     .unwrap();
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub struct ChannelShift {
+    red:  (f32, f32),
+    blue: (f32, f32)
+}
+
+impl ChannelShift {
+    /// Create a new channel shift
+    ///
+    /// # Arguments
+    /// - dx_r: Pixels to move the red channel to the right
+    /// - dy_r: Pixels to move the red channel down
+    /// - dx_b: Pixels to move the blue channel to the right
+    /// - dy_b: Pixels to move the blue channel down
+    #[must_use]
+    pub fn new(dx_r: f32, dy_r: f32, dx_b: f32, dy_b: f32) -> ChannelShift {
+        ChannelShift {
+            red:  (dx_r, dy_r),
+            blue: (dx_b, dy_b)
+        }
+    }
+}
+
+impl OperationsTrait for ChannelShift {
+    fn name(&self) -> &'static str {
+        "Channel Shift"
+    }
+
+    fn execute_impl(&self, image: &mut Image) -> Result<(), ImageErrors> {
+        let colorspace = image.colorspace();
+        let dimensions = image.dimensions();
+        let depth = image.depth().bit_type();
+
+        let (red, blue) = match colorspace {
+            ColorSpace::RGB | ColorSpace::RGBA => (0, 2),
+            ColorSpace::BGR | ColorSpace::BGRA => (2, 0),
+            ColorSpace::ARGB => (1, 3),
+            _ => {
+                return Err(ImageErrors::UnsupportedColorspace(
+                    colorspace,
+                    self.name(),
+                    self.supported_colorspaces()
+                ))
+            }
+        };
+
+        for frame in image.frames_mut() {
+            let channels = frame.channels_mut(colorspace, false);
+
+            for (index, (dx, dy)) in [(red, self.red), (blue, self.blue)] {
+                if dx == 0.0 && dy == 0.0 {
+                    continue;
+                }
+                // sample each output pixel from where it was before the shift
+                let inverse = [
+                    [1.0, 0.0, -f64::from(dx)],
+                    [0.0, 1.0, -f64::from(dy)],
+                    [0.0, 0.0, 1.0]
+                ];
+                let source = channels[index].clone();
+                let channel = &mut channels[index];
+
+                match depth {
+                    BitType::U8 => warp::<u8>(
+                        source.reinterpret_as()?,
+                        channel.reinterpret_as_mut()?,
+                        dimensions,
+                        dimensions,
+                        &inverse,
+                        BorderMode::Replicate
+                    ),
+                    BitType::U16 => warp::<u16>(
+                        source.reinterpret_as()?,
+                        channel.reinterpret_as_mut()?,
+                        dimensions,
+                        dimensions,
+                        &inverse,
+                        BorderMode::Replicate
+                    ),
+                    BitType::F32 => warp::<f32>(
+                        source.reinterpret_as()?,
+                        channel.reinterpret_as_mut()?,
+                        dimensions,
+                        dimensions,
+                        &inverse,
+                        BorderMode::Replicate
+                    ),
+                    d => return Err(ImageErrors::ImageOperationNotImplemented(self.name(), d))
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn supported_colorspaces(&self) -> &'static [ColorSpace] {
+        &[
+            ColorSpace::RGB,
+            ColorSpace::RGBA,
+            ColorSpace::BGR,
+            ColorSpace::BGRA,
+            ColorSpace::ARGB
+        ]
+    }
+
+    fn supported_types(&self) -> &'static [BitType] {
+        &[BitType::U8, BitType::U16, BitType::F32]
+    }
+}
@@ -0,0 +1,249 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! Chroma keying (green and blue screen removal)
+//!
+//! Pixels close in color to a key color become transparent, the distance is measured
+//! on the chroma (CbCr) plane of BT.601 YCbCr, which ignores brightness so uneven lighting
+//! of the backdrop matters less than with a distance between RGB values.
+use zune_core::bit_depth::BitType;
+use zune_core::colorspace::ColorSpace;
+use zune_image::core_filters::colorspace::expand::AddAlpha;
+use zune_image::errors::ImageErrors;
+use zune_image::image::Image;
+use zune_image::traits::{LightSpace, OperationsTrait};
+
+/// Make pixels of a key color transparent
+///
+/// Alpha is computed from the chroma distance `d` between a pixel and the key color,
+/// pixels with `d <= tolerance` become fully transparent, pixels with
+/// `d >= tolerance + softness` are left opaque and alpha ramps linearly in between.
+/// Distances range from 0.0 to about 1.0 (e.g pure green to pure magenta).
+///
+/// RGB and BGR images gain an alpha channel, images which already have alpha
+/// keep it, multiplied by the keyed alpha.
+///
+/// # Example
+/// ```
+/// use zune_core::colorspace::ColorSpace;
+/// use zune_image::image::Image;
+/// use zune_image::traits::OperationsTrait;
+/// use zune_imageprocs::chroma_key::ChromaKey;
+///
+/// // a green screen pixel and a red pixel
+/// let mut image = Image::from_u8(&[0, 255, 0, 255, 0, 0], 2, 1, ColorSpace::RGB);
+///
+/// ChromaKey::new([0.0, 1.0, 0.0], 0.1, 0.1).execute(&mut image).unwrap();
+///
+/// assert_eq!(image.colorspace(), ColorSpace::RGBA);
+/// assert_eq!(image.flatten_to_u8()[0], [0, 255, 0, 0, 255, 0, 0, 255]);
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub struct ChromaKey {
+    color:          [f32; 3],
+    tolerance:      f32,
+    softness:       f32,
+    spill_suppress: bool
+}
+
+impl ChromaKey {
+    /// Create a new chroma key operation
+    ///
+    /// # Arguments
+    /// - color: Red, green and blue of the backdrop in `0.0..=1.0`
+    /// - tolerance: Chroma distance below which pixels become fully transparent
+    /// - softness: Width of the ramp from transparent to opaque after `tolerance`,
+    ///   0.0 gives a hard edge
+    #[must_use]
+    pub fn new(color: [f32; 3], tolerance: f32, softness: f32) -> ChromaKey {
+        ChromaKey {
+            color,
+            tolerance,
+            softness,
+            spill_suppress: false
+        }
+    }
+    /// Suppress color spill from the backdrop on pixels that are kept
+    ///
+    /// Light bouncing off a green screen tints the subject green, when set the
+    /// strongest channel of the key color (e.g green) is limited to the average of the
+    /// other two channels of every pixel that is not fully transparent.
+    ///
+    /// Default is false
+    #[must_use]
+    pub fn set_spill_suppression(mut self, yes: bool) -> Self {
+        self.spill_suppress = yes;
+        self
+    }
+}
+
+impl OperationsTrait for ChromaKey {
+    fn name(&self) -> &'static str {
+        "Chroma Key"
+    }
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn execute_impl(&self, image: &mut Image) -> Result<(), ImageErrors> {
+        if self.tolerance < 0.0 || self.softness < 0.0 {
+            return Err(ImageErrors::GenericString(format!(
+                "Chroma key tolerance ({}) and softness ({}) cannot be negative",
+                self.tolerance, self.softness
+            )));
+        }
+        if !image.colorspace().has_alpha() {
+            AddAlpha::default().execute(image)?;
+        }
+        let colorspace = image.colorspace();
+        let premultiplied = image.metadata().is_premultiplied_alpha();
+        let depth = image.depth().bit_type();
+
+        for frame in image.frames_mut() {
+            let [c0, c1, c2, c3] = frame.channels_mut(colorspace, false) else {
+                return Err(ImageErrors::GenericStr("Expected four channels"));
+            };
+            let [r, g, b, a] = match colorspace {
+                ColorSpace::RGBA => [c0, c1, c2, c3],
+                ColorSpace::BGRA => [c2, c1, c0, c3],
+                ColorSpace::ARGB => [c1, c2, c3, c0],
+                _ => {
+                    return Err(ImageErrors::UnsupportedColorspace(
+                        colorspace,
+                        self.name(),
+                        self.supported_colorspaces()
+                    ))
+                }
+            };
+            match depth {
+                BitType::U8 => self.key(
+                    [
+                        r.reinterpret_as_mut::<u8>()?,
+                        g.reinterpret_as_mut::<u8>()?,
+                        b.reinterpret_as_mut::<u8>()?,
+                        a.reinterpret_as_mut::<u8>()?
+                    ],
+                    255.0,
+                    premultiplied,
+                    |x| x.round() as u8
+                ),
+                BitType::U16 => self.key(
+                    [
+                        r.reinterpret_as_mut::<u16>()?,
+                        g.reinterpret_as_mut::<u16>()?,
+                        b.reinterpret_as_mut::<u16>()?,
+                        a.reinterpret_as_mut::<u16>()?
+                    ],
+                    65535.0,
+                    premultiplied,
+                    |x| x.round() as u16
+                ),
+                BitType::F32 => self.key(
+                    [
+                        r.reinterpret_as_mut::<f32>()?,
+                        g.reinterpret_as_mut::<f32>()?,
+                        b.reinterpret_as_mut::<f32>()?,
+                        a.reinterpret_as_mut::<f32>()?
+                    ],
+                    1.0,
+                    premultiplied,
+                    |x| x
+                ),
+                depth => {
+                    return Err(ImageErrors::ImageOperationNotImplemented(
+                        self.name(),
+                        depth
+                    ))
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn supported_colorspaces(&self) -> &'static [ColorSpace] {
+        &[
+            ColorSpace::RGB,
+            ColorSpace::RGBA,
+            ColorSpace::BGR,
+            ColorSpace::BGRA,
+            ColorSpace::ARGB
+        ]
+    }
+
+    fn supported_types(&self) -> &'static [BitType] {
+        &[BitType::U8, BitType::U16, BitType::F32]
+    }
+
+    fn light_space(&self) -> LightSpace {
+        LightSpace::Encoded
+    }
+
+    fn output_colorspace(&self, input: ColorSpace) -> ColorSpace {
+        match input {
+            ColorSpace::RGB => ColorSpace::RGBA,
+            ColorSpace::BGR => ColorSpace::BGRA,
+            _ => input
+        }
+    }
+}
+
+impl ChromaKey {
+    /// Key one frame, `planes` are red, green, blue and alpha in native units
+    ///
+    /// Integer conversions saturate, float results are not clamped so HDR values survive
+    fn key<T: Copy>(
+        &self, planes: [&mut [T]; 4], max: f32, premultiplied: bool, convert: impl Fn(f32) -> T
+    ) where
+        f32: From<T>
+    {
+        let [r, g, b, a] = planes;
+        let key = chroma(self.color);
+        // the channel spill is suppressed on, e.g green for a green screen
+        let dominant = (0..3)
+            .max_by(|x, y| self.color[*x].total_cmp(&self.color[*y]))
+            .unwrap_or(1);
+
+        for (((r, g), b), a) in r.iter_mut().zip(g).zip(b).zip(a) {
+            let alpha = f32::from(*a) / max;
+            let mut rgb = [*r, *g, *b].map(|x| f32::from(x) / max);
+
+            if premultiplied && alpha > 0.0 {
+                rgb = rgb.map(|x| x / alpha);
+            }
+            let (cb, cr) = chroma(rgb);
+            let distance = (cb - key.0).hypot(cr - key.1);
+
+            let keyed = if distance <= self.tolerance {
+                0.0
+            } else if self.softness <= 0.0 {
+                1.0
+            } else {
+                ((distance - self.tolerance) / self.softness).min(1.0)
+            };
+            if self.spill_suppress && keyed > 0.0 {
+                let others = (rgb[0] + rgb[1] + rgb[2] - rgb[dominant]) / 2.0;
+                rgb[dominant] = rgb[dominant].min(others);
+            }
+            let new_alpha = alpha * keyed;
+
+            if premultiplied {
+                rgb = rgb.map(|x| x * new_alpha);
+            }
+            *r = convert(rgb[0] * max);
+            *g = convert(rgb[1] * max);
+            *b = convert(rgb[2] * max);
+            *a = convert(new_alpha * max);
+        }
+    }
+}
+
+/// Return the BT.601 Cb and Cr of a color in `0.0..=1.0`, without offset
+fn chroma([r, g, b]: [f32; 3]) -> (f32, f32) {
+    let cb = -0.168_736 * r - 0.331_264 * g + 0.5 * b;
+    let cr = 0.5 * r - 0.418_688 * g - 0.081_312 * b;
+    (cb, cr)
+}
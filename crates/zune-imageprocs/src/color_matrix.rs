@@ -50,6 +50,7 @@
 
 use zune_core::bit_depth::BitType;
 use zune_core::colorspace::ColorSpace;
+use zune_image::core_filters::colorspace::CONVERTIBLE_COLORSPACES;
 use zune_image::errors::ImageErrors;
 use zune_image::image::Image;
 use zune_image::traits::OperationsTrait;
@@ -168,6 +169,11 @@ impl OperationsTrait for ColorMatrix {
     fn supported_types(&self) -> &'static [BitType] {
         &[BitType::U8, BitType::U16, BitType::F32]
     }
+
+    fn supported_colorspaces(&self) -> &'static [ColorSpace] {
+        // we convert to RGBA and back
+        &CONVERTIBLE_COLORSPACES
+    }
 }
 
 fn color_matrix_component<T: NumOps<T> + Copy>(
@@ -0,0 +1,449 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! Composite an overlay image onto an image, e.g for watermarks
+//!
+//! The overlay is placed relative to an edge or corner of the image given by a [`Gravity`],
+//! optionally repeated across the whole image, and drawn over the image with the
+//! Porter-Duff `over` operator using the overlay alpha scaled by a global opacity.
+//!
+//! ```text
+//!  north-west     north      north-east
+//!           ┌─────────────────┐
+//!     west  │      center     │  east
+//!           └─────────────────┘
+//!  south-west     south      south-east
+//! ```
+use zune_core::bit_depth::{BitDepth, BitType};
+use zune_core::colorspace::ColorSpace;
+use zune_image::core_filters::colorspace::expand::{AddAlpha, GrayToRgb};
+use zune_image::errors::ImageErrors;
+use zune_image::image::Image;
+use zune_image::traits::OperationsTrait;
+
+/// Where an overlay is placed on an image
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Gravity {
+    NorthWest,
+    North,
+    NorthEast,
+    West,
+    Center,
+    East,
+    SouthWest,
+    South,
+    SouthEast
+}
+
+impl Gravity {
+    /// Parse a gravity from its lowercase name, e.g `south-east`
+    pub fn from_string_result(input: &str) -> Result<Self, String> {
+        match input {
+            "north-west" => Ok(Self::NorthWest),
+            "north" => Ok(Self::North),
+            "north-east" => Ok(Self::NorthEast),
+            "west" => Ok(Self::West),
+            "center" => Ok(Self::Center),
+            "east" => Ok(Self::East),
+            "south-west" => Ok(Self::SouthWest),
+            "south" => Ok(Self::South),
+            "south-east" => Ok(Self::SouthEast),
+            _ => Err(format!(
+                "Unknown gravity {input}, expected one of north-west, north, north-east, west, center, east, south-west, south or south-east"
+            ))
+        }
+    }
+
+    /// Offset of an overlay of `overlay` pixels inside `length` pixels along one axis,
+    /// `start` and `end` tell whether this gravity sticks to the start or end of the axis
+    #[allow(clippy::cast_possible_wrap)]
+    fn offset(start: bool, end: bool, length: usize, overlay: usize, margin: usize) -> isize {
+        let (length, overlay, margin) = (length as isize, overlay as isize, margin as isize);
+
+        match (start, end) {
+            (true, _) => margin,
+            (_, true) => length - overlay - margin,
+            _ => (length - overlay) / 2
+        }
+    }
+
+    pub(crate) fn position(
+        self, (width, height): (usize, usize), (o_width, o_height): (usize, usize),
+        (m_x, m_y): (usize, usize)
+    ) -> (isize, isize) {
+        use Gravity::{East, North, NorthEast, NorthWest, South, SouthEast, SouthWest, West};
+
+        let west = matches!(self, NorthWest | West | SouthWest);
+        let east = matches!(self, NorthEast | East | SouthEast);
+        let north = matches!(self, NorthWest | North | NorthEast);
+        let south = matches!(self, SouthWest | South | SouthEast);
+
+        (
+            Gravity::offset(west, east, width, o_width, m_x),
+            Gravity::offset(north, south, height, o_height, m_y)
+        )
+    }
+}
+
+/// Draw an overlay image over an image
+///
+/// The overlay is converted to the colorspace and depth of the image it is drawn on,
+/// only its first frame is used and it is drawn on every frame of the image.
+/// Parts of the overlay outside the image are clipped.
+///
+/// Images are expected to have straight (not premultiplied) alpha.
+///
+/// # Example
+/// Tile a half transparent logo across an image
+/// ```
+/// use zune_core::colorspace::ColorSpace;
+/// use zune_image::image::Image;
+/// use zune_image::traits::OperationsTrait;
+/// use zune_imageprocs::composite::{Composite, Gravity};
+///
+/// let logo = Image::fill(255_u8, ColorSpace::RGB, 8, 4);
+/// let mut image = Image::fill(0_u8, ColorSpace::RGB, 100, 100);
+///
+/// Composite::new(logo)
+///     .set_gravity(Gravity::Center)
+///     .set_opacity(0.5)
+///     .set_tiled(true)
+///     .set_spacing(16, 16)
+///     .execute(&mut image)
+///     .unwrap();
+/// ```
+pub struct Composite {
+    overlay: Image,
+    gravity: Gravity,
+    margin:  (usize, usize),
+    opacity: f32,
+    tiled:   bool,
+    spacing: (usize, usize)
+}
+
+impl Composite {
+    /// Create a new composite operation drawing `overlay` at the center
+    /// of the image, fully opaque
+    #[must_use]
+    pub fn new(overlay: Image) -> Composite {
+        Composite {
+            overlay,
+            gravity: Gravity::Center,
+            margin: (0, 0),
+            opacity: 1.0,
+            tiled: false,
+            spacing: (0, 0)
+        }
+    }
+    /// Set where the overlay is placed
+    ///
+    /// When tiling this is where one of the tiles is placed,
+    /// other tiles are laid out from it.
+    #[must_use]
+    pub fn set_gravity(mut self, gravity: Gravity) -> Self {
+        self.gravity = gravity;
+        self
+    }
+    /// Set the distance in pixels between the overlay and the edges it sticks to
+    ///
+    /// Margins are ignored on axes where the overlay is centered
+    #[must_use]
+    pub fn set_margin(mut self, x: usize, y: usize) -> Self {
+        self.margin = (x, y);
+        self
+    }
+    /// Set the opacity of the overlay from 0.0 (invisible) to 1.0,
+    /// it multiplies the overlay alpha
+    #[must_use]
+    pub fn set_opacity(mut self, opacity: f32) -> Self {
+        self.opacity = opacity;
+        self
+    }
+    /// Repeat the overlay across the whole image
+    #[must_use]
+    pub fn set_tiled(mut self, yes: bool) -> Self {
+        self.tiled = yes;
+        self
+    }
+    /// Set the horizontal and vertical gap in pixels between tiles
+    #[must_use]
+    pub fn set_spacing(mut self, x: usize, y: usize) -> Self {
+        self.spacing = (x, y);
+        self
+    }
+
+    /// Return the top left corner of every copy of the overlay that touches the image
+    #[allow(clippy::cast_possible_wrap, clippy::cast_sign_loss)]
+    fn placements(
+        &self, dimensions: (usize, usize), overlay: (usize, usize)
+    ) -> Vec<(isize, isize)> {
+        let (x, y) = self.gravity.position(dimensions, overlay, self.margin);
+
+        if !self.tiled {
+            return vec![(x, y)];
+        }
+        let repeat = |start: isize, length: usize, overlay: usize, spacing: usize| {
+            let step = (overlay + spacing) as isize;
+            // first copy that ends inside the image
+            let first = start + ((-(overlay as isize) - start).div_euclid(step) + 1) * step;
+
+            (first..length as isize).step_by(step as usize)
+        };
+        repeat(y, dimensions.1, overlay.1, self.spacing.1)
+            .flat_map(|y| repeat(x, dimensions.0, overlay.0, self.spacing.0).map(move |x| (x, y)))
+            .collect()
+    }
+
+    /// Convert the overlay to the layout of an image in `colorspace`, with alpha,
+    /// and normalized to `0.0..=1.0`
+    fn prepare_overlay(&self, colorspace: ColorSpace) -> Result<Image, ImageErrors> {
+        let mut overlay = self.overlay.clone();
+
+        let target = match colorspace {
+            ColorSpace::Luma | ColorSpace::LumaA => ColorSpace::LumaA,
+            ColorSpace::BGR | ColorSpace::BGRA => ColorSpace::BGRA,
+            _ => ColorSpace::RGBA
+        };
+        if overlay.colorspace().is_grayscale() && !target.is_grayscale() {
+            GrayToRgb::new().execute(&mut overlay)?;
+        }
+        if !overlay.colorspace().has_alpha() {
+            AddAlpha::default().execute(&mut overlay)?;
+        }
+        if overlay.colorspace() != target {
+            overlay.convert_color(target)?;
+        }
+        overlay.convert_depth(BitDepth::Float32)?;
+
+        Ok(overlay)
+    }
+}
+
+impl OperationsTrait for Composite {
+    fn name(&self) -> &'static str {
+        "Composite"
+    }
+
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        clippy::too_many_lines
+    )]
+    fn execute_impl(&self, image: &mut Image) -> Result<(), ImageErrors> {
+        if !(0.0..=1.0).contains(&self.opacity) {
+            return Err(ImageErrors::GenericString(format!(
+                "Opacity {} is outside the range 0.0..=1.0",
+                self.opacity
+            )));
+        }
+        let colorspace = image.colorspace();
+        let overlay = self.prepare_overlay(colorspace)?;
+
+        let dimensions = image.dimensions();
+        let overlay_dimensions = overlay.dimensions();
+
+        if overlay_dimensions.0 == 0 || overlay_dimensions.1 == 0 {
+            return Ok(());
+        }
+        let placements = self.placements(dimensions, overlay_dimensions);
+
+        let overlay_frame = overlay
+            .frames_ref()
+            .first()
+            .ok_or(ImageErrors::NoImageBuffer)?;
+        let overlay_channels = overlay_frame.channels_ref(overlay.colorspace(), false);
+        let (overlay_alpha, overlay_colors) = overlay_channels
+            .split_last()
+            .ok_or(ImageErrors::GenericStr("Overlay has no channels"))?;
+
+        let overlay_alpha: Vec<f32> = overlay_alpha
+            .reinterpret_as::<f32>()?
+            .iter()
+            .map(|x| x.clamp(0.0, 1.0) * self.opacity)
+            .collect();
+
+        let layout = Layout {
+            dimensions,
+            overlay_dimensions,
+            placements: &placements
+        };
+        let depth = image.depth().bit_type();
+        let num_colors = colorspace.num_components() - usize::from(colorspace.has_alpha());
+
+        for frame in image.frames_mut() {
+            let (colors, alpha) = frame
+                .channels_mut(colorspace, false)
+                .split_at_mut(num_colors);
+            let alpha = alpha.first_mut();
+
+            for (channel, overlay) in colors.iter_mut().zip(overlay_colors) {
+                let overlay = overlay.reinterpret_as::<f32>()?;
+
+                match depth {
+                    BitType::U8 => composite_channel(
+                        channel.reinterpret_as_mut::<u8>()?,
+                        alpha
+                            .as_deref()
+                            .map(|x| x.reinterpret_as::<u8>())
+                            .transpose()?,
+                        overlay,
+                        &overlay_alpha,
+                        &layout,
+                        255.0,
+                        |x| x.round() as u8
+                    ),
+                    BitType::U16 => composite_channel(
+                        channel.reinterpret_as_mut::<u16>()?,
+                        alpha
+                            .as_deref()
+                            .map(|x| x.reinterpret_as::<u16>())
+                            .transpose()?,
+                        overlay,
+                        &overlay_alpha,
+                        &layout,
+                        65535.0,
+                        |x| x.round() as u16
+                    ),
+                    BitType::F32 => composite_channel(
+                        channel.reinterpret_as_mut::<f32>()?,
+                        alpha
+                            .as_deref()
+                            .map(|x| x.reinterpret_as::<f32>())
+                            .transpose()?,
+                        overlay,
+                        &overlay_alpha,
+                        &layout,
+                        1.0,
+                        |x| x
+                    ),
+                    d => return Err(ImageErrors::ImageOperationNotImplemented(self.name(), d))
+                }
+            }
+            // alpha is updated last since colors are weighted by the previous alpha
+            if let Some(alpha) = alpha {
+                match depth {
+                    BitType::U8 => composite_alpha(
+                        alpha.reinterpret_as_mut::<u8>()?,
+                        &overlay_alpha,
+                        &layout,
+                        255.0,
+                        |x| x.round() as u8
+                    ),
+                    BitType::U16 => composite_alpha(
+                        alpha.reinterpret_as_mut::<u16>()?,
+                        &overlay_alpha,
+                        &layout,
+                        65535.0,
+                        |x| x.round() as u16
+                    ),
+                    BitType::F32 => {
+                        composite_alpha(
+                            alpha.reinterpret_as_mut::<f32>()?,
+                            &overlay_alpha,
+                            &layout,
+                            1.0,
+                            |x| x
+                        );
+                    }
+                    d => return Err(ImageErrors::ImageOperationNotImplemented(self.name(), d))
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn supported_colorspaces(&self) -> &'static [ColorSpace] {
+        &[
+            ColorSpace::Luma,
+            ColorSpace::LumaA,
+            ColorSpace::RGB,
+            ColorSpace::RGBA,
+            ColorSpace::BGR,
+            ColorSpace::BGRA
+        ]
+    }
+
+    fn supported_types(&self) -> &'static [BitType] {
+        &[BitType::U8, BitType::U16, BitType::F32]
+    }
+}
+
+/// Where copies of the overlay land on the image
+struct Layout<'a> {
+    dimensions:         (usize, usize),
+    overlay_dimensions: (usize, usize),
+    placements:         &'a [(isize, isize)]
+}
+
+impl Layout<'_> {
+    /// Call `func` with the image and overlay index of every pixel covered by the overlay
+    #[allow(clippy::cast_possible_wrap, clippy::cast_sign_loss)]
+    fn for_each_pixel(&self, mut func: impl FnMut(usize, usize)) {
+        let (width, height) = self.dimensions;
+        let (o_width, o_height) = self.overlay_dimensions;
+
+        for &(x, y) in self.placements {
+            // clip the overlay to the image
+            let x_start = x.max(0) as usize;
+            let y_start = y.max(0) as usize;
+            let x_end = (x + o_width as isize).clamp(0, width as isize) as usize;
+            let y_end = (y + o_height as isize).clamp(0, height as isize) as usize;
+
+            for i_y in y_start..y_end {
+                let o_y = (i_y as isize - y) as usize;
+
+                for i_x in x_start..x_end {
+                    let o_x = (i_x as isize - x) as usize;
+
+                    func(i_y * width + i_x, o_y * o_width + o_x);
+                }
+            }
+        }
+    }
+}
+
+/// Draw one color channel of the overlay over `channel`, all values in native units
+///
+/// Integer conversions saturate, float results are not clamped so HDR values survive
+fn composite_channel<T: Copy>(
+    channel: &mut [T], alpha: Option<&[T]>, overlay: &[f32], overlay_alpha: &[f32],
+    layout: &Layout, max: f32, convert: impl Fn(f32) -> T
+) where
+    f32: From<T>
+{
+    layout.for_each_pixel(|i, o| {
+        let src_alpha = overlay_alpha[o];
+
+        if src_alpha <= 0.0 {
+            return;
+        }
+        let dst_alpha = alpha.map_or(1.0, |a| f32::from(a[i]) / max);
+        let dst_weight = dst_alpha * (1.0 - src_alpha);
+        let out_alpha = src_alpha + dst_weight;
+
+        if out_alpha > 0.0 {
+            let color = overlay[o] * max * src_alpha + f32::from(channel[i]) * dst_weight;
+            channel[i] = convert(color / out_alpha);
+        }
+    });
+}
+
+/// Combine the overlay alpha with the alpha channel of the image
+fn composite_alpha<T: Copy>(
+    alpha: &mut [T], overlay_alpha: &[f32], layout: &Layout, max: f32, convert: impl Fn(f32) -> T
+) where
+    f32: From<T>
+{
+    layout.for_each_pixel(|i, o| {
+        let src_alpha = overlay_alpha[o];
+        let dst_alpha = f32::from(alpha[i]) / max;
+
+        alpha[i] = convert((src_alpha + dst_alpha * (1.0 - src_alpha)) * max);
+    });
+}
@@ -0,0 +1,410 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! Concatenate images side by side or on top of each other
+//!
+//! [`HStack`] appends images to the right of an image and [`VStack`] below it, e.g to
+//! build "before | after" comparison strips. Images whose height (for `HStack`) or width
+//! (for `VStack`) differs are either padded or scaled to fit, see [`Harmonize`].
+//!
+//! ```text
+//!        HStack              VStack
+//!  ┌─────┬───────┬───┐     ┌───────┐
+//!  │     │       │ 3 │     │   1   │
+//!  │  1  │   2   ├───┤     ├───────┤
+//!  │     │       │   │     │   2   │
+//!  └─────┴───────┴───┘     └───────┘
+//! ```
+use zune_core::bit_depth::BitType;
+use zune_core::colorspace::ColorSpace;
+use zune_image::channel::Channel;
+use zune_image::errors::ImageErrors;
+use zune_image::image::Image;
+use zune_image::traits::OperationsTrait;
+
+use crate::montage::blit;
+use crate::resize::{Resize, ResizeMethod};
+
+/// How images of different sizes are made to fit next to each other
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Harmonize {
+    /// Center smaller images on the background color, the output is
+    /// as tall (or wide) as the largest image
+    Pad,
+    /// Scale images, keeping their aspect ratio, to the height (or width)
+    /// of the image the operation runs on
+    Scale
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Axis {
+    Horizontal,
+    Vertical
+}
+
+impl Axis {
+    /// Split dimensions into the length along and across this axis
+    const fn split(self, (width, height): (usize, usize)) -> (usize, usize) {
+        match self {
+            Axis::Horizontal => (width, height),
+            Axis::Vertical => (height, width)
+        }
+    }
+    /// Inverse of [`split`](Self::split)
+    const fn join(self, along: usize, across: usize) -> (usize, usize) {
+        self.split((along, across))
+    }
+}
+
+/// Settings shared by [`HStack`] and [`VStack`]
+#[derive(Copy, Clone, Debug)]
+struct StackLayout {
+    harmonize:  Harmonize,
+    spacing:    usize,
+    background: [u8; 4]
+}
+
+impl Default for StackLayout {
+    fn default() -> Self {
+        StackLayout {
+            harmonize:  Harmonize::Pad,
+            spacing:    0,
+            background: [255, 255, 255, 255]
+        }
+    }
+}
+
+/// Append images to the right of an image
+///
+/// Appended images are converted to the colorspace and depth of the image,
+/// only their first frame is used and it is appended to every frame of the image.
+///
+/// # Example
+/// ```
+/// use zune_core::colorspace::ColorSpace;
+/// use zune_image::image::Image;
+/// use zune_image::traits::OperationsTrait;
+/// use zune_imageprocs::concat::{HStack, Harmonize};
+///
+/// let mut before = Image::fill(0_u8, ColorSpace::RGB, 100, 50);
+/// let after = Image::fill(255_u8, ColorSpace::RGB, 200, 100);
+///
+/// HStack::new(vec![after])
+///     .set_harmonize(Harmonize::Scale)
+///     .set_spacing(4)
+///     .execute(&mut before)
+///     .unwrap();
+///
+/// // the second image was scaled to 100x50
+/// assert_eq!(before.dimensions(), (100 + 4 + 100, 50));
+/// ```
+pub struct HStack {
+    images: Vec<Image>,
+    layout: StackLayout
+}
+
+impl HStack {
+    /// Create an operation appending `images` in order, padding
+    /// them on a white background
+    #[must_use]
+    pub fn new(images: Vec<Image>) -> HStack {
+        HStack {
+            images,
+            layout: StackLayout::default()
+        }
+    }
+    /// Set how images of different heights are made to fit
+    #[must_use]
+    pub fn set_harmonize(mut self, harmonize: Harmonize) -> Self {
+        self.layout.harmonize = harmonize;
+        self
+    }
+    /// Set the number of pixels between images
+    #[must_use]
+    pub fn set_spacing(mut self, spacing: usize) -> Self {
+        self.layout.spacing = spacing;
+        self
+    }
+    /// Set the color of padding and spacing as 8 bit RGBA
+    ///
+    /// It is converted to the colorspace and depth of the image
+    #[must_use]
+    pub fn set_background(mut self, rgba: [u8; 4]) -> Self {
+        self.layout.background = rgba;
+        self
+    }
+}
+
+impl OperationsTrait for HStack {
+    fn name(&self) -> &'static str {
+        "HStack"
+    }
+
+    fn execute_impl(&self, image: &mut Image) -> Result<(), ImageErrors> {
+        concatenate(image, &self.images, Axis::Horizontal, self.layout)
+    }
+
+    fn supported_types(&self) -> &'static [BitType] {
+        &[BitType::U8, BitType::U16, BitType::F32]
+    }
+}
+
+/// Append images below an image
+///
+/// Appended images are converted to the colorspace and depth of the image,
+/// only their first frame is used and it is appended to every frame of the image.
+///
+/// # Example
+/// ```
+/// use zune_core::colorspace::ColorSpace;
+/// use zune_image::image::Image;
+/// use zune_image::traits::OperationsTrait;
+/// use zune_imageprocs::concat::VStack;
+///
+/// let mut top = Image::fill(0_u8, ColorSpace::RGB, 100, 50);
+/// let bottom = Image::fill(255_u8, ColorSpace::Luma, 80, 20);
+///
+/// VStack::new(vec![bottom]).execute(&mut top).unwrap();
+///
+/// // the narrower image is centered on a white background
+/// assert_eq!(top.dimensions(), (100, 70));
+/// ```
+pub struct VStack {
+    images: Vec<Image>,
+    layout: StackLayout
+}
+
+impl VStack {
+    /// Create an operation appending `images` in order, padding
+    /// them on a white background
+    #[must_use]
+    pub fn new(images: Vec<Image>) -> VStack {
+        VStack {
+            images,
+            layout: StackLayout::default()
+        }
+    }
+    /// Set how images of different widths are made to fit
+    #[must_use]
+    pub fn set_harmonize(mut self, harmonize: Harmonize) -> Self {
+        self.layout.harmonize = harmonize;
+        self
+    }
+    /// Set the number of pixels between images
+    #[must_use]
+    pub fn set_spacing(mut self, spacing: usize) -> Self {
+        self.layout.spacing = spacing;
+        self
+    }
+    /// Set the color of padding and spacing as 8 bit RGBA
+    ///
+    /// It is converted to the colorspace and depth of the image
+    #[must_use]
+    pub fn set_background(mut self, rgba: [u8; 4]) -> Self {
+        self.layout.background = rgba;
+        self
+    }
+}
+
+impl OperationsTrait for VStack {
+    fn name(&self) -> &'static str {
+        "VStack"
+    }
+
+    fn execute_impl(&self, image: &mut Image) -> Result<(), ImageErrors> {
+        concatenate(image, &self.images, Axis::Vertical, self.layout)
+    }
+
+    fn supported_types(&self) -> &'static [BitType] {
+        &[BitType::U8, BitType::U16, BitType::F32]
+    }
+}
+
+/// Convert `other` to the layout of `image`, scaling it if requested
+fn prepare_image(
+    image: &Image, other: &Image, axis: Axis, harmonize: Harmonize
+) -> Result<Image, ImageErrors> {
+    let mut other = other.clone();
+    other.convert_color(image.colorspace())?;
+    other.convert_depth(image.depth())?;
+
+    let (_, target) = axis.split(image.dimensions());
+    let (along, across) = axis.split(other.dimensions());
+
+    if harmonize == Harmonize::Scale && across != target && across != 0 && target != 0 {
+        // keep the aspect ratio, rounding to the nearest pixel
+        let along = ((along * target + across / 2) / across).max(1);
+        let (width, height) = axis.join(along, target);
+
+        Resize::new(width, height, ResizeMethod::Bilinear).execute(&mut other)?;
+    }
+    Ok(other)
+}
+
+/// Create a `width` by `height` image filled with `rgba` in the colorspace
+/// and depth of `image`
+pub(crate) fn background_image(
+    image: &Image, rgba: [u8; 4], width: usize, height: usize
+) -> Result<Image, ImageErrors> {
+    let pixels: Vec<u8> = rgba
+        .iter()
+        .copied()
+        .cycle()
+        .take(width * height * 4)
+        .collect();
+
+    let mut background = Image::from_u8(&pixels, width, height, ColorSpace::RGBA);
+    background.convert_color(image.colorspace())?;
+    background.convert_depth(image.depth())?;
+
+    Ok(background)
+}
+
+/// Copy `src` of `dimensions` into `dst` at `position`, `name` is the
+/// operation reported for unsupported depths
+pub(crate) fn blit_channel(
+    src: &Channel, dst: &mut Channel, dimensions: (usize, usize), dst_width: usize,
+    position: (usize, usize), depth: BitType, name: &'static str
+) -> Result<(), ImageErrors> {
+    match depth {
+        BitType::U8 => blit::<u8>(
+            src.reinterpret_as()?,
+            dst.reinterpret_as_mut()?,
+            dimensions,
+            dst_width,
+            position
+        ),
+        BitType::U16 => blit::<u16>(
+            src.reinterpret_as()?,
+            dst.reinterpret_as_mut()?,
+            dimensions,
+            dst_width,
+            position
+        ),
+        BitType::F32 => blit::<f32>(
+            src.reinterpret_as()?,
+            dst.reinterpret_as_mut()?,
+            dimensions,
+            dst_width,
+            position
+        ),
+        d => return Err(ImageErrors::ImageOperationNotImplemented(name, d))
+    }
+    Ok(())
+}
+
+fn concatenate(
+    image: &mut Image, others: &[Image], axis: Axis, layout: StackLayout
+) -> Result<(), ImageErrors> {
+    let others = others
+        .iter()
+        .map(|x| prepare_image(image, x, axis, layout.harmonize))
+        .collect::<Result<Vec<Image>, ImageErrors>>()?;
+
+    let colorspace = image.colorspace();
+    let depth = image.depth().bit_type();
+    let dimensions = image.dimensions();
+
+    let all_dimensions: Vec<(usize, usize)> = core::iter::once(dimensions)
+        .chain(others.iter().map(Image::dimensions))
+        .collect();
+
+    let total_along = all_dimensions
+        .iter()
+        .map(|x| axis.split(*x).0)
+        .sum::<usize>()
+        + layout.spacing * others.len();
+    let total_across = all_dimensions
+        .iter()
+        .map(|x| axis.split(*x).1)
+        .max()
+        .unwrap_or(0);
+
+    let (width, height) = axis.join(total_along, total_across);
+
+    // top left corner of every image, centered across the axis
+    let mut positions = Vec::with_capacity(all_dimensions.len());
+    let mut along = 0;
+
+    for dims in &all_dimensions {
+        let (length, across) = axis.split(*dims);
+        positions.push(axis.join(along, (total_across - across) / 2));
+        along += length + layout.spacing;
+    }
+
+    let background = background_image(image, layout.background, width, height)?;
+    let background = background.frames_ref()[0].channels_ref(colorspace, false);
+
+    for frame in image.frames_mut() {
+        let mut canvas = background.to_vec();
+
+        let sources = core::iter::once(frame.channels_ref(colorspace, false)).chain(
+            others
+                .iter()
+                .map(|x| x.frames_ref()[0].channels_ref(colorspace, false))
+        );
+        for ((channels, dims), position) in sources.zip(&all_dimensions).zip(&positions) {
+            if dims.0 == 0 || dims.1 == 0 {
+                continue;
+            }
+            for (src, dst) in channels.iter().zip(canvas.iter_mut()) {
+                blit_channel(src, dst, *dims, width, *position, depth, "concat")?;
+            }
+        }
+        for (channel, new_channel) in frame.channels_mut(colorspace, false).iter_mut().zip(canvas) {
+            *channel = new_channel;
+        }
+    }
+    image.set_dimensions(width, height);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use zune_core::colorspace::ColorSpace;
+    use zune_image::image::Image;
+    use zune_image::traits::OperationsTrait;
+
+    use crate::concat::{HStack, Harmonize, VStack};
+
+    #[test]
+    fn test_hstack_pads_shorter_images() {
+        let mut image = Image::fill(10_u8, ColorSpace::Luma, 2, 1);
+        let other = Image::fill(20_u8, ColorSpace::Luma, 1, 3);
+
+        HStack::new(vec![other])
+            .set_spacing(1)
+            .set_background([0, 0, 0, 255])
+            .execute(&mut image)
+            .unwrap();
+
+        assert_eq!(image.dimensions(), (4, 3));
+        let pixels = image.flatten_to_u8().remove(0);
+        #[rustfmt::skip]
+        assert_eq!(pixels, [
+            0, 0, 0, 20,
+            10, 10, 0, 20,
+            0, 0, 0, 20
+        ]);
+    }
+
+    #[test]
+    fn test_vstack_scales_to_width() {
+        let mut image = Image::fill(0_u16, ColorSpace::RGB, 40, 10);
+        let other = Image::fill(255_u8, ColorSpace::Luma, 20, 20);
+
+        VStack::new(vec![other.clone(), other])
+            .set_harmonize(Harmonize::Scale)
+            .execute(&mut image)
+            .unwrap();
+
+        assert_eq!(image.dimensions(), (40, 90));
+        assert_eq!(image.colorspace(), ColorSpace::RGB);
+    }
+}
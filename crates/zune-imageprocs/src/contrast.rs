@@ -32,6 +32,7 @@ use zune_core::colorspace::ColorSpace;
 use zune_image::errors::ImageErrors;
 use zune_image::image::Image;
 use zune_image::traits::OperationsTrait;
+use zune_image::view::ImageViewMut;
 
 /// Adjust the contrast of an image
 ///
@@ -81,6 +82,12 @@ impl OperationsTrait for Contrast {
         }
         Ok(())
     }
+    fn execute_view_impl(&self, view: &mut ImageViewMut) -> Result<(), ImageErrors> {
+        match view.depth().bit_type() {
+            BitType::U8 => view.for_each_row_mut(true, |row| contrast_u8(row, self.contrast)),
+            d => Err(ImageErrors::ImageOperationNotImplemented(self.name(), d))
+        }
+    }
     fn supported_colorspaces(&self) -> &'static [ColorSpace] {
         &[
             ColorSpace::RGBA,
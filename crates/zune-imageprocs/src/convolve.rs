@@ -16,6 +16,8 @@
 
 use zune_core::bit_depth::BitType;
 use zune_core::log::trace;
+#[cfg(feature = "threads")]
+use zune_core::threads::ThreadPool;
 use zune_image::channel::Channel;
 use zune_image::errors::ImageErrors;
 use zune_image::image::Image;
@@ -86,64 +88,47 @@ impl OperationsTrait for Convolve {
         {
             trace!("Running convolve in multithreaded mode");
 
-            std::thread::scope(|s| {
-                let mut errors = vec![];
-                for channel in image.channels_mut(true) {
-                    let scope = s.spawn(|| {
-                        // Hello
-                        let mut out_channel = Channel::new_with_bit_type(
-                            width * height * depth.size_of(),
-                            depth.bit_type()
-                        );
-
-                        match depth.bit_type() {
-                            BitType::U8 => {
-                                convolve(
-                                    channel.reinterpret_as::<u8>()?,
-                                    out_channel.reinterpret_as_mut::<u8>()?,
-                                    width,
-                                    height,
-                                    &self.weights,
-                                    self.scale
-                                )?;
-                            }
-                            BitType::U16 => {
-                                convolve(
-                                    channel.reinterpret_as::<u16>()?,
-                                    out_channel.reinterpret_as_mut::<u16>()?,
-                                    width,
-                                    height,
-                                    &self.weights,
-                                    self.scale
-                                )?;
-                            }
-                            BitType::F32 => {
-                                convolve(
-                                    channel.reinterpret_as::<f32>()?,
-                                    out_channel.reinterpret_as_mut::<f32>()?,
-                                    width,
-                                    height,
-                                    &self.weights,
-                                    self.scale
-                                )?;
-                            }
-                            d => {
-                                return Err(ImageErrors::ImageOperationNotImplemented(
-                                    self.name(),
-                                    d
-                                ))
-                            }
-                        }
-
-                        *channel = out_channel;
-                        Ok(())
-                    });
-                    errors.push(scope);
+            ThreadPool::new().try_for_each(image.channels_mut(true), |channel| {
+                // Hello
+                let mut out_channel =
+                    Channel::new_with_bit_type(width * height * depth.size_of(), depth.bit_type());
+
+                match depth.bit_type() {
+                    BitType::U8 => {
+                        convolve(
+                            channel.reinterpret_as::<u8>()?,
+                            out_channel.reinterpret_as_mut::<u8>()?,
+                            width,
+                            height,
+                            &self.weights,
+                            self.scale
+                        )?;
+                    }
+                    BitType::U16 => {
+                        convolve(
+                            channel.reinterpret_as::<u16>()?,
+                            out_channel.reinterpret_as_mut::<u16>()?,
+                            width,
+                            height,
+                            &self.weights,
+                            self.scale
+                        )?;
+                    }
+                    BitType::F32 => {
+                        convolve(
+                            channel.reinterpret_as::<f32>()?,
+                            out_channel.reinterpret_as_mut::<f32>()?,
+                            width,
+                            height,
+                            &self.weights,
+                            self.scale
+                        )?;
+                    }
+                    d => return Err(ImageErrors::ImageOperationNotImplemented(self.name(), d))
                 }
-                errors
-                    .into_iter()
-                    .map(|x| x.join().unwrap())
-                    .collect::<Result<Vec<()>, ImageErrors>>()
+
+                *channel = out_channel;
+                Ok(())
             })?;
         }
         #[cfg(not(feature = "threads"))]
@@ -124,7 +124,15 @@ impl OperationsTrait for Crop {
 
     fn execute_impl(&self, image: &mut Image) -> Result<(), ImageErrors> {
         let new_dims = self.width * self.height * image.depth().size_of();
-        let (old_width, _) = image.dimensions();
+        let (old_width, old_height) = image.dimensions();
+
+        if self.x + self.width > old_width || self.y + self.height > old_height {
+            let msg = format!(
+                "Crop of {}x{} at ({},{}) is outside of the {old_width}x{old_height} image",
+                self.width, self.height, self.x, self.y
+            );
+            return Err(ImageErrors::GenericString(msg));
+        }
         let depth = image.depth().bit_type();
 
         for channel in image.channels_mut(false) {
@@ -0,0 +1,281 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! Tone curves
+//!
+//! A curve maps input intensities to output intensities, and is defined by a few control
+//! points that a smooth curve passes through, as in the curves tool of photo editors.
+//!
+//! Curves generalize many simpler adjustments, e.g `(0,0.1), (1,0.9)` lowers contrast,
+//! `(0,0), (0.5,0.6), (1,1)` brightens midtones and `(0.1,0), (0.9,1)` works like levels.
+//!
+//! # Algorithm
+//! Points are joined with a monotone cubic spline (Fritsch-Carlson), which unlike a normal
+//! cubic spline never overshoots between points, so a curve whose points only go up
+//! never goes down.
+//!
+//! Outside the first and last point, the curve is flat.
+//!
+//! For integer images, curves are evaluated once for every possible value into
+//! a lookup table, float images are evaluated per pixel.
+use zune_core::bit_depth::BitType;
+use zune_image::errors::ImageErrors;
+use zune_image::image::Image;
+use zune_image::traits::{LightSpace, OperationsTrait};
+
+/// Apply tone curves to every channel, and a master curve to all of them
+///
+/// Points are `(input, output)` pairs in the range `0.0..=1.0`, and an empty
+/// list of points is an identity curve which leaves values untouched.
+///
+/// Each channel is first mapped by its own curve, then by the master curve.
+///
+/// # Alpha channel
+/// - Alpha channel is ignored
+///
+/// # Example
+/// An S curve increasing contrast, and cooling the image down by lowering red
+/// ```
+/// use zune_core::colorspace::ColorSpace;
+/// use zune_image::image::Image;
+/// use zune_image::traits::OperationsTrait;
+/// use zune_imageprocs::curves::Curves;
+///
+/// let mut image = Image::fill(128_u8, ColorSpace::RGB, 100, 100);
+///
+/// Curves::new(vec![vec![(0.0, 0.0), (0.5, 0.45), (1.0, 1.0)]])
+///     .set_master(&[(0.0, 0.0), (0.25, 0.2), (0.75, 0.8), (1.0, 1.0)])
+///     .execute(&mut image)
+///     .unwrap();
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Curves {
+    channels: Vec<Vec<(f32, f32)>>,
+    master:   Vec<(f32, f32)>
+}
+
+impl Curves {
+    /// Create a new curves adjustment
+    ///
+    /// # Arguments
+    /// - points_per_channel: Curve points for each channel, in the colorspace order
+    ///   excluding alpha, e.g `[red, green, blue]` for RGBA images. Channels without
+    ///   a curve are left untouched.
+    #[must_use]
+    pub fn new(points_per_channel: Vec<Vec<(f32, f32)>>) -> Curves {
+        Curves {
+            channels: points_per_channel,
+            master:   vec![]
+        }
+    }
+    /// Set the curve applied to every channel after the channel's own curve
+    #[must_use]
+    pub fn set_master(mut self, points: &[(f32, f32)]) -> Curves {
+        self.master = points.to_vec();
+        self
+    }
+}
+
+impl OperationsTrait for Curves {
+    fn name(&self) -> &'static str {
+        "Curves"
+    }
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn execute_impl(&self, image: &mut Image) -> Result<(), ImageErrors> {
+        let colorspace = image.colorspace();
+        let color_channels = colorspace.num_components() - usize::from(colorspace.has_alpha());
+
+        if self.channels.len() > color_channels {
+            return Err(ImageErrors::GenericString(format!(
+                "Found {} channel curves but {colorspace:?} has {color_channels} color channels",
+                self.channels.len()
+            )));
+        }
+        let master = MonotoneSpline::new(&self.master)?;
+        let curves = self
+            .channels
+            .iter()
+            .map(|x| MonotoneSpline::new(x))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let map = |channel: usize, value: f32| {
+            let value = match curves.get(channel) {
+                Some(Some(curve)) => curve.evaluate(value),
+                _ => value
+            };
+            master.as_ref().map_or(value, |x| x.evaluate(value))
+        };
+        let depth = image.depth().bit_type();
+
+        match depth {
+            BitType::U8 => {
+                let tables: Vec<Vec<u8>> = (0..color_channels)
+                    .map(|c| {
+                        (0..=255_u8)
+                            .map(|x| (map(c, f32::from(x) / 255.0) * 255.0).round() as u8)
+                            .collect()
+                    })
+                    .collect();
+
+                for frame in image.frames_mut() {
+                    for (c, channel) in frame.channels_mut(colorspace, true).iter_mut().enumerate()
+                    {
+                        for pixel in channel.reinterpret_as_mut::<u8>()? {
+                            *pixel = tables[c][usize::from(*pixel)];
+                        }
+                    }
+                }
+            }
+            BitType::U16 => {
+                let tables: Vec<Vec<u16>> = (0..color_channels)
+                    .map(|c| {
+                        (0..=65535_u16)
+                            .map(|x| (map(c, f32::from(x) / 65535.0) * 65535.0).round() as u16)
+                            .collect()
+                    })
+                    .collect();
+
+                for frame in image.frames_mut() {
+                    for (c, channel) in frame.channels_mut(colorspace, true).iter_mut().enumerate()
+                    {
+                        for pixel in channel.reinterpret_as_mut::<u16>()? {
+                            *pixel = tables[c][usize::from(*pixel)];
+                        }
+                    }
+                }
+            }
+            BitType::F32 => {
+                for frame in image.frames_mut() {
+                    for (c, channel) in frame.channels_mut(colorspace, true).iter_mut().enumerate()
+                    {
+                        for pixel in channel.reinterpret_as_mut::<f32>()? {
+                            *pixel = map(c, *pixel);
+                        }
+                    }
+                }
+            }
+            d => return Err(ImageErrors::ImageOperationNotImplemented(self.name(), d))
+        }
+        Ok(())
+    }
+
+    fn supported_types(&self) -> &'static [BitType] {
+        &[BitType::U8, BitType::U16, BitType::F32]
+    }
+
+    fn light_space(&self) -> LightSpace {
+        LightSpace::Encoded
+    }
+}
+
+/// A monotone cubic hermite spline through a set of points
+#[derive(Clone, Debug)]
+pub struct MonotoneSpline {
+    points:   Vec<(f32, f32)>,
+    tangents: Vec<f32>
+}
+
+impl MonotoneSpline {
+    /// Create a spline through `points`, or `None` if there are no points
+    ///
+    /// Points don't need to be sorted
+    ///
+    /// # Errors
+    /// If there is a single point, points are outside `0.0..=1.0`
+    /// or two points have the same input
+    pub fn new(points: &[(f32, f32)]) -> Result<Option<MonotoneSpline>, ImageErrors> {
+        if points.is_empty() {
+            return Ok(None);
+        }
+        if points.len() == 1 {
+            return Err(ImageErrors::GenericStr("A curve needs at least two points"));
+        }
+        let in_range = |x: f32| (0.0..=1.0).contains(&x);
+
+        if let Some(point) = points.iter().find(|(x, y)| !in_range(*x) || !in_range(*y)) {
+            return Err(ImageErrors::GenericString(format!(
+                "Curve point {point:?} is outside of 0.0..=1.0"
+            )));
+        }
+        let mut points = points.to_vec();
+        points.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        if points.windows(2).any(|x| x[0].0.total_cmp(&x[1].0).is_eq()) {
+            return Err(ImageErrors::GenericStr(
+                "Two curve points have the same input"
+            ));
+        }
+        // secant slopes between points
+        let slopes: Vec<f32> = points
+            .windows(2)
+            .map(|x| (x[1].1 - x[0].1) / (x[1].0 - x[0].0))
+            .collect();
+
+        let last = slopes.len() - 1;
+        let mut tangents: Vec<f32> = (0..points.len())
+            .map(|i| match i {
+                0 => slopes[0],
+                i if i > last => slopes[last],
+                // a local extremum, keep the curve flat to avoid overshooting
+                i if slopes[i - 1] * slopes[i] <= 0.0 => 0.0,
+                i => slopes[i - 1].midpoint(slopes[i])
+            })
+            .collect();
+
+        // limit tangents so every segment stays monotone
+        for (i, slope) in slopes.iter().enumerate() {
+            if *slope == 0.0 {
+                tangents[i] = 0.0;
+                tangents[i + 1] = 0.0;
+                continue;
+            }
+            let a = tangents[i] / slope;
+            let b = tangents[i + 1] / slope;
+            let magnitude = a * a + b * b;
+
+            if magnitude > 9.0 {
+                let scale = 3.0 / magnitude.sqrt();
+                tangents[i] = scale * a * slope;
+                tangents[i + 1] = scale * b * slope;
+            }
+        }
+        Ok(Some(MonotoneSpline { points, tangents }))
+    }
+
+    /// Evaluate the spline at `x`
+    ///
+    /// Values before the first point or after the last point take
+    /// the output of that point
+    #[must_use]
+    pub fn evaluate(&self, x: f32) -> f32 {
+        let first = self.points[0];
+        let last = self.points[self.points.len() - 1];
+
+        if x.is_nan() || x <= first.0 {
+            return first.1;
+        }
+        if x >= last.0 {
+            return last.1;
+        }
+        // the segment containing x, points are sorted
+        let i = self.points.partition_point(|p| p.0 <= x) - 1;
+
+        let (x0, y0) = self.points[i];
+        let (x1, y1) = self.points[i + 1];
+        let h = x1 - x0;
+        let t = (x - x0) / h;
+
+        let (t2, t3) = (t * t, t * t * t);
+
+        (2.0 * t3 - 3.0 * t2 + 1.0) * y0
+            + (t3 - 2.0 * t2 + t) * h * self.tangents[i]
+            + (-2.0 * t3 + 3.0 * t2) * y1
+            + (t3 - t2) * h * self.tangents[i + 1]
+    }
+}
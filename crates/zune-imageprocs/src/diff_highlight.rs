@@ -0,0 +1,273 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! Visualize differences between two images
+//!
+//! # Algorithm
+//!
+//! ```text
+//! diff = max(|image[c,x,y] - other[c,x,y]|) over all color channels c
+//!
+//! if diff > tolerance:
+//!     pixel = (max,0,0) // red
+//! else:
+//!     pixel = pixel * DIM_FACTOR
+//! ```
+//!
+//! Alpha channels are ignored when comparing and are left untouched.
+use zune_core::bit_depth::BitType;
+use zune_core::colorspace::ColorSpace;
+use zune_image::errors::ImageErrors;
+use zune_image::image::Image;
+use zune_image::traits::OperationsTrait;
+
+use crate::traits::NumOps;
+
+/// Amount identical pixels are multiplied by, so that
+/// differing pixels stand out
+const DIM_FACTOR: f32 = 0.3;
+
+/// Highlight pixels that differ between two images
+///
+/// Pixels whose difference exceeds the tolerance are painted red, and all other
+/// pixels are dimmed, making it easy to spot where two renders diverge.
+///
+/// Grayscale images are converted to RGB (or RGBA) so that the highlight can be drawn.
+///
+/// # Example
+/// ```
+/// use zune_core::colorspace::ColorSpace;
+/// use zune_image::image::Image;
+/// use zune_image::traits::OperationsTrait;
+/// use zune_imageprocs::diff_highlight::DiffHighlight;
+///
+/// let reference = Image::fill::<u8>(100, ColorSpace::RGB, 100, 100);
+/// let mut render = Image::fill::<u8>(110, ColorSpace::RGB, 100, 100);
+///
+/// // pixels differing by more than 5 will be painted red
+/// DiffHighlight::new(&reference, 5.0).execute(&mut render).unwrap();
+/// ```
+pub struct DiffHighlight<'src> {
+    image:     &'src Image,
+    tolerance: f32
+}
+
+impl<'src> DiffHighlight<'src> {
+    /// Create a new diff highlight operation
+    ///
+    /// # Arguments
+    /// - other_image: The image to compare against, it must match in dimensions,
+    ///   number of frames, depth and colorspace
+    /// - tolerance: Maximum difference allowed between two pixels before they are
+    ///   considered different, this is in the range of the image depth,
+    ///   i.e 0-255 for 8 bit images, 0-65535 for 16 bit images and 0.0-1.0 for float images
+    #[must_use]
+    pub fn new(other_image: &'src Image, tolerance: f32) -> DiffHighlight<'src> {
+        DiffHighlight {
+            image: other_image,
+            tolerance
+        }
+    }
+}
+
+impl OperationsTrait for DiffHighlight<'_> {
+    fn name(&self) -> &'static str {
+        "Diff Highlight"
+    }
+
+    fn execute_impl(&self, image: &mut Image) -> Result<(), ImageErrors> {
+        if image.dimensions() != self.image.dimensions() {
+            return Err(ImageErrors::GenericStr(
+                "Image dimensions are incompatible for diff"
+            ));
+        }
+        if image.depth() != self.image.depth() {
+            return Err(ImageErrors::GenericStr(
+                "Image depths do not match for diff"
+            ));
+        }
+        if image.colorspace() != self.image.colorspace() {
+            return Err(ImageErrors::GenericStr(
+                "Image colorspace does not match for diff"
+            ));
+        }
+        if image.frames_len() != self.image.frames_len() {
+            return Err(ImageErrors::GenericStr(
+                "Image frame count does not match for diff"
+            ));
+        }
+        let colorspace = image.colorspace();
+        let b_type = image.depth().bit_type();
+
+        // compute which pixels differ before we modify anything
+        let mut masks = Vec::with_capacity(image.frames_len());
+
+        for (frame, other) in image.frames_ref().iter().zip(self.image.frames_ref()) {
+            let ours = frame.channels_ref(colorspace, true);
+            let theirs = other.channels_ref(colorspace, true);
+
+            let mask = match b_type {
+                BitType::U8 => diff_mask::<u8>(
+                    &reinterpret_all(ours)?,
+                    &reinterpret_all(theirs)?,
+                    self.tolerance
+                ),
+                BitType::U16 => diff_mask::<u16>(
+                    &reinterpret_all(ours)?,
+                    &reinterpret_all(theirs)?,
+                    self.tolerance
+                ),
+                BitType::F32 => diff_mask::<f32>(
+                    &reinterpret_all(ours)?,
+                    &reinterpret_all(theirs)?,
+                    self.tolerance
+                ),
+                d => return Err(ImageErrors::ImageOperationNotImplemented(self.name(), d))
+            };
+            masks.push(mask);
+        }
+
+        // we need three color channels to draw red
+        if colorspace == ColorSpace::Luma {
+            image.convert_color(ColorSpace::RGB)?;
+        } else if colorspace == ColorSpace::LumaA {
+            image.convert_color(ColorSpace::RGBA)?;
+        }
+        let colorspace = image.colorspace();
+
+        for (frame, mask) in image.frames_mut().iter_mut().zip(&masks) {
+            let channels = frame.channels_mut(colorspace, true);
+
+            if let [r, g, b] = channels {
+                match b_type {
+                    BitType::U8 => highlight_diff::<u8>(
+                        r.reinterpret_as_mut()?,
+                        g.reinterpret_as_mut()?,
+                        b.reinterpret_as_mut()?,
+                        mask
+                    ),
+                    BitType::U16 => highlight_diff::<u16>(
+                        r.reinterpret_as_mut()?,
+                        g.reinterpret_as_mut()?,
+                        b.reinterpret_as_mut()?,
+                        mask
+                    ),
+                    BitType::F32 => highlight_diff::<f32>(
+                        r.reinterpret_as_mut()?,
+                        g.reinterpret_as_mut()?,
+                        b.reinterpret_as_mut()?,
+                        mask
+                    ),
+                    d => return Err(ImageErrors::ImageOperationNotImplemented(self.name(), d))
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn supported_colorspaces(&self) -> &'static [ColorSpace] {
+        &[
+            ColorSpace::RGB,
+            ColorSpace::RGBA,
+            ColorSpace::Luma,
+            ColorSpace::LumaA
+        ]
+    }
+
+    fn supported_types(&self) -> &'static [BitType] {
+        &[BitType::U8, BitType::U16, BitType::F32]
+    }
+}
+
+fn reinterpret_all<T: Default + 'static>(
+    channels: &[zune_image::channel::Channel]
+) -> Result<Vec<&[T]>, ImageErrors> {
+    let mut out = Vec::with_capacity(channels.len());
+
+    for channel in channels {
+        out.push(channel.reinterpret_as::<T>()?);
+    }
+    Ok(out)
+}
+
+/// Return a mask where `true` indicates that the pixel at that position
+/// differs by more than `tolerance` in any of the channels
+pub fn diff_mask<T>(image: &[&[T]], other: &[&[T]], tolerance: f32) -> Vec<bool>
+where
+    T: Copy + NumOps<T>
+{
+    let length = image.first().map_or(0, |x| x.len());
+
+    let mut mask = vec![false; length];
+
+    for (ours, theirs) in image.iter().zip(other) {
+        for ((a, b), m) in ours.iter().zip(theirs.iter()).zip(mask.iter_mut()) {
+            let diff = (a.to_f32() - b.to_f32()).abs();
+
+            *m |= diff > tolerance;
+        }
+    }
+    mask
+}
+
+/// Paint pixels marked in `mask` red and dim the rest
+pub fn highlight_diff<T>(r: &mut [T], g: &mut [T], b: &mut [T], mask: &[bool])
+where
+    T: Copy + NumOps<T>
+{
+    let max = T::max_val();
+    let min = T::min_val();
+
+    for (((r, g), b), differs) in r.iter_mut().zip(g).zip(b).zip(mask) {
+        if *differs {
+            *r = max;
+            *g = min;
+            *b = min;
+        } else {
+            *r = T::from_f32(r.to_f32() * DIM_FACTOR);
+            *g = T::from_f32(g.to_f32() * DIM_FACTOR);
+            *b = T::from_f32(b.to_f32() * DIM_FACTOR);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use zune_core::colorspace::ColorSpace;
+    use zune_image::image::Image;
+    use zune_image::traits::OperationsTrait;
+
+    use crate::diff_highlight::DiffHighlight;
+
+    #[test]
+    fn test_diff_highlight_marks_changed_pixels() {
+        let reference = Image::fill::<u8>(100, ColorSpace::Luma, 10, 10);
+        let mut render = reference.clone();
+
+        render
+            .modify_pixels_mut::<u8, _>(|y, x, px| {
+                if x == 2 && y == 3 {
+                    *px[0] = 200;
+                }
+            })
+            .unwrap();
+
+        DiffHighlight::new(&reference, 10.0)
+            .execute(&mut render)
+            .unwrap();
+
+        assert_eq!(render.colorspace(), ColorSpace::RGB);
+
+        let channels = render.channels_ref(true);
+        let r = channels[0].reinterpret_as::<u8>().unwrap();
+        let g = channels[1].reinterpret_as::<u8>().unwrap();
+
+        assert_eq!((r[3 * 10 + 2], g[3 * 10 + 2]), (255, 0));
+        assert_eq!((r[0], g[0]), (30, 30));
+    }
+}
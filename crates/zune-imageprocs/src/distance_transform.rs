@@ -0,0 +1,296 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! Distance transform
+//!
+//! Replaces every pixel with its distance to the nearest zero pixel, zero pixels
+//! are the background and every other pixel is the foreground.
+//!
+//! ```text
+//!  0 0 0 0 0        0 0 0 0 0
+//!  0 1 1 1 0        0 1 1 1 0
+//!  0 1 1 1 0   ->   0 1 2 1 0    (Manhattan)
+//!  0 1 1 1 0        0 1 1 1 0
+//!  0 0 0 0 0        0 0 0 0 0
+//! ```
+//!
+//! Distances are in pixels, for integer images they are rounded and saturate at the
+//! maximum value of the depth, for float images they are stored as is.
+//! Pixels in an image without any zero pixel are infinitely far from the background.
+//!
+//! The input is usually a mask from an operation like [threshold](crate::threshold),
+//! and the output is useful for things like outlines, glows and shape analysis.
+//!
+//! # Algorithm
+//! - Manhattan uses the classic two pass chamfer algorithm.
+//! - Euclidean is exact, using the separable lower envelope of parabolas algorithm from
+//!   Felzenszwalb and Huttenlocher, [Distance Transforms of Sampled Functions](https://cs.brown.edu/people/pfelzens/papers/dt-final.pdf)
+use zune_core::bit_depth::BitType;
+use zune_image::errors::ImageErrors;
+use zune_image::image::Image;
+use zune_image::traits::OperationsTrait;
+
+/// Stand in for infinity that keeps the parabola intersections finite
+const FAR: f64 = 1e20;
+
+/// How distance between two pixels is measured
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum DistanceMetric {
+    /// Straight line distance, `sqrt(dx^2 + dy^2)`
+    Euclidean,
+    /// Distance when only moving horizontally and vertically, `|dx| + |dy|`
+    Manhattan
+}
+
+/// Replace pixels with their distance to the nearest zero pixel
+///
+/// Every channel except alpha is transformed independently
+///
+/// # Example
+/// ```
+/// use zune_core::colorspace::ColorSpace;
+/// use zune_image::image::Image;
+/// use zune_image::traits::OperationsTrait;
+/// use zune_imageprocs::distance_transform::{DistanceMetric, DistanceTransform};
+///
+/// let mut image = Image::fill(255_u8, ColorSpace::Luma, 100, 100);
+///
+/// DistanceTransform::new(DistanceMetric::Euclidean)
+///     .execute(&mut image)
+///     .unwrap();
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub struct DistanceTransform {
+    metric: DistanceMetric
+}
+
+impl DistanceTransform {
+    /// Create a new distance transform operation
+    ///
+    /// # Arguments
+    /// - metric: How to measure distance between pixels
+    #[must_use]
+    pub fn new(metric: DistanceMetric) -> DistanceTransform {
+        DistanceTransform { metric }
+    }
+}
+
+impl OperationsTrait for DistanceTransform {
+    fn name(&self) -> &'static str {
+        "Distance Transform"
+    }
+
+    #[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
+    fn execute_impl(&self, image: &mut Image) -> Result<(), ImageErrors> {
+        let (width, height) = image.dimensions();
+        let depth = image.depth().bit_type();
+
+        for channel in image.channels_mut(true) {
+            match depth {
+                BitType::U8 => transform_channel(
+                    channel.reinterpret_as_mut::<u8>()?,
+                    width,
+                    height,
+                    self.metric,
+                    |x| x.round() as u8
+                ),
+                BitType::U16 => transform_channel(
+                    channel.reinterpret_as_mut::<u16>()?,
+                    width,
+                    height,
+                    self.metric,
+                    |x| x.round() as u16
+                ),
+                BitType::F32 => transform_channel(
+                    channel.reinterpret_as_mut::<f32>()?,
+                    width,
+                    height,
+                    self.metric,
+                    |x| x
+                ),
+                d => return Err(ImageErrors::ImageOperationNotImplemented(self.name(), d))
+            }
+        }
+        Ok(())
+    }
+
+    fn supported_types(&self) -> &'static [BitType] {
+        &[BitType::U8, BitType::U16, BitType::F32]
+    }
+}
+
+fn transform_channel<T: Copy + Default + PartialEq>(
+    channel: &mut [T], width: usize, height: usize, metric: DistanceMetric,
+    convert: impl Fn(f32) -> T
+) {
+    let foreground: Vec<bool> = channel.iter().map(|x| *x != T::default()).collect();
+    let distances = distance_transform(&foreground, width, height, metric);
+
+    for (pixel, distance) in channel.iter_mut().zip(distances) {
+        *pixel = convert(distance);
+    }
+}
+
+/// Calculate the distance of every foreground pixel to the nearest background pixel
+///
+/// # Arguments
+/// - foreground: Mask of `width*height` pixels, `true` for foreground pixels
+/// - width: Mask width
+/// - height: Mask height
+/// - metric: How to measure distance
+///
+/// # Returns
+/// Distances in pixels, background pixels are zero and if there are no
+/// background pixels every distance is infinite.
+///
+/// # Panics
+/// If `foreground` is smaller than `width*height`
+#[allow(clippy::cast_possible_truncation)]
+#[must_use]
+pub fn distance_transform(
+    foreground: &[bool], width: usize, height: usize, metric: DistanceMetric
+) -> Vec<f32> {
+    if width == 0 || height == 0 {
+        return vec![];
+    }
+    let mut distances: Vec<f64> = foreground[..width * height]
+        .iter()
+        .map(|x| if *x { FAR } else { 0.0 })
+        .collect();
+
+    match metric {
+        DistanceMetric::Manhattan => manhattan(&mut distances, width, height),
+        DistanceMetric::Euclidean => squared_euclidean(&mut distances, width, height)
+    }
+
+    distances
+        .iter()
+        .map(|x| {
+            if *x >= FAR {
+                f32::INFINITY
+            } else if metric == DistanceMetric::Euclidean {
+                x.sqrt() as f32
+            } else {
+                *x as f32
+            }
+        })
+        .collect()
+}
+
+fn manhattan(distances: &mut [f64], width: usize, height: usize) {
+    // forward pass, looking up and left
+    for y in 0..height {
+        for x in 0..width {
+            let mut current = distances[y * width + x];
+
+            if y > 0 {
+                current = current.min(distances[(y - 1) * width + x] + 1.0);
+            }
+            if x > 0 {
+                current = current.min(distances[y * width + x - 1] + 1.0);
+            }
+            distances[y * width + x] = current;
+        }
+    }
+    // backward pass, looking down and right
+    for y in (0..height).rev() {
+        for x in (0..width).rev() {
+            let mut current = distances[y * width + x];
+
+            if y + 1 < height {
+                current = current.min(distances[(y + 1) * width + x] + 1.0);
+            }
+            if x + 1 < width {
+                current = current.min(distances[y * width + x + 1] + 1.0);
+            }
+            distances[y * width + x] = current;
+        }
+    }
+}
+
+fn squared_euclidean(distances: &mut [f64], width: usize, height: usize) {
+    let longest = width.max(height);
+
+    let mut input = vec![0.0; longest];
+    let mut output = vec![0.0; longest];
+    let mut vertices = vec![0; longest];
+    let mut boundaries = vec![0.0; longest + 1];
+
+    // columns
+    for x in 0..width {
+        for y in 0..height {
+            input[y] = distances[y * width + x];
+        }
+        distance_1d(
+            &input[..height],
+            &mut output[..height],
+            &mut vertices,
+            &mut boundaries
+        );
+        for y in 0..height {
+            distances[y * width + x] = output[y];
+        }
+    }
+    // rows, using the column distances
+    for row in distances.chunks_exact_mut(width) {
+        input[..width].copy_from_slice(row);
+        distance_1d(
+            &input[..width],
+            &mut output[..width],
+            &mut vertices,
+            &mut boundaries
+        );
+        row.copy_from_slice(&output[..width]);
+    }
+}
+
+/// One dimensional squared distance transform of `input` into `output`
+///
+/// Computes the lower envelope of parabolas rooted at every sample,
+/// `vertices` holds the parabolas in the envelope and `boundaries` where
+/// they intersect
+#[allow(clippy::cast_precision_loss)]
+fn distance_1d(input: &[f64], output: &mut [f64], vertices: &mut [usize], boundaries: &mut [f64]) {
+    if input.is_empty() {
+        return;
+    }
+    let mut k = 0;
+
+    vertices[0] = 0;
+    boundaries[0] = f64::NEG_INFINITY;
+    boundaries[1] = f64::INFINITY;
+
+    for q in 1..input.len() {
+        let qf = q as f64;
+
+        loop {
+            let p = vertices[k] as f64;
+            let intersection =
+                ((input[q] + qf * qf) - (input[vertices[k]] + p * p)) / (2.0 * (qf - p));
+
+            if intersection <= boundaries[k] {
+                k -= 1;
+            } else {
+                k += 1;
+                vertices[k] = q;
+                boundaries[k] = intersection;
+                boundaries[k + 1] = f64::INFINITY;
+                break;
+            }
+        }
+    }
+
+    k = 0;
+    for (q, out) in output.iter_mut().enumerate() {
+        while boundaries[k + 1] < q as f64 {
+            k += 1;
+        }
+        let offset = q.abs_diff(vertices[k]) as f64;
+        *out = offset * offset + input[vertices[k]];
+    }
+}
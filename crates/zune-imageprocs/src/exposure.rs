@@ -22,6 +22,7 @@ use zune_core::bit_depth::BitType;
 use zune_image::errors::ImageErrors;
 use zune_image::image::Image;
 use zune_image::traits::OperationsTrait;
+use zune_image::view::ImageViewMut;
 
 /// Adjust exposure of image
 ///
@@ -88,24 +89,9 @@ impl OperationsTrait for Exposure {
 
         for channel in image.channels_mut(true) {
             match bit_type {
-                BitType::U8 => {
-                    let raw_px = channel.reinterpret_as_mut::<u8>()?;
-                    for x in raw_px.iter_mut() {
-                        *x = ((f32::from(*x) - black) * self.exposure).clamp(0., 255.0) as _;
-                    }
-                }
-                BitType::U16 => {
-                    let raw_px = channel.reinterpret_as_mut::<u16>()?;
-                    for x in raw_px.iter_mut() {
-                        *x = ((f32::from(*x) - black) * self.exposure).clamp(0., 65535.0) as _;
-                    }
-                }
-                BitType::F32 => {
-                    let raw_px = channel.reinterpret_as_mut::<f32>()?;
-                    raw_px
-                        .iter_mut()
-                        .for_each(|x| *x = (*x - black) * self.exposure);
-                }
+                BitType::U8 => expose_u8(channel.reinterpret_as_mut()?, black, self.exposure),
+                BitType::U16 => expose_u16(channel.reinterpret_as_mut()?, black, self.exposure),
+                BitType::F32 => expose_f32(channel.reinterpret_as_mut()?, black, self.exposure),
                 d => return Err(ImageErrors::ImageOperationNotImplemented(self.name(), d))
             }
         }
@@ -113,7 +99,39 @@ impl OperationsTrait for Exposure {
         Ok(())
     }
 
+    fn execute_view_impl(&self, view: &mut ImageViewMut) -> Result<(), ImageErrors> {
+        let black = self.black.clamp(0.0, 1.0) * f32::from(view.depth().max_value());
+        let exposure = self.exposure;
+
+        match view.depth().bit_type() {
+            BitType::U8 => view.for_each_row_mut(true, |row| expose_u8(row, black, exposure)),
+            BitType::U16 => view.for_each_row_mut(true, |row| expose_u16(row, black, exposure)),
+            BitType::F32 => view.for_each_row_mut(true, |row| expose_f32(row, black, exposure)),
+            d => Err(ImageErrors::ImageOperationNotImplemented(self.name(), d))
+        }
+    }
+
     fn supported_types(&self) -> &'static [BitType] {
         &[BitType::U8, BitType::U16, BitType::F32]
     }
 }
+
+#[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
+fn expose_u8(pixels: &mut [u8], black: f32, exposure: f32) {
+    for x in pixels.iter_mut() {
+        *x = ((f32::from(*x) - black) * exposure).clamp(0., 255.0) as _;
+    }
+}
+
+#[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
+fn expose_u16(pixels: &mut [u16], black: f32, exposure: f32) {
+    for x in pixels.iter_mut() {
+        *x = ((f32::from(*x) - black) * exposure).clamp(0., 65535.0) as _;
+    }
+}
+
+fn expose_f32(pixels: &mut [f32], black: f32, exposure: f32) {
+    for x in pixels.iter_mut() {
+        *x = (*x - black) * exposure;
+    }
+}
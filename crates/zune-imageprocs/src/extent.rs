@@ -0,0 +1,226 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! Place an image on a canvas of a new size
+//!
+//! This is ImageMagick's `-extent`, the image keeps its scale and is positioned on
+//! the canvas by a [`Gravity`]. Sides of the image that fall outside the canvas are
+//! cropped, and parts of the canvas the image doesn't cover are filled with a
+//! background color.
+//!
+//! ```text
+//!   larger canvas, center         smaller canvas, north-west
+//!  ┌───────────────────┐            ┌─────────┬ ─ ─ ┐
+//!  │    ┌─────────┐    │            │ canvas  │
+//!  │    │  image  │    │            ├─────────┘     │
+//!  │    └─────────┘    │              image
+//!  └───────────────────┘            └ ─ ─ ─ ─ ─ ─ ─ ┘
+//! ```
+use zune_core::bit_depth::BitType;
+use zune_image::errors::ImageErrors;
+use zune_image::image::Image;
+use zune_image::traits::OperationsTrait;
+
+use crate::composite::Gravity;
+use crate::concat::{background_image, blit_channel};
+use crate::crop::Crop;
+
+/// Resize the canvas of an image, cropping or padding it as needed
+///
+/// The background is converted to the colorspace and depth of the image,
+/// its alpha is dropped for images without an alpha channel.
+///
+/// # Example
+/// Center an image on a square white canvas
+/// ```
+/// use zune_core::colorspace::ColorSpace;
+/// use zune_image::image::Image;
+/// use zune_image::traits::OperationsTrait;
+/// use zune_imageprocs::composite::Gravity;
+/// use zune_imageprocs::extent::Extent;
+///
+/// let mut image = Image::fill(0_u8, ColorSpace::RGB, 120, 80);
+///
+/// Extent::new(120, 120, Gravity::Center, [255, 255, 255, 255])
+///     .execute(&mut image)
+///     .unwrap();
+///
+/// assert_eq!(image.dimensions(), (120, 120));
+/// ```
+pub struct Extent {
+    width:      usize,
+    height:     usize,
+    gravity:    Gravity,
+    background: [u8; 4]
+}
+
+impl Extent {
+    /// Create a new extent operation
+    ///
+    /// # Arguments
+    /// - width, height: Dimensions of the new canvas
+    /// - gravity: Where the image is placed on the canvas
+    /// - background: RGBA color of the canvas where the image doesn't cover it
+    #[must_use]
+    pub fn new(width: usize, height: usize, gravity: Gravity, background: [u8; 4]) -> Extent {
+        Extent {
+            width,
+            height,
+            gravity,
+            background
+        }
+    }
+}
+
+/// Part of an image of `length` pixels placed at `offset` on a canvas of `canvas`
+/// pixels along one axis
+///
+/// Returns where the visible part starts in the image, its length and where it
+/// starts on the canvas
+#[allow(clippy::cast_sign_loss, clippy::cast_possible_wrap)]
+fn visible_span(offset: isize, length: usize, canvas: usize) -> (usize, usize, usize) {
+    let start = offset.min(0).unsigned_abs().min(length);
+    let position = offset.max(0) as usize;
+    let visible = (length - start).min(canvas.saturating_sub(position));
+
+    (start, visible, position.min(canvas))
+}
+
+impl OperationsTrait for Extent {
+    fn name(&self) -> &'static str {
+        "Extent"
+    }
+
+    fn execute_impl(&self, image: &mut Image) -> Result<(), ImageErrors> {
+        if self.width == 0 || self.height == 0 {
+            return Err(ImageErrors::GenericString(format!(
+                "Extent dimensions must be non-zero, got {}x{}",
+                self.width, self.height
+            )));
+        }
+        let (width, height) = image.dimensions();
+        let (x, y) = self
+            .gravity
+            .position((self.width, self.height), (width, height), (0, 0));
+
+        let (src_x, visible_w, dst_x) = visible_span(x, width, self.width);
+        let (src_y, visible_h, dst_y) = visible_span(y, height, self.height);
+
+        // drop the sides that fall outside of the canvas
+        if (visible_w, visible_h) != (width, height) {
+            Crop::new(visible_w, visible_h, src_x, src_y).execute(image)?;
+        }
+        let colorspace = image.colorspace();
+        let depth = image.depth().bit_type();
+
+        let background = background_image(image, self.background, self.width, self.height)?;
+        let background = background.frames_ref()[0].channels_ref(colorspace, false);
+
+        for frame in image.frames_mut() {
+            let mut canvas = background.to_vec();
+
+            if visible_w != 0 && visible_h != 0 {
+                for (src, dst) in frame
+                    .channels_ref(colorspace, false)
+                    .iter()
+                    .zip(&mut canvas)
+                {
+                    blit_channel(
+                        src,
+                        dst,
+                        (visible_w, visible_h),
+                        self.width,
+                        (dst_x, dst_y),
+                        depth,
+                        self.name()
+                    )?;
+                }
+            }
+            for (channel, new_channel) in
+                frame.channels_mut(colorspace, false).iter_mut().zip(canvas)
+            {
+                *channel = new_channel;
+            }
+        }
+        image.set_dimensions(self.width, self.height);
+
+        Ok(())
+    }
+
+    fn supported_types(&self) -> &'static [BitType] {
+        &[BitType::U8, BitType::U16, BitType::F32]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use zune_core::colorspace::ColorSpace;
+    use zune_image::image::Image;
+    use zune_image::traits::OperationsTrait;
+
+    use crate::composite::Gravity;
+    use crate::extent::Extent;
+
+    fn pixels(image: &Image) -> Vec<u8> {
+        image.flatten_to_u8().remove(0)
+    }
+
+    #[test]
+    fn test_extent_pads_and_crops() {
+        let source: Vec<u8> = (1..=6).collect();
+        let black = [0, 0, 0, 255];
+
+        // 3x2 on a 5x3 canvas, south-east
+        let mut image = Image::from_u8(&source, 3, 2, ColorSpace::Luma);
+        Extent::new(5, 3, Gravity::SouthEast, black)
+            .execute(&mut image)
+            .unwrap();
+
+        assert_eq!(image.dimensions(), (5, 3));
+        #[rustfmt::skip]
+        assert_eq!(pixels(&image), [
+            0, 0, 0, 0, 0,
+            0, 0, 1, 2, 3,
+            0, 0, 4, 5, 6
+        ]);
+
+        // wider canvas, but shorter, centered
+        let mut image = Image::from_u8(&source, 3, 2, ColorSpace::Luma);
+        Extent::new(5, 1, Gravity::Center, black)
+            .execute(&mut image)
+            .unwrap();
+        assert_eq!(pixels(&image), [0, 1, 2, 3, 0]);
+
+        // smaller canvas, keeps the bottom right corner
+        let mut image = Image::from_u8(&source, 3, 2, ColorSpace::Luma);
+        Extent::new(2, 1, Gravity::SouthEast, black)
+            .execute(&mut image)
+            .unwrap();
+        assert_eq!(pixels(&image), [5, 6]);
+    }
+
+    #[test]
+    fn test_extent_background() {
+        let mut image = Image::fill(0_u16, ColorSpace::RGBA, 2, 2);
+
+        Extent::new(3, 2, Gravity::West, [255, 0, 0, 0])
+            .execute(&mut image)
+            .unwrap();
+
+        let channels = image.channels_ref(false);
+        let red = channels[0].reinterpret_as::<u16>().unwrap();
+        let alpha = channels[3].reinterpret_as::<u16>().unwrap();
+
+        assert_eq!(red, [0, 0, 65535, 0, 0, 65535]);
+        assert_eq!(alpha, [0; 6]);
+
+        assert!(Extent::new(0, 2, Gravity::West, [0; 4])
+            .execute(&mut image)
+            .is_err());
+    }
+}
@@ -0,0 +1,177 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! Flatten alpha onto a solid background
+//!
+//! Formats like PPM or baseline JPEG can't store alpha, so an image with
+//! transparency has to be composited onto a background before encoding,
+//! otherwise transparent pixels show whatever color happens to be stored in them.
+use zune_core::bit_depth::BitType;
+use zune_core::colorspace::ColorSpace;
+use zune_image::errors::ImageErrors;
+use zune_image::image::Image;
+use zune_image::metadata::AlphaState;
+use zune_image::traits::OperationsTrait;
+
+/// Composite an image with alpha onto a solid background color, removing the alpha channel
+///
+/// RGBA and ARGB images become RGB, BGRA becomes BGR and LumaA becomes Luma,
+/// images without alpha are left untouched.
+///
+/// Both straight and premultiplied alpha images are supported, the alpha state
+/// is read from the image metadata.
+///
+/// # Example
+/// ```
+/// use zune_core::colorspace::ColorSpace;
+/// use zune_image::image::Image;
+/// use zune_image::traits::OperationsTrait;
+/// use zune_imageprocs::flatten_alpha::FlattenAlpha;
+///
+/// // a half transparent black pixel
+/// let mut image = Image::from_u8(&[0, 0, 0, 128], 1, 1, ColorSpace::RGBA);
+///
+/// FlattenAlpha::new([1.0, 1.0, 1.0]).execute(&mut image).unwrap();
+///
+/// assert_eq!(image.colorspace(), ColorSpace::RGB);
+/// assert_eq!(image.flatten_to_u8()[0], [127, 127, 127]);
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub struct FlattenAlpha {
+    background: [f32; 3]
+}
+
+impl FlattenAlpha {
+    /// Create a new flatten operation
+    ///
+    /// # Arguments
+    /// - background_color: Red, green and blue of the background in `0.0..=1.0`,
+    ///   luma images use its BT.601 luma
+    #[must_use]
+    pub fn new(background_color: [f32; 3]) -> FlattenAlpha {
+        FlattenAlpha {
+            background: background_color
+        }
+    }
+}
+
+impl OperationsTrait for FlattenAlpha {
+    fn name(&self) -> &'static str {
+        "Flatten Alpha"
+    }
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn execute_impl(&self, image: &mut Image) -> Result<(), ImageErrors> {
+        let colorspace = image.colorspace();
+
+        let Some(alpha_position) = colorspace.alpha_position() else {
+            return Ok(());
+        };
+        let [r, g, b] = self.background;
+
+        // background in the order of color channels
+        let background = match colorspace {
+            ColorSpace::LumaA => vec![0.299 * r + 0.587 * g + 0.114 * b],
+            ColorSpace::BGRA => vec![b, g, r],
+            _ => vec![r, g, b]
+        };
+        let premultiplied = image.metadata().is_premultiplied_alpha();
+        let depth = image.depth().bit_type();
+
+        for frame in image.frames_mut() {
+            let (before, rest) = frame
+                .channels_mut(colorspace, false)
+                .split_at_mut(alpha_position);
+            let Some((alpha, after)) = rest.split_first_mut() else {
+                return Err(ImageErrors::GenericStr("Missing alpha channel"));
+            };
+            let colors = before.iter_mut().chain(after.iter_mut());
+
+            for (channel, background) in colors.zip(&background) {
+                match depth {
+                    BitType::U8 => flatten(
+                        channel.reinterpret_as_mut::<u8>()?,
+                        alpha.reinterpret_as::<u8>()?,
+                        background * 255.0,
+                        255.0,
+                        premultiplied,
+                        |x| x.round() as u8
+                    ),
+                    BitType::U16 => flatten(
+                        channel.reinterpret_as_mut::<u16>()?,
+                        alpha.reinterpret_as::<u16>()?,
+                        background * 65535.0,
+                        65535.0,
+                        premultiplied,
+                        |x| x.round() as u16
+                    ),
+                    BitType::F32 => flatten(
+                        channel.reinterpret_as_mut::<f32>()?,
+                        alpha.reinterpret_as::<f32>()?,
+                        *background,
+                        1.0,
+                        premultiplied,
+                        |x| x
+                    ),
+                    d => return Err(ImageErrors::ImageOperationNotImplemented(self.name(), d))
+                }
+            }
+        }
+        image.convert_color(self.output_colorspace(colorspace))?;
+        image.metadata_mut().set_alpha(AlphaState::NonPreMultiplied);
+
+        Ok(())
+    }
+
+    fn supported_colorspaces(&self) -> &'static [ColorSpace] {
+        &[
+            ColorSpace::Luma,
+            ColorSpace::LumaA,
+            ColorSpace::RGB,
+            ColorSpace::RGBA,
+            ColorSpace::BGR,
+            ColorSpace::BGRA,
+            ColorSpace::ARGB
+        ]
+    }
+
+    fn supported_types(&self) -> &'static [BitType] {
+        &[BitType::U8, BitType::U16, BitType::F32]
+    }
+
+    fn output_colorspace(&self, input: ColorSpace) -> ColorSpace {
+        match input {
+            ColorSpace::LumaA => ColorSpace::Luma,
+            ColorSpace::RGBA | ColorSpace::ARGB => ColorSpace::RGB,
+            ColorSpace::BGRA => ColorSpace::BGR,
+            _ => input
+        }
+    }
+}
+
+/// Composite a color channel onto `background`, all values in native units
+///
+/// Integer conversions saturate, float results are not clamped so HDR values survive
+fn flatten<T: Copy>(
+    channel: &mut [T], alpha: &[T], background: f32, max: f32, premultiplied: bool,
+    convert: impl Fn(f32) -> T
+) where
+    f32: From<T>
+{
+    for (pixel, alpha) in channel.iter_mut().zip(alpha) {
+        let alpha = (f32::from(*alpha) / max).clamp(0.0, 1.0);
+        let color = f32::from(*pixel);
+
+        let flattened = if premultiplied {
+            color + background * (1.0 - alpha)
+        } else {
+            background + (color - background) * alpha
+        };
+        *pixel = convert(flattened);
+    }
+}
@@ -0,0 +1,233 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! Flood fill
+//!
+//! Starting from a seed pixel, replace the color of every connected pixel
+//! similar to the seed, like the paint bucket tool in image editors.
+//!
+//! Pixels are connected when they touch horizontally or vertically (4-connectivity),
+//! and similar when every channel, including alpha, is within a tolerance of the seed pixel.
+//!
+//! # Algorithm
+//! This uses a scanline fill, each span of similar pixels in a row is filled at once
+//! and only a single seed per span is pushed for the rows above and below, which keeps the
+//! stack small compared to a naive pixel by pixel fill.
+use zune_core::bit_depth::BitType;
+use zune_image::errors::ImageErrors;
+use zune_image::image::Image;
+use zune_image::traits::OperationsTrait;
+
+use crate::traits::NumOps;
+
+/// Fill the region connected to a pixel with a color
+///
+/// # Example
+/// Paint the white background of an RGB image red
+/// ```
+/// use zune_core::colorspace::ColorSpace;
+/// use zune_image::image::Image;
+/// use zune_image::traits::OperationsTrait;
+/// use zune_imageprocs::flood_fill::FloodFill;
+///
+/// let mut image = Image::fill(255_u8, ColorSpace::RGB, 100, 100);
+///
+/// FloodFill::new(0, 0, &[255.0, 0.0, 0.0], 10.0)
+///     .execute(&mut image)
+///     .unwrap();
+/// ```
+#[derive(Clone, Debug)]
+pub struct FloodFill {
+    x:         usize,
+    y:         usize,
+    color:     Vec<f32>,
+    tolerance: f32
+}
+
+impl FloodFill {
+    /// Create a new flood fill operation
+    ///
+    /// # Arguments
+    /// - x: Column of the seed pixel
+    /// - y: Row of the seed pixel
+    /// - color: The fill color, one value per channel in the image colorspace order, e.g
+    ///   `[r, g, b, a]` for RGBA images. Values are in the range of the image depth, they
+    ///   saturate at the maximum for integer images, and are stored as is for float images.
+    /// - tolerance: Maximum difference between a channel of a pixel and the seed pixel
+    ///   for the pixel to be filled, in the range of the image depth. Zero only fills
+    ///   pixels identical to the seed pixel.
+    #[must_use]
+    pub fn new(x: usize, y: usize, color: &[f32], tolerance: f32) -> FloodFill {
+        FloodFill {
+            x,
+            y,
+            color: color.to_vec(),
+            tolerance
+        }
+    }
+}
+
+impl OperationsTrait for FloodFill {
+    fn name(&self) -> &'static str {
+        "Flood Fill"
+    }
+
+    fn execute_impl(&self, image: &mut Image) -> Result<(), ImageErrors> {
+        let (width, height) = image.dimensions();
+        let colorspace = image.colorspace();
+        let depth = image.depth().bit_type();
+
+        if self.x >= width || self.y >= height {
+            return Err(ImageErrors::GenericString(format!(
+                "Flood fill seed ({},{}) is outside of the {width}x{height} image",
+                self.x, self.y
+            )));
+        }
+        if self.color.len() != colorspace.num_components() {
+            return Err(ImageErrors::GenericString(format!(
+                "Flood fill color has {} values but {colorspace:?} has {} channels",
+                self.color.len(),
+                colorspace.num_components()
+            )));
+        }
+        let seed = self.y * width + self.x;
+
+        for frame in image.frames_mut() {
+            let channels = frame.channels_mut(colorspace, false);
+
+            let mut similar = vec![true; width * height];
+
+            for channel in channels.iter() {
+                match depth {
+                    BitType::U8 => {
+                        mark_similar::<u8>(
+                            channel.reinterpret_as()?,
+                            seed,
+                            self.tolerance,
+                            &mut similar
+                        );
+                    }
+                    BitType::U16 => {
+                        mark_similar::<u16>(
+                            channel.reinterpret_as()?,
+                            seed,
+                            self.tolerance,
+                            &mut similar
+                        );
+                    }
+                    BitType::F32 => {
+                        mark_similar::<f32>(
+                            channel.reinterpret_as()?,
+                            seed,
+                            self.tolerance,
+                            &mut similar
+                        );
+                    }
+                    d => return Err(ImageErrors::ImageOperationNotImplemented(self.name(), d))
+                }
+            }
+            let filled = flood_fill_mask(&similar, width, height, self.x, self.y);
+
+            for (channel, value) in channels.iter_mut().zip(&self.color) {
+                match depth {
+                    BitType::U8 => fill::<u8>(channel.reinterpret_as_mut()?, &filled, *value),
+                    BitType::U16 => fill::<u16>(channel.reinterpret_as_mut()?, &filled, *value),
+                    BitType::F32 => fill::<f32>(channel.reinterpret_as_mut()?, &filled, *value),
+                    d => return Err(ImageErrors::ImageOperationNotImplemented(self.name(), d))
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn supported_types(&self) -> &'static [BitType] {
+        &[BitType::U8, BitType::U16, BitType::F32]
+    }
+}
+
+/// Clear pixels in `similar` whose value differs from the seed by more than `tolerance`
+fn mark_similar<T: Copy>(channel: &[T], seed: usize, tolerance: f32, similar: &mut [bool])
+where
+    f32: From<T>
+{
+    let seed = f32::from(channel[seed]);
+
+    for (similar, pixel) in similar.iter_mut().zip(channel) {
+        *similar &= (f32::from(*pixel) - seed).abs() <= tolerance;
+    }
+}
+
+fn fill<T: Copy + NumOps<T>>(channel: &mut [T], filled: &[bool], value: f32) {
+    // float to int casts saturate, so out of range values are clamped
+    let value = T::from_f32(value);
+
+    for (pixel, _) in channel.iter_mut().zip(filled).filter(|(_, x)| **x) {
+        *pixel = value;
+    }
+}
+
+/// Find the pixels connected to `(x, y)` through pixels that can be filled
+///
+/// # Arguments
+/// - fillable: Mask of `width*height` pixels, `true` for pixels that can be filled
+/// - width: Mask width
+/// - height: Mask height
+/// - x: Column of the seed pixel
+/// - y: Row of the seed pixel
+///
+/// # Returns
+/// A mask with `true` for every pixel reached from the seed, if the seed pixel isn't
+/// fillable, nothing is reached.
+///
+/// # Panics
+/// If `(x, y)` is outside the mask or `fillable` is smaller than `width*height`
+#[must_use]
+pub fn flood_fill_mask(
+    fillable: &[bool], width: usize, height: usize, x: usize, y: usize
+) -> Vec<bool> {
+    let mut filled = vec![false; width * height];
+    let mut stack = vec![(x, y)];
+
+    let can_fill = |filled: &[bool], index: usize| fillable[index] && !filled[index];
+
+    while let Some((x, y)) = stack.pop() {
+        let row = y * width;
+
+        if !can_fill(&filled, row + x) {
+            continue;
+        }
+        // extend the span as far as possible in both directions
+        let mut left = x;
+        while left > 0 && can_fill(&filled, row + left - 1) {
+            left -= 1;
+        }
+        let mut right = x;
+        while right + 1 < width && can_fill(&filled, row + right + 1) {
+            right += 1;
+        }
+        filled[row + left..=row + right].fill(true);
+
+        // one seed for every span touching this one in the rows above and below
+        let neighbours = [y.checked_sub(1), Some(y + 1).filter(|y| *y < height)];
+
+        for neighbour in neighbours.into_iter().flatten() {
+            let neighbour_row = neighbour * width;
+            let mut in_span = false;
+
+            for column in left..=right {
+                let fillable = can_fill(&filled, neighbour_row + column);
+
+                if fillable && !in_span {
+                    stack.push((column, neighbour));
+                }
+                in_span = fillable;
+            }
+        }
+    }
+    filled
+}
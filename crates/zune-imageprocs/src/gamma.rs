@@ -25,9 +25,11 @@
 //!
 use zune_core::bit_depth::BitType;
 use zune_core::log::trace;
+#[cfg(feature = "threads")]
+use zune_core::threads::ThreadPool;
 use zune_image::errors::ImageErrors;
 use zune_image::image::Image;
-use zune_image::traits::OperationsTrait;
+use zune_image::traits::{LightSpace, OperationsTrait};
 
 use crate::traits::NumOps;
 
@@ -98,40 +100,32 @@ impl OperationsTrait for Gamma {
         {
             trace!("Running gamma correction in multithreaded mode");
 
-            std::thread::scope(|s| {
-                let mut errors = vec![];
-                for channel in image.channels_mut(false) {
-                    let t = s.spawn(|| match depth.bit_type() {
-                        BitType::U16 => {
-                            gamma(channel.reinterpret_as_mut::<u16>()?, self.value, max_value);
-                            Ok(())
-                        }
-                        BitType::U8 => {
-                            gamma(channel.reinterpret_as_mut::<u8>()?, self.value, max_value);
-                            Ok(())
-                        }
-                        BitType::F32 => {
-                            // for floats, we can't use LUT tables, the scope is too big
-                            let value_inv = 1.0 / f32::from(max_value);
-
-                            channel
-                                .reinterpret_as_mut::<f32>()?
-                                .iter_mut()
-                                .for_each(|x| {
-                                    *x = value_inv * x.powf(self.value);
-                                });
-                            Ok(())
-                        }
-                        d => {
-                            return Err(ImageErrors::ImageOperationNotImplemented(self.name(), d));
-                        }
-                    });
-                    errors.push(t);
+            ThreadPool::new().try_for_each(image.channels_mut(false), |channel| {
+                match depth.bit_type() {
+                    BitType::U16 => {
+                        gamma(channel.reinterpret_as_mut::<u16>()?, self.value, max_value);
+                        Ok(())
+                    }
+                    BitType::U8 => {
+                        gamma(channel.reinterpret_as_mut::<u8>()?, self.value, max_value);
+                        Ok(())
+                    }
+                    BitType::F32 => {
+                        // for floats, we can't use LUT tables, the scope is too big
+                        let value_inv = 1.0 / f32::from(max_value);
+
+                        channel
+                            .reinterpret_as_mut::<f32>()?
+                            .iter_mut()
+                            .for_each(|x| {
+                                *x = value_inv * x.powf(self.value);
+                            });
+                        Ok(())
+                    }
+                    d => {
+                        return Err(ImageErrors::ImageOperationNotImplemented(self.name(), d));
+                    }
                 }
-                errors
-                    .into_iter()
-                    .map(|x| x.join().unwrap())
-                    .collect::<Result<Vec<()>, ImageErrors>>()
             })?;
         }
         Ok(())
@@ -139,6 +133,10 @@ impl OperationsTrait for Gamma {
     fn supported_types(&self) -> &'static [BitType] {
         &[BitType::U8, BitType::U16, BitType::F32]
     }
+
+    fn light_space(&self) -> LightSpace {
+        LightSpace::Encoded
+    }
 }
 
 #[allow(
@@ -19,11 +19,18 @@
 
 use zune_core::bit_depth::BitType;
 use zune_core::log::trace;
+#[cfg(feature = "threads")]
+use zune_core::threads::ThreadPool;
+use zune_image::cancel;
+#[cfg(feature = "threads")]
+use zune_image::cancel::CancellationToken;
 use zune_image::errors::ImageErrors;
 use zune_image::image::Image;
-use zune_image::traits::OperationsTrait;
+use zune_image::traits::{OperationsTrait, UnsignedSample};
 
+use crate::traits::NumOps;
 use crate::transpose;
+use crate::utils::match_unsigned;
 
 #[derive(Default)]
 pub struct GaussianBlur {
@@ -55,33 +62,21 @@ impl OperationsTrait for GaussianBlur {
         {
             trace!("Running gaussian blur in single threaded mode");
 
-            match depth.bit_type() {
-                BitType::U8 => {
-                    let mut temp = vec![0; width * height];
+            match_unsigned!(
+                depth.bit_type(),
+                T => {
+                    let mut temp = vec![T::default(); width * height];
 
                     for channel in image.get_channels_mut(false) {
-                        gaussian_blur_u8(
-                            channel.reinterpret_as_mut::<u8>()?,
-                            &mut temp,
-                            width,
-                            height,
-                            self.sigma
-                        );
-                    }
-                }
-                BitType::U16 => {
-                    let mut temp = vec![0; width * height];
-
-                    for channel in image.get_channels_mut(false) {
-                        gaussian_blur_u16(
-                            channel.reinterpret_as_mut::<u16>()?,
+                        gaussian_blur_unsigned::<T>(
+                            channel.reinterpret_as_mut()?,
                             &mut temp,
                             width,
                             height,
                             self.sigma
                         );
                     }
-                }
+                },
                 BitType::F32 => {
                     let mut temp = vec![0.0; width * height];
                     for channel in image.get_channels_mut(false) {
@@ -100,65 +95,50 @@ impl OperationsTrait for GaussianBlur {
                         d
                     ))
                 }
-            }
+            );
         }
 
         #[cfg(feature = "threads")]
         {
             trace!("Running gaussian blur in multithreaded mode");
-            std::thread::scope(|s| {
-                let mut errors = vec![];
-                // blur each channel on a separate thread
-                for channel in image.channels_mut(false) {
-                    let result = s.spawn(|| match depth.bit_type() {
-                        BitType::U8 => {
-                            let mut temp = vec![0; width * height];
-
-                            gaussian_blur_u8(
-                                channel.reinterpret_as_mut::<u8>()?,
-                                &mut temp,
-                                width,
-                                height,
-                                self.sigma
-                            );
-                            Ok(())
-                        }
-                        BitType::U16 => {
-                            let mut temp = vec![0; width * height];
-
-                            gaussian_blur_u16(
-                                channel.reinterpret_as_mut::<u16>()?,
-                                &mut temp,
-                                width,
-                                height,
-                                self.sigma
-                            );
-                            Ok(())
-                        }
-                        BitType::F32 => {
-                            let mut temp = vec![0.0; width * height];
-
-                            gaussian_blur_f32(
-                                channel.reinterpret_as_mut()?,
-                                &mut temp,
-                                width,
-                                height,
-                                self.sigma
-                            );
-                            Ok(())
-                        }
-                        d => return Err(ImageErrors::ImageOperationNotImplemented(self.name(), d))
-                    });
-                    errors.push(result);
-                }
-                errors
-                    .into_iter()
-                    .map(|x| x.join().unwrap())
-                    .collect::<Result<Vec<()>, ImageErrors>>()
+            // workers don't see the cancellation token of this thread
+            let token = cancel::current();
+
+            ThreadPool::new().try_for_each(image.channels_mut(false), |channel| {
+                let _scope = token.as_ref().map(CancellationToken::enter);
+
+                match_unsigned!(
+                    depth.bit_type(),
+                    T => {
+                        let mut temp = vec![T::default(); width * height];
+
+                        gaussian_blur_unsigned::<T>(
+                            channel.reinterpret_as_mut()?,
+                            &mut temp,
+                            width,
+                            height,
+                            self.sigma
+                        );
+                        Ok(())
+                    },
+                    BitType::F32 => {
+                        let mut temp = vec![0.0; width * height];
+
+                        gaussian_blur_f32(
+                            channel.reinterpret_as_mut()?,
+                            &mut temp,
+                            width,
+                            height,
+                            self.sigma
+                        );
+                        Ok(())
+                    }
+                    d => return Err(ImageErrors::ImageOperationNotImplemented(self.name(), d))
+                )
             })?;
         }
 
-        Ok(())
+        cancel::check()
     }
     fn supported_types(&self) -> &'static [BitType] {
         &[BitType::U8, BitType::U16]
@@ -208,48 +188,6 @@ fn create_box_gauss(sigma: f32) -> [usize; 3] {
     return radii.map(|c| if (c % 2) == 0 { c + 1 } else { c });
 }
 
-/// Carry out a gaussian blur on bytes that represent a single image channel
-///
-///
-/// # Arguments
-/// - in_out_image: A single image channel, we will store blurred pixels in that same buffer
-/// - scratch_space: Buffer used to store intermediate components, dimensions must be equal to
-///    `in_out_image`
-///  - width,height: Dimensions of the image
-///  - sigma: A measure of how much to blur the image by.
-pub fn gaussian_blur_u16(
-    in_out_image: &mut [u16], scratch_space: &mut [u16], width: usize, height: usize, sigma: f32
-) {
-    // use the box blur implementation
-    let blur_radii = create_box_gauss(sigma);
-
-    for (pos, blur_radius) in blur_radii.iter().enumerate() {
-        // carry out horizontal box blur
-        // for the first iteration, samples are written to scratch space,
-        // so the next iteration, samples should be read from scratch space, as that is our input
-        match pos % 2 {
-            0 => crate::box_blur::box_blur_inner(in_out_image, scratch_space, width, *blur_radius),
-            1 => crate::box_blur::box_blur_inner(scratch_space, in_out_image, width, *blur_radius),
-            _ => unreachable!()
-        };
-    }
-    // transpose
-    // we do three iterations above, so when that is done, results will always be in
-    // scratch_space, so wr transpose writing to in_out_image which is used below
-    transpose::transpose_u16(scratch_space, in_out_image, width, height);
-
-    for (pos, blur_radius) in blur_radii.iter().enumerate() {
-        // carry out horizontal box blur
-        match pos % 2 {
-            0 => crate::box_blur::box_blur_inner(in_out_image, scratch_space, height, *blur_radius),
-            1 => crate::box_blur::box_blur_inner(scratch_space, in_out_image, height, *blur_radius),
-            _ => unreachable!()
-        };
-    }
-    // transpose back
-    transpose::transpose_u16(scratch_space, in_out_image, height, width);
-}
-
 pub fn gaussian_blur_f32(
     in_out_image: &mut [f32], scratch_space: &mut [f32], width: usize, height: usize, sigma: f32
 ) {
@@ -303,7 +241,7 @@ pub fn gaussian_blur_f32(
     transpose::transpose_generic(scratch_space, in_out_image, height, width);
 }
 
-/// Carry out a gaussian blur on bytes that represent a single image channel
+/// Carry out a gaussian blur on a single channel of unsigned samples
 ///
 ///
 /// # Arguments
@@ -312,9 +250,12 @@ pub fn gaussian_blur_f32(
 ///    `in_out_image`
 ///  - width,height: Dimensions of the image
 ///  - sigma: A measure of how much to blur the image by.
-pub fn gaussian_blur_u8(
-    in_out_image: &mut [u8], scratch_space: &mut [u8], width: usize, height: usize, sigma: f32
-) {
+pub fn gaussian_blur_unsigned<T>(
+    in_out_image: &mut [T], scratch_space: &mut [T], width: usize, height: usize, sigma: f32
+) where
+    T: UnsignedSample + NumOps<T>,
+    u32: From<T>
+{
     // use the box blur implementation
     let blur_radii = create_box_gauss(sigma);
 
@@ -356,7 +297,7 @@ pub fn gaussian_blur_u8(
     // transpose
     // we do three iterations above, so when that is done, results will always be in
     // scratch_space, so wr transpose writing to in_out_image which is used below
-    transpose::transpose_u8(scratch_space, in_out_image, width, height);
+    transpose::transpose_unsigned(scratch_space, in_out_image, width, height);
 
     for (pos, blur_radius) in blur_radii.iter().enumerate() {
         // carry out horizontal box blur
@@ -367,5 +308,19 @@ pub fn gaussian_blur_u8(
         };
     }
     // transpose back
-    transpose::transpose_u8(scratch_space, in_out_image, height, width);
+    transpose::transpose_unsigned(scratch_space, in_out_image, height, width);
+}
+
+#[deprecated(since = "0.4.13", note = "use `gaussian_blur_unsigned`")]
+pub fn gaussian_blur_u16(
+    in_out_image: &mut [u16], scratch_space: &mut [u16], width: usize, height: usize, sigma: f32
+) {
+    gaussian_blur_unsigned(in_out_image, scratch_space, width, height, sigma);
+}
+
+#[deprecated(since = "0.4.13", note = "use `gaussian_blur_unsigned`")]
+pub fn gaussian_blur_u8(
+    in_out_image: &mut [u8], scratch_space: &mut [u8], width: usize, height: usize, sigma: f32
+) {
+    gaussian_blur_unsigned(in_out_image, scratch_space, width, height, sigma);
 }
@@ -0,0 +1,310 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! Ordered halftone (dither) an image
+//!
+//! Every channel is reduced to a few levels, black and white by default, and the
+//! lost precision is traded for a regular pattern whose density follows the
+//! original value, like a printed halftone.
+//!
+//! # Algorithm
+//! ```text
+//! steps = levels - 1
+//! pixel = floor(pixel * steps + threshold[y % size][x % size]) / steps
+//! ```
+//! where pixels are normalized to `0..=1` and `threshold` is a `size` x `size`
+//! [Bayer matrix](https://en.wikipedia.org/wiki/Ordered_dithering) scaled to `0..1`.
+use zune_core::bit_depth::BitType;
+use zune_core::colorspace::ColorSpace;
+use zune_image::errors::ImageErrors;
+use zune_image::image::Image;
+use zune_image::traits::{LightSpace, OperationsTrait};
+
+/// Reduce an image to a few levels with an ordered dither pattern
+///
+/// # Alpha channel
+/// - Alpha channel is ignored
+///
+/// # Example
+/// ```
+/// use zune_core::colorspace::ColorSpace;
+/// use zune_image::image::Image;
+/// use zune_image::traits::OperationsTrait;
+/// use zune_imageprocs::halftone::Halftone;
+///
+/// let mut image = Image::fill(128_u8, ColorSpace::Luma, 8, 8);
+/// Halftone::new(4).execute(&mut image).unwrap();
+///
+/// // mid gray becomes a checkerboard of black and white pixels
+/// let channels = image.channels_ref(false);
+/// let pixels = channels[0].reinterpret_as::<u8>().unwrap();
+///
+/// assert_eq!(pixels.iter().filter(|x| **x == 255).count(), 32);
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub struct Halftone {
+    matrix_size: usize,
+    levels:      usize
+}
+
+impl Halftone {
+    /// Create a new halftone operation with two levels per channel
+    ///
+    /// # Arguments
+    /// - matrix_size: Width and height of the dither pattern, a power of two from 2 to 16.
+    ///   Larger patterns reproduce more shades but are more visible
+    #[must_use]
+    pub fn new(matrix_size: usize) -> Halftone {
+        Halftone {
+            matrix_size,
+            levels: 2
+        }
+    }
+    /// Set the number of levels every channel is reduced to, at least 2
+    ///
+    /// Levels are spread evenly over the range of the image depth
+    #[must_use]
+    pub fn set_levels(mut self, levels: usize) -> Self {
+        self.levels = levels;
+        self
+    }
+}
+
+impl OperationsTrait for Halftone {
+    fn name(&self) -> &'static str {
+        "Halftone"
+    }
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn execute_impl(&self, image: &mut Image) -> Result<(), ImageErrors> {
+        if !(2..=16).contains(&self.matrix_size) || !self.matrix_size.is_power_of_two() {
+            return Err(ImageErrors::GenericString(format!(
+                "Halftone matrix size must be 2, 4, 8 or 16, got {}",
+                self.matrix_size
+            )));
+        }
+        if self.levels < 2 {
+            return Err(ImageErrors::GenericStr("Halftone needs at least 2 levels"));
+        }
+        let (width, height) = image.dimensions();
+        let depth = image.depth().bit_type();
+        // 10 and 12 bit images are stored as u16 but don't use the whole range
+        let max_value = f32::from(image.depth().max_value());
+        let matrix = bayer_matrix(self.matrix_size);
+
+        for channel in image.channels_mut(true) {
+            match depth {
+                BitType::U8 => halftone(
+                    channel.reinterpret_as_mut::<u8>()?,
+                    width,
+                    height,
+                    &matrix,
+                    self.levels,
+                    255.0,
+                    |x| x.round() as u8
+                ),
+                BitType::U16 => halftone(
+                    channel.reinterpret_as_mut::<u16>()?,
+                    width,
+                    height,
+                    &matrix,
+                    self.levels,
+                    max_value,
+                    |x| x.round() as u16
+                ),
+                BitType::F32 => halftone(
+                    channel.reinterpret_as_mut::<f32>()?,
+                    width,
+                    height,
+                    &matrix,
+                    self.levels,
+                    1.0,
+                    |x| x
+                ),
+                d => return Err(ImageErrors::ImageOperationNotImplemented(self.name(), d))
+            }
+        }
+        Ok(())
+    }
+
+    fn supported_colorspaces(&self) -> &'static [ColorSpace] {
+        &[
+            ColorSpace::Luma,
+            ColorSpace::LumaA,
+            ColorSpace::RGB,
+            ColorSpace::RGBA,
+            ColorSpace::BGR,
+            ColorSpace::BGRA,
+            ColorSpace::ARGB,
+            ColorSpace::CMYK
+        ]
+    }
+
+    fn supported_types(&self) -> &'static [BitType] {
+        &[BitType::U8, BitType::U16, BitType::F32]
+    }
+
+    fn light_space(&self) -> LightSpace {
+        LightSpace::Encoded
+    }
+}
+
+/// Return the rows of a `size` x `size` Bayer matrix
+///
+/// Thresholds are in `0..1`, centered in the range each of them covers
+///
+/// # Panics
+/// If `size` is not a power of two
+#[must_use]
+#[allow(clippy::cast_precision_loss)]
+pub fn bayer_matrix(size: usize) -> Vec<Vec<f32>> {
+    assert!(size.is_power_of_two(), "{size} is not a power of two");
+
+    let mut matrix = vec![0_usize];
+    let mut n = 1;
+
+    // every step splits each cell into a 2x2 block ordered 0 2 / 3 1
+    while n < size {
+        let mut next = vec![0; 4 * n * n];
+
+        for y in 0..n {
+            for x in 0..n {
+                let value = 4 * matrix[y * n + x];
+
+                next[y * 2 * n + x] = value;
+                next[y * 2 * n + x + n] = value + 2;
+                next[(y + n) * 2 * n + x] = value + 3;
+                next[(y + n) * 2 * n + x + n] = value + 1;
+            }
+        }
+        matrix = next;
+        n *= 2;
+    }
+    let cells = (size * size) as f32;
+
+    matrix
+        .chunks_exact(size)
+        .map(|row| row.iter().map(|x| (*x as f32 + 0.5) / cells).collect())
+        .collect()
+}
+
+/// Halftone a single channel
+///
+/// # Arguments
+/// - channel: Channel to halftone, in place
+/// - width: Channel width
+/// - height: Channel height
+/// - matrix: Rows of a square threshold matrix, see [`bayer_matrix`]
+/// - levels: Number of levels in the output, at least 2
+/// - max: Value of white for the channel depth
+/// - convert: Converts a level back to a pixel
+#[allow(clippy::cast_precision_loss)]
+pub fn halftone<T: Copy>(
+    channel: &mut [T], width: usize, height: usize, matrix: &[Vec<f32>], levels: usize, max: f32,
+    convert: impl Fn(f32) -> T
+) where
+    f32: From<T>
+{
+    if width == 0 {
+        return;
+    }
+    let steps = (levels - 1) as f32;
+
+    for (y, row) in channel.chunks_exact_mut(width).take(height).enumerate() {
+        let thresholds = &matrix[y % matrix.len()];
+
+        for (pixel, threshold) in row.iter_mut().zip(thresholds.iter().cycle()) {
+            let value = (f32::from(*pixel) / max).clamp(0.0, 1.0) * steps;
+            let level = (value + threshold).floor().min(steps);
+
+            *pixel = convert(level / steps * max);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use zune_core::bit_depth::BitDepth;
+    use zune_core::colorspace::ColorSpace;
+    use zune_image::image::Image;
+    use zune_image::traits::OperationsTrait;
+
+    use crate::halftone::{bayer_matrix, Halftone};
+
+    #[test]
+    fn test_bayer_matrix() {
+        let matrix: Vec<f32> = bayer_matrix(4)
+            .iter()
+            .flatten()
+            .map(|x| x * 16.0 - 0.5)
+            .collect();
+
+        #[rustfmt::skip]
+        let expected = [
+            0.0, 8.0, 2.0, 10.0,
+            12.0, 4.0, 14.0, 6.0,
+            3.0, 11.0, 1.0, 9.0,
+            15.0, 7.0, 13.0, 5.0
+        ];
+        assert_eq!(matrix, expected);
+    }
+
+    #[test]
+    fn test_halftone_density() {
+        for value in (0..=65535_u16).step_by(4096).chain([65535]) {
+            let mut image = Image::fill(value, ColorSpace::Luma, 16, 16);
+
+            Halftone::new(16).execute(&mut image).unwrap();
+
+            let channels = image.channels_ref(false);
+            let pixels = channels[0].reinterpret_as::<u16>().unwrap();
+
+            assert!(pixels.iter().all(|x| *x == 0 || *x == 65535));
+            // the fraction of white pixels in a tile of the pattern is the value, within a pixel
+            let white = pixels.iter().filter(|x| **x == 65535).count();
+            let expected = usize::from(value) * 256 / 65535;
+
+            assert!(
+                white.abs_diff(expected) <= 1,
+                "{value}: {white} white pixels"
+            );
+        }
+    }
+
+    #[test]
+    fn test_halftone_twelve_bit() {
+        let mut image = Image::fill(2048_u16, ColorSpace::Luma, 16, 16);
+        image.set_depth(BitDepth::Twelve);
+
+        Halftone::new(16).execute(&mut image).unwrap();
+
+        let channels = image.channels_ref(false);
+        let pixels = channels[0].reinterpret_as::<u16>().unwrap();
+
+        // white is the largest 12 bit value, and half the pixels are white
+        assert!(pixels.iter().all(|x| *x == 0 || *x == 4095));
+        assert_eq!(pixels.iter().filter(|x| **x == 4095).count(), 128);
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn test_halftone_levels() {
+        let mut image = Image::fill(0.3_f32, ColorSpace::RGB, 4, 4);
+
+        Halftone::new(2).set_levels(3).execute(&mut image).unwrap();
+
+        for channel in image.channels_ref(false) {
+            let pixels = channel.reinterpret_as::<f32>().unwrap();
+            assert!(pixels.iter().all(|x| *x == 0.0 || *x == 0.5));
+        }
+        let mut image = Image::fill(0_u8, ColorSpace::RGB, 4, 4);
+
+        assert!(Halftone::new(3).execute(&mut image).is_err());
+        assert!(Halftone::new(4).set_levels(1).execute(&mut image).is_err());
+    }
+}
@@ -19,6 +19,7 @@ use std::f32::consts::PI;
 
 use zune_core::bit_depth::BitType;
 use zune_core::colorspace::ColorSpace;
+use zune_image::core_filters::colorspace::CONVERTIBLE_COLORSPACES;
 use zune_image::errors::ImageErrors;
 use zune_image::image::Image;
 use zune_image::traits::OperationsTrait;
@@ -141,6 +142,11 @@ impl OperationsTrait for HsvAdjust {
     fn supported_types(&self) -> &'static [BitType] {
         &[BitType::F32, BitType::U8, BitType::U16]
     }
+
+    fn supported_colorspaces(&self) -> &'static [ColorSpace] {
+        // we convert to RGBA and back
+        &CONVERTIBLE_COLORSPACES
+    }
 }
 
 #[allow(clippy::many_single_char_names)]
@@ -21,6 +21,7 @@ use zune_core::colorspace::ColorSpace;
 use zune_image::errors::ImageErrors;
 use zune_image::image::Image;
 use zune_image::traits::OperationsTrait;
+use zune_image::view::ImageViewMut;
 
 use crate::traits::NumOps;
 
@@ -60,6 +61,15 @@ impl OperationsTrait for Invert {
         Ok(())
     }
 
+    fn execute_view_impl(&self, view: &mut ImageViewMut) -> Result<(), ImageErrors> {
+        match view.depth().bit_type() {
+            BitType::U8 => view.for_each_row_mut(true, invert::<u8>),
+            BitType::U16 => view.for_each_row_mut(true, invert::<u16>),
+            BitType::F32 => view.for_each_row_mut(true, invert::<f32>),
+            d => Err(ImageErrors::ImageOperationNotImplemented(self.name(), d))
+        }
+    }
+
     fn supported_colorspaces(&self) -> &'static [ColorSpace] {
         &[
             ColorSpace::RGB,
@@ -53,33 +53,59 @@ pub mod bilateral_filter;
 pub mod blend;
 pub mod box_blur;
 pub mod brighten;
+pub mod channel_shift;
+pub mod chroma_key;
 pub mod color_matrix;
+pub mod composite;
+pub mod concat;
 pub mod contrast;
 pub mod convolve;
 pub mod crop;
+pub mod curves;
+pub mod diff_highlight;
+pub mod distance_transform;
 pub mod exposure;
+pub mod extent;
+pub mod flatten_alpha;
 pub mod flip;
+pub mod flood_fill;
 pub mod flop;
 pub mod gamma;
 pub mod gaussian_blur;
+pub mod halftone;
 pub mod histogram;
 pub mod hsv_adjust;
 pub mod invert;
+pub mod lut3d;
+pub mod masked;
 pub mod mathops;
 pub mod median;
 pub mod mirror;
+pub mod montage;
+pub mod non_local_means;
+pub mod normalize;
+pub mod on_channels;
 pub mod pad;
+pub mod pixelate;
+pub mod predict;
 pub mod premul_alpha;
 mod prewitt;
 pub mod resize;
 pub mod rotate;
+pub mod scale_with_fixed_borders;
 pub mod scharr;
+pub mod smart_sharpen;
 pub mod sobel;
 pub mod spatial;
+pub mod sprite_sheet;
 pub mod spatial_ops;
 pub mod stretch_contrast;
+pub mod swap_channels;
 pub mod threshold;
+pub mod tonemap;
 pub mod traits;
 pub mod transpose;
 pub mod unsharpen;
+pub mod vignette;
+pub mod warp;
 mod utils;
@@ -0,0 +1,340 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! 3D lookup tables for color grading
+//!
+//! A 3D LUT maps every RGB color to a new RGB color, it's stored as a cube of
+//! `size x size x size` output colors sampled at evenly spaced inputs, and colors
+//! between the samples are trilinearly interpolated.
+//!
+//! LUTs are usually distributed as Adobe/Resolve `.cube` files, which look like
+//!
+//! ```text
+//! TITLE "Warm"
+//! LUT_3D_SIZE 2
+//! # red changes fastest, then green, then blue
+//! 0.0 0.0 0.0
+//! 1.0 0.0 0.0
+//! 0.0 1.0 0.0
+//! ...
+//! ```
+//! and can be loaded with [`Lut3D::load_cube`]
+use std::path::Path;
+
+use zune_core::bit_depth::BitType;
+use zune_core::colorspace::ColorSpace;
+use zune_core::log::warn;
+use zune_image::errors::ImageErrors;
+use zune_image::image::Image;
+use zune_image::traits::{LightSpace, OperationsTrait};
+
+/// Apply a 3D lookup table to an RGB image
+///
+/// Integer images are normalized to `0.0..=1.0` before the lookup,
+/// and the results are scaled back, float images are used as is.
+///
+/// # Alpha channel
+/// - Alpha channel is ignored
+///
+/// # Example
+/// ```
+/// use zune_core::colorspace::ColorSpace;
+/// use zune_image::image::Image;
+/// use zune_image::traits::OperationsTrait;
+/// use zune_imageprocs::lut3d::Lut3D;
+///
+/// // swaps red and blue
+/// let cube = "LUT_3D_SIZE 2
+/// 0 0 0
+/// 0 0 1
+/// 0 1 0
+/// 0 1 1
+/// 1 0 0
+/// 1 0 1
+/// 1 1 0
+/// 1 1 1";
+/// let lut = Lut3D::from_cube(cube).unwrap();
+///
+/// let mut image = Image::from_u8(&[255, 10, 0], 1, 1, ColorSpace::RGB);
+/// lut.execute(&mut image).unwrap();
+///
+/// assert_eq!(image.flatten_to_u8()[0], [0, 10, 255]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct Lut3D {
+    size:       usize,
+    table:      Vec<[f32; 3]>,
+    domain_min: [f32; 3],
+    domain_max: [f32; 3]
+}
+
+impl Lut3D {
+    /// Create a LUT from its samples
+    ///
+    /// # Arguments
+    /// - size: Number of samples along each axis
+    /// - table: `size^3` output colors, red changes fastest, then green, then blue
+    ///
+    /// # Errors
+    /// If size is less than 2 or the table doesn't have `size^3` entries
+    pub fn new(size: usize, table: Vec<[f32; 3]>) -> Result<Lut3D, ImageErrors> {
+        if size < 2 {
+            return Err(ImageErrors::GenericString(format!(
+                "LUT size must be at least 2, found {size}"
+            )));
+        }
+        if Some(table.len()) != size.checked_pow(3) {
+            return Err(ImageErrors::GenericString(format!(
+                "LUT of size {size} needs {} entries, found {}",
+                size.saturating_pow(3),
+                table.len()
+            )));
+        }
+        Ok(Lut3D {
+            size,
+            table,
+            domain_min: [0.0; 3],
+            domain_max: [1.0; 3]
+        })
+    }
+
+    /// Load a LUT from an Adobe `.cube` file
+    ///
+    /// # Errors
+    /// If the file can't be read or isn't a valid 3D `.cube` file
+    pub fn load_cube<P: AsRef<Path>>(path: P) -> Result<Lut3D, ImageErrors> {
+        let contents = std::fs::read_to_string(path)?;
+        Lut3D::from_cube(&contents)
+    }
+
+    /// Parse the contents of an Adobe `.cube` file
+    ///
+    /// # Errors
+    /// If the contents aren't a valid 3D `.cube` file, 1D LUTs are not supported
+    pub fn from_cube(contents: &str) -> Result<Lut3D, ImageErrors> {
+        let mut size = None;
+        let mut domain_min = [0.0; 3];
+        let mut domain_max = [1.0; 3];
+        let mut table = vec![];
+
+        for (number, line) in contents.lines().enumerate() {
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let error = |message: &str| {
+                ImageErrors::GenericString(format!(
+                    "Invalid cube file, line {}: {message}",
+                    number + 1
+                ))
+            };
+            let (keyword, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+
+            match keyword {
+                "TITLE" => (),
+                "LUT_3D_SIZE" => {
+                    size = Some(
+                        rest.trim()
+                            .parse::<usize>()
+                            .map_err(|_| error("invalid size"))?
+                    );
+                }
+                "LUT_1D_SIZE" => return Err(error("1D LUTs are not supported")),
+                "DOMAIN_MIN" => {
+                    domain_min = parse_triplet(rest).ok_or_else(|| error("invalid domain"))?;
+                }
+                "DOMAIN_MAX" => {
+                    domain_max = parse_triplet(rest).ok_or_else(|| error("invalid domain"))?;
+                }
+                "LUT_3D_INPUT_RANGE" => {
+                    let range: Vec<f32> = rest
+                        .split_whitespace()
+                        .map(str::parse)
+                        .collect::<Result<_, _>>()
+                        .map_err(|_| error("invalid input range"))?;
+
+                    let [min, max] = range[..] else {
+                        return Err(error("input range needs two values"));
+                    };
+                    domain_min = [min; 3];
+                    domain_max = [max; 3];
+                }
+                _ if keyword.starts_with(|x: char| x.is_ascii_digit() || x == '-' || x == '.') => {
+                    table.push(parse_triplet(line).ok_or_else(|| error("invalid color"))?);
+                }
+                _ => {
+                    warn!("Ignoring unknown cube keyword {keyword}");
+                }
+            }
+        }
+        let size = size.ok_or(ImageErrors::GenericStr(
+            "Invalid cube file, missing LUT_3D_SIZE"
+        ))?;
+
+        if domain_min
+            .iter()
+            .zip(&domain_max)
+            .any(|(min, max)| min >= max)
+        {
+            return Err(ImageErrors::GenericStr(
+                "Invalid cube file, DOMAIN_MIN must be smaller than DOMAIN_MAX"
+            ));
+        }
+        let mut lut = Lut3D::new(size, table)?;
+        lut.domain_min = domain_min;
+        lut.domain_max = domain_max;
+
+        Ok(lut)
+    }
+
+    /// Look up a color, trilinearly interpolating between samples
+    ///
+    /// Inputs outside the LUT domain are clamped to it
+    #[allow(
+        clippy::cast_precision_loss,
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss
+    )]
+    #[must_use]
+    pub fn lookup(&self, rgb: [f32; 3]) -> [f32; 3] {
+        let last = self.size - 1;
+
+        // position of the color in the cube, and the two samples around it on every axis
+        let mut low = [0; 3];
+        let mut high = [0; 3];
+        let mut fraction = [0.0; 3];
+
+        for axis in 0..3 {
+            let (min, max) = (self.domain_min[axis], self.domain_max[axis]);
+            let position = ((rgb[axis] - min) / (max - min)).clamp(0.0, 1.0) * last as f32;
+            // NaN inputs end up at zero
+            let position = if position.is_nan() { 0.0 } else { position };
+
+            low[axis] = (position as usize).min(last);
+            high[axis] = (low[axis] + 1).min(last);
+            fraction[axis] = position - low[axis] as f32;
+        }
+        let sample = |r: usize, g: usize, b: usize| self.table[r + self.size * (g + self.size * b)];
+        let lerp = |a: [f32; 3], b: [f32; 3], t: f32| [0, 1, 2].map(|i| a[i] + (b[i] - a[i]) * t);
+        let [r0, g0, b0] = low;
+        let [r1, g1, b1] = high;
+        let [fr, fg, fb] = fraction;
+
+        let c00 = lerp(sample(r0, g0, b0), sample(r1, g0, b0), fr);
+        let c10 = lerp(sample(r0, g1, b0), sample(r1, g1, b0), fr);
+        let c01 = lerp(sample(r0, g0, b1), sample(r1, g0, b1), fr);
+        let c11 = lerp(sample(r0, g1, b1), sample(r1, g1, b1), fr);
+
+        lerp(lerp(c00, c10, fg), lerp(c01, c11, fg), fb)
+    }
+}
+
+fn parse_triplet(input: &str) -> Option<[f32; 3]> {
+    let mut values = input.split_whitespace().map(|x| x.parse::<f32>().ok());
+
+    let triplet = [values.next()??, values.next()??, values.next()??];
+
+    values.next().is_none().then_some(triplet)
+}
+
+impl OperationsTrait for Lut3D {
+    fn name(&self) -> &'static str {
+        "3D LUT"
+    }
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn execute_impl(&self, image: &mut Image) -> Result<(), ImageErrors> {
+        let colorspace = image.colorspace();
+        let depth = image.depth().bit_type();
+
+        for frame in image.frames_mut() {
+            let ((ColorSpace::ARGB, [_, red, green, blue])
+            | (ColorSpace::RGB | ColorSpace::RGBA, [red, green, blue, ..])
+            | (ColorSpace::BGR | ColorSpace::BGRA, [blue, green, red, ..])) =
+                (colorspace, frame.channels_mut(colorspace, false))
+            else {
+                return Err(ImageErrors::UnsupportedColorspace(
+                    colorspace,
+                    self.name(),
+                    self.supported_colorspaces()
+                ));
+            };
+            match depth {
+                BitType::U8 => apply_lut(
+                    self,
+                    [
+                        red.reinterpret_as_mut::<u8>()?,
+                        green.reinterpret_as_mut::<u8>()?,
+                        blue.reinterpret_as_mut::<u8>()?
+                    ],
+                    255.0,
+                    |x| x.round() as u8
+                ),
+                BitType::U16 => apply_lut(
+                    self,
+                    [
+                        red.reinterpret_as_mut::<u16>()?,
+                        green.reinterpret_as_mut::<u16>()?,
+                        blue.reinterpret_as_mut::<u16>()?
+                    ],
+                    65535.0,
+                    |x| x.round() as u16
+                ),
+                BitType::F32 => apply_lut(
+                    self,
+                    [
+                        red.reinterpret_as_mut::<f32>()?,
+                        green.reinterpret_as_mut::<f32>()?,
+                        blue.reinterpret_as_mut::<f32>()?
+                    ],
+                    1.0,
+                    |x| x
+                ),
+                d => return Err(ImageErrors::ImageOperationNotImplemented(self.name(), d))
+            }
+        }
+        Ok(())
+    }
+
+    fn supported_colorspaces(&self) -> &'static [ColorSpace] {
+        &[
+            ColorSpace::RGB,
+            ColorSpace::RGBA,
+            ColorSpace::BGR,
+            ColorSpace::BGRA,
+            ColorSpace::ARGB
+        ]
+    }
+
+    fn supported_types(&self) -> &'static [BitType] {
+        &[BitType::U8, BitType::U16, BitType::F32]
+    }
+
+    fn light_space(&self) -> LightSpace {
+        LightSpace::Encoded
+    }
+}
+
+/// Apply `lut` to red, green and blue channels in place
+///
+/// Values are divided by `max` before the lookup, and the results
+/// multiplied by `max` before `convert`
+fn apply_lut<T: Copy>(lut: &Lut3D, rgb: [&mut [T]; 3], max: f32, convert: impl Fn(f32) -> T)
+where
+    f32: From<T>
+{
+    let [red, green, blue] = rgb;
+
+    for ((r, g), b) in red.iter_mut().zip(green.iter_mut()).zip(blue.iter_mut()) {
+        let input = [*r, *g, *b].map(|x| f32::from(x) / max);
+        let [new_r, new_g, new_b] = lut.lookup(input).map(|x| convert(x * max));
+
+        (*r, *g, *b) = (new_r, new_g, new_b);
+    }
+}
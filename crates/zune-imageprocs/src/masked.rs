@@ -0,0 +1,218 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! Apply an operation through a mask
+//!
+//! A mask is a grayscale image as large as the image, where each pixel weighs
+//! how much of the processed pixel replaces the original one, black keeps the
+//! original, white takes the processed pixel and values in between blend them.
+//!
+//! This lets any operation be applied selectively, e.g blur the background of
+//! an image with a mask made by [`Threshold`](crate::threshold::Threshold) or
+//! [`FloodFill`](crate::flood_fill::FloodFill), without every operation needing its own
+//! masking option.
+use zune_core::bit_depth::{BitDepth, BitType};
+use zune_core::colorspace::ColorSpace;
+use zune_image::errors::ImageErrors;
+use zune_image::image::Image;
+use zune_image::metadata::AlphaState;
+use zune_image::traits::{LightSpace, OperationsTrait};
+
+/// Run an operation and blend its result with the original image using a mask
+///
+/// The mask is converted to [`ColorSpace::Luma`], its first frame is used for all
+/// frames of the image. The inner operation must not change the image
+/// dimensions, colorspace or depth.
+///
+/// # Example
+/// Blur the left half of an image
+/// ```
+/// use zune_core::colorspace::ColorSpace;
+/// use zune_image::image::Image;
+/// use zune_image::traits::OperationsTrait;
+/// use zune_imageprocs::box_blur::BoxBlur;
+/// use zune_imageprocs::masked::Masked;
+///
+/// let mut image = Image::fill(100_u8, ColorSpace::RGB, 10, 10);
+///
+/// let mask_pixels: Vec<u8> = (0..100).map(|x| if x % 10 < 5 { 255 } else { 0 }).collect();
+/// let mask = Image::from_u8(&mask_pixels, 10, 10, ColorSpace::Luma);
+///
+/// Masked::new(mask, Box::new(BoxBlur::new(3)))
+///     .execute(&mut image)
+///     .unwrap();
+/// ```
+pub struct Masked {
+    mask:  Image,
+    inner: Box<dyn OperationsTrait>
+}
+
+impl Masked {
+    /// Create a new operation running `inner` where `mask` is non zero
+    ///
+    /// # Arguments
+    /// - mask: Weight of the processed pixels, of the same dimensions as the image
+    /// - inner: The operation to run
+    #[must_use]
+    pub fn new(mask: Image, inner: Box<dyn OperationsTrait>) -> Masked {
+        Masked { mask, inner }
+    }
+
+    /// Return the weight of every pixel of the mask, from 0.0 to 1.0
+    fn weights(&self) -> Result<Vec<f32>, ImageErrors> {
+        let mut mask = self.mask.clone();
+
+        if mask.colorspace() != ColorSpace::Luma {
+            mask.convert_color(ColorSpace::Luma)?;
+        }
+        mask.convert_depth(BitDepth::Float32)?;
+
+        let frame = mask
+            .frames_ref()
+            .first()
+            .ok_or(ImageErrors::NoImageBuffer)?;
+        let channel = &frame.channels_ref(ColorSpace::Luma, false)[0];
+
+        Ok(channel
+            .reinterpret_as::<f32>()?
+            .iter()
+            .map(|x| x.clamp(0.0, 1.0))
+            .collect())
+    }
+}
+
+impl OperationsTrait for Masked {
+    fn name(&self) -> &'static str {
+        "Masked"
+    }
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn execute_impl(&self, image: &mut Image) -> Result<(), ImageErrors> {
+        if self.mask.dimensions() != image.dimensions() {
+            return Err(ImageErrors::GenericString(format!(
+                "Mask dimensions {:?} do not match image dimensions {:?}",
+                self.mask.dimensions(),
+                image.dimensions()
+            )));
+        }
+        let weights = self.weights()?;
+        let original = image.clone();
+
+        self.inner.execute(image)?;
+
+        if image.dimensions() != original.dimensions()
+            || image.colorspace() != original.colorspace()
+            || image.depth() != original.depth()
+            || image.frames_len() != original.frames_len()
+        {
+            return Err(ImageErrors::GenericString(format!(
+                "{} cannot run through a mask, it changes the image layout",
+                self.inner.name()
+            )));
+        }
+        let colorspace = image.colorspace();
+        let depth = image.depth().bit_type();
+
+        for (frame, original_frame) in image.frames_mut().iter_mut().zip(original.frames_ref()) {
+            let original_channels = original_frame.channels_ref(colorspace, false);
+
+            for (channel, original_channel) in frame
+                .channels_mut(colorspace, false)
+                .iter_mut()
+                .zip(original_channels)
+            {
+                match depth {
+                    BitType::U8 => blend(
+                        original_channel.reinterpret_as::<u8>()?,
+                        channel.reinterpret_as_mut::<u8>()?,
+                        &weights,
+                        f32::from,
+                        |x| x.round() as u8
+                    ),
+                    BitType::U16 => blend(
+                        original_channel.reinterpret_as::<u16>()?,
+                        channel.reinterpret_as_mut::<u16>()?,
+                        &weights,
+                        f32::from,
+                        |x| x.round() as u16
+                    ),
+                    BitType::F32 => blend(
+                        original_channel.reinterpret_as::<f32>()?,
+                        channel.reinterpret_as_mut::<f32>()?,
+                        &weights,
+                        |x| x,
+                        |x| x
+                    ),
+                    d => return Err(ImageErrors::ImageOperationNotImplemented(self.name(), d))
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn supported_colorspaces(&self) -> &'static [ColorSpace] {
+        self.inner.supported_colorspaces()
+    }
+
+    fn supported_types(&self) -> &'static [BitType] {
+        self.inner.supported_types()
+    }
+
+    fn alpha_state(&self) -> AlphaState {
+        self.inner.alpha_state()
+    }
+
+    fn light_space(&self) -> LightSpace {
+        self.inner.light_space()
+    }
+}
+
+/// Move `processed` pixels back towards `original` ones, keeping `weight` of the processed value
+fn blend<T: Copy>(
+    original: &[T], processed: &mut [T], weights: &[f32], to_f32: impl Fn(T) -> f32,
+    from_f32: impl Fn(f32) -> T
+) {
+    for ((new, old), weight) in processed.iter_mut().zip(original).zip(weights) {
+        let (new_value, old_value) = (to_f32(*new), to_f32(*old));
+
+        *new = from_f32(old_value + (new_value - old_value) * weight);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use zune_core::colorspace::ColorSpace;
+    use zune_image::image::Image;
+    use zune_image::traits::OperationsTrait;
+
+    use crate::invert::Invert;
+    use crate::masked::Masked;
+
+    #[test]
+    fn test_mask_weights_pixels() {
+        let mut image = Image::from_u8(&[0, 0, 0, 100], 2, 2, ColorSpace::Luma);
+        let mask = Image::from_u8(&[0, 255, 128, 255], 2, 2, ColorSpace::Luma);
+
+        Masked::new(mask, Box::new(Invert::new()))
+            .execute(&mut image)
+            .unwrap();
+
+        // untouched, inverted, half way and inverted
+        assert_eq!(image.flatten_to_u8()[0], [0, 255, 128, 155]);
+    }
+
+    #[test]
+    fn test_mask_dimensions_must_match() {
+        let mut image = Image::fill(0_u8, ColorSpace::RGB, 4, 4);
+        let mask = Image::fill(255_u8, ColorSpace::Luma, 2, 2);
+
+        assert!(Masked::new(mask, Box::new(Invert::new()))
+            .execute(&mut image)
+            .is_err());
+    }
+}
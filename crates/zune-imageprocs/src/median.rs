@@ -40,13 +40,16 @@
 
 use zune_core::bit_depth::BitType;
 use zune_core::log::trace;
+#[cfg(feature = "threads")]
+use zune_core::threads::ThreadPool;
 use zune_image::channel::Channel;
 use zune_image::errors::ImageErrors;
 use zune_image::image::Image;
-use zune_image::traits::OperationsTrait;
+use zune_image::traits::{OperationsTrait, UnsignedSample};
 
 use crate::pad::{pad, PadMethod};
-use crate::utils::z_prefetch;
+use crate::traits::NumOps;
+use crate::utils::{match_unsigned, z_prefetch};
 
 /// Median returns a new image in which each pixel is the median of its neighbors.
 ///
@@ -84,17 +87,11 @@ impl OperationsTrait for Median {
             for channel in image.get_channels_mut(false) {
                 let mut new_channel = Channel::new_with_bit_type(channel.len(), depth.bit_type());
 
-                match depth.bit_type() {
-                    BitType::U16 => median_u16(
-                        channel.reinterpret_as::<u16>()?,
-                        new_channel.reinterpret_as_mut::<u16>()?,
-                        self.radius,
-                        width,
-                        height
-                    ),
-                    BitType::U8 => median_u8(
-                        channel.reinterpret_as::<u8>()?,
-                        new_channel.reinterpret_as_mut::<u8>()?,
+                match_unsigned!(
+                    depth.bit_type(),
+                    T => median_unsigned::<T>(
+                        channel.reinterpret_as()?,
+                        new_channel.reinterpret_as_mut()?,
                         self.radius,
                         width,
                         height
@@ -105,7 +102,7 @@ impl OperationsTrait for Median {
                             d
                         ))
                     }
-                }
+                );
                 *channel = new_channel;
             }
         }
@@ -113,44 +110,22 @@ impl OperationsTrait for Median {
         {
             trace!("Running median filter multithreaded mode");
 
-            std::thread::scope(|s| {
-                let mut errors = vec![];
-                for channel in image.channels_mut(true) {
-                    let result = s.spawn(|| {
-                        let mut new_channel =
-                            Channel::new_with_bit_type(channel.len(), depth.bit_type());
-
-                        match depth.bit_type() {
-                            BitType::U16 => median_u16(
-                                channel.reinterpret_as::<u16>()?,
-                                new_channel.reinterpret_as_mut::<u16>()?,
-                                self.radius,
-                                width,
-                                height
-                            ),
-                            BitType::U8 => median_u8(
-                                channel.reinterpret_as::<u8>()?,
-                                new_channel.reinterpret_as_mut::<u8>()?,
-                                self.radius,
-                                width,
-                                height
-                            ),
-                            d => {
-                                return Err(ImageErrors::ImageOperationNotImplemented(
-                                    self.name(),
-                                    d
-                                ))
-                            }
-                        }
-                        *channel = new_channel;
-                        Ok(())
-                    });
-                    errors.push(result);
-                }
-                errors
-                    .into_iter()
-                    .map(|x| x.join().unwrap())
-                    .collect::<Result<Vec<()>, ImageErrors>>()
+            ThreadPool::new().try_for_each(image.channels_mut(true), |channel| {
+                let mut new_channel = Channel::new_with_bit_type(channel.len(), depth.bit_type());
+
+                match_unsigned!(
+                    depth.bit_type(),
+                    T => median_unsigned::<T>(
+                        channel.reinterpret_as()?,
+                        new_channel.reinterpret_as_mut()?,
+                        self.radius,
+                        width,
+                        height
+                    ),
+                    d => return Err(ImageErrors::ImageOperationNotImplemented(self.name(), d))
+                );
+                *channel = new_channel;
+                Ok(())
             })?;
         }
         Ok(())
@@ -159,9 +134,16 @@ impl OperationsTrait for Median {
         &[BitType::U8, BitType::U16]
     }
 }
+/// Median filter a single channel of unsigned samples
+///
+/// # Arguments
+/// - in_channel: The channel to filter
+/// - out_channel: Where filtered samples are stored, must have the same length as `in_channel`
+/// - radius: Radius of the median window
+/// - width,height: Dimensions of the image
 #[allow(clippy::cast_possible_truncation)]
-pub fn median_u16(
-    in_channel: &[u16], out_channel: &mut [u16], radius: usize, width: usize, height: usize
+pub fn median_unsigned<T: UnsignedSample + NumOps<T>>(
+    in_channel: &[T], out_channel: &mut [T], radius: usize, width: usize, height: usize
 ) {
     /*
      * Okay rico, we run a tight shift here
@@ -173,21 +155,19 @@ pub fn median_u16(
      * that need to be preserved across iterations, yep why the need for lambdas
      */
 
-    // array containing our histogram for each median window
-    let mut histogram_arr = vec![0_u32; 65536];
-    // give it a fixed size for optimizer to remove bounds check
-    let histogram: &mut [u32; 65536] = histogram_arr.get_mut(..).unwrap().try_into().unwrap();
+    // array containing our histogram for each median window, one entry per sample value
+    let mut histogram = vec![0_u32; T::LEVELS];
 
     let radius_size = (2 * radius) + 1;
 
     // vector containing items that will be dropped from the
     // histogram in the next iteration
-    let mut to_be_dropped = vec![0; radius_size];
+    let mut to_be_dropped = vec![T::default(); radius_size];
     // the current position of our window, used for handling edges and
     // knowing when we went to another row
     let mut counter = 0;
 
-    let func = |array: &[u16]| -> u16 {
+    let func = |array: &[T]| -> T {
         // the position of our median
         let median_pos = array.len() / 2;
 
@@ -198,30 +178,30 @@ pub fn median_u16(
             histogram.fill(0);
             // add everything
             for c in array {
-                histogram[usize::from(*c)] += 1;
+                histogram[c.to_index()] += 1;
             }
         } else {
             // drop items that fell off from previous run
             for x in &to_be_dropped {
-                histogram[usize::from(*x)] -= 1;
+                histogram[x.to_index()] -= 1;
             }
             // add the new window values added to the histogram
             // these are the rightmost values
             array.chunks_exact(radius_size).for_each(|v| {
-                let to_add = usize::from(*v.last().unwrap());
+                let to_add = v.last().unwrap().to_index();
                 histogram[to_add] += 1;
             });
         }
 
         // iterate through our histogram to find the median
         let mut accum = 0;
-        let mut median = 0;
+        let mut median = T::default();
 
         for (pos, v) in histogram.iter().enumerate() {
             accum += *v;
             if accum >= median_pos as u32 {
                 // we found the median
-                median = pos as u16;
+                median = T::from_usize(pos);
                 break;
             }
         }
@@ -251,89 +231,20 @@ pub fn median_u16(
     );
     spatial_median(&padded_input, out_channel, radius, width, height, func);
 }
-#[allow(clippy::cast_possible_truncation)]
+
+#[deprecated(since = "0.4.13", note = "use `median_unsigned`")]
+pub fn median_u16(
+    in_channel: &[u16], out_channel: &mut [u16], radius: usize, width: usize, height: usize
+) {
+    median_unsigned(in_channel, out_channel, radius, width, height);
+}
+
+#[deprecated(since = "0.4.13", note = "use `median_unsigned`")]
 pub fn median_u8(
     in_channel: &[u8], out_channel: &mut [u8], radius: usize, width: usize, height: usize
 ) {
-    // duplicated from above, but uses array instead of vec, and
-
-    // array containing our histogram for each median window
-    let mut histogram = [0_u32; 256];
-
-    let radius_size = (2 * radius) + 1;
-
-    // vector containing items that will be dropped from the
-    // histogram in the next iteration
-    let mut to_be_dropped = vec![0; radius_size];
-    // the current position of our window, used for handling edges and
-    // knowing when we went to another row
-    let mut counter = 0;
-
-    let func = |array: &[u8]| -> u8 {
-        // the position of our median
-        let median_pos = array.len() / 2;
-
-        if (counter % width) == 0 {
-            // when we are starting a new row
-
-            // zero out histogram
-            histogram.fill(0);
-            // add everything
-            for c in array {
-                histogram[usize::from(*c)] += 1;
-            }
-        } else {
-            // drop items that fell off from previous run
-            for x in &to_be_dropped {
-                histogram[usize::from(*x)] -= 1;
-            }
-            // add the new window values added to the histogram
-            // these are the rightmost values
-            array.chunks_exact(radius_size).for_each(|v| {
-                let to_add = usize::from(*v.last().unwrap());
-                histogram[to_add] += 1;
-            });
-        }
-
-        // iterate through our histogram to find the median
-        let mut accum = 0;
-        let mut median = 0;
-
-        for (pos, v) in histogram.iter().enumerate() {
-            accum += *v;
-            if accum >= median_pos as u32 {
-                // we found the median
-                median = pos as u8;
-                break;
-            }
-        }
-        counter += 1;
-        // set up for our next loop
-        // add the things to be dropped in since they fell off our window in the to_be_dropped
-        // in the next iteration, they will be subtracted from the histogram
-
-        debug_assert!(array.chunks_exact(radius_size).len() == to_be_dropped.len());
-        array
-            .chunks_exact(radius_size)
-            .zip(to_be_dropped.iter_mut())
-            .for_each(|(x, v)| {
-                *v = x[0];
-            });
-        median
-    };
-
-    // pad input
-    let padded_input = pad(
-        in_channel,
-        width,
-        height,
-        radius,
-        radius,
-        PadMethod::Replicate
-    );
-    spatial_median(&padded_input, out_channel, radius, width, height, func);
+    median_unsigned(in_channel, out_channel, radius, width, height);
 }
-
 pub fn spatial_median<T, F>(
     in_channel: &[T], out_channel: &mut [T], radius: usize, width: usize, height: usize,
     mut function: F
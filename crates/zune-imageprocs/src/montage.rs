@@ -0,0 +1,205 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! Lay out multiple images in a grid
+//!
+//! Every image occupies a cell as large as the largest image, smaller images are centered in
+//! their cells and cells are separated by padding filled with the background color
+//!
+//! ```text
+//!  ┌────────────────────────────┐
+//!  │ ┌──────┐ ┌──────┐ ┌──────┐ │
+//!  │ │  1   │ │  2   │ │  3   │ │
+//!  │ └──────┘ └──────┘ └──────┘ │
+//!  │ ┌──────┐ ┌──────┐          │
+//!  │ │  4   │ │  5   │          │
+//!  │ └──────┘ └──────┘          │
+//!  └────────────────────────────┘
+//! ```
+use zune_core::bit_depth::BitType;
+use zune_core::colorspace::ColorSpace;
+use zune_image::errors::ImageErrors;
+use zune_image::image::Image;
+
+/// Create a montage of images
+///
+/// The output has the colorspace and depth of the first image, other
+/// images are converted to match it.
+/// Only the first frame of animated images is used.
+///
+/// # Example
+/// ```
+/// use zune_core::colorspace::ColorSpace;
+/// use zune_image::image::Image;
+/// use zune_imageprocs::montage::Montage;
+///
+/// let images = vec![Image::fill(255_u8, ColorSpace::RGB, 100, 50); 5];
+///
+/// let montage = Montage::new()
+///     .set_columns(3)
+///     .set_padding(4)
+///     .set_background([0, 0, 0, 255])
+///     .build(&images)
+///     .unwrap();
+///
+/// assert_eq!(montage.dimensions(), (3 * 100 + 4 * 4, 2 * 50 + 3 * 4));
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub struct Montage {
+    columns:    usize,
+    padding:    usize,
+    background: [u8; 4]
+}
+
+impl Default for Montage {
+    fn default() -> Self {
+        Montage {
+            columns:    0,
+            padding:    0,
+            background: [255, 255, 255, 255]
+        }
+    }
+}
+
+impl Montage {
+    /// Create a montage with no padding on a white background,
+    /// with columns chosen to make the grid as square as possible
+    #[must_use]
+    pub fn new() -> Montage {
+        Montage::default()
+    }
+    /// Set the number of columns in the grid, zero picks them automatically
+    #[must_use]
+    pub fn set_columns(mut self, columns: usize) -> Montage {
+        self.columns = columns;
+        self
+    }
+    /// Set the number of pixels between cells and around the grid
+    #[must_use]
+    pub fn set_padding(mut self, padding: usize) -> Montage {
+        self.padding = padding;
+        self
+    }
+    /// Set the background color as 8 bit RGBA
+    ///
+    /// It is converted to the colorspace and depth of the output
+    #[must_use]
+    pub fn set_background(mut self, rgba: [u8; 4]) -> Montage {
+        self.background = rgba;
+        self
+    }
+
+    /// Lay out `images` in a grid, returning the new image
+    ///
+    /// # Errors
+    /// If `images` is empty or images cannot be converted to the colorspace
+    /// and depth of the first image
+    pub fn build(&self, images: &[Image]) -> Result<Image, ImageErrors> {
+        let first = images
+            .first()
+            .ok_or(ImageErrors::GenericStr("No images for the montage"))?;
+
+        let (colorspace, depth) = (first.colorspace(), first.depth());
+
+        let columns = match self.columns {
+            0 => (1..=images.len())
+                .find(|x| x * x >= images.len())
+                .unwrap_or(1),
+            c => c.min(images.len())
+        };
+        let rows = images.len().div_ceil(columns);
+
+        let cell_w = images.iter().map(|x| x.dimensions().0).max().unwrap_or(0);
+        let cell_h = images.iter().map(|x| x.dimensions().1).max().unwrap_or(0);
+
+        let width = columns * cell_w + (columns + 1) * self.padding;
+        let height = rows * cell_h + (rows + 1) * self.padding;
+
+        let mut canvas = self.background_image(colorspace, width, height)?;
+        canvas.convert_depth(depth)?;
+
+        for (position, image) in images.iter().enumerate() {
+            let mut image = image.clone();
+            image.convert_color(colorspace)?;
+            image.convert_depth(depth)?;
+
+            let (img_w, img_h) = image.dimensions();
+
+            if img_w == 0 || img_h == 0 {
+                continue;
+            }
+            let (column, row) = (position % columns, position / columns);
+
+            let x = self.padding + column * (cell_w + self.padding) + (cell_w - img_w) / 2;
+            let y = self.padding + row * (cell_h + self.padding) + (cell_h - img_h) / 2;
+
+            let src_channels = image.frames_ref()[0].channels_ref(colorspace, false);
+            let dst_channels = canvas.frames_mut()[0].channels_mut(colorspace, false);
+
+            for (src, dst) in src_channels.iter().zip(dst_channels.iter_mut()) {
+                match depth.bit_type() {
+                    BitType::U8 => blit::<u8>(
+                        src.reinterpret_as()?,
+                        dst.reinterpret_as_mut()?,
+                        (img_w, img_h),
+                        width,
+                        (x, y)
+                    ),
+                    BitType::U16 => blit::<u16>(
+                        src.reinterpret_as()?,
+                        dst.reinterpret_as_mut()?,
+                        (img_w, img_h),
+                        width,
+                        (x, y)
+                    ),
+                    BitType::F32 => blit::<f32>(
+                        src.reinterpret_as()?,
+                        dst.reinterpret_as_mut()?,
+                        (img_w, img_h),
+                        width,
+                        (x, y)
+                    ),
+                    d => return Err(ImageErrors::ImageOperationNotImplemented("montage", d))
+                }
+            }
+        }
+        Ok(canvas)
+    }
+
+    fn background_image(
+        &self, colorspace: ColorSpace, width: usize, height: usize
+    ) -> Result<Image, ImageErrors> {
+        let pixels: Vec<u8> = self
+            .background
+            .iter()
+            .copied()
+            .cycle()
+            .take(width * height * 4)
+            .collect();
+
+        let mut image = Image::from_u8(&pixels, width, height, ColorSpace::RGBA);
+        image.convert_color(colorspace)?;
+
+        Ok(image)
+    }
+}
+
+/// Copy a `dimensions` sized channel into `dst` at `(x, y)`
+pub(crate) fn blit<T: Copy>(
+    src: &[T], dst: &mut [T], dimensions: (usize, usize), dst_width: usize, (x, y): (usize, usize)
+) {
+    let (width, height) = dimensions;
+
+    for (src_row, dst_row) in src
+        .chunks_exact(width)
+        .zip(dst.chunks_exact_mut(dst_width).skip(y))
+        .take(height)
+    {
+        dst_row[x..x + width].copy_from_slice(src_row);
+    }
+}
@@ -0,0 +1,295 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! Non-local means denoising
+//!
+//! Unlike blurs, which average a pixel with its neighbours, non-local means averages a pixel
+//! with every pixel in a search window whose *surrounding patch* looks similar, so noise is
+//! averaged away while edges and textures, whose patches differ, are kept.
+//!
+//! Each candidate pixel `q` of pixel `p` gets a weight
+//!
+//! ```text
+//! weight = exp(-distance(patch(p), patch(q)) / strength^2)
+//! ```
+//!
+//! where distance is the mean squared difference of the patches, color images
+//! share weights between channels, which avoids color fringes.
+//!
+//! # Performance
+//! Patch distances for a whole image are computed for one offset at a time and summed with
+//! an integral image (Darbon et al., *Fast nonlocal filtering applied to electron cryomicroscopy*),
+//! so the cost is `O(pixels * (2 * search_radius + 1)^2)` regardless of the patch size.
+//!
+//! A description of the algorithm can be found [here](https://www.ipol.im/pub/art/2011/bcm_nlm/)
+use zune_core::bit_depth::BitType;
+use zune_core::colorspace::ColorSpace;
+use zune_image::errors::ImageErrors;
+use zune_image::image::Image;
+use zune_image::traits::OperationsTrait;
+
+/// Denoise an image with non-local means
+///
+/// # Alpha channel
+/// - Alpha channel is ignored
+///
+/// # Example
+/// ```
+/// use zune_core::colorspace::ColorSpace;
+/// use zune_image::image::Image;
+/// use zune_image::traits::OperationsTrait;
+/// use zune_imageprocs::non_local_means::NonLocalMeans;
+///
+/// let mut image = Image::fill(128_u8, ColorSpace::RGB, 100, 100);
+///
+/// NonLocalMeans::new(0.05)
+///     .set_search_radius(3)
+///     .execute(&mut image)
+///     .unwrap();
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub struct NonLocalMeans {
+    strength:      f32,
+    patch_radius:  usize,
+    search_radius: usize
+}
+
+impl NonLocalMeans {
+    /// Create a new non-local means filter
+    ///
+    /// Patch radius defaults to 2 (5x5 patches) and search radius to 5 (11x11 windows)
+    ///
+    /// # Arguments
+    /// - strength: Filtering strength in `0.0..=1.0` units, about the standard deviation of
+    ///   the noise works well, e.g `0.04` for noise with a deviation of 10 in 8 bit images.
+    ///   Larger values remove more noise but also more detail.
+    #[must_use]
+    pub fn new(strength: f32) -> NonLocalMeans {
+        NonLocalMeans {
+            strength,
+            patch_radius: 2,
+            search_radius: 5
+        }
+    }
+    /// Set the radius of patches compared to find similar pixels
+    #[must_use]
+    pub fn set_patch_radius(mut self, radius: usize) -> NonLocalMeans {
+        self.patch_radius = radius;
+        self
+    }
+    /// Set the radius of the window searched for similar pixels
+    ///
+    /// Run time grows with the square of the radius
+    #[must_use]
+    pub fn set_search_radius(mut self, radius: usize) -> NonLocalMeans {
+        self.search_radius = radius;
+        self
+    }
+}
+
+impl OperationsTrait for NonLocalMeans {
+    fn name(&self) -> &'static str {
+        "Non-local Means"
+    }
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn execute_impl(&self, image: &mut Image) -> Result<(), ImageErrors> {
+        if !(self.strength > 0.0 && self.strength.is_finite()) {
+            return Err(ImageErrors::GenericString(format!(
+                "Denoise strength must be positive, found {}",
+                self.strength
+            )));
+        }
+        let (width, height) = image.dimensions();
+        let colorspace = image.colorspace();
+        let depth = image.depth().bit_type();
+
+        let max = match depth {
+            BitType::U8 => 255.0,
+            BitType::U16 => 65535.0,
+            BitType::F32 => 1.0,
+            d => return Err(ImageErrors::ImageOperationNotImplemented(self.name(), d))
+        };
+
+        for frame in image.frames_mut() {
+            let channels = frame.channels_mut(colorspace, true);
+
+            let mut pixels = vec![];
+
+            for channel in channels.iter() {
+                pixels.push(match depth {
+                    BitType::U8 => normalize(channel.reinterpret_as::<u8>()?, max),
+                    BitType::U16 => normalize(channel.reinterpret_as::<u16>()?, max),
+                    _ => channel.reinterpret_as::<f32>()?.to_vec()
+                });
+            }
+            let inputs: Vec<&[f32]> = pixels.iter().map(Vec::as_slice).collect();
+            let denoised = non_local_means(
+                &inputs,
+                width,
+                height,
+                self.strength,
+                self.patch_radius,
+                self.search_radius
+            );
+
+            for (channel, denoised) in channels.iter_mut().zip(&denoised) {
+                match depth {
+                    BitType::U8 => {
+                        denormalize(denoised, channel.reinterpret_as_mut()?, max, |x| {
+                            x.round() as u8
+                        });
+                    }
+                    BitType::U16 => {
+                        denormalize(denoised, channel.reinterpret_as_mut()?, max, |x| {
+                            x.round() as u16
+                        });
+                    }
+                    _ => channel
+                        .reinterpret_as_mut::<f32>()?
+                        .copy_from_slice(denoised)
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn supported_colorspaces(&self) -> &'static [ColorSpace] {
+        &[
+            ColorSpace::Luma,
+            ColorSpace::LumaA,
+            ColorSpace::RGB,
+            ColorSpace::RGBA,
+            ColorSpace::BGR,
+            ColorSpace::BGRA,
+            ColorSpace::ARGB
+        ]
+    }
+
+    fn supported_types(&self) -> &'static [BitType] {
+        &[BitType::U8, BitType::U16, BitType::F32]
+    }
+}
+
+fn normalize<T: Copy>(pixels: &[T], max: f32) -> Vec<f32>
+where
+    f32: From<T>
+{
+    pixels.iter().map(|x| f32::from(*x) / max).collect()
+}
+
+fn denormalize<T>(pixels: &[f32], out: &mut [T], max: f32, convert: impl Fn(f32) -> T) {
+    for (out, pixel) in out.iter_mut().zip(pixels) {
+        *out = convert(*pixel * max);
+    }
+}
+
+/// Denoise channels of an image with non-local means, returning the denoised channels
+///
+/// All channels share the same weights, pixels outside the image replicate the edges
+///
+/// # Arguments
+/// - channels: Channels of the image, each `width * height` pixels
+/// - strength: Filtering strength, in the same units as the pixels
+/// - patch_radius: Radius of the patches compared
+/// - search_radius: Radius of the window searched for similar patches
+#[allow(
+    clippy::cast_precision_loss,
+    clippy::cast_possible_truncation,
+    clippy::cast_possible_wrap
+)]
+#[must_use]
+pub fn non_local_means(
+    channels: &[&[f32]], width: usize, height: usize, strength: f32, patch_radius: usize,
+    search_radius: usize
+) -> Vec<Vec<f32>> {
+    let length = width * height;
+
+    if channels.is_empty() || length == 0 {
+        return channels.iter().map(|x| x.to_vec()).collect();
+    }
+    let mut sums = vec![vec![0.0_f32; length]; channels.len()];
+    let mut weights = vec![0.0_f32; length];
+    let mut distances = vec![0.0_f64; length];
+    // one larger in both dimensions, the first row and column stay zero
+    let stride = width + 1;
+    let mut integral = vec![0.0_f64; stride * (height + 1)];
+
+    let inverse_strength = 1.0 / (strength * strength);
+    let search = search_radius as isize;
+    let shift = |position: usize, offset: isize, size: usize| {
+        position.saturating_add_signed(offset).min(size - 1)
+    };
+
+    for dy in -search..=search {
+        for dx in -search..=search {
+            // squared difference between every pixel and the pixel at the offset
+            for y in 0..height {
+                let sy = shift(y, dy, height);
+
+                for x in 0..width {
+                    let sx = shift(x, dx, width);
+
+                    distances[y * width + x] = channels
+                        .iter()
+                        .map(|c| {
+                            let difference = f64::from(c[y * width + x] - c[sy * width + sx]);
+                            difference * difference
+                        })
+                        .sum();
+                }
+            }
+            for y in 0..height {
+                let mut row_sum = 0.0;
+
+                for x in 0..width {
+                    row_sum += distances[y * width + x];
+                    integral[(y + 1) * stride + x + 1] = integral[y * stride + x + 1] + row_sum;
+                }
+            }
+            // mean patch distance gives the weight of the pixel at the offset
+            for y in 0..height {
+                let (y0, y1) = (
+                    y.saturating_sub(patch_radius),
+                    (y + patch_radius).min(height - 1)
+                );
+                let sy = shift(y, dy, height);
+
+                for x in 0..width {
+                    let (x0, x1) = (
+                        x.saturating_sub(patch_radius),
+                        (x + patch_radius).min(width - 1)
+                    );
+                    let sx = shift(x, dx, width);
+
+                    let patch_sum = integral[(y1 + 1) * stride + x1 + 1]
+                        - integral[y0 * stride + x1 + 1]
+                        - integral[(y1 + 1) * stride + x0]
+                        + integral[y0 * stride + x0];
+                    let area = (x1 - x0 + 1) * (y1 - y0 + 1) * channels.len();
+                    let distance = (patch_sum / area as f64) as f32;
+
+                    let weight = (-distance * inverse_strength).exp();
+
+                    weights[y * width + x] += weight;
+
+                    for (sum, channel) in sums.iter_mut().zip(channels) {
+                        sum[y * width + x] += weight * channel[sy * width + sx];
+                    }
+                }
+            }
+        }
+    }
+    // the pixel itself always has a weight of one, so weights are never zero
+    for sum in &mut sums {
+        for (pixel, weight) in sum.iter_mut().zip(&weights) {
+            *pixel /= weight;
+        }
+    }
+    sums
+}
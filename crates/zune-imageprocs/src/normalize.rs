@@ -0,0 +1,296 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! Bring float samples into a displayable range
+//!
+//! Float images coming from scientific instruments, renderers or
+//! other image processing steps can hold any value, while encoders
+//! and displays expect samples in `0.0..=1.0`.
+//!
+//! [`Normalize`] linearly maps the range of values present in an image to a
+//! target range and [`Clamp`] cuts values outside a range.
+//!
+//! Both operations work on [`BitType::F32`] images only, convert other depths with
+//! [`Depth`](zune_image::core_filters::depth::Depth) first.
+//!
+//! `NaN` samples are ignored when looking for the range of an image and are left
+//! as `NaN` by both operations.
+use std::sync::Once;
+
+use zune_core::bit_depth::BitType;
+use zune_core::log::trace;
+use zune_image::errors::ImageErrors;
+use zune_image::image::Image;
+use zune_image::traits::OperationsTrait;
+
+mod avx2;
+mod neon;
+pub(crate) mod scalar;
+mod tests;
+
+static START: Once = Once::new();
+
+/// Linearly map the values of an image to a range
+///
+/// The smallest value of the color channels is sent to `min` and the largest one to `max`,
+/// the range is taken over all color channels together so that colors are preserved,
+/// wrap the operation in [`OnChannels`](crate::on_channels::OnChannels) to normalize channels
+/// separately.
+///
+/// Alpha channels are left untouched, and images with a single value everywhere
+/// are set to `min`. Images containing infinite values are rejected, use
+/// [`Clamp`] to get rid of them first.
+///
+/// # Example
+/// ```
+/// use zune_core::colorspace::ColorSpace;
+/// use zune_image::image::Image;
+/// use zune_image::traits::OperationsTrait;
+/// use zune_imageprocs::normalize::Normalize;
+///
+/// let pixels = [-250.0, 0.0, 750.0, 1750.0];
+/// let mut image = Image::from_f32(&pixels, 2, 2, ColorSpace::Luma);
+///
+/// Normalize::new(0.0, 1.0).execute(&mut image).unwrap();
+///
+/// let channel = &image.channels_ref(false)[0];
+/// assert_eq!(channel.reinterpret_as::<f32>().unwrap(), &[0.0, 0.125, 0.5, 1.0]);
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub struct Normalize {
+    min: f32,
+    max: f32
+}
+
+impl Normalize {
+    /// Create a new normalize operation
+    ///
+    /// # Arguments
+    /// - min: The value the smallest sample of the image becomes
+    /// - max: The value the largest sample of the image becomes
+    #[must_use]
+    pub fn new(min: f32, max: f32) -> Normalize {
+        Normalize { min, max }
+    }
+}
+
+impl OperationsTrait for Normalize {
+    fn name(&self) -> &'static str {
+        "Normalize"
+    }
+
+    fn execute_impl(&self, image: &mut Image) -> Result<(), ImageErrors> {
+        if !self.min.is_finite() || !self.max.is_finite() {
+            return Err(ImageErrors::GenericString(format!(
+                "Normalize range {}..{} is not finite",
+                self.min, self.max
+            )));
+        }
+        let depth = image.depth().bit_type();
+
+        if depth != BitType::F32 {
+            return Err(ImageErrors::ImageOperationNotImplemented(
+                self.name(),
+                depth
+            ));
+        }
+        let mut range: Option<(f32, f32)> = None;
+
+        for channel in image.channels_ref(true) {
+            if let Some((lo, hi)) = min_max_f32(channel.reinterpret_as::<f32>()?) {
+                range = Some(match range {
+                    Some((min, max)) => (min.min(lo), max.max(hi)),
+                    None => (lo, hi)
+                });
+            }
+        }
+        // empty image, or nothing but NaN
+        let Some((lo, hi)) = range else {
+            return Ok(());
+        };
+        if !lo.is_finite() || !hi.is_finite() {
+            return Err(ImageErrors::GenericStr(
+                "Cannot normalize an image containing infinite values, clamp it first"
+            ));
+        }
+        trace!("Normalizing {lo}..{hi} to {}..{}", self.min, self.max);
+
+        let scale = if hi > lo { (self.max - self.min) / (hi - lo) } else { 0.0 };
+        let offset = self.min - lo * scale;
+
+        for channel in image.channels_mut(true) {
+            scale_offset_f32(channel.reinterpret_as_mut::<f32>()?, scale, offset);
+        }
+        Ok(())
+    }
+
+    fn supported_types(&self) -> &'static [BitType] {
+        &[BitType::F32]
+    }
+}
+
+/// Limit the values of an image to a range
+///
+/// Values below `min` become `min` and those above `max` become `max`,
+/// alpha channels are left untouched.
+///
+/// # Example
+/// ```
+/// use zune_core::colorspace::ColorSpace;
+/// use zune_image::image::Image;
+/// use zune_image::traits::OperationsTrait;
+/// use zune_imageprocs::normalize::Clamp;
+///
+/// let mut image = Image::from_f32(&[-0.5, 0.25, 0.75, 3.0], 2, 2, ColorSpace::Luma);
+///
+/// Clamp::new(0.0, 1.0).execute(&mut image).unwrap();
+///
+/// let channel = &image.channels_ref(false)[0];
+/// assert_eq!(channel.reinterpret_as::<f32>().unwrap(), &[0.0, 0.25, 0.75, 1.0]);
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub struct Clamp {
+    min: f32,
+    max: f32
+}
+
+impl Clamp {
+    /// Create a new clamp operation
+    ///
+    /// # Arguments
+    /// - min: Smallest value allowed, must not be greater than `max`
+    /// - max: Largest value allowed
+    #[must_use]
+    pub fn new(min: f32, max: f32) -> Clamp {
+        Clamp { min, max }
+    }
+}
+
+impl OperationsTrait for Clamp {
+    fn name(&self) -> &'static str {
+        "Clamp"
+    }
+
+    fn execute_impl(&self, image: &mut Image) -> Result<(), ImageErrors> {
+        if self.min.is_nan() || self.max.is_nan() || self.min > self.max {
+            return Err(ImageErrors::GenericString(format!(
+                "Clamp minimum {} must not be greater than maximum {}",
+                self.min, self.max
+            )));
+        }
+        let depth = image.depth().bit_type();
+
+        for channel in image.channels_mut(true) {
+            match depth {
+                BitType::F32 => {
+                    clamp_f32(channel.reinterpret_as_mut::<f32>()?, self.min, self.max);
+                }
+                d => return Err(ImageErrors::ImageOperationNotImplemented(self.name(), d))
+            }
+        }
+        Ok(())
+    }
+
+    fn supported_types(&self) -> &'static [BitType] {
+        &[BitType::F32]
+    }
+}
+
+/// Return the smallest and largest value of `data`, ignoring `NaN`
+///
+/// Returns `None` if `data` is empty or only contains `NaN`
+pub fn min_max_f32(data: &[f32]) -> Option<(f32, f32)> {
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    {
+        #[cfg(feature = "avx2")]
+        {
+            if is_x86_feature_detected!("avx2") {
+                START.call_once(|| {
+                    trace!("Using AVX2 normalize algorithm");
+                });
+                unsafe {
+                    return avx2::min_max_avx2(data);
+                }
+            }
+        }
+    }
+    #[cfg(target_arch = "aarch64")]
+    {
+        #[cfg(feature = "neon")]
+        {
+            if std::arch::is_aarch64_feature_detected!("neon") {
+                START.call_once(|| {
+                    trace!("Using NEON normalize algorithm");
+                });
+                unsafe {
+                    return neon::min_max_neon(data);
+                }
+            }
+        }
+    }
+    scalar::min_max_scalar(data)
+}
+
+/// Replace every value `x` of `data` with `x * scale + offset`
+pub fn scale_offset_f32(data: &mut [f32], scale: f32, offset: f32) {
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    {
+        #[cfg(feature = "avx2")]
+        {
+            if is_x86_feature_detected!("avx2") {
+                unsafe {
+                    return avx2::scale_offset_avx2(data, scale, offset);
+                }
+            }
+        }
+    }
+    #[cfg(target_arch = "aarch64")]
+    {
+        #[cfg(feature = "neon")]
+        {
+            if std::arch::is_aarch64_feature_detected!("neon") {
+                unsafe {
+                    return neon::scale_offset_neon(data, scale, offset);
+                }
+            }
+        }
+    }
+    scalar::scale_offset_scalar(data, scale, offset);
+}
+
+/// Limit every value of `data` to `min..=max`, `NaN` values are kept
+///
+/// # Panics
+/// If `min` is greater than `max` or either of them is `NaN`
+pub fn clamp_f32(data: &mut [f32], min: f32, max: f32) {
+    assert!(min <= max, "min {min} must not be greater than max {max}");
+
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    {
+        #[cfg(feature = "avx2")]
+        {
+            if is_x86_feature_detected!("avx2") {
+                unsafe {
+                    return avx2::clamp_avx2(data, min, max);
+                }
+            }
+        }
+    }
+    #[cfg(target_arch = "aarch64")]
+    {
+        #[cfg(feature = "neon")]
+        {
+            if std::arch::is_aarch64_feature_detected!("neon") {
+                unsafe {
+                    return neon::clamp_neon(data, min, max);
+                }
+            }
+        }
+    }
+    scalar::clamp_scalar(data, min, max);
+}
@@ -0,0 +1,90 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+#![cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#![cfg(feature = "avx2")]
+//! AVX2 normalize and clamp kernels
+//!
+//! These process 8 samples at a time, the tail that doesn't fill a vector
+//! is handled by the scalar code.
+//!
+//! `_mm256_min_ps` and `_mm256_max_ps` return their second operand when either is `NaN`,
+//! operand order is chosen so that the reduction skips `NaN` while clamping keeps it.
+#[cfg(target_arch = "x86")]
+use core::arch::x86::*;
+#[cfg(target_arch = "x86_64")]
+use core::arch::x86_64::*;
+
+use crate::normalize::scalar::{clamp_scalar, min_max_scalar, scale_offset_scalar};
+
+#[target_feature(enable = "avx2")]
+pub(crate) unsafe fn min_max_avx2(data: &[f32]) -> Option<(f32, f32)> {
+    let mut min = _mm256_set1_ps(f32::INFINITY);
+    let mut max = _mm256_set1_ps(f32::NEG_INFINITY);
+
+    let mut chunks = data.chunks_exact(8);
+
+    for chunk in chunks.by_ref() {
+        let x = _mm256_loadu_ps(chunk.as_ptr());
+        // NaN in x keeps the accumulator
+        min = _mm256_min_ps(x, min);
+        max = _mm256_max_ps(x, max);
+    }
+    let mut mins = [0.0_f32; 8];
+    let mut maxs = [0.0_f32; 8];
+
+    _mm256_storeu_ps(mins.as_mut_ptr(), min);
+    _mm256_storeu_ps(maxs.as_mut_ptr(), max);
+
+    let (mut lo, mut hi) = mins
+        .iter()
+        .zip(&maxs)
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), (a, b)| {
+            (lo.min(*a), hi.max(*b))
+        });
+
+    if let Some((tail_lo, tail_hi)) = min_max_scalar(chunks.remainder()) {
+        lo = lo.min(tail_lo);
+        hi = hi.max(tail_hi);
+    }
+    (lo <= hi).then_some((lo, hi))
+}
+
+#[target_feature(enable = "avx2")]
+pub(crate) unsafe fn scale_offset_avx2(data: &mut [f32], scale: f32, offset: f32) {
+    let scale_v = _mm256_set1_ps(scale);
+    let offset_v = _mm256_set1_ps(offset);
+
+    let mut chunks = data.chunks_exact_mut(8);
+
+    for chunk in chunks.by_ref() {
+        let x = _mm256_loadu_ps(chunk.as_ptr());
+        // no fma, to match the scalar rounding
+        let y = _mm256_add_ps(_mm256_mul_ps(x, scale_v), offset_v);
+
+        _mm256_storeu_ps(chunk.as_mut_ptr(), y);
+    }
+    scale_offset_scalar(chunks.into_remainder(), scale, offset);
+}
+
+#[target_feature(enable = "avx2")]
+pub(crate) unsafe fn clamp_avx2(data: &mut [f32], min: f32, max: f32) {
+    let min_v = _mm256_set1_ps(min);
+    let max_v = _mm256_set1_ps(max);
+
+    let mut chunks = data.chunks_exact_mut(8);
+
+    for chunk in chunks.by_ref() {
+        let x = _mm256_loadu_ps(chunk.as_ptr());
+        // NaN in x is passed through both
+        let y = _mm256_max_ps(min_v, _mm256_min_ps(max_v, x));
+
+        _mm256_storeu_ps(chunk.as_mut_ptr(), y);
+    }
+    clamp_scalar(chunks.into_remainder(), min, max);
+}
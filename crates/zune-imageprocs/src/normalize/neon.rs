@@ -0,0 +1,76 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+#![cfg(target_arch = "aarch64")]
+#![cfg(feature = "neon")]
+//! NEON normalize and clamp kernels
+//!
+//! These process 4 samples at a time, the tail that doesn't fill a vector
+//! is handled by the scalar code.
+//!
+//! The reduction uses `vminnmq_f32`/`vmaxnmq_f32`, which skip `NaN` like
+//! `f32::min`, while clamping uses `vminq_f32`/`vmaxq_f32`, which propagate it.
+use core::arch::aarch64::*;
+
+use crate::normalize::scalar::{clamp_scalar, min_max_scalar, scale_offset_scalar};
+
+#[target_feature(enable = "neon")]
+pub(crate) unsafe fn min_max_neon(data: &[f32]) -> Option<(f32, f32)> {
+    let mut min = vdupq_n_f32(f32::INFINITY);
+    let mut max = vdupq_n_f32(f32::NEG_INFINITY);
+
+    let mut chunks = data.chunks_exact(4);
+
+    for chunk in chunks.by_ref() {
+        let x = vld1q_f32(chunk.as_ptr());
+
+        min = vminnmq_f32(min, x);
+        max = vmaxnmq_f32(max, x);
+    }
+    let mut lo = vminnmvq_f32(min);
+    let mut hi = vmaxnmvq_f32(max);
+
+    if let Some((tail_lo, tail_hi)) = min_max_scalar(chunks.remainder()) {
+        lo = lo.min(tail_lo);
+        hi = hi.max(tail_hi);
+    }
+    (lo <= hi).then_some((lo, hi))
+}
+
+#[target_feature(enable = "neon")]
+pub(crate) unsafe fn scale_offset_neon(data: &mut [f32], scale: f32, offset: f32) {
+    let scale_v = vdupq_n_f32(scale);
+    let offset_v = vdupq_n_f32(offset);
+
+    let mut chunks = data.chunks_exact_mut(4);
+
+    for chunk in chunks.by_ref() {
+        let x = vld1q_f32(chunk.as_ptr());
+        // no fused multiply add, to match the scalar rounding
+        let y = vaddq_f32(vmulq_f32(x, scale_v), offset_v);
+
+        vst1q_f32(chunk.as_mut_ptr(), y);
+    }
+    scale_offset_scalar(chunks.into_remainder(), scale, offset);
+}
+
+#[target_feature(enable = "neon")]
+pub(crate) unsafe fn clamp_neon(data: &mut [f32], min: f32, max: f32) {
+    let min_v = vdupq_n_f32(min);
+    let max_v = vdupq_n_f32(max);
+
+    let mut chunks = data.chunks_exact_mut(4);
+
+    for chunk in chunks.by_ref() {
+        let x = vld1q_f32(chunk.as_ptr());
+        let y = vmaxq_f32(vminq_f32(x, max_v), min_v);
+
+        vst1q_f32(chunk.as_mut_ptr(), y);
+    }
+    clamp_scalar(chunks.into_remainder(), min, max);
+}
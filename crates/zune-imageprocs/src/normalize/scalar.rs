@@ -0,0 +1,35 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! Scalar normalize and clamp kernels
+//!
+//! The SIMD kernels produce the same results bit for bit, they also
+//! use these for the tail of the data that doesn't fill a vector.
+
+pub(crate) fn min_max_scalar(data: &[f32]) -> Option<(f32, f32)> {
+    // f32::min and f32::max return the other value when one is NaN
+    let (min, max) = data
+        .iter()
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), x| {
+            (min.min(*x), max.max(*x))
+        });
+
+    (min <= max).then_some((min, max))
+}
+
+pub(crate) fn scale_offset_scalar(data: &mut [f32], scale: f32, offset: f32) {
+    for x in data.iter_mut() {
+        *x = *x * scale + offset;
+    }
+}
+
+pub(crate) fn clamp_scalar(data: &mut [f32], min: f32, max: f32) {
+    for x in data.iter_mut() {
+        *x = x.clamp(min, max);
+    }
+}
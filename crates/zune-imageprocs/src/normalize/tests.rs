@@ -0,0 +1,156 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+#![cfg(test)]
+#![allow(clippy::float_cmp)]
+
+use nanorand::Rng;
+use zune_core::colorspace::ColorSpace;
+use zune_image::image::Image;
+use zune_image::traits::OperationsTrait;
+
+use crate::normalize::{clamp_f32, min_max_f32, scalar, scale_offset_f32, Clamp, Normalize};
+
+/// Random values in `-1000.0..1000.0`, with a few NaNs sprinkled in
+fn random_data(length: usize) -> Vec<f32> {
+    let mut rng = nanorand::WyRand::new();
+
+    (0..length)
+        .map(|_| {
+            if rng.generate_range(0_u32..16) == 0 {
+                f32::NAN
+            } else {
+                rng.generate::<f32>() * 2000.0 - 1000.0
+            }
+        })
+        .collect()
+}
+
+/// Compare floats bit for bit, so that NaN equals NaN
+fn assert_same(a: &[f32], b: &[f32]) {
+    let a: Vec<u32> = a.iter().map(|x| x.to_bits()).collect();
+    let b: Vec<u32> = b.iter().map(|x| x.to_bits()).collect();
+
+    assert_eq!(a, b);
+}
+
+#[test]
+fn test_kernels_match_scalar() {
+    // lengths that leave a tail for both 4 and 8 wide vectors
+    for length in [0, 1, 3, 7, 8, 31, 1027] {
+        let data = random_data(length);
+
+        assert_eq!(min_max_f32(&data), scalar::min_max_scalar(&data));
+
+        let mut simd = data.clone();
+        let mut expected = data.clone();
+
+        scale_offset_f32(&mut simd, 0.0013, -0.25);
+        scalar::scale_offset_scalar(&mut expected, 0.0013, -0.25);
+        assert_same(&simd, &expected);
+
+        let mut simd = data.clone();
+        let mut expected = data.clone();
+
+        clamp_f32(&mut simd, -100.0, 250.0);
+        scalar::clamp_scalar(&mut expected, -100.0, 250.0);
+        assert_same(&simd, &expected);
+    }
+}
+
+#[test]
+fn test_min_max_skips_nan() {
+    let mut data = vec![f32::NAN; 19];
+
+    assert_eq!(min_max_f32(&data), None);
+
+    data[3] = 4.0;
+    data[17] = -2.0;
+    assert_eq!(min_max_f32(&data), Some((-2.0, 4.0)));
+}
+
+#[test]
+fn test_normalize_maps_range() {
+    let mut pixels = random_data(64 * 64);
+    pixels[10] = -1500.0;
+    pixels[20] = 3000.0;
+
+    let mut image = Image::from_f32(&pixels, 64, 64, ColorSpace::Luma);
+
+    Normalize::new(0.0, 1.0).execute(&mut image).unwrap();
+
+    let channel = &image.channels_ref(false)[0];
+    let values = channel.reinterpret_as::<f32>().unwrap();
+
+    assert_eq!(values[10], 0.0);
+    assert!((values[20] - 1.0).abs() < 1e-6);
+    for (value, pixel) in values.iter().zip(&pixels) {
+        assert_eq!(value.is_nan(), pixel.is_nan());
+    }
+    assert!(values
+        .iter()
+        .filter(|x| !x.is_nan())
+        .all(|x| (0.0..=1.0 + 1e-6).contains(x)));
+}
+
+#[test]
+fn test_normalize_keeps_alpha() {
+    let pixels = [10.0, 20.0, 30.0, 0.5, 50.0, 60.0, 70.0, 0.25];
+    let mut image = Image::from_f32(&pixels, 2, 1, ColorSpace::RGBA);
+
+    Normalize::new(0.0, 6.0).execute(&mut image).unwrap();
+
+    let channels = image.channels_ref(false);
+    // all color channels share a range
+    assert_eq!(channels[0].reinterpret_as::<f32>().unwrap(), &[0.0, 4.0]);
+    assert_eq!(channels[2].reinterpret_as::<f32>().unwrap(), &[2.0, 6.0]);
+    assert_eq!(channels[3].reinterpret_as::<f32>().unwrap(), &[0.5, 0.25]);
+}
+
+#[test]
+fn test_normalize_flat_image() {
+    let mut image = Image::fill(42.0_f32, ColorSpace::Luma, 5, 5);
+
+    Normalize::new(-1.0, 1.0).execute(&mut image).unwrap();
+
+    let channel = &image.channels_ref(false)[0];
+    assert!(channel
+        .reinterpret_as::<f32>()
+        .unwrap()
+        .iter()
+        .all(|x| *x == -1.0));
+}
+
+#[test]
+fn test_normalize_rejects_infinity() {
+    let mut image = Image::from_f32(&[0.0, f32::INFINITY], 2, 1, ColorSpace::Luma);
+
+    assert!(Normalize::new(0.0, 1.0).execute(&mut image).is_err());
+}
+
+#[test]
+fn test_normalize_rejects_integers() {
+    let mut image = Image::fill(0_u8, ColorSpace::Luma, 5, 5);
+
+    assert!(Normalize::new(0.0, 1.0).execute(&mut image).is_err());
+}
+
+#[test]
+fn test_clamp() {
+    let pixels = [-0.5, f32::NAN, f32::INFINITY, 0.5];
+    let mut image = Image::from_f32(&pixels, 2, 2, ColorSpace::Luma);
+
+    Clamp::new(0.0, 1.0).execute(&mut image).unwrap();
+
+    let channel = &image.channels_ref(false)[0];
+    assert_same(
+        channel.reinterpret_as::<f32>().unwrap(),
+        &[0.0, f32::NAN, 1.0, 0.5]
+    );
+    assert!(Clamp::new(1.0, 0.0).execute(&mut image).is_err());
+    assert!(Clamp::new(f32::NAN, 0.0).execute(&mut image).is_err());
+}
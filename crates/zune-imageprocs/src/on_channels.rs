@@ -0,0 +1,128 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! Run an operation on some channels of an image
+//!
+//! This lets any per channel operation be limited to a subset of channels,
+//! e.g sharpen only the luma channel of a YCbCr image, or blur only the alpha
+//! channel of an RGBA image, without every operation needing its own
+//! channel selection option.
+use zune_core::bit_depth::BitType;
+use zune_core::colorspace::ColorSpace;
+use zune_image::errors::ImageErrors;
+use zune_image::frame::Frame;
+use zune_image::image::Image;
+use zune_image::traits::OperationsTrait;
+
+/// Run an operation only on the channels selected by a mask
+///
+/// Every selected channel is run through the inner operation on its own,
+/// as a [`ColorSpace::Luma`] image, so the inner operation must support luma
+/// images and must not change the image dimensions or depth.
+///
+/// # Example
+/// Blur the alpha channel of an RGBA image
+/// ```
+/// use zune_core::colorspace::ColorSpace;
+/// use zune_image::image::Image;
+/// use zune_image::traits::OperationsTrait;
+/// use zune_imageprocs::box_blur::BoxBlur;
+/// use zune_imageprocs::on_channels::OnChannels;
+///
+/// let mut image = Image::fill(100_u8, ColorSpace::RGBA, 10, 10);
+/// // alpha is the fourth channel
+/// OnChannels::new(Box::new(BoxBlur::new(3)), 0b1000)
+///     .execute(&mut image)
+///     .unwrap();
+/// ```
+pub struct OnChannels {
+    inner:        Box<dyn OperationsTrait>,
+    channel_mask: u32
+}
+
+impl OnChannels {
+    /// Create a new operation running `inner` on some channels
+    ///
+    /// # Arguments
+    /// - inner: The operation to run
+    /// - channel_mask: The channels to run it on, bit `n` selects channel `n`
+    ///   in the order of the image colorspace, e.g `0b001` is red for RGB
+    ///   and luma for YCbCr
+    #[must_use]
+    pub fn new(inner: Box<dyn OperationsTrait>, channel_mask: u32) -> OnChannels {
+        OnChannels {
+            inner,
+            channel_mask
+        }
+    }
+}
+
+impl OperationsTrait for OnChannels {
+    fn name(&self) -> &'static str {
+        "On Channels"
+    }
+
+    fn execute_impl(&self, image: &mut Image) -> Result<(), ImageErrors> {
+        let colorspace = image.colorspace();
+        let components = colorspace.num_components();
+
+        let all_channels = (1_u64 << components) - 1;
+
+        if u64::from(self.channel_mask) & !all_channels != 0 {
+            return Err(ImageErrors::GenericString(format!(
+                "Channel mask {:#b} selects channels beyond the {components} channels of {colorspace:?}",
+                self.channel_mask
+            )));
+        }
+        let (width, height) = image.dimensions();
+        let depth = image.depth();
+
+        // results are only written back once every channel succeeded, so an
+        // error leaves the image untouched
+        let mut results = vec![];
+
+        for position in (0..components).filter(|x| self.channel_mask & (1 << x) != 0) {
+            // copy the channel of every frame into an image of its own
+            let frames = image
+                .frames_ref()
+                .iter()
+                .map(|frame| {
+                    let channel = &frame.channels_ref(colorspace, false)[position];
+                    Frame::new(vec![channel.clone()])
+                })
+                .collect();
+
+            let mut single = Image::new_frames(frames, depth, width, height, ColorSpace::Luma);
+
+            self.inner.execute(&mut single)?;
+
+            if single.dimensions() != (width, height)
+                || single.depth() != depth
+                || single.colorspace() != ColorSpace::Luma
+                || single.frames_len() != image.frames_len()
+            {
+                return Err(ImageErrors::GenericString(format!(
+                    "{} cannot run on single channels, it changes the image layout",
+                    self.inner.name()
+                )));
+            }
+            results.push((position, single));
+        }
+        for (position, mut single) in results {
+            for (frame, single_frame) in image.frames_mut().iter_mut().zip(single.frames_mut()) {
+                frame.channels_mut(colorspace, false)[position] =
+                    single_frame.channels_vec().swap_remove(0);
+            }
+        }
+        Ok(())
+    }
+
+    fn supported_types(&self) -> &'static [BitType] {
+        self.inner.supported_types()
+    }
+}
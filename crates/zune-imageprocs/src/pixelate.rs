@@ -0,0 +1,299 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! Pixelate (mosaic) an image
+//!
+//! The image is split into square blocks starting at the top left corner, and every
+//! pixel of a block is replaced by the mean of the block. Blocks on the right and bottom
+//! edges are smaller when the image dimensions aren't a multiple of the block size.
+//!
+//! To pixelate part of an image, e.g to redact a face or a license plate, execute
+//! the operation on a view of that region with [`OperationsTrait::execute_view`].
+use zune_core::bit_depth::BitType;
+use zune_image::channel::Channel;
+use zune_image::errors::ImageErrors;
+use zune_image::image::Image;
+use zune_image::traits::{LightSpace, OperationsTrait};
+
+/// Replace blocks of pixels with their mean
+///
+/// # Alpha channel
+/// - Alpha is averaged like the other channels
+/// - Colour channels of images with straight (not premultiplied) alpha are weighted by
+///   alpha, so transparent pixels don't bleed their colour into the block
+///
+/// # Example
+/// ```
+/// use zune_core::colorspace::ColorSpace;
+/// use zune_image::image::Image;
+/// use zune_image::traits::OperationsTrait;
+/// use zune_imageprocs::pixelate::Pixelate;
+///
+/// let mut image = Image::fill(100_u8, ColorSpace::RGB, 100, 100);
+/// // only pixelate the top left quarter
+/// let mut region = image.view_mut(0, 0, 50, 50).unwrap();
+///
+/// Pixelate::new(8).execute_view(&mut region).unwrap();
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub struct Pixelate {
+    block_size: usize
+}
+
+impl Pixelate {
+    /// Create a new pixelate operation
+    ///
+    /// # Arguments
+    /// - block_size: Width and height of the blocks, in pixels. A block size of 1 leaves
+    ///   the image unchanged
+    #[must_use]
+    pub fn new(block_size: usize) -> Pixelate {
+        Pixelate { block_size }
+    }
+}
+
+impl OperationsTrait for Pixelate {
+    fn name(&self) -> &'static str {
+        "Pixelate"
+    }
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn execute_impl(&self, image: &mut Image) -> Result<(), ImageErrors> {
+        if self.block_size == 0 {
+            return Err(ImageErrors::GenericStr(
+                "Pixelate block size must be non-zero"
+            ));
+        }
+        let (width, height) = image.dimensions();
+        let depth = image.depth().bit_type();
+        let colorspace = image.colorspace();
+        // premultiplied colour is already weighted
+        let alpha_position = colorspace
+            .alpha_position()
+            .filter(|_| !image.metadata().is_premultiplied_alpha());
+
+        for frame in image.frames_mut() {
+            let channels = frame.channels_mut(colorspace, false);
+            let alpha = alpha_position.map(|position| channels[position].clone());
+
+            for (position, channel) in channels.iter_mut().enumerate() {
+                let weights = alpha.as_ref().filter(|_| Some(position) != alpha_position);
+
+                match depth {
+                    BitType::U8 => pixelate_channel(
+                        channel,
+                        weights,
+                        (width, height),
+                        self.block_size,
+                        |x| x.round() as u8
+                    )?,
+                    BitType::U16 => pixelate_channel(
+                        channel,
+                        weights,
+                        (width, height),
+                        self.block_size,
+                        |x| x.round() as u16
+                    )?,
+                    BitType::F32 => pixelate_channel(
+                        channel,
+                        weights,
+                        (width, height),
+                        self.block_size,
+                        |x| x as f32
+                    )?,
+                    d => return Err(ImageErrors::ImageOperationNotImplemented(self.name(), d))
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn supported_types(&self) -> &'static [BitType] {
+        &[BitType::U8, BitType::U16, BitType::F32]
+    }
+
+    fn light_space(&self) -> LightSpace {
+        LightSpace::Linear
+    }
+}
+
+/// Pixelate `channel`, weighting its pixels by `weights` if present
+fn pixelate_channel<T: Default + bytemuck::Pod>(
+    channel: &mut Channel, weights: Option<&Channel>, dimensions: (usize, usize),
+    block_size: usize, convert: impl Fn(f64) -> T
+) -> Result<(), ImageErrors>
+where
+    f64: From<T>
+{
+    let (width, height) = dimensions;
+    let channel = channel.reinterpret_as_mut::<T>()?;
+
+    match weights {
+        Some(weights) => {
+            let weights = weights.reinterpret_as::<T>()?;
+            pixelate_weighted(channel, weights, width, height, block_size, convert);
+        }
+        None => pixelate(channel, width, height, block_size, convert)
+    }
+    Ok(())
+}
+
+/// Replace every `block_size` x `block_size` block of a channel with its mean
+///
+/// # Arguments
+/// - channel: Channel to pixelate, in place
+/// - width: Channel width
+/// - height: Channel height
+/// - block_size: Width and height of the blocks, must be non-zero
+/// - convert: Converts a mean back to a pixel
+pub fn pixelate<T: Copy>(
+    channel: &mut [T], width: usize, height: usize, block_size: usize, convert: impl Fn(f64) -> T
+) where
+    f64: From<T>
+{
+    pixelate_inner(channel, None, width, height, block_size, convert);
+}
+
+/// Replace every `block_size` x `block_size` block of a channel with its mean,
+/// weighting every pixel by the matching pixel in `weights`
+///
+/// This is used for colour channels with an alpha channel as `weights`, so that
+/// transparent pixels don't contribute to the colour of their block. Blocks whose
+/// weights are all zero use the plain mean.
+///
+/// # Arguments
+/// - channel: Channel to pixelate, in place
+/// - weights: Weight of every pixel, with the same length as `channel`
+/// - width: Channel width
+/// - height: Channel height
+/// - block_size: Width and height of the blocks, must be non-zero
+/// - convert: Converts a mean back to a pixel
+pub fn pixelate_weighted<T: Copy>(
+    channel: &mut [T], weights: &[T], width: usize, height: usize, block_size: usize,
+    convert: impl Fn(f64) -> T
+) where
+    f64: From<T>
+{
+    pixelate_inner(channel, Some(weights), width, height, block_size, convert);
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn pixelate_inner<T: Copy>(
+    channel: &mut [T], weights: Option<&[T]>, width: usize, height: usize, block_size: usize,
+    convert: impl Fn(f64) -> T
+) where
+    f64: From<T>
+{
+    if width == 0 || height == 0 {
+        return;
+    }
+    // blocks larger than the image cover all of it
+    let block_size = block_size.min(width.max(height));
+
+    for y in (0..height).step_by(block_size) {
+        let rows = block_size.min(height - y);
+
+        for x in (0..width).step_by(block_size) {
+            let columns = block_size.min(width - x);
+            let block_row = |row: usize| row * width + x..row * width + x + columns;
+
+            let (mut sum, mut weighted_sum, mut total_weight) = (0.0, 0.0, 0.0);
+
+            for row in y..y + rows {
+                for (position, pixel) in block_row(row).zip(&channel[block_row(row)]) {
+                    let pixel = f64::from(*pixel);
+                    let weight = weights.map_or(1.0, |weights| f64::from(weights[position]));
+
+                    sum += pixel;
+                    weighted_sum += pixel * weight;
+                    total_weight += weight;
+                }
+            }
+            let mean = if total_weight > 0.0 {
+                weighted_sum / total_weight
+            } else {
+                sum / (rows * columns) as f64
+            };
+            let mean = convert(mean);
+
+            for row in y..y + rows {
+                channel[block_row(row)].fill(mean);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use zune_core::colorspace::ColorSpace;
+    use zune_image::image::Image;
+    use zune_image::traits::OperationsTrait;
+
+    use crate::pixelate::{pixelate, Pixelate};
+
+    #[test]
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn test_pixelate_blocks() {
+        // 5x3 with blocks of 2, the last column and row are blocks of their own
+        let mut channel: Vec<u8> = (0..15).collect();
+
+        pixelate(&mut channel, 5, 3, 2, |x| x.round() as u8);
+
+        #[rustfmt::skip]
+        let expected = [
+            3, 3, 5, 5, 7,
+            3, 3, 5, 5, 7,
+            11, 11, 13, 13, 14
+        ];
+        assert_eq!(channel, expected);
+    }
+
+    #[test]
+    fn test_pixelate_region() {
+        let pixels: Vec<u8> = (0..64).collect();
+        let mut image = Image::from_u8(&pixels, 8, 8, ColorSpace::Luma);
+
+        let mut region = image.view_mut(4, 4, 4, 4).unwrap();
+        Pixelate::new(4).execute_view(&mut region).unwrap();
+
+        let channels = image.channels_ref(false);
+        let pixels = channels[0].reinterpret_as::<u8>().unwrap();
+
+        // outside the region is untouched, inside is the mean of the region
+        assert_eq!(pixels[..32], (0..32).collect::<Vec<u8>>());
+        assert_eq!(pixels[36..40], [50; 4]);
+        assert_eq!(pixels[63], 50);
+        assert_eq!(pixels[59], 59);
+    }
+
+    #[test]
+    fn test_pixelate_zero_block() {
+        let mut image = Image::fill(0_u8, ColorSpace::RGB, 4, 4);
+        assert!(Pixelate::new(0).execute(&mut image).is_err());
+    }
+
+    #[test]
+    fn test_pixelate_huge_block() {
+        let pixels: Vec<u8> = (0..15).collect();
+        let mut image = Image::from_u8(&pixels, 5, 3, ColorSpace::Luma);
+
+        Pixelate::new(usize::MAX).execute(&mut image).unwrap();
+        assert_eq!(image.flatten_to_u8()[0], [7; 15]);
+    }
+
+    #[test]
+    fn test_pixelate_weighs_alpha() {
+        // opaque red next to transparent green
+        let pixels = [255_u8, 0, 0, 255, 0, 255, 0, 0];
+        let mut image = Image::from_u8(&pixels, 2, 1, ColorSpace::RGBA);
+
+        Pixelate::new(2).execute(&mut image).unwrap();
+        // the transparent pixel doesn't tint the block
+        assert_eq!(image.flatten_to_u8()[0], [255, 0, 0, 128, 255, 0, 0, 128]);
+    }
+}
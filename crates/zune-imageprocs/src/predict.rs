@@ -0,0 +1,327 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! Lossless per-row predictors
+//!
+//! These are the building blocks of lossless encoders (PNG filters,
+//! FLIF/WebP-lossless style predictors, delta coded farbfeld streams e.t.c),
+//! a predictor guesses a sample from its already seen neighbours and the
+//! encoder stores the (wrapping) difference between the guess and the real
+//! value, which for natural images is mostly small numbers that entropy
+//! coders love.
+//!
+//! The neighbours of a sample `x` are named as
+//!
+//! ```text
+//!  ┌───┬───┐
+//!  │ c │ b │   <- previous row
+//!  ├───┼───┤
+//!  │ a │ x │   <- current row
+//!  └───┴───┘
+//! ```
+//!
+//! where `a` is the same channel of the pixel to the left, hence rows are
+//! interleaved and the distance to `a` is the number of components in a pixel.
+//!
+//! Neighbours outside the image (the first pixel of a row or the row above the
+//! first row) are treated as zero, which is what PNG does.
+//!
+//! Forward prediction ([`predict_row_u8`]) has all its inputs known up front and uses SIMD
+//! kernels where available, reconstruction ([`unpredict_row_u8`]) depends on the
+//! previously reconstructed sample for everything except [`Predictor::Up`] and is therefore
+//! carried out sample by sample.
+use std::sync::Once;
+
+use zune_core::log::trace;
+
+pub(crate) mod scalar;
+pub(crate) mod sse41;
+mod tests;
+
+static START: Once = Once::new();
+
+/// A prediction applied to every sample of a row
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Predictor {
+    /// No prediction, residuals are the samples themselves
+    None,
+    /// Predict from the pixel to the left, `a`
+    Left,
+    /// Predict from the pixel above, `b`
+    Up,
+    /// Predict from the floored average of the left and top pixels, `(a+b)/2`
+    Average,
+    /// The PNG Paeth predictor, pick whichever of `a`,`b` or `c` is closest to
+    /// the gradient `a+b-c`, with ties resolved in that order
+    Paeth,
+    /// The WebP lossless select predictor, pick `a` if it is strictly closer
+    /// to the gradient `a+b-c` than `b`, otherwise pick `b`
+    Select
+}
+
+impl Predictor {
+    /// All predictors, in the order [`choose_predictor_u8`] tries them
+    pub const ALL: [Predictor; 6] = [
+        Predictor::None,
+        Predictor::Left,
+        Predictor::Up,
+        Predictor::Average,
+        Predictor::Paeth,
+        Predictor::Select
+    ];
+}
+
+fn check_lengths<T>(current: &[T], previous: &[T], out: &[T], components: usize) {
+    assert!(components > 0, "Components cannot be zero");
+    assert_eq!(
+        current.len(),
+        out.len(),
+        "Output and input rows differ in length"
+    );
+    assert!(
+        previous.is_empty() || previous.len() == current.len(),
+        "Previous row should either be empty or have the same length as the current row"
+    );
+}
+
+/// Run `predictor` over a row of 8 bit samples, storing residuals in `out`
+///
+/// # Arguments
+/// - predictor: The predictor to use
+/// - current: The row being predicted
+/// - previous: The row above the current row, or an empty slice if `current` is the first row
+/// - out: Where to store residuals, must have the same length as `current`
+/// - components: Number of interleaved components in a pixel
+///
+/// # Panics
+/// If `out` and `current` differ in length, if `previous` is non-empty and differs in length from
+/// `current` or if `components` is zero
+pub fn predict_row_u8(
+    predictor: Predictor, current: &[u8], previous: &[u8], out: &mut [u8], components: usize
+) {
+    check_lengths(current, previous, out, components);
+
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    {
+        #[cfg(feature = "sse41")]
+        {
+            use crate::predict::sse41::predict_row_sse41;
+
+            if !previous.is_empty() && is_x86_feature_detected!("sse4.1") {
+                START.call_once(|| {
+                    trace!("Using SSE4.1 predict_row_u8 algorithm");
+                });
+                unsafe {
+                    return predict_row_sse41(predictor, current, previous, out, components);
+                }
+            }
+        }
+    }
+    scalar::predict_row(predictor, current, previous, out, components);
+}
+
+/// Run `predictor` over a row of 16 bit samples, storing residuals in `out`
+///
+/// See [`predict_row_u8`] for the arguments
+///
+/// # Panics
+/// Under the same conditions as [`predict_row_u8`]
+pub fn predict_row_u16(
+    predictor: Predictor, current: &[u16], previous: &[u16], out: &mut [u16], components: usize
+) {
+    check_lengths(current, previous, out, components);
+    scalar::predict_row(predictor, current, previous, out, components);
+}
+
+/// Reverse [`predict_row_u8`], reconstructing the samples of a row from its residuals
+///
+/// # Arguments
+/// - predictor: The predictor used to create the residuals
+/// - residuals: Residuals of the current row
+/// - previous: The already reconstructed row above, or an empty slice for the first row
+/// - out: Where to store the reconstructed row, must have the same length as `residuals`
+/// - components: Number of interleaved components in a pixel
+///
+/// # Panics
+/// Under the same conditions as [`predict_row_u8`]
+pub fn unpredict_row_u8(
+    predictor: Predictor, residuals: &[u8], previous: &[u8], out: &mut [u8], components: usize
+) {
+    check_lengths(residuals, previous, out, components);
+    scalar::unpredict_row(predictor, residuals, previous, out, components);
+}
+
+/// Reverse [`predict_row_u16`], reconstructing the samples of a row from its residuals
+///
+/// See [`unpredict_row_u8`] for the arguments
+///
+/// # Panics
+/// Under the same conditions as [`predict_row_u8`]
+pub fn unpredict_row_u16(
+    predictor: Predictor, residuals: &[u16], previous: &[u16], out: &mut [u16], components: usize
+) {
+    check_lengths(residuals, previous, out, components);
+    scalar::unpredict_row(predictor, residuals, previous, out, components);
+}
+
+/// Choose the predictor that is likely to compress a row best
+///
+/// This uses the minimum sum of absolute differences heuristic recommended by the PNG
+/// specification, residuals are interpreted as signed bytes and the predictor with the smallest
+/// sum wins.
+///
+/// # Arguments
+/// - current: The row being predicted
+/// - previous: The row above, or an empty slice for the first row
+/// - scratch: Scratch space for residuals, must have the same length as `current`,
+///   on return it contains residuals of the last predictor tried.
+/// - components: Number of interleaved components in a pixel
+///
+/// # Panics
+/// Under the same conditions as [`predict_row_u8`]
+pub fn choose_predictor_u8(
+    current: &[u8], previous: &[u8], scratch: &mut [u8], components: usize
+) -> Predictor {
+    let mut best = Predictor::None;
+    let mut best_sum = u64::MAX;
+
+    for predictor in Predictor::ALL {
+        predict_row_u8(predictor, current, previous, scratch, components);
+
+        let sum = scratch
+            .iter()
+            // |x as i8|, without the sign juggling
+            .map(|x| u64::from((*x).min(x.wrapping_neg())))
+            .sum::<u64>();
+
+        if sum < best_sum {
+            best = predictor;
+            best_sum = sum;
+        }
+    }
+    best
+}
+
+#[cfg(feature = "benchmarks")]
+#[cfg(test)]
+mod benchmarks {
+    extern crate test;
+
+    use nanorand::Rng;
+
+    use crate::predict::{predict_row_u8, scalar, unpredict_row_u8, Predictor};
+
+    const WIDTH: usize = 800;
+    const HEIGHT: usize = 800;
+    const COMPONENTS: usize = 3;
+
+    fn image() -> Vec<u8> {
+        let mut pixels = vec![0_u8; WIDTH * HEIGHT * COMPONENTS];
+        nanorand::WyRand::new_seed(0).fill(&mut pixels);
+        pixels
+    }
+
+    fn bench_predict(b: &mut test::Bencher, predictor: Predictor, use_simd: bool) {
+        let pixels = image();
+        let mut out = vec![0; pixels.len()];
+        let stride = WIDTH * COMPONENTS;
+
+        b.iter(|| {
+            for (y, out_row) in out.chunks_exact_mut(stride).enumerate().skip(1) {
+                let current = &pixels[y * stride..(y + 1) * stride];
+                let previous = &pixels[(y - 1) * stride..y * stride];
+
+                if use_simd {
+                    predict_row_u8(predictor, current, previous, out_row, COMPONENTS);
+                } else {
+                    scalar::predict_row(predictor, current, previous, out_row, COMPONENTS);
+                }
+            }
+        });
+    }
+
+    fn bench_unpredict(b: &mut test::Bencher, predictor: Predictor) {
+        let residuals = image();
+        let mut out = vec![0; residuals.len()];
+        let stride = WIDTH * COMPONENTS;
+
+        b.iter(|| {
+            for y in 1..HEIGHT {
+                let (previous, current) = out.split_at_mut(y * stride);
+
+                unpredict_row_u8(
+                    predictor,
+                    &residuals[y * stride..(y + 1) * stride],
+                    &previous[(y - 1) * stride..],
+                    &mut current[..stride],
+                    COMPONENTS
+                );
+            }
+        });
+    }
+
+    #[bench]
+    fn predict_left_scalar(b: &mut test::Bencher) {
+        bench_predict(b, Predictor::Left, false);
+    }
+
+    #[bench]
+    fn predict_left_simd(b: &mut test::Bencher) {
+        bench_predict(b, Predictor::Left, true);
+    }
+
+    #[bench]
+    fn predict_up_scalar(b: &mut test::Bencher) {
+        bench_predict(b, Predictor::Up, false);
+    }
+
+    #[bench]
+    fn predict_up_simd(b: &mut test::Bencher) {
+        bench_predict(b, Predictor::Up, true);
+    }
+
+    #[bench]
+    fn predict_average_scalar(b: &mut test::Bencher) {
+        bench_predict(b, Predictor::Average, false);
+    }
+
+    #[bench]
+    fn predict_average_simd(b: &mut test::Bencher) {
+        bench_predict(b, Predictor::Average, true);
+    }
+
+    #[bench]
+    fn predict_paeth_scalar(b: &mut test::Bencher) {
+        bench_predict(b, Predictor::Paeth, false);
+    }
+
+    #[bench]
+    fn predict_paeth_simd(b: &mut test::Bencher) {
+        bench_predict(b, Predictor::Paeth, true);
+    }
+
+    #[bench]
+    fn predict_select_scalar(b: &mut test::Bencher) {
+        bench_predict(b, Predictor::Select, false);
+    }
+
+    #[bench]
+    fn predict_select_simd(b: &mut test::Bencher) {
+        bench_predict(b, Predictor::Select, true);
+    }
+
+    #[bench]
+    fn unpredict_up(b: &mut test::Bencher) {
+        bench_unpredict(b, Predictor::Up);
+    }
+
+    #[bench]
+    fn unpredict_paeth(b: &mut test::Bencher) {
+        bench_unpredict(b, Predictor::Paeth);
+    }
+}
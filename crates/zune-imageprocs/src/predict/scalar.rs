@@ -0,0 +1,184 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! Scalar predictors, generic over the sample type
+use crate::predict::Predictor;
+
+/// A sample that predictors can operate on
+///
+/// Predictor arithmetic is carried out in `i32`, and results are
+/// truncated back, which gives us wrapping semantics for free.
+pub(crate) trait PredictorSample: Copy + Default {
+    fn to_i32(self) -> i32;
+
+    fn from_i32(value: i32) -> Self;
+}
+
+impl PredictorSample for u8 {
+    #[inline(always)]
+    fn to_i32(self) -> i32 {
+        i32::from(self)
+    }
+    #[inline(always)]
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn from_i32(value: i32) -> Self {
+        value as u8
+    }
+}
+
+impl PredictorSample for u16 {
+    #[inline(always)]
+    fn to_i32(self) -> i32 {
+        i32::from(self)
+    }
+    #[inline(always)]
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn from_i32(value: i32) -> Self {
+        value as u16
+    }
+}
+
+#[inline(always)]
+fn average<T: PredictorSample>(a: T, b: T) -> T {
+    T::from_i32((a.to_i32() + b.to_i32()) >> 1)
+}
+
+#[inline(always)]
+fn paeth<T: PredictorSample>(a: T, b: T, c: T) -> T {
+    let (a_i, b_i, c_i) = (a.to_i32(), b.to_i32(), c.to_i32());
+    // (p-a) == (b-c) and (p-b) == (a-c), see the png de-filter code
+    let pa = (b_i - c_i).abs();
+    let pb = (a_i - c_i).abs();
+    let pc = (a_i + b_i - 2 * c_i).abs();
+
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+#[inline(always)]
+fn select<T: PredictorSample>(a: T, b: T, c: T) -> T {
+    let (a_i, b_i, c_i) = (a.to_i32(), b.to_i32(), c.to_i32());
+
+    if (b_i - c_i).abs() < (a_i - c_i).abs() {
+        a
+    } else {
+        b
+    }
+}
+
+/// Predict a single sample from its neighbours
+#[inline(always)]
+pub(crate) fn predict_sample<T: PredictorSample>(predictor: Predictor, a: T, b: T, c: T) -> T {
+    match predictor {
+        Predictor::None => T::default(),
+        Predictor::Left => a,
+        Predictor::Up => b,
+        Predictor::Average => average(a, b),
+        Predictor::Paeth => paeth(a, b, c),
+        Predictor::Select => select(a, b, c)
+    }
+}
+
+#[inline(always)]
+fn wrapping_sub<T: PredictorSample>(x: T, y: T) -> T {
+    T::from_i32(x.to_i32() - y.to_i32())
+}
+
+#[inline(always)]
+fn wrapping_add<T: PredictorSample>(x: T, y: T) -> T {
+    T::from_i32(x.to_i32() + y.to_i32())
+}
+
+#[inline(always)]
+fn predict_with<T: PredictorSample, F: Fn(T, T, T) -> T>(
+    current: &[T], previous: &[T], out: &mut [T], components: usize, predict: F
+) {
+    let zero = T::default();
+    let start = components.min(out.len());
+
+    if previous.is_empty() {
+        for i in 0..start {
+            out[i] = wrapping_sub(current[i], predict(zero, zero, zero));
+        }
+        for i in start..out.len() {
+            out[i] = wrapping_sub(current[i], predict(current[i - components], zero, zero));
+        }
+    } else {
+        for i in 0..start {
+            out[i] = wrapping_sub(current[i], predict(zero, previous[i], zero));
+        }
+        for i in start..out.len() {
+            let (a, b, c) = (
+                current[i - components],
+                previous[i],
+                previous[i - components]
+            );
+            out[i] = wrapping_sub(current[i], predict(a, b, c));
+        }
+    }
+}
+
+#[inline(always)]
+fn unpredict_with<T: PredictorSample, F: Fn(T, T, T) -> T>(
+    residuals: &[T], previous: &[T], out: &mut [T], components: usize, predict: F
+) {
+    let zero = T::default();
+    let start = components.min(out.len());
+
+    if previous.is_empty() {
+        for i in 0..start {
+            out[i] = wrapping_add(residuals[i], predict(zero, zero, zero));
+        }
+        for i in start..out.len() {
+            out[i] = wrapping_add(residuals[i], predict(out[i - components], zero, zero));
+        }
+    } else {
+        for i in 0..start {
+            out[i] = wrapping_add(residuals[i], predict(zero, previous[i], zero));
+        }
+        for i in start..out.len() {
+            let (a, b, c) = (out[i - components], previous[i], previous[i - components]);
+            out[i] = wrapping_add(residuals[i], predict(a, b, c));
+        }
+    }
+}
+
+pub(crate) fn predict_row<T: PredictorSample>(
+    predictor: Predictor, current: &[T], previous: &[T], out: &mut [T], components: usize
+) {
+    match predictor {
+        Predictor::None => out.copy_from_slice(current),
+        Predictor::Left => predict_with(current, previous, out, components, |a, _, _| a),
+        Predictor::Up => predict_with(current, previous, out, components, |_, b, _| b),
+        Predictor::Average => {
+            predict_with(current, previous, out, components, |a, b, _| average(a, b));
+        }
+        Predictor::Paeth => predict_with(current, previous, out, components, paeth),
+        Predictor::Select => predict_with(current, previous, out, components, select)
+    }
+}
+
+pub(crate) fn unpredict_row<T: PredictorSample>(
+    predictor: Predictor, residuals: &[T], previous: &[T], out: &mut [T], components: usize
+) {
+    match predictor {
+        Predictor::None => out.copy_from_slice(residuals),
+        Predictor::Left => unpredict_with(residuals, previous, out, components, |a, _, _| a),
+        Predictor::Up => unpredict_with(residuals, previous, out, components, |_, b, _| b),
+        Predictor::Average => unpredict_with(residuals, previous, out, components, |a, b, _| {
+            average(a, b)
+        }),
+        Predictor::Paeth => unpredict_with(residuals, previous, out, components, paeth),
+        Predictor::Select => unpredict_with(residuals, previous, out, components, select)
+    }
+}
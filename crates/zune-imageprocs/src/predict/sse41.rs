@@ -0,0 +1,156 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+#![cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#![cfg(feature = "sse41")]
+//! SSE4.1 forward predictors
+//!
+//! Only Paeth and Select are implemented here, the compiler already
+//! does a good job on the other predictors.
+//!
+//! Forward prediction reads only from the input rows, so we can predict 16 samples at a
+//! time by loading `x`,`a`,`b` and `c` with unaligned loads at the right offsets.
+//! The first pixel of a row and the tail that doesn't fill a vector are handled by the
+//! scalar code.
+#[cfg(target_arch = "x86")]
+use core::arch::x86::*;
+#[cfg(target_arch = "x86_64")]
+use core::arch::x86_64::*;
+
+use crate::predict::scalar::{predict_row, predict_sample};
+use crate::predict::Predictor;
+
+#[inline]
+#[target_feature(enable = "sse2")]
+unsafe fn abs_diff(x: __m128i, y: __m128i) -> __m128i {
+    _mm_or_si128(_mm_subs_epu8(x, y), _mm_subs_epu8(y, x))
+}
+
+/// Paeth on 16 bit lanes
+#[inline]
+#[target_feature(enable = "sse4.1")]
+unsafe fn paeth_epi16(a: __m128i, b: __m128i, c: __m128i) -> __m128i {
+    /* (p-a) == (b-c), (p-b) == (a-c), (p-c) == (b-c)+(a-c) */
+    let pa = _mm_sub_epi16(b, c);
+    let pb = _mm_sub_epi16(a, c);
+    let pc = _mm_abs_epi16(_mm_add_epi16(pa, pb));
+    let pa = _mm_abs_epi16(pa);
+    let pb = _mm_abs_epi16(pb);
+
+    let smallest = _mm_min_epi16(pc, _mm_min_epi16(pa, pb));
+
+    /* Paeth breaks ties favoring a over b over c. */
+    let b_or_c = _mm_blendv_epi8(c, b, _mm_cmpeq_epi16(smallest, pb));
+    _mm_blendv_epi8(b_or_c, a, _mm_cmpeq_epi16(smallest, pa))
+}
+
+#[inline]
+#[target_feature(enable = "sse4.1")]
+unsafe fn paeth(a: __m128i, b: __m128i, c: __m128i) -> __m128i {
+    let zero = _mm_setzero_si128();
+
+    let lo = paeth_epi16(
+        _mm_unpacklo_epi8(a, zero),
+        _mm_unpacklo_epi8(b, zero),
+        _mm_unpacklo_epi8(c, zero)
+    );
+    let hi = paeth_epi16(
+        _mm_unpackhi_epi8(a, zero),
+        _mm_unpackhi_epi8(b, zero),
+        _mm_unpackhi_epi8(c, zero)
+    );
+    _mm_packus_epi16(lo, hi)
+}
+
+#[inline]
+#[target_feature(enable = "sse4.1")]
+unsafe fn select(a: __m128i, b: __m128i, c: __m128i) -> __m128i {
+    // |p-a| and |p-b|
+    let pa = abs_diff(b, c);
+    let pb = abs_diff(a, c);
+    // pick b unless a is strictly closer, i.e where pb <= pa
+    let use_b = _mm_cmpeq_epi8(_mm_subs_epu8(pb, pa), _mm_setzero_si128());
+    _mm_blendv_epi8(a, b, use_b)
+}
+
+/// Predict 16 samples at a time starting from the second pixel,
+/// returning the index of the first sample not handled
+macro_rules! predict_loop {
+    ($current:ident, $previous:ident, $out:ident, $components:ident, |$a:ident, $b:ident, $c:ident| $predict:expr) => {{
+        let mut i = $components;
+        let len = $out.len();
+
+        let (x_ptr, out_ptr) = ($current.as_ptr(), $out.as_mut_ptr());
+        let (a_ptr, b_ptr) = (x_ptr.wrapping_sub($components), $previous.as_ptr());
+        let c_ptr = b_ptr.wrapping_sub($components);
+
+        // all loads and stores are in bounds since i + 16 <= len and `a`,`c`
+        // lag behind by `components`
+        while i + 16 <= len {
+            let x = _mm_loadu_si128(x_ptr.add(i).cast());
+            #[allow(unused_variables)]
+            let $a = _mm_loadu_si128(a_ptr.add(i).cast());
+            #[allow(unused_variables)]
+            let $b = _mm_loadu_si128(b_ptr.add(i).cast());
+            #[allow(unused_variables)]
+            let $c = _mm_loadu_si128(c_ptr.add(i).cast());
+
+            let residual = _mm_sub_epi8(x, $predict);
+
+            _mm_storeu_si128(out_ptr.add(i).cast(), residual);
+
+            i += 16;
+        }
+        i
+    }};
+}
+
+#[target_feature(enable = "sse4.1")]
+unsafe fn predict_row_sse41_inner(
+    predictor: Predictor, current: &[u8], previous: &[u8], out: &mut [u8], components: usize
+) {
+    if !matches!(predictor, Predictor::Paeth | Predictor::Select) {
+        // the rest are simple enough for the compiler to auto-vectorize
+        return predict_row(predictor, current, previous, out, components);
+    }
+    // first pixel has no left and top-left neighbours
+    for i in 0..components.min(out.len()) {
+        out[i] = current[i].wrapping_sub(predict_sample(predictor, 0, previous[i], 0));
+    }
+
+    let end = if predictor == Predictor::Paeth {
+        predict_loop!(current, previous, out, components, |a, b, c| paeth(a, b, c))
+    } else {
+        predict_loop!(current, previous, out, components, |a, b, c| select(
+            a, b, c
+        ))
+    };
+
+    for i in end..out.len() {
+        let (a, b, c) = (
+            current[i - components],
+            previous[i],
+            previous[i - components]
+        );
+        out[i] = current[i].wrapping_sub(predict_sample(predictor, a, b, c));
+    }
+}
+
+/// Forward prediction of a row with SSE4.1
+///
+/// # Safety
+/// The caller must ensure the CPU supports SSE4.1, `previous` must be non-empty
+/// and the rows must have the same length
+pub(crate) unsafe fn predict_row_sse41(
+    predictor: Predictor, current: &[u8], previous: &[u8], out: &mut [u8], components: usize
+) {
+    debug_assert_eq!(current.len(), previous.len());
+    debug_assert_eq!(current.len(), out.len());
+
+    predict_row_sse41_inner(predictor, current, previous, out, components);
+}
@@ -0,0 +1,122 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+#![cfg(test)]
+
+use nanorand::Rng;
+
+use crate::predict::{
+    choose_predictor_u8, predict_row_u16, predict_row_u8, scalar, unpredict_row_u16,
+    unpredict_row_u8, Predictor
+};
+
+#[test]
+fn test_predict_roundtrip_u8() {
+    let mut rng = nanorand::WyRand::new();
+
+    for components in [1, 2, 3, 4, 6] {
+        // odd width so rows don't fill whole vectors
+        let stride = 37 * components;
+        let mut pixels = vec![0_u8; stride * 5];
+        rng.fill(&mut pixels);
+
+        for predictor in Predictor::ALL {
+            let mut residuals = vec![0; pixels.len()];
+            let mut decoded = vec![0; pixels.len()];
+
+            for y in 0..5 {
+                let row = y * stride..(y + 1) * stride;
+                let previous = if y == 0 { 0..0 } else { row.start - stride..row.start };
+
+                predict_row_u8(
+                    predictor,
+                    &pixels[row.clone()],
+                    &pixels[previous.clone()],
+                    &mut residuals[row.clone()],
+                    components
+                );
+
+                let (done, current) = decoded.split_at_mut(row.start);
+                unpredict_row_u8(
+                    predictor,
+                    &residuals[row.clone()],
+                    &done[previous],
+                    &mut current[..stride],
+                    components
+                );
+            }
+            assert_eq!(
+                pixels, decoded,
+                "{predictor:?} with {components} components"
+            );
+        }
+    }
+}
+
+#[test]
+fn test_predict_roundtrip_u16() {
+    let mut rng = nanorand::WyRand::new();
+
+    let (stride, components) = (23 * 4, 4);
+    let mut pixels = vec![0_u16; stride * 2];
+    rng.fill(&mut pixels);
+    let (first, second) = pixels.split_at(stride);
+
+    for predictor in Predictor::ALL {
+        let mut residuals = vec![0; stride];
+        let mut decoded = vec![0; stride];
+
+        predict_row_u16(predictor, second, first, &mut residuals, components);
+        unpredict_row_u16(predictor, &residuals, first, &mut decoded, components);
+
+        assert_eq!(second, decoded, "{predictor:?}");
+    }
+}
+
+#[test]
+fn test_predict_simd_scalar_identical() {
+    let mut rng = nanorand::WyRand::new();
+
+    for components in [1, 3, 4] {
+        let stride = 131 * components;
+        let mut pixels = vec![0_u8; stride * 2];
+        rng.fill(&mut pixels);
+        // a smooth area makes ties in paeth and select common
+        pixels[..stride / 2].fill(7);
+        pixels[stride..stride + stride / 2].fill(7);
+
+        let (previous, current) = pixels.split_at(stride);
+
+        for predictor in Predictor::ALL {
+            let mut simd_out = vec![0; stride];
+            let mut scalar_out = vec![0; stride];
+
+            predict_row_u8(predictor, current, previous, &mut simd_out, components);
+            scalar::predict_row(predictor, current, previous, &mut scalar_out, components);
+
+            assert_eq!(
+                simd_out, scalar_out,
+                "{predictor:?} with {components} components"
+            );
+        }
+    }
+}
+
+#[test]
+fn test_choose_predictor() {
+    // every row is the same, so predicting from above gives zeros
+    let previous: Vec<u8> = (0..90_u8).map(|x| x.wrapping_mul(37)).collect();
+    let mut scratch = vec![0; previous.len()];
+
+    let predictor = choose_predictor_u8(&previous, &previous, &mut scratch, 3);
+    assert_eq!(predictor, Predictor::Up);
+
+    // a horizontal gradient is best predicted from the left
+    let gradient: Vec<u8> = (0..90_u8).map(|x| x / 3 * 2).collect();
+    let predictor = choose_predictor_u8(&gradient, &[], &mut scratch, 3);
+    assert_eq!(predictor, Predictor::Left);
+}
@@ -97,6 +97,15 @@ impl OperationsTrait for PremultiplyAlpha {
             warn!("Alpha is already in required mode, exiting");
             return Ok(());
         }
+        let depth = image.depth();
+
+        if matches!(depth, BitDepth::Ten | BitDepth::Twelve) {
+            // the u16 routines expect the full 16 bit range
+            image.convert_depth(BitDepth::Sixteen)?;
+            self.execute_impl(image)?;
+            // restore original bit depth
+            return image.convert_depth(depth);
+        }
 
         let bit_type = image.depth();
 
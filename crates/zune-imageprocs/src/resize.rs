@@ -10,28 +10,50 @@
 //!
 //! (BROKEN): Do not use, **IT DOESN'T WORK**
 use zune_core::bit_depth::BitType;
+#[cfg(feature = "threads")]
+use zune_core::threads::ThreadPool;
 use zune_image::channel::Channel;
 use zune_image::errors::ImageErrors;
 use zune_image::image::Image;
-use zune_image::traits::OperationsTrait;
+use zune_image::traits::{LightSpace, OperationsTrait};
 
 use crate::traits::NumOps;
 
 mod bicubic;
 mod bilinear;
+mod weighted;
 
 #[derive(Copy, Clone, Debug)]
 pub enum ResizeMethod {
     Bilinear //Bicubic
 }
 
+/// Extra processing done around resampling
+///
+/// Both options are off by default, which resamples the stored values directly
+#[derive(Copy, Clone, Debug, Default)]
+pub struct ResizeOptions {
+    /// Multiply color channels by alpha before resampling and divide after,
+    /// so colors of transparent pixels don't bleed into their neighbours.
+    ///
+    /// Images whose metadata says they are already premultiplied are always
+    /// resampled this way, and stay premultiplied.
+    pub premultiply: bool,
+    /// Convert sRGB encoded color channels to linear light before resampling
+    /// and back after, which keeps edges from darkening.
+    ///
+    /// Ignored for colorspaces that aren't RGB or luma
+    pub linearize:   bool
+}
+
 /// Resize an image to a new width and height
 /// using the resize method specified
 #[derive(Copy, Clone)]
 pub struct Resize {
     new_width:  usize,
     new_height: usize,
-    method:     ResizeMethod
+    method:     ResizeMethod,
+    options:    ResizeOptions
 }
 
 impl Resize {
@@ -46,9 +68,17 @@ impl Resize {
         Resize {
             new_width,
             new_height,
-            method
+            method,
+            options: ResizeOptions::default()
         }
     }
+
+    /// Set whether to premultiply alpha or convert to linear light while resizing
+    #[must_use]
+    pub fn set_options(mut self, options: ResizeOptions) -> Resize {
+        self.options = options;
+        self
+    }
 }
 
 impl OperationsTrait for Resize {
@@ -58,6 +88,15 @@ impl OperationsTrait for Resize {
 
     #[allow(clippy::too_many_lines)]
     fn execute_impl(&self, image: &mut Image) -> Result<(), ImageErrors> {
+        if self.options.premultiply || self.options.linearize {
+            return weighted::resize_weighted(
+                image,
+                self.options,
+                self.method,
+                self.new_width,
+                self.new_height
+            );
+        }
         let (old_w, old_h) = image.dimensions();
         let depth = image.depth().bit_type();
 
@@ -65,54 +104,43 @@ impl OperationsTrait for Resize {
 
         #[cfg(feature = "threads")]
         {
-            std::thread::scope(|f| {
-                let mut errors = vec![];
-
-                for old_channel in image.channels_mut(false) {
-                    let result = f.spawn(|| {
-                        let mut new_channel = Channel::new_with_bit_type(new_length, depth);
-                        match depth {
-                            BitType::U8 => resize::<u8>(
-                                old_channel.reinterpret_as()?,
-                                new_channel.reinterpret_as_mut()?,
-                                self.method,
-                                old_w,
-                                old_h,
-                                self.new_width,
-                                self.new_height
-                            ),
-                            BitType::U16 => resize::<u16>(
-                                old_channel.reinterpret_as()?,
-                                new_channel.reinterpret_as_mut()?,
-                                self.method,
-                                old_w,
-                                old_h,
-                                self.new_width,
-                                self.new_height
-                            ),
-
-                            BitType::F32 => {
-                                resize::<f32>(
-                                    old_channel.reinterpret_as()?,
-                                    new_channel.reinterpret_as_mut()?,
-                                    self.method,
-                                    old_w,
-                                    old_h,
-                                    self.new_width,
-                                    self.new_height
-                                );
-                            }
-                            d => return Err(ImageErrors::ImageOperationNotImplemented("resize", d))
-                        }
-                        *old_channel = new_channel;
-                        Ok(())
-                    });
-                    errors.push(result);
+            ThreadPool::new().try_for_each(image.channels_mut(false), |old_channel| {
+                let mut new_channel = Channel::new_with_bit_type(new_length, depth);
+                match depth {
+                    BitType::U8 => resize::<u8>(
+                        old_channel.reinterpret_as()?,
+                        new_channel.reinterpret_as_mut()?,
+                        self.method,
+                        old_w,
+                        old_h,
+                        self.new_width,
+                        self.new_height
+                    ),
+                    BitType::U16 => resize::<u16>(
+                        old_channel.reinterpret_as()?,
+                        new_channel.reinterpret_as_mut()?,
+                        self.method,
+                        old_w,
+                        old_h,
+                        self.new_width,
+                        self.new_height
+                    ),
+
+                    BitType::F32 => {
+                        resize::<f32>(
+                            old_channel.reinterpret_as()?,
+                            new_channel.reinterpret_as_mut()?,
+                            self.method,
+                            old_w,
+                            old_h,
+                            self.new_width,
+                            self.new_height
+                        );
+                    }
+                    d => return Err(ImageErrors::ImageOperationNotImplemented("resize", d))
                 }
-                errors
-                    .into_iter()
-                    .map(|x| x.join().unwrap())
-                    .collect::<Result<Vec<()>, ImageErrors>>()
+                *old_channel = new_channel;
+                Ok(())
             })?;
         }
 
@@ -163,6 +191,10 @@ impl OperationsTrait for Resize {
     fn supported_types(&self) -> &'static [BitType] {
         &[BitType::U8, BitType::U16, BitType::F32]
     }
+
+    fn light_space(&self) -> LightSpace {
+        LightSpace::Linear
+    }
 }
 
 /// Return the image resize dimensions that would not cause a distortion
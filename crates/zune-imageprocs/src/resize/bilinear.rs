@@ -18,34 +18,20 @@ pub fn bilinear_impl<T>(
     let w_ratio = 1.0 / out_width as f32 * in_width as f32;
     let h_ratio = 1.0 / out_height as f32 * in_height as f32;
 
-    let smaller_image_to_larger = w_ratio < 1.0 && h_ratio < 1.0;
-
     for y in 0..out_height {
         let new_y = y as f32 * h_ratio;
-        let mut y0 = new_y.floor() as usize;
-        let mut y1 = y0 + 1;
+        // when an axis is enlarged, the samples after the last pixel go beyond
+        // the picture dimensions, so clamp them here
+        let y0 = (new_y.floor() as usize).min(in_height - 1);
+        let y1 = (y0 + 1).min(in_height - 1);
 
-        if smaller_image_to_larger {
-            y1 = y1.min(in_height - 1);
-            y0 = y0.min(in_height - 1);
-        }
         let b = new_y - y0 as f32;
 
         for x in 0..out_width {
             let new_x = x as f32 * w_ratio;
             // floor and truncate are slow due to handling overflow and such, so avoid them here
-            let mut x0 = new_x.floor() as usize;
-            let mut x1 = x0 + 1;
-
-            // PS: I'm not sure about the impact, but it cuts down on code executed
-            // the branch is deterministic hence the CPU should have an easy time predicting it
-            if smaller_image_to_larger {
-                // in case of result image being greater than source image, it may happen that
-                // the above go beyond picture dimensions, so clamp them here if they do
-                // clamp to image width and height
-                x1 = x1.min(in_width - 1);
-                x0 = x0.min(in_width - 1);
-            }
+            let x0 = (new_x.floor() as usize).min(in_width - 1);
+            let x1 = (x0 + 1).min(in_width - 1);
 
             let a = new_x - x0 as f32;
 
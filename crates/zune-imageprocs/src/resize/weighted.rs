@@ -0,0 +1,177 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! Alpha weighted and linear light resizing
+//!
+//! Interpolating sRGB encoded values directly darkens edges between
+//! bright and dark areas, and interpolating colors of fully transparent pixels
+//! (which are usually black) bleeds them into their opaque neighbours as a dark fringe.
+//!
+//! This converts channels to normalized floats, optionally moves color channels
+//! to linear light and premultiplies them by alpha, resizes and then undoes the
+//! conversions before storing the result in the original depth.
+use zune_core::bit_depth::BitType;
+#[cfg(feature = "threads")]
+use zune_core::threads::ThreadPool;
+use zune_image::channel::Channel;
+use zune_image::core_filters::transfer::{is_linear_light, is_srgb, TransferLut};
+use zune_image::errors::ImageErrors;
+use zune_image::image::Image;
+
+use crate::resize::{resize, ResizeMethod, ResizeOptions};
+
+/// Convert a channel to floats in the range `[0,1]`
+#[allow(clippy::cast_precision_loss)]
+fn to_plane(channel: &Channel, bit_type: BitType, max_value: f32) -> Result<Vec<f32>, ImageErrors> {
+    let plane = match bit_type {
+        BitType::U8 => channel
+            .reinterpret_as::<u8>()?
+            .iter()
+            .map(|x| f32::from(*x) / max_value)
+            .collect(),
+        BitType::U16 => channel
+            .reinterpret_as::<u16>()?
+            .iter()
+            .map(|x| f32::from(*x) / max_value)
+            .collect(),
+        BitType::F32 => channel.reinterpret_as::<f32>()?.to_vec(),
+        d => return Err(ImageErrors::ImageOperationNotImplemented("resize", d))
+    };
+    Ok(plane)
+}
+
+/// Convert a plane of normalized floats back to a channel, rounding and clamping integers
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn from_plane(plane: &[f32], bit_type: BitType, max_value: f32) -> Result<Channel, ImageErrors> {
+    let mut channel =
+        Channel::new_with_bit_type(plane.len() * bit_type.to_depth().size_of(), bit_type);
+
+    match bit_type {
+        BitType::U8 => {
+            for (out, x) in channel.reinterpret_as_mut::<u8>()?.iter_mut().zip(plane) {
+                *out = (x * max_value).round().clamp(0.0, max_value) as u8;
+            }
+        }
+        BitType::U16 => {
+            for (out, x) in channel.reinterpret_as_mut::<u16>()?.iter_mut().zip(plane) {
+                *out = (x * max_value).round().clamp(0.0, max_value) as u16;
+            }
+        }
+        BitType::F32 => channel.reinterpret_as_mut::<f32>()?.copy_from_slice(plane),
+        d => return Err(ImageErrors::ImageOperationNotImplemented("resize", d))
+    }
+    Ok(channel)
+}
+
+/// Multiply or divide color planes by the alpha plane, dividing by a zero alpha gives zero
+fn weigh(planes: &mut [Vec<f32>], alpha_position: usize, divide: bool) {
+    let alpha = core::mem::take(&mut planes[alpha_position]);
+
+    for (position, plane) in planes.iter_mut().enumerate() {
+        if position == alpha_position {
+            continue;
+        }
+        for (color, a) in plane.iter_mut().zip(&alpha) {
+            if !divide {
+                *color *= a;
+            } else if *a > 0.0 {
+                *color /= a;
+            } else {
+                *color = 0.0;
+            }
+        }
+    }
+    planes[alpha_position] = alpha;
+}
+
+/// Run color planes through a transfer function, leaving the alpha plane untouched
+fn transfer(planes: &mut [Vec<f32>], alpha_position: Option<usize>, lut: &TransferLut) {
+    for (position, plane) in planes.iter_mut().enumerate() {
+        if Some(position) != alpha_position {
+            for x in plane.iter_mut() {
+                *x = lut.apply(*x);
+            }
+        }
+    }
+}
+
+/// Resize all frames of `image` with the conversions in `options` applied
+pub(crate) fn resize_weighted(
+    image: &mut Image, options: ResizeOptions, method: ResizeMethod, new_width: usize,
+    new_height: usize
+) -> Result<(), ImageErrors> {
+    let (old_w, old_h) = image.dimensions();
+    let colorspace = image.colorspace();
+    let bit_type = image.depth().bit_type();
+    let max_value = f32::from(image.depth().max_value());
+
+    let alpha_position = colorspace.alpha_position();
+    // premultiplied input is un-premultiplied before linearizing and restored after
+    let premultiplied = alpha_position.filter(|_| image.metadata().is_premultiplied_alpha());
+    let weighted = alpha_position.filter(|_| options.premultiply || premultiplied.is_some());
+
+    // images already in linear light (e.g in a linear light pipeline) are not linearized again
+    let linearize = options.linearize && is_srgb(colorspace) && !is_linear_light(image);
+    let luts = linearize.then(|| (TransferLut::srgb_decode(), TransferLut::srgb_encode()));
+
+    let resize_plane = |plane: Vec<f32>| {
+        let mut output = vec![0.0; new_width * new_height];
+        resize::<f32>(
+            &plane,
+            &mut output,
+            method,
+            old_w,
+            old_h,
+            new_width,
+            new_height
+        );
+        output
+    };
+
+    for frame in image.frames_mut() {
+        let channels = frame.channels_mut(colorspace, false);
+
+        let mut planes = channels
+            .iter()
+            .map(|channel| to_plane(channel, bit_type, max_value))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if let Some(position) = premultiplied {
+            weigh(&mut planes, position, true);
+        }
+        if let Some((to_linear, _)) = &luts {
+            transfer(&mut planes, alpha_position, to_linear);
+        }
+        if let Some(position) = weighted {
+            weigh(&mut planes, position, false);
+        }
+
+        #[cfg(feature = "threads")]
+        let mut planes = ThreadPool::new().map(planes, resize_plane);
+
+        #[cfg(not(feature = "threads"))]
+        let mut planes: Vec<Vec<f32>> = planes.into_iter().map(resize_plane).collect();
+
+        if let Some(position) = weighted {
+            weigh(&mut planes, position, true);
+        }
+        if let Some((_, to_srgb)) = &luts {
+            transfer(&mut planes, alpha_position, to_srgb);
+        }
+        if let Some(position) = premultiplied {
+            weigh(&mut planes, position, false);
+        }
+
+        for (channel, plane) in channels.iter_mut().zip(&planes) {
+            *channel = from_plane(plane, bit_type, max_value)?;
+        }
+    }
+    image.set_dimensions(new_width, new_height);
+
+    Ok(())
+}
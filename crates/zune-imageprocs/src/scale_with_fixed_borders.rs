@@ -0,0 +1,221 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! Scale an image while keeping its borders intact (9-slice scaling)
+//!
+//! The image is split into nine regions by a border of `border` pixels
+//!
+//! ```text
+//!  ┌───┬───────────┬───┐
+//!  │ 1 │     2     │ 3 │
+//!  ├───┼───────────┼───┤
+//!  │   │           │   │
+//!  │ 4 │     5     │ 6 │
+//!  │   │           │   │
+//!  ├───┼───────────┼───┤
+//!  │ 7 │     8     │ 9 │
+//!  └───┴───────────┴───┘
+//! ```
+//! - Corners (1,3,7,9) are copied unchanged.
+//! - Top and bottom edges (2,8) are only scaled horizontally.
+//! - Left and right edges (4,6) are only scaled vertically.
+//! - The center (5) is scaled in both directions.
+//!
+//! This is commonly used for UI assets like buttons and panels, whose
+//! rounded corners and outlines would be distorted by a normal resize.
+use zune_core::bit_depth::BitType;
+use zune_image::channel::Channel;
+use zune_image::errors::ImageErrors;
+use zune_image::image::Image;
+use zune_image::traits::OperationsTrait;
+
+use crate::crop::crop;
+use crate::resize::{resize, ResizeMethod};
+use crate::traits::NumOps;
+
+/// Scale an image to new dimensions, preserving `border` pixels
+/// on every edge exactly
+///
+/// Borders must fit in both the input and output image, i.e `2*border`
+/// must be smaller than the input dimensions and not larger than the output
+/// dimensions.
+///
+/// # Example
+/// ```
+/// use zune_core::colorspace::ColorSpace;
+/// use zune_image::image::Image;
+/// use zune_image::traits::OperationsTrait;
+/// use zune_imageprocs::scale_with_fixed_borders::ScaleWithFixedBorders;
+///
+/// let mut image = Image::fill(128_u8, ColorSpace::RGBA, 24, 24);
+///
+/// ScaleWithFixedBorders::new(8, 200, 48).execute(&mut image).unwrap();
+///
+/// assert_eq!(image.dimensions(), (200, 48));
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub struct ScaleWithFixedBorders {
+    border: usize,
+    width:  usize,
+    height: usize
+}
+
+impl ScaleWithFixedBorders {
+    /// Create a new 9-slice scale operation
+    ///
+    /// # Arguments
+    /// - border: Number of pixels on every edge that are kept unscaled
+    /// - width: The new image width
+    /// - height: The new image height
+    #[must_use]
+    pub fn new(border: usize, width: usize, height: usize) -> ScaleWithFixedBorders {
+        ScaleWithFixedBorders {
+            border,
+            width,
+            height
+        }
+    }
+}
+
+impl OperationsTrait for ScaleWithFixedBorders {
+    fn name(&self) -> &'static str {
+        "Scale With Fixed Borders"
+    }
+
+    fn execute_impl(&self, image: &mut Image) -> Result<(), ImageErrors> {
+        let (old_w, old_h) = image.dimensions();
+        let border = self.border;
+
+        if 2 * border >= old_w || 2 * border >= old_h {
+            return Err(ImageErrors::GenericString(format!(
+                "Border of {border} pixels leaves no interior in the {old_w}x{old_h} image"
+            )));
+        }
+        if 2 * border > self.width || 2 * border > self.height || self.width * self.height == 0 {
+            return Err(ImageErrors::GenericString(format!(
+                "Border of {border} pixels doesn't fit in a {}x{} image",
+                self.width, self.height
+            )));
+        }
+        let depth = image.depth().bit_type();
+        let new_length = self.width * self.height * image.depth().size_of();
+
+        for channel in image.channels_mut(false) {
+            let mut new_channel = Channel::new_with_bit_type(new_length, depth);
+
+            match depth {
+                BitType::U8 => scale_with_fixed_borders::<u8>(
+                    channel.reinterpret_as()?,
+                    new_channel.reinterpret_as_mut()?,
+                    (old_w, old_h),
+                    (self.width, self.height),
+                    border
+                ),
+                BitType::U16 => scale_with_fixed_borders::<u16>(
+                    channel.reinterpret_as()?,
+                    new_channel.reinterpret_as_mut()?,
+                    (old_w, old_h),
+                    (self.width, self.height),
+                    border
+                ),
+                BitType::F32 => scale_with_fixed_borders::<f32>(
+                    channel.reinterpret_as()?,
+                    new_channel.reinterpret_as_mut()?,
+                    (old_w, old_h),
+                    (self.width, self.height),
+                    border
+                ),
+                d => return Err(ImageErrors::ImageOperationNotImplemented(self.name(), d))
+            }
+            *channel = new_channel;
+        }
+        image.set_dimensions(self.width, self.height);
+
+        Ok(())
+    }
+
+    fn supported_types(&self) -> &'static [BitType] {
+        &[BitType::U8, BitType::U16, BitType::F32]
+    }
+}
+
+/// Split `0..length` into the start border, interior and end border
+/// as `(offset, length)` pairs
+fn segments(length: usize, border: usize) -> [(usize, usize); 3] {
+    [
+        (0, border),
+        (border, length - 2 * border),
+        (length - border, border)
+    ]
+}
+
+/// 9-slice scale a single channel
+///
+/// # Arguments
+/// - in_channel: Input channel
+/// - out_channel: Output channel, of `out_dims.0 * out_dims.1` pixels
+/// - in_dims: Input width and height
+/// - out_dims: Output width and height
+/// - border: Number of pixels preserved on every edge
+///
+/// # Panics
+/// If `2*border` is not smaller than `in_dims` or is larger than `out_dims`
+pub fn scale_with_fixed_borders<T>(
+    in_channel: &[T], out_channel: &mut [T], in_dims: (usize, usize), out_dims: (usize, usize),
+    border: usize
+) where
+    T: Copy + Default + NumOps<T>,
+    f32: From<T>
+{
+    let (in_w, in_h) = in_dims;
+    let (out_w, out_h) = out_dims;
+
+    let mut region = vec![];
+    let mut scaled = vec![];
+
+    for ((in_y, in_rows), (out_y, out_rows)) in segments(in_h, border)
+        .into_iter()
+        .zip(segments(out_h, border))
+    {
+        for ((in_x, in_cols), (out_x, out_cols)) in segments(in_w, border)
+            .into_iter()
+            .zip(segments(out_w, border))
+        {
+            if out_cols == 0 || out_rows == 0 {
+                continue;
+            }
+            region.resize(in_cols * in_rows, T::default());
+            crop(in_channel, in_w, &mut region, in_cols, in_rows, in_x, in_y);
+
+            // corners and the fixed direction of edges are copied unchanged,
+            // bilinear with a ratio of one is a copy, but skip the floating point round trip
+            let source = if (in_cols, in_rows) == (out_cols, out_rows) {
+                &region
+            } else {
+                scaled.resize(out_cols * out_rows, T::default());
+                resize(
+                    &region,
+                    &mut scaled,
+                    ResizeMethod::Bilinear,
+                    in_cols,
+                    in_rows,
+                    out_cols,
+                    out_rows
+                );
+                &scaled
+            };
+
+            for (src_row, dst_row) in source
+                .chunks_exact(out_cols)
+                .zip(out_channel.chunks_exact_mut(out_w).skip(out_y))
+            {
+                dst_row[out_x..out_x + out_cols].copy_from_slice(src_row);
+            }
+        }
+    }
+}
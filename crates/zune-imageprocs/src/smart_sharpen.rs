@@ -0,0 +1,166 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! Edge aware sharpening
+//!
+//! A plain unsharp mask sharpens everything, including noise and grain in flat
+//! areas like skies or skin. Smart sharpen weighs the unsharp mask by the local
+//! gradient magnitude from a [Sobel](crate::sobel) filter, so edges get sharpened
+//! while flat areas are left alone.
+//!
+//! For every pixel
+//!
+//! ```text
+//! weight = min(gradient / edge_threshold, 1)
+//! pixel  = pixel + strength * weight * (pixel - blurred)
+//! ```
+//!
+//! where gradients are scaled so that a hard black to white edge has a gradient of `1.0`.
+use zune_core::bit_depth::BitType;
+use zune_image::errors::ImageErrors;
+use zune_image::image::Image;
+use zune_image::traits::OperationsTrait;
+
+use crate::gaussian_blur::gaussian_blur_f32;
+use crate::sobel::sobel_float;
+
+/// Sharpen edges without amplifying noise in flat areas
+///
+/// # Alpha channel
+/// - Alpha channel is ignored
+///
+/// # Example
+/// ```
+/// use zune_core::colorspace::ColorSpace;
+/// use zune_image::image::Image;
+/// use zune_image::traits::OperationsTrait;
+/// use zune_imageprocs::smart_sharpen::SmartSharpen;
+///
+/// let mut image = Image::fill(128_u8, ColorSpace::RGB, 100, 100);
+///
+/// SmartSharpen::new(1.0, 2.0, 0.1)
+///     .execute(&mut image)
+///     .unwrap();
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub struct SmartSharpen {
+    strength:       f32,
+    radius:         f32,
+    edge_threshold: f32
+}
+
+impl SmartSharpen {
+    /// Create a new smart sharpen operation
+    ///
+    /// # Arguments
+    /// - strength: How much of the difference between the image and its blur is added
+    ///   back, `1.0` doubles local contrast at edges
+    /// - radius: Sigma of the gaussian blur, larger values sharpen coarser details
+    /// - edge_threshold: Gradient at which sharpening is fully applied, as a fraction of a
+    ///   black to white edge, weaker gradients are sharpened proportionally less.
+    ///   `0.0` sharpens everything like a plain unsharp mask
+    #[must_use]
+    pub fn new(strength: f32, radius: f32, edge_threshold: f32) -> SmartSharpen {
+        SmartSharpen {
+            strength,
+            radius,
+            edge_threshold
+        }
+    }
+}
+
+impl OperationsTrait for SmartSharpen {
+    fn name(&self) -> &'static str {
+        "Smart Sharpen"
+    }
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn execute_impl(&self, image: &mut Image) -> Result<(), ImageErrors> {
+        if !(self.radius > 0.0 && self.radius.is_finite()) {
+            return Err(ImageErrors::GenericString(format!(
+                "Smart sharpen radius must be positive, found {}",
+                self.radius
+            )));
+        }
+        if self.edge_threshold.is_nan() || self.edge_threshold < 0.0 {
+            return Err(ImageErrors::GenericString(format!(
+                "Smart sharpen edge threshold can't be negative, found {}",
+                self.edge_threshold
+            )));
+        }
+        let (width, height) = image.dimensions();
+        let depth = image.depth().bit_type();
+
+        for channel in image.channels_mut(true) {
+            match depth {
+                BitType::U8 => smart_sharpen(
+                    channel.reinterpret_as_mut::<u8>()?,
+                    width,
+                    height,
+                    self,
+                    255.0,
+                    |x| x.round() as u8
+                ),
+                BitType::U16 => smart_sharpen(
+                    channel.reinterpret_as_mut::<u16>()?,
+                    width,
+                    height,
+                    self,
+                    65535.0,
+                    |x| x.round() as u16
+                ),
+                BitType::F32 => smart_sharpen(
+                    channel.reinterpret_as_mut::<f32>()?,
+                    width,
+                    height,
+                    self,
+                    1.0,
+                    |x| x
+                ),
+                d => return Err(ImageErrors::ImageOperationNotImplemented(self.name(), d))
+            }
+        }
+        Ok(())
+    }
+
+    fn supported_types(&self) -> &'static [BitType] {
+        &[BitType::U8, BitType::U16, BitType::F32]
+    }
+}
+
+/// Sharpen a single channel in place
+///
+/// Pixels are divided by `max` before processing and multiplied back before `convert`,
+/// integer conversions saturate
+fn smart_sharpen<T: Copy>(
+    channel: &mut [T], width: usize, height: usize, options: &SmartSharpen, max: f32,
+    convert: impl Fn(f32) -> T
+) where
+    f32: From<T>
+{
+    let pixels: Vec<f32> = channel.iter().map(|x| f32::from(*x) / max).collect();
+
+    let mut blurred = pixels.clone();
+    let mut scratch = vec![0.0; pixels.len()];
+    gaussian_blur_f32(&mut blurred, &mut scratch, width, height, options.radius);
+
+    let mut gradient = vec![0.0; pixels.len()];
+    sobel_float::<f32>(&pixels, &mut gradient, width, height);
+
+    for (((out, pixel), blurred), gradient) in
+        channel.iter_mut().zip(&pixels).zip(&blurred).zip(&gradient)
+    {
+        // a black to white edge has a sobel magnitude of 4
+        let edge = gradient / 4.0;
+        let weight =
+            if edge >= options.edge_threshold { 1.0 } else { edge / options.edge_threshold };
+        let sharpened = pixel + options.strength * weight * (pixel - blurred);
+
+        *out = convert(sharpened * max);
+    }
+}
@@ -8,6 +8,8 @@
 
 //! Sobel derivative filter
 use zune_core::bit_depth::BitType;
+#[cfg(feature = "threads")]
+use zune_core::threads::ThreadPool;
 use zune_image::channel::Channel;
 use zune_image::errors::ImageErrors;
 use zune_image::image::Image;
@@ -93,46 +95,31 @@ impl OperationsTrait for Sobel {
         }
         #[cfg(feature = "threads")]
         {
-            std::thread::scope(|s| {
-                let mut t_results = vec![];
-                for channel in image.channels_mut(true) {
-                    let result = s.spawn(|| {
-                        let mut out_channel = Channel::new_with_bit_type(channel.len(), depth);
-                        match depth {
-                            BitType::U8 => sobel_int::<u8>(
-                                channel.reinterpret_as()?,
-                                out_channel.reinterpret_as_mut()?,
-                                width,
-                                height
-                            ),
-                            BitType::U16 => sobel_int::<u16>(
-                                channel.reinterpret_as()?,
-                                out_channel.reinterpret_as_mut()?,
-                                width,
-                                height
-                            ),
-                            BitType::F32 => sobel_float::<f32>(
-                                channel.reinterpret_as()?,
-                                out_channel.reinterpret_as_mut()?,
-                                width,
-                                height
-                            ),
-                            d => {
-                                return Err(ImageErrors::ImageOperationNotImplemented(
-                                    self.name(),
-                                    d
-                                ))
-                            }
-                        }
-                        *channel = out_channel;
-                        Ok(())
-                    });
-                    t_results.push(result);
+            ThreadPool::new().try_for_each(image.channels_mut(true), |channel| {
+                let mut out_channel = Channel::new_with_bit_type(channel.len(), depth);
+                match depth {
+                    BitType::U8 => sobel_int::<u8>(
+                        channel.reinterpret_as()?,
+                        out_channel.reinterpret_as_mut()?,
+                        width,
+                        height
+                    ),
+                    BitType::U16 => sobel_int::<u16>(
+                        channel.reinterpret_as()?,
+                        out_channel.reinterpret_as_mut()?,
+                        width,
+                        height
+                    ),
+                    BitType::F32 => sobel_float::<f32>(
+                        channel.reinterpret_as()?,
+                        out_channel.reinterpret_as_mut()?,
+                        width,
+                        height
+                    ),
+                    d => return Err(ImageErrors::ImageOperationNotImplemented(self.name(), d))
                 }
-                t_results
-                    .into_iter()
-                    .map(|x| x.join().unwrap())
-                    .collect::<Result<Vec<()>, ImageErrors>>()
+                *channel = out_channel;
+                Ok(())
             })?;
         }
 
@@ -162,7 +149,7 @@ fn sobel_inner_f32<T>(c: &[T; 9]) -> T
     let mut sum_a = 0.0;
     sum_a += (f32::from(c[0]) * -1.) + (f32::from(c[2]) * 1.);
     sum_a += (f32::from(c[3]) * -2.) + (f32::from(c[5]) * 2.);
-    sum_a += (f32::from(c[6]) * -1.) + (f32::from(c[7]) * 1.);
+    sum_a += (f32::from(c[6]) * -1.) + (f32::from(c[8]) * 1.);
 
     // matrix
     // -1,-2,-1,
@@ -196,7 +183,7 @@ fn sobel_inner_i32<T>(c: &[T; 9]) -> T
     let mut sum_a = 0;
     sum_a += (i32::from(c[0]) * -1) + (i32::from(c[2]) * 1);
     sum_a += (i32::from(c[3]) * -2) + (i32::from(c[5]) * 2);
-    sum_a += (i32::from(c[6]) * -1) + (i32::from(c[7]) * 1);
+    sum_a += (i32::from(c[6]) * -1) + (i32::from(c[8]) * 1);
 
     // matrix
     // -1,-2,-1,
@@ -207,7 +194,10 @@ fn sobel_inner_i32<T>(c: &[T; 9]) -> T
     sum_b += (i32::from(c[2]) * -1) + (i32::from(c[6]) * 1);
     sum_b += (i32::from(c[7]) * 2) + (i32::from(c[8]) * 1);
 
-    T::from_f64(f64::from((sum_a * sum_a) + (sum_b * sum_b)).sqrt())
+    // squares overflow i32 for 16 bit images
+    let (sum_a, sum_b) = (f64::from(sum_a), f64::from(sum_b));
+
+    T::from_f64(((sum_a * sum_a) + (sum_b * sum_b)).sqrt())
 }
 
 /// Carry out the sobel filter for a float channel
@@ -84,46 +84,30 @@ impl OperationsTrait for SpatialOps {
                 self.operation
             );
 
-            std::thread::scope(|s| {
-                let mut errors = vec![];
-                for channel in image.channels_mut(false) {
-                    let result = s.spawn(|| {
-                        let mut new_channel =
-                            Channel::new_with_bit_type(channel.len(), depth.bit_type());
-
-                        match depth.bit_type() {
-                            BitType::U16 => spatial_ops(
-                                channel.reinterpret_as::<u16>()?,
-                                new_channel.reinterpret_as_mut::<u16>()?,
-                                self.radius,
-                                width,
-                                height,
-                                self.operation
-                            ),
-                            BitType::U8 => spatial_ops(
-                                channel.reinterpret_as::<u8>()?,
-                                new_channel.reinterpret_as_mut::<u8>()?,
-                                self.radius,
-                                width,
-                                height,
-                                self.operation
-                            ),
-                            d => {
-                                return Err(ImageErrors::ImageOperationNotImplemented(
-                                    self.name(),
-                                    d
-                                ))
-                            }
-                        }
-                        *channel = new_channel;
-                        Ok(())
-                    });
-                    errors.push(result);
+            ThreadPool::new().try_for_each(image.channels_mut(false), |channel| {
+                let mut new_channel = Channel::new_with_bit_type(channel.len(), depth.bit_type());
+
+                match depth.bit_type() {
+                    BitType::U16 => spatial_ops(
+                        channel.reinterpret_as::<u16>()?,
+                        new_channel.reinterpret_as_mut::<u16>()?,
+                        self.radius,
+                        width,
+                        height,
+                        self.operation
+                    ),
+                    BitType::U8 => spatial_ops(
+                        channel.reinterpret_as::<u8>()?,
+                        new_channel.reinterpret_as_mut::<u8>()?,
+                        self.radius,
+                        width,
+                        height,
+                        self.operation
+                    ),
+                    d => return Err(ImageErrors::ImageOperationNotImplemented(self.name(), d))
                 }
-                errors
-                    .into_iter()
-                    .map(|x| x.join().unwrap())
-                    .collect::<Result<Vec<()>, ImageErrors>>()
+                *channel = new_channel;
+                Ok(())
             })?;
         }
         Ok(())
@@ -135,6 +119,8 @@ impl OperationsTrait for SpatialOps {
 
 use zune_core::bit_depth::BitType;
 use zune_core::log::trace;
+#[cfg(feature = "threads")]
+use zune_core::threads::ThreadPool;
 use zune_image::channel::Channel;
 use zune_image::errors::ImageErrors;
 use zune_image::image::Image;
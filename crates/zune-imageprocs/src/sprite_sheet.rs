@@ -0,0 +1,328 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! Pack many small images into one sprite sheet (texture atlas)
+//!
+//! Images are placed tallest first with a skyline packer, which keeps track of
+//! the top edge of everything placed so far and puts every image at the
+//! lowest position it fits, leftmost on ties.
+//!
+//! ```text
+//!  ┌──────────────────────┐
+//!  │┌──────┐┌────┐┌──┐┌─┐ │
+//!  ││  1   ││ 2  ││3 │└─┘ │
+//!  ││      │└────┘└──┘┌──┐│
+//!  │└──────┘┌───┐┌─┐  │5 ││
+//!  │        │ 4 │└─┘  └──┘│
+//!  └──────────────────────┘
+//! ```
+use zune_core::bit_depth::BitType;
+use zune_core::colorspace::ColorSpace;
+use zune_image::errors::ImageErrors;
+use zune_image::image::Image;
+
+use crate::montage::blit;
+
+/// Where an image was placed in the sprite sheet
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct SpriteRect {
+    pub x:      usize,
+    pub y:      usize,
+    pub width:  usize,
+    pub height: usize
+}
+
+/// Create a sprite sheet from images
+///
+/// The sheet is RGBA with a transparent background and the depth of the first image,
+/// other images are converted to match it.
+/// Only the first frame of animated images is used.
+///
+/// # Example
+/// ```
+/// use zune_core::colorspace::ColorSpace;
+/// use zune_image::image::Image;
+/// use zune_imageprocs::sprite_sheet::SpriteSheet;
+///
+/// let images = [
+///     Image::fill(255_u8, ColorSpace::RGB, 64, 32),
+///     Image::fill(255_u8, ColorSpace::RGBA, 32, 32),
+///     Image::fill(255_u8, ColorSpace::Luma, 16, 16)
+/// ];
+///
+/// let (sheet, rects) = SpriteSheet::new()
+///     .set_max_width(128)
+///     .set_padding(1)
+///     .build(&images)
+///     .unwrap();
+///
+/// assert_eq!(sheet.colorspace(), ColorSpace::RGBA);
+/// assert_eq!((rects[0].x, rects[0].y), (1, 1));
+/// assert_eq!((rects[1].x, rects[1].y), (66, 1));
+/// assert_eq!((rects[2].x, rects[2].y), (99, 1));
+/// assert_eq!(sheet.dimensions(), (116, 34));
+/// ```
+#[derive(Copy, Clone, Debug, Default)]
+pub struct SpriteSheet {
+    max_width: usize,
+    padding:   usize
+}
+
+impl SpriteSheet {
+    /// Create a sprite sheet builder with no padding, whose width
+    /// is chosen to make the sheet roughly square
+    #[must_use]
+    pub fn new() -> SpriteSheet {
+        SpriteSheet::default()
+    }
+    /// Set the maximum width of the sheet, zero picks it automatically
+    ///
+    /// Images wider than this widen the sheet to fit them
+    #[must_use]
+    pub fn set_max_width(mut self, max_width: usize) -> SpriteSheet {
+        self.max_width = max_width;
+        self
+    }
+    /// Set the number of transparent pixels between images and around the sheet
+    #[must_use]
+    pub fn set_padding(mut self, padding: usize) -> SpriteSheet {
+        self.padding = padding;
+        self
+    }
+
+    /// Pack `images` into a sheet, returning the sheet and where every
+    /// image was placed, in the order of `images`
+    ///
+    /// # Errors
+    /// If `images` is empty or images cannot be converted to RGBA
+    /// and the depth of the first image
+    pub fn build(&self, images: &[Image]) -> Result<(Image, Vec<SpriteRect>), ImageErrors> {
+        let first = images
+            .first()
+            .ok_or(ImageErrors::GenericStr("No images for the sprite sheet"))?;
+
+        let depth = first.depth();
+        let colorspace = ColorSpace::RGBA;
+
+        let sizes: Vec<(usize, usize)> = images.iter().map(Image::dimensions).collect();
+        let rects = self.pack(&sizes);
+
+        let width = rects.iter().map(|x| x.x + x.width).max().unwrap_or(0) + self.padding;
+        let height = rects.iter().map(|x| x.y + x.height).max().unwrap_or(0) + self.padding;
+
+        let mut canvas = Image::fill(0_u8, colorspace, width, height);
+        canvas.convert_depth(depth)?;
+
+        for (image, rect) in images.iter().zip(&rects) {
+            if rect.width == 0 || rect.height == 0 {
+                continue;
+            }
+            let mut image = image.clone();
+            image.convert_color(colorspace)?;
+            image.convert_depth(depth)?;
+
+            let src_channels = image.frames_ref()[0].channels_ref(colorspace, false);
+            let dst_channels = canvas.frames_mut()[0].channels_mut(colorspace, false);
+            let (size, position) = ((rect.width, rect.height), (rect.x, rect.y));
+
+            for (src, dst) in src_channels.iter().zip(dst_channels.iter_mut()) {
+                match depth.bit_type() {
+                    BitType::U8 => blit::<u8>(
+                        src.reinterpret_as()?,
+                        dst.reinterpret_as_mut()?,
+                        size,
+                        width,
+                        position
+                    ),
+                    BitType::U16 => blit::<u16>(
+                        src.reinterpret_as()?,
+                        dst.reinterpret_as_mut()?,
+                        size,
+                        width,
+                        position
+                    ),
+                    BitType::F32 => blit::<f32>(
+                        src.reinterpret_as()?,
+                        dst.reinterpret_as_mut()?,
+                        size,
+                        width,
+                        position
+                    ),
+                    d => return Err(ImageErrors::ImageOperationNotImplemented("sprite sheet", d))
+                }
+            }
+        }
+        Ok((canvas, rects))
+    }
+
+    /// Choose a position for every image of `sizes`, in the order of `sizes`
+    #[allow(
+        clippy::cast_precision_loss,
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss
+    )]
+    fn pack(&self, sizes: &[(usize, usize)]) -> Vec<SpriteRect> {
+        let padding = self.padding;
+        // every image reserves its padding to the right and bottom,
+        // the left and top padding of the sheet is added when placing
+        let padded = |(w, h): (usize, usize)| (w + padding, h + padding);
+
+        let widest = sizes.iter().map(|x| padded(*x).0).max().unwrap_or(0);
+
+        let bin_width = if self.max_width == 0 {
+            let area: usize = sizes.iter().map(|x| padded(*x).0 * padded(*x).1).sum();
+            (area as f64).sqrt().ceil() as usize
+        } else {
+            self.max_width.saturating_sub(padding)
+        }
+        .max(widest);
+
+        // tallest first packs tighter
+        let mut order: Vec<usize> = (0..sizes.len()).collect();
+        order.sort_by_key(|x| core::cmp::Reverse((sizes[*x].1, sizes[*x].0)));
+
+        let mut skyline = Skyline::new(bin_width);
+        let mut rects = vec![
+            SpriteRect {
+                x:      0,
+                y:      0,
+                width:  0,
+                height: 0
+            };
+            sizes.len()
+        ];
+        for position in order {
+            let (width, height) = sizes[position];
+
+            if width == 0 || height == 0 {
+                // empty images take no space
+                rects[position] = SpriteRect {
+                    x: padding,
+                    y: padding,
+                    width,
+                    height
+                };
+                continue;
+            }
+            let (x, y) = skyline.insert(padded((width, height)));
+
+            rects[position] = SpriteRect {
+                x: x + padding,
+                y: y + padding,
+                width,
+                height
+            };
+        }
+        rects
+    }
+}
+
+/// The top edge of everything placed so far, as segments sorted by x
+/// that together span the width of the bin
+struct Skyline {
+    /// `(x, y, width)` of every segment
+    segments: Vec<(usize, usize, usize)>
+}
+
+impl Skyline {
+    fn new(width: usize) -> Skyline {
+        Skyline {
+            segments: vec![(0, 0, width)]
+        }
+    }
+
+    /// Return the lowest `y` a `width` wide rectangle starting at segment `start` can sit on,
+    /// or `None` if it goes past the right edge
+    fn fit(&self, start: usize, width: usize) -> Option<usize> {
+        let x = self.segments[start].0;
+        let mut y = 0;
+
+        for &(seg_x, seg_y, _) in &self.segments[start..] {
+            if seg_x >= x + width {
+                return Some(y);
+            }
+            y = y.max(seg_y);
+        }
+        let (last_x, _, last_width) = self.segments[self.segments.len() - 1];
+
+        (x + width <= last_x + last_width).then_some(y)
+    }
+
+    /// Place a rectangle at the lowest, then leftmost position it fits and return its corner
+    fn insert(&mut self, (width, height): (usize, usize)) -> (usize, usize) {
+        let (start, y) = (0..self.segments.len())
+            .filter_map(|start| Some((start, self.fit(start, width)?)))
+            .min_by_key(|(start, y)| (*y, self.segments[*start].0))
+            // wider than the bin, only happens for zero width bins
+            .unwrap_or((0, self.segments.iter().map(|x| x.1).max().unwrap_or(0)));
+
+        let x = self.segments[start].0;
+        let end = x + width;
+
+        // cut the segments under the new rectangle out, keeping what sticks out on the right
+        let mut remaining = Vec::with_capacity(self.segments.len() + 1);
+
+        for &(seg_x, seg_y, seg_width) in &self.segments {
+            let seg_end = seg_x + seg_width;
+
+            if seg_end <= x || seg_x >= end {
+                remaining.push((seg_x, seg_y, seg_width));
+            } else if seg_end > end {
+                remaining.push((end, seg_y, seg_end - end));
+            }
+        }
+        let at = remaining.partition_point(|seg| seg.0 < x);
+        remaining.insert(at, (x, y + height, width));
+
+        // merge neighbours of the same height
+        remaining.dedup_by(|next, previous| {
+            if next.1 == previous.1 && previous.0 + previous.2 == next.0 {
+                previous.2 += next.2;
+                true
+            } else {
+                false
+            }
+        });
+        self.segments = remaining;
+
+        (x, y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sprite_sheet::SpriteSheet;
+
+    #[test]
+    fn test_packed_rects_dont_overlap() {
+        let sizes: Vec<(usize, usize)> = (0..40)
+            .map(|x| (3 + (x * 7) % 13, 2 + (x * 5) % 11))
+            .collect();
+
+        for (max_width, padding) in [(0, 0), (0, 2), (40, 1), (1, 0)] {
+            let rects = SpriteSheet::new()
+                .set_max_width(max_width)
+                .set_padding(padding)
+                .pack(&sizes);
+
+            for (a, rect) in rects.iter().enumerate() {
+                assert_eq!((rect.width, rect.height), sizes[a]);
+                assert!(rect.x >= padding && rect.y >= padding);
+
+                for other in &rects[a + 1..] {
+                    let apart_x = rect.x + rect.width + padding <= other.x
+                        || other.x + other.width + padding <= rect.x;
+                    let apart_y = rect.y + rect.height + padding <= other.y
+                        || other.y + other.height + padding <= rect.y;
+
+                    assert!(apart_x || apart_y, "{rect:?} overlaps {other:?}");
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,103 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! Swap two channels of an image
+//!
+//! This fixes images whose channels were stored in the wrong order, e.g red and blue
+//! swapped by a writer expecting BGR, without changing the image colorspace.
+use zune_core::bit_depth::BitType;
+use zune_core::colorspace::ColorSpace;
+use zune_image::errors::ImageErrors;
+use zune_image::image::Image;
+use zune_image::traits::OperationsTrait;
+
+/// Swap two channels of an image
+///
+/// Channels are indexed in colorspace order, e.g for RGBA, `0` is red and `3` is alpha
+///
+/// # Example
+/// Swap red and blue
+/// ```
+/// use zune_core::colorspace::ColorSpace;
+/// use zune_image::image::Image;
+/// use zune_image::traits::OperationsTrait;
+/// use zune_imageprocs::swap_channels::SwapChannels;
+///
+/// let mut image = Image::from_u8(&[10, 20, 30], 1, 1, ColorSpace::RGB);
+///
+/// SwapChannels::new(0, 2).execute(&mut image).unwrap();
+///
+/// assert_eq!(image.flatten_to_u8()[0], [30, 20, 10]);
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub struct SwapChannels {
+    first:  usize,
+    second: usize
+}
+
+impl SwapChannels {
+    /// Create a new operation swapping channels `first` and `second`
+    #[must_use]
+    pub fn new(first: usize, second: usize) -> SwapChannels {
+        SwapChannels { first, second }
+    }
+
+    fn check_channels(&self, colorspace: ColorSpace) -> Result<(), ImageErrors> {
+        let components = colorspace.num_components();
+
+        if self.first >= components || self.second >= components {
+            return Err(ImageErrors::GenericString(format!(
+                "Cannot swap channels {} and {}, {colorspace:?} has {components} channels",
+                self.first, self.second
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl OperationsTrait for SwapChannels {
+    fn name(&self) -> &'static str {
+        "Swap channels"
+    }
+
+    fn execute_impl(&self, image: &mut Image) -> Result<(), ImageErrors> {
+        self.check_channels(image.colorspace())?;
+
+        for frame in image.frames_mut() {
+            frame.channels_vec().swap(self.first, self.second);
+        }
+        Ok(())
+    }
+
+    fn supported_types(&self) -> &'static [BitType] {
+        &[BitType::U8, BitType::U16, BitType::F32]
+    }
+
+    fn interleaved_colorspace(&self, input: ColorSpace) -> Option<ColorSpace> {
+        self.check_channels(input).ok().map(|()| input)
+    }
+
+    fn execute_interleaved(
+        &self, pixels: &mut Vec<u8>, colorspace: ColorSpace
+    ) -> Result<(), ImageErrors> {
+        self.check_channels(colorspace)?;
+
+        swap_channels_interleaved_u8(pixels, colorspace.num_components(), self.first, self.second);
+        Ok(())
+    }
+}
+
+/// Swap samples `first` and `second` of every pixel of interleaved 8 bit pixels
+/// with `components` channels
+pub fn swap_channels_interleaved_u8(
+    pixels: &mut [u8], components: usize, first: usize, second: usize
+) {
+    for pixel in pixels.chunks_exact_mut(components) {
+        pixel.swap(first, second);
+    }
+}
@@ -10,7 +10,8 @@ use zune_core::bit_depth::BitType;
 use zune_core::log::warn;
 use zune_image::errors::ImageErrors;
 use zune_image::image::Image;
-use zune_image::traits::OperationsTrait;
+use zune_image::traits::{LightSpace, OperationsTrait};
+use zune_image::view::ImageViewMut;
 
 use crate::traits::NumOps;
 
@@ -106,9 +107,33 @@ impl OperationsTrait for Threshold {
 
         Ok(())
     }
+    #[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
+    fn execute_view_impl(&self, view: &mut ImageViewMut) -> Result<(), ImageErrors> {
+        if !view.colorspace().is_grayscale() {
+            warn!("Threshold works well with grayscale images, results may be something you don't expect");
+        }
+        let (method, value) = (self.method, self.threshold);
+
+        match view.depth().bit_type() {
+            BitType::U16 => {
+                let value = value.clamp(0., 65535.) as u16;
+                view.for_each_row_mut(true, |row| threshold(row, value, method))
+            }
+            BitType::U8 => {
+                let value = value.clamp(0., 255.) as u8;
+                view.for_each_row_mut(true, |row| threshold(row, value, method))
+            }
+            BitType::F32 => view.for_each_row_mut(true, |row| threshold(row, value, method)),
+            d => Err(ImageErrors::ImageOperationNotImplemented("threshold", d))
+        }
+    }
     fn supported_types(&self) -> &'static [BitType] {
         &[BitType::U8, BitType::U16, BitType::F32]
     }
+
+    fn light_space(&self) -> LightSpace {
+        LightSpace::Encoded
+    }
 }
 #[rustfmt::skip]
 pub fn threshold<T>(in_channel: &mut [T], threshold: T, method: ThresholdMethod)
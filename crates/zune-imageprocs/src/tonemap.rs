@@ -0,0 +1,271 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! Tone mapping, converting HDR images to displayable images
+//!
+//! HDR images (e.g from radiance `.hdr` files) store linear light with values well above
+//! `1.0`, simply clamping them blows out every highlight, tone mapping compresses
+//! the whole range into `0.0..=1.0` instead.
+//!
+//! Pipeline for every color channel is
+//!
+//! ```text
+//! x = pixel * 2^exposure
+//! x = operator(x) / operator(white_point)
+//! x = srgb_encode(clamp(x, 0, 1))
+//! ```
+//!
+//! after which the image is converted to the output depth.
+//!
+//! # Operators
+//! - [Reinhard](ToneMapOperator::Reinhard): `x / (1 + x)`, extended with a white point
+//!   as in Reinhard et al., *Photographic Tone Reproduction for Digital Images*.
+//! - [Aces](ToneMapOperator::Aces): Krzysztof Narkowicz's fit of the ACES filmic curve,
+//!   contrasty with a slight toe.
+//! - [Hable](ToneMapOperator::Hable): John Hable's Uncharted 2 filmic curve.
+use zune_core::bit_depth::{BitDepth, BitType};
+use zune_core::colorspace::ColorSpace;
+use zune_image::channel::Channel;
+use zune_image::core_filters::transfer::linear_to_srgb;
+use zune_image::errors::ImageErrors;
+use zune_image::image::Image;
+use zune_image::traits::OperationsTrait;
+
+/// Tone mapping curve to use
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ToneMapOperator {
+    /// Reinhard's operator, without a white point it never reaches white
+    Reinhard,
+    /// Fit of the ACES filmic curve
+    Aces,
+    /// Hable's filmic curve, white point defaults to `11.2`
+    Hable
+}
+
+impl ToneMapOperator {
+    /// Parse an operator from its lowercase name
+    pub fn from_string_result(input: &str) -> Result<Self, String> {
+        match input {
+            "reinhard" => Ok(Self::Reinhard),
+            "aces" => Ok(Self::Aces),
+            "hable" => Ok(Self::Hable),
+            _ => Err(format!(
+                "Unknown tone map operator {input}, expected one of reinhard, aces or hable"
+            ))
+        }
+    }
+
+    fn default_white_point(self) -> Option<f32> {
+        match self {
+            ToneMapOperator::Reinhard | ToneMapOperator::Aces => None,
+            ToneMapOperator::Hable => Some(11.2)
+        }
+    }
+}
+
+/// Map an HDR float image to a display referred image
+///
+/// # Alpha channel
+/// - Alpha channel is clamped to `0.0..=1.0` but otherwise untouched
+///
+/// # Example
+/// ```
+/// use zune_core::bit_depth::BitDepth;
+/// use zune_core::colorspace::ColorSpace;
+/// use zune_image::image::Image;
+/// use zune_image::traits::OperationsTrait;
+/// use zune_imageprocs::tonemap::{ToneMap, ToneMapOperator};
+///
+/// let mut image = Image::fill(4.0_f32, ColorSpace::RGB, 100, 100);
+///
+/// ToneMap::new(ToneMapOperator::Aces)
+///     .set_exposure(-1.0)
+///     .execute(&mut image)
+///     .unwrap();
+///
+/// assert_eq!(image.depth(), BitDepth::Eight);
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub struct ToneMap {
+    operator:     ToneMapOperator,
+    exposure:     f32,
+    white_point:  Option<f32>,
+    output_depth: BitDepth
+}
+
+impl ToneMap {
+    /// Create a new tone mapping operation
+    ///
+    /// Output is an 8 bit image, with no exposure change and the operator's
+    /// default white point
+    #[must_use]
+    pub fn new(operator: ToneMapOperator) -> ToneMap {
+        ToneMap {
+            operator,
+            exposure: 0.0,
+            white_point: operator.default_white_point(),
+            output_depth: BitDepth::Eight
+        }
+    }
+    /// Set exposure adjustment in stops, applied before tone mapping
+    ///
+    /// Each stop doubles (or for negative values halves) the light
+    #[must_use]
+    pub fn set_exposure(mut self, stops: f32) -> ToneMap {
+        self.exposure = stops;
+        self
+    }
+    /// Set the smallest value (after exposure) that is mapped to white
+    #[must_use]
+    pub fn set_white_point(mut self, white_point: f32) -> ToneMap {
+        self.white_point = Some(white_point);
+        self
+    }
+    /// Set the output depth, one of 8 bit, 16 bit or float.
+    ///
+    /// Float output is display referred, in `0.0..=1.0`
+    #[must_use]
+    pub fn set_output_depth(mut self, depth: BitDepth) -> ToneMap {
+        self.output_depth = depth;
+        self
+    }
+}
+
+impl OperationsTrait for ToneMap {
+    fn name(&self) -> &'static str {
+        "Tone Map"
+    }
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn execute_impl(&self, image: &mut Image) -> Result<(), ImageErrors> {
+        if !matches!(
+            self.output_depth,
+            BitDepth::Eight
+                | BitDepth::Ten
+                | BitDepth::Twelve
+                | BitDepth::Sixteen
+                | BitDepth::Float32
+        ) {
+            return Err(ImageErrors::GenericString(format!(
+                "Unsupported tone map output depth {:?}",
+                self.output_depth
+            )));
+        }
+        if let Some(white) = self.white_point {
+            if !(white > 0.0 && white.is_finite()) {
+                return Err(ImageErrors::GenericString(format!(
+                    "White point must be positive, found {white}"
+                )));
+            }
+        }
+        let scale = self.exposure.exp2();
+        let white = self
+            .white_point
+            .map_or(1.0, |x| curve(self.operator, x, self.white_point));
+
+        let colorspace = image.colorspace();
+        let alpha_position = colorspace.alpha_position();
+
+        for frame in image.frames_mut() {
+            for (position, channel) in frame.channels_mut(colorspace, false).iter_mut().enumerate()
+            {
+                let pixels = channel.reinterpret_as_mut::<f32>()?;
+
+                if Some(position) == alpha_position {
+                    for pixel in pixels.iter_mut() {
+                        *pixel = pixel.clamp(0.0, 1.0);
+                    }
+                } else {
+                    for pixel in pixels.iter_mut() {
+                        let mapped = curve(self.operator, *pixel * scale, self.white_point) / white;
+                        *pixel = linear_to_srgb(mapped.clamp(0.0, 1.0));
+                    }
+                }
+                // depth conversion truncates, round instead so white stays white
+                let length = pixels.len();
+
+                match self.output_depth {
+                    BitDepth::Eight => {
+                        let mut new_channel = Channel::new_with_bit_type(length, BitType::U8);
+                        quantize(
+                            channel.reinterpret_as()?,
+                            new_channel.reinterpret_as_mut()?,
+                            |x| (x * 255.0).round() as u8
+                        );
+                        *channel = new_channel;
+                    }
+                    BitDepth::Ten | BitDepth::Twelve | BitDepth::Sixteen => {
+                        let max = f32::from(self.output_depth.max_value());
+
+                        let mut new_channel = Channel::new_with_bit_type(length * 2, BitType::U16);
+                        quantize(
+                            channel.reinterpret_as()?,
+                            new_channel.reinterpret_as_mut()?,
+                            |x| (x * max).round() as u16
+                        );
+                        *channel = new_channel;
+                    }
+                    _ => ()
+                }
+            }
+        }
+        image.set_depth(self.output_depth);
+
+        Ok(())
+    }
+
+    fn supported_colorspaces(&self) -> &'static [ColorSpace] {
+        &[
+            ColorSpace::Luma,
+            ColorSpace::LumaA,
+            ColorSpace::RGB,
+            ColorSpace::RGBA,
+            ColorSpace::BGR,
+            ColorSpace::BGRA,
+            ColorSpace::ARGB
+        ]
+    }
+
+    fn supported_types(&self) -> &'static [BitType] {
+        &[BitType::F32]
+    }
+
+    fn output_depth(&self, _: BitDepth) -> BitDepth {
+        self.output_depth
+    }
+}
+
+/// Apply a tone mapping curve to a linear value
+fn curve(operator: ToneMapOperator, x: f32, white_point: Option<f32>) -> f32 {
+    let x = x.max(0.0);
+
+    match operator {
+        ToneMapOperator::Reinhard => match white_point {
+            Some(white) => x * (1.0 + x / (white * white)) / (1.0 + x),
+            None => x / (1.0 + x)
+        },
+        ToneMapOperator::Aces => (x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14),
+        ToneMapOperator::Hable => {
+            const A: f32 = 0.15; // shoulder strength
+            const B: f32 = 0.50; // linear strength
+            const C: f32 = 0.10; // linear angle
+            const D: f32 = 0.20; // toe strength
+            const E: f32 = 0.02; // toe numerator
+            const F: f32 = 0.30; // toe denominator
+
+            ((x * (A * x + C * B) + D * E) / (x * (A * x + B) + D * F)) - E / F
+        }
+    }
+}
+
+/// Convert display referred `0.0..=1.0` values to integers
+fn quantize<T>(pixels: &[f32], out: &mut [T], convert: impl Fn(f32) -> T) {
+    for (out, pixel) in out.iter_mut().zip(pixels) {
+        *out = convert(*pixel);
+    }
+}
@@ -15,9 +15,10 @@ use zune_core::log::trace;
 use zune_image::channel::Channel;
 use zune_image::errors::ImageErrors;
 use zune_image::image::Image;
-use zune_image::traits::OperationsTrait;
+use zune_image::traits::{OperationsTrait, UnsignedSample};
 
 use crate::transpose::scalar::transpose_scalar;
+use crate::utils::match_unsigned;
 
 pub(crate) mod scalar;
 pub(crate) mod sse41;
@@ -55,23 +56,14 @@ impl OperationsTrait for Transpose {
         for channel in image.channels_mut(false) {
             let mut out_channel = Channel::new_with_bit_type(out_dim, depth.bit_type());
 
-            match depth.bit_type() {
-                BitType::U8 => {
-                    transpose_u8(
-                        channel.reinterpret_as::<u8>()?,
-                        out_channel.reinterpret_as_mut::<u8>()?,
-                        width,
-                        height
-                    );
-                }
-                BitType::U16 => {
-                    transpose_u16(
-                        channel.reinterpret_as::<u16>()?,
-                        out_channel.reinterpret_as_mut::<u16>()?,
-                        width,
-                        height
-                    );
-                }
+            match_unsigned!(
+                depth.bit_type(),
+                T => transpose_unsigned::<T>(
+                    channel.reinterpret_as()?,
+                    out_channel.reinterpret_as_mut()?,
+                    width,
+                    height
+                ),
                 BitType::F32 => {
                     transpose_float(
                         channel.reinterpret_as()?,
@@ -83,7 +75,7 @@ impl OperationsTrait for Transpose {
                 d => {
                     return Err(ImageErrors::ImageOperationNotImplemented(self.name(), d));
                 }
-            };
+            );
             *channel = out_channel;
         }
 
@@ -142,6 +134,27 @@ pub fn transpose_u8(in_matrix: &[u8], out_matrix: &mut [u8], width: usize, heigh
     transpose_scalar(in_matrix, out_matrix, width, height);
 }
 
+/// Transpose unsigned samples, using the SIMD kernel for their type when available
+pub fn transpose_unsigned<T: UnsignedSample>(
+    in_matrix: &[T], out_matrix: &mut [T], width: usize, height: usize
+) {
+    match T::BIT_TYPE {
+        BitType::U8 => transpose_u8(
+            bytemuck::cast_slice(in_matrix),
+            bytemuck::cast_slice_mut(out_matrix),
+            width,
+            height
+        ),
+        BitType::U16 => transpose_u16(
+            bytemuck::cast_slice(in_matrix),
+            bytemuck::cast_slice_mut(out_matrix),
+            width,
+            height
+        ),
+        _ => transpose_scalar(in_matrix, out_matrix, width, height)
+    }
+}
+
 pub fn transpose_float(in_matrix: &[f32], out_matrix: &mut [f32], width: usize, height: usize) {
     #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
     {
@@ -37,3 +37,27 @@ pub fn z_prefetch<T>(data: &[T], position: usize) {
         }
     }
 }
+
+/// Match on a [`BitType`](zune_core::bit_depth::BitType), evaluating `$body` once for
+/// `u8` and once for `u16` with `$sample` naming the sample type
+///
+/// Operations whose kernels are generic over
+/// [`UnsignedSample`](zune_image::traits::UnsignedSample) use this so both depths
+/// share a single arm, the remaining arms (floats, errors) follow it.
+macro_rules! match_unsigned {
+    ($bit_type:expr, $sample:ident => $body:expr, $($arms:tt)*) => {
+        match $bit_type {
+            zune_core::bit_depth::BitType::U8 => {
+                type $sample = u8;
+                $body
+            }
+            zune_core::bit_depth::BitType::U16 => {
+                type $sample = u16;
+                $body
+            }
+            $($arms)*
+        }
+    };
+}
+
+pub(crate) use match_unsigned;
@@ -0,0 +1,151 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! Lens vignette correction
+//!
+//! Lenses let in less light at the edges of the frame than at the center, making
+//! corners darker, this applies a radial gain to undo that.
+//!
+//! # Algorithm
+//! ```text
+//! pixel = pixel * (1 + strength * r^2)
+//! ```
+//! where `r` is the distance from the image center, normalized so that it's zero at the
+//! center and one at the corners.
+//!
+//! A positive strength brightens the corners, a negative strength darkens them, adding a
+//! vignette. Gains below zero are clamped to zero.
+use zune_core::bit_depth::BitType;
+use zune_core::colorspace::ColorSpace;
+use zune_image::errors::ImageErrors;
+use zune_image::image::Image;
+use zune_image::traits::OperationsTrait;
+
+use crate::traits::NumOps;
+
+/// Correct (or add) lens vignetting
+///
+/// # Alpha channel
+/// - Alpha channel is ignored
+///
+/// # Example
+/// ```
+/// use zune_core::colorspace::ColorSpace;
+/// use zune_image::image::Image;
+/// use zune_image::traits::OperationsTrait;
+/// use zune_imageprocs::vignette::LensVignetteCorrect;
+///
+/// let mut image = Image::fill(100_u8, ColorSpace::RGB, 100, 100);
+/// // corners become 30% brighter
+/// LensVignetteCorrect::new(0.3).execute(&mut image).unwrap();
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub struct LensVignetteCorrect {
+    strength: f32
+}
+
+impl LensVignetteCorrect {
+    /// Create a new vignette correction
+    ///
+    /// # Arguments
+    /// - strength: Gain added at the corners, `0.3` makes corners 30% brighter,
+    ///   negative values darken them
+    #[must_use]
+    pub fn new(strength: f32) -> LensVignetteCorrect {
+        LensVignetteCorrect { strength }
+    }
+}
+
+impl OperationsTrait for LensVignetteCorrect {
+    fn name(&self) -> &'static str {
+        "Lens Vignette Correct"
+    }
+
+    fn execute_impl(&self, image: &mut Image) -> Result<(), ImageErrors> {
+        let (width, height) = image.dimensions();
+        let depth = image.depth().bit_type();
+
+        for channel in image.channels_mut(true) {
+            match depth {
+                BitType::U8 => vignette_correct(
+                    channel.reinterpret_as_mut::<u8>()?,
+                    width,
+                    height,
+                    self.strength
+                ),
+                BitType::U16 => vignette_correct(
+                    channel.reinterpret_as_mut::<u16>()?,
+                    width,
+                    height,
+                    self.strength
+                ),
+                BitType::F32 => vignette_correct(
+                    channel.reinterpret_as_mut::<f32>()?,
+                    width,
+                    height,
+                    self.strength
+                ),
+                d => return Err(ImageErrors::ImageOperationNotImplemented(self.name(), d))
+            }
+        }
+        Ok(())
+    }
+
+    fn supported_colorspaces(&self) -> &'static [ColorSpace] {
+        &[
+            ColorSpace::Luma,
+            ColorSpace::LumaA,
+            ColorSpace::RGB,
+            ColorSpace::RGBA,
+            ColorSpace::BGR,
+            ColorSpace::BGRA,
+            ColorSpace::ARGB
+        ]
+    }
+
+    fn supported_types(&self) -> &'static [BitType] {
+        &[BitType::U8, BitType::U16, BitType::F32]
+    }
+}
+
+/// Apply a radial gain to a single channel
+///
+/// # Arguments
+/// - channel: Channel to correct, in place
+/// - width: Channel width
+/// - height: Channel height
+/// - strength: Gain added at the corners
+///
+/// Integer values saturate at the maximum value of the type
+#[allow(clippy::cast_precision_loss)]
+pub fn vignette_correct<T>(channel: &mut [T], width: usize, height: usize, strength: f32)
+where
+    T: Copy + NumOps<T>,
+    f32: From<T>
+{
+    if width == 0 {
+        return;
+    }
+    let center_x = (width as f32 - 1.0) / 2.0;
+    let center_y = (height as f32 - 1.0) / 2.0;
+    // distance to the corners, where r is one
+    let corner = center_x * center_x + center_y * center_y;
+
+    for (y, row) in channel.chunks_exact_mut(width).enumerate() {
+        let dy = y as f32 - center_y;
+
+        for (x, pixel) in row.iter_mut().enumerate() {
+            let dx = x as f32 - center_x;
+
+            let r2 = if corner > 0.0 { (dx * dx + dy * dy) / corner } else { 0.0 };
+            let gain = (1.0 + strength * r2).max(0.0);
+
+            *pixel = T::from_f32(f32::from(*pixel) * gain);
+        }
+    }
+}
@@ -0,0 +1,445 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! Geometric warps, affine and perspective transforms
+//!
+//! Both transforms take a matrix mapping source pixel coordinates to destination
+//! pixel coordinates,
+//!
+//! ```text
+//!  x'     m00 m01 m02     x
+//!  y'  =  m10 m11 m12  *  y
+//!  w'     m20 m21 m22     1
+//! ```
+//! with the destination at `(x'/w', y'/w')`. Affine transforms have the last row
+//! fixed to `0 0 1`, which means they can rotate, scale, shear and translate but
+//! parallel lines stay parallel, perspective transforms don't have that restriction
+//! and can e.g. map a photographed page back into a rectangle.
+//!
+//! # Algorithm
+//! The matrix is inverted, and every destination pixel is sampled from the source
+//! image with bilinear interpolation, samples outside the source are handled by
+//! the [`BorderMode`].
+use zune_core::bit_depth::BitType;
+use zune_image::channel::Channel;
+use zune_image::errors::ImageErrors;
+use zune_image::image::Image;
+use zune_image::traits::OperationsTrait;
+
+use crate::traits::NumOps;
+
+/// How pixels outside of the source image are sampled
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum BorderMode {
+    /// Pixels outside the image are zero, i.e black (and transparent for images with alpha)
+    Constant,
+    /// Pixels outside the image take the value of the nearest edge pixel
+    ///
+    /// ```text
+    /// a a | a b c | c c
+    /// ```
+    Replicate,
+    /// Pixels outside the image mirror the image across its edges
+    ///
+    /// ```text
+    /// b a | a b c | c b
+    /// ```
+    Reflect
+}
+
+/// Transform an image with a 2x3 affine matrix
+///
+/// # Example
+/// Rotate an image by 10 degrees around its top left corner and move it 20 pixels
+/// to the right
+/// ```
+/// use zune_core::colorspace::ColorSpace;
+/// use zune_image::image::Image;
+/// use zune_image::traits::OperationsTrait;
+/// use zune_imageprocs::warp::Affine;
+///
+/// let (sin, cos) = 10_f32.to_radians().sin_cos();
+///
+/// let mut image = Image::fill(128_u8, ColorSpace::RGB, 100, 100);
+///
+/// Affine::new([[cos, -sin, 20.0], [sin, cos, 0.0]])
+///     .set_output_size(140, 120)
+///     .execute(&mut image)
+///     .unwrap();
+///
+/// assert_eq!(image.dimensions(), (140, 120));
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub struct Affine {
+    matrix:      [[f32; 3]; 2],
+    border:      BorderMode,
+    output_size: Option<(usize, usize)>
+}
+
+impl Affine {
+    /// Create a new affine transform
+    ///
+    /// The output has the same dimensions as the input and
+    /// pixels outside of the source are black
+    ///
+    /// # Arguments
+    /// - matrix: The first two rows of the matrix mapping source to destination coordinates
+    #[must_use]
+    pub fn new(matrix: [[f32; 3]; 2]) -> Affine {
+        Affine {
+            matrix,
+            border: BorderMode::Constant,
+            output_size: None
+        }
+    }
+    /// Set how pixels outside the source image are sampled
+    #[must_use]
+    pub fn set_border_mode(mut self, border: BorderMode) -> Affine {
+        self.border = border;
+        self
+    }
+    /// Set the dimensions of the output image, by default it's the same as the input
+    #[must_use]
+    pub fn set_output_size(mut self, width: usize, height: usize) -> Affine {
+        self.output_size = Some((width, height));
+        self
+    }
+}
+
+impl OperationsTrait for Affine {
+    fn name(&self) -> &'static str {
+        "Affine"
+    }
+
+    fn execute_impl(&self, image: &mut Image) -> Result<(), ImageErrors> {
+        let [first, second] = self.matrix;
+
+        let matrix = [first, second, [0.0, 0.0, 1.0]];
+
+        warp_image(image, &matrix, self.border, self.output_size)
+    }
+
+    fn supported_types(&self) -> &'static [BitType] {
+        &[BitType::U8, BitType::U16, BitType::F32]
+    }
+}
+
+/// Transform an image with a 3x3 perspective matrix (homography)
+///
+/// # Example
+/// Straighten a photographed page whose corners are known
+/// ```
+/// use zune_core::colorspace::ColorSpace;
+/// use zune_image::image::Image;
+/// use zune_image::traits::OperationsTrait;
+/// use zune_imageprocs::warp::Perspective;
+///
+/// let mut image = Image::fill(255_u8, ColorSpace::RGB, 640, 480);
+///
+/// let corners = [(102.0, 40.0), (520.0, 61.0), (571.0, 440.0), (80.0, 410.0)];
+/// let page = [(0.0, 0.0), (420.0, 0.0), (420.0, 594.0), (0.0, 594.0)];
+///
+/// Perspective::from_points(corners, page)
+///     .unwrap()
+///     .set_output_size(420, 594)
+///     .execute(&mut image)
+///     .unwrap();
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub struct Perspective {
+    matrix:      [[f32; 3]; 3],
+    border:      BorderMode,
+    output_size: Option<(usize, usize)>
+}
+
+impl Perspective {
+    /// Create a new perspective transform
+    ///
+    /// The output has the same dimensions as the input and
+    /// pixels outside of the source are black
+    ///
+    /// # Arguments
+    /// - matrix: The matrix mapping source to destination coordinates
+    #[must_use]
+    pub fn new(matrix: [[f32; 3]; 3]) -> Perspective {
+        Perspective {
+            matrix,
+            border: BorderMode::Constant,
+            output_size: None
+        }
+    }
+
+    /// Create a perspective transform mapping four source points to four destination points
+    ///
+    /// Points are `(x, y)` pixel coordinates
+    ///
+    /// # Errors
+    /// If three of the points are on the same line, in which case there is no such transform
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn from_points(
+        src: [(f32, f32); 4], dst: [(f32, f32); 4]
+    ) -> Result<Perspective, ImageErrors> {
+        // each pair of points gives two equations in the 8 unknowns of the
+        // matrix, m22 is fixed to one
+        let mut system = [[0.0_f64; 9]; 8];
+
+        for (i, ((x, y), (u, v))) in src.iter().zip(&dst).enumerate() {
+            let (x, y, u, v) = (f64::from(*x), f64::from(*y), f64::from(*u), f64::from(*v));
+
+            system[2 * i] = [x, y, 1.0, 0.0, 0.0, 0.0, -u * x, -u * y, u];
+            system[2 * i + 1] = [0.0, 0.0, 0.0, x, y, 1.0, -v * x, -v * y, v];
+        }
+        let m = solve(system).ok_or(ImageErrors::GenericStr(
+            "Points are degenerate, no perspective transform maps them"
+        ))?;
+
+        Ok(Perspective::new([
+            [m[0] as f32, m[1] as f32, m[2] as f32],
+            [m[3] as f32, m[4] as f32, m[5] as f32],
+            [m[6] as f32, m[7] as f32, 1.0]
+        ]))
+    }
+    /// Set how pixels outside the source image are sampled
+    #[must_use]
+    pub fn set_border_mode(mut self, border: BorderMode) -> Perspective {
+        self.border = border;
+        self
+    }
+    /// Set the dimensions of the output image, by default it's the same as the input
+    #[must_use]
+    pub fn set_output_size(mut self, width: usize, height: usize) -> Perspective {
+        self.output_size = Some((width, height));
+        self
+    }
+}
+
+impl OperationsTrait for Perspective {
+    fn name(&self) -> &'static str {
+        "Perspective"
+    }
+
+    fn execute_impl(&self, image: &mut Image) -> Result<(), ImageErrors> {
+        warp_image(image, &self.matrix, self.border, self.output_size)
+    }
+
+    fn supported_types(&self) -> &'static [BitType] {
+        &[BitType::U8, BitType::U16, BitType::F32]
+    }
+}
+
+/// Solve an 8x8 linear system given as an augmented matrix with gaussian elimination
+fn solve(mut system: [[f64; 9]; 8]) -> Option<[f64; 8]> {
+    for column in 0..8 {
+        // partial pivoting, use the row with the largest value in this column
+        let pivot = (column..8).max_by(|a, b| {
+            system[*a][column]
+                .abs()
+                .total_cmp(&system[*b][column].abs())
+        })?;
+
+        if system[pivot][column].abs() < 1e-10 {
+            return None;
+        }
+        system.swap(column, pivot);
+
+        for row in 0..8 {
+            if row != column {
+                let factor = system[row][column] / system[column][column];
+                let pivot_row = system[column];
+
+                for (value, pivot_value) in system[row].iter_mut().zip(pivot_row) {
+                    *value -= factor * pivot_value;
+                }
+            }
+        }
+    }
+    let mut solution = [0.0; 8];
+
+    for (i, value) in solution.iter_mut().enumerate() {
+        *value = system[i][8] / system[i][i];
+    }
+    Some(solution)
+}
+
+/// Invert a 3x3 matrix, returning `None` if it's singular
+fn invert(m: &[[f32; 3]; 3]) -> Option<[[f64; 3]; 3]> {
+    let m = m.map(|row| row.map(f64::from));
+
+    let cofactor =
+        |r0: usize, r1: usize, c0: usize, c1: usize| m[r0][c0] * m[r1][c1] - m[r0][c1] * m[r1][c0];
+    let adjugate = [
+        [
+            cofactor(1, 2, 1, 2),
+            -cofactor(0, 2, 1, 2),
+            cofactor(0, 1, 1, 2)
+        ],
+        [
+            -cofactor(1, 2, 0, 2),
+            cofactor(0, 2, 0, 2),
+            -cofactor(0, 1, 0, 2)
+        ],
+        [
+            cofactor(1, 2, 0, 1),
+            -cofactor(0, 2, 0, 1),
+            cofactor(0, 1, 0, 1)
+        ]
+    ];
+    let determinant =
+        m[0][0] * adjugate[0][0] + m[0][1] * adjugate[1][0] + m[0][2] * adjugate[2][0];
+
+    if determinant.abs() < 1e-12 || !determinant.is_finite() {
+        return None;
+    }
+    Some(adjugate.map(|row| row.map(|x| x / determinant)))
+}
+
+fn warp_image(
+    image: &mut Image, matrix: &[[f32; 3]; 3], border: BorderMode,
+    output_size: Option<(usize, usize)>
+) -> Result<(), ImageErrors> {
+    let inverse = invert(matrix).ok_or(ImageErrors::GenericStr(
+        "Transform matrix is not invertible"
+    ))?;
+    let (in_width, in_height) = image.dimensions();
+    let (out_width, out_height) = output_size.unwrap_or((in_width, in_height));
+
+    if out_width == 0 || out_height == 0 {
+        return Err(ImageErrors::GenericStr("Output dimensions cannot be zero"));
+    }
+    let depth = image.depth().bit_type();
+    let new_length = out_width * out_height * image.depth().size_of();
+
+    let in_dims = (in_width, in_height);
+    let out_dims = (out_width, out_height);
+
+    for channel in image.channels_mut(false) {
+        let mut new_channel = Channel::new_with_bit_type(new_length, depth);
+
+        match depth {
+            BitType::U8 => warp::<u8>(
+                channel.reinterpret_as()?,
+                new_channel.reinterpret_as_mut()?,
+                in_dims,
+                out_dims,
+                &inverse,
+                border
+            ),
+            BitType::U16 => warp::<u16>(
+                channel.reinterpret_as()?,
+                new_channel.reinterpret_as_mut()?,
+                in_dims,
+                out_dims,
+                &inverse,
+                border
+            ),
+            BitType::F32 => warp::<f32>(
+                channel.reinterpret_as()?,
+                new_channel.reinterpret_as_mut()?,
+                in_dims,
+                out_dims,
+                &inverse,
+                border
+            ),
+            d => return Err(ImageErrors::ImageOperationNotImplemented("warp", d))
+        }
+        *channel = new_channel;
+    }
+    image.set_dimensions(out_width, out_height);
+
+    Ok(())
+}
+
+/// Map a coordinate that may be outside `0..length` into it,
+/// `None` means the sample is the constant border
+#[allow(clippy::cast_possible_wrap, clippy::cast_sign_loss)]
+fn border_coordinate(position: isize, length: usize, border: BorderMode) -> Option<usize> {
+    let length = length as isize;
+
+    if (0..length).contains(&position) {
+        return Some(position as usize);
+    }
+    match border {
+        BorderMode::Constant => None,
+        BorderMode::Replicate => Some(position.clamp(0, length - 1) as usize),
+        BorderMode::Reflect => {
+            // the pattern repeats every 2*length pixels, forwards then backwards
+            let period = 2 * length;
+            let position = position.rem_euclid(period);
+
+            Some(if position < length { position } else { period - 1 - position } as usize)
+        }
+    }
+}
+
+/// Warp a single channel
+///
+/// # Arguments
+/// - in_channel: Input channel of `in_dims.0 * in_dims.1` pixels
+/// - out_channel: Output channel of `out_dims.0 * out_dims.1` pixels
+/// - in_dims: Input width and height
+/// - out_dims: Output width and height
+/// - inverse: Matrix mapping destination coordinates to source coordinates
+/// - border: How to sample pixels outside the input
+#[allow(
+    clippy::cast_precision_loss,
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss
+)]
+pub fn warp<T>(
+    in_channel: &[T], out_channel: &mut [T], in_dims: (usize, usize), out_dims: (usize, usize),
+    inverse: &[[f64; 3]; 3], border: BorderMode
+) where
+    T: Copy + NumOps<T>,
+    f32: From<T>
+{
+    let (in_width, in_height) = in_dims;
+    let (out_width, _) = out_dims;
+
+    if in_width == 0 || in_height == 0 || out_width == 0 {
+        return;
+    }
+    let sample = |x: isize, y: isize| -> f32 {
+        match (
+            border_coordinate(x, in_width, border),
+            border_coordinate(y, in_height, border)
+        ) {
+            (Some(x), Some(y)) => f32::from(in_channel[y * in_width + x]),
+            _ => 0.0
+        }
+    };
+
+    for (y, out_row) in out_channel.chunks_exact_mut(out_width).enumerate() {
+        for (x, out_pixel) in out_row.iter_mut().enumerate() {
+            let (xf, yf) = (x as f64, y as f64);
+
+            let w = inverse[2][0] * xf + inverse[2][1] * yf + inverse[2][2];
+            let src_x = (inverse[0][0] * xf + inverse[0][1] * yf + inverse[0][2]) / w;
+            let src_y = (inverse[1][0] * xf + inverse[1][1] * yf + inverse[1][2]) / w;
+
+            // points at infinity or far outside the image, keep them out of integer
+            // range issues by treating them as one pixel outside the image
+            let limit = (in_width.max(in_height) * 4) as f64;
+            let (src_x, src_y) = if src_x.is_finite() && src_y.is_finite() {
+                (src_x.clamp(-limit, limit), src_y.clamp(-limit, limit))
+            } else {
+                (-limit, -limit)
+            };
+
+            let (x0, y0) = (src_x.floor(), src_y.floor());
+            let (a, b) = ((src_x - x0) as f32, (src_y - y0) as f32);
+            let (x0, y0) = (x0 as isize, y0 as isize);
+
+            let interpolated = sample(x0, y0) * (1.0 - a) * (1.0 - b)
+                + sample(x0 + 1, y0) * a * (1.0 - b)
+                + sample(x0, y0 + 1) * (1.0 - a) * b
+                + sample(x0 + 1, y0 + 1) * a * b;
+
+            *out_pixel = T::from_f32(interpolated);
+        }
+    }
+}
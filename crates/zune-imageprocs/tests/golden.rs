@@ -0,0 +1,98 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! Compare operation outputs against reference images in `tests/golden`
+//!
+//! After an intentional change in an operation's output, regenerate the
+//! references with `ZUNE_BLESS_GOLDEN=1 cargo test --test golden` and review
+//! the diff.
+
+use std::path::PathBuf;
+
+use zune_core::bit_depth::BitDepth;
+use zune_core::colorspace::ColorSpace;
+use zune_image::core_filters::colorspace::ColorspaceConv;
+use zune_image::testing::{compare_with_golden, synthetic_image};
+use zune_image::traits::OperationsTrait;
+use zune_imageprocs::box_blur::BoxBlur;
+use zune_imageprocs::gaussian_blur::GaussianBlur;
+use zune_imageprocs::hsv_adjust::HsvAdjust;
+use zune_imageprocs::resize::{Resize, ResizeMethod};
+use zune_imageprocs::rotate::Rotate;
+use zune_imageprocs::sobel::Sobel;
+use zune_imageprocs::transpose::Transpose;
+
+fn check_golden(name: &str, operation: &dyn OperationsTrait, colorspace: ColorSpace) {
+    let mut image = synthetic_image(colorspace, BitDepth::Eight, 17, 9);
+    operation.execute(&mut image).unwrap();
+
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(format!("{name}.bin"));
+
+    if let Err(err) = compare_with_golden(&image, path) {
+        panic!("{err}");
+    }
+}
+
+#[test]
+fn test_golden_box_blur() {
+    check_golden("box_blur", &BoxBlur::new(2), ColorSpace::RGB);
+}
+
+#[test]
+fn test_golden_gaussian_blur() {
+    check_golden("gaussian_blur", &GaussianBlur::new(1.5), ColorSpace::RGB);
+}
+
+#[test]
+fn test_golden_grayscale() {
+    check_golden(
+        "grayscale",
+        &ColorspaceConv::new(ColorSpace::Luma),
+        ColorSpace::RGB
+    );
+    check_golden(
+        "grayscale_alpha",
+        &ColorspaceConv::new(ColorSpace::LumaA),
+        ColorSpace::RGBA
+    );
+}
+
+#[test]
+fn test_golden_hsv_adjust() {
+    check_golden(
+        "hsv_adjust",
+        &HsvAdjust::new(30.0, 1.2, 0.9),
+        ColorSpace::RGB
+    );
+}
+
+#[test]
+fn test_golden_resize() {
+    check_golden(
+        "resize_bilinear",
+        &Resize::new(7, 5, ResizeMethod::Bilinear),
+        ColorSpace::RGB
+    );
+}
+
+#[test]
+fn test_golden_rotate() {
+    check_golden("rotate_90", &Rotate::new(90.0), ColorSpace::RGB);
+}
+
+#[test]
+fn test_golden_sobel() {
+    check_golden("sobel", &Sobel::new(), ColorSpace::Luma);
+}
+
+#[test]
+fn test_golden_transpose() {
+    check_golden("transpose", &Transpose::new(), ColorSpace::RGBA);
+}
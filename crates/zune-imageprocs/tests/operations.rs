@@ -0,0 +1,947 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! Run every operation over synthetic images of all colorspaces, depths and
+//! a few awkward dimensions, see `zune_image::testing`
+
+use zune_core::bit_depth::BitDepth;
+use zune_core::colorspace::ColorSpace;
+use zune_image::cancel::CancellationToken;
+use zune_image::core_filters::colorspace::grayscale::{LumaWeights, RgbToGrayScale};
+use zune_image::core_filters::colorspace::ColorspaceConv;
+use zune_image::core_filters::depth::Depth;
+use zune_image::core_filters::metadata::StripMetadata;
+use zune_image::errors::ImageErrors;
+use zune_image::image::Image;
+use zune_image::metadata::{AlphaState, MetadataCategory};
+use zune_image::testing::{synthetic_image, OperationChecker, OperationFailure, TEST_COLORSPACES};
+use zune_image::traits::OperationsTrait;
+use zune_imageprocs::auto_orient::AutoOrient;
+use zune_imageprocs::bilateral_filter::BilateralFilter;
+use zune_imageprocs::box_blur::BoxBlur;
+use zune_imageprocs::brighten::Brighten;
+use zune_imageprocs::channel_shift::ChannelShift;
+use zune_imageprocs::chroma_key::ChromaKey;
+use zune_imageprocs::color_matrix::ColorMatrix;
+use zune_imageprocs::composite::{Composite, Gravity};
+use zune_imageprocs::contrast::Contrast;
+use zune_imageprocs::convolve::Convolve;
+use zune_imageprocs::crop::Crop;
+use zune_imageprocs::curves::{Curves, MonotoneSpline};
+use zune_imageprocs::distance_transform::{DistanceMetric, DistanceTransform};
+use zune_imageprocs::exposure::Exposure;
+use zune_imageprocs::extent::Extent;
+use zune_imageprocs::flatten_alpha::FlattenAlpha;
+use zune_imageprocs::flip::{Flip, VerticalFlip};
+use zune_imageprocs::flood_fill::FloodFill;
+use zune_imageprocs::flop::Flop;
+use zune_imageprocs::gamma::Gamma;
+use zune_imageprocs::gaussian_blur::GaussianBlur;
+use zune_imageprocs::halftone::Halftone;
+use zune_imageprocs::histogram::ChannelHistogram;
+use zune_imageprocs::hsv_adjust::HsvAdjust;
+use zune_imageprocs::invert::Invert;
+use zune_imageprocs::lut3d::Lut3D;
+use zune_imageprocs::median::Median;
+use zune_imageprocs::mirror::{Mirror, MirrorMode};
+use zune_imageprocs::non_local_means::NonLocalMeans;
+use zune_imageprocs::on_channels::OnChannels;
+use zune_imageprocs::pixelate::Pixelate;
+use zune_imageprocs::resize::{Resize, ResizeMethod, ResizeOptions};
+use zune_imageprocs::rotate::Rotate;
+use zune_imageprocs::scale_with_fixed_borders::ScaleWithFixedBorders;
+use zune_imageprocs::scharr::Scharr;
+use zune_imageprocs::smart_sharpen::SmartSharpen;
+use zune_imageprocs::sobel::Sobel;
+use zune_imageprocs::spatial::SpatialOps;
+use zune_imageprocs::spatial_ops::SpatialOperations;
+use zune_imageprocs::stretch_contrast::StretchContrast;
+use zune_imageprocs::swap_channels::SwapChannels;
+use zune_imageprocs::threshold::{Threshold, ThresholdMethod};
+use zune_imageprocs::tonemap::{ToneMap, ToneMapOperator};
+use zune_imageprocs::transpose::Transpose;
+use zune_imageprocs::unsharpen::Unsharpen;
+use zune_imageprocs::vignette::LensVignetteCorrect;
+use zune_imageprocs::warp::{Affine, BorderMode, Perspective};
+
+fn assert_no_failures(failures: &[OperationFailure]) {
+    let report: Vec<String> = failures.iter().map(ToString::to_string).collect();
+    assert!(failures.is_empty(), "\n{}", report.join("\n"));
+}
+
+fn operations() -> Vec<Box<dyn OperationsTrait>> {
+    vec![
+        Box::new(AutoOrient),
+        Box::new(BilateralFilter::new(5, 10.0, 10.0)),
+        Box::new(BoxBlur::new(3)),
+        Box::new(Brighten::new(0.1)),
+        Box::new(ColorMatrix::new([
+            [0.5, 0.5, 0.0, 0.0, 0.0],
+            [0.0, 0.5, 0.5, 0.0, 0.0],
+            [0.5, 0.0, 0.5, 0.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0, 0.0]
+        ])),
+        Box::new(Contrast::new(10.0)),
+        Box::new(DistanceTransform::new(DistanceMetric::Euclidean)),
+        Box::new(DistanceTransform::new(DistanceMetric::Manhattan)),
+        Box::new(Convolve::new(
+            vec![0.0, -1.0, 0.0, -1.0, 5.0, -1.0, 0.0, -1.0, 0.0],
+            1.0
+        )),
+        Box::new(Exposure::new(1.5, 0.0)),
+        Box::new(Flip::new()),
+        Box::new(VerticalFlip::new()),
+        Box::new(Flop::new()),
+        Box::new(Gamma::new(2.2)),
+        Box::new(GaussianBlur::new(1.5)),
+        Box::new(ChannelHistogram::new()),
+        Box::new(HsvAdjust::new(30.0, 1.2, 1.0)),
+        Box::new(Invert::new()),
+        Box::new(OnChannels::new(Box::new(BoxBlur::new(3)), 0b1)),
+        Box::new(Median::new(1)),
+        Box::new(Mirror::new(MirrorMode::East)),
+        Box::new(Resize::new(7, 5, ResizeMethod::Bilinear)),
+        Box::new(
+            Resize::new(7, 5, ResizeMethod::Bilinear).set_options(ResizeOptions {
+                premultiply: true,
+                linearize:   true
+            })
+        ),
+        Box::new(Rotate::new(90.0)),
+        Box::new(Scharr::new()),
+        Box::new(Sobel::new()),
+        Box::new(SpatialOps::new(2, SpatialOperations::Mean)),
+        Box::new(StretchContrast::new(0.1, 0.9)),
+        Box::new(Transpose::new()),
+        Box::new(Unsharpen::new(1.5, 10, 50)),
+        Box::new(LensVignetteCorrect::new(0.4)),
+        Box::new(LensVignetteCorrect::new(-2.0)),
+        Box::new(Pixelate::new(3)),
+        Box::new(Halftone::new(4)),
+        Box::new(Halftone::new(16).set_levels(5)),
+        Box::new(ChannelShift::new(1.5, -0.5, -2.0, 0.0)),
+        Box::new(warm_lut()),
+        Box::new(Curves::new(vec![]).set_master(&[(0.0, 0.1), (0.3, 0.2), (1.0, 0.9)])),
+        Box::new(Curves::new(vec![vec![(0.2, 0.0), (0.8, 1.0)]])),
+        Box::new(FlattenAlpha::new([0.2, 0.4, 0.6])),
+        Box::new(ChromaKey::new([0.0, 1.0, 0.0], 0.2, 0.1).set_spill_suppression(true)),
+        Box::new(
+            Composite::new(Image::fill(200_u8, ColorSpace::LumaA, 3, 2))
+                .set_gravity(Gravity::SouthEast)
+                .set_opacity(0.5)
+                .set_tiled(true)
+                .set_spacing(2, 1)
+        ),
+        Box::new(SmartSharpen::new(1.0, 1.5, 0.1)),
+        Box::new(NonLocalMeans::new(0.05).set_search_radius(2)),
+        Box::new(ToneMap::new(ToneMapOperator::Reinhard).set_white_point(4.0)),
+        Box::new(ToneMap::new(ToneMapOperator::Aces).set_output_depth(BitDepth::Sixteen)),
+        Box::new(ToneMap::new(ToneMapOperator::Hable).set_output_depth(BitDepth::Float32)),
+        Box::new(Affine::new([[0.9, -0.3, 2.0], [0.3, 0.9, -1.5]]).set_output_size(5, 8)),
+        Box::new(
+            Perspective::new([[1.0, 0.1, 0.0], [0.0, 1.2, 0.0], [0.01, 0.02, 1.0]])
+                .set_border_mode(BorderMode::Reflect)
+        ),
+    ]
+}
+
+/// A 3x3x3 LUT that brightens red and darkens blue
+fn warm_lut() -> Lut3D {
+    let steps = [0.0, 0.5, 1.0];
+
+    let table = steps
+        .iter()
+        .flat_map(|b| steps.iter().map(move |g| (*g, *b)))
+        .flat_map(|(g, b)| {
+            steps
+                .iter()
+                .map(move |r| [(r * 1.2_f32).min(1.0), g, b * 0.8])
+        })
+        .collect();
+
+    Lut3D::new(3, table).unwrap()
+}
+
+#[test]
+fn test_operations_matrix() {
+    let failures: Vec<OperationFailure> = operations()
+        .iter()
+        .flat_map(|op| OperationChecker::new().run(op.as_ref()))
+        .collect();
+
+    assert_no_failures(&failures);
+}
+
+#[test]
+fn test_swap_channels_matrix() {
+    // swapping a channel the image doesn't have is an error
+    let colorspaces: Vec<ColorSpace> = TEST_COLORSPACES
+        .into_iter()
+        .filter(|x| x.num_components() > 2)
+        .collect();
+    let checker = OperationChecker::new().set_colorspaces(&colorspaces);
+
+    assert_no_failures(&checker.run(&SwapChannels::new(0, 2)));
+}
+
+#[test]
+fn test_extent_matrix() {
+    // the background color can't be converted to YCCK
+    let colorspaces: Vec<ColorSpace> = TEST_COLORSPACES
+        .into_iter()
+        .filter(|x| *x != ColorSpace::YCCK)
+        .collect();
+    let checker = OperationChecker::new().set_colorspaces(&colorspaces);
+
+    assert_no_failures(&checker.run(&Extent::new(9, 4, Gravity::NorthEast, [10, 20, 30, 128])));
+}
+
+#[test]
+fn test_crop_matrix() {
+    // cropping 2x1 out of a 1x1 image is an error, not a failure
+    let checker = OperationChecker::new()
+        .set_dimensions(&[(3, 2), (17, 9), (31, 64)])
+        .set_check_idempotence(true);
+
+    assert_no_failures(&checker.run(&Crop::new(2, 1, 0, 0)));
+}
+
+#[test]
+fn test_scale_with_fixed_borders_matrix() {
+    // the border must leave an interior, so skip images too small for it
+    let checker = OperationChecker::new().set_dimensions(&[(3, 3), (17, 9), (31, 64)]);
+
+    for (width, height) in [(2, 2), (3, 40), (50, 5), (64, 64)] {
+        assert_no_failures(&checker.run(&ScaleWithFixedBorders::new(1, width, height)));
+    }
+}
+
+#[test]
+fn test_scale_with_fixed_borders_keeps_borders() {
+    let image = synthetic_image(ColorSpace::Luma, BitDepth::Eight, 9, 7);
+    let mut scaled = image.clone();
+
+    ScaleWithFixedBorders::new(2, 30, 20)
+        .execute(&mut scaled)
+        .unwrap();
+
+    let pixel = |image: &Image, x: usize, y: usize| {
+        let (width, _) = image.dimensions();
+        image.channels_ref(false)[0].reinterpret_as::<u8>().unwrap()[y * width + x]
+    };
+    // corners are unchanged
+    for (x, y) in [
+        (0, 0),
+        (1, 1),
+        (8, 0),
+        (7, 1),
+        (0, 6),
+        (1, 5),
+        (8, 6),
+        (7, 5)
+    ] {
+        let (out_x, out_y) = (
+            if x < 2 { x } else { x + 21 },
+            if y < 2 { y } else { y + 13 }
+        );
+        assert_eq!(
+            pixel(&image, x, y),
+            pixel(&scaled, out_x, out_y),
+            "({x},{y})"
+        );
+    }
+    // the left edge is only stretched vertically, so its first row is untouched
+    assert_eq!(pixel(&image, 0, 2), pixel(&scaled, 0, 2));
+
+    assert!(ScaleWithFixedBorders::new(5, 30, 20)
+        .execute(&mut image.clone())
+        .is_err());
+    assert!(ScaleWithFixedBorders::new(2, 3, 20)
+        .execute(&mut image.clone())
+        .is_err());
+}
+
+#[test]
+fn test_idempotent_operations_matrix() {
+    let checker = OperationChecker::new().set_check_idempotence(true);
+
+    for colorspace in [
+        ColorSpace::Luma,
+        ColorSpace::RGB,
+        ColorSpace::RGBA,
+        ColorSpace::HSV
+    ] {
+        assert_no_failures(&checker.run(&ColorspaceConv::new(colorspace)));
+    }
+    for depth in [BitDepth::Eight, BitDepth::Sixteen, BitDepth::Float32] {
+        assert_no_failures(&checker.run(&Depth::new(depth)));
+    }
+    for weights in [LumaWeights::Bt601, LumaWeights::Bt709] {
+        let grayscale = RgbToGrayScale::new(weights);
+
+        assert_no_failures(&checker.run(&grayscale));
+        assert_no_failures(&checker.run(&grayscale.set_preserve_alpha(false)));
+    }
+    assert_no_failures(&checker.run(&Threshold::new(0.5, ThresholdMethod::Binary)));
+    assert_no_failures(&checker.run(&StripMetadata::new(&[MetadataCategory::Exif])));
+    assert_no_failures(&checker.run(&Pixelate::new(4)));
+    assert_no_failures(&checker.run(&Halftone::new(8).set_levels(3)));
+}
+
+#[test]
+fn test_flood_fill_matrix() {
+    // the fill color needs a value for every channel, so check colorspaces one by one
+    for colorspace in TEST_COLORSPACES {
+        let color = vec![0.5; colorspace.num_components()];
+        let checker = OperationChecker::new()
+            .set_colorspaces(&[colorspace])
+            .set_check_idempotence(true);
+
+        assert_no_failures(&checker.run(&FloodFill::new(0, 0, &color, 20.0)));
+    }
+}
+
+fn first_channel_u8(image: &Image) -> Vec<u8> {
+    image.channels_ref(false)[0]
+        .reinterpret_as::<u8>()
+        .unwrap()
+        .to_vec()
+}
+
+#[test]
+fn test_flood_fill_stops_at_edges() {
+    // a ring of 1s splits the image into an inside and an outside
+    #[rustfmt::skip]
+    let pixels = [
+        0, 0, 0, 0, 0,
+        0, 1, 1, 1, 0,
+        0, 1, 0, 1, 0,
+        0, 1, 1, 1, 0,
+        0, 0, 0, 0, 0_u8,
+    ];
+    let mut image = Image::from_u8(&pixels, 5, 5, ColorSpace::Luma);
+
+    FloodFill::new(4, 0, &[9.0], 0.0)
+        .execute(&mut image)
+        .unwrap();
+
+    #[rustfmt::skip]
+    let expected = [
+        9, 9, 9, 9, 9,
+        9, 1, 1, 1, 9,
+        9, 1, 0, 1, 9,
+        9, 1, 1, 1, 9,
+        9, 9, 9, 9, 9,
+    ];
+    assert_eq!(first_channel_u8(&image), expected);
+
+    // with a tolerance the ring is similar to the seed, so the fill goes through
+    FloodFill::new(0, 0, &[5.0], 9.0)
+        .execute(&mut image)
+        .unwrap();
+    assert!(first_channel_u8(&image).iter().all(|x| *x == 5));
+
+    assert!(FloodFill::new(5, 0, &[1.0], 0.0)
+        .execute(&mut image)
+        .is_err());
+    assert!(FloodFill::new(0, 0, &[1.0, 2.0], 0.0)
+        .execute(&mut image)
+        .is_err());
+}
+
+#[test]
+fn test_distance_transform() {
+    let mut pixels = [1_u8; 7 * 5];
+    pixels[2 * 7 + 1] = 0;
+
+    for (metric, expected) in [
+        (DistanceMetric::Manhattan, [3, 2, 3, 4, 5, 6, 7]),
+        // sqrt(1+4)=2.23, sqrt(16+4)=4.47, sqrt(25+4)=5.38
+        (DistanceMetric::Euclidean, [2, 2, 2, 3, 4, 4, 5])
+    ] {
+        let mut image = Image::from_u8(&pixels, 7, 5, ColorSpace::Luma);
+        DistanceTransform::new(metric).execute(&mut image).unwrap();
+
+        assert_eq!(first_channel_u8(&image)[..7], expected, "{metric:?}");
+    }
+    // without a background everything is infinitely far, saturating to the maximum
+    let mut image = Image::fill(1_u16, ColorSpace::Luma, 4, 4);
+    DistanceTransform::new(DistanceMetric::Euclidean)
+        .execute(&mut image)
+        .unwrap();
+
+    assert!(image == Image::fill(u16::MAX, ColorSpace::Luma, 4, 4));
+}
+
+#[test]
+fn test_warp_border_modes() {
+    let pixels = [10_u8, 20, 30];
+    // shift everything two pixels to the right
+    let shift = Affine::new([[1.0, 0.0, 2.0], [0.0, 1.0, 0.0]]).set_output_size(5, 1);
+
+    for (border, expected) in [
+        (BorderMode::Constant, [0, 0, 10, 20, 30]),
+        (BorderMode::Replicate, [10, 10, 10, 20, 30]),
+        (BorderMode::Reflect, [20, 10, 10, 20, 30])
+    ] {
+        let mut image = Image::from_u8(&pixels, 3, 1, ColorSpace::Luma);
+        shift.set_border_mode(border).execute(&mut image).unwrap();
+
+        assert_eq!(first_channel_u8(&image), expected, "{border:?}");
+    }
+}
+
+#[test]
+fn test_perspective_from_points() {
+    let image = synthetic_image(ColorSpace::Luma, BitDepth::Eight, 8, 8);
+
+    // mapping the corners to themselves is the identity
+    let corners = [(0.0, 0.0), (7.0, 0.0), (7.0, 7.0), (0.0, 7.0)];
+    let mut warped = image.clone();
+
+    Perspective::from_points(corners, corners)
+        .unwrap()
+        .execute(&mut warped)
+        .unwrap();
+    assert!(warped == image);
+
+    // three points on a line
+    let line = [(0.0, 0.0), (1.0, 1.0), (2.0, 2.0), (0.0, 7.0)];
+    assert!(Perspective::from_points(line, corners).is_err());
+
+    let singular = Perspective::new([[1.0, 2.0, 0.0], [2.0, 4.0, 0.0], [0.0, 0.0, 1.0]]);
+    assert!(singular.execute(&mut image.clone()).is_err());
+}
+
+#[test]
+fn test_lens_corrections() {
+    let mut image = Image::fill(100_u8, ColorSpace::RGBA, 5, 5);
+    LensVignetteCorrect::new(0.5).execute(&mut image).unwrap();
+
+    let channels = image.channels_ref(false);
+    let red = channels[0].reinterpret_as::<u8>().unwrap();
+    // center unchanged, corners get the full gain, alpha is untouched
+    assert_eq!(red[2 * 5 + 2], 100);
+    assert_eq!(red[0], 150);
+    assert_eq!(red[24], 150);
+    assert!(channels[3]
+        .reinterpret_as::<u8>()
+        .unwrap()
+        .iter()
+        .all(|x| *x == 100));
+
+    let pixels: Vec<u8> = (0..4).flat_map(|x| [x * 10, x * 10, x * 10]).collect();
+    let mut image = Image::from_u8(&pixels, 4, 1, ColorSpace::RGB);
+    ChannelShift::new(1.0, 0.0, -1.0, 0.0)
+        .execute(&mut image)
+        .unwrap();
+
+    let channel = |i: usize| {
+        image.channels_ref(false)[i]
+            .reinterpret_as::<u8>()
+            .unwrap()
+            .to_vec()
+    };
+
+    assert_eq!(channel(0), [0, 0, 10, 20]);
+    assert_eq!(channel(1), [0, 10, 20, 30]);
+    assert_eq!(channel(2), [10, 20, 30, 30]);
+}
+
+#[test]
+fn test_tonemap() {
+    // an HDR gradient, both beyond white and in shadows
+    let pixels = [0.0_f32, 0.01, 0.5, 1.0, 4.0, 100.0];
+
+    for operator in [
+        ToneMapOperator::Reinhard,
+        ToneMapOperator::Aces,
+        ToneMapOperator::Hable
+    ] {
+        let mut image = Image::from_f32(&pixels, 6, 1, ColorSpace::Luma);
+        ToneMap::new(operator)
+            .set_white_point(100.0)
+            .execute(&mut image)
+            .unwrap();
+
+        let mapped = first_channel_u8(&image);
+
+        assert_eq!(mapped[0], 0, "{operator:?}");
+        assert_eq!(mapped[5], 255, "{operator:?}");
+        assert!(
+            mapped.windows(2).all(|x| x[0] < x[1]),
+            "{operator:?} {mapped:?}"
+        );
+    }
+    // one stop more exposure is brighter
+    let tonemap = |exposure| {
+        let mut image = Image::from_f32(&[0.2], 1, 1, ColorSpace::Luma);
+        ToneMap::new(ToneMapOperator::Reinhard)
+            .set_exposure(exposure)
+            .execute(&mut image)
+            .unwrap();
+        first_channel_u8(&image)[0]
+    };
+    assert!(tonemap(1.0) > tonemap(0.0));
+}
+
+#[test]
+fn test_lut3d() {
+    let identity = "TITLE \"identity\"
+# comments and blank lines are skipped
+
+LUT_3D_SIZE 2
+DOMAIN_MIN 0.0 0.0 0.0
+DOMAIN_MAX 1.0 1.0 1.0
+0.0 0.0 0.0
+1.0 0.0 0.0
+0.0 1.0 0.0
+1.0 1.0 0.0
+0.0 0.0 1.0
+1.0 0.0 1.0
+0.0 1.0 1.0
+1.0 1.0 1.0
+";
+    let lut = Lut3D::from_cube(identity).unwrap();
+
+    for depth in [BitDepth::Eight, BitDepth::Sixteen] {
+        let image = synthetic_image(ColorSpace::BGRA, depth, 17, 9);
+        let mut graded = image.clone();
+        lut.execute(&mut graded).unwrap();
+
+        assert!(graded == image, "{depth:?}");
+    }
+    // interpolated between samples
+    assert_eq!(warm_lut().lookup([0.25, 0.5, 0.75]), [0.3, 0.5, 0.6]);
+
+    for invalid in [
+        "LUT_3D_SIZE 2\n0 0 0\n",
+        "LUT_1D_SIZE 2\n0 0 0\n1 1 1",
+        "LUT_3D_SIZE 1\n0 0 0",
+        "0 0 0",
+        "LUT_3D_SIZE 2\n0 0 zero"
+    ] {
+        assert!(Lut3D::from_cube(invalid).is_err(), "{invalid}");
+    }
+}
+
+#[test]
+fn test_curves() {
+    let spline = MonotoneSpline::new(&[(1.0, 1.0), (0.0, 0.0), (0.5, 0.8)])
+        .unwrap()
+        .unwrap();
+    // passes through points and never overshoots
+    assert_eq!(spline.evaluate(0.5), 0.8);
+    let samples: Vec<f32> = (0..=100)
+        .map(|x| spline.evaluate(x as f32 / 100.0))
+        .collect();
+    assert!(samples.windows(2).all(|x| x[0] <= x[1] && x[1] <= 1.0));
+
+    // flat outside the points
+    let levels = MonotoneSpline::new(&[(0.2, 0.0), (0.8, 1.0)])
+        .unwrap()
+        .unwrap();
+    assert_eq!(levels.evaluate(0.1), 0.0);
+    assert_eq!(levels.evaluate(0.9), 1.0);
+
+    let pixels = [0_u8, 64, 128, 255, 128, 255];
+    let mut image = Image::from_u8(&pixels, 2, 1, ColorSpace::RGB);
+    let image_pixels = |image: &Image| image.flatten_to_u8()[0].clone();
+
+    // invert red, the master leaves everything else untouched
+    Curves::new(vec![vec![(0.0, 1.0), (1.0, 0.0)]])
+        .set_master(&[(0.0, 0.0), (1.0, 1.0)])
+        .execute(&mut image)
+        .unwrap();
+    assert_eq!(image_pixels(&image), [255, 64, 128, 0, 128, 255]);
+
+    assert!(Curves::new(vec![vec![]; 4]).execute(&mut image).is_err());
+    assert!(Curves::new(vec![vec![(0.5, 0.5)]])
+        .execute(&mut image)
+        .is_err());
+    assert!(Curves::new(vec![vec![(0.0, 0.0), (0.0, 1.0)]])
+        .execute(&mut image)
+        .is_err());
+    assert!(Curves::new(vec![vec![(0.0, 0.0), (2.0, 1.0)]])
+        .execute(&mut image)
+        .is_err());
+}
+
+#[test]
+fn test_flatten_alpha() {
+    // opaque, half transparent and fully transparent red
+    let pixels = [255_u8, 0, 0, 255, 255, 0, 0, 128, 255, 0, 0, 0];
+    let background = [0.0, 0.0, 1.0];
+
+    let mut image = Image::from_u8(&pixels, 3, 1, ColorSpace::RGBA);
+    FlattenAlpha::new(background).execute(&mut image).unwrap();
+
+    assert_eq!(image.colorspace(), ColorSpace::RGB);
+    assert_eq!(
+        image.flatten_to_u8()[0],
+        [255, 0, 0, 128, 0, 127, 0, 0, 255]
+    );
+
+    // premultiplied colors are already scaled by alpha
+    let mut image = Image::from_u8(&[128, 64, 0, 0], 1, 1, ColorSpace::ARGB);
+    image.metadata_mut().set_alpha(AlphaState::PreMultiplied);
+    FlattenAlpha::new(background).execute(&mut image).unwrap();
+
+    assert_eq!(image.colorspace(), ColorSpace::RGB);
+    assert_eq!(image.flatten_to_u8()[0], [64, 0, 127]);
+
+    // luma uses the luma of the background
+    let mut image = Image::from_u8(&[200, 0], 1, 1, ColorSpace::LumaA);
+    FlattenAlpha::new([1.0, 1.0, 1.0])
+        .execute(&mut image)
+        .unwrap();
+
+    assert_eq!(image.colorspace(), ColorSpace::Luma);
+    assert_eq!(image.flatten_to_u8()[0], [255]);
+}
+
+#[test]
+fn test_chroma_key() {
+    // backdrop green, a darker shade of it, a green tinted skin tone and blue
+    let pixels = [0_u8, 255, 0, 0, 200, 0, 200, 180, 120, 0, 0, 255];
+    let key = [0.0, 1.0, 0.0];
+
+    let mut image = Image::from_u8(&pixels, 4, 1, ColorSpace::RGB);
+    ChromaKey::new(key, 0.2, 0.0).execute(&mut image).unwrap();
+
+    assert_eq!(image.colorspace(), ColorSpace::RGBA);
+    let alpha: Vec<u8> = image.flatten_to_u8()[0].chunks(4).map(|x| x[3]).collect();
+    assert_eq!(alpha, [0, 0, 255, 255]);
+
+    // spill suppression pulls green down to the average of red and blue
+    let mut image = Image::from_u8(&pixels, 4, 1, ColorSpace::RGB);
+    ChromaKey::new(key, 0.2, 0.0)
+        .set_spill_suppression(true)
+        .execute(&mut image)
+        .unwrap();
+    assert_eq!(image.flatten_to_u8()[0][8..12], [200, 160, 120, 255]);
+
+    // softness gives partial alpha, existing alpha is multiplied in BGRA order
+    let mut image = Image::from_u8(&[0, 255, 60, 128], 1, 1, ColorSpace::BGRA);
+    ChromaKey::new(key, 0.0, 1.0).execute(&mut image).unwrap();
+    let alpha = image.flatten_to_u8()[0][3];
+    assert!(alpha > 0 && alpha < 128, "{alpha}");
+
+    assert!(ChromaKey::new(key, -0.1, 0.0).execute(&mut image).is_err());
+}
+
+#[test]
+fn test_composite() {
+    let white = Image::fill(255_u8, ColorSpace::RGB, 2, 2);
+
+    // a corner with margins
+    let mut image = Image::fill(0_u8, ColorSpace::RGB, 6, 4);
+    Composite::new(white.clone())
+        .set_gravity(Gravity::SouthEast)
+        .set_margin(1, 1)
+        .execute(&mut image)
+        .unwrap();
+
+    let red: Vec<u8> = image.flatten_to_u8()[0]
+        .iter()
+        .step_by(3)
+        .copied()
+        .collect();
+    #[rustfmt::skip]
+    assert_eq!(red, [
+        0, 0, 0, 0,   0,   0,
+        0, 0, 0, 255, 255, 0,
+        0, 0, 0, 255, 255, 0,
+        0, 0, 0, 0,   0,   0
+    ]);
+
+    // opacity scales the overlay alpha, luma overlays are expanded
+    let mut image = Image::fill(0_u8, ColorSpace::RGB, 2, 2);
+    Composite::new(Image::fill(255_u8, ColorSpace::Luma, 2, 2))
+        .set_opacity(0.5)
+        .execute(&mut image)
+        .unwrap();
+    assert_eq!(image.flatten_to_u8()[0], [128; 12]);
+
+    // tiles repeat both ways from the centered tile at x=3
+    let mut image = Image::fill(0_u8, ColorSpace::Luma, 7, 1);
+    Composite::new(Image::fill(255_u8, ColorSpace::Luma, 1, 1))
+        .set_tiled(true)
+        .set_spacing(1, 0)
+        .execute(&mut image)
+        .unwrap();
+    assert_eq!(image.flatten_to_u8()[0], [0, 255, 0, 255, 0, 255, 0]);
+
+    // transparent images take the overlay alpha
+    let mut image = Image::fill(0_u8, ColorSpace::RGBA, 2, 2);
+    Composite::new(white)
+        .set_opacity(0.5)
+        .execute(&mut image)
+        .unwrap();
+    assert_eq!(image.flatten_to_u8()[0][..4], [255, 255, 255, 128]);
+
+    assert!(Gravity::from_string_result("south-east").is_ok());
+    assert!(Gravity::from_string_result("up").is_err());
+}
+
+#[test]
+fn test_on_channels() {
+    // a sharp edge in every channel
+    let pixels: Vec<u8> = (0..8).flat_map(|x| [x * 30; 4]).collect();
+    let original = Image::from_u8(&pixels, 8, 1, ColorSpace::RGBA);
+
+    // only alpha is inverted
+    let mut image = original.clone();
+    OnChannels::new(Box::new(Invert::new()), 0b1000)
+        .execute(&mut image)
+        .unwrap();
+
+    let (before, after) = (original.flatten_to_u8(), image.flatten_to_u8());
+
+    for (a, b) in before[0].chunks_exact(4).zip(after[0].chunks_exact(4)) {
+        assert_eq!(a[..3], b[..3]);
+        assert_eq!(255 - a[3], b[3]);
+    }
+    // masks beyond the colorspace are rejected
+    let mut image = original.clone();
+    assert!(OnChannels::new(Box::new(Invert::new()), 0b10000)
+        .execute(&mut image)
+        .is_err());
+
+    // operations that change the layout can't run on channels,
+    // and the image is left as it was
+    let mut image = original.clone();
+    assert!(
+        OnChannels::new(Box::new(Resize::new(4, 1, ResizeMethod::Bilinear)), 0b1)
+            .execute(&mut image)
+            .is_err()
+    );
+    assert_eq!(image.eq_exact(&original), Ok(()));
+}
+
+#[test]
+fn test_resize_options() {
+    // opaque red next to transparent black, enlarged so the middle pixel mixes both
+    let pixels = [255_u8, 0, 0, 255, 0, 0, 0, 0];
+    let resize = |options| {
+        let mut image = Image::from_u8(&pixels, 2, 1, ColorSpace::RGBA);
+        Resize::new(4, 1, ResizeMethod::Bilinear)
+            .set_options(options)
+            .execute(&mut image)
+            .unwrap();
+        image.flatten_to_u8()[0][4..8].to_vec()
+    };
+    // without premultiplying, the black bleeds into the red
+    assert_eq!(resize(ResizeOptions::default()), [127, 0, 0, 127]);
+    let premultiplied = resize(ResizeOptions {
+        premultiply: true,
+        linearize:   false
+    });
+    assert_eq!(premultiplied, [255, 0, 0, 128]);
+
+    // halfway between black and white is brighter than 128 in linear light
+    let resize = |options| {
+        let mut image = Image::from_u8(&[0, 255], 2, 1, ColorSpace::Luma);
+        Resize::new(4, 1, ResizeMethod::Bilinear)
+            .set_options(options)
+            .execute(&mut image)
+            .unwrap();
+        first_channel_u8(&image)
+    };
+    assert_eq!(resize(ResizeOptions::default())[1], 127);
+    let linear = resize(ResizeOptions {
+        premultiply: false,
+        linearize:   true
+    });
+    assert_eq!(linear[1], 188);
+    assert_eq!([linear[0], linear[3]], [0, 255]);
+}
+
+#[test]
+fn test_smart_sharpen() {
+    // a hard edge is sharpened, overshooting on both sides
+    let pixels: Vec<u8> = (0..16 * 4)
+        .map(|i| if i % 16 < 8 { 50 } else { 200 })
+        .collect();
+    let mut image = Image::from_u8(&pixels, 16, 4, ColorSpace::Luma);
+    SmartSharpen::new(1.0, 1.0, 0.2)
+        .execute(&mut image)
+        .unwrap();
+
+    let row = &first_channel_u8(&image)[..16];
+    assert!(row[7] < 50 && row[8] > 200, "{row:?}");
+    assert_eq!((row[0], row[15]), (50, 200));
+
+    // faint noise is below the edge threshold, unlike a plain unsharp mask
+    let noise: Vec<u8> = (0..=255_u8).map(|i| 100 + i % 5 * 2).collect();
+    let largest_change = |edge_threshold| {
+        let mut image = Image::from_u8(&noise, 16, 16, ColorSpace::Luma);
+        SmartSharpen::new(1.0, 1.0, edge_threshold)
+            .execute(&mut image)
+            .unwrap();
+
+        first_channel_u8(&image)
+            .iter()
+            .zip(&noise)
+            .map(|(a, b)| a.abs_diff(*b))
+            .max()
+            .unwrap()
+    };
+    assert!(largest_change(0.5) <= 1);
+    assert!(largest_change(0.0) > 1);
+}
+
+#[test]
+fn test_non_local_means() {
+    // a noisy step edge, noise alternates in a pattern that a blur would smear
+    let (width, height) = (24, 24);
+    let clean = |x: usize| if x < 12 { 60_u8 } else { 190 };
+    let pixels: Vec<u8> = (0..width * height)
+        .map(|i| {
+            let noise = [0_u8, 16, 4, 12, 8][(i * 7 + i / width) % 5];
+            clean(i % width) - 8 + noise
+        })
+        .collect();
+    let error = |pixels: &[u8]| -> usize {
+        pixels
+            .iter()
+            .enumerate()
+            .map(|(i, x)| usize::from(x.abs_diff(clean(i % width))))
+            .sum()
+    };
+
+    let mut image = Image::from_u8(&pixels, width, height, ColorSpace::Luma);
+    NonLocalMeans::new(0.05).execute(&mut image).unwrap();
+    let denoised = first_channel_u8(&image);
+
+    assert!(error(&denoised) * 3 < error(&pixels));
+    // the edge stays sharp
+    for row in denoised.chunks_exact(width) {
+        assert!(row[11] < 80 && row[12] > 170, "{row:?}");
+    }
+    assert!(NonLocalMeans::new(0.0).execute(&mut image).is_err());
+}
+
+#[test]
+fn test_blurs_cancel() {
+    let image = synthetic_image(ColorSpace::RGB, BitDepth::Eight, 64, 64);
+    let blurs: [Box<dyn OperationsTrait>; 2] =
+        [Box::new(BoxBlur::new(5)), Box::new(GaussianBlur::new(3.0))];
+
+    for blur in &blurs {
+        let token = CancellationToken::new();
+        let _scope = token.enter();
+
+        blur.execute(&mut image.clone()).unwrap();
+        token.cancel();
+
+        assert!(
+            matches!(
+                blur.execute(&mut image.clone()),
+                Err(ImageErrors::Cancelled)
+            ),
+            "{}",
+            blur.name()
+        );
+    }
+}
+
+#[test]
+fn test_unsigned_kernels_agree_across_depths() {
+    // order statistics and moves commute with widening 8 bit samples to 16 bits
+    let operations: [Box<dyn OperationsTrait>; 2] =
+        [Box::new(Median::new(2)), Box::new(Transpose::new())];
+
+    for operation in &operations {
+        let mut narrow = synthetic_image(ColorSpace::RGB, BitDepth::Eight, 13, 7);
+        let mut wide = narrow.clone();
+
+        Depth::new(BitDepth::Sixteen).execute(&mut wide).unwrap();
+        operation.execute(&mut narrow).unwrap();
+        operation.execute(&mut wide).unwrap();
+        Depth::new(BitDepth::Eight).execute(&mut wide).unwrap();
+
+        assert!(wide == narrow, "{}", operation.name());
+    }
+}
+
+#[test]
+fn test_execute_view_matches_crop() {
+    // point operations run on the rows in place, the blur on a copy of the region
+    let operations: [(Box<dyn OperationsTrait>, BitDepth); 6] = [
+        (Box::new(Invert::new()), BitDepth::Sixteen),
+        (Box::new(Brighten::new(0.2)), BitDepth::Sixteen),
+        (Box::new(Contrast::new(30.0)), BitDepth::Eight),
+        (Box::new(Exposure::new(1.5, 0.1)), BitDepth::Float32),
+        (
+            Box::new(Threshold::new(100.0, ThresholdMethod::Binary)),
+            BitDepth::Eight
+        ),
+        (Box::new(BoxBlur::new(2)), BitDepth::Sixteen)
+    ];
+
+    for (operation, depth) in &operations {
+        let image = synthetic_image(ColorSpace::RGBA, *depth, 23, 17);
+        let mut processed = image.clone();
+        operation
+            .execute_view(&mut processed.view_mut(5, 3, 11, 9).unwrap())
+            .unwrap();
+
+        let mut expected = image.view(5, 3, 11, 9).unwrap().to_image().unwrap();
+        operation.execute(&mut expected).unwrap();
+
+        let region = processed.view(5, 3, 11, 9).unwrap().to_image().unwrap();
+        assert!(region == expected, "{}", operation.name());
+
+        // pixels outside the region are untouched
+        let mut restored = processed.clone();
+        restored
+            .view_mut(5, 3, 11, 9)
+            .unwrap()
+            .copy_from(&image.view(5, 3, 11, 9).unwrap().to_image().unwrap())
+            .unwrap();
+        assert!(restored == image, "{}", operation.name());
+    }
+}
+
+#[test]
+fn test_execute_on_tiles() {
+    let image = synthetic_image(ColorSpace::RGB, BitDepth::Eight, 23, 17);
+    let operation = Brighten::new(-0.3);
+
+    let mut tiled = image.clone();
+    for mut tile in tiled.tiles_mut(8, 8).unwrap() {
+        operation.execute_view(&mut tile).unwrap();
+    }
+    let mut expected = image.clone();
+    operation.execute(&mut expected).unwrap();
+
+    assert!(tiled == expected);
+}
+
+#[test]
+#[allow(deprecated)]
+fn test_deprecated_kernels_forward() {
+    let pixels: Vec<u8> = (0..=255).cycle().step_by(7).take(12 * 9).collect();
+    let (width, height) = (12, 9);
+
+    let mut old = pixels.clone();
+    let mut new = pixels.clone();
+    let mut scratch = vec![0; pixels.len()];
+
+    zune_imageprocs::box_blur::box_blur_u8(&mut old, &mut scratch, width, height, 3);
+    zune_imageprocs::box_blur::box_blur_unsigned(&mut new, &mut scratch, width, height, 3);
+    assert_eq!(old, new);
+
+    let mut old = vec![0; pixels.len()];
+    let mut new = vec![0; pixels.len()];
+
+    zune_imageprocs::median::median_u8(&pixels, &mut old, 2, width, height);
+    zune_imageprocs::median::median_unsigned(&pixels, &mut new, 2, width, height);
+    assert_eq!(old, new);
+}
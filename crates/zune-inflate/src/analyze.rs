@@ -0,0 +1,76 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! Block level information about deflate streams
+//!
+//! This is returned by [`DeflateDecoder::analyze`](crate::DeflateDecoder::analyze)
+//! and is meant for inspecting what an encoder produced, e.g how it split
+//! its input into blocks and how good its Huffman codes are.
+use alloc::vec::Vec;
+
+/// How a deflate block stores its data
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum BlockType {
+    /// Uncompressed bytes
+    Stored,
+    /// Huffman codes defined by the specification
+    Static,
+    /// Huffman codes described in the block header
+    Dynamic
+}
+
+/// Huffman codes used by a compressed block
+#[derive(Clone, Debug)]
+pub struct HuffmanInfo {
+    /// Codeword length of each literal/length symbol, zero for unused symbols
+    pub litlen_lengths: Vec<u8>,
+    /// Codeword length of each offset symbol, zero for unused symbols
+    pub offset_lengths: Vec<u8>,
+    /// Bits spent describing the codes in the block header, zero for static blocks
+    pub header_bits:    usize
+}
+
+impl HuffmanInfo {
+    /// Return the number of literal/length symbols with a codeword
+    pub fn used_litlen_codes(&self) -> usize {
+        self.litlen_lengths.iter().filter(|x| **x != 0).count()
+    }
+    /// Return the number of offset symbols with a codeword
+    pub fn used_offset_codes(&self) -> usize {
+        self.offset_lengths.iter().filter(|x| **x != 0).count()
+    }
+    /// Return the longest literal/length codeword, in bits
+    pub fn max_litlen_length(&self) -> u8 {
+        self.litlen_lengths.iter().copied().max().unwrap_or(0)
+    }
+    /// Return the longest offset codeword, in bits
+    pub fn max_offset_length(&self) -> u8 {
+        self.offset_lengths.iter().copied().max().unwrap_or(0)
+    }
+}
+
+/// Information about a single deflate block
+#[derive(Clone, Debug)]
+pub struct BlockInfo {
+    /// How the block stores its data
+    pub block_type:        BlockType,
+    /// Whether this is the last block of the stream
+    pub is_final:          bool,
+    /// Position of the block header in the input, in bits
+    pub start_bit:         usize,
+    /// Size of the block including its header, in bits
+    pub compressed_bits:   usize,
+    /// Number of bytes the block decompresses to
+    pub uncompressed_size: usize,
+    /// Number of literals in the block, for stored blocks every byte is a literal
+    pub literals:          usize,
+    /// Number of back references in the block
+    pub matches:           usize,
+    /// Codes used by the block, `None` for stored blocks
+    pub huffman:           Option<HuffmanInfo>
+}
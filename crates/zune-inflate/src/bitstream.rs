@@ -8,17 +8,43 @@
 
 //! `BitStreamReader` API
 //!
-//! This module provides an interface to read and write bits (and bytes) for
-//! huffman
+//! This module provides an interface to read bits (and bytes) for
+//! huffman decoding.
+//!
+//! Bits are read least significant bit first, as used by deflate, and the
+//! reader keeps up to 63 bits buffered, so that after a [`refill`](BitStreamReader::refill)
+//! at least 56 bits can be peeked or consumed without checking the input.
+//!
+//! The buffer is a `u64` on all targets, so the same guarantees hold on 32 bit
+//! targets (e.g wasm32 and armv7), which use a refill that avoids most 64 bit shifts.
+//! Only [`peek_bits`](BitStreamReader::peek_bits) differs, it returns a `usize`
+//! and is limited to 31 bits there.
+//!
+//! # Example
+//! ```
+//! use zune_inflate::bitstream::BitStreamReader;
+//!
+//! let data = [0b1010_1100, 0xFF];
+//! let mut stream = BitStreamReader::new(&data);
+//!
+//! stream.refill();
+//! // lowest bits come first
+//! assert_eq!(stream.get_bits(3), 0b100);
+//! assert_eq!(stream.peek_bits::<5>(), 0b10101);
+//! ```
 
+/// A least significant bit first bit reader
 pub struct BitStreamReader<'src> {
-    // buffer from which we are pulling in bits from
-    // used in decompression.
+    /// Buffer from which we are pulling in bits from
     pub src:       &'src [u8],
-    // position in our buffer,
+    /// Position in our buffer, i.e how many bytes have been moved into `buffer`
     pub position:  usize,
+    /// Number of valid bits in `buffer`
     pub bits_left: u8,
+    /// The bit buffer, the next bit to be read is the lowest bit
     pub buffer:    u64,
+    /// Number of bytes past the end of `src` which were filled with
+    /// zeroes during refill
     pub over_read: usize
 }
 
@@ -26,8 +52,8 @@ impl<'src> BitStreamReader<'src> {
     /// Create a new `BitStreamReader` instance
     ///
     /// # Expectations
-    /// The buffer must be padded with fill bytes in the end,
-    /// if not, this becomes UB in the refill phase.
+    /// Reading past the end of the buffer returns zero bits,
+    /// use [`over_read`](Self::over_read) to check if this happened.
     pub fn new(in_buffer: &'src [u8]) -> BitStreamReader<'src> {
         BitStreamReader {
             bits_left: 0,
@@ -40,58 +66,108 @@ impl<'src> BitStreamReader<'src> {
     /// Refill the bitstream ensuring the buffer has bits between
     /// 56 and 63.
     ///
+    /// This uses [`refill_u64`](Self::refill_u64) on 64 bit targets and
+    /// [`refill_u32`](Self::refill_u32) elsewhere (e.g wasm32 and armv7),
+    /// both consume the same bytes and leave the same number of bits in the buffer.
     #[inline(always)]
     pub fn refill(&mut self) {
-        /*
-         * The refill always guarantees refills between 56-63
-         *
-         * Bits stored will never go above 63 and if bits are in the range 56-63 no refills occur.
-         */
-        let mut buf = [0; 8];
+        #[cfg(target_pointer_width = "64")]
+        {
+            self.refill_u64();
+        }
+        #[cfg(not(target_pointer_width = "64"))]
+        {
+            self.refill_u32();
+        }
+    }
+    /// Refill the bitstream only if at least 8 bytes remain in the
+    /// input buffer
+    ///
+    /// This is a faster variant of [`refill`](Self::refill) for hot loops,
+    /// callers must not rely on the buffer being full after calling it.
+    #[inline(always)]
+    pub fn refill_inner_loop(&mut self) {
+        if let Some(bytes) = self.src.get(self.position..self.position + 8) {
+            let bytes: [u8; 8] = bytes.try_into().unwrap();
 
-        match self.src.get(self.position..self.position + 8) {
-            Some(bytes) => {
-                buf.copy_from_slice(bytes);
-                // create a u64 from an array of u8's
-                let new_buffer = u64::from_le_bytes(buf);
-                // num indicates how many bytes we actually consumed.
-                let num = 63 ^ self.bits_left;
-                // offset position
-                self.position += (num >> 3) as usize;
-                // shift number of bits
-                self.buffer |= new_buffer << self.bits_left;
-                // update bits left
-                // bits left are now between 56-63
-                self.bits_left |= 56;
+            #[cfg(target_pointer_width = "64")]
+            {
+                self.refill_u64_from(bytes);
             }
+            #[cfg(not(target_pointer_width = "64"))]
+            {
+                self.refill_u32_from(bytes);
+            }
+        }
+    }
+    /// Refill the bitstream with a single unaligned 64 bit load
+    ///
+    /// Branchless and the fastest refill where 64 bit shifts are native,
+    /// [`refill`](Self::refill) uses it on 64 bit targets.
+    #[inline(always)]
+    pub fn refill_u64(&mut self) {
+        match self.src.get(self.position..self.position + 8) {
+            Some(bytes) => self.refill_u64_from(bytes.try_into().unwrap()),
             None => self.refill_slow()
         }
     }
+    /// Refill the bitstream with two 32 bit loads
+    ///
+    /// Targets without 64 bit registers emulate shifts of the 64 bit buffer by a
+    /// variable amount with several instructions and branches, this only shifts
+    /// when a whole word is missing, [`refill`](Self::refill) uses it on 32 bit targets.
+    ///
+    /// It is available on all targets so it can be tested and benchmarked anywhere.
     #[inline(always)]
-    pub fn refill_inner_loop(&mut self) {
+    pub fn refill_u32(&mut self) {
+        match self.src.get(self.position..self.position + 8) {
+            Some(bytes) => self.refill_u32_from(bytes.try_into().unwrap()),
+            None => self.refill_slow()
+        }
+    }
+    #[inline(always)]
+    fn refill_u64_from(&mut self, bytes: [u8; 8]) {
         /*
          * The refill always guarantees refills between 56-63
          *
          * Bits stored will never go above 63 and if bits are in the range 56-63 no refills occur.
          */
-        let mut buf = [0; 8];
+        // create a u64 from an array of u8's
+        let new_buffer = u64::from_le_bytes(bytes);
+        // num indicates how many bytes we actually consumed.
+        let num = 63 ^ self.bits_left;
+        // offset position
+        self.position += (num >> 3) as usize;
+        // shift number of bits
+        self.buffer |= new_buffer << self.bits_left;
+        // update bits left
+        // bits left are now between 56-63
+        self.bits_left |= 56;
+    }
+    #[inline(always)]
+    fn refill_u32_from(&mut self, bytes: [u8; 8]) {
+        /*
+         * Same result as refill_u64_from, whole words are added while
+         * at least 32 bits are missing, then the remaining whole bytes.
+         */
+        let mut offset = 0;
 
-        if let Some(bytes) = self.src.get(self.position..self.position + 8) {
-            {
-                buf.copy_from_slice(bytes);
-                // create a u64 from an array of u8's
-                let new_buffer = u64::from_le_bytes(buf);
-                // num indicates how many bytes we actually consumed.
-                let num = 63 ^ self.bits_left;
-                // offset position
-                self.position += (num >> 3) as usize;
-                // shift number of bits
-                self.buffer |= new_buffer << self.bits_left;
-                // update bits left
-                // bits left are now between 56-63
-                self.bits_left |= 56;
+        if self.bits_left < 32 {
+            let word = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+
+            self.buffer |= u64::from(word) << self.bits_left;
+            self.bits_left += 32;
+            offset = 4;
+        }
+        for byte in &bytes[offset..] {
+            if self.bits_left >= 56 {
+                break;
             }
+            self.buffer |= u64::from(*byte) << self.bits_left;
+            self.bits_left += 8;
+            offset += 1;
         }
+        self.position += offset;
     }
     #[inline(never)]
     fn refill_slow(&mut self) {
@@ -112,17 +188,32 @@ impl<'src> BitStreamReader<'src> {
         }
     }
 
+    /// Look at the next `LOOKAHEAD` bits without consuming them
+    ///
+    /// The bit buffer must contain at least `LOOKAHEAD` bits, and `LOOKAHEAD`
+    /// must be less than [`usize::BITS`], i.e at most 31 on 32 bit targets,
+    /// as the bits are returned in a `usize`. Use [`get_bits`](Self::get_bits)
+    /// to read wider values on all targets.
     #[inline(always)]
     pub fn peek_bits<const LOOKAHEAD: usize>(&self) -> usize {
         debug_assert!(self.bits_left >= LOOKAHEAD as u8);
+        debug_assert!(LOOKAHEAD < usize::BITS as usize);
         (self.buffer & ((1 << LOOKAHEAD) - 1)) as usize
     }
+    /// Look at the next `lookahead` bits without consuming them
+    ///
+    /// The same limits as [`peek_bits`](Self::peek_bits) apply.
     #[inline(always)]
     pub fn peek_var_bits(&self, lookahead: usize) -> usize {
         debug_assert!(self.bits_left >= lookahead as u8);
+        debug_assert!(lookahead < usize::BITS as usize);
         (self.buffer & ((1 << lookahead) - 1)) as usize
     }
 
+    /// Consume and return the next `num_bits` bits
+    ///
+    /// The bit buffer must contain at least `num_bits` bits, so at most 63
+    /// bits can be read at once, on all targets.
     #[inline(always)]
     pub fn get_bits(&mut self, num_bits: u8) -> u64 {
         debug_assert!(self.bits_left >= num_bits);
@@ -159,6 +250,9 @@ impl<'src> BitStreamReader<'src> {
         self.bits_left >= bits
     }
 
+    /// Discard the next `bits` bits
+    ///
+    /// The bit buffer must contain at least `bits` bits, on all targets.
     #[inline(always)]
     pub fn drop_bits(&mut self, bits: u8) {
         debug_assert!(self.bits_left >= bits);
@@ -0,0 +1,213 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! Match copy kernels
+//!
+//! A deflate match copies `length` bytes starting `offset` bytes behind
+//! the current output position, the source and destination may overlap.
+//!
+//! The kernels here are specialized on the number of bytes copied per step and
+//! [`copy_match`] picks one depending on the offset and length of the match.
+//!
+//! # Slop
+//! For speed, kernels are allowed to write up to `CHUNK` bytes past the end
+//! of the match, callers must ensure the output has that much extra space.
+//! The bytes written past the match are garbage and will be overwritten
+//! by later matches or literals.
+//!
+//! With the `paranoid` feature, [`copy_match`] and [`copy_rep_matches`]
+//! are replaced by a byte by byte reference copy which never writes
+//! past the match and asserts that the match is in bounds.
+
+#[cfg(not(feature = "paranoid"))]
+use core::cell::Cell;
+
+/// Number of bytes the kernels may write past the end of a match
+pub const MAX_COPY_SLOP: usize = 32;
+
+/// A safe version of src.copy_within that helps me because I tend to always
+/// confuse the arguments
+#[inline(always)]
+pub fn fixed_copy_within<const SIZE: usize>(
+    dest: &mut [u8], src_offset: usize, dest_offset: usize
+) {
+    // for debug builds ensure we don't go out of bounds
+    debug_assert!(
+        dest_offset + SIZE <= dest.len(),
+        "[dst]: End position {} out of range for slice of length {}",
+        dest_offset + SIZE,
+        dest.len()
+    );
+
+    dest.copy_within(src_offset..src_offset + SIZE, dest_offset);
+}
+
+/// Copy a match whose offset is one, i.e a run of a single byte
+#[inline(always)]
+pub fn copy_rle(dest: &mut [u8], src_offset: usize, dest_offset: usize, length: usize) {
+    let byte_to_repeat = dest[src_offset];
+    dest[dest_offset..dest_offset + length].fill(byte_to_repeat);
+}
+
+/// Copy a match `CHUNK` bytes at a time.
+///
+/// The match offset must be at least `CHUNK` so that the
+/// source of every step has already been written.
+///
+/// May write up to `CHUNK-1` bytes past the end of the match
+#[inline(always)]
+pub fn copy_chunked<const CHUNK: usize>(
+    dest: &mut [u8], src_offset: usize, dest_offset: usize, length: usize
+) {
+    debug_assert!(dest_offset - src_offset >= CHUNK);
+
+    let end = dest_offset + length;
+    let mut src = src_offset;
+    let mut dst = dest_offset;
+
+    loop {
+        fixed_copy_within::<CHUNK>(dest, src, dst);
+
+        src += CHUNK;
+        dst += CHUNK;
+
+        if dst >= end {
+            break;
+        }
+    }
+}
+
+/// Copy a match whose offset is smaller than `CHUNK`
+///
+/// Each copy from the start of the match doubles the
+/// number of repeated bytes until a whole chunk of the pattern exists,
+/// after which whole chunks are copied.
+///
+/// May write up to `CHUNK-1` bytes past the end of the match
+#[inline(always)]
+pub fn copy_overlapping<const CHUNK: usize>(
+    dest: &mut [u8], src_offset: usize, dest_offset: usize, length: usize
+) {
+    let offset = dest_offset - src_offset;
+
+    debug_assert!(offset > 0 && offset < CHUNK);
+
+    let end = dest_offset + length;
+    let mut dst = dest_offset;
+
+    // replicate the pattern, only the first `dst - src_offset`
+    // bytes of each copy are valid
+    while dst - src_offset < CHUNK {
+        fixed_copy_within::<CHUNK>(dest, src_offset, dst);
+
+        dst += dst - src_offset;
+
+        if dst >= end {
+            return;
+        }
+    }
+    // smallest multiple of the offset that spans a chunk, copies
+    // from this far back only read bytes that have been written
+    let stride = CHUNK.div_ceil(offset) * offset;
+
+    loop {
+        fixed_copy_within::<CHUNK>(dest, dst - stride, dst);
+
+        dst += CHUNK;
+
+        if dst >= end {
+            break;
+        }
+    }
+}
+
+/// Copy a match of `length` bytes from `src_offset` to `dest_offset`,
+/// choosing a kernel based on the offset and length.
+///
+/// `dest` must have at least [`MAX_COPY_SLOP`] bytes of space after the match
+#[cfg(not(feature = "paranoid"))]
+#[inline(always)]
+pub fn copy_match(dest: &mut [u8], src_offset: usize, dest_offset: usize, length: usize) {
+    let offset = dest_offset - src_offset;
+
+    if offset >= 16 {
+        if length <= 16 {
+            // short matches are the most common, do them in one step
+            fixed_copy_within::<16>(dest, src_offset, dest_offset);
+        } else if offset >= 32 {
+            copy_chunked::<32>(dest, src_offset, dest_offset, length);
+        } else {
+            copy_chunked::<16>(dest, src_offset, dest_offset, length);
+        }
+    } else if offset == 1 {
+        copy_rle(dest, src_offset, dest_offset, length);
+    } else if offset >= 8 {
+        copy_chunked::<8>(dest, src_offset, dest_offset, length);
+    } else {
+        copy_overlapping::<16>(dest, src_offset, dest_offset, length);
+    }
+}
+
+/// Copy a match byte by byte, writing at most two bytes past its end
+///
+/// This is used outside the fast loop where there is no
+/// space for the slop of the other kernels
+#[cfg(not(feature = "paranoid"))]
+#[inline(always)]
+pub fn copy_rep_matches(dest: &mut [u8], offset: usize, dest_offset: usize, length: usize) {
+    // This is a slightly complicated rep match copier that has
+    // no bounds check.
+
+    // The only invariant we need to uphold is dest[dest_offset] should
+    // copy from dest[offset]
+    // i.e in the first iteration, the first entry in the window will point
+    // to dest[offset] and the
+    // last entry will point to dest[dest_offset]
+    // it's easy to prove dest[offset] since we take our slice
+    // from offset.
+    // but proving dest[dest_offset] is trickier
+    // If we were at offset, to get to dest_offset, we could
+    // 1. Get difference between dest_offset and offset
+    // 2. Add that difference to offset.
+    //
+
+    let diff = dest_offset - offset + 1;
+
+    // note
+    for window in Cell::from_mut(&mut dest[offset..dest_offset + length + 2])
+        .as_slice_of_cells()
+        .windows(diff)
+    {
+        window.last().unwrap().set(window[0].get());
+    }
+}
+
+/// Checked reference version of `copy_match`, used by the `paranoid` feature
+#[cfg(feature = "paranoid")]
+pub fn copy_match(dest: &mut [u8], src_offset: usize, dest_offset: usize, length: usize) {
+    assert!(
+        src_offset < dest_offset,
+        "Match source {src_offset} is not behind destination {dest_offset}"
+    );
+    assert!(
+        dest_offset + length <= dest.len(),
+        "Match end {} out of range for slice of length {}",
+        dest_offset + length,
+        dest.len()
+    );
+
+    for i in 0..length {
+        dest[dest_offset + i] = dest[src_offset + i];
+    }
+}
+
+/// Checked reference version of `copy_rep_matches`, used by the `paranoid` feature
+#[cfg(feature = "paranoid")]
+pub fn copy_rep_matches(dest: &mut [u8], offset: usize, dest_offset: usize, length: usize) {
+    copy_match(dest, offset, dest_offset, length);
+}
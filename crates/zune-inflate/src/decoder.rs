@@ -11,6 +11,7 @@
 use alloc::vec::Vec;
 use alloc::{format, vec};
 
+use crate::analyze::{BlockInfo, BlockType, HuffmanInfo};
 use crate::bitstream::BitStreamReader;
 use crate::constants::{
     DEFLATE_BLOCKTYPE_DYNAMIC_HUFFMAN, DEFLATE_BLOCKTYPE_RESERVED, DEFLATE_BLOCKTYPE_STATIC,
@@ -23,13 +24,15 @@ use crate::constants::{
     LITLEN_ENOUGH, LITLEN_TABLE_BITS, OFFSET_DECODE_RESULTS, OFFSET_ENOUGH, OFFSET_TABLEBITS,
     PRECODE_DECODE_RESULTS, PRECODE_ENOUGH, PRECODE_TABLE_BITS
 };
+use crate::copy::{copy_match, copy_rep_matches};
 use crate::errors::{DecodeErrorStatus, InflateDecodeErrors};
 #[cfg(feature = "gzip")]
 use crate::gzip_constants::{
     GZIP_CM_DEFLATE, GZIP_FCOMMENT, GZIP_FEXTRA, GZIP_FHCRC, GZIP_FNAME, GZIP_FOOTER_SIZE,
     GZIP_FRESERVED, GZIP_ID1, GZIP_ID2
 };
-use crate::utils::{copy_rep_matches, fixed_copy_within, make_decode_table_entry};
+use crate::huffman::build_decode_table;
+use crate::utils::paranoid_assert;
 
 struct DeflateHeaderTables {
     litlen_decode_table: [u32; LITLEN_ENOUGH],
@@ -54,7 +57,9 @@ impl Default for DeflateHeaderTables {
 pub struct DeflateOptions {
     limit:            usize,
     confirm_checksum: bool,
-    size_hint:        usize
+    confirm_header:   bool,
+    size_hint:        usize,
+    reference_mode:   bool
 }
 
 impl Default for DeflateOptions {
@@ -62,7 +67,9 @@ impl Default for DeflateOptions {
         DeflateOptions {
             limit:            1 << 30,
             confirm_checksum: true,
-            size_hint:        37000
+            confirm_header:   true,
+            size_hint:        0,
+            reference_mode:   false
         }
     }
 }
@@ -120,12 +127,33 @@ impl DeflateOptions {
         self
     }
 
+    /// Get whether the decoder will confirm the zlib
+    /// header `FCHECK` bits
+    pub const fn get_confirm_header(&self) -> bool {
+        self.confirm_header
+    }
+    /// Set whether the decoder should confirm the zlib header
+    /// `FCHECK` bits before decoding
+    ///
+    /// Some encoders write wrong check bits while the stream itself is fine,
+    /// disabling this allows such streams to be decoded.
+    ///
+    /// # Notes
+    /// This only has an influence for zlib decoding
+    pub fn set_confirm_header(mut self, yes: bool) -> Self {
+        self.confirm_header = yes;
+        self
+    }
+
     /// Get the default set size hint for the decompressor
     ///
     /// The decompressor initializes the internal storage for decompressed bytes
     /// with this size and will reallocate the vec if the decompressed size becomes bigger
     /// than this, but when the user currently knows how big the output will be, can be used
     /// to prevent unnecessary re-allocations
+    ///
+    /// The default is zero, in which case the initial size is estimated from the
+    /// input length
     pub const fn get_size_hint(&self) -> usize {
         self.size_hint
     }
@@ -137,6 +165,22 @@ impl DeflateOptions {
         self.size_hint = hint;
         self
     }
+
+    /// Get whether the decoder runs in reference mode
+    pub const fn get_reference_mode(&self) -> bool {
+        self.reference_mode
+    }
+    /// Set whether the decoder should run in reference mode
+    ///
+    /// In reference mode, every huffman block is decoded by the careful
+    /// generic loop, skipping the optimized fast loop. Output is bit exact
+    /// with the default mode but slower, this is meant for conformance testing,
+    /// where both modes are compared against each other and a reference decoder.
+    #[must_use]
+    pub const fn set_reference_mode(mut self, yes: bool) -> Self {
+        self.reference_mode = yes;
+        self
+    }
 }
 
 /// A deflate decoder instance.
@@ -157,7 +201,9 @@ pub struct DeflateDecoder<'a> {
     is_last_block:         bool,
     static_codes_loaded:   bool,
     deflate_header_tables: DeflateHeaderTables,
-    options:               DeflateOptions
+    options:               DeflateOptions,
+    bytes_read:            usize,
+    output:                Vec<u8>
 }
 
 impl<'a> DeflateDecoder<'a> {
@@ -221,9 +267,36 @@ impl<'a> DeflateDecoder<'a> {
             is_last_block: false,
             static_codes_loaded: false,
             deflate_header_tables: DeflateHeaderTables::default(),
-            options
+            options,
+            bytes_read: 0,
+            output: Vec::new()
         }
     }
+    /// Decode the next stream into `buffer` instead of a newly allocated vector
+    ///
+    /// The contents of `buffer` are discarded, only its allocation is reused,
+    /// so callers decoding many similar sized streams can hand back the vector
+    /// returned by a previous decode and avoid allocating the output each time.
+    ///
+    /// # Example
+    /// ```
+    /// use zune_inflate::DeflateDecoder;
+    ///
+    /// // a stored block holding "abc"
+    /// let data = [0x01, 0x03, 0x00, 0xFC, 0xFF, b'a', b'b', b'c'];
+    ///
+    /// let buffer = Vec::with_capacity(1024);
+    ///
+    /// let mut decoder = DeflateDecoder::new(&data);
+    /// decoder.set_output_buffer(buffer);
+    ///
+    /// let bytes = decoder.decode_deflate().unwrap();
+    /// assert_eq!(bytes, b"abc");
+    /// assert!(bytes.capacity() >= 1024);
+    /// ```
+    pub fn set_output_buffer(&mut self, buffer: Vec<u8>) {
+        self.output = buffer;
+    }
     /// Decode zlib-encoded data returning the uncompressed in a `Vec<u8>`
     /// or an error if something went wrong.
     ///
@@ -250,8 +323,45 @@ impl<'a> DeflateDecoder<'a> {
     ///
     #[cfg(feature = "zlib")]
     pub fn decode_zlib(&mut self) -> Result<Vec<u8>, InflateDecodeErrors> {
-        use crate::utils::calc_adler_hash;
+        use zune_core::checksum::adler32;
+
+        self.read_zlib_header()?;
+
+        let data = self.start_deflate_block()?;
+
+        // Get number of consumed bytes from the input
+        let out_pos = self.consumed_bytes();
+
+        if self.options.confirm_checksum {
+            // read adler
+            if let Some(adler) = self.data.get(out_pos..out_pos + 4) {
+                let adler_bits: [u8; 4] = adler.try_into().unwrap();
 
+                let adler32_expected = u32::from_be_bytes(adler_bits);
+
+                let adler32_found = adler32::adler32(&data);
+
+                if adler32_expected != adler32_found {
+                    let err_msg =
+                        DecodeErrorStatus::MismatchedAdler(adler32_expected, adler32_found);
+                    let err = InflateDecodeErrors::new(err_msg, data);
+
+                    return Err(err);
+                }
+            } else {
+                let err = InflateDecodeErrors::new(DecodeErrorStatus::InsufficientData, data);
+
+                return Err(err);
+            }
+        }
+        self.bytes_read = (out_pos + 4).min(self.data.len());
+
+        Ok(data)
+    }
+
+    /// Check the zlib header and move past it
+    #[cfg(feature = "zlib")]
+    fn read_zlib_header(&mut self) -> Result<(), InflateDecodeErrors> {
         if self.data.len()
             < 2 /* zlib header */
             + 4
@@ -295,7 +405,7 @@ impl<'a> DeflateDecoder<'a> {
         }
         let flag_checks = (u16::from(cmf) * 256) + u16::from(flg);
 
-        if flag_checks % 31 != 0 {
+        if self.options.confirm_header && flag_checks % 31 != 0 {
             return Err(InflateDecodeErrors::new_with_error(
                 DecodeErrorStatus::Generic("FCHECK integrity not preserved")
             ));
@@ -303,37 +413,8 @@ impl<'a> DeflateDecoder<'a> {
 
         self.position = 2;
 
-        let data = self.decode_deflate()?;
-
-        if self.options.confirm_checksum {
-            // Get number of consumed bytes from the input
-            let out_pos = self.stream.get_position() + self.position + self.stream.over_read;
-
-            // read adler
-            if let Some(adler) = self.data.get(out_pos..out_pos + 4) {
-                let adler_bits: [u8; 4] = adler.try_into().unwrap();
-
-                let adler32_expected = u32::from_be_bytes(adler_bits);
-
-                let adler32_found = calc_adler_hash(&data);
-
-                if adler32_expected != adler32_found {
-                    let err_msg =
-                        DecodeErrorStatus::MismatchedAdler(adler32_expected, adler32_found);
-                    let err = InflateDecodeErrors::new(err_msg, data);
-
-                    return Err(err);
-                }
-            } else {
-                let err = InflateDecodeErrors::new(DecodeErrorStatus::InsufficientData, data);
-
-                return Err(err);
-            }
-        }
-
-        Ok(data)
+        Ok(())
     }
-
     /// Decode a gzip encoded data and return the uncompressed data in a
     /// `Vec<u8>` or an error if something went wrong
     ///
@@ -459,9 +540,9 @@ impl<'a> DeflateDecoder<'a> {
             ));
         }
 
-        let data = self.decode_deflate()?;
+        let data = self.start_deflate_block()?;
 
-        let mut out_pos = self.stream.get_position() + self.position + self.stream.over_read;
+        let mut out_pos = self.consumed_bytes();
 
         if self.options.confirm_checksum {
             // Get number of consumed bytes from the input
@@ -471,7 +552,7 @@ impl<'a> DeflateDecoder<'a> {
 
                 let crc32_expected = u32::from_le_bytes(crc_bits);
 
-                let crc32_found = !crate::crc::crc32(&data, !0);
+                let crc32_found = zune_core::checksum::crc32::crc32(&data);
 
                 if crc32_expected != crc32_found {
                     let err_msg = DecodeErrorStatus::MismatchedCRC(crc32_expected, crc32_found);
@@ -504,9 +585,71 @@ impl<'a> DeflateDecoder<'a> {
 
             return Err(err);
         }
+        self.bytes_read = out_pos + 4;
 
         Ok(data)
     }
+    /// Decode every member of a gzip file and return their concatenated data
+    ///
+    /// A gzip file may hold several members back to back, e.g files compressed
+    /// separately and then concatenated, or logs appended to over time. Decompressors
+    /// are expected to output the data of all of them, [`decode_gzip`](Self::decode_gzip)
+    /// only decodes the first.
+    ///
+    /// Decoding stops at the end of the input or at the first bytes that don't start a
+    /// member, e.g padding after the last one, these bytes are returned by
+    /// [`remaining`](Self::remaining). The output limit applies to the total size.
+    ///
+    /// # Errors
+    /// If a member is corrupt, the error holds the data of the members before it
+    /// followed by what was decoded of that member.
+    ///
+    /// # Note
+    /// This needs the `gzip` feature enabled to be available, otherwise it's a
+    /// compile time error
+    #[cfg(feature = "gzip")]
+    pub fn decode_gzip_multi(&mut self) -> Result<Vec<u8>, InflateDecodeErrors> {
+        let mut output = core::mem::take(&mut self.output);
+        let mut buffer = Vec::new();
+        let mut position = 0;
+
+        output.clear();
+        self.bytes_read = 0;
+
+        loop {
+            let options = self
+                .options
+                .set_limit(self.options.limit.saturating_sub(output.len()));
+            let mut member = DeflateDecoder::new_with_options(&self.data[position..], options);
+            member.set_output_buffer(buffer);
+
+            match member.decode_gzip() {
+                Ok(data) => {
+                    output.extend_from_slice(&data);
+                    buffer = data;
+                }
+                Err(mut err) => {
+                    output.append(&mut err.data);
+                    err.data = output;
+
+                    // report the limit and size of the whole output
+                    if let DecodeErrorStatus::OutputLimitExceeded(limit, size) = &mut err.error {
+                        *limit = self.options.limit;
+                        *size = err.data.len();
+                    }
+                    return Err(err);
+                }
+            }
+            position += member.bytes_read();
+
+            if !self.data[position..].starts_with(&[GZIP_ID1, GZIP_ID2]) {
+                break;
+            }
+        }
+        self.bytes_read = position;
+
+        Ok(output)
+    }
     /// Decode a deflate stream returning the data as `Vec<u8>` or an error
     /// indicating what went wrong.
     /// # Arguments
@@ -531,20 +674,162 @@ impl<'a> DeflateDecoder<'a> {
     ///
     ///  [InflateDecodeErrors]:crate::errors::InflateDecodeErrors
     pub fn decode_deflate(&mut self) -> Result<Vec<u8>, InflateDecodeErrors> {
-        self.start_deflate_block()
+        let data = self.start_deflate_block()?;
+        self.bytes_read = self.consumed_bytes();
+
+        Ok(data)
+    }
+    /// Decode a bare deflate stream starting at the first byte of the input,
+    /// ignoring any bytes after the end of the stream
+    ///
+    /// Unlike [`decode_deflate`](Self::decode_deflate) this always starts at the
+    /// beginning of the input, even if the decoder was used before.
+    ///
+    /// Use [`bytes_read`](Self::bytes_read) to find where the stream ended, which is
+    /// how containers holding deflate streams back to back (e.g gzip members or ZIP
+    /// local entries) locate the structure after it.
+    ///
+    /// # Example
+    /// ```
+    /// use zune_inflate::DeflateDecoder;
+    ///
+    /// // a stored block holding "abc", followed by bytes of the container
+    /// let data = [0x01, 0x03, 0x00, 0xFC, 0xFF, b'a', b'b', b'c', 0xDE, 0xAD];
+    ///
+    /// let mut decoder = DeflateDecoder::new(&data);
+    ///
+    /// assert_eq!(decoder.decode_deflate_raw().unwrap(), b"abc");
+    /// assert_eq!(decoder.bytes_read(), 8);
+    /// ```
+    pub fn decode_deflate_raw(&mut self) -> Result<Vec<u8>, InflateDecodeErrors> {
+        self.position = 0;
+        self.decode_deflate()
+    }
+    /// Return the number of input bytes consumed by the last successful decode
+    ///
+    /// This covers everything the decode read, i.e the zlib header and adler32 checksum
+    /// for [`decode_zlib`](Self::decode_zlib), the gzip header and footer for
+    /// [`decode_gzip`](Self::decode_gzip) and the stream up to and including its
+    /// final partial byte for raw deflate, bytes after that were not touched.
+    ///
+    /// This is only meaningful after a decode succeeded
+    pub const fn bytes_read(&self) -> usize {
+        self.bytes_read
+    }
+    /// Return the input bytes after the end of the last successful decode
+    ///
+    /// These are the bytes not counted by [`bytes_read`](Self::bytes_read), e.g trailing
+    /// data of a container, or further streams when several were concatenated, which
+    /// can be handed to a new decoder.
+    ///
+    /// # Example
+    /// ```
+    /// use zune_inflate::DeflateDecoder;
+    ///
+    /// // a zlib stream holding "abc", twice
+    /// let stream = [
+    ///     0x78, 0x01, 0x01, 0x03, 0x00, 0xFC, 0xFF, b'a', b'b', b'c', 0x02, 0x4D, 0x01, 0x27
+    /// ];
+    /// let data = [stream, stream].concat();
+    ///
+    /// let mut output = vec![];
+    /// let mut input = data.as_slice();
+    ///
+    /// while !input.is_empty() {
+    ///     let mut decoder = DeflateDecoder::new(input);
+    ///     output.extend(decoder.decode_zlib().unwrap());
+    ///     input = decoder.remaining();
+    /// }
+    /// assert_eq!(output, b"abcabc");
+    /// ```
+    pub fn remaining(&self) -> &'a [u8] {
+        &self.data[self.bytes_read.min(self.data.len())..]
+    }
+    /// Walk the blocks of a bare deflate stream starting at the first byte of the input,
+    /// reporting how each block is stored instead of decompressing it
+    ///
+    /// Symbols are decoded to find block boundaries and sizes, but no output is written,
+    /// so this needs little memory even for streams that decompress to a lot of data.
+    ///
+    /// # Returns
+    /// Information about each block, in stream order.
+    ///
+    /// # Example
+    /// ```
+    /// use zune_inflate::analyze::BlockType;
+    /// use zune_inflate::DeflateDecoder;
+    ///
+    /// // a stored block holding "abc"
+    /// let data = [0x01, 0x03, 0x00, 0xFC, 0xFF, b'a', b'b', b'c'];
+    ///
+    /// let blocks = DeflateDecoder::new(&data).analyze().unwrap();
+    ///
+    /// assert_eq!(blocks.len(), 1);
+    /// assert_eq!(blocks[0].block_type, BlockType::Stored);
+    /// assert_eq!(blocks[0].uncompressed_size, 3);
+    /// assert_eq!(blocks[0].compressed_bits, data.len() * 8);
+    /// ```
+    pub fn analyze(&mut self) -> Result<Vec<BlockInfo>, InflateDecodeErrors> {
+        self.position = 0;
+        self.analyze_blocks()
+    }
+    /// Walk the blocks of a zlib stream, see [`analyze`](Self::analyze)
+    ///
+    /// The header is checked like in [`decode_zlib`](Self::decode_zlib), but since
+    /// nothing is decompressed the adler32 checksum is not.
+    ///
+    /// Block positions are relative to the start of the input, i.e they include
+    /// the two byte zlib header.
+    ///
+    /// # Note
+    /// This needs the `zlib` feature enabled to be available otherwise it's a
+    /// compile time error
+    #[cfg(feature = "zlib")]
+    pub fn analyze_zlib(&mut self) -> Result<Vec<BlockInfo>, InflateDecodeErrors> {
+        self.read_zlib_header()?;
+        self.analyze_blocks()
+    }
+    /// Number of bytes from the start of the input up to the current stream position,
+    /// a partially consumed byte counts as consumed
+    fn consumed_bytes(&self) -> usize {
+        // bits left include the zeroes added past the end of the input, which
+        // `position` does not count
+        self.position
+            + (self.stream.position + self.stream.over_read)
+                .saturating_sub(usize::from(self.stream.bits_left >> 3))
     }
     /// Main inner loop for decompressing deflate data
     #[allow(unused_assignments)]
     #[allow(clippy::never_loop)] // wrong submission
     fn start_deflate_block(&mut self) -> Result<Vec<u8>, InflateDecodeErrors> {
+        self.bytes_read = 0;
         // start deflate decode
         // re-read the stream so that we can remove code read by zlib
         self.stream = BitStreamReader::new(&self.data[self.position..]);
 
         self.stream.refill();
 
+        let total_in = self.stream.src.len();
+
         // Output space for our decoded bytes.
-        let mut out_block = vec![0; self.options.size_hint];
+        let initial_size = if self.options.size_hint == 0 {
+            total_in
+                .saturating_mul(INITIAL_RATIO)
+                .min(self.options.limit)
+        } else {
+            // leave room for the sloppy copies of the fast loop so that
+            // an exact hint never needs a reallocation
+            self.options.size_hint + FASTLOOP_MAX_BYTES_WRITTEN
+        };
+        let mut out_block = core::mem::take(&mut self.output);
+
+        if out_block.capacity() == 0 {
+            // a fresh zeroed allocation can come straight from the allocator
+            out_block = vec![0; initial_size];
+        } else {
+            out_block.clear();
+            out_block.resize(initial_size, 0);
+        }
         // bits used
 
         let mut src_offset = 0;
@@ -595,14 +880,19 @@ impl<'a> DeflateDecoder<'a> {
                 }
                 let len = len as usize;
 
-                let start = self.stream.get_position() + self.position + self.stream.over_read;
+                let start = self.consumed_bytes();
 
                 // ensure there is enough space for a fast copy
                 if dest_offset + len + FASTCOPY_BYTES > out_block.len() {
                     // and if there is not, resize
-                    let new_len = out_block.len() + RESIZE_BY + len;
-
-                    out_block.resize(new_len, 0);
+                    grow_output(
+                        &mut out_block,
+                        dest_offset + len + FASTCOPY_BYTES,
+                        dest_offset,
+                        self.stream.get_position(),
+                        total_in,
+                        self.options.limit
+                    );
                 }
 
                 if self.data.get((start + len).saturating_sub(1)).is_none() {
@@ -628,9 +918,10 @@ impl<'a> DeflateDecoder<'a> {
 
                 dest_offset += len;
 
-                // get the new position to write.
-                self.stream.position =
-                    len + (self.stream.position - usize::from(self.stream.bits_left >> 3));
+                // continue reading after the stored bytes, dropping any zeroes
+                // added past the end of the input while reading the block header
+                self.stream.position = start + len - self.position;
+                self.stream.over_read = 0;
 
                 self.stream.reset();
 
@@ -649,7 +940,7 @@ impl<'a> DeflateDecoder<'a> {
             }
 
             // build decode tables for static and dynamic tables
-            match self.build_decode_table(block_type) {
+            match self.build_decode_table(block_type, None) {
                 Ok(_) => (),
                 Err(value) => {
                     out_block.truncate(dest_offset);
@@ -679,7 +970,8 @@ impl<'a> DeflateDecoder<'a> {
             let mut saved_bitbuf;
 
             'decode: loop {
-                let close_src = 3 * FASTCOPY_BYTES < self.stream.remaining_bytes();
+                let close_src = !self.options.reference_mode
+                    && 3 * FASTCOPY_BYTES < self.stream.remaining_bytes();
 
                 if close_src {
                     self.stream.refill_inner_loop();
@@ -692,8 +984,14 @@ impl<'a> DeflateDecoder<'a> {
                         // Resize the output vector here to ensure we can always have
                         // enough space for sloppy copies
                         if dest_offset + FASTLOOP_MAX_BYTES_WRITTEN > out_block.len() {
-                            let curr_len = out_block.len();
-                            out_block.resize(curr_len + FASTLOOP_MAX_BYTES_WRITTEN + RESIZE_BY, 0)
+                            grow_output(
+                                &mut out_block,
+                                dest_offset + FASTLOOP_MAX_BYTES_WRITTEN,
+                                dest_offset,
+                                self.stream.get_position(),
+                                total_in,
+                                self.options.limit
+                            );
                         }
                         // At this point entry contains the next value of the litlen
                         // This will always be the case so meaning all our exit paths need
@@ -793,6 +1091,7 @@ impl<'a> DeflateDecoder<'a> {
                             saved_bitbuf = self.stream.buffer;
 
                             pos += self.stream.peek_var_bits(entry_position);
+                            paranoid_assert!(pos < LITLEN_ENOUGH, "litlen subtable index {pos}");
                             entry = litlen_decode_table[pos.min(LITLEN_ENOUGH - 1)];
 
                             self.stream.drop_bits(entry as u8);
@@ -843,7 +1142,9 @@ impl<'a> DeflateDecoder<'a> {
                         if (entry & HUFFDEC_EXCEPTIONAL) != 0 {
                             self.stream.drop_bits(OFFSET_TABLEBITS as u8);
                             let extra = self.stream.peek_var_bits(((entry >> 8) & 0x3F) as usize);
-                            entry = offset_decode_table[((entry >> 16) as usize + extra) & 511];
+                            let pos = (entry >> 16) as usize + extra;
+                            paranoid_assert!(pos < OFFSET_ENOUGH, "offset subtable index {pos}");
+                            entry = offset_decode_table[pos & 511];
                             // refill to handle some weird edge case where we have
                             // less bits than needed for reading the lit-len
                         }
@@ -870,95 +1171,14 @@ impl<'a> DeflateDecoder<'a> {
                         if self.stream.bits_left < 11 {
                             self.stream.refill_inner_loop();
                         }
-                        // Copy some bytes unconditionally
-                        // This makes us copy smaller match lengths quicker because we don't need
-                        // a loop + don't send too much pressure to the Memory unit.
-                        fixed_copy_within::<FASTCOPY_BYTES>(
-                            &mut out_block,
-                            src_offset,
-                            dest_offset
-                        );
+                        // Output has FASTLOOP_MAX_BYTES_WRITTEN bytes of space, so the
+                        // kernels can write past the match without bounds checks
+                        copy_match(&mut out_block, src_offset, dest_offset, length);
 
                         entry = litlen_decode_table[self.stream.peek_bits::<LITLEN_DECODE_BITS>()];
 
-                        let mut current_position = dest_offset;
-
                         dest_offset += length;
 
-                        if offset == 1 {
-                            // RLE fill with a single byte
-                            let byte_to_repeat = out_block[src_offset];
-                            out_block[current_position..dest_offset].fill(byte_to_repeat);
-                        } else if offset <= FASTCOPY_BYTES
-                            && current_position + offset < dest_offset
-                        {
-                            // The second conditional ensures we only come
-                            // here if the first copy didn't succeed to copy just enough bytes for a rep
-                            // match to be valid, i.e we want this path to be taken the least amount
-                            // of times possible
-
-                            // the unconditional copy above copied some bytes
-                            // don't let it go into waste
-                            // Increment the position we are in by the number of correct bytes
-                            // currently copied
-                            let mut src_position = src_offset + offset;
-                            let mut dest_position = current_position + offset;
-
-                            // loop copying offset bytes in place
-                            // notice this loop does fixed copies but increments in offset bytes :)
-                            // that is intentional.
-                            loop {
-                                fixed_copy_within::<FASTCOPY_BYTES>(
-                                    &mut out_block,
-                                    src_position,
-                                    dest_position
-                                );
-
-                                src_position += offset;
-                                dest_position += offset;
-
-                                if dest_position > dest_offset {
-                                    break;
-                                }
-                            }
-                        } else if length > FASTCOPY_BYTES {
-                            current_position += FASTCOPY_BYTES;
-                            // fast non-overlapping copy
-                            //
-                            // We have enough space to write the ML+FAST_COPY bytes ahead
-                            // so we know this won't come to shoot us in the foot.
-                            //
-                            // An optimization is to copy FAST_COPY_BITS per invocation
-                            // Currently FASTCOPY_BYTES is 16, this fits in nicely as we
-                            // it's a single SIMD instruction on a lot of things, i.e x86,Arm and even
-                            // wasm.
-
-                            // current position of the match
-                            let mut dest_src_offset = src_offset + FASTCOPY_BYTES;
-
-                            // Number of bytes we are to copy
-                            // copy in batches of FAST_BYTES
-                            'match_lengths: loop {
-                                // Safety: We resized out_block hence we know it can handle
-                                // sloppy copies without it being out of bounds
-                                //
-                                // Reason: This is a latency critical loop, even branches start
-                                // to matter
-                                fixed_copy_within::<FASTCOPY_BYTES>(
-                                    &mut out_block,
-                                    dest_src_offset,
-                                    current_position
-                                );
-
-                                dest_src_offset += FASTCOPY_BYTES;
-                                current_position += FASTCOPY_BYTES;
-
-                                if current_position > dest_offset {
-                                    break 'match_lengths;
-                                }
-                            }
-                        }
-
                         if dest_offset > self.options.limit {
                             out_block.truncate(dest_offset);
 
@@ -1004,8 +1224,10 @@ impl<'a> DeflateDecoder<'a> {
 
                     if (entry & HUFFDEC_SUITABLE_POINTER) != 0 {
                         let extra = self.stream.peek_var_bits(((entry >> 8) & 0x3F) as usize);
+                        let pos = (entry >> 16) as usize + extra;
+                        paranoid_assert!(pos < LITLEN_ENOUGH, "litlen subtable index {pos}");
 
-                        entry = litlen_decode_table[(entry >> 16) as usize + extra];
+                        entry = litlen_decode_table[pos];
                         saved_bitbuf = self.stream.buffer;
 
                         self.stream.drop_bits((entry & 0xFF) as u8);
@@ -1014,7 +1236,17 @@ impl<'a> DeflateDecoder<'a> {
                     length = (entry >> 16) as usize;
 
                     if (entry & HUFFDEC_LITERAL) != 0 {
-                        resize_and_push(&mut out_block, dest_offset, length as u8);
+                        if dest_offset >= out_block.len() {
+                            grow_output(
+                                &mut out_block,
+                                dest_offset + 1,
+                                dest_offset,
+                                self.stream.get_position(),
+                                total_in,
+                                self.options.limit
+                            );
+                        }
+                        out_block[dest_offset] = length as u8;
 
                         dest_offset += 1;
 
@@ -1038,14 +1270,22 @@ impl<'a> DeflateDecoder<'a> {
                         self.stream.drop_bits(OFFSET_TABLEBITS as u8);
 
                         let extra = self.stream.peek_var_bits(((entry >> 8) & 0x3F) as usize);
+                        let pos = (entry >> 16) as usize + extra;
+                        paranoid_assert!(pos < OFFSET_ENOUGH, "offset subtable index {pos}");
 
-                        entry = offset_decode_table[((entry >> 16) as usize + extra) & 511];
+                        entry = offset_decode_table[pos & 511];
                     }
 
                     // ensure there is enough space for a fast copy
                     if dest_offset + length + FASTCOPY_BYTES > out_block.len() {
-                        let new_len = out_block.len() + RESIZE_BY + length;
-                        out_block.resize(new_len, 0);
+                        grow_output(
+                            &mut out_block,
+                            dest_offset + length + FASTCOPY_BYTES,
+                            dest_offset,
+                            self.stream.get_position(),
+                            total_in,
+                            self.options.limit
+                        );
                     }
                     saved_bitbuf = self.stream.buffer;
 
@@ -1117,9 +1357,191 @@ impl<'a> DeflateDecoder<'a> {
         Ok(out_block)
     }
 
+    /// Number of bits from the start of the input up to the current stream position
+    fn consumed_bits(&self) -> usize {
+        (self.position + self.stream.position + self.stream.over_read) * 8
+            - usize::from(self.stream.bits_left)
+    }
+    /// Walk blocks starting at `self.position` without writing any output
+    fn analyze_blocks(&mut self) -> Result<Vec<BlockInfo>, InflateDecodeErrors> {
+        let error = |status| Err(InflateDecodeErrors::new_with_error(status));
+
+        self.bytes_read = 0;
+        self.stream = BitStreamReader::new(&self.data[self.position..]);
+        self.stream.refill();
+
+        let mut blocks = vec![];
+        // total output so far, back references can't point before the start
+        let mut produced = 0;
+
+        loop {
+            self.stream.refill();
+
+            let start_bit = self.consumed_bits();
+
+            self.is_last_block = self.stream.get_bits(1) == 1;
+            let block_type = self.stream.get_bits(2);
+
+            let mut info = BlockInfo {
+                block_type: BlockType::Stored,
+                is_final: self.is_last_block,
+                start_bit,
+                compressed_bits: 0,
+                uncompressed_size: 0,
+                literals: 0,
+                matches: 0,
+                huffman: None
+            };
+
+            if block_type == DEFLATE_BLOCKTYPE_UNCOMPRESSED {
+                if self.stream.over_read > usize::from(self.stream.get_bits_left() >> 3) {
+                    return error(DecodeErrorStatus::Generic("over-read stream"));
+                }
+                let partial_bits = self.stream.get_bits_left() & 7;
+
+                self.stream.drop_bits(partial_bits);
+
+                let len = self.stream.get_bits(16) as u16;
+                let nlen = self.stream.get_bits(16) as u16;
+
+                if len != !nlen {
+                    return error(DecodeErrorStatus::Generic("Len and nlen do not match"));
+                }
+                let len = usize::from(len);
+                let start = self.consumed_bytes();
+
+                if self.data.get((start + len).saturating_sub(1)).is_none() {
+                    return error(DecodeErrorStatus::CorruptData);
+                }
+                self.stream.position = start + len - self.position;
+                self.stream.over_read = 0;
+                self.stream.reset();
+
+                info.uncompressed_size = len;
+                info.literals = len;
+            } else if block_type == DEFLATE_BLOCKTYPE_RESERVED {
+                return error(DecodeErrorStatus::Generic(
+                    "Reserved block type 0b11 encountered"
+                ));
+            } else {
+                let mut huffman = HuffmanInfo {
+                    litlen_lengths: vec![],
+                    offset_lengths: vec![],
+                    header_bits:    0
+                };
+                // static tables are skipped when already loaded, but we want their lengths
+                self.static_codes_loaded = false;
+
+                if let Err(status) = self.build_decode_table(block_type, Some(&mut huffman)) {
+                    return error(status);
+                }
+                if block_type == DEFLATE_BLOCKTYPE_DYNAMIC_HUFFMAN {
+                    info.block_type = BlockType::Dynamic;
+                    huffman.header_bits = self.consumed_bits() - start_bit - 3;
+                } else {
+                    info.block_type = BlockType::Static;
+                }
+                info.huffman = Some(huffman);
+
+                self.analyze_symbols(&mut info, produced)?;
+            }
+            info.compressed_bits = self.consumed_bits() - start_bit;
+            produced += info.uncompressed_size;
+
+            blocks.push(info);
+
+            if self.is_last_block {
+                break;
+            }
+        }
+        self.bytes_read = self.consumed_bytes();
+
+        Ok(blocks)
+    }
+    /// Decode the symbols of a compressed block up to its end, counting them into `info`
+    ///
+    /// This is the generic decode loop of [`start_deflate_block`](Self::start_deflate_block)
+    /// without the output, `produced` is the number of bytes preceding blocks decompress to.
+    fn analyze_symbols(
+        &mut self, info: &mut BlockInfo, produced: usize
+    ) -> Result<(), InflateDecodeErrors> {
+        let error = |status| Err(InflateDecodeErrors::new_with_error(status));
+
+        let litlen_decode_table = &self.deflate_header_tables.litlen_decode_table;
+        let offset_decode_table = &self.deflate_header_tables.offset_decode_table;
+
+        loop {
+            self.stream.refill();
+
+            if self.stream.over_read > usize::from(self.stream.bits_left >> 3) {
+                return error(DecodeErrorStatus::CorruptData);
+            }
+            let mut entry = litlen_decode_table[self.stream.peek_bits::<LITLEN_DECODE_BITS>()];
+            let mut saved_bitbuf = self.stream.buffer;
+
+            self.stream.drop_bits((entry & 0xFF) as u8);
+
+            if (entry & HUFFDEC_SUITABLE_POINTER) != 0 {
+                let extra = self.stream.peek_var_bits(((entry >> 8) & 0x3F) as usize);
+                let pos = (entry >> 16) as usize + extra;
+
+                entry = litlen_decode_table[pos.min(LITLEN_ENOUGH - 1)];
+                saved_bitbuf = self.stream.buffer;
+
+                self.stream.drop_bits((entry & 0xFF) as u8);
+            }
+
+            if (entry & HUFFDEC_LITERAL) != 0 {
+                info.literals += 1;
+                info.uncompressed_size += 1;
+                continue;
+            }
+            if (entry & HUFFDEC_END_OF_BLOCK) != 0 {
+                break;
+            }
+            let mask = (1 << entry as u8) - 1;
+            let length =
+                (entry >> 16) as usize + ((saved_bitbuf & mask) as usize >> ((entry >> 8) as u8));
+
+            self.stream.refill();
+
+            entry = offset_decode_table[self.stream.peek_bits::<OFFSET_TABLEBITS>()];
+
+            if (entry & HUFFDEC_EXCEPTIONAL) != 0 {
+                self.stream.drop_bits(OFFSET_TABLEBITS as u8);
+
+                let extra = self.stream.peek_var_bits(((entry >> 8) & 0x3F) as usize);
+                let pos = (entry >> 16) as usize + extra;
+
+                entry = offset_decode_table[pos & 511];
+            }
+            saved_bitbuf = self.stream.buffer;
+
+            let mask = (1 << (entry & 0xFF) as u8) - 1;
+            let offset =
+                (entry >> 16) as usize + ((saved_bitbuf & mask) as usize >> ((entry >> 8) as u8));
+
+            if offset > produced + info.uncompressed_size {
+                return error(DecodeErrorStatus::CorruptData);
+            }
+            self.stream.drop_bits(entry as u8);
+
+            info.matches += 1;
+            info.uncompressed_size += length;
+        }
+        if self.stream.over_read > usize::from(self.stream.bits_left >> 3) {
+            return error(DecodeErrorStatus::CorruptData);
+        }
+        Ok(())
+    }
+
     /// Build decode tables for static and dynamic
     /// huffman blocks.
-    fn build_decode_table(&mut self, block_type: u64) -> Result<(), DecodeErrorStatus> {
+    ///
+    /// If `code_lengths` is given, the codeword lengths of the block are stored there
+    fn build_decode_table(
+        &mut self, block_type: u64, code_lengths: Option<&mut HuffmanInfo>
+    ) -> Result<(), DecodeErrorStatus> {
         const COUNT: usize =
             DEFLATE_NUM_LITLEN_SYMS + DEFLATE_NUM_OFFSET_SYMS + DELFATE_MAX_LENS_OVERRUN;
 
@@ -1174,7 +1596,7 @@ impl<'a> DeflateDecoder<'a> {
                 precode_lens[usize::from(*i)] = bits;
             }
 
-            self.build_decode_table_inner(
+            build_decode_table(
                 &precode_lens,
                 &PRECODE_DECODE_RESULTS,
                 &mut precode_decode_table,
@@ -1292,8 +1714,12 @@ impl<'a> DeflateDecoder<'a> {
             num_litlen_syms = 288;
             num_offset_syms = 32;
         }
+        if let Some(info) = code_lengths {
+            info.litlen_lengths = lens[..num_litlen_syms].to_vec();
+            info.offset_lengths = lens[num_litlen_syms..num_litlen_syms + num_offset_syms].to_vec();
+        }
         // build offset decode table
-        self.build_decode_table_inner(
+        build_decode_table(
             &lens[num_litlen_syms..],
             &OFFSET_DECODE_RESULTS,
             &mut offset_decode_table,
@@ -1302,7 +1728,7 @@ impl<'a> DeflateDecoder<'a> {
             DEFLATE_MAX_OFFSET_CODEWORD_LENGTH
         )?;
 
-        self.build_decode_table_inner(
+        build_decode_table(
             &lens,
             &LITLEN_DECODE_RESULTS,
             &mut litlen_decode_table,
@@ -1316,322 +1742,47 @@ impl<'a> DeflateDecoder<'a> {
 
         Ok(())
     }
-    /// Build the decode table for the precode
-    #[allow(clippy::needless_range_loop)]
-    fn build_decode_table_inner(
-        &mut self, lens: &[u8], decode_results: &[u32], decode_table: &mut [u32],
-        table_bits: usize, num_syms: usize, mut max_codeword_len: usize
-    ) -> Result<(), DecodeErrorStatus> {
-        const BITS: u32 = usize::BITS - 1;
-
-        let mut len_counts: [u32; DEFLATE_MAX_CODEWORD_LENGTH + 1] =
-            [0; DEFLATE_MAX_CODEWORD_LENGTH + 1];
-        let mut offsets: [u32; DEFLATE_MAX_CODEWORD_LENGTH + 1] =
-            [0; DEFLATE_MAX_CODEWORD_LENGTH + 1];
-        let mut sorted_syms: [u16; DEFLATE_MAX_NUM_SYMS] = [0; DEFLATE_MAX_NUM_SYMS];
-
-        let mut i;
-
-        // count how many codewords have each length, including 0.
-        for sym in 0..num_syms {
-            len_counts[usize::from(lens[sym])] += 1;
-        }
-
-        /*
-         * Determine the actual maximum codeword length that was used, and
-         * decrease table_bits to it if allowed.
-         */
-        while max_codeword_len > 1 && len_counts[max_codeword_len] == 0 {
-            max_codeword_len -= 1;
-        }
-        /*
-         * Sort the symbols primarily by increasing codeword length and
-         *	A temporary array of length @num_syms.
-         * secondarily by increasing symbol value; or equivalently by their
-         * codewords in lexicographic order, since a canonical code is assumed.
-         *
-         * For efficiency, also compute 'codespace_used' in the same pass over
-         * 'len_counts[]' used to build 'offsets[]' for sorting.
-         */
-        offsets[0] = 0;
-        offsets[1] = len_counts[0];
-
-        let mut codespace_used = 0_u32;
-
-        for len in 1..max_codeword_len {
-            offsets[len + 1] = offsets[len] + len_counts[len];
-            codespace_used = (codespace_used << 1) + len_counts[len];
-        }
-        codespace_used = (codespace_used << 1) + len_counts[max_codeword_len];
-
-        for sym in 0..num_syms {
-            let pos = usize::from(lens[sym]);
-            sorted_syms[offsets[pos] as usize] = sym as u16;
-            offsets[pos] += 1;
-        }
-        i = (offsets[0]) as usize;
-
-        /*
-         * Check whether the lengths form a complete code (exactly fills the
-         * codespace), an incomplete code (doesn't fill the codespace), or an
-         * overfull code (overflows the codespace).  A codeword of length 'n'
-         * uses proportion '1/(2^n)' of the codespace.  An overfull code is
-         * nonsensical, so is considered invalid.  An incomplete code is
-         * considered valid only in two specific cases; see below.
-         */
-
-        // Overfull code
-        if codespace_used > 1 << max_codeword_len {
-            return Err(DecodeErrorStatus::Generic("Overflown code"));
-        }
-        // incomplete code
-        if codespace_used < 1 << max_codeword_len {
-            let entry = if codespace_used == 0 {
-                /*
-                 * An empty code is allowed.  This can happen for the
-                 * offset code in DEFLATE, since a dynamic Huffman block
-                 * need not contain any matches.
-                 */
-
-                /* sym=0, len=1 (arbitrary) */
-                make_decode_table_entry(decode_results, 0, 1)
-            } else {
-                /*
-                 * Allow codes with a single used symbol, with codeword
-                 * length 1.  The DEFLATE RFC is unclear regarding this
-                 * case.  What zlib's decompressor does is permit this
-                 * for the litlen and offset codes and assume the
-                 * codeword is '0' rather than '1'.  We do the same
-                 * except we allow this for precodes too, since there's
-                 * no convincing reason to treat the codes differently.
-                 * We also assign both codewords '0' and '1' to the
-                 * symbol to avoid having to handle '1' specially.
-                 */
-                if codespace_used != 1 << (max_codeword_len - 1) || len_counts[1] != 1 {
-                    return Err(DecodeErrorStatus::Generic(
-                        "Cannot work with empty pre-code table"
-                    ));
-                }
-                make_decode_table_entry(decode_results, usize::from(sorted_syms[i]), 1)
-            };
-            /*
-             * Note: the decode table still must be fully initialized, in
-             * case the stream is malformed and contains bits from the part
-             * of the codespace the incomplete code doesn't use.
-             */
-            decode_table.fill(entry);
-            return Ok(());
-        }
-
-        /*
-         * The lengths form a complete code.  Now, enumerate the codewords in
-         * lexicographic order and fill the decode table entries for each one.
-         *
-         * First, process all codewords with len <= table_bits.  Each one gets
-         * '2^(table_bits-len)' direct entries in the table.
-         *
-         * Since DEFLATE uses bit-reversed codewords, these entries aren't
-         * consecutive but rather are spaced '2^len' entries apart.  This makes
-         * filling them naively somewhat awkward and inefficient, since strided
-         * stores are less cache-friendly and preclude the use of word or
-         * vector-at-a-time stores to fill multiple entries per instruction.
-         *
-         * To optimize this, we incrementally double the table size.  When
-         * processing codewords with length 'len', the table is treated as
-         * having only '2^len' entries, so each codeword uses just one entry.
-         * Then, each time 'len' is incremented, the table size is doubled and
-         * the first half is copied to the second half.  This significantly
-         * improves performance over naively doing strided stores.
-         *
-         * Note that some entries copied for each table doubling may not have
-         * been initialized yet, but it doesn't matter since they're guaranteed
-         * to be initialized later (because the Huffman code is complete).
-         */
-        let mut codeword = 0;
-        let mut len = 1;
-        let mut count = len_counts[1];
-
-        while count == 0 {
-            len += 1;
-
-            if len >= len_counts.len() {
-                break;
-            }
-            count = len_counts[len];
-        }
-
-        let mut curr_table_end = 1 << len;
-
-        while len <= table_bits {
-            // Process all count codewords with length len
-            loop {
-                let entry = make_decode_table_entry(
-                    decode_results,
-                    usize::from(sorted_syms[i]),
-                    len as u32
-                );
-                i += 1;
-                // fill first entry for current codeword
-                decode_table[codeword] = entry;
-
-                if codeword == curr_table_end - 1 {
-                    // last codeword (all 1's)
-                    for _ in len..table_bits {
-                        decode_table.copy_within(0..curr_table_end, curr_table_end);
-
-                        curr_table_end <<= 1;
-                    }
-                    return Ok(());
-                }
-                /*
-                 * To advance to the lexicographically next codeword in
-                 * the canonical code, the codeword must be incremented,
-                 * then 0's must be appended to the codeword as needed
-                 * to match the next codeword's length.
-                 *
-                 * Since the codeword is bit-reversed, appending 0's is
-                 * a no-op.  However, incrementing it is nontrivial.  To
-                 * do so efficiently, use the 'bsr' instruction to find
-                 * the last (highest order) 0 bit in the codeword, set
-                 * it, and clear any later (higher order) 1 bits.  But
-                 * 'bsr' actually finds the highest order 1 bit, so to
-                 * use it first flip all bits in the codeword by XOR' ing
-                 * it with (1U << len) - 1 == cur_table_end - 1.
-                 */
-
-                let adv = BITS - (codeword ^ (curr_table_end - 1)).leading_zeros();
-                let bit = 1 << adv;
-
-                codeword &= bit - 1;
-                codeword |= bit;
-                count -= 1;
-
-                if count == 0 {
-                    break;
-                }
-            }
-            // advance to the next codeword length
-            loop {
-                len += 1;
-
-                if len <= table_bits {
-                    // dest is decode_table[curr_table_end]
-                    // source is decode_table(start of table);
-                    // size is curr_table;
-
-                    decode_table.copy_within(0..curr_table_end, curr_table_end);
-
-                    //decode_table.copy_within(range, curr_table_end);
-                    curr_table_end <<= 1;
-                }
-                count = len_counts[len];
-
-                if count != 0 {
-                    break;
-                }
-            }
-        }
-        // process codewords with len > table_bits.
-        // Require sub-tables
-        curr_table_end = 1 << table_bits;
-
-        let mut subtable_prefix = usize::MAX;
-        let mut subtable_start = 0;
-        let mut subtable_bits;
-
-        loop {
-            /*
-             * Start a new sub-table if the first 'table_bits' bits of the
-             * codeword don't match the prefix of the current subtable.
-             */
-            if codeword & ((1_usize << table_bits) - 1) != subtable_prefix {
-                subtable_prefix = codeword & ((1 << table_bits) - 1);
-                subtable_start = curr_table_end;
-
-                /*
-                 * Calculate the subtable length.  If the codeword has
-                 * length 'table_bits + n', then the subtable needs
-                 * '2^n' entries.  But it may need more; if fewer than
-                 * '2^n' codewords of length 'table_bits + n' remain,
-                 * then the length will need to be incremented to bring
-                 * in longer codewords until the subtable can be
-                 * completely filled.  Note that because the Huffman
-                 * code is complete, it will always be possible to fill
-                 * the sub-table eventually.
-                 */
-                subtable_bits = len - table_bits;
-                codespace_used = count;
-
-                while codespace_used < (1 << subtable_bits) {
-                    subtable_bits += 1;
-
-                    if subtable_bits + table_bits > 15 {
-                        return Err(DecodeErrorStatus::CorruptData);
-                    }
-
-                    codespace_used = (codespace_used << 1) + len_counts[table_bits + subtable_bits];
-                }
-
-                /*
-                 * Create the entry that points from the main table to
-                 * the subtable.
-                 */
-                decode_table[subtable_prefix] = (subtable_start as u32) << 16
-                    | HUFFDEC_EXCEPTIONAL
-                    | HUFFDEC_SUITABLE_POINTER
-                    | (subtable_bits as u32) << 8
-                    | table_bits as u32;
-
-                curr_table_end = subtable_start + (1 << subtable_bits);
-            }
-
-            /* Fill the sub-table entries for the current codeword. */
-
-            let stride = 1 << (len - table_bits);
-
-            let mut j = subtable_start + (codeword >> table_bits);
-
-            let entry = make_decode_table_entry(
-                decode_results,
-                sorted_syms[i] as usize,
-                (len - table_bits) as u32
-            );
-            i += 1;
-
-            while j < curr_table_end {
-                decode_table[j] = entry;
-                j += stride;
-            }
-            //advance to the next codeword
-            if codeword == (1 << len) - 1 {
-                // last codeword
-                return Ok(());
-            }
-
-            let adv = BITS - (codeword ^ ((1 << len) - 1)).leading_zeros();
-            let bit = 1 << adv;
-
-            codeword &= bit - 1;
-            codeword |= bit;
-            count -= 1;
-
-            while count == 0 {
-                len += 1;
-                count = len_counts[len];
-            }
-        }
-    }
 }
 
-const RESIZE_BY: usize = 1024 * 4; // 4 kb
+/// Expected compression ratio used to size the output when
+/// no size hint is given
+const INITIAL_RATIO: usize = 4;
 
-/// Resize vector if its current space wont
-/// be able to store a new byte and then push an element to that new space
-#[inline(always)]
-fn resize_and_push(buf: &mut Vec<u8>, position: usize, elm: u8) {
-    if buf.len() <= position {
-        let new_len = buf.len() + RESIZE_BY;
-        buf.resize(new_len, 0);
-    }
-    buf[position] = elm;
+/// Smallest number of bytes the output grows by
+const MIN_GROW_BY: usize = 1024 * 4; // 4 kb
+
+/// Resize `buf` so that it can hold at least `needed` bytes
+///
+/// The output still to come is estimated from the compression ratio seen so far,
+/// i.e `produced` output bytes from `consumed` of `total_in` input bytes,
+/// so that large streams only need a few reallocations.
+/// The buffer always grows by at least an eighth of its size, so a bad
+/// estimate can't lead to many small reallocations.
+///
+/// The size is capped slightly above `limit`, the caller is responsible
+/// for reporting streams that exceed it.
+#[cold]
+#[inline(never)]
+fn grow_output(
+    buf: &mut Vec<u8>, needed: usize, produced: usize, consumed: usize, total_in: usize,
+    limit: usize
+) {
+    let current = buf.len();
+
+    let remaining = produced
+        .saturating_mul(total_in.saturating_sub(consumed))
+        .checked_div(consumed)
+        .unwrap_or(0);
+    // leave a bit of room in case the ratio gets worse
+    let grow_by = remaining
+        .saturating_add(remaining / 8)
+        .max(current / 8)
+        .max(MIN_GROW_BY);
+
+    // allow writes past the limit to be caught by the limit checks
+    let cap = limit.saturating_add(FASTLOOP_MAX_BYTES_WRITTEN);
+
+    let new_len = current.saturating_add(grow_by).min(cap).max(needed);
+
+    buf.resize(new_len, 0);
 }
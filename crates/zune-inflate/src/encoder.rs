@@ -9,8 +9,12 @@ use alloc::boxed::Box;
 use alloc::vec;
 use alloc::vec::Vec;
 
-use crate::constants::{DEFLATE_BLOCKTYPE_UNCOMPRESSED, MAX_SEQ_PER_BLOCK};
+use crate::constants::{
+    DEFLATE_BLOCKTYPE_UNCOMPRESSED, DEFLATE_MAX_MATCH_LEN, DEFLATE_MIN_LENGTH, MAX_SEQ_PER_BLOCK
+};
+use crate::encoder::compress::compress_deflate;
 
+mod compress;
 mod hc_matchfinder;
 
 const _SEQ_LENGTH_SHIFT: u32 = 23;
@@ -75,38 +79,153 @@ impl EncodedSequences {
     }
 }
 
-#[derive(Debug, Copy, Clone)]
+/// Compression presets for the deflate encoder
+///
+/// Each preset maps to a set of match finder settings, see
+/// [`DeflateEncodeOptions::new`] for the exact values
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum DeflateEncodingStrategy {
-    NoCompression
+    /// Store the data without compressing it
+    NoCompression,
+    /// Greedy matching with short hash chains
+    Fast,
+    /// Lazy matching with moderate hash chains, a good balance between
+    /// speed and compression
+    Default,
+    /// Lazy matching with long hash chains, the slowest but produces the
+    /// smallest output
+    Best
 }
 
 impl DeflateEncodingStrategy {
-    #[allow(dead_code)]
+    /// Return the compression level hint stored in the zlib header
     fn to_level(self) -> u8 {
         match self {
-            Self::NoCompression => 0
+            Self::NoCompression => 0,
+            Self::Fast => 1,
+            Self::Default => 2,
+            Self::Best => 3
         }
     }
 }
 
-pub struct DeflateEncodingOptions {
-    strategy: DeflateEncodingStrategy
+/// Options that influence compression speed and ratio of the
+/// deflate encoder
+///
+/// Options are created from a [preset](DeflateEncodingStrategy) and individual
+/// knobs can then be overridden
+///
+/// # Example
+/// ```
+/// use zune_inflate::{DeflateDecoder, DeflateEncodeOptions, DeflateEncoder, DeflateEncodingStrategy};
+///
+/// let data = b"a deflate stream, a deflate stream, a deflate stream".repeat(10);
+///
+/// let options = DeflateEncodeOptions::new(DeflateEncodingStrategy::Default)
+///     .set_max_chain_length(512);
+///
+/// let compressed = DeflateEncoder::new_with_options(&data, options).encode_zlib();
+/// assert!(compressed.len() < data.len());
+///
+/// let decoded = DeflateDecoder::new(&compressed).decode_zlib().unwrap();
+/// assert_eq!(decoded, data);
+/// ```
+#[derive(Debug, Copy, Clone)]
+pub struct DeflateEncodeOptions {
+    strategy:         DeflateEncodingStrategy,
+    lazy_matching:    bool,
+    max_chain_length: usize,
+    nice_length:      usize
 }
 
-impl Default for DeflateEncodingOptions {
+impl Default for DeflateEncodeOptions {
     fn default() -> Self {
-        DeflateEncodingOptions {
-            strategy: DeflateEncodingStrategy::NoCompression
+        DeflateEncodeOptions::new(DeflateEncodingStrategy::Default)
+    }
+}
+
+impl DeflateEncodeOptions {
+    /// Create options from a compression preset
+    ///
+    /// The presets map to the following match finder settings
+    ///
+    /// | Preset          | Lazy matching | Max chain length | Nice length |
+    /// |-----------------|---------------|------------------|-------------|
+    /// | `NoCompression` | N/A           | N/A              | N/A         |
+    /// | `Fast`          | No            | 8                | 32          |
+    /// | `Default`       | Yes           | 128              | 128         |
+    /// | `Best`          | Yes           | 4096             | 258         |
+    pub const fn new(strategy: DeflateEncodingStrategy) -> DeflateEncodeOptions {
+        let (lazy_matching, max_chain_length, nice_length) = match strategy {
+            DeflateEncodingStrategy::NoCompression => (false, 0, 0),
+            DeflateEncodingStrategy::Fast => (false, 8, 32),
+            DeflateEncodingStrategy::Default => (true, 128, 128),
+            DeflateEncodingStrategy::Best => (true, 4096, DEFLATE_MAX_MATCH_LEN)
+        };
+        DeflateEncodeOptions {
+            strategy,
+            lazy_matching,
+            max_chain_length,
+            nice_length
         }
     }
+    /// Get the preset these options were created from
+    pub const fn get_strategy(&self) -> DeflateEncodingStrategy {
+        self.strategy
+    }
+    /// Get whether lazy matching is enabled
+    pub const fn get_lazy_matching(&self) -> bool {
+        self.lazy_matching
+    }
+    /// Set whether the match finder uses lazy matching
+    ///
+    /// With lazy matching, a match is only emitted if the next position
+    /// does not contain a longer match, this improves compression at the cost
+    /// of speed.
+    pub const fn set_lazy_matching(mut self, yes: bool) -> Self {
+        self.lazy_matching = yes;
+        self
+    }
+    /// Get the maximum number of previous positions searched for a match
+    pub const fn get_max_chain_length(&self) -> usize {
+        self.max_chain_length
+    }
+    /// Set the maximum number of previous positions searched for a match
+    ///
+    /// Longer chains find better matches but take more time, a value of zero
+    /// disables matching
+    pub const fn set_max_chain_length(mut self, length: usize) -> Self {
+        self.max_chain_length = length;
+        self
+    }
+    /// Get the match length at which the match finder stops searching
+    pub const fn get_nice_length(&self) -> usize {
+        self.nice_length
+    }
+    /// Set the match length at which the match finder stops searching for
+    /// a longer match
+    ///
+    /// The value is clamped between 3 and 258, the minimum and maximum
+    /// match lengths in deflate
+    pub const fn set_nice_length(mut self, length: usize) -> Self {
+        self.nice_length = if length < DEFLATE_MIN_LENGTH {
+            DEFLATE_MIN_LENGTH
+        } else if length > DEFLATE_MAX_MATCH_LEN {
+            DEFLATE_MAX_MATCH_LEN
+        } else {
+            length
+        };
+        self
+    }
 }
 
 /// A simple Deflate Encoder.
 ///
-/// Not yet complete
+/// Compressed blocks currently use the fixed huffman codes, the amount of
+/// effort spent finding matches is configured via [`DeflateEncodeOptions`]
 pub struct DeflateEncoder<'a> {
     data:            &'a [u8],
-    options:         DeflateEncodingOptions,
+    options:         DeflateEncodeOptions,
     output_position: usize,
     input_position:  usize,
     output:          Vec<u8>
@@ -115,11 +234,12 @@ pub struct DeflateEncoder<'a> {
 impl<'a> DeflateEncoder<'a> {
     /// Create a new deflate encoder.
     ///
-    /// The
+    /// The encoder uses the [`Default`](DeflateEncodingStrategy::Default) preset
     pub fn new(data: &'a [u8]) -> DeflateEncoder<'a> {
-        DeflateEncoder::new_with_options(data, DeflateEncodingOptions::default())
+        DeflateEncoder::new_with_options(data, DeflateEncodeOptions::default())
     }
-    pub fn new_with_options(data: &'a [u8], options: DeflateEncodingOptions) -> DeflateEncoder<'a> {
+    /// Create a new deflate encoder with the specified options
+    pub fn new_with_options(data: &'a [u8], options: DeflateEncodeOptions) -> DeflateEncoder<'a> {
         let length = data.len() + 1024;
         let out_array = vec![0; length];
 
@@ -216,6 +336,18 @@ impl<'a> DeflateEncoder<'a> {
             DeflateEncodingStrategy::NoCompression => {
                 self.encode_no_compression(self.data.len());
             }
+            _ => {
+                let compressed = compress_deflate(self.data, &self.options);
+
+                let end = self.output_position + compressed.len();
+
+                if self.output.len() < end {
+                    self.output.resize(end, 0);
+                }
+                self.output[self.output_position..end].copy_from_slice(&compressed);
+                self.output_position = end;
+                self.input_position = self.data.len();
+            }
         }
     }
 
@@ -229,7 +361,7 @@ impl<'a> DeflateEncoder<'a> {
         self.encode_deflate();
 
         // add adler hash
-        let hash = crate::utils::calc_adler_hash(self.data);
+        let hash = zune_core::checksum::adler32::adler32(self.data);
         self.output[self.output_position..self.output_position + 4]
             .copy_from_slice(&hash.to_be_bytes());
         self.output_position += 4;
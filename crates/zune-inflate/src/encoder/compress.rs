@@ -0,0 +1,567 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! A hash chain LZ77 compressor emitting fixed huffman blocks
+//!
+//! # Algorithm
+//! The input is walked byte by byte, the hash of the next three bytes is used to
+//! look up previous positions with the same hash, up to `max_chain_length`
+//! candidates are compared and the longest match within the 32 KB window is taken,
+//! stopping early once a match of `nice_length` is found.
+//!
+//! With lazy matching, before committing to a match at position `p` the matcher
+//! also checks `p+1`, and if that produces a longer match, emits a literal
+//! at `p` and uses the later match instead.
+//!
+//! The resulting symbols are encoded in blocks using the fixed huffman codes
+//! from RFC 1951 section 3.2.6, falling back to stored blocks whenever that
+//! would be smaller.
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::constants::{
+    DEFLATE_BLOCKTYPE_STATIC, DEFLATE_BLOCKTYPE_UNCOMPRESSED, DEFLATE_MAX_BLOCK_SIZE,
+    DEFLATE_MAX_MATCH_LEN, DEFLATE_MIN_LENGTH
+};
+use crate::encoder::DeflateEncodeOptions;
+
+const HASH_LOG: usize = 15;
+const HASH_SIZE: usize = 1 << HASH_LOG;
+const WINDOW_MASK: usize = DEFLATE_MAX_BLOCK_SIZE - 1;
+/// Marker for an empty hash chain entry
+const NO_POS: u32 = u32::MAX;
+/// Number of input bytes covered by a single block
+const BLOCK_INPUT_SIZE: usize = 1 << 16;
+/// End of block symbol
+const END_OF_BLOCK: u16 = 256;
+
+#[rustfmt::skip]
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31,
+    35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258
+];
+#[rustfmt::skip]
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2,
+    3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0
+];
+#[rustfmt::skip]
+const OFFSET_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193,
+    257, 385, 513, 769, 1025, 1537, 2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577
+];
+#[rustfmt::skip]
+const OFFSET_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6,
+    7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13
+];
+
+/// A literal or a match found by the matcher
+#[derive(Copy, Clone)]
+enum Symbol {
+    Literal(u8),
+    Match { length: u16, offset: u16 }
+}
+
+/// A least significant bit first bit writer
+struct BitWriter {
+    out:       Vec<u8>,
+    buffer:    u64,
+    bits_left: u8
+}
+
+impl BitWriter {
+    fn new(capacity: usize) -> BitWriter {
+        BitWriter {
+            out:       Vec::with_capacity(capacity),
+            buffer:    0,
+            bits_left: 0
+        }
+    }
+    /// Write the lower `num_bits` of `value`, `num_bits` must be at most 32
+    #[inline(always)]
+    fn put_bits(&mut self, value: u32, num_bits: u8) {
+        debug_assert!(num_bits <= 32);
+
+        self.buffer |= u64::from(value) << self.bits_left;
+        self.bits_left += num_bits;
+
+        while self.bits_left >= 8 {
+            self.out.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.bits_left -= 8;
+        }
+    }
+    /// Pad the stream with zero bits to the next byte boundary
+    fn align(&mut self) {
+        if self.bits_left > 0 {
+            self.out.push(self.buffer as u8);
+            self.buffer = 0;
+            self.bits_left = 0;
+        }
+    }
+    fn write_bytes(&mut self, bytes: &[u8]) {
+        debug_assert_eq!(self.bits_left, 0);
+        self.out.extend_from_slice(bytes);
+    }
+    fn finish(mut self) -> Vec<u8> {
+        self.align();
+        self.out
+    }
+}
+
+/// Reverse the lower `len` bits of a huffman code, since huffman codes
+/// are packed starting from the most significant bit
+const fn reverse_bits(code: u32, len: u8) -> u32 {
+    code.reverse_bits() >> (32 - len)
+}
+
+/// Return the bit-reversed fixed huffman code and its length for a litlen symbol
+#[inline(always)]
+const fn fixed_litlen_code(symbol: u16) -> (u32, u8) {
+    let symbol = symbol as u32;
+
+    match symbol {
+        0..=143 => (reverse_bits(0x30 + symbol, 8), 8),
+        144..=255 => (reverse_bits(0x190 + symbol - 144, 9), 9),
+        256..=279 => (reverse_bits(symbol - 256, 7), 7),
+        _ => (reverse_bits(0xC0 + symbol - 280, 8), 8)
+    }
+}
+
+fn length_slot(length: u16) -> usize {
+    // the last slot is for 258, which would otherwise fall into slot 27
+    if length == 258 {
+        return 28;
+    }
+    LENGTH_BASE.partition_point(|base| *base <= length) - 1
+}
+
+fn offset_slot(offset: u16) -> usize {
+    OFFSET_BASE.partition_point(|base| *base <= offset) - 1
+}
+
+/// Size in bits needed to encode `symbols` using the fixed huffman codes
+fn fixed_block_cost(symbols: &[Symbol]) -> usize {
+    let mut bits = 3 + usize::from(fixed_litlen_code(END_OF_BLOCK).1);
+
+    for symbol in symbols {
+        bits += match *symbol {
+            Symbol::Literal(byte) => usize::from(fixed_litlen_code(u16::from(byte)).1),
+            Symbol::Match { length, offset } => {
+                let l_slot = length_slot(length);
+                let o_slot = offset_slot(offset);
+
+                usize::from(fixed_litlen_code(257 + l_slot as u16).1)
+                    + usize::from(LENGTH_EXTRA[l_slot])
+                    + 5
+                    + usize::from(OFFSET_EXTRA[o_slot])
+            }
+        };
+    }
+    bits
+}
+
+fn write_fixed_block(writer: &mut BitWriter, symbols: &[Symbol], is_final: bool) {
+    writer.put_bits(
+        u32::from(is_final) | (DEFLATE_BLOCKTYPE_STATIC << 1) as u32,
+        3
+    );
+
+    for symbol in symbols {
+        match *symbol {
+            Symbol::Literal(byte) => {
+                let (code, len) = fixed_litlen_code(u16::from(byte));
+                writer.put_bits(code, len);
+            }
+            Symbol::Match { length, offset } => {
+                let l_slot = length_slot(length);
+                let (code, len) = fixed_litlen_code(257 + l_slot as u16);
+                writer.put_bits(code, len);
+                writer.put_bits(
+                    u32::from(length - LENGTH_BASE[l_slot]),
+                    LENGTH_EXTRA[l_slot]
+                );
+
+                let o_slot = offset_slot(offset);
+                writer.put_bits(reverse_bits(o_slot as u32, 5), 5);
+                writer.put_bits(
+                    u32::from(offset - OFFSET_BASE[o_slot]),
+                    OFFSET_EXTRA[o_slot]
+                );
+            }
+        }
+    }
+    let (code, len) = fixed_litlen_code(END_OF_BLOCK);
+    writer.put_bits(code, len);
+}
+
+fn write_stored_block(writer: &mut BitWriter, data: &[u8], is_final: bool) {
+    // stored blocks can hold at most u16::MAX bytes
+    let mut chunks = data.chunks(usize::from(u16::MAX)).peekable();
+
+    while let Some(chunk) = chunks.next() {
+        let bfinal = is_final && chunks.peek().is_none();
+
+        writer.put_bits(
+            u32::from(bfinal) | (DEFLATE_BLOCKTYPE_UNCOMPRESSED << 1) as u32,
+            3
+        );
+        writer.align();
+
+        let len = chunk.len() as u16;
+        writer.write_bytes(&len.to_le_bytes());
+        writer.write_bytes(&(!len).to_le_bytes());
+        writer.write_bytes(chunk);
+    }
+}
+
+/// Return the number of leading bytes that are equal in `a` and `b`
+#[inline(always)]
+fn match_length(a: &[u8], b: &[u8]) -> usize {
+    let mut length = 0;
+
+    // compare eight bytes at a time
+    for (x, y) in a.chunks_exact(8).zip(b.chunks_exact(8)) {
+        let x = u64::from_le_bytes(x.try_into().unwrap());
+        let y = u64::from_le_bytes(y.try_into().unwrap());
+        let diff = x ^ y;
+
+        if diff != 0 {
+            return length + (diff.trailing_zeros() / 8) as usize;
+        }
+        length += 8;
+    }
+    length
+        + a[length..]
+            .iter()
+            .zip(&b[length..])
+            .take_while(|(x, y)| x == y)
+            .count()
+}
+
+/// Hash chain match finder
+struct MatchFinder {
+    head: Vec<u32>,
+    prev: Vec<u32>
+}
+
+impl MatchFinder {
+    fn new() -> MatchFinder {
+        MatchFinder {
+            head: vec![NO_POS; HASH_SIZE],
+            prev: vec![NO_POS; DEFLATE_MAX_BLOCK_SIZE]
+        }
+    }
+
+    #[inline(always)]
+    fn hash(bytes: &[u8]) -> usize {
+        let value = u32::from(bytes[0]) | u32::from(bytes[1]) << 8 | u32::from(bytes[2]) << 16;
+
+        (value.wrapping_mul(0x9E37_79B1) >> (32 - HASH_LOG)) as usize
+    }
+
+    /// Add position `pos` to the hash chains
+    #[inline(always)]
+    fn insert(&mut self, src: &[u8], pos: usize) {
+        if pos + DEFLATE_MIN_LENGTH > src.len() {
+            return;
+        }
+        let hash = Self::hash(&src[pos..]);
+
+        self.prev[pos & WINDOW_MASK] = self.head[hash];
+        self.head[hash] = pos as u32;
+    }
+
+    /// Add all positions from `inserted` up to (but not including) `end` to the
+    /// hash chains
+    #[inline(always)]
+    fn insert_until(&mut self, src: &[u8], inserted: &mut usize, end: usize) {
+        while *inserted < end {
+            self.insert(src, *inserted);
+            *inserted += 1;
+        }
+    }
+
+    /// Find the longest match for `pos`, returning `(length, offset)`
+    ///
+    /// `pos` must have been inserted into the hash chains beforehand
+    fn longest_match(
+        &self, src: &[u8], pos: usize, options: &DeflateEncodeOptions
+    ) -> (usize, usize) {
+        let max_length = (src.len() - pos).min(DEFLATE_MAX_MATCH_LEN);
+
+        if max_length < DEFLATE_MIN_LENGTH {
+            return (0, 0);
+        }
+        let nice_length = options.get_nice_length().min(max_length);
+
+        let mut best_length = DEFLATE_MIN_LENGTH - 1;
+        let mut best_offset = 0;
+        // the current position is at the head of the chain, skip it
+        let mut candidate = self.prev[pos & WINDOW_MASK];
+        let mut chain = options.get_max_chain_length();
+
+        let current = &src[pos..pos + max_length];
+
+        while candidate != NO_POS && chain > 0 {
+            let candidate_pos = candidate as usize;
+            let offset = pos - candidate_pos;
+
+            if offset > DEFLATE_MAX_BLOCK_SIZE || offset == 0 {
+                break;
+            }
+            let previous = &src[candidate_pos..candidate_pos + max_length];
+
+            // quick reject, the byte that would make this match longer must match
+            if previous[best_length] == current[best_length] {
+                let length = match_length(previous, current);
+
+                if length > best_length {
+                    best_length = length;
+                    best_offset = offset;
+
+                    if length >= nice_length {
+                        break;
+                    }
+                }
+            }
+            let next = self.prev[candidate_pos & WINDOW_MASK];
+            // positions only ever decrease along a chain, an increase means
+            // the slot was overwritten by a newer position
+            if next != NO_POS && next as usize >= candidate_pos {
+                break;
+            }
+            candidate = next;
+            chain -= 1;
+        }
+        if best_length < DEFLATE_MIN_LENGTH {
+            return (0, 0);
+        }
+        (best_length, best_offset)
+    }
+}
+
+/// Compress `src` into a raw deflate stream
+pub(crate) fn compress_deflate(src: &[u8], options: &DeflateEncodeOptions) -> Vec<u8> {
+    let mut writer = BitWriter::new(src.len() / 2 + 64);
+
+    if src.is_empty() {
+        write_fixed_block(&mut writer, &[], true);
+        return writer.finish();
+    }
+    let mut finder = MatchFinder::new();
+    let mut symbols = Vec::with_capacity(BLOCK_INPUT_SIZE);
+
+    let mut pos = 0;
+    let mut block_start = 0;
+    // positions below this have been added to the hash chains
+    let mut inserted = 0;
+    // a match found by the lazy evaluation at the previous position
+    let mut pending: Option<(usize, usize)> = None;
+
+    while pos < src.len() {
+        let (length, offset) = match pending.take() {
+            Some(found) => found,
+            None => {
+                finder.insert_until(src, &mut inserted, pos + 1);
+                finder.longest_match(src, pos, options)
+            }
+        };
+
+        if length == 0 {
+            symbols.push(Symbol::Literal(src[pos]));
+            pos += 1;
+        } else {
+            if options.get_lazy_matching()
+                && length < options.get_nice_length()
+                && pos + 1 < src.len()
+            {
+                finder.insert_until(src, &mut inserted, pos + 2);
+                let next = finder.longest_match(src, pos + 1, options);
+
+                if next.0 > length {
+                    // a better match starts at the next byte
+                    pending = Some(next);
+                }
+            }
+            if pending.is_some() {
+                symbols.push(Symbol::Literal(src[pos]));
+                pos += 1;
+            } else {
+                symbols.push(Symbol::Match {
+                    length: length as u16,
+                    offset: offset as u16
+                });
+                pos += length;
+                finder.insert_until(src, &mut inserted, pos);
+            }
+        }
+
+        if pos - block_start >= BLOCK_INPUT_SIZE || pos >= src.len() {
+            let is_final = pos >= src.len();
+            let block = &src[block_start..pos];
+
+            // stored blocks need 5 bytes of header per 64 KB, plus alignment
+            let stored_cost = (block.len() + 5 * (block.len() / 0xFFFF + 1)) * 8 + 10;
+
+            if fixed_block_cost(&symbols) < stored_cost {
+                write_fixed_block(&mut writer, &symbols, is_final);
+            } else {
+                write_stored_block(&mut writer, block, is_final);
+            }
+            symbols.clear();
+            block_start = pos;
+        }
+    }
+    writer.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::{compress_deflate, length_slot, offset_slot};
+    use crate::{DeflateDecoder, DeflateEncodeOptions, DeflateEncodingStrategy};
+
+    const STRATEGIES: [DeflateEncodingStrategy; 3] = [
+        DeflateEncodingStrategy::Fast,
+        DeflateEncodingStrategy::Default,
+        DeflateEncodingStrategy::Best
+    ];
+
+    /// Compress `data` and check it decodes back, returning the compressed stream
+    fn round_trip(data: &[u8], options: DeflateEncodeOptions) -> Vec<u8> {
+        let compressed = compress_deflate(data, &options);
+        let decoded = DeflateDecoder::new(&compressed).decode_deflate().unwrap();
+
+        assert_eq!(decoded, data, "{options:?}");
+        compressed
+    }
+
+    /// Pseudo random bytes that do not compress
+    fn noise(length: usize) -> Vec<u8> {
+        let mut state = 0x2545_F491_u32;
+
+        (0..length)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state as u8
+            })
+            .collect()
+    }
+
+    fn text(length: usize) -> Vec<u8> {
+        b"the quick brown fox jumps over the lazy dog, "
+            .iter()
+            .cycle()
+            .zip(noise(length))
+            // sprinkle some differences so matches have varying lengths
+            .map(|(a, b)| if b < 8 { b'!' } else { *a })
+            .collect()
+    }
+
+    #[test]
+    fn empty_input() {
+        for strategy in STRATEGIES {
+            round_trip(&[], DeflateEncodeOptions::new(strategy));
+        }
+    }
+
+    #[test]
+    fn short_inputs() {
+        let data = text(300);
+
+        for strategy in STRATEGIES {
+            for length in 0..20 {
+                round_trip(&data[..length], DeflateEncodeOptions::new(strategy));
+            }
+        }
+    }
+
+    #[test]
+    fn repetitive_input_compresses() {
+        let data = text(100_000);
+
+        for strategy in STRATEGIES {
+            let compressed = round_trip(&data, DeflateEncodeOptions::new(strategy));
+            assert!(compressed.len() < data.len() / 4, "{strategy:?}");
+        }
+    }
+
+    #[test]
+    fn long_runs() {
+        // runs longer than the maximum match length and the window
+        let mut data = vec![0_u8; 70_000];
+        data.extend_from_slice(&[1; 300]);
+        data.extend_from_slice(&noise(1000));
+        data.extend_from_slice(&[0; 40_000]);
+
+        for strategy in STRATEGIES {
+            round_trip(&data, DeflateEncodeOptions::new(strategy));
+        }
+    }
+
+    #[test]
+    fn incompressible_input_is_stored() {
+        let data = noise(200_000);
+
+        for strategy in STRATEGIES {
+            let compressed = round_trip(&data, DeflateEncodeOptions::new(strategy));
+            // stored blocks only add a few bytes of headers
+            assert!(compressed.len() <= data.len() + 64, "{strategy:?}");
+        }
+    }
+
+    #[test]
+    fn stronger_presets_are_not_larger() {
+        let data = text(200_000);
+
+        let sizes: Vec<usize> = STRATEGIES
+            .iter()
+            .map(|strategy| round_trip(&data, DeflateEncodeOptions::new(*strategy)).len())
+            .collect();
+
+        assert!(sizes[0] >= sizes[1] && sizes[1] >= sizes[2], "{sizes:?}");
+    }
+
+    #[test]
+    fn overridden_knobs() {
+        let data = text(50_000);
+
+        let no_matches =
+            DeflateEncodeOptions::new(DeflateEncodingStrategy::Default).set_max_chain_length(0);
+        let greedy = DeflateEncodeOptions::new(DeflateEncodingStrategy::Best)
+            .set_lazy_matching(false)
+            .set_nice_length(0);
+
+        let literals = round_trip(&data, no_matches);
+        let matched = round_trip(&data, greedy);
+
+        assert!(matched.len() < literals.len());
+        assert_eq!(greedy.get_nice_length(), 3);
+        assert_eq!(greedy.set_nice_length(1000).get_nice_length(), 258);
+    }
+
+    #[test]
+    fn symbol_slots() {
+        assert_eq!(length_slot(3), 0);
+        assert_eq!(length_slot(10), 7);
+        assert_eq!(length_slot(11), 8);
+        assert_eq!(length_slot(257), 27);
+        assert_eq!(length_slot(258), 28);
+
+        assert_eq!(offset_slot(1), 0);
+        assert_eq!(offset_slot(4), 3);
+        assert_eq!(offset_slot(5), 4);
+        assert_eq!(offset_slot(24577), 29);
+        assert_eq!(offset_slot(32768), 29);
+    }
+}
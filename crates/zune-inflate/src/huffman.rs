@@ -0,0 +1,402 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! Huffman decode table construction
+//!
+//! This exposes the table builder used by the deflate decoder so that
+//! other formats using canonical, LSB-first huffman codes can reuse it.
+//!
+//! A decode table is indexed by the next `table_bits` bits of the stream
+//! (as returned by [`BitStreamReader::peek_bits`](crate::bitstream::BitStreamReader::peek_bits)),
+//! and each entry is a `u32` laid out as
+//!
+//! ```text
+//! 31-16: Symbol specific data, copied from `decode_results`
+//!        or the sub-table start for pointer entries
+//! 15   : HUFFDEC_EXCEPTIONAL, set for sub-table pointers
+//! 14   : HUFFDEC_SUITABLE_POINTER, set for sub-table pointers
+//! 11-8 : Codeword length, or number of sub-table bits for pointer entries
+//! 7-0  : Number of bits to consume
+//! ```
+//!
+//! For codewords longer than `table_bits`, the main table entry is a pointer
+//! to a sub-table, which is indexed by the remaining bits of the codeword.
+//!
+//! The caller chooses what is stored in each entry via `decode_results`,
+//! bits 11-0 of each `decode_results` entry must be zero since the builder
+//! adds the codeword length there, and bits 15-14 are reserved for sub-table pointers.
+//! The remaining bits can be used freely, e.g. the deflate decoder uses bit 13
+//! to mark the end of block symbol.
+use crate::constants::{DEFLATE_MAX_CODEWORD_LENGTH, DEFLATE_MAX_NUM_SYMS};
+pub use crate::constants::{HUFFDEC_EXCEPTIONAL, HUFFDEC_SUITABLE_POINTER};
+use crate::errors::DecodeErrorStatus;
+
+/// Maximum codeword length supported by the table builder
+pub const MAX_CODEWORD_LENGTH: usize = DEFLATE_MAX_CODEWORD_LENGTH;
+
+/// Maximum number of symbols supported by the table builder
+pub const MAX_NUM_SYMS: usize = DEFLATE_MAX_NUM_SYMS;
+
+/// Create a decode table entry for the given symbol
+///
+/// This combines the static part `decode_results[sym]` with the dynamic part
+/// `len`, which is the remaining codeword length (the codeword length for main
+/// table entries, or the codeword length minus `table_bits` for sub-table entries).
+///
+/// In all cases, `len` is added to each of the two low-order bytes to create the
+/// decode table entry.
+#[inline(always)]
+pub fn make_decode_table_entry(decode_results: &[u32], sym: usize, len: u32) -> u32 {
+    decode_results[sym] + (len << 8) + len
+}
+
+/// Build a decode table for a canonical huffman code
+///
+/// # Arguments
+/// - `lens`: Codeword length for each symbol, a length of zero means the symbol
+///   is unused.
+/// - `decode_results`: Static part of the entry for each symbol, this is placed in the
+///   upper bits of the table entry, see the [module documentation](self) for the layout.
+/// - `decode_table`: The table to fill, it must be large enough to hold the main table
+///   (`1 << table_bits` entries) plus any sub-tables needed.
+/// - `table_bits`: Number of bits used to index the main table.
+/// - `num_syms`: Number of symbols in the alphabet, at most [`MAX_NUM_SYMS`].
+/// - `max_codeword_len`: Maximum codeword length allowed by the format,
+///   at most [`MAX_CODEWORD_LENGTH`].
+///
+/// # Returns
+/// - `Ok(())`: The table was successfully built
+/// - `Err`: The codeword lengths do not describe a valid huffman code,
+///   or the arguments are out of range
+///
+/// # Panics
+/// If `lens` or `decode_results` have less than `num_syms` entries, or `decode_table`
+/// is too small to hold the main table and sub-tables.
+///
+/// # Example
+/// ```
+/// use zune_inflate::huffman::build_decode_table;
+/// // symbol 0 -> `0`, symbol 1 -> `10`, symbol 2 -> `11`
+/// let lens = [1, 2, 2];
+/// let decode_results = [0 << 16, 1 << 16, 2 << 16];
+/// let mut table = [0_u32; 1 << 2];
+///
+/// build_decode_table(&lens, &decode_results, &mut table, 2, 3, 2).unwrap();
+/// // codewords are bit reversed, so `01` in the stream is symbol 1
+/// assert_eq!(table[0b01] >> 16, 1);
+/// assert_eq!(table[0b01] & 0xFF, 2);
+/// ```
+#[allow(clippy::needless_range_loop)]
+pub fn build_decode_table(
+    lens: &[u8], decode_results: &[u32], decode_table: &mut [u32], table_bits: usize,
+    num_syms: usize, mut max_codeword_len: usize
+) -> Result<(), DecodeErrorStatus> {
+    const BITS: u32 = usize::BITS - 1;
+
+    if num_syms > MAX_NUM_SYMS {
+        return Err(DecodeErrorStatus::Generic(
+            "Too many symbols for huffman table"
+        ));
+    }
+    if max_codeword_len > MAX_CODEWORD_LENGTH || max_codeword_len == 0 {
+        return Err(DecodeErrorStatus::Generic(
+            "Invalid maximum codeword length for huffman table"
+        ));
+    }
+
+    let mut len_counts: [u32; MAX_CODEWORD_LENGTH + 1] = [0; MAX_CODEWORD_LENGTH + 1];
+    let mut offsets: [u32; MAX_CODEWORD_LENGTH + 1] = [0; MAX_CODEWORD_LENGTH + 1];
+    let mut sorted_syms: [u16; MAX_NUM_SYMS] = [0; MAX_NUM_SYMS];
+
+    let mut i;
+
+    // count how many codewords have each length, including 0.
+    for sym in 0..num_syms {
+        len_counts[usize::from(lens[sym])] += 1;
+    }
+
+    /*
+     * Determine the actual maximum codeword length that was used, and
+     * decrease table_bits to it if allowed.
+     */
+    while max_codeword_len > 1 && len_counts[max_codeword_len] == 0 {
+        max_codeword_len -= 1;
+    }
+    /*
+     * Sort the symbols primarily by increasing codeword length and
+     * secondarily by increasing symbol value; or equivalently by their
+     * codewords in lexicographic order, since a canonical code is assumed.
+     *
+     * For efficiency, also compute 'codespace_used' in the same pass over
+     * 'len_counts[]' used to build 'offsets[]' for sorting.
+     */
+    offsets[0] = 0;
+    offsets[1] = len_counts[0];
+
+    let mut codespace_used = 0_u32;
+
+    for len in 1..max_codeword_len {
+        offsets[len + 1] = offsets[len] + len_counts[len];
+        codespace_used = (codespace_used << 1) + len_counts[len];
+    }
+    codespace_used = (codespace_used << 1) + len_counts[max_codeword_len];
+
+    for sym in 0..num_syms {
+        let pos = usize::from(lens[sym]);
+        sorted_syms[offsets[pos] as usize] = sym as u16;
+        offsets[pos] += 1;
+    }
+    i = (offsets[0]) as usize;
+
+    /*
+     * Check whether the lengths form a complete code (exactly fills the
+     * codespace), an incomplete code (doesn't fill the codespace), or an
+     * overfull code (overflows the codespace).  A codeword of length 'n'
+     * uses proportion '1/(2^n)' of the codespace.  An overfull code is
+     * nonsensical, so is considered invalid.  An incomplete code is
+     * considered valid only in two specific cases; see below.
+     */
+
+    // Overfull code
+    if codespace_used > 1 << max_codeword_len {
+        return Err(DecodeErrorStatus::Generic("Overflown code"));
+    }
+    // incomplete code
+    if codespace_used < 1 << max_codeword_len {
+        let entry = if codespace_used == 0 {
+            /*
+             * An empty code is allowed.  This can happen for the
+             * offset code in DEFLATE, since a dynamic Huffman block
+             * need not contain any matches.
+             */
+
+            /* sym=0, len=1 (arbitrary) */
+            make_decode_table_entry(decode_results, 0, 1)
+        } else {
+            /*
+             * Allow codes with a single used symbol, with codeword
+             * length 1.  The DEFLATE RFC is unclear regarding this
+             * case.  What zlib's decompressor does is permit this
+             * for the litlen and offset codes and assume the
+             * codeword is '0' rather than '1'.  We do the same
+             * except we allow this for precodes too, since there's
+             * no convincing reason to treat the codes differently.
+             * We also assign both codewords '0' and '1' to the
+             * symbol to avoid having to handle '1' specially.
+             */
+            if codespace_used != 1 << (max_codeword_len - 1) || len_counts[1] != 1 {
+                return Err(DecodeErrorStatus::Generic(
+                    "Cannot work with empty pre-code table"
+                ));
+            }
+            make_decode_table_entry(decode_results, usize::from(sorted_syms[i]), 1)
+        };
+        /*
+         * Note: the decode table still must be fully initialized, in
+         * case the stream is malformed and contains bits from the part
+         * of the codespace the incomplete code doesn't use.
+         */
+        decode_table.fill(entry);
+        return Ok(());
+    }
+
+    /*
+     * The lengths form a complete code.  Now, enumerate the codewords in
+     * lexicographic order and fill the decode table entries for each one.
+     *
+     * First, process all codewords with len <= table_bits.  Each one gets
+     * '2^(table_bits-len)' direct entries in the table.
+     *
+     * Since DEFLATE uses bit-reversed codewords, these entries aren't
+     * consecutive but rather are spaced '2^len' entries apart.  This makes
+     * filling them naively somewhat awkward and inefficient, since strided
+     * stores are less cache-friendly and preclude the use of word or
+     * vector-at-a-time stores to fill multiple entries per instruction.
+     *
+     * To optimize this, we incrementally double the table size.  When
+     * processing codewords with length 'len', the table is treated as
+     * having only '2^len' entries, so each codeword uses just one entry.
+     * Then, each time 'len' is incremented, the table size is doubled and
+     * the first half is copied to the second half.  This significantly
+     * improves performance over naively doing strided stores.
+     *
+     * Note that some entries copied for each table doubling may not have
+     * been initialized yet, but it doesn't matter since they're guaranteed
+     * to be initialized later (because the Huffman code is complete).
+     */
+    let mut codeword = 0;
+    let mut len = 1;
+    let mut count = len_counts[1];
+
+    while count == 0 {
+        len += 1;
+
+        if len >= len_counts.len() {
+            break;
+        }
+        count = len_counts[len];
+    }
+
+    let mut curr_table_end = 1 << len;
+
+    while len <= table_bits {
+        // Process all count codewords with length len
+        loop {
+            let entry =
+                make_decode_table_entry(decode_results, usize::from(sorted_syms[i]), len as u32);
+            i += 1;
+            // fill first entry for current codeword
+            decode_table[codeword] = entry;
+
+            if codeword == curr_table_end - 1 {
+                // last codeword (all 1's)
+                for _ in len..table_bits {
+                    decode_table.copy_within(0..curr_table_end, curr_table_end);
+
+                    curr_table_end <<= 1;
+                }
+                return Ok(());
+            }
+            /*
+             * To advance to the lexicographically next codeword in
+             * the canonical code, the codeword must be incremented,
+             * then 0's must be appended to the codeword as needed
+             * to match the next codeword's length.
+             *
+             * Since the codeword is bit-reversed, appending 0's is
+             * a no-op.  However, incrementing it is nontrivial.  To
+             * do so efficiently, use the 'bsr' instruction to find
+             * the last (highest order) 0 bit in the codeword, set
+             * it, and clear any later (higher order) 1 bits.  But
+             * 'bsr' actually finds the highest order 1 bit, so to
+             * use it first flip all bits in the codeword by XOR' ing
+             * it with (1U << len) - 1 == cur_table_end - 1.
+             */
+
+            let adv = BITS - (codeword ^ (curr_table_end - 1)).leading_zeros();
+            let bit = 1 << adv;
+
+            codeword &= bit - 1;
+            codeword |= bit;
+            count -= 1;
+
+            if count == 0 {
+                break;
+            }
+        }
+        // advance to the next codeword length
+        loop {
+            len += 1;
+
+            if len <= table_bits {
+                // dest is decode_table[curr_table_end]
+                // source is decode_table(start of table);
+                // size is curr_table;
+
+                decode_table.copy_within(0..curr_table_end, curr_table_end);
+
+                //decode_table.copy_within(range, curr_table_end);
+                curr_table_end <<= 1;
+            }
+            count = len_counts[len];
+
+            if count != 0 {
+                break;
+            }
+        }
+    }
+    // process codewords with len > table_bits.
+    // Require sub-tables
+    curr_table_end = 1 << table_bits;
+
+    let mut subtable_prefix = usize::MAX;
+    let mut subtable_start = 0;
+    let mut subtable_bits;
+
+    loop {
+        /*
+         * Start a new sub-table if the first 'table_bits' bits of the
+         * codeword don't match the prefix of the current subtable.
+         */
+        if codeword & ((1_usize << table_bits) - 1) != subtable_prefix {
+            subtable_prefix = codeword & ((1 << table_bits) - 1);
+            subtable_start = curr_table_end;
+
+            /*
+             * Calculate the subtable length.  If the codeword has
+             * length 'table_bits + n', then the subtable needs
+             * '2^n' entries.  But it may need more; if fewer than
+             * '2^n' codewords of length 'table_bits + n' remain,
+             * then the length will need to be incremented to bring
+             * in longer codewords until the subtable can be
+             * completely filled.  Note that because the Huffman
+             * code is complete, it will always be possible to fill
+             * the sub-table eventually.
+             */
+            subtable_bits = len - table_bits;
+            codespace_used = count;
+
+            while codespace_used < (1 << subtable_bits) {
+                subtable_bits += 1;
+
+                if subtable_bits + table_bits > 15 {
+                    return Err(DecodeErrorStatus::CorruptData);
+                }
+
+                codespace_used = (codespace_used << 1) + len_counts[table_bits + subtable_bits];
+            }
+
+            /*
+             * Create the entry that points from the main table to
+             * the subtable.
+             */
+            decode_table[subtable_prefix] = (subtable_start as u32) << 16
+                | HUFFDEC_EXCEPTIONAL
+                | HUFFDEC_SUITABLE_POINTER
+                | (subtable_bits as u32) << 8
+                | table_bits as u32;
+
+            curr_table_end = subtable_start + (1 << subtable_bits);
+        }
+
+        /* Fill the sub-table entries for the current codeword. */
+
+        let stride = 1 << (len - table_bits);
+
+        let mut j = subtable_start + (codeword >> table_bits);
+
+        let entry = make_decode_table_entry(
+            decode_results,
+            sorted_syms[i] as usize,
+            (len - table_bits) as u32
+        );
+        i += 1;
+
+        while j < curr_table_end {
+            decode_table[j] = entry;
+            j += stride;
+        }
+        //advance to the next codeword
+        if codeword == (1 << len) - 1 {
+            // last codeword
+            return Ok(());
+        }
+
+        let adv = BITS - (codeword ^ ((1 << len) - 1)).leading_zeros();
+        let bit = 1 << adv;
+
+        codeword &= bit - 1;
+        codeword |= bit;
+        count -= 1;
+
+        while count == 0 {
+            len += 1;
+            count = len_counts[len];
+        }
+    }
+}
@@ -37,6 +37,10 @@
 //!
 //! These features are enabled by default
 //!
+//! The following features are disabled by default
+//! - paranoid: Use fully checked match copies and assert on decode table
+//!   indices, this is slower and meant for CI and fuzzing builds
+//!
 //! To disable a feature , modify Cargo.toml to disable default features
 //! and add the needed feature , e.g below will include zlib decoding and disable gzip decoding
 //! ```toml
@@ -90,13 +94,15 @@
 extern crate alloc;
 
 pub use crate::decoder::{DeflateDecoder, DeflateOptions};
-pub use crate::encoder::DeflateEncoder;
+pub use crate::encoder::{DeflateEncodeOptions, DeflateEncoder, DeflateEncodingStrategy};
 
-mod bitstream;
+pub mod analyze;
+pub mod bitstream;
 mod constants;
-mod crc;
+pub mod copy;
 mod decoder;
 mod encoder;
 pub mod errors;
 mod gzip_constants;
+pub mod huffman;
 mod utils;
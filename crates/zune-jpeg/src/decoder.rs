@@ -17,6 +17,7 @@ use zune_core::bytestream::{ZByteReader, ZReaderTrait};
 use zune_core::colorspace::ColorSpace;
 use zune_core::log::{error, trace, warn};
 use zune_core::options::DecoderOptions;
+use zune_core::timings::{DecodeTimings, StageTimer};
 
 use crate::color_convert::choose_ycbcr_to_rgb_convert_func;
 use crate::components::{Components, SampleRatios};
@@ -146,9 +147,13 @@ pub struct JpegDecoder<T: ZReaderTrait> {
     // exif data, lifted from app2
     pub(crate) exif_data:        Option<Vec<u8>>,
 
-    pub(crate) icc_data: Vec<ICCChunk>,
-    pub(crate) is_mjpeg: bool,
-    pub(crate) coeff:    usize // Solves some weird bug :)
+    pub(crate) icc_data:  Vec<ICCChunk>,
+    pub(crate) is_mjpeg:  bool,
+    pub(crate) coeff:     usize, // Solves some weird bug :)
+    // the input ended before the image, see `is_truncated`
+    pub(crate) truncated: bool,
+    // time spent in each decoding stage, see `timings`
+    pub(crate) timings:   DecodeTimings
 }
 
 impl<T> JpegDecoder<T>
@@ -192,7 +197,9 @@ where
             exif_data:         None,
             icc_data:          vec![],
             is_mjpeg:          false,
-            coeff:             1
+            coeff:             1,
+            truncated:         false,
+            timings:           DecodeTimings::default()
         }
     }
     /// Decode a buffer already in memory
@@ -346,6 +353,13 @@ where
             trace!("Headers decoded!");
             return Ok(());
         }
+        let timer = StageTimer::start(self.options.get_collect_timings());
+        let result = self.parse_headers();
+        self.timings.record("headers", timer);
+        result
+    }
+
+    fn parse_headers(&mut self) -> Result<(), DecodeErrors> {
         // match output colorspace here
         // we know this will only be called once per image
         // so makes sense
@@ -494,6 +508,15 @@ where
                         self.is_mjpeg = true;
                     }
                     length -= 5;
+
+                    // version, units and densities
+                    if &buffer == b"JFIF\0" && length >= 2 + 7 && self.stream.has(7) {
+                        self.stream.skip(2);
+                        self.info.density_unit = self.stream.get_u8();
+                        self.info.set_x(self.stream.get_u16_be());
+                        self.info.set_y(self.stream.get_u16_be());
+                        length -= 7;
+                    }
                 }
                 self.stream.skip(length.saturating_sub(2) as usize);
 
@@ -832,6 +855,41 @@ where
             None
         };
     }
+
+    /// Return true if the image data ended before the whole image was decoded
+    ///
+    /// Parts of the image past the end of the data are zero, for progressive
+    /// images the missing scans instead reduce the quality of the whole image.
+    ///
+    /// In strict mode this only happens if
+    /// [`DecoderOptions::set_allow_partial`](zune_core::options::DecoderOptions::set_allow_partial)
+    /// is set, otherwise truncated images are an error
+    #[must_use]
+    pub const fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// Return the time spent in each decoding stage
+    ///
+    /// Stages are `headers`, `decode mcus` (entropy decoding and IDCT) and
+    /// `post process` (up-sampling and color conversion) for baseline images,
+    /// and `headers`, `decode scans` and `post process` (IDCT, up-sampling and color conversion)
+    /// for progressive images.
+    ///
+    /// Timings are only recorded when
+    /// [`DecoderOptions::set_collect_timings`](zune_core::options::DecoderOptions::set_collect_timings)
+    /// is set
+    #[must_use]
+    pub const fn timings(&self) -> &DecodeTimings {
+        &self.timings
+    }
+
+    /// Return the stage timings mutably
+    ///
+    /// This allows wrappers to add stages they run on the decoded pixels
+    pub fn timings_mut(&mut self) -> &mut DecodeTimings {
+        &mut self.timings
+    }
 }
 
 /// A struct representing Image Information
@@ -846,10 +904,13 @@ pub struct ImageInfo {
     pub pixel_density: u8,
     /// Start of frame markers
     pub sof:           SOFMarkers,
-    /// Horizontal sample
+    /// Horizontal pixel density from the JFIF header, zero if there is none
     pub x_density:     u16,
-    /// Vertical sample
+    /// Vertical pixel density from the JFIF header, zero if there is none
     pub y_density:     u16,
+    /// Unit of the densities, `0` if they only give the pixel aspect ratio,
+    /// `1` for dots per inch and `2` for dots per centimeter
+    pub density_unit:  u8,
     /// Number of components
     pub components:    u8
 }
@@ -890,7 +951,6 @@ impl ImageInfo {
     /// Set image x-density(dots per pixel)
     ///
     /// Found in the APP(0) marker
-    #[allow(dead_code)]
     pub(crate) fn set_x(&mut self, sample: u16) {
         self.x_density = sample;
     }
@@ -898,7 +958,6 @@ impl ImageInfo {
     /// Set image y-density
     ///
     /// Found in the APP(0) marker
-    #[allow(dead_code)]
     pub(crate) fn set_y(&mut self, sample: u16) {
         self.y_density = sample;
     }
@@ -13,6 +13,7 @@ use zune_core::bytestream::ZReaderTrait;
 use zune_core::colorspace::ColorSpace;
 use zune_core::colorspace::ColorSpace::Luma;
 use zune_core::log::{error, trace, warn};
+use zune_core::timings::StageTimer;
 
 use crate::bitstream::BitStream;
 use crate::components::SampleRatios;
@@ -185,17 +186,31 @@ impl<T: ZReaderTrait> JpegDecoder<T> {
             if stream.overread_by > 37
             // favourite number :)
             {
-                if self.options.get_strict_mode() {
+                if self.options.get_strict_mode() && !self.options.get_allow_partial() {
                     return Err(DecodeErrors::FormatStatic("Premature end of buffer"));
                 };
 
                 error!("Premature end of buffer");
+                self.truncated = true;
                 break;
             }
             // decode a whole MCU width,
             // this takes into account interleaved components.
-            self.decode_mcu_width(mcu_width, &mut tmp, &mut stream)?;
+            let timer = StageTimer::start(self.options.get_collect_timings());
+            let result = self.decode_mcu_width(mcu_width, &mut tmp, &mut stream);
+            self.timings.record("decode mcus", timer);
+
+            if let Err(err) = result {
+                // decoding the zeros past the end of the data may fail
+                if !(self.options.get_allow_partial() && stream.overread_by > 0) {
+                    return Err(err);
+                }
+                error!("Premature end of buffer: {err:?}");
+                self.truncated = true;
+                break;
+            }
             // process that width up until it's impossible
+            let timer = StageTimer::start(self.options.get_collect_timings());
             self.post_process(
                 pixels,
                 i,
@@ -205,6 +220,7 @@ impl<T: ZReaderTrait> JpegDecoder<T> {
                 &mut pixels_written,
                 &mut upsampler_scratch_space
             )?;
+            self.timings.record("post process", timer);
         }
         // it may happen that some images don't have the whole buffer
         // so we can't panic in case of that
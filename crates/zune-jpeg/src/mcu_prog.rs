@@ -27,6 +27,7 @@ use core::cmp::min;
 use zune_core::bytestream::{ZByteReader, ZReaderTrait};
 use zune_core::colorspace::ColorSpace;
 use zune_core::log::{debug, error, warn};
+use zune_core::timings::StageTimer;
 
 use crate::bitstream::BitStream;
 use crate::components::{ComponentID, SampleRatios};
@@ -121,6 +122,7 @@ impl<T: ZReaderTrait> JpegDecoder<T> {
             self.spec_end
         );
 
+        let timer = StageTimer::start(self.options.get_collect_timings());
         // there are multiple scans in the stream, this should resolve the first scan
         self.parse_entropy_coded_data(&mut stream, &mut block)?;
 
@@ -169,10 +171,17 @@ impl<T: ZReaderTrait> JpegDecoder<T> {
                             continue 'eoi;
                         }
                         Err(msg) => {
-                            if self.options.get_strict_mode() {
+                            // only running out of input is a truncated image,
+                            // a bad marker in the middle of the data is corruption
+                            let ended = self.stream.eof();
+
+                            if self.options.get_strict_mode()
+                                && !(self.options.get_allow_partial() && ended)
+                            {
                                 return Err(msg);
                             }
                             error!("{:?}", msg);
+                            self.truncated |= ended;
                             break 'eoi;
                         }
                     }
@@ -187,15 +196,24 @@ impl<T: ZReaderTrait> JpegDecoder<T> {
                     marker = marker_n;
                 }
                 Err(e) => {
-                    if self.options.get_strict_mode() {
+                    let ended = self.stream.eof();
+
+                    if self.options.get_strict_mode() && !(self.options.get_allow_partial() && ended)
+                    {
                         return Err(e);
                     }
                     error!("{}", e);
+                    self.truncated |= ended;
                 }
             }
         }
 
-        self.finish_progressive_decoding(&block, mcu_width, pixels)
+        self.timings.record("decode scans", timer);
+
+        let timer = StageTimer::start(self.options.get_collect_timings());
+        let result = self.finish_progressive_decoding(&block, mcu_width, pixels);
+        self.timings.record("post process", timer);
+        result
     }
 
     #[allow(clippy::too_many_lines, clippy::cast_sign_loss)]
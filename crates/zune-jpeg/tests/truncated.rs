@@ -0,0 +1,78 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+use zune_core::options::DecoderOptions;
+use zune_jpeg::JpegDecoder;
+
+fn open(name: &str) -> Vec<u8> {
+    let path = env!("CARGO_MANIFEST_DIR").to_string() + "/../../test-images/jpeg/" + name;
+    std::fs::read(path).unwrap()
+}
+
+fn decode(data: &[u8], options: DecoderOptions) -> Result<(Vec<u8>, bool), String> {
+    let mut decoder = JpegDecoder::new_with_options(data, options);
+    let pixels = decoder.decode().map_err(|x| format!("{x:?}"))?;
+
+    Ok((pixels, decoder.is_truncated()))
+}
+
+#[test]
+fn test_truncated_baseline() {
+    let data = open("2029.jpg");
+    let strict = DecoderOptions::default().set_strict_mode(true);
+
+    let (full, is_truncated) = decode(&data, strict).unwrap();
+    assert!(!is_truncated);
+
+    let truncated = &data[..data.len() / 2];
+    assert!(decode(truncated, strict).is_err());
+
+    let (pixels, is_truncated) = decode(truncated, strict.set_allow_partial(true)).unwrap();
+    assert!(is_truncated);
+    assert_eq!(pixels.len(), full.len());
+
+    // the top survives while the bottom is lost
+    let rows = full.len() / 10;
+    assert_eq!(pixels[..rows], full[..rows]);
+    assert!(pixels[full.len() - rows..].iter().all(|x| *x == 0));
+}
+
+#[test]
+fn test_truncated_progressive() {
+    let data = open("down_sampled_grayscale_prog.jpg");
+    let strict = DecoderOptions::default().set_strict_mode(true);
+
+    let (full, _) = decode(&data, strict).unwrap();
+
+    let truncated = &data[..data.len() / 2];
+    assert!(decode(truncated, strict).is_err());
+
+    let (pixels, is_truncated) = decode(truncated, strict.set_allow_partial(true)).unwrap();
+    assert!(is_truncated);
+    assert_eq!(pixels.len(), full.len());
+}
+
+#[test]
+fn test_corrupt_progressive_is_not_truncated() {
+    let mut data = open("down_sampled_grayscale_prog.jpg");
+    // replace the second start of scan marker with an unknown one
+    let sos = data
+        .windows(2)
+        .enumerate()
+        .filter(|(_, x)| *x == [0xFF, 0xDA])
+        .nth(1)
+        .unwrap()
+        .0;
+    data[sos + 1] = 0x4F;
+
+    let options = DecoderOptions::default()
+        .set_strict_mode(true)
+        .set_allow_partial(true);
+    let err = decode(&data, options).unwrap_err();
+    assert!(err.contains("Unknown marker"), "{err}");
+}
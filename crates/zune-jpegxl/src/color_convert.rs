@@ -40,11 +40,11 @@ where
 
 pub fn fill_row_g16<T>(pixels: &[u8], oxs: usize, luma: &mut [T])
 where
-    T: From<i16>
+    T: TryFrom<u16> + Default
 {
     for (rg, lm) in pixels.chunks_exact(2).zip(luma).take(oxs) {
-        let value = i16::from_ne_bytes([rg[0], rg[1]]);
-        *lm = T::from(value);
+        let value = u16::from_ne_bytes([rg[0], rg[1]]);
+        *lm = T::try_from(value).unwrap_or_default();
     }
 }
 
@@ -60,14 +60,14 @@ where
 
 pub fn fill_row_ga16<T>(pixels: &[u8], oxs: usize, luma: &mut [T], alpha: &mut [T])
 where
-    T: From<i16>
+    T: TryFrom<u16> + Default
 {
     for ((rg, lm), am) in pixels.chunks_exact(4).zip(luma).zip(alpha).take(oxs) {
-        let luma_bits = i16::from_ne_bytes([rg[0], rg[1]]);
-        let alpha_bits = i16::from_ne_bytes([rg[2], rg[3]]);
+        let luma_bits = u16::from_ne_bytes([rg[0], rg[1]]);
+        let alpha_bits = u16::from_ne_bytes([rg[2], rg[3]]);
 
-        *lm = T::from(luma_bits);
-        *am = T::from(alpha_bits);
+        *lm = T::try_from(luma_bits).unwrap_or_default();
+        *am = T::try_from(alpha_bits).unwrap_or_default();
     }
 }
 
@@ -1576,7 +1576,19 @@ fn prepare_header(frame: &mut FrameState, add_image_header: bool, is_last: bool)
         }
         if have_alpha {
             output.put_bits(2, 1); // extra channel
-            output.put_bits(1, 1); // all default (8 bit alpha)
+
+            if depth == BitDepth::Eight {
+                output.put_bits(1, 1); // all default (8 bit alpha)
+            } else {
+                output.put_bits(1, 0); // not all default
+                output.put_bits(2, 0); // type alpha
+                output.put_bits(1, 0); // integer sample
+                output.put_bits(2, 0b11); // bits per sample: 1 + u(6)
+                output.put_bits(6, (frame.option.get_depth().bit_size() - 1) as u64);
+                output.put_bits(2, 0); // no dim shift
+                output.put_bits(2, 0); // no name
+                output.put_bits(1, 0); // alpha not premultiplied
+            }
         } else {
             output.put_bits(2, 0); // no extra channel
         }
@@ -0,0 +1,68 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! Buffers reused across decodes
+use alloc::vec::Vec;
+
+/// Buffers that hold the output and intermediate data of a decode, reused
+/// across decodes by [`PngDecoder::decode_reusing`](crate::PngDecoder::decode_reusing)
+///
+/// Decoding allocates a buffer for the inflated image data, a scratch row for un-filtering
+/// and the output image. When decoding many images of similar sizes, e.g on a server,
+/// keeping one `DecodeBuffers` per thread lets every decode after the first one
+/// run without these allocations.
+///
+/// # Example
+/// ```no_run
+/// use zune_png::{DecodeBuffers, PngDecoder};
+///
+/// let files: Vec<Vec<u8>> = vec![];
+/// let mut buffers = DecodeBuffers::new();
+///
+/// for file in &files {
+///     let mut decoder = PngDecoder::new(file);
+///     decoder.decode_reusing(&mut buffers).unwrap();
+///     // use the pixels before the next decode overwrites them
+///     let pixels = buffers.image();
+/// }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct DecodeBuffers {
+    /// Inflated, filtered image data
+    pub(crate) inflate: Vec<u8>,
+    /// Scratch space for un-filtering and post processing rows
+    pub(crate) scratch: Vec<u8>,
+    /// Decoded pixels
+    pub(crate) image:   Vec<u8>
+}
+
+impl DecodeBuffers {
+    /// Create empty buffers, they grow to fit the first images decoded into them
+    pub const fn new() -> DecodeBuffers {
+        DecodeBuffers {
+            inflate: Vec::new(),
+            scratch: Vec::new(),
+            image:   Vec::new()
+        }
+    }
+    /// Return the pixels of the last decoded image
+    ///
+    /// The layout is the same as the one returned by
+    /// [`PngDecoder::decode_raw`](crate::PngDecoder::decode_raw)
+    pub fn image(&self) -> &[u8] {
+        &self.image
+    }
+    /// Return the pixels of the last decoded image, consuming the buffers
+    pub fn into_image(self) -> Vec<u8> {
+        self.image
+    }
+    /// Return the number of bytes allocated by all buffers
+    pub fn capacity(&self) -> usize {
+        self.inflate.capacity() + self.scratch.capacity() + self.image.capacity()
+    }
+}
@@ -4,6 +4,8 @@
  * This software is free software; You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
  */
 
+#[cfg(feature = "diagnostics")]
+use alloc::boxed::Box;
 use alloc::vec::Vec;
 use alloc::{format, vec};
 use core::cmp::min;
@@ -11,23 +13,30 @@ use core::cmp::min;
 use zune_core::bit_depth::{BitDepth, ByteEndian};
 use zune_core::bytestream::{ZByteReader, ZReaderTrait};
 use zune_core::colorspace::ColorSpace;
-use zune_core::log::trace;
-use zune_core::options::DecoderOptions;
+use zune_core::log::{trace, warn};
+use zune_core::options::{DecoderOptions, PngStrictness};
 use zune_core::result::DecodingResult;
+#[cfg(feature = "threads")]
+use zune_core::threads::ThreadPool;
+use zune_core::timings::{DecodeTimings, StageTimer};
+use zune_core::utils::{checked_image_size, checked_row_size};
+use zune_inflate::errors::DecodeErrorStatus;
 use zune_inflate::DeflateOptions;
 
 use crate::apng::{ActlChunk, FrameInfo, SingleFrame};
+use crate::buffers::DecodeBuffers;
 use crate::constants::PNG_SIGNATURE;
 use crate::enums::{FilterMethod, InterlaceMethod, PngChunkType, PngColor};
-use crate::error::PngDecodeErrors;
 use crate::error::PngDecodeErrors::GenericStatic;
+use crate::error::{ErrorLocation, PngDecodeErrors};
 use crate::filters::de_filter::{
     handle_avg, handle_avg_first, handle_paeth, handle_paeth_first, handle_sub, handle_up
 };
 use crate::options::default_chunk_handler;
 use crate::utils::{
-    add_alpha, convert_be_to_target_endian_u16, convert_u16_to_u8_slice, expand_bits_to_byte,
-    expand_palette, expand_trns, is_le
+    add_alpha, can_convert_colorspace, composite_background, convert_be_to_target_endian_u16,
+    convert_colorspace, convert_u16_to_u8_slice, expand_bits_to_byte, expand_palette, expand_trns,
+    is_le
 };
 
 /// A palette entry.
@@ -76,6 +85,18 @@ pub struct TimeInfo {
     pub second: u8
 }
 
+/// Number of pixels per unit along each axis
+///
+/// Extracted from and written to the pHYs chunk
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub struct PhysicalDimensions {
+    pub x_pixels_per_unit: u32,
+    pub y_pixels_per_unit: u32,
+    /// True if the unit is the meter, otherwise the values only
+    /// describe the pixel aspect ratio
+    pub unit_is_meter:     bool
+}
+
 /// iTXt details
 ///
 /// UTF-8 encoded text
@@ -118,10 +139,24 @@ pub struct PngInfo {
     pub height:               usize,
     /// Image gamma
     pub gamma:                Option<f32>,
+    /// Number of significant bits of each channel in the file,
+    /// from the `sBIT` chunk
+    ///
+    /// For palette images, these are the bits of the red, green
+    /// and blue palette entries
+    pub significant_bits:     Option<Vec<u8>>,
+    /// Background color from the `bKGD` chunk, as red, green and blue
+    ///
+    /// Palette indices are resolved and gray levels are repeated across all three
+    /// entries. Values are in the sample range of the image depth, with 1, 2 and 4 bit
+    /// levels scaled to 8 bits unless raw samples are kept.
+    pub background:           Option<[u16; 3]>,
     /// Image interlace method
     pub interlace_method:     InterlaceMethod,
     /// Image time info
     pub time_info:            Option<TimeInfo>,
+    /// Pixel size or aspect ratio from the `pHYs` chunk
+    pub physical_dimensions:  Option<PhysicalDimensions>,
     /// Image exif data
     pub exif:                 Option<Vec<u8>>,
     /// Icc profile
@@ -165,9 +200,13 @@ where
     pub(crate) options:                 DecoderOptions,
     pub(crate) png_info:                PngInfo,
     pub(crate) palette:                 Vec<PLTEEntry>,
+    /// Number of entries in the pLTE chunk, `palette` always holds 256
+    pub(crate) palette_entries:         usize,
     pub(crate) frames:                  Vec<SingleFrame>,
     pub(crate) actl_info:               Option<ActlChunk>,
     pub(crate) previous_stride:         Vec<u8>,
+    /// Allocation reused for the inflated data of the next frame
+    pub(crate) inflate_buffer:          Vec<u8>,
     pub(crate) trns_bytes:              [u16; 4],
     pub(crate) seen_hdr:                bool,
     pub(crate) seen_ptle:               bool,
@@ -175,7 +214,19 @@ where
     pub(crate) seen_trns:               bool,
     pub(crate) seen_iend:               bool,
     pub(crate) current_frame:           usize,
-    pub(crate) called_from_decode_into: bool
+    pub(crate) called_from_decode_into: bool,
+    /// Number of chunk headers read, used by the CRC policy
+    pub(crate) chunks_read:             usize,
+    /// The input ended early and was salvaged, see [`DecoderOptions::set_allow_partial`]
+    pub(crate) truncated:               bool,
+    /// Type and start offset of the last chunk whose header was read
+    pub(crate) chunk_location:          Option<([u8; 4], usize)>,
+    /// Type and start offset of the first chunk holding data of each frame
+    pub(crate) frame_locations:         Vec<([u8; 4], usize)>,
+    /// Where the last error occurred, see [`error_location`](Self::error_location)
+    pub(crate) error_location:          Option<ErrorLocation>,
+    /// Time spent in each decoding stage, see [`DecoderOptions::set_collect_timings`]
+    pub(crate) timings:                 DecodeTimings
 }
 
 impl<T: ZReaderTrait> PngDecoder<T> {
@@ -210,9 +261,11 @@ impl<T: ZReaderTrait> PngDecoder<T> {
             stream:                  ZByteReader::new(data),
             options:                 options,
             palette:                 Vec::new(),
+            palette_entries:         0,
             png_info:                PngInfo::default(),
             actl_info:               None,
             previous_stride:         vec![],
+            inflate_buffer:          vec![],
             frames:                  vec![],
             seen_ptle:               false,
             seen_trns:               false,
@@ -220,7 +273,13 @@ impl<T: ZReaderTrait> PngDecoder<T> {
             seen_iend:               false,
             trns_bytes:              [0; 4],
             current_frame:           0,
-            called_from_decode_into: true
+            called_from_decode_into: true,
+            chunks_read:             0,
+            truncated:               false,
+            chunk_location:          None,
+            frame_locations:         vec![],
+            error_location:          None,
+            timings:                 DecodeTimings::default()
         }
     }
 
@@ -258,6 +317,36 @@ impl<T: ZReaderTrait> PngDecoder<T> {
             _ => unreachable!()
         }
     }
+    /// Return the depth of the samples before they were scaled to fill
+    /// the 16 bit range, as recorded by the `sBIT` chunk
+    ///
+    /// Only 16 bit images whose channels all have 10 or 12 significant bits
+    /// return a depth, decoded samples of such images can be shifted right
+    /// by `16 - bits` to recover the original values.
+    ///
+    /// # Returns
+    /// - `Some(depth)`: Either [`BitDepth::Ten`] or [`BitDepth::Twelve`]
+    /// - `None`: The headers weren't decoded, the image has no `sBIT` chunk,
+    ///   samples are stripped to 8 bits or rescaled to the full range, or
+    ///   channels use other or differing bit counts
+    pub fn get_significant_depth(&self) -> Option<BitDepth> {
+        if self.png_info.depth != 16
+            || self.options.png_get_strip_to_8bit()
+            || self.options.png_get_rescale_significant_bits()
+        {
+            return None;
+        }
+        let bits = self.png_info.significant_bits.as_ref()?;
+        let first = *bits.first()?;
+
+        if bits.iter().any(|x| *x != first) {
+            return None;
+        }
+        match BitDepth::from_u16_bits(first) {
+            Some(depth @ (BitDepth::Ten | BitDepth::Twelve)) => Some(depth),
+            _ => None
+        }
+    }
     /// Get image colorspace
     ///
     /// If an image is a palette type, the colorspace is
@@ -270,7 +359,55 @@ impl<T: ZReaderTrait> PngDecoder<T> {
     ///  - `Some(colorspace)`: The colorspace which the decoded bytes will be in
     ///  - `None`: If the image headers haven't been decoded, or there was an error
     ///     during decoding
+    ///
+    /// If an output colorspace was set via
+    /// [`DecoderOptions::png_set_out_colorspace`](zune_core::options::DecoderOptions::png_set_out_colorspace)
+    /// and the decoder can convert to it, that colorspace is returned.
+    /// Otherwise images composited onto their background via
+    /// [`DecoderOptions::png_set_composite_background`](zune_core::options::DecoderOptions::png_set_composite_background)
+    /// lose their alpha channel
     pub const fn get_colorspace(&self) -> Option<ColorSpace> {
+        let native = match self.native_colorspace() {
+            Some(colorspace) => colorspace,
+            None => return None
+        };
+        match self.options.png_get_out_colorspace() {
+            Some(out) if can_convert_colorspace(native, out) => Some(out),
+            _ if self.composites_background() => match native {
+                ColorSpace::LumaA => Some(ColorSpace::Luma),
+                ColorSpace::RGBA => Some(ColorSpace::RGB),
+                _ => Some(native)
+            },
+            _ => Some(native)
+        }
+    }
+    /// Return true if pixels will be blended onto the background color
+    /// during decoding
+    ///
+    /// This needs the image to have an alpha channel after expansion
+    /// and not be animated
+    const fn composites_background(&self) -> bool {
+        let has_alpha = match self.native_colorspace() {
+            Some(colorspace) => colorspace.has_alpha(),
+            None => false
+        };
+        self.options.png_get_composite_background() && self.actl_info.is_none() && has_alpha
+    }
+    /// Return the color pixels are blended onto, in the sample range
+    /// of the image, or `None` if the image isn't composited
+    fn composite_color(&self) -> Option<[u16; 3]> {
+        if !self.composites_background() {
+            return None;
+        }
+        // white when the image doesn't specify a background
+        let white = if self.png_info.depth == 16 { 65535 } else { 255 };
+
+        Some(self.png_info.background.unwrap_or([white; 3]))
+    }
+    /// Get the colorspace the image will be in after expanding
+    /// palettes, tRNS chunks and adding alpha channels but before converting to
+    /// the user requested output colorspace
+    const fn native_colorspace(&self) -> Option<ColorSpace> {
         if !self.seen_hdr {
             return None;
         }
@@ -323,18 +460,39 @@ impl<T: ZReaderTrait> PngDecoder<T> {
         self.actl_info.is_some() && self.frames.len() > self.current_frame
     }
 
-    pub(crate) fn read_chunk_header(&mut self) -> Result<PngChunk, PngDecodeErrors> {
-        // Format is length - chunk type - [data] -  crc chunk, load crc chunk now
-        let chunk_length = self.stream.get_u32_be_err()? as usize;
-        let chunk_type_int = self.stream.get_u32_be_err()?.to_be_bytes();
+    /// Return true if the input ended before the image did
+    ///
+    /// This can only happen when [`DecoderOptions::set_allow_partial`] is set,
+    /// in which case rows past the end of the input are zero
+    pub const fn is_truncated(&self) -> bool {
+        self.truncated
+    }
 
-        let mut crc_bytes = [0; 4];
+    /// Return the time spent in each decoding stage
+    ///
+    /// Stages are `headers`, `inflate` and `unfilter`, the latter includes
+    /// de-interlacing and expanding palettes and low bit depths.
+    ///
+    /// Timings are only recorded when [`DecoderOptions::set_collect_timings`] is set,
+    /// and are summed over all frames decoded so far
+    pub const fn timings(&self) -> &DecodeTimings {
+        &self.timings
+    }
 
-        let crc_ref = self.stream.peek_at(chunk_length, 4)?;
+    /// Return the stage timings mutably
+    ///
+    /// This allows wrappers to add stages they run on the decoded pixels
+    pub fn timings_mut(&mut self) -> &mut DecodeTimings {
+        &mut self.timings
+    }
 
-        crc_bytes.copy_from_slice(crc_ref);
+    pub(crate) fn read_chunk_header(&mut self) -> Result<PngChunk, PngDecodeErrors> {
+        // Format is length - chunk type - [data] -  crc chunk, load crc chunk now
+        let start = self.stream.get_position();
+        let mut chunk_length = self.stream.get_u32_be_err()? as usize;
+        let chunk_type_int = self.stream.get_u32_be_err()?.to_be_bytes();
 
-        let crc = u32::from_be_bytes(crc_bytes);
+        self.chunk_location = Some((chunk_type_int, start));
 
         let chunk_type = match &chunk_type_int {
             b"IHDR" => PngChunkType::IHDR,
@@ -345,6 +503,8 @@ impl<T: ZReaderTrait> PngDecoder<T> {
             b"pHYs" => PngChunkType::pHYs,
             b"tIME" => PngChunkType::tIME,
             b"gAMA" => PngChunkType::gAMA,
+            b"sBIT" => PngChunkType::sBit,
+            b"bKGD" => PngChunkType::bKGD,
             b"acTL" => PngChunkType::acTL,
             b"fcTL" => PngChunkType::fcTL,
             b"iCCP" => PngChunkType::iCCP,
@@ -357,6 +517,25 @@ impl<T: ZReaderTrait> PngDecoder<T> {
         };
 
         if !self.stream.has(chunk_length + 4 /*crc stream*/) {
+            if self.options.get_allow_partial()
+                && matches!(chunk_type, PngChunkType::IDAT | PngChunkType::fdAT)
+            {
+                // keep whatever image data is there, the crc is gone with the rest
+                warn!(
+                    "Chunk {:?} is truncated, decoding the {} bytes present",
+                    chunk_type,
+                    self.stream.remaining()
+                );
+                chunk_length = self.stream.remaining();
+                self.truncated = true;
+
+                return Ok(PngChunk {
+                    length: chunk_length,
+                    chunk: chunk_type_int,
+                    chunk_type,
+                    crc: 0
+                });
+            }
             let err = format!(
                 "Not enough bytes for chunk {:?}, bytes requested are {}, but bytes present are {}",
                 chunk_type,
@@ -366,10 +545,25 @@ impl<T: ZReaderTrait> PngDecoder<T> {
 
             return Err(PngDecodeErrors::Generic(err));
         }
-        // Confirm the CRC here.
+        let mut crc_bytes = [0; 4];
+
+        crc_bytes.copy_from_slice(self.stream.peek_at(chunk_length, 4)?);
 
-        if self.options.png_get_confirm_crc() {
-            use crate::crc::crc32_slice8;
+        let crc = u32::from_be_bytes(crc_bytes);
+
+        // critical chunks have the ancillary bit (bit 5 of first byte) unset
+        let critical = chunk_type_int[0] & (1 << 5) == 0;
+        let position = self.chunks_read;
+        self.chunks_read += 1;
+
+        // Confirm the CRC here.
+        if self.options.png_get_confirm_crc()
+            && self
+                .options
+                .png_get_crc_policy()
+                .should_confirm(position, critical)
+        {
+            use zune_core::checksum::crc32;
 
             // go back and point to chunk type.
             self.stream.rewind(4);
@@ -377,10 +571,21 @@ impl<T: ZReaderTrait> PngDecoder<T> {
             let bytes = self.stream.peek_at(0, chunk_length + 4).unwrap();
 
             // calculate crc
-            let calc_crc = !crc32_slice8(bytes, u32::MAX);
+            let calc_crc = crc32::crc32(bytes);
 
             if crc != calc_crc {
-                return Err(PngDecodeErrors::BadCrc(crc, calc_crc));
+                let strictness = if critical {
+                    PngStrictness::CRITICAL_CRC
+                } else {
+                    PngStrictness::ANCILLARY_CRC
+                };
+                if self.options.png_get_strictness().contains(strictness) {
+                    return Err(PngDecodeErrors::BadCrc(crc, calc_crc));
+                }
+                warn!(
+                    "CRC mismatch for chunk {:?}, expected {} but found {}, ignoring",
+                    chunk_type, crc, calc_crc
+                );
             }
             // go point after the chunk type
             // The other parts expect the bit-reader to point to the
@@ -402,9 +607,94 @@ impl<T: ZReaderTrait> PngDecoder<T> {
     /// After calling this, header information can
     /// be accessed by public headers
     pub fn decode_headers(&mut self) -> Result<(), PngDecodeErrors> {
+        // every decoding method starts here, so this forgets the previous error
+        self.error_location = None;
+
         if self.seen_headers && self.seen_iend {
             return Ok(());
         }
+        let timer = StageTimer::start(self.options.get_collect_timings());
+        let result = self.read_headers();
+        self.timings.record("headers", timer);
+        result.map_err(|err| self.with_context(err))
+    }
+
+    /// Return the stream offset and chunk being read when the last error
+    /// returned by this decoder occurred
+    ///
+    /// This is set for errors returned while reading headers or decoding,
+    /// and reset when decoding is attempted again
+    pub const fn error_location(&self) -> Option<ErrorLocation> {
+        self.error_location
+    }
+
+    /// Record the current stream position and the chunk being read as the
+    /// location of `err`
+    fn with_context(&mut self, err: PngDecodeErrors) -> PngDecodeErrors {
+        // the signature is read whole, point at its start
+        let offset = match err {
+            PngDecodeErrors::BadSignature => 0,
+            _ => self.stream.get_position()
+        };
+        let location = ErrorLocation {
+            offset,
+            chunk: self.chunk_location.map(|(chunk, _)| chunk),
+            chunk_offset: self.chunk_location.map_or(0, |(_, start)| start)
+        };
+        self.with_location(err, location)
+    }
+
+    /// Attach the first data chunk of the current frame to `err`, for errors found
+    /// while decoding image data after all chunks were read
+    fn with_frame_context(&mut self, err: PngDecodeErrors) -> PngDecodeErrors {
+        match self.frame_locations.get(self.current_frame) {
+            Some(&(chunk, start)) => {
+                let location = ErrorLocation {
+                    offset:       start,
+                    chunk:        Some(chunk),
+                    chunk_offset: start
+                };
+                self.with_location(err, location)
+            }
+            None => self.with_context(err)
+        }
+    }
+
+    /// Record `location` as the location of `err` unless an inner call already
+    /// did, and with the `diagnostics` feature attach the bytes around it
+    fn with_location(&mut self, err: PngDecodeErrors, location: ErrorLocation) -> PngDecodeErrors {
+        if self.error_location.is_some() {
+            return err;
+        }
+        self.error_location = Some(location);
+
+        #[cfg(feature = "diagnostics")]
+        {
+            let window = self.options.get_error_context_size();
+
+            if window != 0 {
+                let context = self.stream.byte_context(location.offset, window);
+
+                return PngDecodeErrors::WithContext(Box::new(err), context);
+            }
+        }
+        err
+    }
+
+    /// Return true if the chunk starting at `start` does not fit in the rest of the input
+    ///
+    /// This moves the stream position
+    fn chunk_runs_past_end(&mut self, start: usize) -> bool {
+        self.stream.set_position(start);
+
+        match self.stream.get_u32_be_err() {
+            // type and crc follow the length
+            Ok(length) => !self.stream.has(length as usize + 8),
+            Err(_) => true
+        }
+    }
+
+    fn read_headers(&mut self) -> Result<(), PngDecodeErrors> {
         if !self.seen_hdr {
             // READ PNG signature
             let signature = self.stream.get_u64_be_err()?;
@@ -420,11 +710,43 @@ impl<T: ZReaderTrait> PngDecoder<T> {
             }
         }
         loop {
-            let header = self.read_chunk_header()?;
+            let start = self.stream.get_position();
+
+            let header = match self.read_chunk_header() {
+                Ok(header) => header,
+                Err(err) => {
+                    // only the input ending after some image data is salvaged,
+                    // corrupt chunks are still errors
+                    if self.options.get_allow_partial()
+                        && !self.frames.is_empty()
+                        && self.chunk_runs_past_end(start)
+                    {
+                        warn!("Input ends before IEND, decoding what is present");
+                        self.truncated = true;
+                        self.seen_iend = true;
+                        break;
+                    }
+                    return Err(err);
+                }
+            };
 
             self.parse_header(header)?;
 
             if header.chunk_type == PngChunkType::IEND {
+                let remaining = self.stream.remaining();
+
+                if remaining != 0 {
+                    if self
+                        .options
+                        .png_get_strictness()
+                        .contains(PngStrictness::TRAILING_DATA)
+                    {
+                        return Err(PngDecodeErrors::Generic(format!(
+                            "Found {remaining} bytes after IEND chunk"
+                        )));
+                    }
+                    warn!("Ignoring {} bytes after IEND chunk", remaining);
+                }
                 break;
             }
             // break here, we already have content for one
@@ -454,12 +776,21 @@ impl<T: ZReaderTrait> PngDecoder<T> {
             PngChunkType::gAMA => {
                 self.parse_gama(header)?;
             }
+            PngChunkType::sBit => {
+                self.parse_sbit(header)?;
+            }
+            PngChunkType::bKGD => {
+                self.parse_bkgd(header)?;
+            }
             PngChunkType::acTL => {
                 self.parse_actl(header)?;
             }
             PngChunkType::tIME => {
                 self.parse_time(header)?;
             }
+            PngChunkType::pHYs => {
+                self.parse_phys(header)?;
+            }
             PngChunkType::eXIf => {
                 self.parse_exif(header)?;
             }
@@ -479,7 +810,11 @@ impl<T: ZReaderTrait> PngDecoder<T> {
                 // may read more headers internally
                 self.parse_fctl(header)?;
             }
-            PngChunkType::IEND => self.seen_iend = true,
+            PngChunkType::IEND => {
+                // skip crc
+                self.stream.skip(header.length + 4);
+                self.seen_iend = true;
+            }
             _ => default_chunk_handler(header.length, header.chunk, &mut self.stream, header.crc)?
         }
 
@@ -503,35 +838,32 @@ impl<T: ZReaderTrait> PngDecoder<T> {
     ///
     /// # Returns
     ///  - `Some(usize)`: Minimum size for a buffer needed to decode the image
-    ///  - `None`: Indicates the image headers was not decoded.
-    ///
-    /// # Panics
-    /// In case `width*height*colorspace` calculation may overflow a usize
+    ///  - `None`: Indicates the image headers was not decoded, or
+    ///    the size overflows a `usize`
     pub fn output_buffer_size(&self) -> Option<usize> {
         if !self.seen_hdr {
             return None;
         }
 
         let info = &self.png_info;
-        let bytes = if info.depth == 16 && !self.options.png_get_strip_to_8bit() { 2 } else { 1 };
+        let depth = if info.depth == 16 && !self.options.png_get_strip_to_8bit() {
+            BitDepth::Sixteen
+        } else {
+            BitDepth::Eight
+        };
 
         let out_n = self.get_colorspace()?.num_components();
-        let dims = self.get_dimensions().unwrap();
+        let (width, height) = self.get_dimensions()?;
 
-        dims.0
-            .checked_mul(dims.1)?
-            .checked_mul(out_n)?
-            .checked_mul(bytes)
+        checked_image_size(width, height, out_n, depth)
     }
     /// Return the number of bytes required to hold a decoded image frame
     /// decoded without regard to the given input transformations
     ///
     /// # Returns
     ///  - `Some(usize)`: Minimum size for a buffer needed to decode the image
-    ///  - `None`: Indicates the image headers was not decoded.
-    ///
-    /// # Panics
-    /// In case `width*height*colorspace` calculation may overflow a usize
+    ///  - `None`: Indicates the image headers was not decoded, or
+    ///    the size overflows a `usize`
     fn inner_buffer_size(&self) -> Option<usize> {
         if !self.seen_hdr {
             return None;
@@ -541,14 +873,11 @@ impl<T: ZReaderTrait> PngDecoder<T> {
         let p_info = &self.png_info;
         // only difference with output is here we don't care about
         // stripping 16 bit to 8 bit
-        let bytes = if p_info.depth == 16 { 2 } else { 1 };
+        let depth = if p_info.depth == 16 { BitDepth::Sixteen } else { BitDepth::Eight };
 
         let out_n = self.get_colorspace()?.num_components();
 
-        info.width
-            .checked_mul(info.height)?
-            .checked_mul(out_n)?
-            .checked_mul(bytes)
+        checked_image_size(info.width, info.height, out_n, depth)
     }
 
     /// Get png information which was extracted from the headers
@@ -609,14 +938,15 @@ impl<T: ZReaderTrait> PngDecoder<T> {
             && self.png_info.depth == 16
             && self.options.png_get_strip_to_8bit()
         {
-            let image_len = self.output_buffer_size().unwrap();
+            let image_len = self.output_buffer_size().ok_or(TOO_LARGE_IMAGE)?;
 
             if out.len() < image_len {
                 return Err(PngDecodeErrors::TooSmallOutput(image_len, out.len()));
             }
             // allocate new size
-            let mut temp_alloc = vec![0; self.inner_buffer_size().unwrap()];
-            self.decode_into_inner(&mut temp_alloc)?;
+            let mut temp_alloc = vec![0; self.inner_buffer_size().ok_or(TOO_LARGE_IMAGE)?];
+            self.decode_into_inner(&mut temp_alloc)
+                .map_err(|err| self.with_frame_context(err))?;
 
             let out = &mut out[..image_len];
             // then convert it to 8 bit by taking top bit
@@ -626,6 +956,7 @@ impl<T: ZReaderTrait> PngDecoder<T> {
             return Ok(());
         }
         self.decode_into_inner(out)
+            .map_err(|err| self.with_frame_context(err))
     }
     fn decode_into_inner(&mut self, out: &mut [u8]) -> Result<(), PngDecodeErrors> {
         // decode headers
@@ -653,13 +984,17 @@ impl<T: ZReaderTrait> PngDecoder<T> {
         let out = &mut out[..image_len];
 
         // go parse IDAT chunks returning the inflate
+        let timer = StageTimer::start(self.options.get_collect_timings());
         let deflate_data = self.inflate()?;
+        self.timings.record("inflate", timer);
 
         // then release it, we no longer need it
         self.frames[self.current_frame].fdat = vec![];
         // remove idat chunks from memory
         // we are already done with them.
 
+        let timer = StageTimer::start(self.options.get_collect_timings());
+
         if png_info.interlace_method == InterlaceMethod::Standard {
             // allocate out to be enough to hold raw decoded bytes
             let dims = self.frame_info().unwrap();
@@ -668,6 +1003,13 @@ impl<T: ZReaderTrait> PngDecoder<T> {
         } else if png_info.interlace_method == InterlaceMethod::Adam7 {
             self.decode_interlaced(&deflate_data, out, &png_info, &info)?;
         }
+        self.timings.record("unfilter", timer);
+        // keep the allocation for the next frame
+        self.inflate_buffer = deflate_data;
+
+        if self.options.png_get_rescale_significant_bits() {
+            self.rescale_significant_bits(out);
+        }
 
         // convert to set endian if need be
         if self.get_depth().unwrap() == BitDepth::Sixteen {
@@ -692,15 +1034,57 @@ impl<T: ZReaderTrait> PngDecoder<T> {
     /// returns: `Result<Vec<u8, Global>, PngErrors>`
     ///
     pub fn decode_raw(&mut self) -> Result<Vec<u8>, PngDecodeErrors> {
+        let mut out = Vec::new();
+        self.decode_raw_into(&mut out)?;
+        Ok(out)
+    }
+
+    /// Decode the current frame into `buffers`, reusing their allocations
+    ///
+    /// This decodes the same pixels as [`decode_raw`](Self::decode_raw), which can be
+    /// retrieved with [`DecodeBuffers::image`]. The inflated data, un-filtering scratch space and
+    /// output are all kept in `buffers`, so decoding many images of similar sizes
+    /// with the same buffers avoids allocating them for every image.
+    ///
+    /// Interlaced images still allocate a buffer per Adam7 pass.
+    ///
+    /// # Errors
+    /// See [`decode_raw`](Self::decode_raw), on error the contents of [`DecodeBuffers::image`]
+    /// are unspecified
+    pub fn decode_reusing(&mut self, buffers: &mut DecodeBuffers) -> Result<(), PngDecodeErrors> {
+        self.inflate_buffer = core::mem::take(&mut buffers.inflate);
+        self.previous_stride = core::mem::take(&mut buffers.scratch);
+
+        let result = self.decode_raw_into(&mut buffers.image);
+
+        buffers.inflate = core::mem::take(&mut self.inflate_buffer);
+        buffers.scratch = core::mem::take(&mut self.previous_stride);
+
+        result
+    }
+
+    /// Decode the current frame into `out`, replacing its contents
+    fn decode_raw_into(&mut self, out: &mut Vec<u8>) -> Result<(), PngDecodeErrors> {
         self.decode_headers()?;
         self.called_from_decode_into = false;
 
+        if self.can_unfilter_in_place() {
+            let data = self
+                .decode_in_place()
+                .map_err(|err| self.with_frame_context(err))?;
+            // the image is the inflated data, so the old output becomes
+            // the inflate buffer of the next frame
+            self.inflate_buffer = core::mem::replace(out, data);
+            return Ok(());
+        }
+
         // allocate
-        let new_len = self.output_buffer_size().unwrap();
-        let t = self.inner_buffer_size().unwrap();
-        let mut out: Vec<u8> = vec![0; t];
+        let new_len = self.output_buffer_size().ok_or(TOO_LARGE_IMAGE)?;
+        let t = self.inner_buffer_size().ok_or(TOO_LARGE_IMAGE)?;
+        out.clear();
+        out.resize(t, 0);
         //decode
-        self.decode_into(&mut out)?;
+        self.decode_into(out)?;
         if self.options.png_get_strip_to_8bit() && self.png_info.depth == 16 {
             // in case we are to convert from 16 bit to 8 bit, we can do it here
             // we optimize it by using the same buffer the 16 bit data is stored in
@@ -721,7 +1105,177 @@ impl<T: ZReaderTrait> PngDecoder<T> {
             out.truncate(new_len);
         }
 
-        Ok(out)
+        Ok(())
+    }
+
+    /// Return true if the current frame can be un-filtered inside the inflated data
+    ///
+    /// This is the case for non-interlaced 8 bit images whose scanlines need
+    /// no post processing, where un-filtered rows are exactly as long as the filtered rows
+    /// without their filter byte
+    fn can_unfilter_in_place(&self) -> bool {
+        let info = &self.png_info;
+        let add_alpha_channel = self.options.png_get_add_alpha_channel() && !info.color.has_alpha();
+
+        info.depth == 8
+            && info.interlace_method == InterlaceMethod::Standard
+            && !(self.seen_trns || self.seen_ptle || add_alpha_channel)
+            && !self.composites_background()
+            && self.get_colorspace() == self.native_colorspace()
+            && self.frame_info().is_some()
+    }
+
+    /// Decode the current frame by un-filtering scanlines inside the inflated data,
+    /// so that the inflate output becomes the image without a separate allocation
+    /// and copy
+    ///
+    /// Callers must check [`can_unfilter_in_place`](Self::can_unfilter_in_place)
+    fn decode_in_place(&mut self) -> Result<Vec<u8>, PngDecodeErrors> {
+        let info = self.frame_info().unwrap();
+        let (width, height) = (info.width, info.height);
+
+        let components = usize::from(self.png_info.color.num_components());
+        let stride = checked_row_size(width, components, 8).ok_or(TOO_LARGE_IMAGE)?;
+        let image_len = stride.checked_mul(height).ok_or(TOO_LARGE_IMAGE)?;
+
+        // the size hint passed to inflate is exactly the filtered image size,
+        // so this is the only allocation holding the image
+        let timer = StageTimer::start(self.options.get_collect_timings());
+        let mut data = self.inflate()?;
+        self.timings.record("inflate", timer);
+        // then release it, we no longer need it
+        self.frames[self.current_frame].fdat = vec![];
+
+        // account for filter bytes
+        if data.len().saturating_sub(height) < image_len {
+            let msg = format!(
+                "Not enough pixels, expected {} but found {}",
+                image_len,
+                data.len()
+            );
+            return Err(PngDecodeErrors::Generic(msg));
+        }
+        let mut raw = core::mem::take(&mut self.previous_stride);
+        raw.resize(stride, 0);
+
+        let timer = StageTimer::start(self.options.get_collect_timings());
+        // Row `i` starts at `i * (stride + 1)` in the inflated data and at `i * stride` in
+        // the output, so the output row never reaches the next filtered row.
+        // The filtered row is copied out first since un-filtering overwrites it
+        for i in 0..height {
+            let in_start = i * (stride + 1);
+            let filter_byte = data[in_start];
+
+            raw.copy_from_slice(&data[in_start + 1..in_start + 1 + stride]);
+
+            let (prev, current) = data.split_at_mut(i * stride);
+            let prev_row = prev.get(prev.len().saturating_sub(stride)..).unwrap_or(&[]);
+
+            unfilter_row(
+                filter_byte,
+                i == 0,
+                prev_row,
+                &raw,
+                &mut current[..stride],
+                components,
+                self.options.use_sse2(),
+                self.options.use_sse41()
+            )?;
+        }
+        self.timings.record("unfilter", timer);
+        self.previous_stride = raw;
+
+        data.truncate(image_len);
+
+        if self.options.png_get_rescale_significant_bits() {
+            self.rescale_significant_bits(&mut data);
+        }
+        // one more frame decoded
+        self.current_frame += 1;
+
+        Ok(data)
+    }
+
+    /// Return the significant bits of each channel of the decoded image,
+    /// which may have been expanded from gray or had an alpha channel added
+    fn output_significant_bits(&self) -> Option<Vec<u8>> {
+        let bits = self.png_info.significant_bits.as_ref()?;
+        let colorspace = self.get_colorspace()?;
+        let components = colorspace.num_components();
+        // palettes are expanded to 8 bit samples
+        let depth = if self.png_info.color == PngColor::Palette { 8 } else { self.png_info.depth };
+        let gray = matches!(self.png_info.color, PngColor::Luma | PngColor::LumaA);
+
+        let output = (0..components)
+            .map(|i| {
+                if colorspace.has_alpha() && i == components - 1 {
+                    // an alpha channel added by the decoder is either opaque or transparent
+                    if self.png_info.color.has_alpha() {
+                        bits[bits.len() - 1]
+                    } else {
+                        depth
+                    }
+                } else if gray {
+                    bits[0]
+                } else {
+                    bits[i]
+                }
+            })
+            .collect();
+
+        Some(output)
+    }
+
+    /// Scale samples holding values in the range given by the `sBIT` chunk
+    /// to the full range of the image depth
+    ///
+    /// 16 bit samples in `out` are expected to be big endian
+    #[allow(clippy::cast_possible_truncation)]
+    fn rescale_significant_bits(&self, out: &mut [u8]) {
+        let Some(bits) = self.output_significant_bits() else {
+            return;
+        };
+        let depth = if self.png_info.color == PngColor::Palette { 8 } else { self.png_info.depth };
+
+        // samples below 8 bits are already expanded to the full range
+        if depth < 8 || bits.iter().all(|x| *x == depth) {
+            return;
+        }
+        let bytes = usize::from(depth / 8);
+        let full = (1_u32 << depth) - 1;
+        let max: Vec<u32> = bits.iter().map(|x| (1_u32 << x) - 1).collect();
+
+        let read = |sample: &[u8]| {
+            if bytes == 2 {
+                u32::from(u16::from_be_bytes([sample[0], sample[1]]))
+            } else {
+                u32::from(sample[0])
+            }
+        };
+        let exceeds = out
+            .chunks_exact(bytes)
+            .zip(max.iter().cycle())
+            .any(|(sample, max)| read(sample) > *max);
+
+        if exceeds {
+            warn!("Samples exceed the range of the sBIT chunk, assuming they are already scaled");
+            return;
+        }
+        trace!(
+            "Rescaling samples with {:?} significant bits to {} bits",
+            bits,
+            depth
+        );
+
+        for (sample, max) in out.chunks_exact_mut(bytes).zip(max.iter().cycle()) {
+            let value = (read(sample) * full + max / 2) / max;
+
+            if bytes == 2 {
+                sample.copy_from_slice(&(value as u16).to_be_bytes());
+            } else {
+                sample[0] = value as u8;
+            }
+        }
     }
 
     /// Return the **yet to be decoded** frame's frame information
@@ -762,75 +1316,136 @@ impl<T: ZReaderTrait> PngDecoder<T> {
     fn decode_interlaced(
         &mut self, deflate_data: &[u8], out: &mut [u8], info: &PngInfo, frame_info: &FrameInfo
     ) -> Result<(), PngDecodeErrors> {
-        const XORIG: [usize; 7] = [0, 4, 0, 2, 0, 1, 0];
-        const YORIG: [usize; 7] = [0, 0, 4, 0, 2, 0, 1];
-
-        const XSPC: [usize; 7] = [8, 8, 4, 4, 2, 2, 1];
-        const YSPC: [usize; 7] = [8, 8, 8, 4, 4, 2, 2];
-
         let bytes = if info.depth == 16 { 2 } else { 1 };
 
         let out_n = self.get_colorspace().unwrap().num_components();
 
-        let new_len = frame_info.width * frame_info.height * out_n * bytes;
-
-        // A mad idea would be to make this multithreaded :)
-        // They called me a mad man - Thanos
         let out_bytes = out_n * bytes;
 
+        let passes = adam7_passes(info, frame_info, deflate_data.len())?;
+
+        #[cfg(feature = "threads")]
+        {
+            if self.options.png_get_threaded_adam7() {
+                return self.decode_interlaced_threaded(
+                    deflate_data,
+                    out,
+                    info,
+                    frame_info,
+                    &passes
+                );
+            }
+        }
+
+        let new_len = frame_info.width * frame_info.height * out_n * bytes;
+
         // temporary space for  holding interlaced images
         let mut final_out = vec![0_u8; new_len];
 
-        let mut image_offset = 0;
-
-        // get the maximum height and width for the whole interlace part
-        for p in 0..7 {
-            let x = (frame_info
-                .width
-                .saturating_sub(XORIG[p])
-                .saturating_add(XSPC[p])
-                .saturating_sub(1))
-                / XSPC[p];
-
-            let y = (frame_info
-                .height
-                .saturating_sub(YORIG[p])
-                .saturating_add(YSPC[p])
-                .saturating_sub(1))
-                / YSPC[p];
-
-            if x != 0 && y != 0 {
-                let mut image_len = usize::from(info.color.num_components()) * x;
-
-                image_len *= usize::from(info.depth);
-                image_len += 7;
-                image_len /= 8;
-                image_len += 1; // filter byte
-                image_len *= y;
-
-                if image_offset + image_len > deflate_data.len() {
-                    return Err(PngDecodeErrors::GenericStatic("Too short data"));
-                }
+        for pass in &passes {
+            let deflate_slice = &deflate_data[pass.offset..pass.offset + pass.length];
 
-                let deflate_slice = &deflate_data[image_offset..image_offset + image_len];
+            self.create_png_image_raw(
+                deflate_slice,
+                pass.width,
+                pass.height,
+                &mut final_out,
+                info
+            )?;
 
-                self.create_png_image_raw(deflate_slice, x, y, &mut final_out, info)?;
+            for j in 0..pass.height {
+                for i in 0..pass.width {
+                    let out_y = j * ADAM7_YSPC[pass.index] + ADAM7_YORIG[pass.index];
+                    let out_x = i * ADAM7_XSPC[pass.index] + ADAM7_XORIG[pass.index];
 
-                for j in 0..y {
-                    for i in 0..x {
-                        let out_y = j * YSPC[p] + YORIG[p];
-                        let out_x = i * XSPC[p] + XORIG[p];
+                    let final_start = out_y * frame_info.width * out_bytes + out_x * out_bytes;
+                    let out_start = (j * pass.width + i) * out_bytes;
 
-                        let final_start = out_y * info.width * out_bytes + out_x * out_bytes;
-                        let out_start = (j * x + i) * out_bytes;
+                    out[final_start..final_start + out_bytes]
+                        .copy_from_slice(&final_out[out_start..out_start + out_bytes]);
+                }
+            }
+        }
+        Ok(())
+    }
 
-                        out[final_start..final_start + out_bytes]
-                            .copy_from_slice(&final_out[out_start..out_start + out_bytes]);
+    /// Decode Adam7 passes on multiple threads
+    ///
+    /// Passes are independent once inflated, so they are un-filtered in parallel,
+    /// and the output rows are then split between threads to de-interlace the passes into `out`.
+    ///
+    /// Threads come from the shared [`ThreadPool`] budget
+    #[cfg(feature = "threads")]
+    fn decode_interlaced_threaded(
+        &self, deflate_data: &[u8], out: &mut [u8], info: &PngInfo, frame_info: &FrameInfo,
+        passes: &[Adam7Pass]
+    ) -> Result<(), PngDecodeErrors> {
+        let bytes = if info.depth == 16 { 2 } else { 1 };
+        let out_bytes = self.get_colorspace().unwrap().num_components() * bytes;
+        let stride = frame_info.width * out_bytes;
+
+        let row_decoder = self.row_decoder();
+
+        let pool = ThreadPool::new();
+
+        // un-filter passes in parallel
+        let decoded = pool
+            .map(passes, |pass| {
+                let mut pass_out = vec![0_u8; pass.width * pass.height * out_bytes];
+                let mut previous_stride = vec![];
+
+                row_decoder.create_png_image_raw(
+                    &deflate_data[pass.offset..pass.offset + pass.length],
+                    pass.width,
+                    pass.height,
+                    &mut pass_out,
+                    info,
+                    &mut previous_stride
+                )?;
+                Ok(pass_out)
+            })
+            .into_iter()
+            .collect::<Result<Vec<Vec<u8>>, PngDecodeErrors>>()?;
+
+        // then split output rows between threads, copying pixels of
+        // every pass that lands on those rows
+        let rows_per_thread = frame_info.height.div_ceil(pool.num_threads()).max(1);
+        let decoded = &decoded;
+
+        pool.for_each(
+            out[..frame_info.height * stride]
+                .chunks_mut(rows_per_thread * stride)
+                .enumerate(),
+            |(i, rows)| {
+                let first_row = i * rows_per_thread;
+
+                for (k, row) in rows.chunks_exact_mut(stride).enumerate() {
+                    let out_y = first_row + k;
+
+                    for (pass, pixels) in passes.iter().zip(decoded) {
+                        let (yorig, yspc) = (ADAM7_YORIG[pass.index], ADAM7_YSPC[pass.index]);
+
+                        if out_y < yorig {
+                            continue;
+                        }
+                        let j = (out_y - yorig) / yspc;
+
+                        if j * yspc + yorig != out_y {
+                            // row not in this pass
+                            continue;
+                        }
+                        let pass_row =
+                            &pixels[j * pass.width * out_bytes..(j + 1) * pass.width * out_bytes];
+
+                        for (i, pixel) in pass_row.chunks_exact(out_bytes).enumerate() {
+                            let out_x = i * ADAM7_XSPC[pass.index] + ADAM7_XORIG[pass.index];
+
+                            row[out_x * out_bytes..(out_x + 1) * out_bytes].copy_from_slice(pixel);
+                        }
                     }
                 }
-                image_offset += image_len;
             }
-        }
+        );
         Ok(())
     }
 
@@ -878,14 +1493,17 @@ impl<T: ZReaderTrait> PngDecoder<T> {
         //  3 - We use bytemuck to to safe align, hence keeping the no unsafe mantra except
         //      for platform specific intrinsics
 
-        if !self.seen_headers || !self.seen_iend {
-            self.decode_headers()?;
-        }
+        self.decode_headers()?;
         // in case we are to strip 16 bit to 8 bit, use decode_raw which does that for us
         if self.options.png_get_strip_to_8bit() && self.png_info.depth == 16 {
             let bytes = self.decode_raw()?;
             return Ok(DecodingResult::U8(bytes));
         }
+        if self.can_unfilter_in_place()
+        {
+            let data = self.decode_in_place().map_err(|err| self.with_frame_context(err))?;
+            return Ok(DecodingResult::U8(data));
+        }
         // configure that the decoder converts samples to native endian
         if is_le()
         {
@@ -927,6 +1545,315 @@ impl<T: ZReaderTrait> PngDecoder<T> {
 
         Err(PngDecodeErrors::GenericStatic("Not implemented"))
     }
+    /// Create the png data from post deflated data
+    ///
+    /// See [`RowDecoder::create_png_image_raw`]
+    fn create_png_image_raw(
+        &mut self, deflate_data: &[u8], width: usize, height: usize, out: &mut [u8], info: &PngInfo
+    ) -> Result<(), PngDecodeErrors> {
+        // take the scratch space so that we can borrow the decoder immutably
+        let mut previous_stride = core::mem::take(&mut self.previous_stride);
+
+        let result = self.row_decoder().create_png_image_raw(
+            deflate_data,
+            width,
+            height,
+            out,
+            info,
+            &mut previous_stride
+        );
+        self.previous_stride = previous_stride;
+
+        result
+    }
+
+    /// Return the state needed to un-filter and post process scanlines
+    fn row_decoder(&self) -> RowDecoder<'_> {
+        RowDecoder {
+            options:           self.options,
+            palette:           &self.palette,
+            palette_entries:   self.palette_entries,
+            trns_bytes:        self.trns_bytes,
+            seen_trns:         self.seen_trns,
+            seen_ptle:         self.seen_ptle,
+            out_colorspace:    self.get_colorspace().unwrap(),
+            native_colorspace: self.native_colorspace().unwrap(),
+            background:        self.composite_color()
+        }
+    }
+
+    /// Undo deflate decoding
+    #[allow(clippy::manual_memcpy)]
+    fn inflate(&mut self) -> Result<Vec<u8>, PngDecodeErrors> {
+        let flat_data = &self.frames[self.current_frame];
+
+        // An annoying thing is that deflate doesn't
+        // store its uncompressed size,
+        // so we can't pre-allocate storage and pass that willy nilly
+        //
+        // Meaning we are left with some design choices
+        // 1. Have deflate resize at will
+        // 2. Have deflate return incomplete, to indicate we need to extend
+        // the vec, extend and go back to inflate.
+        //
+        //
+        // so choose point 1.
+        //
+        // This allows the zlib decoder to optimize its own paths(which it does)
+        // because it controls the allocation and doesn't have to check for near EOB
+        // runs.
+        //
+        // the exact size of the filtered frame, so the decoder allocates once
+        // and 8 bit images can be un-filtered in place
+        let filtered_len = self.filtered_len();
+        let size_hint = filtered_len.unwrap_or(0);
+
+        let option = DeflateOptions::default()
+            .set_size_hint(size_hint)
+            .set_limit(size_hint + 4 * (self.png_info.height))
+            .set_confirm_checksum(self.options.inflate_get_confirm_adler())
+            .set_confirm_header(
+                self.options
+                    .png_get_strictness()
+                    .contains(PngStrictness::ZLIB_HEADER)
+            );
+
+        let mut decoder = zune_inflate::DeflateDecoder::new_with_options(&flat_data.fdat, option);
+        decoder.set_output_buffer(core::mem::take(&mut self.inflate_buffer));
+
+        let mut data = match decoder.decode_zlib() {
+            Ok(data) => data,
+            // errors in complete data are corruption, not truncation
+            Err(err)
+                if self.options.get_allow_partial()
+                    && (self.truncated
+                        || matches!(err.error, DecodeErrorStatus::InsufficientData)) =>
+            {
+                warn!(
+                    "Image data ends early ({:?}), decoding what is present",
+                    err.error
+                );
+                self.truncated = true;
+                err.data
+            }
+            Err(err) => return Err(PngDecodeErrors::ZlibDecodeErrors(err))
+        };
+
+        if let Some(len) =
+            filtered_len.filter(|x| self.options.get_allow_partial() && data.len() < *x)
+        {
+            // missing scanlines become zeros with no filter,
+            // so they decode to zero and earlier rows are untouched
+            self.truncated = true;
+            data.resize(len, 0);
+        }
+        if self.truncated {
+            self.clear_invalid_rows(&mut data);
+        }
+        Ok(data)
+    }
+
+    /// Zero inflated data from the first scanline with an invalid filter byte
+    ///
+    /// Inflating a truncated stream may produce a few garbage bytes at the end,
+    /// this turns them into missing rows instead of an error
+    fn clear_invalid_rows(&self, data: &mut [u8]) {
+        let Some(info) = self.frame_info() else {
+            return;
+        };
+        let components = usize::from(self.png_info.color.num_components());
+        let depth = usize::from(self.png_info.depth);
+
+        // (offset, row length with the filter byte, rows) of every pass
+        let passes: Vec<(usize, usize, usize)> =
+            if self.png_info.interlace_method == InterlaceMethod::Adam7 {
+                adam7_passes(&self.png_info, &info, usize::MAX)
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|x| (x.offset, x.length / x.height, x.height))
+                    .collect()
+            } else {
+                checked_row_size(info.width, components, depth)
+                    .map(|x| (0, x + 1, info.height))
+                    .into_iter()
+                    .collect()
+            };
+
+        for (offset, row_len, rows) in passes {
+            for row in 0..rows {
+                let start = offset + row * row_len;
+
+                if data
+                    .get(start)
+                    .and_then(|x| FilterMethod::from_int(*x))
+                    .is_none()
+                {
+                    let start = start.min(data.len());
+                    data[start..].fill(0);
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Return the size of the inflated data of the current frame,
+    /// i.e scanlines plus their filter bytes, or `None` if it is unknown
+    fn filtered_len(&self) -> Option<usize> {
+        let info = self.frame_info()?;
+
+        if self.png_info.interlace_method == InterlaceMethod::Adam7 {
+            let passes = adam7_passes(&self.png_info, &info, usize::MAX).ok()?;
+
+            return Some(passes.iter().map(|x| x.length).sum());
+        }
+        let components = usize::from(self.png_info.color.num_components());
+
+        checked_row_size(info.width, components, usize::from(self.png_info.depth))?
+            .checked_add(1)?
+            .checked_mul(info.height)
+    }
+}
+
+/// Un-filter one scanline of `raw` bytes into `current`
+///
+/// `prev_row` is the previous un-filtered scanline, it is ignored for the first row
+#[allow(clippy::too_many_arguments)]
+fn unfilter_row(
+    filter_byte: u8, first_row: bool, prev_row: &[u8], raw: &[u8], current: &mut [u8],
+    components: usize, use_sse2: bool, use_sse4: bool
+) -> Result<(), PngDecodeErrors> {
+    // get it's type
+    let mut filter = FilterMethod::from_int(filter_byte)
+        .ok_or_else(|| PngDecodeErrors::Generic(format!("Unknown filter {filter_byte}")))?;
+
+    if first_row {
+        // match our filters to special filters for first row
+        // these special filters do not need the previous scanline and treat it
+        // as zero
+
+        if filter == FilterMethod::Paeth {
+            filter = FilterMethod::PaethFirst;
+        }
+        if filter == FilterMethod::Up {
+            // up for the first row becomes a memcpy
+            filter = FilterMethod::None;
+        }
+        if filter == FilterMethod::Average {
+            filter = FilterMethod::AvgFirst;
+        }
+    }
+
+    match filter {
+        FilterMethod::None => current[..raw.len()].copy_from_slice(raw),
+
+        FilterMethod::Average => handle_avg(prev_row, raw, current, components, use_sse4),
+
+        FilterMethod::Sub => handle_sub(raw, current, components, use_sse2),
+
+        FilterMethod::Up => handle_up(prev_row, raw, current),
+
+        FilterMethod::Paeth => handle_paeth(prev_row, raw, current, components, use_sse4),
+
+        FilterMethod::PaethFirst => handle_paeth_first(raw, current, components),
+
+        FilterMethod::AvgFirst => handle_avg_first(raw, current, components),
+
+        FilterMethod::Unknown => unreachable!()
+    }
+    Ok(())
+}
+
+/// Error returned when the size of the decoded image overflows a `usize`
+const TOO_LARGE_IMAGE: PngDecodeErrors =
+    PngDecodeErrors::GenericStatic("Image is too large to decode on this platform");
+
+const ADAM7_XORIG: [usize; 7] = [0, 4, 0, 2, 0, 1, 0];
+const ADAM7_YORIG: [usize; 7] = [0, 0, 4, 0, 2, 0, 1];
+
+const ADAM7_XSPC: [usize; 7] = [8, 8, 4, 4, 2, 2, 1];
+const ADAM7_YSPC: [usize; 7] = [8, 8, 8, 4, 4, 2, 2];
+
+/// A non-empty Adam7 pass of an interlaced image
+struct Adam7Pass {
+    /// Pass number, from 0 to 6
+    index:  usize,
+    width:  usize,
+    height: usize,
+    /// Offset of the pass in the inflated data
+    offset: usize,
+    /// Length of the pass in the inflated data, including filter bytes
+    length: usize
+}
+
+/// Return the non-empty Adam7 passes of a frame and their
+/// positions in the inflated data
+fn adam7_passes(
+    info: &PngInfo, frame_info: &FrameInfo, data_len: usize
+) -> Result<Vec<Adam7Pass>, PngDecodeErrors> {
+    let mut passes = Vec::with_capacity(7);
+    let mut image_offset = 0;
+
+    // get the maximum height and width for the whole interlace part
+    for p in 0..7 {
+        let x = (frame_info
+            .width
+            .saturating_sub(ADAM7_XORIG[p])
+            .saturating_add(ADAM7_XSPC[p])
+            .saturating_sub(1))
+            / ADAM7_XSPC[p];
+
+        let y = (frame_info
+            .height
+            .saturating_sub(ADAM7_YORIG[p])
+            .saturating_add(ADAM7_YSPC[p])
+            .saturating_sub(1))
+            / ADAM7_YSPC[p];
+
+        if x != 0 && y != 0 {
+            // row bytes plus the filter byte
+            let image_len = checked_row_size(
+                x,
+                usize::from(info.color.num_components()),
+                usize::from(info.depth)
+            )
+            .and_then(|row| (row + 1).checked_mul(y))
+            .ok_or(TOO_LARGE_IMAGE)?;
+
+            if image_len > data_len.saturating_sub(image_offset) {
+                return Err(PngDecodeErrors::GenericStatic("Too short data"));
+            }
+            passes.push(Adam7Pass {
+                index:  p,
+                width:  x,
+                height: y,
+                offset: image_offset,
+                length: image_len
+            });
+            image_offset += image_len;
+        }
+    }
+    Ok(passes)
+}
+
+/// State needed to un-filter and post process scanlines
+///
+/// This is separate from [`PngDecoder`] so that independent parts of an image,
+/// e.g Adam7 passes, can be decoded on multiple threads.
+#[derive(Copy, Clone)]
+struct RowDecoder<'a> {
+    options:           DecoderOptions,
+    palette:           &'a [PLTEEntry],
+    palette_entries:   usize,
+    trns_bytes:        [u16; 4],
+    seen_trns:         bool,
+    seen_ptle:         bool,
+    out_colorspace:    ColorSpace,
+    native_colorspace: ColorSpace,
+    /// Color to blend pixels onto, if the image is composited
+    background:        Option<[u16; 3]>
+}
+
+impl RowDecoder<'_> {
     /// Create the png data from post deflated data
     ///
     /// `out` needs to have enough space to hold data, otherwise
@@ -937,29 +1864,33 @@ impl<T: ZReaderTrait> PngDecoder<T> {
     /// away from this method to the caller of this method
     #[allow(clippy::manual_memcpy, clippy::comparison_chain)]
     fn create_png_image_raw(
-        &mut self, deflate_data: &[u8], width: usize, height: usize, out: &mut [u8], info: &PngInfo
+        &self, deflate_data: &[u8], width: usize, height: usize, out: &mut [u8], info: &PngInfo,
+        previous_stride: &mut Vec<u8>
     ) -> Result<(), PngDecodeErrors> {
         let use_sse4 = self.options.use_sse41();
         let use_sse2 = self.options.use_sse2();
+        let use_neon = self.options.use_neon();
+        // keep low bit depth samples in their original range instead of scaling them
+        let keep_raw = self.options.png_get_keep_raw_samples();
 
         let bytes = if info.depth == 16 { 2 } else { 1 };
+        let depth = if info.depth == 16 { BitDepth::Sixteen } else { BitDepth::Eight };
 
-        let out_colorspace = self.get_colorspace().unwrap();
-
-        let mut img_width_bytes;
+        let out_colorspace = self.out_colorspace;
+        let native_colorspace = self.native_colorspace;
+        // whether we convert to a user requested colorspace
+        let convert = out_colorspace != native_colorspace;
 
-        img_width_bytes = usize::from(info.component) * width;
-        img_width_bytes *= usize::from(info.depth);
-        img_width_bytes += 7;
-        img_width_bytes /= 8;
+        let img_width_bytes =
+            checked_row_size(width, usize::from(info.component), usize::from(info.depth))
+                .ok_or(TOO_LARGE_IMAGE)?;
 
         let out_n = usize::from(info.color.num_components());
 
-        let image_len = img_width_bytes * height;
+        let image_len = img_width_bytes.checked_mul(height).ok_or(TOO_LARGE_IMAGE)?;
 
-        if deflate_data.len() < image_len + height
         // account for filter bytes
-        {
+        if deflate_data.len().saturating_sub(height) < image_len {
             let msg = format!(
                 "Not enough pixels, expected {} but found {}",
                 image_len,
@@ -986,6 +1917,13 @@ impl<T: ZReaderTrait> PngDecoder<T> {
         chunk_size += 1;
 
         let out_chunk_size = width * out_colorspace.num_components() * bytes;
+        // size of a scanline before conversion to the output colorspace
+        let row_size = width * native_colorspace.num_components() * bytes;
+
+        // When converting colorspaces, scanlines are un-filtered and post processed
+        // in two alternating rows and converted into `out` once done, so that we
+        // still go through the image once
+        let mut rows = if convert { vec![0_u8; row_size * 2] } else { vec![] };
 
         // each chunk is a width stride of unfiltered data
         let chunks = deflate_data.chunks_exact(chunk_size);
@@ -1000,12 +1938,12 @@ impl<T: ZReaderTrait> PngDecoder<T> {
         let mut will_post_process = self.seen_trns | self.seen_ptle | (info.depth < 8);
 
         let add_alpha_channel =
-            self.options.png_get_add_alpha_channel() && (!self.png_info.color.has_alpha());
+            self.options.png_get_add_alpha_channel() && (!info.color.has_alpha());
 
-        will_post_process |= add_alpha_channel;
+        will_post_process |= add_alpha_channel | convert | self.background.is_some();
 
-        if will_post_process && self.previous_stride.len() < out_chunk_size {
-            self.previous_stride.resize(out_chunk_size, 0);
+        if will_post_process && previous_stride.len() < row_size {
+            previous_stride.resize(row_size, 0);
         }
         let n_components = usize::from(info.color.num_components());
 
@@ -1014,9 +1952,19 @@ impl<T: ZReaderTrait> PngDecoder<T> {
             // current points to the start of the row where we are writing de-filtered output to
             // prev is all rows we already wrote output to.
 
-            let (prev, mut current) = out.split_at_mut(out_position);
+            let (prev, mut current) = if convert {
+                let (first, second) = rows.split_at_mut(row_size);
+
+                if i % 2 == 0 {
+                    (second, first)
+                } else {
+                    (first, second)
+                }
+            } else {
+                out.split_at_mut(out_position)
+            };
 
-            current = &mut current[0..out_chunk_size];
+            current = &mut current[0..row_size];
 
             // get the previlet (w,h)ous row.
             //Set this to a dummy to handle special case of first row, if we aren't in the first
@@ -1024,9 +1972,14 @@ impl<T: ZReaderTrait> PngDecoder<T> {
             let mut prev_row: &[u8] = &[0_u8];
 
             if !first_row {
-                // normal bit depth, use the previous row as normal
-                prev_row = &prev[prev_row_start..prev_row_start + out_chunk_size];
-                prev_row_start += out_chunk_size;
+                if convert {
+                    // the other row holds the previous scanline
+                    prev_row = prev;
+                } else {
+                    // normal bit depth, use the previous row as normal
+                    prev_row = &prev[prev_row_start..prev_row_start + out_chunk_size];
+                    prev_row_start += out_chunk_size;
+                }
             }
 
             out_position += out_chunk_size;
@@ -1036,53 +1989,28 @@ impl<T: ZReaderTrait> PngDecoder<T> {
             // raw image bytes
             let raw = &in_stride[1..];
 
-            // get it's type
-            let mut filter = FilterMethod::from_int(filter_byte)
-                .ok_or_else(|| PngDecodeErrors::Generic(format!("Unknown filter {filter_byte}")))?;
-
-            if first_row {
-                // match our filters to special filters for first row
-                // these special filters do not need the previous scanline and treat it
-                // as zero
-
-                if filter == FilterMethod::Paeth {
-                    filter = FilterMethod::PaethFirst;
-                }
-                if filter == FilterMethod::Up {
-                    // up for the first row becomes a memcpy
-                    filter = FilterMethod::None;
-                }
-                if filter == FilterMethod::Average {
-                    filter = FilterMethod::AvgFirst;
-                }
-
-                first_row = false;
-            }
-
-            match filter {
-                FilterMethod::None => current[0..width_stride].copy_from_slice(raw),
-
-                FilterMethod::Average => handle_avg(prev_row, raw, current, components, use_sse4),
-
-                FilterMethod::Sub => handle_sub(raw, current, components, use_sse2),
-
-                FilterMethod::Up => handle_up(prev_row, raw, current),
-
-                FilterMethod::Paeth => handle_paeth(prev_row, raw, current, components, use_sse4),
-
-                FilterMethod::PaethFirst => handle_paeth_first(raw, current, components),
-
-                FilterMethod::AvgFirst => handle_avg_first(raw, current, components),
-
-                FilterMethod::Unknown => unreachable!()
-            }
+            unfilter_row(
+                filter_byte,
+                first_row,
+                prev_row,
+                raw,
+                current,
+                components,
+                use_sse2,
+                use_sse4
+            )?;
+            first_row = false;
 
             if will_post_process && i > 0 {
                 // run the post processor two scanlines behind so that we
                 // don't mess with any filters that require previous row
 
                 // read the row we are about to filter
-                let to_filter_row = &mut prev[(i - 1) * out_chunk_size..(i) * out_chunk_size];
+                let to_filter_row = if convert {
+                    prev
+                } else {
+                    &mut prev[(i - 1) * out_chunk_size..(i) * out_chunk_size]
+                };
 
                 if info.depth < 8 {
                     // check if we will run any other transform
@@ -1096,65 +2024,66 @@ impl<T: ZReaderTrait> PngDecoder<T> {
                             width,
                             usize::from(info.depth),
                             n_components,
-                            self.seen_ptle,
+                            self.seen_ptle || keep_raw,
                             to_filter_row,
-                            &mut self.previous_stride
+                            previous_stride
                         )
                     } else {
                         // no extra transform, just depth upscaling, so let's
                         // do that,
 
                         // copy the row to a temporary space
-                        self.previous_stride[..width_stride]
+                        previous_stride[..width_stride]
                             .copy_from_slice(&to_filter_row[..width_stride]);
 
                         expand_bits_to_byte(
                             width,
                             usize::from(info.depth),
                             n_components,
-                            self.seen_ptle,
-                            &self.previous_stride,
+                            self.seen_ptle || keep_raw,
+                            previous_stride,
                             to_filter_row
                         )
                     }
                 } else {
                     // copy the row to a temporary space
-                    self.previous_stride[..width_stride]
-                        .copy_from_slice(&to_filter_row[..width_stride]);
+                    previous_stride[..width_stride].copy_from_slice(&to_filter_row[..width_stride]);
                 }
 
-                if self.seen_trns && self.png_info.color != PngColor::Palette {
+                if self.seen_trns && info.color != PngColor::Palette {
                     // the expansion is a trns expansion
                     // bytes are already in position, so finish the business
 
                     if info.depth <= 8 {
                         expand_trns::<false>(
-                            &self.previous_stride,
+                            previous_stride,
                             to_filter_row,
                             info.color,
                             self.trns_bytes,
-                            info.depth
+                            info.depth,
+                            keep_raw
                         );
                     } else if info.depth == 16 {
                         // Tested by test_palette_trns_16bit.
                         expand_trns::<true>(
-                            &self.previous_stride,
+                            previous_stride,
                             to_filter_row,
                             info.color,
                             self.trns_bytes,
-                            info.depth
+                            info.depth,
+                            keep_raw
                         );
                     }
                 }
 
-                if self.seen_ptle && self.png_info.color == PngColor::Palette {
+                if self.seen_ptle && info.color == PngColor::Palette {
                     if self.palette.is_empty() {
                         return Err(PngDecodeErrors::EmptyPalette);
                     }
                     let plte_entry: &[PLTEEntry; 256] = self.palette[..256].try_into().unwrap();
 
                     // so now we have two things
-                    // the palette entries stored in self.previous_stride
+                    // the palette entries stored in previous_stride
                     // the row to fill the palette sored in to_filter row,
                     // so we can finally expand the entries
 
@@ -1168,19 +2097,44 @@ impl<T: ZReaderTrait> PngDecoder<T> {
                         //
                         // The palette is set that the alpha channel is initialized as 255 for non alpha
                         // images,
-                        expand_palette(&self.previous_stride, to_filter_row, plte_entry, 4);
+                        expand_palette(
+                            previous_stride,
+                            to_filter_row,
+                            plte_entry,
+                            self.palette_entries,
+                            4,
+                            use_sse4,
+                            use_neon
+                        );
                     } else {
                         // Normal expansion
-                        expand_palette(&self.previous_stride, to_filter_row, plte_entry, 3);
+                        expand_palette(
+                            previous_stride,
+                            to_filter_row,
+                            plte_entry,
+                            self.palette_entries,
+                            3,
+                            use_sse4,
+                            use_neon
+                        );
                     }
                 } else if add_alpha_channel {
                     // the image is a normal RGB/ Luma image, which we need to add the alpha channel
                     // do it here
-                    add_alpha(
-                        &self.previous_stride,
-                        to_filter_row,
-                        self.png_info.color,
-                        self.get_depth().unwrap()
+                    add_alpha(previous_stride, to_filter_row, info.color, depth);
+                }
+                if let Some(background) = self.background {
+                    composite_background(to_filter_row, native_colorspace, background, bytes);
+                }
+                if convert {
+                    let start = ((i - 1) % 2) * row_size;
+
+                    convert_colorspace(
+                        &rows[start..start + row_size],
+                        &mut out[(i - 1) * out_chunk_size..i * out_chunk_size],
+                        native_colorspace,
+                        out_colorspace,
+                        bytes
                     );
                 }
             }
@@ -1188,7 +2142,12 @@ impl<T: ZReaderTrait> PngDecoder<T> {
 
         if will_post_process {
             for i in height..height + min(height, 1) {
-                let to_filter_row = &mut out[(i - 1) * out_chunk_size..i * out_chunk_size];
+                let to_filter_row = if convert {
+                    let start = ((i - 1) % 2) * row_size;
+                    &mut rows[start..start + row_size]
+                } else {
+                    &mut out[(i - 1) * out_chunk_size..i * out_chunk_size]
+                };
 
                 // check if we will run any other transform
                 let extra_transform = self.seen_ptle | self.seen_trns;
@@ -1202,56 +2161,57 @@ impl<T: ZReaderTrait> PngDecoder<T> {
                             width,
                             usize::from(info.depth),
                             n_components,
-                            self.seen_ptle,
+                            self.seen_ptle || keep_raw,
                             to_filter_row,
-                            &mut self.previous_stride
+                            previous_stride
                         )
                     } else {
                         // no extra transform, just depth upscaling, so let's
                         // do that,
 
                         // copy the row to a temporary space
-                        self.previous_stride[..width_stride]
+                        previous_stride[..width_stride]
                             .copy_from_slice(&to_filter_row[..width_stride]);
 
                         expand_bits_to_byte(
                             width,
                             usize::from(info.depth),
                             n_components,
-                            self.seen_ptle,
-                            &self.previous_stride,
+                            self.seen_ptle || keep_raw,
+                            previous_stride,
                             to_filter_row
                         )
                     }
                 } else {
                     // copy the row to a temporary space
-                    self.previous_stride[..width_stride]
-                        .copy_from_slice(&to_filter_row[..width_stride]);
+                    previous_stride[..width_stride].copy_from_slice(&to_filter_row[..width_stride]);
                 }
-                if self.seen_trns && self.png_info.color != PngColor::Palette {
+                if self.seen_trns && info.color != PngColor::Palette {
                     // the expansion is a trns expansion
                     // bytes are already in position, so finish the business
 
                     if info.depth <= 8 {
                         expand_trns::<false>(
-                            &self.previous_stride,
+                            previous_stride,
                             to_filter_row,
                             info.color,
                             self.trns_bytes,
-                            info.depth
+                            info.depth,
+                            keep_raw
                         );
                     } else if info.depth == 16 {
                         // Tested by test_palette_trns_16bit.
                         expand_trns::<true>(
-                            &self.previous_stride,
+                            previous_stride,
                             to_filter_row,
                             info.color,
                             self.trns_bytes,
-                            info.depth
+                            info.depth,
+                            keep_raw
                         );
                     }
                 }
-                if self.seen_ptle && self.png_info.color == PngColor::Palette {
+                if self.seen_ptle && info.color == PngColor::Palette {
                     if self.palette.is_empty() {
                         return Err(PngDecodeErrors::EmptyPalette);
                     }
@@ -1259,60 +2219,45 @@ impl<T: ZReaderTrait> PngDecoder<T> {
                     let plte_entry: &[PLTEEntry; 256] = self.palette[..256].try_into().unwrap();
 
                     if self.seen_trns | add_alpha_channel {
-                        expand_palette(&self.previous_stride, to_filter_row, plte_entry, 4);
+                        expand_palette(
+                            previous_stride,
+                            to_filter_row,
+                            plte_entry,
+                            self.palette_entries,
+                            4,
+                            use_sse4,
+                            use_neon
+                        );
                     } else {
-                        expand_palette(&self.previous_stride, to_filter_row, plte_entry, 3);
+                        expand_palette(
+                            previous_stride,
+                            to_filter_row,
+                            plte_entry,
+                            self.palette_entries,
+                            3,
+                            use_sse4,
+                            use_neon
+                        );
                     }
                 } else if add_alpha_channel {
-                    add_alpha(
-                        &self.previous_stride,
-                        to_filter_row,
-                        self.png_info.color,
-                        self.get_depth().unwrap()
+                    add_alpha(previous_stride, to_filter_row, info.color, depth);
+                }
+                if let Some(background) = self.background {
+                    composite_background(to_filter_row, native_colorspace, background, bytes);
+                }
+                if convert {
+                    let start = ((i - 1) % 2) * row_size;
+
+                    convert_colorspace(
+                        &rows[start..start + row_size],
+                        &mut out[(i - 1) * out_chunk_size..i * out_chunk_size],
+                        native_colorspace,
+                        out_colorspace,
+                        bytes
                     );
                 }
             }
         }
         Ok(())
     }
-
-    /// Undo deflate decoding
-    #[allow(clippy::manual_memcpy)]
-    fn inflate(&mut self) -> Result<Vec<u8>, PngDecodeErrors> {
-        let flat_data = &self.frames[self.current_frame];
-
-        // An annoying thing is that deflate doesn't
-        // store its uncompressed size,
-        // so we can't pre-allocate storage and pass that willy nilly
-        //
-        // Meaning we are left with some design choices
-        // 1. Have deflate resize at will
-        // 2. Have deflate return incomplete, to indicate we need to extend
-        // the vec, extend and go back to inflate.
-        //
-        //
-        // so choose point 1.
-        //
-        // This allows the zlib decoder to optimize its own paths(which it does)
-        // because it controls the allocation and doesn't have to check for near EOB
-        // runs.
-        //
-        let depth_scale = if self.png_info.depth == 16 { 2 } else { 1 };
-
-        let size_hint = (self.png_info.width + 1)
-            * self.png_info.height
-            * depth_scale
-            * usize::from(self.png_info.color.num_components());
-
-        let option = DeflateOptions::default()
-            .set_size_hint(size_hint)
-            .set_limit(size_hint + 4 * (self.png_info.height))
-            .set_confirm_checksum(self.options.inflate_get_confirm_adler());
-
-        let mut decoder = zune_inflate::DeflateDecoder::new_with_options(&flat_data.fdat, option);
-
-        decoder
-            .decode_zlib()
-            .map_err(PngDecodeErrors::ZlibDecodeErrors)
-    }
 }
@@ -7,27 +7,37 @@
 use alloc::vec;
 use alloc::vec::Vec;
 
+use zune_core::bit_depth::BitDepth;
 use zune_core::bytestream::ZByteWriter;
+use zune_core::log::warn;
 use zune_core::options::EncoderOptions;
-use zune_inflate::DeflateEncoder;
+use zune_inflate::{DeflateEncodeOptions, DeflateEncoder};
 
 use crate::constants::PNG_SIGNATURE;
-use crate::decoder::PngChunk;
+use crate::decoder::{PhysicalDimensions, PngChunk};
 use crate::enums::{FilterMethod, PngChunkType};
 use crate::filters::{choose_compression_filter, filter_scanline};
 use crate::headers::writers::{
-    write_chunk, write_exif, write_gamma, write_header_fn, write_iend, write_ihdr
+    is_valid_keyword, write_chunk, write_exif, write_gamma, write_header_fn, write_iccp,
+    write_iend, write_ihdr, write_itxt, write_phys, write_sbit, write_text
 };
 
 #[derive(Default)]
 pub struct PngEncoder<'a> {
     pub(crate) options:         EncoderOptions,
     pub(crate) data:            &'a [u8],
+    /// `data` scaled to the 16 bit range, for 10 and 12 bit images
+    pub(crate) scaled_data:     Vec<u8>,
     pub(crate) row_filter:      FilterMethod,
     pub(crate) encoded_chunks:  Vec<u8>,
     pub(crate) filter_scanline: Vec<u8>,
     pub(crate) gamma:           Option<f32>,
-    pub(crate) exif:            Option<&'a [u8]>
+    /// zlib compressed ICC profile
+    pub(crate) icc_profile:     Option<Vec<u8>>,
+    pub(crate) physical_dims:   Option<PhysicalDimensions>,
+    pub(crate) exif:            Option<&'a [u8]>,
+    pub(crate) text:            Vec<(&'a str, &'a str)>,
+    pub(crate) deflate_options: DeflateEncodeOptions
 }
 
 impl<'a> PngEncoder<'a> {
@@ -38,6 +48,12 @@ impl<'a> PngEncoder<'a> {
     /// If you are encoding 16 bit data, it is expected that
     /// the data is laid  out in big endian (in order to avoid a
     /// potentially expensive clone and conversion step)
+    ///
+    /// # 10 and 12 bit images
+    ///
+    /// Data with a depth of [`BitDepth::Ten`] or [`BitDepth::Twelve`] is laid out
+    /// like 16 bit data with samples in the range of that depth, it is stored
+    /// as a 16 bit png whose `sBIT` chunk records the original depth
     pub fn new(data: &'a [u8], options: EncoderOptions) -> PngEncoder<'a> {
         PngEncoder {
             options,
@@ -47,11 +63,55 @@ impl<'a> PngEncoder<'a> {
         }
     }
 
+    /// Set the options used to compress image data and the ICC profile
+    ///
+    /// Defaults to [`DeflateEncodeOptions::default`], which uses the
+    /// [`Default`](zune_inflate::DeflateEncodingStrategy::Default) preset.
+    /// Earlier versions stored image data without compressing it, use the
+    /// [`NoCompression`](zune_inflate::DeflateEncodingStrategy::NoCompression)
+    /// preset to get that behaviour back, e.g when encoding speed matters more than size.
+    pub fn set_deflate_options(&mut self, options: DeflateEncodeOptions) {
+        self.deflate_options = options;
+    }
+
     /// Add exif data which will be encoded
     pub fn add_exif_segment(&mut self, exif: &'a [u8]) {
         self.exif = Some(exif);
     }
 
+    /// Set the gamma which will be stored in the `gAMA` chunk
+    ///
+    /// This is the value stored in the file, the inverse of the display
+    /// gamma, e.g `1.0/2.2` for images encoded for a display with a gamma of 2.2
+    pub fn set_gamma(&mut self, gamma: f32) {
+        self.gamma = Some(gamma);
+    }
+
+    /// Add an ICC profile which will be compressed and stored in the `iCCP` chunk
+    ///
+    /// The profile is compressed with the options current at the time of
+    /// the call, see [`set_deflate_options`](Self::set_deflate_options)
+    pub fn set_icc_profile(&mut self, icc: &[u8]) {
+        self.icc_profile =
+            Some(DeflateEncoder::new_with_options(icc, self.deflate_options).encode_zlib());
+    }
+
+    /// Set the pixel size or aspect ratio which will be stored in the `pHYs` chunk
+    pub fn set_physical_dimensions(&mut self, dimensions: PhysicalDimensions) {
+        self.physical_dims = Some(dimensions);
+    }
+
+    /// Add a textual key-value pair which will be encoded
+    ///
+    /// ASCII text is stored in a `tEXt` chunk, other text in an uncompressed
+    /// `iTXt` chunk.
+    ///
+    /// The keyword must be 1-79 printable Latin-1 characters, with no leading, trailing
+    /// or consecutive spaces, pairs with invalid keywords are not written
+    pub fn add_text_chunk(&mut self, keyword: &'a str, text: &'a str) {
+        self.text.push((keyword, text));
+    }
+
     pub fn encode_headers(&self, writer: &mut ZByteWriter) {
         // write signature
         writer.write_u64_be(PNG_SIGNATURE);
@@ -60,12 +120,39 @@ impl<'a> PngEncoder<'a> {
 
         // extra headers
         // need to check their existence because  write_header_fn will do
-        // some writing even if they don't exist
+        // some writing even if they don't exist.
+        //
+        // They are written in an order that satisfies the spec, color information
+        // (gAMA, iCCP and sBIT) before PLTE and everything else before IDAT
+        if self.gamma.is_some() {
+            write_header_fn(self, writer, b"gAMA", write_gamma);
+        }
+        if self.icc_profile.is_some() {
+            write_header_fn(self, writer, b"iCCP", write_iccp);
+        }
+        if matches!(self.options.get_depth(), BitDepth::Ten | BitDepth::Twelve) {
+            write_header_fn(self, writer, b"sBIT", write_sbit);
+        }
+        if self.physical_dims.is_some() {
+            write_header_fn(self, writer, b"pHYs", write_phys);
+        }
         if self.exif.is_some() {
             write_header_fn(self, writer, b"eXIf", write_exif);
         }
-        if self.gamma.is_some() {
-            write_header_fn(self, writer, b"gAMA", write_gamma);
+        for (keyword, text) in &self.text {
+            if !is_valid_keyword(keyword) {
+                warn!("Invalid text chunk keyword {keyword:?}, not writing it");
+                continue;
+            }
+            if text.is_ascii() {
+                write_header_fn(self, writer, b"tEXt", |_, writer| {
+                    write_text(keyword, text, writer)
+                });
+            } else {
+                write_header_fn(self, writer, b"iTXt", |_, writer| {
+                    write_itxt(keyword, text, writer)
+                });
+            }
         }
     }
 
@@ -97,6 +184,15 @@ impl<'a> PngEncoder<'a> {
         if let Some(exif) = self.exif {
             out_dims += exif.len() + 40;
         }
+        if let Some(icc) = &self.icc_profile {
+            // keyword, separators and chunk overhead
+            out_dims += icc.len() + 40;
+        }
+        for (keyword, text) in &self.text {
+            // Latin-1 keywords are never longer than their UTF-8 form, the rest covers
+            // separators and chunk overhead
+            out_dims += keyword.len() + text.len() + 40;
+        }
 
         vec![0; out_dims]
     }
@@ -106,6 +202,9 @@ impl<'a> PngEncoder<'a> {
 
         self.encode_headers(&mut writer);
 
+        if matches!(self.options.get_depth(), BitDepth::Ten | BitDepth::Twelve) {
+            self.scale_to_sixteen_bits();
+        }
         // encode filters
         self.add_filters();
 
@@ -125,6 +224,26 @@ impl<'a> PngEncoder<'a> {
             * self.options.get_colorspace().num_components()
     }
 
+    /// Scale 10 and 12 bit samples to the 16 bit range by left bit replication,
+    /// the way the png specification recommends, so that shifting right
+    /// by the number of missing bits recovers the original samples
+    fn scale_to_sixteen_bits(&mut self) {
+        let depth = self.options.get_depth();
+        let max = depth.max_value();
+        let shift = 16 - depth.significant_bits();
+        // replicate the top bits into the low bits
+        let replicate = depth.significant_bits() - shift;
+
+        self.scaled_data = self
+            .data
+            .chunks_exact(2)
+            .flat_map(|x| {
+                let sample = u16::from_be_bytes([x[0], x[1]]).min(max);
+                ((sample << shift) | (sample >> replicate)).to_be_bytes()
+            })
+            .collect();
+    }
+
     fn add_filters(&mut self) {
         let scanline_length = (self.calculate_scanline_size() + 1)
             .checked_mul(self.options.get_height())
@@ -138,6 +257,8 @@ impl<'a> PngEncoder<'a> {
         // one row above the current processing row
         let mut previous_scanline: &[u8] = &[];
 
+        let data = if self.scaled_data.is_empty() { self.data } else { &self.scaled_data };
+
         let scanline_size = self.calculate_scanline_size();
 
         for (i, filter_s) in self
@@ -146,7 +267,7 @@ impl<'a> PngEncoder<'a> {
             .take(self.options.get_height())
             .enumerate()
         {
-            let (previous, current) = self.data.split_at(i * scanline_size);
+            let (previous, current) = data.split_at(i * scanline_size);
 
             if i > 0 {
                 // previous row now becomes defined
@@ -164,7 +285,9 @@ impl<'a> PngEncoder<'a> {
             );
         }
         // encode filtered scanline
-        self.encoded_chunks = DeflateEncoder::new(&self.filter_scanline).encode_zlib();
+        self.encoded_chunks =
+            DeflateEncoder::new_with_options(&self.filter_scanline, self.deflate_options)
+                .encode_zlib();
     }
     fn write_idat_chunks(&self, writer: &mut ZByteWriter) {
         debug_assert!(!self.encoded_chunks.is_empty());
@@ -7,9 +7,12 @@
  */
 
 //! Errors possible during png operations
+use alloc::boxed::Box;
 use alloc::string::String;
 use core::fmt::{Debug, Display, Formatter};
 
+use zune_core::diagnostics::ByteContext;
+
 /// Errors possible during decoding
 pub enum PngDecodeErrors {
     /// Image signature is not png signature
@@ -27,7 +30,66 @@ pub enum PngDecodeErrors {
     /// Unsupported Animated PNG
     UnsupportedAPNGImage,
     /// Too small output slice
-    TooSmallOutput(usize, usize)
+    TooSmallOutput(usize, usize),
+    /// An error with the bytes around the position it occurred at
+    ///
+    /// Only returned when the crate is built with the `diagnostics` feature
+    /// and [`DecoderOptions::set_error_context_size`] is not zero
+    ///
+    /// [`DecoderOptions::set_error_context_size`]: zune_core::options::DecoderOptions::set_error_context_size
+    WithContext(Box<PngDecodeErrors>, ByteContext)
+}
+
+/// Where in the stream an error occurred, see [`PngDecoder::error_location`]
+///
+/// [`PngDecoder::error_location`]: crate::PngDecoder::error_location
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ErrorLocation {
+    /// Offset from the start of the stream the decoder had reached
+    ///
+    /// Errors found while decompressing or un-filtering image data point to the
+    /// first chunk holding the frame data, since by then all chunks have been read
+    pub offset:       usize,
+    /// Type of the chunk being read, e.g `*b"IDAT"`, or `None` for errors before the first chunk
+    pub chunk:        Option<[u8; 4]>,
+    /// Offset of the start of that chunk, i.e its length field
+    pub chunk_offset: usize
+}
+
+impl Display for ErrorLocation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "offset {:#x}", self.offset)?;
+
+        if let Some(chunk) = self.chunk {
+            write!(
+                f,
+                " in the {} chunk starting at {:#x}",
+                String::from_utf8_lossy(&chunk),
+                self.chunk_offset
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl PngDecodeErrors {
+    /// Return the bytes around the position decoding failed at, if known
+    ///
+    /// This requires the `diagnostics` feature, and is only present for errors
+    /// raised while reading chunks
+    pub fn context(&self) -> Option<&ByteContext> {
+        match self {
+            Self::WithContext(_, context) => Some(context),
+            _ => None
+        }
+    }
+    /// Return the error without the bytes around its position
+    pub fn without_context(&self) -> &PngDecodeErrors {
+        match self {
+            Self::WithContext(err, _) => err,
+            err => err
+        }
+    }
 }
 
 impl Display for PngDecodeErrors {
@@ -61,6 +123,7 @@ impl Debug for PngDecodeErrors {
             Self::TooSmallOutput(expected, found) => {
                 write!(f, "Too small output, expected buffer with at least {expected} bytes but got one with {found} bytes")
             }
+            Self::WithContext(err, _) => write!(f, "{err:?}"),
         }
     }
 }
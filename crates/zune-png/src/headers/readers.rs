@@ -6,12 +6,16 @@
 
 use alloc::{format, vec};
 
+use zune_core::bit_depth::BitDepth;
 use zune_core::bytestream::ZReaderTrait;
 use zune_core::log::{trace, warn};
+use zune_core::utils::checked_image_size;
 use zune_inflate::DeflateDecoder;
 
 use crate::apng::{ActlChunk, BlendOp, DisposeOp, FrameInfo, SingleFrame};
-use crate::decoder::{ItxtChunk, PLTEEntry, PngChunk, TextChunk, TimeInfo, ZtxtChunk};
+use crate::decoder::{
+    ItxtChunk, PLTEEntry, PhysicalDimensions, PngChunk, TextChunk, TimeInfo, ZtxtChunk
+};
 use crate::enums::{FilterMethod, InterlaceMethod, PngChunkType, PngColor};
 use crate::error::PngDecodeErrors;
 use crate::PngDecoder;
@@ -121,6 +125,20 @@ impl<T: ZReaderTrait> PngDecoder<T> {
 
         self.seen_hdr = true;
 
+        // reject images whose decoded size can't be represented, on 32 bit targets
+        // it would wrap and under-allocate the output
+        let components = self.get_colorspace().map_or(4, |x| x.num_components());
+        let depth = if self.png_info.depth == 16 { BitDepth::Sixteen } else { BitDepth::Eight };
+
+        if checked_image_size(self.png_info.width, self.png_info.height, components, depth)
+            .is_none()
+        {
+            return Err(PngDecodeErrors::Generic(format!(
+                "Image dimensions {}x{} are too large to decode on this platform",
+                self.png_info.width, self.png_info.height
+            )));
+        }
+
         let frame_info = FrameInfo {
             seq_number:     -1,
             width:          self.png_info.width,
@@ -155,6 +173,8 @@ impl<T: ZReaderTrait> PngDecoder<T> {
             pal_chunk.blue = self.stream.get_u8();
         }
 
+        self.palette_entries = (chunk.length / 3).min(256);
+
         // skip crc chunk
         self.stream.skip(4);
         self.seen_ptle = true;
@@ -165,6 +185,10 @@ impl<T: ZReaderTrait> PngDecoder<T> {
         if self.frames.is_empty() {
             self.frames.push(SingleFrame::new(vec![], None));
         }
+        if self.frame_locations.is_empty() {
+            // idat chunks hold the first frame
+            self.frame_locations.extend(self.chunk_location);
+        }
         // get a reference to the IDAT chunk stream and push it,
         // we will later pass these to the deflate decoder as a whole, to get the whole
         // uncompressed stream.
@@ -239,6 +263,110 @@ impl<T: ZReaderTrait> PngDecoder<T> {
         Ok(())
     }
 
+    /// Parse the significant bits chunk
+    pub(crate) fn parse_sbit(&mut self, chunk: PngChunk) -> Result<(), PngDecodeErrors> {
+        // palette entries are always 8 bits
+        let (components, max_bits) = match self.png_info.color {
+            PngColor::Palette => (3, 8),
+            color => (usize::from(color.num_components()), self.png_info.depth)
+        };
+        if chunk.length != components {
+            let error = format!(
+                "sBIT chunk length is {} but colour type {:?} needs {components}",
+                chunk.length, self.png_info.color
+            );
+            if self.options.get_strict_mode() {
+                return Err(PngDecodeErrors::Generic(error));
+            }
+            warn!("{}, ignoring it", error);
+            self.stream.skip(chunk.length + 4);
+            return Ok(());
+        }
+        let bits = self.stream.peek_at(0, components).unwrap().to_vec();
+        // skip bits and crc
+        self.stream.skip(components + 4);
+
+        if bits.iter().any(|x| *x == 0 || *x > max_bits) {
+            let error = format!("Invalid sBIT values {bits:?} for a {max_bits} bit image");
+
+            if self.options.get_strict_mode() {
+                return Err(PngDecodeErrors::Generic(error));
+            }
+            warn!("{}, ignoring them", error);
+            return Ok(());
+        }
+        self.png_info.significant_bits = Some(bits);
+
+        Ok(())
+    }
+
+    /// Parse the background color chunk
+    pub(crate) fn parse_bkgd(&mut self, chunk: PngChunk) -> Result<(), PngDecodeErrors> {
+        let length = match self.png_info.color {
+            PngColor::Palette => 1,
+            PngColor::Luma | PngColor::LumaA => 2,
+            _ => 6
+        };
+        if chunk.length != length {
+            let error = format!(
+                "bKGD chunk length is {} but colour type {:?} needs {length}",
+                chunk.length, self.png_info.color
+            );
+            if self.options.get_strict_mode() {
+                return Err(PngDecodeErrors::Generic(error));
+            }
+            warn!("{}, ignoring it", error);
+            self.stream.skip(chunk.length + 4);
+            return Ok(());
+        }
+        let depth = self.png_info.depth;
+        // largest sample value of the image, samples above it are clamped
+        let max_value = (1_u32 << depth) - 1;
+
+        let background = match self.png_info.color {
+            PngColor::Palette => {
+                let index = usize::from(self.stream.get_u8());
+
+                // the palette always holds 256 entries once seen
+                self.seen_ptle.then(|| {
+                    let entry = self.palette[index];
+                    [entry.red, entry.green, entry.blue].map(u16::from)
+                })
+            }
+            PngColor::Luma | PngColor::LumaA => {
+                let mut gray = u32::from(self.stream.get_u16_be()).min(max_value);
+
+                if depth < 8 && !self.options.png_get_keep_raw_samples() {
+                    // match the scaling of low bit depth samples
+                    gray = gray * 255 / max_value;
+                }
+                Some([gray as u16; 3])
+            }
+            _ => {
+                let mut color = [0; 3];
+
+                for value in &mut color {
+                    *value = u32::from(self.stream.get_u16_be()).min(max_value) as u16;
+                }
+                Some(color)
+            }
+        };
+        // skip crc
+        self.stream.skip(4);
+
+        if background.is_none() {
+            let error = "bKGD chunk of a palette image appears before the palette";
+
+            if self.options.get_strict_mode() {
+                return Err(PngDecodeErrors::GenericStatic(error));
+            }
+            warn!("{}, ignoring it", error);
+        }
+        self.png_info.background = background;
+
+        Ok(())
+    }
+
     /// Parse the animation control chunk
     pub(crate) fn parse_actl(&mut self, chunk: PngChunk) -> Result<(), PngDecodeErrors> {
         if chunk.length != 8 {
@@ -261,6 +389,32 @@ impl<T: ZReaderTrait> PngDecoder<T> {
         Ok(())
     }
 
+    /// Parse the pHYs chunk
+    pub(crate) fn parse_phys(&mut self, chunk: PngChunk) -> Result<(), PngDecodeErrors> {
+        if chunk.length != 9 {
+            if self.options.get_strict_mode() {
+                return Err(PngDecodeErrors::GenericStatic("Invalid pHYs chunk length"));
+            }
+            warn!("Invalid pHYs chunk length {:?}", chunk.length);
+            // skip chunk + crc
+            self.stream.skip(chunk.length + 4);
+            return Ok(());
+        }
+        let x_pixels_per_unit = self.stream.get_u32_be();
+        let y_pixels_per_unit = self.stream.get_u32_be();
+        let unit_is_meter = self.stream.get_u8() == 1;
+
+        self.png_info.physical_dimensions = Some(PhysicalDimensions {
+            x_pixels_per_unit,
+            y_pixels_per_unit,
+            unit_is_meter
+        });
+        // skip past crc
+        self.stream.skip(4);
+
+        Ok(())
+    }
+
     /// Parse the tIME chunk if present in PNG
     pub(crate) fn parse_time(&mut self, chunk: PngChunk) -> Result<(), PngDecodeErrors> {
         if chunk.length != 7 {
@@ -490,6 +644,7 @@ impl<T: ZReaderTrait> PngDecoder<T> {
                 // moves behind chunk length and chunk header
                 // the caller will read it as IEND and terminate
                 self.stream.rewind(8);
+                self.chunks_read -= 1;
                 self.seen_iend = true;
                 break;
             }
@@ -504,6 +659,7 @@ impl<T: ZReaderTrait> PngDecoder<T> {
                 // we will decode the frame we have before we
                 // go to the next frame
                 self.stream.rewind(8);
+                self.chunks_read -= 1;
                 break;
             } else if next_header.chunk_type == PngChunkType::fdAT {
                 if should_add_fctl {
@@ -511,6 +667,7 @@ impl<T: ZReaderTrait> PngDecoder<T> {
                     //
                     // captures fctl->fdat sequence of apng
                     self.frames.push(SingleFrame::new(vec![], Some(fctl_info)));
+                    self.frame_locations.extend(self.chunk_location);
                 }
                 // get frame data
                 // skip four  bytes since it's usually sequence number
@@ -5,9 +5,9 @@
  */
 
 use zune_core::bytestream::ZByteWriter;
+use zune_core::checksum::crc32;
 use zune_core::colorspace::ColorSpace;
 
-use crate::crc::calc_crc;
 use crate::decoder::PngChunk;
 use crate::encoder::PngEncoder;
 
@@ -50,6 +50,76 @@ pub fn write_gamma(ctx: &PngEncoder, writer: &mut ZByteWriter) {
     }
 }
 
+/// Write the already compressed ICC profile
+pub(crate) fn write_iccp(ctx: &PngEncoder, writer: &mut ZByteWriter) {
+    if let Some(icc) = &ctx.icc_profile {
+        // the profile name is only informative
+        write_keyword("ICC Profile", writer);
+        // compression method, zlib
+        writer.write_u8(0);
+        writer.write_all(icc).unwrap();
+    }
+}
+
+pub(crate) fn write_phys(ctx: &PngEncoder, writer: &mut ZByteWriter) {
+    if let Some(dims) = ctx.physical_dims {
+        writer.write_u32_be(dims.x_pixels_per_unit);
+        writer.write_u32_be(dims.y_pixels_per_unit);
+        writer.write_u8(u8::from(dims.unit_is_meter));
+    }
+}
+
+/// Write the number of significant bits of each channel, used for
+/// 10 and 12 bit images stored as 16 bit
+pub(crate) fn write_sbit(ctx: &PngEncoder, writer: &mut ZByteWriter) {
+    let bits = ctx.options.get_depth().significant_bits() as u8;
+
+    for _ in 0..ctx.options.get_colorspace().num_components() {
+        writer.write_u8(bits);
+    }
+}
+
+/// Return true if `keyword` can be used as a tEXt or iTXt keyword
+///
+/// Keywords are 1-79 printable Latin-1 characters with no leading, trailing
+/// or consecutive spaces
+pub(crate) fn is_valid_keyword(keyword: &str) -> bool {
+    let printable = keyword
+        .chars()
+        .all(|x| matches!(u32::from(x), 32..=126 | 161..=255));
+
+    printable
+        && (1..=79).contains(&keyword.chars().count())
+        && !keyword.starts_with(' ')
+        && !keyword.ends_with(' ')
+        && !keyword.contains("  ")
+}
+
+/// Write a Latin-1 keyword followed by its null separator
+fn write_keyword(keyword: &str, writer: &mut ZByteWriter) {
+    for c in keyword.chars() {
+        // validated by is_valid_keyword
+        writer.write_u8(u32::from(c) as u8);
+    }
+    writer.write_u8(0);
+}
+
+pub(crate) fn write_text(keyword: &str, text: &str, writer: &mut ZByteWriter) {
+    write_keyword(keyword, writer);
+    writer.write_all(text.as_bytes()).unwrap();
+}
+
+pub(crate) fn write_itxt(keyword: &str, text: &str, writer: &mut ZByteWriter) {
+    write_keyword(keyword, writer);
+    // uncompressed, compression method
+    writer.write_u8(0);
+    writer.write_u8(0);
+    // empty language tag and translated keyword
+    writer.write_u8(0);
+    writer.write_u8(0);
+    writer.write_all(text.as_bytes()).unwrap();
+}
+
 // iend is a no-op
 pub fn write_iend(_: &PngEncoder, _: &mut ZByteWriter) {}
 
@@ -89,10 +159,10 @@ pub fn write_header_fn<F: Fn(&PngEncoder, &mut ZByteWriter)>(
     writer.set_position(start);
 
     let bytes = writer.peek_at(0, length).unwrap();
-    let crc32 = calc_crc(bytes);
+    let crc = crc32::crc32(bytes);
 
     writer.set_position(end);
-    writer.write_u32_be(crc32);
+    writer.write_u32_be(crc);
 }
 
 pub(crate) fn write_chunk(chunk: PngChunk, data: &[u8], writer: &mut ZByteWriter) {
@@ -111,9 +181,9 @@ pub(crate) fn write_chunk(chunk: PngChunk, data: &[u8], writer: &mut ZByteWriter
     // get everything until where we wrote
 
     let data = writer.peek_at(0, 4/*name*/ + data.len()).unwrap();
-    let crc32 = calc_crc(data);
+    let crc = crc32::crc32(data);
     // go back to bytes past data
     writer.set_position(end);
     // and write crc32
-    writer.write_u32_be(crc32);
+    writer.write_u32_be(crc);
 }
@@ -16,6 +16,8 @@
 //! - Platform specific intrinsics for accelerated decoding on x86
 //! - Endian aware decoding support.
 //! - Support for animated PNG and post processing of the same
+//! - Multi-threaded de-interlacing of Adam7 images (behind the `threads` feature)
+//! - Reusing buffers across decodes with [`DecodeBuffers`]
 //!
 //!
 //! ## portable-simd
@@ -137,6 +139,74 @@
 //! assert!(decoder.get_colorspace().unwrap().has_alpha());
 //! ```
 //!
+//! ### Decoding to a specific colorspace
+//!
+//! The decoder can also expand Luma to RGB and add or strip the alpha channel
+//! while un-filtering, without a second pass over the image.
+//!
+//!```no_run
+//! use zune_core::colorspace::ColorSpace;
+//! use zune_core::options::DecoderOptions;
+//! use zune_png::PngDecoder;
+//! // always output RGBA, be it from Luma, LumaA, RGB or palette images
+//! let options = DecoderOptions::default().png_set_out_colorspace(ColorSpace::RGBA);
+//! let mut decoder = PngDecoder::new_with_options(&[],options);
+//!
+//! decoder.decode().unwrap();
+//! assert_eq!(decoder.get_colorspace(),Some(ColorSpace::RGBA));
+//! ```
+//!
+//! ### Low bit depth grayscale images
+//!
+//! Grayscale images with 1, 2 or 4 bits per sample are expanded to a byte per sample
+//! and scaled to the full 8 bit range, e.g a 2 bit sample of `3` becomes `255`.
+//!
+//! To get the stored values instead, e.g. for masks or label maps, use
+//! [`DecoderOptions::png_set_keep_raw_samples`](zune_core::options::DecoderOptions::png_set_keep_raw_samples)
+//!
+//!```no_run
+//! use zune_core::options::DecoderOptions;
+//! use zune_png::PngDecoder;
+//! // a 2 bit sample of 3 stays 3
+//! let options = DecoderOptions::default().png_set_keep_raw_samples(true);
+//! let mut decoder = PngDecoder::new_with_options(&[],options);
+//!
+//! let pixels = decoder.decode_raw();
+//! ```
+//!
+//! ### Significant bits
+//!
+//! Images with an `sBIT` chunk store samples with fewer bits than their depth, e.g 12 bit
+//! scans in a 16 bit png. The bits of each channel are in [`PngInfo::significant_bits`].
+//!
+//! Some writers store such samples without scaling them to the full range, to scale them use
+//! [`DecoderOptions::png_set_rescale_significant_bits`](zune_core::options::DecoderOptions::png_set_rescale_significant_bits)
+//!
+//!```no_run
+//! use zune_core::options::DecoderOptions;
+//! use zune_png::PngDecoder;
+//! // a 12 bit sample of 4095 becomes 65535
+//! let options = DecoderOptions::default().png_set_rescale_significant_bits(true);
+//! let mut decoder = PngDecoder::new_with_options(&[],options);
+//!
+//! let pixels = decoder.decode_raw();
+//! ```
+//!
+//! ### Compositing onto the background color
+//!
+//! Images with transparency can be blended onto the color of their `bKGD` chunk,
+//! or white if they have none, while decoding. The alpha channel is then dropped,
+//! so an RGBA image decodes to RGB.
+//!
+//!```no_run
+//! use zune_core::options::DecoderOptions;
+//! use zune_png::PngDecoder;
+//! let options = DecoderOptions::default().png_set_composite_background(true);
+//! let mut decoder = PngDecoder::new_with_options(&[],options);
+//!
+//! let pixels = decoder.decode_raw();
+//! ```
+//!
 //! # Extracting metadata
 //!
 //! Once headers have been decoded, image metadata can be accessed via [`get_info()`](PngDecoder::get_info) method
@@ -164,14 +234,18 @@ extern crate core;
 #[cfg(feature = "std")]
 pub use apng::post_process_image;
 pub use apng::{BlendOp, DisposeOp};
-pub use decoder::{ItxtChunk, PngDecoder, PngInfo, TextChunk, TimeInfo, ZtxtChunk};
+pub use buffers::DecodeBuffers;
+pub use decoder::{
+    ItxtChunk, PhysicalDimensions, PngDecoder, PngInfo, TextChunk, TimeInfo, ZtxtChunk
+};
 pub use encoder::PngEncoder;
 pub use enums::InterlaceMethod;
 pub use zune_core;
+pub use zune_inflate::{DeflateEncodeOptions, DeflateEncodingStrategy};
 
 mod apng;
+mod buffers;
 mod constants;
-mod crc;
 mod decoder;
 mod encoder;
 mod enums;
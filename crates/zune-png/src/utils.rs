@@ -7,11 +7,13 @@
 //! Utilities required by multiple implementations
 //! that help to do small things
 use zune_core::bit_depth::{BitDepth, ByteEndian};
+use zune_core::colorspace::ColorSpace;
 
 use crate::decoder::PLTEEntry;
 use crate::enums::PngColor;
 
 mod avx;
+mod neon;
 mod sse;
 
 /// scalar impl of big-endian to native endian
@@ -84,7 +86,40 @@ pub const fn is_le() -> bool {
     u16::from_le_bytes([234, 231]) == u16::from_ne_bytes([234, 231])
 }
 
+/// Expand palette indices in `input` to `components` (3 or 4) samples per pixel in `out`
+///
+/// # Arguments
+///
+/// * `entries`: The number of entries in the pLTE chunk, at most 16 fit a SIMD lookup table
+/// * `use_sse4`, `use_neon`: Whether to use intrinsics for small palettes
+///
 pub(crate) fn expand_palette(
+    input: &[u8], out: &mut [u8], palette: &[PLTEEntry; 256], entries: usize, components: usize,
+    _use_sse4: bool, _use_neon: bool
+) {
+    if entries <= 16 && (components == 3 || components == 4) {
+        #[cfg(feature = "sse")]
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        {
+            if _use_sse4 {
+                unsafe {
+                    return sse::expand_palette_ssse3(input, out, palette, components);
+                }
+            }
+        }
+        #[cfg(target_arch = "aarch64")]
+        {
+            if _use_neon {
+                unsafe {
+                    return neon::expand_palette_neon(input, out, palette, components);
+                }
+            }
+        }
+    }
+    expand_palette_scalar(input, out, palette, components)
+}
+
+pub(crate) fn expand_palette_scalar(
     input: &[u8], out: &mut [u8], palette: &[PLTEEntry; 256], components: usize
 ) {
     if components == 0 {
@@ -118,9 +153,12 @@ pub(crate) fn expand_palette(
 /// * `color`: Input color space
 /// * `trns_bytes`:  The tRNS bytes present for the images
 /// * `depth`:  The depth of the image
+/// * `raw_samples`: Whether samples of images with less than 8 bpp were kept
+///   unscaled by [`expand_bits_to_byte`], in which case the tRNS value isn't scaled either
 ///
 pub fn expand_trns<const SIXTEEN_BITS: bool>(
-    input: &[u8], out: &mut [u8], color: PngColor, trns_bytes: [u16; 4], depth: u8
+    input: &[u8], out: &mut [u8], color: PngColor, trns_bytes: [u16; 4], depth: u8,
+    raw_samples: bool
 ) {
     const DEPTH_SCALE_TABLE: [u8; 9] = [0, 0xff, 0x55, 0, 0x11, 0, 0, 0, 0x01];
 
@@ -180,7 +218,7 @@ pub fn expand_trns<const SIXTEEN_BITS: bool>(
     } else {
         match color {
             PngColor::Luma => {
-                let scale = DEPTH_SCALE_TABLE[usize::from(depth)];
+                let scale = if raw_samples { 1 } else { DEPTH_SCALE_TABLE[usize::from(depth)] };
 
                 let depth_mask = (1_u16 << depth) - 1;
                 // BUG: This overflowing is indicative of a wrong tRNS value
@@ -194,7 +232,7 @@ pub fn expand_trns<const SIXTEEN_BITS: bool>(
             PngColor::RGB => {
                 let depth_mask = (1_u16 << depth) - 1;
 
-                let scale = DEPTH_SCALE_TABLE[usize::from(depth)];
+                let scale = if raw_samples { 1 } else { DEPTH_SCALE_TABLE[usize::from(depth)] };
 
                 let r = (trns_bytes[0] & 255 & depth_mask) as u8 * scale;
                 let g = (trns_bytes[1] & 255 & depth_mask) as u8 * scale;
@@ -216,11 +254,13 @@ pub fn expand_trns<const SIXTEEN_BITS: bool>(
 
 /// Expand bits to bytes expand images with less than 8 bpp
 pub(crate) fn expand_bits_to_byte(
-    width: usize, depth: usize, out_n: usize, plte_present: bool, input: &[u8], out: &mut [u8]
+    width: usize, depth: usize, out_n: usize, raw_samples: bool, input: &[u8], out: &mut [u8]
 ) {
-    let scale = if plte_present {
+    let scale = if raw_samples {
         // When a palette is used we only separate the indexes in this pass,
         // the palette pass will convert indexes to the right colors later.
+        //
+        // Otherwise the caller asked for the unscaled values of the samples.
         1
     } else {
         match depth {
@@ -351,6 +391,109 @@ pub(crate) fn add_alpha(input: &[u8], output: &mut [u8], colorspace: PngColor, d
     }
 }
 
+/// Blend a scanline with an alpha channel onto a solid background color,
+/// leaving every pixel fully opaque
+///
+/// `background` holds the red, green and blue samples, gray images only use the first.
+/// `bytes` is the number of bytes per sample, 16 bit samples are big endian
+pub(crate) fn composite_background(
+    row: &mut [u8], colorspace: ColorSpace, background: [u16; 3], bytes: usize
+) {
+    debug_assert!(colorspace.has_alpha());
+
+    let max = if bytes == 2 { 65535_u64 } else { 255 };
+    let color_bytes = (colorspace.num_components() - 1) * bytes;
+
+    let read = |sample: &[u8]| {
+        if bytes == 2 {
+            u64::from(u16::from_be_bytes([sample[0], sample[1]]))
+        } else {
+            u64::from(sample[0])
+        }
+    };
+
+    for pixel in row.chunks_exact_mut(colorspace.num_components() * bytes) {
+        let (color, alpha) = pixel.split_at_mut(color_bytes);
+        let alpha_value = read(alpha);
+
+        if alpha_value == max {
+            continue;
+        }
+        for (sample, bg) in color.chunks_exact_mut(bytes).zip(background) {
+            let value =
+                (read(sample) * alpha_value + u64::from(bg) * (max - alpha_value) + max / 2) / max;
+
+            if bytes == 2 {
+                sample.copy_from_slice(&(value as u16).to_be_bytes());
+            } else {
+                sample[0] = value as u8;
+            }
+        }
+        alpha.fill(255);
+    }
+}
+
+/// Return true if the decoder can convert pixels from `from` to `to`
+/// colorspace via [`convert_colorspace`]
+///
+/// We only support expanding gray to RGB and adding or stripping the alpha
+/// channel, reducing RGB to gray is left to the caller
+pub(crate) const fn can_convert_colorspace(from: ColorSpace, to: ColorSpace) -> bool {
+    matches!(
+        (from, to),
+        (
+            ColorSpace::Luma | ColorSpace::LumaA,
+            ColorSpace::Luma | ColorSpace::LumaA | ColorSpace::RGB | ColorSpace::RGBA
+        ) | (
+            ColorSpace::RGB | ColorSpace::RGBA,
+            ColorSpace::RGB | ColorSpace::RGBA
+        )
+    )
+}
+
+/// Convert a scanline from `from` colorspace to `to` colorspace
+///
+/// `bytes` is the number of bytes per sample, the alpha channel is set to
+/// fully opaque when the input has none.
+///
+/// The caller should ensure the conversion is supported by calling
+/// [`can_convert_colorspace`]
+pub(crate) fn convert_colorspace(
+    input: &[u8], output: &mut [u8], from: ColorSpace, to: ColorSpace, bytes: usize
+) {
+    debug_assert!(can_convert_colorspace(from, to));
+
+    let from_color = (from.num_components() - usize::from(from.has_alpha())) * bytes;
+    let to_color = (to.num_components() - usize::from(to.has_alpha())) * bytes;
+
+    let in_chunk = from.num_components() * bytes;
+    let out_chunk = to.num_components() * bytes;
+
+    for (in_px, out_px) in input
+        .chunks_exact(in_chunk)
+        .zip(output.chunks_exact_mut(out_chunk))
+    {
+        let (in_color, in_alpha) = in_px.split_at(from_color);
+        let (out_color, out_alpha) = out_px.split_at_mut(to_color);
+
+        if from_color == to_color {
+            out_color.copy_from_slice(in_color);
+        } else {
+            // gray to RGB, replicate the gray sample
+            for sample in out_color.chunks_exact_mut(bytes) {
+                sample.copy_from_slice(in_color);
+            }
+        }
+        if !out_alpha.is_empty() {
+            if in_alpha.is_empty() {
+                out_alpha.fill(255);
+            } else {
+                out_alpha.copy_from_slice(in_alpha);
+            }
+        }
+    }
+}
+
 pub fn convert_u16_to_u8_slice(slice: &mut [u16]) -> &mut [u8] {
     // Converting a u16 slice to a u8 slice is always correct because
     // the alignment of the target is smaller.
@@ -0,0 +1,67 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software; You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+#![cfg(target_arch = "aarch64")]
+//! NEON optimized utility functions
+use core::arch::aarch64::*;
+
+use crate::decoder::PLTEEntry;
+use crate::utils::expand_palette_scalar;
+
+/// Expand palette indices using the first 16 palette entries as lookup tables
+///
+/// `vqtbl1q_u8` returns zero for indices past the tables, so blocks holding one,
+/// e.g in corrupt images, and the tail that doesn't fill a block are expanded
+/// by the scalar code.
+///
+/// # Safety
+/// - Responsibility of the caller to ensure the system supports neon
+#[target_feature(enable = "neon")]
+pub unsafe fn expand_palette_neon(
+    input: &[u8], out: &mut [u8], palette: &[PLTEEntry; 256], components: usize
+) {
+    let mut tables = [[0_u8; 16]; 4];
+
+    for (i, entry) in palette.iter().take(16).enumerate() {
+        tables[0][i] = entry.red;
+        tables[1][i] = entry.green;
+        tables[2][i] = entry.blue;
+        tables[3][i] = entry.alpha;
+    }
+    let red = vld1q_u8(tables[0].as_ptr());
+    let green = vld1q_u8(tables[1].as_ptr());
+    let blue = vld1q_u8(tables[2].as_ptr());
+    let alpha = vld1q_u8(tables[3].as_ptr());
+
+    let mut pos = 0;
+
+    while pos + 16 <= input.len() && (pos + 16) * components <= out.len() {
+        let block = &input[pos..pos + 16];
+        let out_block = &mut out[pos * components..(pos + 16) * components];
+
+        let indices = vld1q_u8(block.as_ptr());
+
+        if vmaxvq_u8(indices) > 15 {
+            expand_palette_scalar(block, out_block, palette, components);
+            pos += 16;
+            continue;
+        }
+        let r = vqtbl1q_u8(red, indices);
+        let g = vqtbl1q_u8(green, indices);
+        let b = vqtbl1q_u8(blue, indices);
+
+        if components == 4 {
+            let a = vqtbl1q_u8(alpha, indices);
+            vst4q_u8(out_block.as_mut_ptr(), uint8x16x4_t(r, g, b, a));
+        } else {
+            vst3q_u8(out_block.as_mut_ptr(), uint8x16x3_t(r, g, b));
+        }
+        pos += 16;
+    }
+    let out_pos = (pos * components).min(out.len());
+
+    expand_palette_scalar(&input[pos..], &mut out[out_pos..], palette, components);
+}
@@ -6,6 +6,8 @@
 
 #![cfg(any(target_arch = "x86", target_arch = "x86_64"))]
 //! SSE optimized utility functions
+use crate::decoder::PLTEEntry;
+use crate::utils::expand_palette_scalar;
 
 /// Convert from big endian to native endian
 ///
@@ -30,3 +32,89 @@ pub unsafe fn convert_be_to_ne_sse4(out: &mut [u8]) {
     // deal with remainder
     crate::utils::convert_be_to_ne_scalar(out.chunks_exact_mut(16).into_remainder());
 }
+
+/// Expand palette indices using the first 16 palette entries as shuffle tables
+///
+/// Blocks holding an index past the tables, e.g in corrupt images, and the tail
+/// that doesn't fill a block are expanded by the scalar code.
+///
+/// # Safety
+/// - Responsibility of the caller to ensure the system
+///   supports executing ssse3 instructions or higher
+#[target_feature(enable = "ssse3")]
+#[allow(dead_code)]
+pub unsafe fn expand_palette_ssse3(
+    input: &[u8], out: &mut [u8], palette: &[PLTEEntry; 256], components: usize
+) {
+    #[cfg(target_arch = "x86")]
+    use core::arch::x86::*;
+    #[cfg(target_arch = "x86_64")]
+    use core::arch::x86_64::*;
+
+    let mut tables = [[0_u8; 16]; 4];
+
+    for (i, entry) in palette.iter().take(16).enumerate() {
+        tables[0][i] = entry.red;
+        tables[1][i] = entry.green;
+        tables[2][i] = entry.blue;
+        tables[3][i] = entry.alpha;
+    }
+    let red = _mm_loadu_si128(tables[0].as_ptr().cast());
+    let green = _mm_loadu_si128(tables[1].as_ptr().cast());
+    let blue = _mm_loadu_si128(tables[2].as_ptr().cast());
+    let alpha = _mm_loadu_si128(tables[3].as_ptr().cast());
+
+    let max_index = _mm_set1_epi8(15);
+    // drops the alpha of 4 RGBA pixels, leaving 12 RGB bytes
+    let drop_alpha = _mm_setr_epi8(0, 1, 2, 4, 5, 6, 8, 9, 10, 12, 13, 14, -1, -1, -1, -1);
+
+    let mut pos = 0;
+
+    while pos + 16 <= input.len() && (pos + 16) * components <= out.len() {
+        let block = &input[pos..pos + 16];
+        let out_block = &mut out[pos * components..(pos + 16) * components];
+
+        let indices = _mm_loadu_si128(block.as_ptr().cast());
+        let in_range = _mm_cmpeq_epi8(_mm_max_epu8(indices, max_index), max_index);
+
+        if _mm_movemask_epi8(in_range) != 0xFFFF {
+            expand_palette_scalar(block, out_block, palette, components);
+            pos += 16;
+            continue;
+        }
+        let r = _mm_shuffle_epi8(red, indices);
+        let g = _mm_shuffle_epi8(green, indices);
+        let b = _mm_shuffle_epi8(blue, indices);
+        let a = _mm_shuffle_epi8(alpha, indices);
+
+        let rg_lo = _mm_unpacklo_epi8(r, g);
+        let rg_hi = _mm_unpackhi_epi8(r, g);
+        let ba_lo = _mm_unpacklo_epi8(b, a);
+        let ba_hi = _mm_unpackhi_epi8(b, a);
+
+        // four pixels each
+        let pixels = [
+            _mm_unpacklo_epi16(rg_lo, ba_lo),
+            _mm_unpackhi_epi16(rg_lo, ba_lo),
+            _mm_unpacklo_epi16(rg_hi, ba_hi),
+            _mm_unpackhi_epi16(rg_hi, ba_hi)
+        ];
+
+        if components == 4 {
+            for (chunk, px) in out_block.chunks_exact_mut(16).zip(pixels) {
+                _mm_storeu_si128(chunk.as_mut_ptr().cast(), px);
+            }
+        } else {
+            let mut rgb = [0_u8; 16];
+
+            for (chunk, px) in out_block.chunks_exact_mut(12).zip(pixels) {
+                _mm_storeu_si128(rgb.as_mut_ptr().cast(), _mm_shuffle_epi8(px, drop_alpha));
+                chunk.copy_from_slice(&rgb[..12]);
+            }
+        }
+        pos += 16;
+    }
+    let out_pos = (pos * components).min(out.len());
+
+    expand_palette_scalar(&input[pos..], &mut out[out_pos..], palette, components);
+}
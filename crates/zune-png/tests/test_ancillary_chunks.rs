@@ -0,0 +1,84 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+use zune_core::bit_depth::BitDepth;
+use zune_core::colorspace::ColorSpace;
+use zune_core::options::EncoderOptions;
+use zune_png::{PhysicalDimensions, PngDecoder, PngEncoder};
+
+/// Return the names of the chunks in `png`, in file order
+fn chunk_names(png: &[u8]) -> Vec<[u8; 4]> {
+    let mut names = vec![];
+    let mut position = 8;
+
+    while position < png.len() {
+        let length = u32::from_be_bytes(png[position..position + 4].try_into().unwrap());
+        names.push(png[position + 4..position + 8].try_into().unwrap());
+        // length, name, data and crc
+        position += 12 + length as usize;
+    }
+    names
+}
+
+#[test]
+fn test_ancillary_chunks_round_trip() {
+    let pixels = [7_u8; 4 * 4 * 3];
+    let icc: Vec<u8> = (0..=255).cycle().take(1000).collect();
+    let exif = b"MM\x00\x2a\x00\x00\x00\x08\x00\x00";
+    let options = EncoderOptions::new(4, 4, ColorSpace::RGB, BitDepth::Eight);
+
+    let mut encoder = PngEncoder::new(&pixels, options);
+    // set out of spec order, the encoder orders them
+    encoder.add_text_chunk("Comment", "archived");
+    encoder.add_exif_segment(exif);
+    encoder.set_physical_dimensions(PhysicalDimensions {
+        x_pixels_per_unit: 11811,
+        y_pixels_per_unit: 5906,
+        unit_is_meter:     true
+    });
+    encoder.set_icc_profile(&icc);
+    encoder.set_gamma(0.45455);
+
+    let data = encoder.encode();
+
+    assert_eq!(
+        chunk_names(&data),
+        [*b"IHDR", *b"gAMA", *b"iCCP", *b"pHYs", *b"eXIf", *b"tEXt", *b"IDAT", *b"IEND"]
+    );
+
+    let mut decoder = PngDecoder::new(&data);
+    assert_eq!(decoder.decode_raw().unwrap(), pixels);
+
+    let info = decoder.get_info().unwrap();
+
+    assert_eq!(info.gamma, Some(0.45455));
+    assert_eq!(info.icc_profile.as_deref(), Some(&icc[..]));
+    assert_eq!(info.exif.as_deref(), Some(&exif[..]));
+    assert_eq!(
+        info.physical_dimensions,
+        Some(PhysicalDimensions {
+            x_pixels_per_unit: 11811,
+            y_pixels_per_unit: 5906,
+            unit_is_meter:     true
+        })
+    );
+
+    // check the chunks against an independent decoder
+    let reader = png::Decoder::new(data.as_slice()).read_info().unwrap();
+    let info = reader.info();
+
+    let dims = info.pixel_dims.unwrap();
+
+    assert_eq!((dims.xppu, dims.yppu), (11811, 5906));
+    assert_eq!(dims.unit, png::Unit::Meter);
+    assert_eq!(
+        info.source_gamma,
+        Some(png::ScaledFloat::from_scaled(45455))
+    );
+    assert_eq!(info.icc_profile.as_deref(), Some(&icc[..]));
+}
@@ -0,0 +1,179 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+use std::path::Path;
+
+use zune_core::bit_depth::BitDepth;
+use zune_core::checksum::crc32;
+use zune_core::colorspace::ColorSpace;
+use zune_core::options::{DecoderOptions, EncoderOptions};
+use zune_core::result::DecodingResult;
+use zune_png::{PngDecoder, PngEncoder};
+
+/// Insert a bKGD chunk after the IHDR chunk of `png`
+fn add_bkgd(png: &[u8], color: &[u8]) -> Vec<u8> {
+    // signature and IHDR chunk
+    let (head, tail) = png.split_at(8 + 25);
+
+    let mut chunk = b"bKGD".to_vec();
+    chunk.extend_from_slice(color);
+
+    let mut output = head.to_vec();
+    output.extend_from_slice(&(color.len() as u32).to_be_bytes());
+    output.extend_from_slice(&chunk);
+    output.extend_from_slice(&crc32::crc32(&chunk).to_be_bytes());
+    output.extend_from_slice(tail);
+    output
+}
+
+fn composite_options() -> DecoderOptions {
+    DecoderOptions::default().png_set_composite_background(true)
+}
+
+fn decode_samples(data: &[u8], options: DecoderOptions) -> (Vec<u16>, ColorSpace) {
+    let mut decoder = PngDecoder::new_with_options(data, options);
+    let pixels = match decoder.decode().unwrap() {
+        DecodingResult::U8(pixels) => pixels.iter().map(|x| u16::from(*x)).collect(),
+        DecodingResult::U16(pixels) => pixels,
+        _ => unreachable!()
+    };
+    (pixels, decoder.get_colorspace().unwrap())
+}
+
+#[test]
+fn test_composite_rgba() {
+    let pixels = [255, 0, 0, 255, 255, 0, 0, 0, 255, 0, 0, 128, 10, 20, 30, 64];
+    let options = EncoderOptions::new(4, 1, ColorSpace::RGBA, BitDepth::Eight);
+    let png = PngEncoder::new(&pixels, options).encode();
+    // blue background
+    let data = add_bkgd(&png, &[0, 0, 0, 0, 0, 255]);
+
+    let mut decoder = PngDecoder::new_with_options(&data, composite_options());
+    decoder.decode_headers().unwrap();
+
+    assert_eq!(decoder.get_info().unwrap().background, Some([0, 0, 255]));
+    assert_eq!(decoder.get_colorspace(), Some(ColorSpace::RGB));
+
+    let decoded = decoder.decode_raw().unwrap();
+    let expected = [255, 0, 0, 0, 0, 255, 128, 0, 127, 3, 5, 199];
+    assert_eq!(decoded, expected);
+}
+
+#[test]
+fn test_composite_without_bkgd_uses_white() {
+    let pixels = [0, 255, 0, 0, 0, 128];
+    let options = EncoderOptions::new(3, 1, ColorSpace::LumaA, BitDepth::Eight);
+    let png = PngEncoder::new(&pixels, options).encode();
+
+    let mut decoder = PngDecoder::new_with_options(&png, composite_options());
+    let decoded = decoder.decode_raw().unwrap();
+
+    assert_eq!(decoder.get_info().unwrap().background, None);
+    assert_eq!(decoder.get_colorspace(), Some(ColorSpace::Luma));
+    assert_eq!(decoded, [0, 255, 127]);
+}
+
+#[test]
+fn test_composite_keeps_requested_colorspace() {
+    let pixels = [0, 0, 0, 0];
+    let options = EncoderOptions::new(1, 1, ColorSpace::RGBA, BitDepth::Eight);
+    let png = PngEncoder::new(&pixels, options).encode();
+    let data = add_bkgd(&png, &[0, 10, 0, 20, 0, 30]);
+
+    let options = composite_options().png_set_out_colorspace(ColorSpace::RGBA);
+    let mut decoder = PngDecoder::new_with_options(&data, options);
+
+    assert_eq!(decoder.decode_raw().unwrap(), [10, 20, 30, 255]);
+    assert_eq!(decoder.get_colorspace(), Some(ColorSpace::RGBA));
+}
+
+#[test]
+fn test_composite_opaque_image_is_unchanged() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/png_suite/basn2c08.png");
+    let data = std::fs::read(path).unwrap();
+
+    let expected = decode_samples(&data, DecoderOptions::default());
+    let decoded = decode_samples(&data, composite_options());
+
+    assert_eq!(decoded, expected);
+}
+
+#[test]
+fn test_composite_png_suite() {
+    // alpha, tRNS and palette images with and without a bKGD chunk
+    let files = [
+        "bgai4a16.png",
+        "bgbn4a08.png",
+        "bggn4a16.png",
+        "bgwn6a08.png",
+        "bgyn6a16.png",
+        "tbbn0g04.png",
+        "tbrn2c08.png",
+        "tbyn3p08.png",
+        "tbwn0g16.png"
+    ];
+    for file in files {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/png_suite")
+            .join(file);
+        let data = std::fs::read(path).unwrap();
+
+        let mut decoder = PngDecoder::new(&data);
+        decoder.decode_headers().unwrap();
+        let max: u64 = if decoder.get_depth() == Some(BitDepth::Sixteen) { 65535 } else { 255 };
+        let background = decoder
+            .get_info()
+            .unwrap()
+            .background
+            .unwrap_or([max as u16; 3]);
+
+        let (pixels, colorspace) = decode_samples(&data, DecoderOptions::default());
+        let channels = colorspace.num_components();
+
+        let mut expected = vec![];
+        for pixel in pixels.chunks_exact(channels) {
+            let (color, alpha) = pixel.split_at(channels - 1);
+            let alpha = u64::from(alpha[0]);
+
+            for (c, bg) in color.iter().zip(background) {
+                let value = (u64::from(*c) * alpha + u64::from(bg) * (max - alpha) + max / 2) / max;
+                expected.push(value as u16);
+            }
+        }
+        let (decoded, out_colorspace) = decode_samples(&data, composite_options());
+
+        assert_eq!(out_colorspace.num_components(), channels - 1, "{file}");
+        assert_eq!(decoded, expected, "{file}");
+    }
+}
+
+#[test]
+fn test_bkgd_png_suite() {
+    // (file, background), palette and low bit depth gray colors are expanded
+    let files = [
+        ("bgyn6a16.png", [65535, 65535, 0]),
+        ("tbyn3p08.png", [255, 255, 0]),
+        ("tbbn0g04.png", [0, 0, 0]),
+        ("bgwn6a08.png", [255, 255, 255])
+    ];
+    for (file, background) in files {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/png_suite")
+            .join(file);
+        let data = std::fs::read(path).unwrap();
+
+        let mut decoder = PngDecoder::new(&data);
+        decoder.decode_headers().unwrap();
+
+        assert_eq!(
+            decoder.get_info().unwrap().background,
+            Some(background),
+            "{file}"
+        );
+    }
+}
@@ -0,0 +1,47 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+use zune_core::bit_depth::BitDepth;
+use zune_core::colorspace::ColorSpace;
+use zune_core::options::{DecoderOptions, EncoderOptions};
+use zune_png::{DeflateEncodeOptions, DeflateEncodingStrategy, PngDecoder, PngEncoder};
+
+fn encode(pixels: &[u8], strategy: Option<DeflateEncodingStrategy>) -> Vec<u8> {
+    let options = EncoderOptions::new(64, 64, ColorSpace::RGB, BitDepth::Eight);
+    let mut encoder = PngEncoder::new(pixels, options);
+
+    if let Some(strategy) = strategy {
+        encoder.set_deflate_options(DeflateEncodeOptions::new(strategy));
+    }
+    encoder.encode()
+}
+
+fn decode(png: &[u8]) -> Vec<u8> {
+    let options = DecoderOptions::default().set_strict_mode(true);
+    PngDecoder::new_with_options(png, options)
+        .decode_raw()
+        .unwrap()
+}
+
+#[test]
+fn test_deflate_presets_round_trip() {
+    let pixels: Vec<u8> = (0..64 * 64 * 3).map(|x| (x % 251) as u8).collect();
+
+    let stored = encode(&pixels, Some(DeflateEncodingStrategy::NoCompression));
+    let default = encode(&pixels, None);
+
+    assert_eq!(
+        default,
+        encode(&pixels, Some(DeflateEncodingStrategy::Default))
+    );
+    assert!(default.len() < stored.len());
+
+    for png in [&stored, &default] {
+        assert_eq!(decode(png), pixels);
+    }
+}
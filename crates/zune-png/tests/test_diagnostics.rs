@@ -0,0 +1,60 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! Bytes attached to decoding errors by the `diagnostics` feature
+#![cfg(feature = "diagnostics")]
+
+use zune_core::options::DecoderOptions;
+use zune_png::error::PngDecodeErrors;
+use zune_png::PngDecoder;
+
+fn open(name: &str) -> Vec<u8> {
+    let path = format!("{}/tests/png_suite/{name}.png", env!("CARGO_MANIFEST_DIR"));
+    std::fs::read(path).unwrap()
+}
+
+#[test]
+fn test_error_has_context() {
+    // bit depth 3 in the IHDR chunk
+    let data = open("xd3n2c08");
+    let options = DecoderOptions::default().set_error_context_size(32);
+    let err = PngDecoder::new_with_options(&data, options)
+        .decode_headers()
+        .unwrap_err();
+
+    let context = err.context().expect("No context for error");
+    let ihdr = 16..16 + 13;
+
+    assert!(ihdr.contains(&context.offset()), "{}", context.offset());
+    assert_eq!(context.start(), 0);
+    assert_eq!(context.bytes(), &data[..context.bytes().len()]);
+    assert!(matches!(err.without_context(), PngDecodeErrors::Generic(_)));
+}
+
+#[test]
+fn test_context_size() {
+    let data = open("xd3n2c08");
+    let options = DecoderOptions::default().set_error_context_size(4);
+    let err = PngDecoder::new_with_options(&data, options)
+        .decode_headers()
+        .unwrap_err();
+
+    let context = err.context().unwrap();
+    // the window starts at a line boundary and ends 4 bytes after the offset
+    assert_eq!(context.start(), 16);
+    assert_eq!(
+        context.start() + context.bytes().len(),
+        context.offset() + 5
+    );
+
+    // nothing is attached by default
+    let err = PngDecoder::new(&data).decode_headers().unwrap_err();
+
+    assert!(err.context().is_none());
+    assert!(matches!(err, PngDecodeErrors::Generic(_)));
+}
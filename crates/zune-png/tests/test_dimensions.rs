@@ -0,0 +1,51 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+use zune_core::bit_depth::BitDepth;
+use zune_core::checksum::crc32;
+use zune_core::colorspace::ColorSpace;
+use zune_core::options::{DecoderOptions, EncoderOptions};
+use zune_png::{PngDecoder, PngEncoder};
+
+/// Encode a small RGBA 16 bit image and rewrite its IHDR dimensions
+fn image_with_dimensions(width: u32, height: u32) -> Vec<u8> {
+    let pixels = vec![0; 4 * 4 * 4 * 2];
+    let options = EncoderOptions::new(4, 4, ColorSpace::RGBA, BitDepth::Sixteen);
+    let mut data = PngEncoder::new(&pixels, options).encode();
+
+    // signature, then chunk length, then IHDR
+    let ihdr = 8 + 4;
+    data[ihdr + 4..ihdr + 8].copy_from_slice(&width.to_be_bytes());
+    data[ihdr + 8..ihdr + 12].copy_from_slice(&height.to_be_bytes());
+
+    let crc = crc32::crc32(&data[ihdr..ihdr + 4 + 13]);
+    data[ihdr + 17..ihdr + 21].copy_from_slice(&crc.to_be_bytes());
+
+    data
+}
+
+#[test]
+fn test_overflowing_dimensions() {
+    // 2^31-1 squared, times 8 bytes per pixel overflows a 64 bit usize
+    let data = image_with_dimensions(0x7FFF_FFFF, 0x7FFF_FFFF);
+    let options = DecoderOptions::default()
+        .set_max_width(usize::MAX)
+        .set_max_height(usize::MAX);
+
+    let mut decoder = PngDecoder::new_with_options(&data, options);
+
+    assert!(decoder.decode_headers().is_err());
+    assert!(decoder.decode_raw().is_err());
+}
+
+#[test]
+fn test_valid_dimensions_still_decode() {
+    let data = image_with_dimensions(4, 4);
+
+    assert!(PngDecoder::new(&data).decode_raw().is_ok());
+}
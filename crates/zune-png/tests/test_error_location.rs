@@ -0,0 +1,96 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! Stream offsets and chunks recorded for decoding errors
+
+use zune_core::options::DecoderOptions;
+use zune_png::error::{ErrorLocation, PngDecodeErrors};
+use zune_png::PngDecoder;
+
+fn open(name: &str) -> Vec<u8> {
+    let path = format!("{}/tests/png_suite/{name}.png", env!("CARGO_MANIFEST_DIR"));
+    std::fs::read(path).unwrap()
+}
+
+/// Offset of the length field of the first `chunk` in `data`
+fn chunk_start(data: &[u8], chunk: &[u8; 4]) -> usize {
+    data.windows(4).position(|x| x == chunk).unwrap() - 4
+}
+
+#[test]
+fn test_signature_location() {
+    let data = open("xs1n0g01");
+    let mut decoder = PngDecoder::new(&data);
+    let err = decoder.decode_headers().unwrap_err();
+
+    assert!(matches!(err, PngDecodeErrors::BadSignature));
+    assert_eq!(
+        decoder.error_location(),
+        Some(ErrorLocation {
+            offset:       0,
+            chunk:        None,
+            chunk_offset: 0
+        })
+    );
+}
+
+#[test]
+fn test_header_location() {
+    // bit depth 3 in the IHDR chunk
+    let data = open("xd3n2c08");
+    let mut decoder = PngDecoder::new(&data);
+    decoder.decode_headers().unwrap_err();
+
+    let location = decoder.error_location().unwrap();
+
+    assert_eq!(location.chunk, Some(*b"IHDR"));
+    assert_eq!(location.chunk_offset, 8);
+    assert!((16..16 + 13).contains(&location.offset), "{location}");
+}
+
+#[test]
+fn test_truncated_chunk_location() {
+    let data = open("basn0g08");
+    let idat = chunk_start(&data, b"IDAT");
+    let data = &data[..idat + 20];
+
+    let mut decoder = PngDecoder::new(data);
+    decoder.decode_raw().unwrap_err();
+    let location = decoder.error_location().unwrap();
+
+    assert_eq!(location.chunk, Some(*b"IDAT"));
+    assert_eq!(location.chunk_offset, idat);
+    assert_eq!(location.offset, idat + 8);
+    assert!(location.to_string().contains("in the IDAT chunk"), "{location}");
+}
+
+#[test]
+fn test_image_data_location() {
+    let mut data = open("basn2c08");
+    let idat = chunk_start(&data, b"IDAT");
+    // corrupt the zlib header
+    data[idat + 8] = 0xff;
+    data[idat + 9] = 0xff;
+
+    let options = DecoderOptions::default().png_set_confirm_crc(false);
+    let mut decoder = PngDecoder::new_with_options(&data, options);
+    let err = decoder.decode_raw().unwrap_err();
+
+    assert!(matches!(err, PngDecodeErrors::ZlibDecodeErrors(_)));
+    assert_eq!(
+        decoder.error_location(),
+        Some(ErrorLocation {
+            offset:       idat,
+            chunk:        Some(*b"IDAT"),
+            chunk_offset: idat
+        })
+    );
+    // later calls forget the error
+    decoder.decode_headers().unwrap();
+    assert_eq!(decoder.error_location(), None);
+}
@@ -106,3 +106,23 @@ fn test_paeth() {
         test_decoding(path);
     }
 }
+
+#[test]
+fn test_in_place_matches_decode_into() {
+    // non-interlaced 8 bit images are un-filtered in place by decode_raw,
+    // while decode_into always un-filters into the caller's buffer
+    for file in [
+        "f00n2c08", "f01n0g08", "f02n2c08", "f03n0g08", "f04n2c08", "basn6a08"
+    ] {
+        let path = env!("CARGO_MANIFEST_DIR").to_string() + "/tests/png_suite/" + file + ".png";
+        let contents = open_and_read(path);
+
+        let mut decoder = zune_png::PngDecoder::new(&contents);
+        decoder.decode_headers().unwrap();
+
+        let mut out = vec![0; decoder.output_buffer_size().unwrap()];
+        decoder.decode_into(&mut out).unwrap();
+
+        assert_eq!(decode_zune(&contents), out, "{file}");
+    }
+}
@@ -0,0 +1,82 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! Grayscale images with less than 8 bits per sample
+use zune_core::options::DecoderOptions;
+use zune_png::PngDecoder;
+
+fn open(name: &str) -> Vec<u8> {
+    let path = env!("CARGO_MANIFEST_DIR").to_string() + "/tests/png_suite/" + name + ".png";
+    std::fs::read(path).unwrap()
+}
+
+fn decode(name: &str, keep_raw: bool) -> Vec<u8> {
+    let options = DecoderOptions::default().png_set_keep_raw_samples(keep_raw);
+    let data = open(name);
+
+    PngDecoder::new_with_options(&data, options)
+        .decode_raw()
+        .unwrap()
+}
+
+#[test]
+fn test_samples_are_scaled_to_full_range() {
+    for (name, scale) in [
+        ("basn0g01", 0xFF),
+        ("basn0g02", 0x55),
+        ("basn0g04", 0x11),
+        ("basi0g01", 0xFF),
+        ("basi0g02", 0x55),
+        ("basi0g04", 0x11)
+    ] {
+        let pixels = decode(name, false);
+
+        assert!(pixels.iter().all(|x| x % scale == 0), "{name}");
+        // the gradients in the suite span (almost) the whole range
+        assert_eq!(pixels.iter().min(), Some(&0), "{name}");
+        assert!(*pixels.iter().max().unwrap() >= 255 - scale, "{name}");
+    }
+}
+
+#[test]
+fn test_keep_raw_samples() {
+    for (name, scale) in [
+        ("basn0g01", 0xFF),
+        ("basn0g02", 0x55),
+        ("basn0g04", 0x11),
+        ("basi0g01", 0xFF),
+        ("basi0g02", 0x55),
+        ("basi0g04", 0x11)
+    ] {
+        let scaled = decode(name, false);
+        let raw = decode(name, true);
+
+        assert_eq!(raw.len(), scaled.len(), "{name}");
+        assert!(raw.iter().all(|x| *x <= 255 / scale), "{name}");
+
+        for (r, s) in raw.iter().zip(&scaled) {
+            assert_eq!(r * scale, *s, "{name}");
+        }
+    }
+}
+
+#[test]
+fn test_keep_raw_samples_with_trns() {
+    // 4 bit grayscale with a transparent gray level
+    let scaled = decode("tbbn0g04", false);
+    let raw = decode("tbbn0g04", true);
+
+    assert_eq!(raw.len(), scaled.len());
+
+    for (r, s) in raw.chunks_exact(2).zip(scaled.chunks_exact(2)) {
+        assert_eq!(r[0] * 0x11, s[0]);
+        // the same pixels are transparent
+        assert_eq!(r[1], s[1]);
+    }
+    assert!(raw.chunks_exact(2).any(|x| x[1] == 0));
+}
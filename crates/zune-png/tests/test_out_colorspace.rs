@@ -0,0 +1,69 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+use zune_core::bit_depth::BitDepth;
+use zune_core::colorspace::ColorSpace;
+use zune_core::options::{DecoderOptions, EncoderOptions};
+use zune_png::{PngDecoder, PngEncoder};
+
+fn encode(pixels: &[u8], width: usize, height: usize, colorspace: ColorSpace) -> Vec<u8> {
+    let options = EncoderOptions::new(width, height, colorspace, BitDepth::Eight);
+    PngEncoder::new(pixels, options).encode()
+}
+
+fn decode_to(data: &[u8], colorspace: ColorSpace) -> (Vec<u8>, ColorSpace) {
+    let options = DecoderOptions::default().png_set_out_colorspace(colorspace);
+    let mut decoder = PngDecoder::new_with_options(data, options);
+    let pixels = decoder.decode_raw().unwrap();
+
+    (pixels, decoder.get_colorspace().unwrap())
+}
+
+#[test]
+fn test_luma_to_rgba() {
+    let (width, height) = (17, 9);
+    let luma: Vec<u8> = (0..width * height).map(|x| (x * 7) as u8).collect();
+
+    let data = encode(&luma, width, height, ColorSpace::Luma);
+    let (pixels, colorspace) = decode_to(&data, ColorSpace::RGBA);
+
+    assert_eq!(colorspace, ColorSpace::RGBA);
+    assert_eq!(pixels.len(), width * height * 4);
+
+    for (gray, px) in luma.iter().zip(pixels.chunks_exact(4)) {
+        assert_eq!(px, &[*gray, *gray, *gray, 255]);
+    }
+}
+
+#[test]
+fn test_rgba_to_rgb() {
+    let (width, height) = (13, 11);
+    let rgba: Vec<u8> = (0..width * height * 4).map(|x| (x * 3) as u8).collect();
+
+    let data = encode(&rgba, width, height, ColorSpace::RGBA);
+    let (pixels, colorspace) = decode_to(&data, ColorSpace::RGB);
+
+    assert_eq!(colorspace, ColorSpace::RGB);
+    assert_eq!(pixels.len(), width * height * 3);
+
+    for (input, px) in rgba.chunks_exact(4).zip(pixels.chunks_exact(3)) {
+        assert_eq!(&input[..3], px);
+    }
+}
+
+#[test]
+fn test_unsupported_conversion_is_ignored() {
+    let (width, height) = (5, 5);
+    let rgb = vec![100; width * height * 3];
+
+    let data = encode(&rgb, width, height, ColorSpace::RGB);
+    let (pixels, colorspace) = decode_to(&data, ColorSpace::Luma);
+
+    assert_eq!(colorspace, ColorSpace::RGB);
+    assert_eq!(pixels, rgb);
+}
@@ -102,3 +102,66 @@ fn test_palette_8bpp() {
         test_decoding(path);
     }
 }
+
+/// Encode 8 bit palette indices with the png crate
+fn encode_indexed(indices: &[u8], width: u32, palette: &[[u8; 3]], trns: Option<&[u8]>) -> Vec<u8> {
+    let mut out = vec![];
+    {
+        let height = indices.len() as u32 / width;
+        let mut encoder = png::Encoder::new(&mut out, width, height);
+
+        encoder.set_color(png::ColorType::Indexed);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_palette(palette.concat());
+
+        if let Some(trns) = trns {
+            encoder.set_trns(trns.to_vec());
+        }
+        let mut writer = encoder.write_header().unwrap();
+        writer.write_image_data(indices).unwrap();
+    }
+    out
+}
+
+#[test]
+fn test_small_palette_expansion() {
+    // blocks of 16 pixels, a tail, and indices past the palette which expand to opaque black
+    let (width, height) = (37, 5);
+
+    for entries in [2, 15, 16, 17] {
+        let palette: Vec<[u8; 3]> = (0..entries)
+            .map(|i| [i * 13, 255 - i * 7, i * 3 + 1])
+            .collect();
+        let alpha: Vec<u8> = (0..entries).map(|i| 255 - i * 11).collect();
+
+        let indices: Vec<u8> = (0..width * height)
+            .map(|i| if i % 29 == 28 { 200 } else { (i * 7 % usize::from(entries)) as u8 })
+            .collect();
+
+        for trns in [None, Some(alpha.as_slice())] {
+            let data = encode_indexed(&indices, width as u32, &palette, trns);
+
+            let expected: Vec<u8> = indices
+                .iter()
+                .flat_map(|&index| {
+                    let index = usize::from(index);
+                    let [r, g, b] = palette.get(index).copied().unwrap_or([0, 0, 0]);
+                    let a = trns.and_then(|x| x.get(index)).copied().unwrap_or(255);
+
+                    if trns.is_some() {
+                        vec![r, g, b, a]
+                    } else {
+                        vec![r, g, b]
+                    }
+                })
+                .collect();
+
+            assert_eq!(
+                decode_zune(&data),
+                expected,
+                "{entries} entries, tRNS: {}",
+                trns.is_some()
+            );
+        }
+    }
+}
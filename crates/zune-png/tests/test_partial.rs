@@ -0,0 +1,80 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+use zune_core::options::DecoderOptions;
+use zune_png::PngDecoder;
+
+fn open(name: &str) -> Vec<u8> {
+    let path = env!("CARGO_MANIFEST_DIR").to_string() + "/tests/png_suite/" + name + ".png";
+    std::fs::read(path).unwrap()
+}
+
+fn decode_partial(data: &[u8]) -> (Vec<u8>, bool) {
+    let options = DecoderOptions::default().set_allow_partial(true);
+    let mut decoder = PngDecoder::new_with_options(data, options);
+
+    let pixels = decoder.decode_raw().unwrap();
+    (pixels, decoder.is_truncated())
+}
+
+#[test]
+fn test_truncated_rows_are_salvaged() {
+    for name in ["f00n2c08", "basn0g08"] {
+        let data = open(name);
+        let full = PngDecoder::new(&data).decode_raw().unwrap();
+
+        let truncated = &data[..data.len() * 6 / 10];
+        assert!(PngDecoder::new(truncated).decode_raw().is_err(), "{name}");
+
+        let (pixels, is_truncated) = decode_partial(truncated);
+        assert!(is_truncated, "{name}");
+        assert_eq!(pixels.len(), full.len(), "{name}");
+
+        // all 32x32 images, the top row survives and the bottom one is lost
+        let row = full.len() / 32;
+        assert_eq!(pixels[..row], full[..row], "{name}");
+        assert!(pixels[full.len() - row..].iter().all(|x| *x == 0), "{name}");
+    }
+}
+
+#[test]
+fn test_truncated_interlaced() {
+    let data = open("basi2c08");
+    let full = PngDecoder::new(&data).decode_raw().unwrap();
+
+    let (pixels, is_truncated) = decode_partial(&data[..data.len() * 6 / 10]);
+    assert!(is_truncated);
+    assert_eq!(pixels.len(), full.len());
+
+    // the first pass, holding the top left pixel, survives while the last one is lost
+    assert_eq!(pixels[..3], full[..3]);
+    assert_ne!(pixels, full);
+}
+
+#[test]
+fn test_complete_image_is_not_truncated() {
+    let data = open("f00n2c08");
+    let (pixels, is_truncated) = decode_partial(&data);
+
+    assert!(!is_truncated);
+    assert_eq!(pixels, PngDecoder::new(&data).decode_raw().unwrap());
+}
+
+#[test]
+fn test_corrupt_chunk_is_not_truncation() {
+    let mut data = open("f00n2c08");
+    // corrupt the crc of the IEND chunk, the file is complete
+    let last = data.len() - 1;
+    data[last] ^= 0xff;
+
+    let options = DecoderOptions::default().set_allow_partial(true);
+    let mut decoder = PngDecoder::new_with_options(&data, options);
+
+    assert!(decoder.decode_raw().is_err());
+    assert!(!decoder.is_truncated());
+}
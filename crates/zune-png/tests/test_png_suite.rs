@@ -0,0 +1,200 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! Conformance tests against the [PngSuite](http://www.schaik.com/pngsuite/)
+//!
+//! Every image in `tests/png_suite` is decoded and compared against a reference
+//! dump in `tests/png_suite/reference`, images whose names start with `x` are
+//! corrupt and must be rejected.
+//!
+//! A reference dump is the zlib compressed image expanded to RGBA, 8 bits per sample
+//! for images with a depth of 8 bits or less and 16 bit big endian samples otherwise,
+//! gamma and background chunks are not applied.
+//!
+//! The dumps are produced by the `png` crate, after adding images to the suite run
+//! `cargo test --test test_png_suite -- --ignored` to regenerate them.
+use std::fs::read;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use zune_core::bit_depth::{BitDepth, ByteEndian};
+use zune_core::options::DecoderOptions;
+use zune_inflate::DeflateDecoder;
+use zune_png::error::PngDecodeErrors;
+use zune_png::PngDecoder;
+
+fn suite_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/png_suite")
+}
+
+fn reference_path(name: &str) -> PathBuf {
+    suite_dir()
+        .join("reference")
+        .join(name.to_string() + ".rgba.zlib")
+}
+
+/// Return the names of all images in the suite, sorted
+fn suite_images() -> Vec<String> {
+    let mut names: Vec<String> = std::fs::read_dir(suite_dir())
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "png"))
+        .map(|path| path.file_stem().unwrap().to_str().unwrap().to_string())
+        .collect();
+    names.sort();
+    names
+}
+
+fn open(name: &str) -> Vec<u8> {
+    read(suite_dir().join(name.to_string() + ".png")).unwrap()
+}
+
+/// Expand interleaved Luma, LumaA, RGB or RGBA pixels to RGBA
+fn expand_to_rgba(pixels: &[u8], components: usize, sixteen_bits: bool) -> Vec<u8> {
+    let bytes = if sixteen_bits { 2 } else { 1 };
+    let mut out = Vec::with_capacity(pixels.len() / components * 4);
+
+    for pixel in pixels.chunks_exact(components * bytes) {
+        let sample = |i: usize| &pixel[i * bytes..(i + 1) * bytes];
+
+        let (rgb, alpha) = match components {
+            1 => ([sample(0), sample(0), sample(0)], None),
+            2 => ([sample(0), sample(0), sample(0)], Some(sample(1))),
+            3 => ([sample(0), sample(1), sample(2)], None),
+            4 => ([sample(0), sample(1), sample(2)], Some(sample(3))),
+            _ => unreachable!()
+        };
+        for channel in rgb {
+            out.extend_from_slice(channel);
+        }
+        match alpha {
+            Some(alpha) => out.extend_from_slice(alpha),
+            None => out.extend_from_slice(&[255; 2][..bytes])
+        }
+    }
+    out
+}
+
+fn decode_zune(data: &[u8]) -> Result<Vec<u8>, PngDecodeErrors> {
+    let options = DecoderOptions::default().set_byte_endian(ByteEndian::BE);
+    let mut decoder = PngDecoder::new_with_options(data, options);
+
+    let pixels = decoder.decode_raw()?;
+    let components = decoder.get_colorspace().unwrap().num_components();
+    let sixteen_bits = decoder.get_depth() == Some(BitDepth::Sixteen);
+
+    Ok(expand_to_rgba(&pixels, components, sixteen_bits))
+}
+
+fn decode_ref(data: &[u8]) -> Vec<u8> {
+    let mut decoder = png::Decoder::new(data);
+    decoder.set_transformations(png::Transformations::EXPAND);
+    let mut reader = decoder.read_info().unwrap();
+
+    let mut pixels = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut pixels).unwrap();
+    pixels.truncate(info.buffer_size());
+
+    let sixteen_bits = info.bit_depth == png::BitDepth::Sixteen;
+
+    expand_to_rgba(&pixels, info.color_type.samples(), sixteen_bits)
+}
+
+/// Check that an error is the one expected for a corrupt image
+type ErrorCheck = fn(&PngDecodeErrors) -> bool;
+
+fn is_corrupt(name: &str) -> bool {
+    name.starts_with('x')
+}
+
+#[test]
+fn test_suite_matches_reference() {
+    let mut failures = vec![];
+
+    for name in suite_images().iter().filter(|x| !is_corrupt(x)) {
+        let compressed = read(reference_path(name))
+            .unwrap_or_else(|_| panic!("No reference dump for {name}, regenerate them"));
+        let expected = DeflateDecoder::new(&compressed).decode_zlib().unwrap();
+
+        match decode_zune(&open(name)) {
+            Ok(pixels) if pixels == expected => {}
+            Ok(pixels) if pixels.len() != expected.len() => failures.push(format!(
+                "{name}: expected {} bytes, found {}",
+                expected.len(),
+                pixels.len()
+            )),
+            Ok(pixels) => {
+                let position = pixels.iter().zip(&expected).position(|(a, b)| a != b);
+                failures.push(format!("{name}: first differing byte at {position:?}"));
+            }
+            Err(err) => failures.push(format!("{name}: {err:?}"))
+        }
+    }
+    assert!(failures.is_empty(), "\n{}", failures.join("\n"));
+}
+
+#[test]
+fn test_corrupt_images_are_rejected() {
+    use PngDecodeErrors::*;
+
+    let generic: ErrorCheck = |err| matches!(err, Generic(_) | GenericStatic(_));
+    let bad_signature: ErrorCheck = |err| matches!(err, BadSignature);
+    let bad_crc: ErrorCheck = |err| matches!(err, BadCrc(_, _));
+    let bad_zlib: ErrorCheck = |err| matches!(err, ZlibDecodeErrors(_));
+
+    let expected: [(&str, &str, ErrorCheck); 14] = [
+        ("xc1n0g08", "color type 1", generic),
+        ("xc9n2c08", "color type 9", generic),
+        ("xcrn0g04", "added cr bytes", bad_signature),
+        ("xcsn0g01", "incorrect IDAT checksum", bad_crc),
+        ("xd0n2c08", "bit depth 0", generic),
+        ("xd3n2c08", "bit depth 3", generic),
+        ("xd9n2c08", "bit depth 99", generic),
+        ("xdtn0g01", "missing IDAT chunk", bad_zlib),
+        ("xhdn0g08", "incorrect IHDR checksum", bad_crc),
+        ("xlfn0g04", "added lf bytes", bad_signature),
+        ("xs1n0g01", "signature byte 1 MSBit reset", bad_signature),
+        ("xs2n0g01", "signature byte 2 is a 'Q'", bad_signature),
+        ("xs4n0g01", "signature byte 4 lowercase", bad_signature),
+        ("xs7n0g01", "signature byte 7 is a space", bad_signature)
+    ];
+
+    let corrupt: Vec<String> = suite_images()
+        .into_iter()
+        .filter(|x| is_corrupt(x))
+        .collect();
+    let listed: Vec<&str> = expected.iter().map(|(name, _, _)| *name).collect();
+    assert_eq!(corrupt, listed, "Corrupt images without an expected error");
+
+    for (name, reason, is_expected) in expected {
+        match decode_zune(&open(name)) {
+            Ok(_) => panic!("{name} ({reason}) decoded successfully"),
+            Err(err) => assert!(
+                is_expected(&err),
+                "{name} ({reason}) failed with {err:?}"
+            )
+        }
+    }
+}
+
+#[test]
+#[ignore = "regenerates the reference dumps"]
+fn regenerate_reference_dumps() {
+    std::fs::create_dir_all(suite_dir().join("reference")).unwrap();
+
+    for name in suite_images().iter().filter(|x| !is_corrupt(x)) {
+        let pixels = decode_ref(&open(name));
+
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::best());
+        encoder.write_all(&pixels).unwrap();
+
+        std::fs::write(reference_path(name), encoder.finish().unwrap()).unwrap();
+    }
+}
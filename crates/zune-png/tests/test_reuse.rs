@@ -0,0 +1,45 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+use zune_png::{DecodeBuffers, PngDecoder};
+
+fn open(name: &str) -> Vec<u8> {
+    let path = env!("CARGO_MANIFEST_DIR").to_string() + "/tests/png_suite/" + name + ".png";
+    std::fs::read(path).unwrap()
+}
+
+#[test]
+fn test_reused_buffers_match_decode_raw() {
+    let mut buffers = DecodeBuffers::new();
+
+    // in place, post processed, 16 bit and interlaced images
+    for name in [
+        "basn2c08", "basn3p04", "basn6a16", "basi2c08", "f02n2c08", "basn0g08"
+    ] {
+        let data = open(name);
+        let expected = PngDecoder::new(&data).decode_raw().unwrap();
+
+        PngDecoder::new(&data).decode_reusing(&mut buffers).unwrap();
+        assert_eq!(buffers.image(), expected, "{name}");
+    }
+}
+
+#[test]
+fn test_buffers_stop_growing() {
+    let data = open("f02n2c08");
+    let mut buffers = DecodeBuffers::new();
+
+    PngDecoder::new(&data).decode_reusing(&mut buffers).unwrap();
+    PngDecoder::new(&data).decode_reusing(&mut buffers).unwrap();
+    let capacity = buffers.capacity();
+
+    for _ in 0..4 {
+        PngDecoder::new(&data).decode_reusing(&mut buffers).unwrap();
+        assert_eq!(buffers.capacity(), capacity);
+    }
+}
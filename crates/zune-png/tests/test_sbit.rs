@@ -0,0 +1,175 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+use std::path::Path;
+
+use zune_core::bit_depth::BitDepth;
+use zune_core::checksum::crc32;
+use zune_core::colorspace::ColorSpace;
+use zune_core::options::{DecoderOptions, EncoderOptions};
+use zune_core::result::DecodingResult;
+use zune_png::{PngDecoder, PngEncoder};
+
+fn encode_u16(pixels: &[u16], width: usize, colorspace: ColorSpace, depth: BitDepth) -> Vec<u8> {
+    let height = pixels.len() / width / colorspace.num_components();
+    let bytes: Vec<u8> = pixels.iter().flat_map(|x| x.to_be_bytes()).collect();
+    let options = EncoderOptions::new(width, height, colorspace, depth);
+
+    PngEncoder::new(&bytes, options).encode()
+}
+
+/// Insert an sBIT chunk after the IHDR chunk of `png`
+fn add_sbit(png: &[u8], bits: &[u8]) -> Vec<u8> {
+    // signature and IHDR chunk
+    let (head, tail) = png.split_at(8 + 25);
+
+    let mut chunk = b"sBIT".to_vec();
+    chunk.extend_from_slice(bits);
+
+    let mut output = head.to_vec();
+    output.extend_from_slice(&(bits.len() as u32).to_be_bytes());
+    output.extend_from_slice(&chunk);
+    output.extend_from_slice(&crc32::crc32(&chunk).to_be_bytes());
+    output.extend_from_slice(tail);
+    output
+}
+
+fn decode_u16(data: &[u8], options: DecoderOptions) -> Vec<u16> {
+    let mut decoder = PngDecoder::new_with_options(data, options);
+
+    match decoder.decode().unwrap() {
+        DecodingResult::U16(pixels) => pixels,
+        _ => panic!("Expected 16 bit samples")
+    }
+}
+
+#[test]
+fn test_sbit_round_trip() {
+    for depth in [BitDepth::Ten, BitDepth::Twelve] {
+        let max = depth.max_value();
+        let pixels: Vec<u16> = (0..=max).collect();
+        let data = encode_u16(&pixels, 32, ColorSpace::RGBA, depth);
+
+        let mut decoder = PngDecoder::new(&data);
+        decoder.decode_headers().unwrap();
+
+        let bits = depth.significant_bits() as u8;
+        assert_eq!(decoder.get_depth(), Some(BitDepth::Sixteen));
+        assert_eq!(decoder.get_significant_depth(), Some(depth));
+        assert_eq!(
+            decoder.get_info().unwrap().significant_bits,
+            Some(vec![bits; 4])
+        );
+
+        let DecodingResult::U16(decoded) = decoder.decode().unwrap() else {
+            panic!("Expected 16 bit samples");
+        };
+        // samples fill the 16 bit range, shifting recovers the originals
+        assert_eq!(decoded.last(), Some(&u16::MAX));
+
+        let shift = 16 - depth.significant_bits();
+        let restored: Vec<u16> = decoded.iter().map(|x| x >> shift).collect();
+        assert_eq!(restored, pixels);
+    }
+}
+
+#[test]
+fn test_sixteen_bit_has_no_significant_depth() {
+    let data = encode_u16(&[0, 1000, 65535, 7], 2, ColorSpace::Luma, BitDepth::Sixteen);
+
+    let mut decoder = PngDecoder::new(&data);
+    decoder.decode_headers().unwrap();
+
+    assert_eq!(decoder.get_info().unwrap().significant_bits, None);
+    assert_eq!(decoder.get_significant_depth(), None);
+}
+
+#[test]
+fn test_sbit_png_suite() {
+    // 13 significant bits are stored as plain 16 bit
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/png_suite/cs3n2c16.png");
+    let data = std::fs::read(path).unwrap();
+
+    let mut decoder = PngDecoder::new(&data);
+    decoder.decode_headers().unwrap();
+
+    assert_eq!(
+        decoder.get_info().unwrap().significant_bits,
+        Some(vec![13; 3])
+    );
+    assert_eq!(decoder.get_significant_depth(), None);
+}
+
+#[test]
+fn test_rescale_unscaled_samples() {
+    // 12 bit values written as is into a 16 bit png
+    let pixels: Vec<u16> = (0..4096).collect();
+    let png = encode_u16(&pixels, 64, ColorSpace::Luma, BitDepth::Sixteen);
+    let png = add_sbit(&png, &[12]);
+
+    let options = DecoderOptions::default();
+    assert_eq!(decode_u16(&png, options), pixels);
+
+    let rescaled = decode_u16(&png, options.png_set_rescale_significant_bits(true));
+    let expected: Vec<u16> = pixels
+        .iter()
+        .map(|x| ((u32::from(*x) * 65535 + 2047) / 4095) as u16)
+        .collect();
+
+    assert_eq!(rescaled, expected);
+}
+
+#[test]
+fn test_rescale_keeps_scaled_samples() {
+    // the encoder scales 12 bit samples to the 16 bit range itself
+    let pixels: Vec<u16> = (0..4096).collect();
+    let png = encode_u16(&pixels, 64, ColorSpace::Luma, BitDepth::Twelve);
+
+    let options = DecoderOptions::default();
+    let expected = decode_u16(&png, options);
+
+    let rescaled = decode_u16(&png, options.png_set_rescale_significant_bits(true));
+    assert_eq!(rescaled, expected);
+}
+
+#[test]
+fn test_rescale_per_channel() {
+    // 5 bit color with 8 bit alpha, converted to RGBA
+    let pixels = [31, 255, 16, 128, 0, 0];
+    let options = EncoderOptions::new(3, 1, ColorSpace::LumaA, BitDepth::Eight);
+    let png = add_sbit(&PngEncoder::new(&pixels, options).encode(), &[5, 8]);
+
+    let options = DecoderOptions::default()
+        .png_set_out_colorspace(ColorSpace::RGBA)
+        .png_set_rescale_significant_bits(true);
+
+    let mut decoder = PngDecoder::new_with_options(&png, options);
+    let decoded = decoder.decode_raw().unwrap();
+
+    assert_eq!(
+        decoder.get_info().unwrap().significant_bits,
+        Some(vec![5, 8])
+    );
+    assert_eq!(
+        decoded,
+        [255, 255, 255, 255, 132, 132, 132, 128, 0, 0, 0, 0]
+    );
+}
+
+#[test]
+fn test_rescale_has_no_significant_depth() {
+    let pixels: Vec<u16> = (0..4096).collect();
+    let png = encode_u16(&pixels, 64, ColorSpace::Luma, BitDepth::Twelve);
+
+    let options = DecoderOptions::default().png_set_rescale_significant_bits(true);
+    let mut decoder = PngDecoder::new_with_options(&png, options);
+    decoder.decode_headers().unwrap();
+
+    // rescaled samples fill the 16 bit range
+    assert_eq!(decoder.get_significant_depth(), None);
+}
@@ -0,0 +1,124 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+use zune_core::bit_depth::BitDepth;
+use zune_core::colorspace::ColorSpace;
+use zune_core::options::{DecoderOptions, EncoderOptions, PngCrcPolicy, PngStrictness};
+use zune_png::{PngDecoder, PngEncoder};
+
+const EXIF: &[u8] = b"MM\0*\0\0\0\x08\0\0";
+
+fn encode_image() -> (Vec<u8>, Vec<u8>) {
+    let (width, height) = (8, 8);
+    let pixels: Vec<u8> = (0..width * height * 3).map(|x| x as u8).collect();
+
+    let options = EncoderOptions::new(width, height, ColorSpace::RGB, BitDepth::Eight);
+    let mut encoder = PngEncoder::new(&pixels, options);
+    encoder.add_exif_segment(EXIF);
+
+    (encoder.encode(), pixels)
+}
+
+/// Return the offset of the chunk data for the chunk named `name`
+fn find_chunk(data: &[u8], name: &[u8; 4]) -> usize {
+    data.windows(4).position(|x| x == name).unwrap() + 4
+}
+
+fn decode(
+    data: &[u8], strictness: PngStrictness
+) -> Result<Vec<u8>, zune_png::error::PngDecodeErrors> {
+    let options = DecoderOptions::default().png_set_strictness(strictness);
+    PngDecoder::new_with_options(data, options).decode_raw()
+}
+
+#[test]
+fn test_bad_ancillary_crc() {
+    let (mut data, pixels) = encode_image();
+    // corrupt the crc of the eXIf chunk
+    let crc_pos = find_chunk(&data, b"eXIf") + EXIF.len();
+    data[crc_pos] ^= 0xFF;
+
+    assert!(decode(&data, PngStrictness::STRICT).is_err());
+    assert_eq!(decode(&data, PngStrictness::PERMISSIVE).unwrap(), pixels);
+}
+
+#[test]
+fn test_bad_critical_crc() {
+    let (mut data, _) = encode_image();
+    // corrupt the crc of the IHDR chunk
+    let crc_pos = find_chunk(&data, b"IHDR") + 13;
+    data[crc_pos] ^= 0xFF;
+
+    assert!(decode(&data, PngStrictness::PERMISSIVE).is_err());
+    assert!(decode(&data, PngStrictness::NONE).is_ok());
+}
+
+#[test]
+fn test_trailing_data() {
+    let (mut data, pixels) = encode_image();
+    data.extend_from_slice(b"garbage after the image");
+
+    assert!(decode(&data, PngStrictness::TRAILING_DATA).is_err());
+    assert_eq!(decode(&data, PngStrictness::PERMISSIVE).unwrap(), pixels);
+    // the default tolerates trailing data
+    let mut decoder = PngDecoder::new(&data);
+    assert_eq!(decoder.decode_raw().unwrap(), pixels);
+    // and so does strict mode
+    let options = DecoderOptions::default().set_strict_mode(true);
+    let mut decoder = PngDecoder::new_with_options(&data, options);
+    assert_eq!(decoder.decode_raw().unwrap(), pixels);
+}
+
+#[test]
+fn test_bad_zlib_fcheck() {
+    let (mut data, pixels) = encode_image();
+    // flip a bit in FCHECK of the zlib header
+    let flg_pos = find_chunk(&data, b"IDAT") + 1;
+    data[flg_pos] ^= 1;
+
+    let decode = |strictness| {
+        let options = DecoderOptions::default()
+            .png_set_confirm_crc(false)
+            .png_set_strictness(strictness);
+        PngDecoder::new_with_options(&data, options).decode_raw()
+    };
+    assert!(decode(PngStrictness::ZLIB_HEADER).is_err());
+    assert_eq!(decode(PngStrictness::PERMISSIVE).unwrap(), pixels);
+}
+
+#[test]
+fn test_crc_policy() {
+    let (data, pixels) = encode_image();
+
+    let decode = |data: &[u8], policy| {
+        let options = DecoderOptions::default()
+            .png_set_strictness(PngStrictness::STRICT)
+            .png_set_crc_policy(policy);
+        PngDecoder::new_with_options(data, options).decode_raw()
+    };
+    // chunks are IHDR, eXIf, IDAT and IEND
+    let mut bad_exif = data.clone();
+    bad_exif[find_chunk(&data, b"eXIf") + EXIF.len()] ^= 0xFF;
+
+    let mut bad_ihdr = data.clone();
+    bad_ihdr[find_chunk(&data, b"IHDR") + 13] ^= 0xFF;
+
+    assert!(decode(&bad_exif, PngCrcPolicy::All).is_err());
+    assert_eq!(
+        decode(&bad_exif, PngCrcPolicy::CriticalOnly).unwrap(),
+        pixels
+    );
+    assert!(decode(&bad_ihdr, PngCrcPolicy::CriticalOnly).is_err());
+
+    assert_eq!(
+        decode(&bad_exif, PngCrcPolicy::FirstChunks(1)).unwrap(),
+        pixels
+    );
+    assert!(decode(&bad_exif, PngCrcPolicy::FirstChunks(2)).is_err());
+    assert!(decode(&bad_ihdr, PngCrcPolicy::FirstChunks(1)).is_err());
+}
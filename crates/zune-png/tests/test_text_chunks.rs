@@ -0,0 +1,40 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+use zune_core::bit_depth::BitDepth;
+use zune_core::colorspace::ColorSpace;
+use zune_core::options::EncoderOptions;
+use zune_png::{PngDecoder, PngEncoder};
+
+#[test]
+fn test_text_chunks_round_trip() {
+    let pixels = [0_u8; 4 * 4];
+    let options = EncoderOptions::new(4, 4, ColorSpace::Luma, BitDepth::Eight);
+
+    let mut encoder = PngEncoder::new(&pixels, options);
+    encoder.add_text_chunk("Comment", "plain ascii");
+    encoder.add_text_chunk("Title", "caf\u{e9}");
+    // invalid keywords are skipped
+    encoder.add_text_chunk(" Padded", "dropped");
+    encoder.add_text_chunk("", "dropped");
+
+    let data = encoder.encode();
+
+    let mut decoder = PngDecoder::new(&data);
+    assert_eq!(decoder.decode_raw().unwrap(), pixels);
+
+    let info = decoder.get_info().unwrap();
+
+    assert_eq!(info.text_chunk.len(), 1);
+    assert_eq!(info.text_chunk[0].keyword, b"Comment");
+    assert_eq!(info.text_chunk[0].text, b"plain ascii");
+
+    assert_eq!(info.itxt_chunk.len(), 1);
+    assert_eq!(info.itxt_chunk[0].keyword, b"Title");
+    assert_eq!(info.itxt_chunk[0].text, "caf\u{e9}".as_bytes());
+}
@@ -0,0 +1,37 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+#![cfg(feature = "threads")]
+
+use std::fs::read;
+use std::path::Path;
+
+use zune_core::options::DecoderOptions;
+use zune_png::PngDecoder;
+
+fn decode(data: &[u8], threaded: bool) -> Vec<u8> {
+    let options = DecoderOptions::default().png_set_threaded_adam7(threaded);
+    PngDecoder::new_with_options(data, options)
+        .decode_raw()
+        .unwrap()
+}
+
+fn test_decoding<P: AsRef<Path>>(path: P) {
+    let contents = read(path).unwrap();
+
+    assert_eq!(decode(&contents, false), decode(&contents, true));
+}
+
+#[test]
+fn test_threaded_adam7() {
+    for name in [
+        "basi0g01", "basi0g16", "basi2c08", "basi2c16", "basi3p02", "basi4a08", "basi6a16"
+    ] {
+        let path = env!("CARGO_MANIFEST_DIR").to_string() + "/tests/png_suite/" + name + ".png";
+        test_decoding(path);
+    }
+}
@@ -17,10 +17,11 @@ use zune_core::colorspace::ColorSpace;
 use zune_core::log::trace;
 use zune_core::options::DecoderOptions;
 use zune_core::result::DecodingResult;
+use zune_core::utils::checked_image_size;
 
 /// An instance of a PPM decoder
 ///
-/// The decoder can currently decode P5 and P6 formats
+/// The decoder can currently decode P5, P6, P7 (PAM) and PFM formats
 pub struct PPMDecoder<T>
 where
     T: ZReaderTrait
@@ -31,6 +32,7 @@ where
     reader:          ZByteReader<T>,
     colorspace:      ColorSpace,
     bit_depth:       BitDepth,
+    max_value:       usize,
     options:         DecoderOptions
 }
 
@@ -113,6 +115,7 @@ where
             reader,
             colorspace: ColorSpace::Unknown,
             bit_depth: BitDepth::Eight,
+            max_value: 255,
             options
         }
     }
@@ -161,6 +164,7 @@ where
 
             return Err(PPMDecodeErrors::InvalidHeader(msg));
         }
+        self.output_size()?;
 
         Ok(())
     }
@@ -230,118 +234,123 @@ where
         Ok(())
     }
     /// Decode header types from P7 format
+    ///
+    /// The header is a list of `KEY value` lines terminated by an `ENDHDR` line,
+    /// `WIDTH`, `HEIGHT`, `DEPTH` and `MAXVAL` are required, `TUPLTYPE` is optional
+    /// and the colorspace is inferred from `DEPTH` if it is missing or unknown.
     fn decode_p7_header(&mut self) -> Result<(), PPMDecodeErrors> {
-        let mut seen_depth = false;
-        let mut seen_width = false;
-        let mut seen_height = false;
-        let mut seen_max_val = false;
-        let mut seen_tuple_type = false;
+        let mut width = None;
+        let mut height = None;
+        let mut depth = None;
+        let mut max_value = None;
+        let mut tuple_type = Vec::new();
 
-        let mut byte_header = Vec::with_capacity(20);
+        let mut line = Vec::with_capacity(40);
 
-        'infinite: loop {
+        loop {
             if self.reader.eof() {
-                return Err(PPMDecodeErrors::InvalidHeader("No more bytes".to_string()));
+                return Err(PPMDecodeErrors::InvalidHeader(
+                    "No ENDHDR found before end of stream".to_string()
+                ));
             }
-            skip_spaces(&mut self.reader);
-
-            let value_size = get_bytes_until_whitespace(&mut self.reader, &mut byte_header);
-            let value = &byte_header[..value_size];
-
-            match value {
-                // Notice the explicit space,
-                // It's needed
-                b"WIDTH " => {
-                    self.width = self.get_integer();
-
-                    if self.width > self.options.get_max_width() {
-                        return Err(PPMDecodeErrors::LargeDimensions(
-                            self.options.get_max_width(),
-                            self.width
-                        ));
-                    }
-                    seen_width = true;
-                }
-                b"HEIGHT " => {
-                    self.height = self.get_integer();
-
-                    if self.height > self.options.get_max_height() {
-                        return Err(PPMDecodeErrors::LargeDimensions(
-                            self.options.get_max_height(),
-                            self.height
-                        ));
-                    }
+            read_line(&mut self.reader, &mut line);
 
-                    seen_height = true;
-                }
-                b"DEPTH " => {
-                    let depth = self.get_integer();
+            let contents = trim_whitespace(&line);
 
-                    if depth > 4 {
-                        let msg = format!("Depth {depth} is greater than 4");
-                        return Err(PPMDecodeErrors::InvalidHeader(msg));
+            if contents.is_empty() || contents[0] == b'#' {
+                continue;
+            }
+            let key_end = contents
+                .iter()
+                .position(u8::is_ascii_whitespace)
+                .unwrap_or(contents.len());
+
+            let (key, value) = contents.split_at(key_end);
+            let value = trim_whitespace(value);
+
+            match key {
+                b"WIDTH" => width = Some(parse_header_integer("WIDTH", value)?),
+                b"HEIGHT" => height = Some(parse_header_integer("HEIGHT", value)?),
+                b"DEPTH" => depth = Some(parse_header_integer("DEPTH", value)?),
+                b"MAXVAL" => max_value = Some(parse_header_integer("MAXVAL", value)?),
+                b"TUPLTYPE" => {
+                    // multiple TUPLTYPE lines are concatenated, separated by a space
+                    if !tuple_type.is_empty() {
+                        tuple_type.push(b' ');
                     }
-
-                    seen_depth = true;
+                    tuple_type.extend_from_slice(value);
                 }
-                b"MAXVAL " => {
-                    let max_value = self.get_integer();
+                b"ENDHDR" => break,
+                _ => {
+                    let msg = format!(
+                        "Unknown/unsupported header declaration {}",
+                        String::from_utf8_lossy(key)
+                    );
+                    return Err(PPMDecodeErrors::InvalidHeader(msg));
+                }
+            }
+        }
+        let missing = |name: &str| PPMDecodeErrors::InvalidHeader(format!("Missing {name} header"));
 
-                    if max_value > usize::from(u16::MAX) {
-                        let msg = format!("MAX value {max_value} greater than 65535");
+        self.width = width.ok_or_else(|| missing("WIDTH"))?;
+        self.height = height.ok_or_else(|| missing("HEIGHT"))?;
+        let depth = depth.ok_or_else(|| missing("DEPTH"))?;
+        let max_value = max_value.ok_or_else(|| missing("MAXVAL"))?;
 
-                        return Err(PPMDecodeErrors::Generic(msg));
-                    }
+        if self.width > self.options.get_max_width() {
+            return Err(PPMDecodeErrors::LargeDimensions(
+                self.options.get_max_width(),
+                self.width
+            ));
+        }
+        if self.height > self.options.get_max_height() {
+            return Err(PPMDecodeErrors::LargeDimensions(
+                self.options.get_max_height(),
+                self.height
+            ));
+        }
+        self.set_max_value(max_value)?;
+
+        let expected_depth = match &tuple_type[..] {
+            b"GRAYSCALE" | b"BLACKANDWHITE" => Some((ColorSpace::Luma, 1)),
+            b"GRAYSCALE_ALPHA" | b"BLACKANDWHITE_ALPHA" => Some((ColorSpace::LumaA, 2)),
+            b"RGB" => Some((ColorSpace::RGB, 3)),
+            b"RGB_ALPHA" => Some((ColorSpace::RGBA, 4)),
+            _ => None
+        };
 
-                    if max_value > 255 {
-                        // 16 bit
-                        self.bit_depth = BitDepth::Sixteen;
-                    } else {
-                        self.bit_depth = BitDepth::Eight;
-                    }
-                    seen_max_val = true;
+        self.colorspace = match expected_depth {
+            Some((colorspace, components)) => {
+                if depth != components {
+                    let msg = format!(
+                        "Tuple type {} expects a DEPTH of {components} but found {depth}",
+                        String::from_utf8_lossy(&tuple_type)
+                    );
+                    return Err(PPMDecodeErrors::InvalidHeader(msg));
                 }
-                b"TUPLTYPE " => {
-                    let value_size = get_bytes_until_whitespace(&mut self.reader, &mut byte_header);
-                    let new_value = &byte_header[..value_size];
-
-                    // Order matters here.
-                    // we want to match RGB_ALPHA before matching RGB
-                    if new_value.starts_with(b"RGB_ALPHA") {
-                        self.colorspace = ColorSpace::RGBA;
-                    } else if new_value.starts_with(b"RGB") {
-                        self.colorspace = ColorSpace::RGB;
-                    } else if new_value.starts_with(b"GRAYSCALE_ALPHA") {
-                        self.colorspace = ColorSpace::LumaA;
-                    } else if new_value.starts_with(b"GRAYSCALE") {
-                        self.colorspace = ColorSpace::Luma;
-                    } else {
-                        let msg = format!(
-                            "Unknown/unsupported tuple type {}",
-                            String::from_utf8_lossy(new_value)
-                        );
-                        return Err(PPMDecodeErrors::InvalidHeader(msg));
-                    }
-                    seen_tuple_type = true;
-                }
-                _ => {
-                    if value.starts_with(b"ENDHDR") {
-                        break 'infinite;
-                    } else {
-                        let msg = format!(
-                            "Unknown/unsupported header declaration {}",
-                            String::from_utf8_lossy(value)
-                        );
+                colorspace
+            }
+            None => {
+                let colorspace = match depth {
+                    1 => ColorSpace::Luma,
+                    2 => ColorSpace::LumaA,
+                    3 => ColorSpace::RGB,
+                    4 => ColorSpace::RGBA,
+                    _ => {
+                        let msg =
+                            format!("Unsupported DEPTH {depth}, expected a value from 1 to 4");
                         return Err(PPMDecodeErrors::InvalidHeader(msg));
                     }
+                };
+                if !tuple_type.is_empty() {
+                    trace!(
+                        "Unknown tuple type {}, treating it as {colorspace:?}",
+                        String::from_utf8_lossy(&tuple_type)
+                    );
                 }
+                colorspace
             }
-        }
-        if !seen_max_val || !seen_tuple_type || !seen_height || !seen_width || !seen_depth {
-            return Err(PPMDecodeErrors::InvalidHeader(
-                "Not all expected headers were found".to_string()
-            ));
-        }
+        };
 
         self.decoded_headers = true;
 
@@ -398,23 +407,50 @@ where
         // skip ascii space
         skip_spaces(&mut self.reader);
 
-        if max_value > usize::from(u16::MAX) {
-            let msg = format!("MAX value {max_value} greater than 65535");
+        self.set_max_value(max_value)?;
+
+        trace!("Bit Depth: {:?}", self.bit_depth);
+        self.decoded_headers = true;
+
+        Ok(())
+    }
+
+    /// Validate the maximum sample value and set the bit depth from it
+    fn set_max_value(&mut self, max_value: usize) -> Result<(), PPMDecodeErrors> {
+        if max_value == 0 || max_value > usize::from(u16::MAX) {
+            let msg = format!("MAX value {max_value} should be between 1 and 65535");
 
             return Err(PPMDecodeErrors::Generic(msg));
         }
+        self.max_value = max_value;
 
         if max_value > 255 {
             // 16 bit
             self.bit_depth = BitDepth::Sixteen;
+        } else {
+            self.bit_depth = BitDepth::Eight;
         }
-
-        trace!("Bit Depth: {:?}", self.bit_depth);
-        self.decoded_headers = true;
-
         Ok(())
     }
 
+    /// Return the number of bytes in the decoded image, or an error
+    /// if it overflows a `usize`
+    fn output_size(&self) -> Result<usize, PPMDecodeErrors> {
+        checked_image_size(
+            self.width,
+            self.height,
+            self.colorspace.num_components(),
+            self.bit_depth
+        )
+        .ok_or_else(|| {
+            let msg = format!(
+                "Image dimensions {}x{} are too large to decode on this platform",
+                self.width, self.height
+            );
+            PPMDecodeErrors::Generic(msg)
+        })
+    }
+
     fn get_integer(&mut self) -> usize {
         let mut value = 0_usize;
 
@@ -422,10 +458,11 @@ where
             let byte = self.reader.get_u8();
 
             if byte.is_ascii_digit() {
-                // if it overflows, we have bigger problems.
+                // saturate on overflow, so huge values are rejected
+                // by the dimension checks instead of wrapping to small ones
                 value = value
-                    .wrapping_mul(10_usize)
-                    .wrapping_add(usize::from(byte - b'0'))
+                    .saturating_mul(10_usize)
+                    .saturating_add(usize::from(byte - b'0'))
             } else {
                 // rewind to the previous byte
                 self.reader.rewind(1);
@@ -531,8 +568,7 @@ where
             ));
         }
         // okay check if the stream is large enough for the bit depth
-        let size =
-            self.width * self.height * self.colorspace.num_components() * self.bit_depth.size_of();
+        let size = self.output_size()?;
 
         let remaining = self.reader.remaining();
 
@@ -547,6 +583,11 @@ where
                 // get the bytes
                 data.copy_from_slice(self.reader.get(size).unwrap());
 
+                if self.max_value != 255 {
+                    let max_value = self.max_value as u32;
+                    data.iter_mut()
+                        .for_each(|x| *x = scale_sample(u32::from(*x), max_value, 255) as u8);
+                }
                 Ok(DecodingResult::U8(data))
             }
             BitType::U16 => {
@@ -559,12 +600,18 @@ where
                 // borrowing uninitialized memory from the heap
                 let remaining = self.reader.remaining_bytes();
 
-                let data = remaining
+                let mut data = remaining
                     .chunks_exact(2)
                     .take(size / 2)
                     .map(|b| u16::from_be_bytes(b.try_into().unwrap()))
                     .collect::<Vec<u16>>();
 
+                if self.max_value != 65535 {
+                    let max_value = self.max_value as u32;
+                    data.iter_mut()
+                        .for_each(|x| *x = scale_sample(u32::from(*x), max_value, 65535) as u16);
+                }
+
                 Ok(DecodingResult::U16(data))
             }
             BitType::F32 => {
@@ -618,6 +665,59 @@ where
     }
 }
 
+/// Rescale a sample from `0..=max_value` to `0..=target`, rounding to nearest
+///
+/// Samples above `max_value` are invalid, they are clamped to it
+fn scale_sample(sample: u32, max_value: u32, target: u32) -> u32 {
+    (sample.min(max_value) * target + max_value / 2) / max_value
+}
+
+/// Read bytes up to and including the next newline, the newline is not
+/// written to `write_to`
+fn read_line<T>(z: &mut ZByteReader<T>, write_to: &mut Vec<u8>)
+where
+    T: ZReaderTrait
+{
+    write_to.clear();
+
+    while !z.eof() {
+        let byte = z.get_u8();
+
+        if byte == b'\n' {
+            break;
+        }
+        write_to.push(byte);
+    }
+}
+
+/// Remove leading and trailing ascii whitespace
+fn trim_whitespace(bytes: &[u8]) -> &[u8] {
+    let start = bytes
+        .iter()
+        .position(|x| !x.is_ascii_whitespace())
+        .unwrap_or(bytes.len());
+    let end = bytes
+        .iter()
+        .rposition(|x| !x.is_ascii_whitespace())
+        .map_or(start, |x| x + 1);
+
+    &bytes[start..end]
+}
+
+/// Parse the value of a P7 header line as an integer
+fn parse_header_integer(name: &str, value: &[u8]) -> Result<usize, PPMDecodeErrors> {
+    core::str::from_utf8(value)
+        .ok()
+        .and_then(|x| x.parse::<usize>().ok())
+        .ok_or_else(|| {
+            let msg = format!(
+                "Invalid {name} value {}, expected an integer",
+                String::from_utf8_lossy(value)
+            );
+            PPMDecodeErrors::InvalidHeader(msg)
+        })
+}
+
 /// Skip all whitespace characters and comments
 /// until one hits a character that isn't a space or
 /// we reach eof
@@ -119,7 +119,7 @@ impl<'a> PPMEncoder<'a> {
                 let tuple_type = convert_tuple_type_to_pam(colorspace);
 
                 format!(
-                    "P7\nWIDTH {width}\nHEIGHT {height}\nDEPTH {components}\nMAXVAL {max_val}\nTUPLTYPE {tuple_type}\n ENDHDR\n",
+                    "P7\nWIDTH {width}\nHEIGHT {height}\nDEPTH {components}\nMAXVAL {max_val}\nTUPLTYPE {tuple_type}\nENDHDR\n",
                 )
             }
         };
@@ -128,6 +128,22 @@ impl<'a> PPMEncoder<'a> {
 
         Ok(())
     }
+    /// Encode only the image header
+    ///
+    /// The header is followed by the samples, 16 bit samples in big endian,
+    /// this allows writing an image in pieces without holding the
+    /// whole encoded image in memory
+    pub fn encode_header(&self) -> Result<Vec<u8>, PPMEncodeErrors> {
+        let mut out = vec![0; PPM_HEADER_SIZE];
+        let mut stream = ZByteWriter::new(&mut out);
+
+        self.encode_headers(&mut stream)?;
+
+        let position = stream.position();
+        out.truncate(position);
+
+        Ok(out)
+    }
     /// Encode into a user provided buffer
     ///
     /// # Arguments
@@ -0,0 +1,170 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! Tests for P7 (PAM) headers
+use zune_core::bit_depth::BitDepth;
+use zune_core::colorspace::ColorSpace;
+use zune_core::options::{DecoderOptions, EncoderOptions};
+use zune_core::result::DecodingResult;
+use zune_ppm::{PPMDecoder, PPMEncoder};
+
+fn pam(header: &str, data: &[u8]) -> Vec<u8> {
+    let mut file = header.as_bytes().to_vec();
+    file.extend_from_slice(data);
+    file
+}
+
+#[test]
+fn test_tuple_types() {
+    let cases = [
+        ("GRAYSCALE", 1, ColorSpace::Luma),
+        ("GRAYSCALE_ALPHA", 2, ColorSpace::LumaA),
+        ("RGB", 3, ColorSpace::RGB),
+        ("RGB_ALPHA", 4, ColorSpace::RGBA),
+        ("BLACKANDWHITE", 1, ColorSpace::Luma)
+    ];
+    for (tuple_type, depth, colorspace) in cases {
+        let header = format!(
+            "P7\nWIDTH 2\nHEIGHT 1\nDEPTH {depth}\nMAXVAL 255\nTUPLTYPE {tuple_type}\nENDHDR\n"
+        );
+        let file = pam(&header, &vec![7; 2 * depth]);
+        let mut decoder = PPMDecoder::new(&file);
+
+        decoder.decode_headers().unwrap();
+
+        assert_eq!(decoder.get_colorspace(), Some(colorspace), "{tuple_type}");
+        assert_eq!(decoder.get_bit_depth(), Some(BitDepth::Eight));
+        assert!(decoder.decode().is_ok(), "{tuple_type}");
+    }
+}
+
+#[test]
+fn test_header_order_comments_and_missing_tuple_type() {
+    let file = pam(
+        "P7\n# a comment\nTUPLTYPE\tGRAYSCALE_ALPHA \nDEPTH 2\nMAXVAL 65535\nHEIGHT 1\nWIDTH 1\nENDHDR\n",
+        &[1, 2, 3, 4]
+    );
+    let mut decoder = PPMDecoder::new(&file);
+    decoder.decode_headers().unwrap();
+    assert_eq!(decoder.get_colorspace(), Some(ColorSpace::LumaA));
+    assert_eq!(decoder.get_bit_depth(), Some(BitDepth::Sixteen));
+
+    // no tuple type, colorspace comes from depth
+    let file = pam(
+        "P7\nWIDTH 1\nHEIGHT 1\nDEPTH 4\nMAXVAL 255\nENDHDR\n",
+        &[0; 4]
+    );
+    let mut decoder = PPMDecoder::new(&file);
+    decoder.decode_headers().unwrap();
+    assert_eq!(decoder.get_colorspace(), Some(ColorSpace::RGBA));
+}
+
+#[test]
+fn test_data_starting_with_whitespace() {
+    // pixel bytes that look like whitespace or comments must not be eaten by the header parser
+    let data = [b'\n', b' ', b'#', 9];
+    let file = pam(
+        "P7\nWIDTH 1\nHEIGHT 1\nDEPTH 4\nMAXVAL 255\nTUPLTYPE RGB_ALPHA\nENDHDR\n",
+        &data
+    );
+    let pixels = PPMDecoder::new(&file).decode().unwrap();
+
+    match pixels {
+        DecodingResult::U8(pixels) => assert_eq!(pixels, data),
+        _ => panic!("Expected 8 bit output")
+    }
+}
+
+#[test]
+fn test_invalid_headers() {
+    let invalid = [
+        // depth doesn't match tuple type
+        "P7\nWIDTH 1\nHEIGHT 1\nDEPTH 3\nMAXVAL 255\nTUPLTYPE RGB_ALPHA\nENDHDR\n",
+        // maxval out of range
+        "P7\nWIDTH 1\nHEIGHT 1\nDEPTH 1\nMAXVAL 0\nTUPLTYPE GRAYSCALE\nENDHDR\n",
+        "P7\nWIDTH 1\nHEIGHT 1\nDEPTH 1\nMAXVAL 65536\nTUPLTYPE GRAYSCALE\nENDHDR\n",
+        // missing depth
+        "P7\nWIDTH 1\nHEIGHT 1\nMAXVAL 255\nTUPLTYPE GRAYSCALE\nENDHDR\n",
+        // unknown tuple type with unsupported depth
+        "P7\nWIDTH 1\nHEIGHT 1\nDEPTH 5\nMAXVAL 255\nTUPLTYPE CMYK_ALPHA\nENDHDR\n",
+        // no ENDHDR
+        "P7\nWIDTH 1\nHEIGHT 1\nDEPTH 1\nMAXVAL 255\n",
+        // not a number
+        "P7\nWIDTH one\nHEIGHT 1\nDEPTH 1\nMAXVAL 255\nENDHDR\n"
+    ];
+    for header in invalid {
+        assert!(
+            PPMDecoder::new(header.as_bytes()).decode_headers().is_err(),
+            "{header:?}"
+        );
+    }
+}
+
+#[test]
+fn test_max_value_scaling() {
+    let file = pam(
+        "P7\nWIDTH 2\nHEIGHT 1\nDEPTH 1\nMAXVAL 1\nTUPLTYPE BLACKANDWHITE\nENDHDR\n",
+        &[0, 1]
+    );
+    match PPMDecoder::new(&file).decode().unwrap() {
+        DecodingResult::U8(pixels) => assert_eq!(pixels, [0, 255]),
+        _ => panic!("Expected 8 bit output")
+    }
+
+    let file = pam(
+        "P7\nWIDTH 2\nHEIGHT 1\nDEPTH 1\nMAXVAL 1023\nTUPLTYPE GRAYSCALE\nENDHDR\n",
+        &[0x03, 0xFF, 0x02, 0x00]
+    );
+    match PPMDecoder::new(&file).decode().unwrap() {
+        DecodingResult::U16(pixels) => assert_eq!(pixels, [65535, 32800]),
+        _ => panic!("Expected 16 bit output")
+    }
+}
+
+#[test]
+fn test_16_bit_alpha_round_trip() {
+    for colorspace in [ColorSpace::LumaA, ColorSpace::RGBA] {
+        let (width, height) = (3, 2);
+        let samples: Vec<u16> = (0..width * height * colorspace.num_components())
+            .map(|x| (x * 4099) as u16)
+            .collect();
+        let bytes: Vec<u8> = samples.iter().flat_map(|x| x.to_ne_bytes()).collect();
+
+        let options = EncoderOptions::new(width, height, colorspace, BitDepth::Sixteen);
+        let encoded = PPMEncoder::new(&bytes, options).encode().unwrap();
+
+        let mut decoder = PPMDecoder::new(&encoded);
+        decoder.decode_headers().unwrap();
+
+        assert_eq!(decoder.get_colorspace(), Some(colorspace));
+        assert_eq!(decoder.get_bit_depth(), Some(BitDepth::Sixteen));
+        assert_eq!(decoder.get_dimensions(), Some((width, height)));
+
+        match decoder.decode().unwrap() {
+            DecodingResult::U16(pixels) => assert_eq!(pixels, samples),
+            _ => panic!("Expected 16 bit output")
+        }
+    }
+}
+
+#[test]
+fn test_overflowing_dimensions() {
+    let options = DecoderOptions::default()
+        .set_max_width(usize::MAX)
+        .set_max_height(usize::MAX);
+
+    let headers = [
+        "P7\nWIDTH 4294967296\nHEIGHT 4294967296\nDEPTH 4\nMAXVAL 65535\nENDHDR\n",
+        // doesn't fit in a usize, saturates instead of wrapping
+        "P6 99999999999999999999999 1 255\n"
+    ];
+    for header in headers {
+        let mut decoder = PPMDecoder::new_with_options(header.as_bytes(), options);
+        assert!(decoder.decode_headers().is_err(), "{header:?}");
+    }
+}
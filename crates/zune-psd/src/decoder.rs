@@ -42,15 +42,18 @@ pub struct PSDDecoder<T>
 where
     T: ZReaderTrait
 {
-    width:          usize,
-    height:         usize,
-    decoded_header: bool,
-    stream:         ZByteReader<T>,
-    options:        DecoderOptions,
-    depth:          BitDepth,
-    color_type:     Option<ColorModes>,
-    compression:    CompressionMethod,
-    channel_count:  usize
+    width:              usize,
+    height:             usize,
+    decoded_header:     bool,
+    stream:             ZByteReader<T>,
+    options:            DecoderOptions,
+    depth:              BitDepth,
+    color_type:         Option<ColorModes>,
+    compression:        CompressionMethod,
+    /// Channels of the composite image we return
+    channel_count:      usize,
+    /// Channels stored in the file, including spot colors and masks
+    file_channel_count: usize
 }
 
 impl<T> PSDDecoder<T>
@@ -81,7 +84,8 @@ where
             depth: BitDepth::Eight,
             color_type: None,
             compression: CompressionMethod::NoCompression,
-            channel_count: 0
+            channel_count: 0,
+            file_channel_count: 0
         }
     }
 
@@ -112,11 +116,12 @@ where
         // Read the number of channels (R, G, B, A, etc).
         let channel_count = self.stream.get_u16_be_err()?;
 
-        if channel_count > 4 {
+        // the spec allows up to 56 channels
+        if channel_count > 56 {
             return Err(PSDDecodeErrors::UnsupportedChannelCount(channel_count));
         }
 
-        self.channel_count = usize::from(channel_count);
+        self.file_channel_count = usize::from(channel_count);
 
         let height = self.stream.get_u32_be_err()? as usize;
         let width = self.stream.get_u32_be_err()? as usize;
@@ -138,7 +143,7 @@ where
         self.width = width;
         self.height = height;
 
-        if self.width == 0 || self.height == 0 || self.channel_count == 0 {
+        if self.width == 0 || self.height == 0 || self.file_channel_count == 0 {
             return Err(PSDDecodeErrors::ZeroDimensions);
         }
 
@@ -189,12 +194,18 @@ where
         if compression > 1 {
             return Err(PSDDecodeErrors::UnknownCompression);
         }
-        if self.color_type == Some(ColorModes::Grayscale) {
-            // PSD may have grayscale images with more than one
-            // channel and will specify channel_count as 3.
-            // So let's fix that here
-            self.channel_count = 1;
+        // Files may have more channels than the color mode needs (e.g grayscale images
+        // specifying channel_count as 3, or spot colors), only the color channels and
+        // alpha for RGB are part of the composite image
+        let (min_channels, max_channels) = match self.color_type {
+            Some(ColorModes::Grayscale) => (1, 1),
+            Some(ColorModes::RGB) => (3, 4),
+            _ => (4, 4)
+        };
+        if self.file_channel_count < min_channels {
+            return Err(PSDDecodeErrors::UnsupportedChannelCount(channel_count));
         }
+        self.channel_count = self.file_channel_count.min(max_channels);
 
         self.compression = CompressionMethod::from_int(compression).unwrap();
 
@@ -210,6 +221,8 @@ where
 
     /// Decode an image to bytes without regard to depth or endianness
     ///
+    /// 16 bit samples are stored in big endian
+    ///
     /// # Returns
     /// Ok(bytes):  Raw bytes of the image
     /// Err(E): An error if it occurred during decoding
@@ -217,102 +230,49 @@ where
         if !self.decoded_header {
             self.decode_headers()?;
         }
+        let sample_size = self.depth.size_of();
+        // size of a single channel in bytes
+        let plane_size = self.width * self.height * sample_size;
 
-        let pixel_count = self.width * self.height;
-
-        let mut result = match (self.compression, self.depth) {
-            (CompressionMethod::RLE, BitDepth::Eight) => {
-                // RLE
-                // Loop until you get the number of unpacked bytes you are expecting:
-                //     Read the next source byte into n.
-                //     If n is between 0 and 127 inclusive, copy the next n+1 bytes
-                //     literally. Else if n is between -127 and -1 inclusive, copy the next
-                //     byte -n+1 times. Else if n is 128, noop.
-                // Endloop
-
-                // The RLE-compressed data is preceded by a 2-byte data count for each row
-                // in the data, which we're going to just skip.
-                let skipped = self.height * self.channel_count * 2;
-                self.stream.skip(skipped);
-
-                let mut out_channel = vec![0; pixel_count * self.channel_count + 10];
-
-                for channel in 0..self.channel_count {
-                    let pixel_count = self.width * self.height;
-                    self.psd_decode_rle(pixel_count, &mut out_channel[channel..])?;
-                }
-
-                out_channel.truncate(pixel_count * self.channel_count);
-
-                out_channel
-            }
-            (CompressionMethod::NoCompression, BitDepth::Eight) => {
-                // We're at the raw image data.  It's each channel in order (Red, Green,
-                // Blue, Alpha, ...) where each channel consists of an 8-bit
-                // value for each pixel in the image.
-
-                // Read the data by channel.
-
-                let mut out_channel = vec![0; self.width * self.height * self.channel_count + 10];
-                let pixel_count = self.width * self.height;
+        // The image data section stores the composite image channel by channel (Red, Green,
+        // Blue, Alpha, ...), extra channels (spot colors, masks) follow the ones we read.
+        let mut planes = vec![0; plane_size * self.channel_count];
 
+        match self.compression {
+            CompressionMethod::NoCompression => {
                 // check we have enough data
-                if !self.stream.has(pixel_count * self.channel_count) {
+                if !self.stream.has(planes.len()) {
                     return Err(PSDDecodeErrors::Generic("Incomplete bitstream"));
                 }
-
-                for channel in 0..self.channel_count {
-                    let mut i = channel;
-
-                    while i < pixel_count {
-                        out_channel[i] = self.stream.get_u8();
-                        i += self.channel_count;
-                    }
-                }
-
-                out_channel.truncate(pixel_count * self.channel_count);
-                out_channel
+                self.stream.read_exact(&mut planes)?;
             }
+            CompressionMethod::RLE => {
+                // The RLE-compressed data is preceded by a 2-byte data count for each row
+                // of every channel in the file, which we're going to just skip.
+                self.stream.skip(self.height * self.file_channel_count * 2);
 
-            (CompressionMethod::NoCompression, BitDepth::Sixteen) => {
-                // We're at the raw image data.  It's each channel in order (Red, Green,
-                // Blue, Alpha, ...) where each channel consists of an 8-bit
-                // value for each pixel in the image.
-
-                // Read the data by channel.
-
-                // size of a single channel
-                let channel_dimensions = self.width * self.height;
-
-                let mut out_channel = vec![0; 2 * (channel_dimensions * self.channel_count + 10)];
-
-                let pixel_count = channel_dimensions * 2;
-
-                // check we have enough data
-                if !self.stream.has(pixel_count * self.channel_count) {
-                    return Err(PSDDecodeErrors::Generic("Incomplete bitstream"));
-                }
-
-                // iterate per channel
-                for channel in 0..self.channel_count {
-                    let i = channel * 2;
-                    let out_chunks = out_channel[i..].chunks_exact_mut(self.channel_count * 2);
-
-                    // iterate only taking the image dimensions
-                    for out in out_chunks.take(channel_dimensions) {
-                        let value = self.stream.get_u16_be();
-
-                        out[..2].copy_from_slice(&value.to_ne_bytes());
-                    }
+                for plane in planes.chunks_exact_mut(plane_size) {
+                    self.psd_decode_rle(plane)?;
                 }
-
-                out_channel.truncate(pixel_count * self.channel_count);
-                out_channel
             }
-            _ => return Err(PSDDecodeErrors::Generic("Not implemented or Unknown"))
-        };
+        }
+        // interleave channels
+        let pixel_size = sample_size * self.channel_count;
+        let mut result = vec![0; planes.len()];
+
+        for (channel, plane) in planes.chunks_exact(plane_size).enumerate() {
+            let offset = channel * sample_size;
+
+            for (pixel, sample) in result
+                .chunks_exact_mut(pixel_size)
+                .zip(plane.chunks_exact(sample_size))
+            {
+                pixel[offset..offset + sample_size].copy_from_slice(sample);
+            }
+        }
+
         // remove white matte from psd
-        if self.channel_count >= 4 {
+        if self.color_type == Some(ColorModes::RGB) && self.channel_count == 4 {
             match self.depth {
                 BitDepth::Sixteen => {
                     for pixel in result.chunks_exact_mut(8) {
@@ -330,9 +290,9 @@ where
                             let y = (f32::from(px1) * ra + inv_a) as u16;
                             let z = (f32::from(px2) * ra + inv_a) as u16;
 
-                            pixel[0..2].copy_from_slice(&x.to_ne_bytes());
-                            pixel[2..4].copy_from_slice(&y.to_ne_bytes());
-                            pixel[4..6].copy_from_slice(&z.to_ne_bytes());
+                            pixel[0..2].copy_from_slice(&x.to_be_bytes());
+                            pixel[2..4].copy_from_slice(&y.to_be_bytes());
+                            pixel[4..6].copy_from_slice(&z.to_be_bytes());
                         }
                     }
                 }
@@ -381,61 +341,47 @@ where
         Err(PSDDecodeErrors::Generic("Not implemented"))
     }
 
-    fn psd_decode_rle(
-        &mut self, pixel_count: usize, buffer: &mut [u8]
-    ) -> Result<(), PSDDecodeErrors> {
-        let mut count = 0;
-        let mut nleft = pixel_count - count;
-
+    /// Decode PackBits compressed data until `buffer` is full
+    ///
+    /// Loop until you get the number of unpacked bytes you are expecting:
+    ///     Read the next source byte into n.
+    ///     If n is between 0 and 127 inclusive, copy the next n+1 bytes
+    ///     literally. Else if n is between -127 and -1 inclusive, copy the next
+    ///     byte -n+1 times. Else if n is 128, noop.
+    /// Endloop
+    fn psd_decode_rle(&mut self, buffer: &mut [u8]) -> Result<(), PSDDecodeErrors> {
         let mut position = 0;
 
-        while nleft > 0 {
-            let mut len = usize::from(self.stream.get_u8());
+        while position < buffer.len() {
+            let len = usize::from(self.stream.get_u8_err()?);
 
             match len.cmp(&128) {
                 Ordering::Less => {
                     // copy next len+1 bytes literally
-                    len += 1;
-                    if len > nleft {
-                        return Err(PSDDecodeErrors::BadRLE);
-                    }
-                    count += len;
+                    let len = len + 1;
 
-                    if position + (self.channel_count * len) > buffer.len() {
-                        return Err(PSDDecodeErrors::BadRLE);
-                    }
+                    let output = buffer
+                        .get_mut(position..position + len)
+                        .ok_or(PSDDecodeErrors::BadRLE)?;
+                    self.stream.read_exact(output)?;
 
-                    while len > 0 {
-                        buffer[position] = self.stream.get_u8();
-                        position += self.channel_count;
-                        len -= 1;
-                    }
+                    position += len;
                 }
                 Ordering::Equal => (),
                 Ordering::Greater => {
                     // Next -len+1 bytes in the dest are replicated from next source byte.
                     // (Interpret len as a negative 8-bit int.)
-                    len = 257_usize.wrapping_sub(len) & 255;
-
-                    if len > nleft {
-                        return Err(PSDDecodeErrors::BadRLE);
-                    }
-                    count += len;
-                    let val = self.stream.get_u8();
+                    let len = 257 - len;
+                    let value = self.stream.get_u8_err()?;
 
-                    if position + (self.channel_count * len) > buffer.len() {
-                        return Err(PSDDecodeErrors::BadRLE);
-                    }
+                    buffer
+                        .get_mut(position..position + len)
+                        .ok_or(PSDDecodeErrors::BadRLE)?
+                        .fill(value);
 
-                    while len > 0 {
-                        buffer[position] = val;
-                        position += self.channel_count;
-                        len -= 1;
-                    }
+                    position += len;
                 }
             }
-
-            nleft = pixel_count - count;
         }
         Ok(())
     }
@@ -0,0 +1,78 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+use std::fs::read;
+use std::path::Path;
+
+use zune_core::bit_depth::BitDepth;
+use zune_core::colorspace::ColorSpace;
+use zune_core::result::DecodingResult;
+use zune_psd::PSDDecoder;
+
+fn open(name: &str) -> Vec<u8> {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("../../test-images/psd")
+        .join(name);
+    read(path).unwrap()
+}
+
+#[test]
+fn test_rgb_uncompressed() {
+    // 2x1 image, red plane, green plane, blue plane
+    let psd = open("rgb_raw_2x1.psd");
+
+    let mut decoder = PSDDecoder::new(&psd);
+    let pixels = decoder.decode_raw().unwrap();
+
+    assert_eq!(pixels, [1, 3, 5, 2, 4, 6]);
+    assert_eq!(decoder.get_colorspace(), Some(ColorSpace::RGB));
+}
+
+#[test]
+fn test_rgb_rle_with_spot_channel() {
+    // 3x1 image with red, green, blue, alpha and a spot color channel,
+    // red is a run, green a literal and blue a no-op followed by a run
+    let psd = open("rgba_rle_spot_channel.psd");
+
+    let mut decoder = PSDDecoder::new(&psd);
+    let pixels = decoder.decode_raw().unwrap();
+
+    assert_eq!(pixels, [10, 1, 7, 255, 10, 2, 7, 255, 10, 3, 7, 255]);
+    assert_eq!(decoder.get_colorspace(), Some(ColorSpace::RGBA));
+}
+
+#[test]
+fn test_gray_16_bit_rle() {
+    // 2x1 16 bit gray image with an extra channel, runs work on bytes
+    let psd = open("grayscale_16bits_rle.psd");
+
+    let mut decoder = PSDDecoder::new(&psd);
+
+    match decoder.decode().unwrap() {
+        DecodingResult::U16(pixels) => assert_eq!(pixels, [0x1234, 0xABCD]),
+        _ => panic!("expected 16 bit pixels")
+    }
+    assert_eq!(decoder.get_bit_depth(), Some(BitDepth::Sixteen));
+    assert_eq!(decoder.get_colorspace(), Some(ColorSpace::Luma));
+}
+
+#[test]
+fn test_bad_rle() {
+    // run longer than the image
+    let psd = open("bad_rle.psd");
+
+    assert!(PSDDecoder::new(&psd).decode_raw().is_err());
+}
+
+#[test]
+fn test_too_few_channels() {
+    // rgb image with two channels
+    let psd = open("too_few_channels.psd");
+
+    assert!(PSDDecoder::new(&psd).decode_headers().is_err());
+}
@@ -0,0 +1,296 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use core::str;
+
+use zune_core::checksum::crc32::crc32;
+use zune_inflate::{DeflateDecoder, DeflateOptions};
+
+use crate::errors::ZipErrors;
+
+const LOCAL_HEADER_SIGNATURE: u32 = 0x0403_4b50;
+const CENTRAL_HEADER_SIGNATURE: u32 = 0x0201_4b50;
+const END_OF_CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x0605_4b50;
+
+const LOCAL_HEADER_SIZE: usize = 30;
+const CENTRAL_HEADER_SIZE: usize = 46;
+const END_OF_CENTRAL_DIRECTORY_SIZE: usize = 22;
+/// The end of central directory record may be followed by a comment
+/// of at most this many bytes
+const MAX_COMMENT_SIZE: usize = u16::MAX as usize;
+
+/// Compression method of an entry
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum CompressionMethod {
+    /// Entry is stored without compression
+    Stored,
+    /// Entry is compressed with deflate
+    Deflate,
+    /// Any other method, reading such an entry returns an error
+    Unsupported(u16)
+}
+
+impl CompressionMethod {
+    fn from_u16(method: u16) -> CompressionMethod {
+        match method {
+            0 => CompressionMethod::Stored,
+            8 => CompressionMethod::Deflate,
+            m => CompressionMethod::Unsupported(m)
+        }
+    }
+}
+
+/// A single entry of a ZIP archive, as recorded in the central directory
+#[derive(Clone, Debug)]
+pub struct ZipEntry<'a> {
+    name:              &'a [u8],
+    method:            CompressionMethod,
+    flags:             u16,
+    crc:               u32,
+    compressed_size:   usize,
+    uncompressed_size: usize,
+    local_offset:      usize
+}
+
+impl<'a> ZipEntry<'a> {
+    /// Return the name of the entry, or `None` if it is not valid UTF-8
+    ///
+    /// Names use `/` as the path separator
+    pub fn name(&self) -> Option<&'a str> {
+        str::from_utf8(self.name).ok()
+    }
+    /// Return the raw bytes of the entry name
+    pub const fn name_bytes(&self) -> &'a [u8] {
+        self.name
+    }
+    /// Return true if this entry is a directory
+    pub fn is_dir(&self) -> bool {
+        self.name.last() == Some(&b'/')
+    }
+    /// Return true if this entry is encrypted
+    pub const fn is_encrypted(&self) -> bool {
+        (self.flags & 1) != 0
+    }
+    /// Return the compression method of the entry
+    pub const fn compression_method(&self) -> CompressionMethod {
+        self.method
+    }
+    /// Return the CRC-32 of the uncompressed entry
+    pub const fn crc32(&self) -> u32 {
+        self.crc
+    }
+    /// Return the size of the entry as stored in the archive
+    pub const fn compressed_size(&self) -> usize {
+        self.compressed_size
+    }
+    /// Return the size of the entry once decompressed
+    pub const fn uncompressed_size(&self) -> usize {
+        self.uncompressed_size
+    }
+}
+
+/// A ZIP archive read from memory
+///
+/// The central directory is parsed on construction,
+/// entries are only decompressed when read.
+pub struct ZipArchive<'a> {
+    data:    &'a [u8],
+    entries: Vec<ZipEntry<'a>>
+}
+
+impl<'a> ZipArchive<'a> {
+    /// Parse the central directory of an archive
+    ///
+    /// # Errors
+    /// If the data is not a ZIP archive, is corrupt or uses
+    /// ZIP64 or multiple disks
+    pub fn new(data: &'a [u8]) -> Result<ZipArchive<'a>, ZipErrors> {
+        let eocd = find_end_of_central_directory(data)?;
+
+        let disk = read_u16(data, eocd + 4)?;
+        let cd_disk = read_u16(data, eocd + 6)?;
+        let disk_entries = read_u16(data, eocd + 8)?;
+        let num_entries = read_u16(data, eocd + 10)?;
+        let cd_size = read_u32(data, eocd + 12)?;
+        let cd_offset = read_u32(data, eocd + 16)?;
+
+        if num_entries == u16::MAX || cd_size == u32::MAX || cd_offset == u32::MAX {
+            return Err(ZipErrors::Unsupported("ZIP64 archives"));
+        }
+        if disk != 0 || cd_disk != 0 || disk_entries != num_entries {
+            return Err(ZipErrors::Unsupported("multi-disk archives"));
+        }
+        let cd_start = cd_offset as usize;
+        let cd_end = cd_start
+            .checked_add(cd_size as usize)
+            .filter(|end| *end <= eocd)
+            .ok_or(ZipErrors::CorruptArchive("central directory out of bounds"))?;
+
+        let mut entries = Vec::with_capacity(usize::from(num_entries));
+        let mut position = cd_start;
+
+        for _ in 0..num_entries {
+            if position + CENTRAL_HEADER_SIZE > cd_end
+                || read_u32(data, position)? != CENTRAL_HEADER_SIGNATURE
+            {
+                return Err(ZipErrors::CorruptArchive(
+                    "invalid central directory header"
+                ));
+            }
+            let flags = read_u16(data, position + 8)?;
+            let method = read_u16(data, position + 10)?;
+            let crc = read_u32(data, position + 16)?;
+            let compressed_size = read_u32(data, position + 20)?;
+            let uncompressed_size = read_u32(data, position + 24)?;
+            let name_len = usize::from(read_u16(data, position + 28)?);
+            let extra_len = usize::from(read_u16(data, position + 30)?);
+            let comment_len = usize::from(read_u16(data, position + 32)?);
+            let local_offset = read_u32(data, position + 42)?;
+
+            if compressed_size == u32::MAX
+                || uncompressed_size == u32::MAX
+                || local_offset == u32::MAX
+            {
+                return Err(ZipErrors::Unsupported("ZIP64 entries"));
+            }
+            let name_start = position + CENTRAL_HEADER_SIZE;
+            let name = data
+                .get(name_start..name_start + name_len)
+                .ok_or(ZipErrors::CorruptArchive("entry name out of bounds"))?;
+
+            entries.push(ZipEntry {
+                name,
+                method: CompressionMethod::from_u16(method),
+                flags,
+                crc,
+                compressed_size: compressed_size as usize,
+                uncompressed_size: uncompressed_size as usize,
+                local_offset: local_offset as usize
+            });
+            position = name_start + name_len + extra_len + comment_len;
+        }
+
+        Ok(ZipArchive { data, entries })
+    }
+
+    /// Return all entries of the archive, in central directory order
+    pub fn entries(&self) -> &[ZipEntry<'a>] {
+        &self.entries
+    }
+
+    /// Return the first entry with this name, if any
+    pub fn find(&self, name: &str) -> Option<&ZipEntry<'a>> {
+        self.entries.iter().find(|e| e.name == name.as_bytes())
+    }
+
+    /// Decompress an entry of this archive
+    ///
+    /// The size and CRC-32 of the result are checked against
+    /// the ones recorded in the central directory.
+    ///
+    /// # Errors
+    /// If the entry is encrypted, uses an unsupported compression method, is corrupt
+    /// or does not match its recorded size and CRC
+    pub fn read(&self, entry: &ZipEntry) -> Result<Vec<u8>, ZipErrors> {
+        if entry.is_encrypted() {
+            return Err(ZipErrors::Encrypted);
+        }
+        let data = self.raw_data(entry)?;
+
+        let output = match entry.method {
+            CompressionMethod::Stored => data.to_vec(),
+            CompressionMethod::Deflate => {
+                let options = DeflateOptions::default()
+                    .set_limit(entry.uncompressed_size)
+                    .set_size_hint(entry.uncompressed_size);
+
+                DeflateDecoder::new_with_options(data, options)
+                    .decode_deflate_raw()
+                    .map_err(|e| ZipErrors::Inflate(e.error))?
+            }
+            CompressionMethod::Unsupported(method) => {
+                return Err(ZipErrors::UnsupportedCompression(method))
+            }
+        };
+        if output.len() != entry.uncompressed_size {
+            return Err(ZipErrors::SizeMismatch(
+                entry.uncompressed_size,
+                output.len()
+            ));
+        }
+        let crc = crc32(&output);
+
+        if crc != entry.crc {
+            return Err(ZipErrors::CrcMismatch(entry.crc, crc));
+        }
+        Ok(output)
+    }
+
+    /// Decompress the first entry with this name
+    ///
+    /// # Errors
+    /// See [`read`](Self::read), additionally errors if no entry has this name
+    pub fn read_by_name(&self, name: &str) -> Result<Vec<u8>, ZipErrors> {
+        let entry = self
+            .find(name)
+            .ok_or_else(|| ZipErrors::EntryNotFound(name.to_string()))?;
+
+        self.read(entry)
+    }
+
+    /// Return the compressed bytes of an entry
+    ///
+    /// Sizes are taken from the central directory since the local header
+    /// may defer them to a data descriptor after the entry.
+    fn raw_data(&self, entry: &ZipEntry) -> Result<&'a [u8], ZipErrors> {
+        let offset = entry.local_offset;
+
+        if read_u32(self.data, offset)? != LOCAL_HEADER_SIGNATURE {
+            return Err(ZipErrors::CorruptArchive("invalid local header"));
+        }
+        let name_len = usize::from(read_u16(self.data, offset + 26)?);
+        let extra_len = usize::from(read_u16(self.data, offset + 28)?);
+        let start = offset + LOCAL_HEADER_SIZE + name_len + extra_len;
+
+        start
+            .checked_add(entry.compressed_size)
+            .and_then(|end| self.data.get(start..end))
+            .ok_or(ZipErrors::CorruptArchive("entry data out of bounds"))
+    }
+}
+
+/// Find the offset of the end of central directory record
+///
+/// It is the last structure in the archive but may be followed by a comment,
+/// so scan backwards for its signature
+fn find_end_of_central_directory(data: &[u8]) -> Result<usize, ZipErrors> {
+    if data.len() < END_OF_CENTRAL_DIRECTORY_SIZE {
+        return Err(ZipErrors::NoEndOfCentralDirectory);
+    }
+    let last = data.len() - END_OF_CENTRAL_DIRECTORY_SIZE;
+    let first = last.saturating_sub(MAX_COMMENT_SIZE);
+
+    (first..=last)
+        .rev()
+        .find(|pos| read_u32(data, *pos).ok() == Some(END_OF_CENTRAL_DIRECTORY_SIGNATURE))
+        .ok_or(ZipErrors::NoEndOfCentralDirectory)
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Result<u16, ZipErrors> {
+    data.get(offset..offset + 2)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]))
+        .ok_or(ZipErrors::CorruptArchive("unexpected end of data"))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32, ZipErrors> {
+    data.get(offset..offset + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or(ZipErrors::CorruptArchive("unexpected end of data"))
+}
@@ -0,0 +1,92 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! Errors possible when reading archives
+use alloc::string::String;
+use core::fmt::{Debug, Display, Formatter};
+
+use zune_inflate::errors::DecodeErrorStatus;
+
+/// Possible errors that may occur when reading an archive
+pub enum ZipErrors {
+    /// The end of central directory record was not found
+    ///
+    /// Indicates that the data is not a ZIP archive or is truncated
+    NoEndOfCentralDirectory,
+    /// A structure of the archive is invalid or extends past the end of the data
+    CorruptArchive(&'static str),
+    /// The archive uses a feature this reader does not support, e.g ZIP64
+    Unsupported(&'static str),
+    /// The entry is encrypted
+    Encrypted,
+    /// The entry is compressed with a method other than stored (0) or deflate (8)
+    UnsupportedCompression(u16),
+    /// The deflate stream of the entry could not be decoded
+    Inflate(DecodeErrorStatus),
+    /// The decompressed entry does not have the size recorded in the archive
+    ///
+    /// # Arguments
+    /// - 1st argument is the recorded size
+    /// - 2nd argument is the size found
+    SizeMismatch(usize, usize),
+    /// The decompressed entry does not match the CRC-32 recorded in the archive
+    ///
+    /// # Arguments
+    /// - 1st argument is the recorded CRC
+    /// - 2nd argument is the CRC found
+    CrcMismatch(u32, u32),
+    /// No entry has this name
+    EntryNotFound(String)
+}
+
+impl Debug for ZipErrors {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ZipErrors::NoEndOfCentralDirectory => {
+                writeln!(
+                    f,
+                    "No end of central directory record found, not a ZIP archive"
+                )
+            }
+            ZipErrors::CorruptArchive(reason) => writeln!(f, "Corrupt archive, {reason}"),
+            ZipErrors::Unsupported(feature) => {
+                writeln!(f, "Unsupported archive feature: {feature}")
+            }
+            ZipErrors::Encrypted => writeln!(f, "Encrypted entries are not supported"),
+            ZipErrors::UnsupportedCompression(method) => {
+                writeln!(
+                    f,
+                    "Unsupported compression method {method}, only stored (0) and deflate (8) are supported"
+                )
+            }
+            ZipErrors::Inflate(err) => writeln!(f, "Could not inflate entry: {err:?}"),
+            ZipErrors::SizeMismatch(expected, found) => {
+                writeln!(
+                    f,
+                    "Entry size mismatch, expected {expected} but found {found}"
+                )
+            }
+            ZipErrors::CrcMismatch(expected, found) => {
+                writeln!(
+                    f,
+                    "Entry CRC mismatch, expected {expected:#010x} but found {found:#010x}"
+                )
+            }
+            ZipErrors::EntryNotFound(name) => writeln!(f, "No entry named {name:?} in the archive")
+        }
+    }
+}
+
+impl Display for ZipErrors {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        writeln!(f, "{self:?}")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ZipErrors {}
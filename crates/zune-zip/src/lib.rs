@@ -0,0 +1,41 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software; You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+//! A minimal ZIP archive reader
+//!
+//! This reads entries of an in-memory ZIP archive, it exists so that
+//! images stored in ZIP and CBZ archives can be decoded without unpacking them first.
+//!
+//! [Format Specification](https://pkware.cachefly.net/webdocs/casestudies/APPNOTE.TXT)
+//!
+//! # Features
+//! - Stored and deflate compressed entries, deflate is decoded by `zune-inflate`
+//! - CRC-32 and size checks for every entry read
+//! - `no_std`
+//!
+//! Encryption, ZIP64, multi-disk archives and other compression methods are not supported,
+//! they are reported as errors instead.
+//!
+//! # Example
+//! ```no_run
+//! use zune_zip::ZipArchive;
+//!
+//! let data = std::fs::read("comic.cbz").unwrap();
+//! let archive = ZipArchive::new(&data).unwrap();
+//!
+//! let page = archive.read_by_name("page-01.png").unwrap();
+//! ```
+//!
+//! ## `no_std`
+//! You can use `no_std` with alloc feature to compile for `no_std` endpoints
+
+#![cfg_attr(not(feature = "std"), no_std)]
+extern crate alloc;
+
+pub use archive::*;
+pub use errors::*;
+
+mod archive;
+mod errors;
@@ -0,0 +1,70 @@
+"""
+Generate the test archives in this directory.
+
+Archives are written by python's zipfile module, expected contents are
+recomputed by the tests from the same formulas used here.
+
+Run from this directory with `python3 generate.py`
+"""
+import io
+import zipfile
+
+
+def pattern(length):
+    return bytes((i * 7 + i // 251) & 0xFF for i in range(length))
+
+
+def ppm(width, height):
+    pixels = bytes((x * 31 + y * 17 + c * 83) & 0xFF
+                   for y in range(height) for x in range(width) for c in range(3))
+    return b"P6\n%d %d\n255\n" % (width, height) + pixels
+
+
+class Unseekable(io.RawIOBase):
+    """Forces zipfile to write data descriptors after every entry"""
+
+    def __init__(self):
+        self.buffer = bytearray()
+
+    def writable(self):
+        return True
+
+    def write(self, b):
+        self.buffer += b
+        return len(b)
+
+
+def write_basic():
+    with zipfile.ZipFile("basic.zip", "w") as z:
+        z.writestr("stored.bin", pattern(1000), compress_type=zipfile.ZIP_STORED)
+        z.writestr("deflated.bin", pattern(100000), compress_type=zipfile.ZIP_DEFLATED)
+        z.writestr("empty.bin", b"", compress_type=zipfile.ZIP_DEFLATED)
+        z.writestr("pages/", b"")
+        z.writestr("pages/page-01.ppm", ppm(16, 9), compress_type=zipfile.ZIP_DEFLATED)
+        z.comment = b"archive comment"
+
+
+def write_descriptors():
+    stream = Unseekable()
+    with zipfile.ZipFile(stream, "w") as z:
+        info = zipfile.ZipInfo("streamed.bin")
+        info.compress_type = zipfile.ZIP_DEFLATED
+        with z.open(info, "w") as f:
+            f.write(pattern(50000))
+    with open("data_descriptor.zip", "wb") as f:
+        f.write(stream.buffer)
+
+
+def write_bad_crc():
+    with zipfile.ZipFile("bad_crc.zip", "w") as z:
+        z.writestr("deflated.bin", pattern(5000), compress_type=zipfile.ZIP_DEFLATED)
+    data = bytearray(open("bad_crc.zip", "rb").read())
+    # flip a bit of the crc in the central directory header
+    central = data.rfind(b"PK\x01\x02")
+    data[central + 16] ^= 1
+    open("bad_crc.zip", "wb").write(data)
+
+
+write_basic()
+write_descriptors()
+write_bad_crc()
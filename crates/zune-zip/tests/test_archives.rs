@@ -0,0 +1,112 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+use std::fs::read;
+
+use zune_zip::{CompressionMethod, ZipArchive, ZipErrors};
+
+fn open_archive(name: &str) -> Vec<u8> {
+    let path = env!("CARGO_MANIFEST_DIR").to_string() + "/tests/archives/" + name;
+    read(path).unwrap()
+}
+
+/// Same as `pattern` in tests/archives/generate.py
+fn pattern(length: usize) -> Vec<u8> {
+    (0..length)
+        .map(|i| ((i * 7 + i / 251) & 0xFF) as u8)
+        .collect()
+}
+
+#[test]
+fn test_list_entries() {
+    let data = open_archive("basic.zip");
+    let archive = ZipArchive::new(&data).unwrap();
+
+    let names: Vec<_> = archive
+        .entries()
+        .iter()
+        .map(|e| e.name().unwrap())
+        .collect();
+    assert_eq!(
+        names,
+        [
+            "stored.bin",
+            "deflated.bin",
+            "empty.bin",
+            "pages/",
+            "pages/page-01.ppm"
+        ]
+    );
+    let stored = archive.find("stored.bin").unwrap();
+    assert_eq!(stored.compression_method(), CompressionMethod::Stored);
+    assert_eq!(stored.uncompressed_size(), 1000);
+
+    let deflated = archive.find("deflated.bin").unwrap();
+    assert_eq!(deflated.compression_method(), CompressionMethod::Deflate);
+    assert!(deflated.compressed_size() < deflated.uncompressed_size());
+
+    assert!(archive.find("pages/").unwrap().is_dir());
+    assert!(archive.find("missing.bin").is_none());
+}
+
+#[test]
+fn test_read_entries() {
+    let data = open_archive("basic.zip");
+    let archive = ZipArchive::new(&data).unwrap();
+
+    assert_eq!(archive.read_by_name("stored.bin").unwrap(), pattern(1000));
+    assert_eq!(
+        archive.read_by_name("deflated.bin").unwrap(),
+        pattern(100_000)
+    );
+    assert!(archive.read_by_name("empty.bin").unwrap().is_empty());
+
+    let page = archive.read_by_name("pages/page-01.ppm").unwrap();
+    assert!(page.starts_with(b"P6\n16 9\n255\n"));
+    assert_eq!(page.len(), 12 + 16 * 9 * 3);
+}
+
+#[test]
+fn test_data_descriptor() {
+    // sizes are only in the central directory and after the entry data
+    let data = open_archive("data_descriptor.zip");
+    let archive = ZipArchive::new(&data).unwrap();
+
+    assert_eq!(
+        archive.read_by_name("streamed.bin").unwrap(),
+        pattern(50000)
+    );
+}
+
+#[test]
+fn test_errors() {
+    let data = open_archive("bad_crc.zip");
+    let archive = ZipArchive::new(&data).unwrap();
+    assert!(matches!(
+        archive.read_by_name("deflated.bin"),
+        Err(ZipErrors::CrcMismatch(..))
+    ));
+    assert!(matches!(
+        archive.read_by_name("missing.bin"),
+        Err(ZipErrors::EntryNotFound(_))
+    ));
+
+    assert!(matches!(
+        ZipArchive::new(b"not a zip archive at all, just some text"),
+        Err(ZipErrors::NoEndOfCentralDirectory)
+    ));
+    // cut the archive inside the central directory
+    let data = open_archive("basic.zip");
+    let eocd = data.len() - 22 - b"archive comment".len();
+    let mut truncated = data[..eocd - 10].to_vec();
+    truncated.extend_from_slice(&data[eocd..]);
+    assert!(matches!(
+        ZipArchive::new(&truncated),
+        Err(ZipErrors::CorruptArchive(_))
+    ));
+}
@@ -0,0 +1,75 @@
+"""
+Generate the zlib conformance vectors in this directory.
+
+Every input is compressed by the reference zlib (through python's zlib module)
+with a spread of levels, strategies and window sizes. Expected output is not
+stored, tests decode the streams with miniz_oxide and compare byte for byte.
+
+Run from this directory with `python3 generate.py`
+"""
+import gzip
+import zlib
+
+
+def lcg_bytes(length, seed):
+    out = bytearray()
+    state = seed
+    for _ in range(length):
+        state = (state * 1103515245 + 12345) & 0x7FFFFFFF
+        out.append(state >> 16 & 0xFF)
+    return bytes(out)
+
+
+def text():
+    words = b"the quick brown fox jumps over a lazy dog while zlib watches".split()
+    rng = lcg_bytes(40000, 7)
+    return b" ".join(words[x % len(words)] for x in rng)
+
+
+INPUTS = {
+    "empty": b"",
+    "single": b"a",
+    "text": text(),
+    "random": lcg_bytes(16384, 1),
+    # long runs produce distance one matches of the maximum length
+    "runs": b"\x00" * 70000 + b"ab" * 300 + b"\xff" * 1000,
+    # repeats further apart than small windows allow
+    "far": lcg_bytes(4096, 3) * 12,
+}
+
+STRATEGIES = {
+    "default": zlib.Z_DEFAULT_STRATEGY,
+    "filtered": zlib.Z_FILTERED,
+    "huffman": zlib.Z_HUFFMAN_ONLY,
+    "rle": zlib.Z_RLE,
+    "fixed": zlib.Z_FIXED,
+}
+
+SETTINGS = [
+    # (level, strategy, window bits)
+    (0, "default", 15),
+    (1, "default", 15),
+    (9, "default", 15),
+    (6, "filtered", 15),
+    (6, "huffman", 15),
+    (6, "rle", 15),
+    (6, "fixed", 15),
+    (9, "default", 9),
+]
+
+
+def compress(data, level, strategy, wbits):
+    c = zlib.compressobj(level, zlib.DEFLATED, wbits, 9, STRATEGIES[strategy])
+    return c.compress(data) + c.flush()
+
+
+for name, data in INPUTS.items():
+    for level, strategy, wbits in SETTINGS:
+        stream = compress(data, level, strategy, wbits)
+        with open(f"{name}-l{level}-{strategy}-w{wbits}.zlib", "wb") as f:
+            f.write(stream)
+    # raw deflate, no header or checksum
+    with open(f"{name}-l6.deflate", "wb") as f:
+        f.write(compress(data, 6, "default", -15))
+    with open(f"{name}.gz", "wb") as f:
+        f.write(gzip.compress(data, 6, mtime=0))
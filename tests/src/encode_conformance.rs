@@ -0,0 +1,341 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! Conformance tests for the zune encoders
+//!
+//! Synthetic images are encoded with every zune encoder, for every colorspace and depth
+//! it supports, and decoded with an independent implementation
+//! - png: the `png` crate
+//! - qoi: the `qoi` crate
+//! - ppm (P5 and P6, the `image` crate can't decode PAM alpha), farbfeld and hdr: the `image`
+//!   crate
+//! - jpeg xl: the `jxl-oxide` crate
+//! - zlib (zune-inflate): miniz_oxide
+//!
+//! Lossless formats must decode to the source pixels, hdr is lossy and must decode to the
+//! same floats as zune-hdr, and within the precision of its shared exponent from the source.
+//!
+//! Run with `--features conformance`.
+use image::{ColorType, DynamicImage, ImageFormat};
+use zune_core::bit_depth::BitDepth;
+use zune_core::colorspace::ColorSpace;
+use zune_core::options::EncoderOptions;
+use zune_farbfeld::FarbFeldEncoder;
+use zune_hdr::{HdrDecoder, HdrEncoder};
+use zune_inflate::{DeflateEncodeOptions, DeflateEncoder, DeflateEncodingStrategy};
+use zune_jpegxl::JxlSimpleEncoder;
+use zune_png::PngEncoder;
+use zune_ppm::PPMEncoder;
+use zune_qoi::QoiEncoder;
+
+/// Odd sizes so rows don't line up with any block or byte boundary
+const SIZES: [(usize, usize); 4] = [(1, 1), (7, 3), (33, 17), (64, 64)];
+
+/// Generate `len` samples of at most `max`
+///
+/// Smooth gradients interrupted by noise and flat runs, so encoders take
+/// their run, match and literal paths.
+fn samples(len: usize, max: u32, seed: u32) -> Vec<u32> {
+    let mut state = seed.wrapping_mul(0x9E37_79B9) | 1;
+
+    (0..len)
+        .map(|i| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+
+            let value = match (i / 97) % 3 {
+                0 => (i as u32).wrapping_mul(3),
+                1 => state,
+                _ => seed
+            };
+            value % (max + 1)
+        })
+        .collect()
+}
+
+fn samples_u8(width: usize, height: usize, colorspace: ColorSpace) -> Vec<u8> {
+    let len = width * height * colorspace.num_components();
+
+    samples(len, 255, (width * height) as u32)
+        .into_iter()
+        .map(|x| x as u8)
+        .collect()
+}
+
+fn samples_u16(width: usize, height: usize, colorspace: ColorSpace) -> Vec<u16> {
+    let len = width * height * colorspace.num_components();
+
+    samples(len, 65535, (width * height) as u32)
+        .into_iter()
+        .map(|x| x as u16)
+        .collect()
+}
+
+fn ne_bytes(samples: &[u16]) -> Vec<u8> {
+    samples.iter().flat_map(|x| x.to_ne_bytes()).collect()
+}
+
+fn be_bytes(samples: &[u16]) -> Vec<u8> {
+    samples.iter().flat_map(|x| x.to_be_bytes()).collect()
+}
+
+/// Decode `data` with the `png` crate, returning the color type, depth and raw samples
+fn png_decode(data: &[u8]) -> (png::ColorType, png::BitDepth, Vec<u8>) {
+    let mut reader = png::Decoder::new(data).read_info().unwrap();
+    let mut pixels = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut pixels).unwrap();
+
+    pixels.truncate(info.buffer_size());
+
+    (info.color_type, info.bit_depth, pixels)
+}
+
+fn image_decode(data: &[u8], format: ImageFormat) -> DynamicImage {
+    image::load_from_memory_with_format(data, format).unwrap()
+}
+
+#[test]
+fn test_png_conformance() {
+    let colorspaces = [
+        (ColorSpace::Luma, png::ColorType::Grayscale),
+        (ColorSpace::LumaA, png::ColorType::GrayscaleAlpha),
+        (ColorSpace::RGB, png::ColorType::Rgb),
+        (ColorSpace::RGBA, png::ColorType::Rgba)
+    ];
+
+    for (width, height) in SIZES {
+        for (colorspace, color_type) in colorspaces {
+            let pixels = samples_u8(width, height, colorspace);
+            let options = EncoderOptions::new(width, height, colorspace, BitDepth::Eight);
+            let encoded = PngEncoder::new(&pixels, options).encode();
+
+            assert_eq!(
+                png_decode(&encoded),
+                (color_type, png::BitDepth::Eight, pixels),
+                "{colorspace:?} {width}x{height} 8 bit"
+            );
+
+            // 16 bit png input is big endian, as is the png crate's output
+            let pixels = be_bytes(&samples_u16(width, height, colorspace));
+            let options = EncoderOptions::new(width, height, colorspace, BitDepth::Sixteen);
+            let encoded = PngEncoder::new(&pixels, options).encode();
+
+            assert_eq!(
+                png_decode(&encoded),
+                (color_type, png::BitDepth::Sixteen, pixels),
+                "{colorspace:?} {width}x{height} 16 bit"
+            );
+        }
+    }
+}
+
+#[test]
+fn test_qoi_conformance() {
+    let colorspaces = [
+        (ColorSpace::RGB, qoi::Channels::Rgb),
+        (ColorSpace::RGBA, qoi::Channels::Rgba)
+    ];
+
+    for (width, height) in SIZES {
+        for (colorspace, channels) in colorspaces {
+            let pixels = samples_u8(width, height, colorspace);
+            let options = EncoderOptions::new(width, height, colorspace, BitDepth::Eight);
+            let encoded = QoiEncoder::new(&pixels, options).encode().unwrap();
+
+            let (header, decoded) = qoi::decode_to_vec(&encoded).unwrap();
+
+            assert_eq!(header.channels, channels, "{colorspace:?} {width}x{height}");
+            assert_eq!(
+                (header.width as usize, header.height as usize),
+                (width, height)
+            );
+            assert_eq!(decoded, pixels, "{colorspace:?} {width}x{height}");
+        }
+    }
+}
+
+#[test]
+fn test_ppm_conformance() {
+    let colorspaces = [
+        (ColorSpace::Luma, ColorType::L8, ColorType::L16),
+        (ColorSpace::RGB, ColorType::Rgb8, ColorType::Rgb16)
+    ];
+
+    for (width, height) in SIZES {
+        for (colorspace, color_8, color_16) in colorspaces {
+            let pixels = samples_u8(width, height, colorspace);
+            let options = EncoderOptions::new(width, height, colorspace, BitDepth::Eight);
+            let encoded = PPMEncoder::new(&pixels, options).encode().unwrap();
+
+            let decoded = image_decode(&encoded, ImageFormat::Pnm);
+
+            assert_eq!(decoded.color(), color_8, "{colorspace:?} {width}x{height}");
+            assert_eq!(
+                decoded.as_bytes(),
+                pixels,
+                "{colorspace:?} {width}x{height}"
+            );
+
+            // 16 bit input is native endian, as are the image crate's samples
+            let pixels = ne_bytes(&samples_u16(width, height, colorspace));
+            let options = EncoderOptions::new(width, height, colorspace, BitDepth::Sixteen);
+            let encoded = PPMEncoder::new(&pixels, options).encode().unwrap();
+
+            let decoded = image_decode(&encoded, ImageFormat::Pnm);
+
+            assert_eq!(decoded.color(), color_16, "{colorspace:?} {width}x{height}");
+            assert_eq!(
+                decoded.as_bytes(),
+                pixels,
+                "{colorspace:?} {width}x{height}"
+            );
+        }
+    }
+}
+
+#[test]
+fn test_farbfeld_conformance() {
+    for (width, height) in SIZES {
+        let samples = samples_u16(width, height, ColorSpace::RGBA);
+        let pixels = ne_bytes(&samples);
+        let options = EncoderOptions::new(width, height, ColorSpace::RGBA, BitDepth::Sixteen);
+        let encoded = FarbFeldEncoder::new(&pixels, options).encode().unwrap();
+
+        let decoded = image_decode(&encoded, ImageFormat::Farbfeld);
+
+        assert_eq!(decoded.color(), ColorType::Rgba16, "{width}x{height}");
+        assert_eq!(
+            decoded.into_rgba16().into_raw(),
+            samples,
+            "{width}x{height}"
+        );
+    }
+}
+
+#[test]
+fn test_hdr_conformance() {
+    for (width, height) in SIZES {
+        // [0, 64) with a fractional part, and some zeros
+        let pixels: Vec<f32> = samples(width * height * 3, 65535, 7)
+            .into_iter()
+            .map(|x| x as f32 / 1024.0)
+            .collect();
+
+        let options = EncoderOptions::new(width, height, ColorSpace::RGB, BitDepth::Float32);
+        let encoded = HdrEncoder::new(&pixels, options).encode().unwrap();
+
+        // the dynamic image of image 0.24 is tone mapped to 8 bits, so read the floats
+        let decoded: Vec<f32> = image::codecs::hdr::HdrDecoder::new(encoded.as_slice())
+            .unwrap()
+            .read_image_hdr()
+            .unwrap()
+            .into_iter()
+            .flat_map(|x| x.0)
+            .collect();
+        let reference = HdrDecoder::new(&encoded).decode().unwrap();
+
+        assert_eq!(decoded, reference, "{width}x{height}");
+
+        for (pixel, expected) in decoded.chunks_exact(3).zip(pixels.chunks_exact(3)) {
+            // every channel of a pixel shares the exponent of the largest one,
+            // and keeps 8 bits of mantissa relative to it
+            let max = expected.iter().fold(0.0_f32, |a, &b| a.max(b));
+            let tolerance = max / 128.0;
+
+            for (a, b) in pixel.iter().zip(expected) {
+                assert!(
+                    (a - b).abs() <= tolerance,
+                    "{width}x{height}: {pixel:?} != {expected:?}"
+                );
+            }
+        }
+    }
+}
+
+/// Decode `data` with `jxl-oxide`, scaling its `[0, 1]` floats back to samples of at most `max`
+fn jxl_decode(data: &[u8], max: u16) -> (usize, usize, usize, Vec<u16>) {
+    let mut image = jxl_oxide::JxlImage::from_reader(data).unwrap();
+    let render = match image.render_next_frame().unwrap() {
+        jxl_oxide::RenderResult::Done(render) => render,
+        _ => panic!("jxl-oxide returned no frame")
+    };
+    let buffer = render.image();
+    let samples = buffer
+        .buf()
+        .iter()
+        .map(|x| (x * f32::from(max)).round() as u16)
+        .collect();
+
+    (buffer.width(), buffer.height(), buffer.channels(), samples)
+}
+
+#[test]
+fn test_jxl_conformance() {
+    let colorspaces = [
+        ColorSpace::Luma,
+        ColorSpace::LumaA,
+        ColorSpace::RGB,
+        ColorSpace::RGBA
+    ];
+
+    // the encoder rejects images narrower than 2 pixels
+    for (width, height) in SIZES.into_iter().filter(|&(width, _)| width > 1) {
+        for colorspace in colorspaces {
+            let channels = colorspace.num_components();
+
+            let pixels = samples_u8(width, height, colorspace);
+            let options = EncoderOptions::new(width, height, colorspace, BitDepth::Eight);
+            let encoded = JxlSimpleEncoder::new(&pixels, options).encode().unwrap();
+
+            let expected = pixels.iter().map(|&x| u16::from(x)).collect();
+
+            assert_eq!(
+                jxl_decode(&encoded, 255),
+                (width, height, channels, expected),
+                "{colorspace:?} {width}x{height} 8 bit"
+            );
+
+            // 16 bit jxl input is native endian
+            let samples = samples_u16(width, height, colorspace);
+            let options = EncoderOptions::new(width, height, colorspace, BitDepth::Sixteen);
+            let encoded = JxlSimpleEncoder::new(&ne_bytes(&samples), options)
+                .encode()
+                .unwrap();
+
+            assert_eq!(
+                jxl_decode(&encoded, 65535),
+                (width, height, channels, samples),
+                "{colorspace:?} {width}x{height} 16 bit"
+            );
+        }
+    }
+}
+
+#[test]
+fn test_zlib_conformance() {
+    let strategies = [
+        DeflateEncodingStrategy::NoCompression,
+        DeflateEncodingStrategy::Fast,
+        DeflateEncodingStrategy::Default,
+        DeflateEncodingStrategy::Best
+    ];
+
+    for (width, height) in SIZES {
+        let data = samples_u8(width, height, ColorSpace::RGBA);
+
+        for strategy in strategies {
+            let options = DeflateEncodeOptions::new(strategy);
+            let encoded = DeflateEncoder::new_with_options(&data, options).encode_zlib();
+
+            let decoded = miniz_oxide::inflate::decompress_to_vec_zlib(&encoded).unwrap();
+
+            assert_eq!(decoded, data, "{strategy:?} {width}x{height}");
+        }
+    }
+}
@@ -10,7 +10,8 @@ use std::ffi::OsStr;
 use std::fs::read;
 use std::path::{Path, PathBuf};
 
-use zune_inflate::DeflateDecoder;
+use zune_inflate::errors::DecodeErrorStatus;
+use zune_inflate::{DeflateDecoder, DeflateOptions};
 
 use crate::{hash, sample_path, TestEntry};
 
@@ -66,3 +67,68 @@ fn test_inflate() {
         panic!("Errors found during test decoding\n {:#?}", files);
     }
 }
+
+#[test]
+fn test_bytes_read() {
+    let file = read(inflate_path().join("vectors/text-l6.deflate")).unwrap();
+
+    // bytes after the stream are left alone
+    let mut data = file.clone();
+    data.extend_from_slice(b"PK\x03\x04");
+
+    let mut decoder = DeflateDecoder::new(&data);
+    let expected = decoder.decode_deflate_raw().unwrap();
+    assert_eq!(decoder.bytes_read(), file.len());
+
+    // gzip members back to back
+    let member = read(inflate_path().join("vectors/text.gz")).unwrap();
+    let data = [member.as_slice(), member.as_slice()].concat();
+
+    let mut position = 0;
+    while position < data.len() {
+        let mut decoder = DeflateDecoder::new(&data[position..]);
+
+        assert_eq!(decoder.decode_gzip().unwrap(), expected);
+        assert_eq!(decoder.bytes_read(), member.len());
+
+        position += decoder.bytes_read();
+    }
+}
+
+#[test]
+fn test_gzip_multi() {
+    let members: Vec<Vec<u8>> = ["text.gz", "empty.gz", "single.gz", "text.gz"]
+        .iter()
+        .map(|name| read(inflate_path().join("vectors").join(name)).unwrap())
+        .collect();
+    let expected: Vec<u8> = members
+        .iter()
+        .flat_map(|x| DeflateDecoder::new(x).decode_gzip().unwrap())
+        .collect();
+
+    // padding after the last member is left alone
+    let mut data = members.concat();
+    data.extend_from_slice(&[0; 7]);
+
+    let mut decoder = DeflateDecoder::new(&data);
+    assert_eq!(decoder.decode_gzip_multi().unwrap(), expected);
+    assert_eq!(decoder.bytes_read(), data.len() - 7);
+    assert_eq!(decoder.remaining(), &[0; 7]);
+
+    // the limit covers all members
+    let options = DeflateOptions::default().set_limit(expected.len() - 1);
+    let err = DeflateDecoder::new_with_options(&data, options)
+        .decode_gzip_multi()
+        .unwrap_err();
+    assert!(matches!(err.error, DecodeErrorStatus::OutputLimitExceeded(..)));
+
+    // a corrupt member keeps the output of the ones before it
+    let first = DeflateDecoder::new(&members[0]).decode_gzip().unwrap();
+    let mut data = members[..2].concat();
+    let crc = data.len() - 8;
+    data[crc] ^= 1;
+
+    let err = DeflateDecoder::new(&data).decode_gzip_multi().unwrap_err();
+    assert!(matches!(err.error, DecodeErrorStatus::MismatchedCRC(..)));
+    assert_eq!(err.data, first);
+}
@@ -0,0 +1,125 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! Tests for the zune-inflate bit reader
+//!
+//! Both refill strategies are exercised on the host, the 32 bit one is what
+//! wasm32 and armv7 targets use, and compared against a bit at a time reader.
+use zune_inflate::bitstream::BitStreamReader;
+
+/// Read `num_bits` bits starting at bit `position`, least significant bit first,
+/// bits past the end of `data` are zero
+fn reference_bits(data: &[u8], position: usize, num_bits: u8) -> u64 {
+    (0..usize::from(num_bits)).fold(0, |value, i| {
+        let bit = position + i;
+        let byte = data.get(bit / 8).copied().unwrap_or(0);
+
+        value | (u64::from((byte >> (bit % 8)) & 1) << i)
+    })
+}
+
+fn xorshift(state: &mut u32) -> u32 {
+    *state ^= *state << 13;
+    *state ^= *state >> 17;
+    *state ^= *state << 5;
+    *state
+}
+
+/// Read all of `data` with random widths, refilling with `refill`, and
+/// return the state after every read
+fn read_all(
+    data: &[u8], seed: u32, refill: fn(&mut BitStreamReader)
+) -> Vec<(u64, usize, u8, usize)> {
+    let mut stream = BitStreamReader::new(data);
+    let mut state = seed | 1;
+    let mut bit_position = 0;
+    let mut states = Vec::new();
+
+    while bit_position < data.len() * 8 + 64 {
+        refill(&mut stream);
+
+        assert!((56..=63).contains(&stream.get_bits_left()));
+
+        let num_bits = (xorshift(&mut state) % 57) as u8;
+
+        if num_bits <= 31 && xorshift(&mut state) & 1 == 0 {
+            let peeked = stream.peek_var_bits(usize::from(num_bits));
+            assert_eq!(peeked as u64, reference_bits(data, bit_position, num_bits));
+        }
+        let value = stream.get_bits(num_bits);
+
+        assert_eq!(
+            value,
+            reference_bits(data, bit_position, num_bits),
+            "bit {bit_position}, {num_bits} bits"
+        );
+        bit_position += usize::from(num_bits);
+
+        states.push((
+            value,
+            stream.get_position(),
+            stream.get_bits_left(),
+            stream.over_read
+        ));
+    }
+    states
+}
+
+#[test]
+fn test_refill_strategies_agree() {
+    let mut state = 0x1234_5678;
+
+    for len in 0..80 {
+        let data: Vec<u8> = (0..len).map(|_| xorshift(&mut state) as u8).collect();
+
+        for seed in 0..16 {
+            let wide = read_all(&data, seed, |x| x.refill_u64());
+            let narrow = read_all(&data, seed, |x| x.refill_u32());
+
+            assert_eq!(wide, narrow, "length {len}, seed {seed}");
+            // the strategy refill picks for this target agrees as well
+            assert_eq!(wide, read_all(&data, seed, |x| x.refill()));
+        }
+    }
+}
+
+#[test]
+fn test_refill_inner_loop_matches_refill() {
+    let data: Vec<u8> = (0..=255).collect();
+
+    for skip in 0..=56 {
+        let mut fast = BitStreamReader::new(&data);
+        let mut slow = BitStreamReader::new(&data);
+
+        fast.refill();
+        slow.refill();
+        fast.drop_bits(skip);
+        slow.drop_bits(skip);
+
+        fast.refill_inner_loop();
+        slow.refill();
+
+        assert_eq!(fast.get_bits_left(), slow.get_bits_left());
+        assert_eq!(fast.get_position(), slow.get_position());
+        assert_eq!(fast.get_bits(56), slow.get_bits(56));
+    }
+}
+
+#[test]
+fn test_peek_bits_at_32_bit_limit() {
+    let data = [0xFF, 0x00, 0xAA, 0x55, 0x0F];
+    let mut stream = BitStreamReader::new(&data);
+
+    stream.refill();
+
+    // the widest peek allowed on every target
+    assert_eq!(stream.peek_bits::<31>(), 0x55AA_00FF & 0x7FFF_FFFF);
+    // wider values are read with get_bits on all targets
+    assert_eq!(stream.get_bits(40), 0x0F_55AA_00FF);
+    assert_eq!(stream.get_bits_left(), 16);
+}
@@ -0,0 +1,596 @@
+/*
+ * Copyright (c) 2023.
+ *
+ * This software is free software;
+ *
+ * You can redistribute it or modify it under terms of the MIT, Apache License or Zlib license
+ */
+
+//! Conformance tests for zune-inflate
+//!
+//! Every stream is decoded twice, once normally and once in reference mode (which
+//! skips the fast loop), both outputs and the number of input bytes read must
+//! match miniz_oxide.
+//! Streams miniz_oxide rejects must be rejected too.
+//!
+//! The streams come from
+//! - `test-images/inflate/vectors`, made by the reference zlib, see `generate.py` there
+//! - `test-images/inflate/{zlib,gzip}`, real world files
+//! - hand assembled adversarial streams, below
+//! - the directory in the `ZUNE_INFLATE_CORPUS` environment variable if set, every file there
+//!   (e.g the Calgary and Canterbury corpora) is compressed at all levels by miniz_oxide
+//!
+//! Run with `--features paranoid` to also check table indices and match copies.
+use std::ffi::OsStr;
+use std::fs::read;
+use std::path::Path;
+
+use zune_inflate::{DeflateDecoder, DeflateOptions};
+
+use crate::inflate::inflate_path;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Format {
+    Deflate,
+    Zlib,
+    Gzip
+}
+
+impl Format {
+    fn from_path(path: &Path) -> Option<Format> {
+        match path.extension().and_then(OsStr::to_str) {
+            Some("deflate") => Some(Format::Deflate),
+            Some("zlib") => Some(Format::Zlib),
+            Some("gz") => Some(Format::Gzip),
+            _ => None
+        }
+    }
+}
+
+/// Decode `data`, returning the output and the number of bytes read
+fn zune_decode(data: &[u8], format: Format, reference_mode: bool) -> Option<(Vec<u8>, usize)> {
+    let options = DeflateOptions::default().set_reference_mode(reference_mode);
+    let mut decoder = DeflateDecoder::new_with_options(data, options);
+
+    let output = match format {
+        Format::Deflate => decoder.decode_deflate_raw(),
+        Format::Zlib => decoder.decode_zlib(),
+        Format::Gzip => decoder.decode_gzip()
+    }
+    .ok()?;
+
+    Some((output, decoder.bytes_read()))
+}
+
+/// Skip the gzip header, miniz_oxide only decodes raw deflate and zlib
+fn gzip_payload(data: &[u8]) -> &[u8] {
+    let flags = data[3];
+    let mut position = 10;
+
+    if flags & 4 != 0 {
+        // FEXTRA
+        position += 2 + usize::from(u16::from_le_bytes([data[position], data[position + 1]]));
+    }
+    for flag in [8, 16] {
+        // FNAME and FCOMMENT, zero terminated
+        if flags & flag != 0 {
+            position += data[position..].iter().position(|x| *x == 0).unwrap() + 1;
+        }
+    }
+    if flags & 2 != 0 {
+        // FHCRC
+        position += 2;
+    }
+    &data[position..]
+}
+
+/// Decode `data` with miniz_oxide, returning the output and the number of bytes read
+fn miniz_decode(data: &[u8], format: Format) -> Option<(Vec<u8>, usize)> {
+    use miniz_oxide::inflate::core::inflate_flags::{
+        TINFL_FLAG_PARSE_ZLIB_HEADER, TINFL_FLAG_USING_NON_WRAPPING_OUTPUT_BUF
+    };
+    use miniz_oxide::inflate::core::{decompress, DecompressorOxide};
+    use miniz_oxide::inflate::TINFLStatus;
+
+    let (payload, header, footer, flags) = match format {
+        Format::Deflate => (data, 0, 0, 0),
+        Format::Zlib => (data, 0, 0, TINFL_FLAG_PARSE_ZLIB_HEADER),
+        Format::Gzip => {
+            let payload = gzip_payload(data);
+            (payload, data.len() - payload.len(), 8, 0)
+        }
+    };
+    let output = if format == Format::Zlib {
+        miniz_oxide::inflate::decompress_to_vec_zlib(payload)
+    } else {
+        miniz_oxide::inflate::decompress_to_vec(payload)
+    }
+    .ok()?;
+
+    // decode again into an exactly sized buffer, to find where the stream ends
+    let mut buffer = vec![0; output.len()];
+    let (status, bytes_read, _) = decompress(
+        &mut DecompressorOxide::new(),
+        payload,
+        &mut buffer,
+        0,
+        flags | TINFL_FLAG_USING_NON_WRAPPING_OUTPUT_BUF
+    );
+    assert_eq!(status, TINFLStatus::Done);
+
+    Some((output, header + bytes_read + footer))
+}
+
+/// Decode `data` in both modes and compare the output and
+/// the number of bytes read against miniz_oxide
+fn check(name: &str, data: &[u8], format: Format) -> Result<(), String> {
+    let expected = miniz_decode(data, format);
+
+    for reference_mode in [false, true] {
+        let found = zune_decode(data, format, reference_mode);
+
+        let reason = match (&found, &expected) {
+            (Some((found, _)), Some((expected, _))) if found != expected => {
+                let position = found
+                    .iter()
+                    .zip(expected)
+                    .position(|(a, b)| a != b)
+                    .unwrap_or(found.len().min(expected.len()));
+                format!(
+                    "output differs at byte {position}, lengths {} and {} (expected)",
+                    found.len(),
+                    expected.len()
+                )
+            }
+            (Some((_, found)), Some((_, expected))) if found != expected => {
+                format!("read {found} bytes, expected {expected}")
+            }
+            (Some(_), Some(_)) | (None, None) => continue,
+            (None, _) => "failed to decode a valid stream".to_string(),
+            (_, None) => "decoded a stream the reference rejects".to_string()
+        };
+        let mode = if reference_mode { "reference" } else { "default" };
+
+        return Err(format!("{name} ({format:?}, {mode} mode): {reason}"));
+    }
+    Ok(())
+}
+
+fn check_all(streams: impl Iterator<Item = (String, Vec<u8>, Format)>) {
+    let mut count = 0;
+    let errors: Vec<String> = streams
+        .inspect(|_| count += 1)
+        .filter_map(|(name, data, format)| check(&name, &data, format).err())
+        .collect();
+
+    assert!(count > 0, "No streams were tested");
+    assert!(errors.is_empty(), "Conformance errors\n{:#?}", errors);
+}
+
+fn read_dir_streams(dir: &Path) -> impl Iterator<Item = (String, Vec<u8>, Format)> {
+    let mut paths: Vec<_> = std::fs::read_dir(dir)
+        .unwrap()
+        .map(|x| x.unwrap().path())
+        .collect();
+    paths.sort();
+
+    paths.into_iter().filter_map(|path| {
+        let format = Format::from_path(&path)?;
+        Some((path.display().to_string(), read(&path).unwrap(), format))
+    })
+}
+
+#[test]
+fn test_zlib_vectors() {
+    check_all(read_dir_streams(&inflate_path().join("vectors")));
+}
+
+#[test]
+fn test_real_world_files() {
+    let path = inflate_path();
+    check_all(read_dir_streams(&path.join("zlib")).chain(read_dir_streams(&path.join("gzip"))));
+}
+
+#[test]
+fn test_external_corpus() {
+    let Some(dir) = std::env::var_os("ZUNE_INFLATE_CORPUS") else {
+        eprintln!("ZUNE_INFLATE_CORPUS is not set, skipping the external corpus");
+        return;
+    };
+    let mut paths: Vec<_> = std::fs::read_dir(dir)
+        .unwrap()
+        .map(|x| x.unwrap().path())
+        .filter(|x| x.is_file())
+        .collect();
+    paths.sort();
+
+    check_all(paths.into_iter().flat_map(|path| {
+        let data = read(&path).unwrap();
+        let name = path.display().to_string();
+
+        let mut streams: Vec<_> = (0..=10_u8)
+            .map(|level| {
+                let stream = miniz_oxide::deflate::compress_to_vec(&data, level);
+                (format!("{name} level {level}"), stream, Format::Deflate)
+            })
+            .collect();
+        let stream = miniz_oxide::deflate::compress_to_vec_zlib(&data, 6);
+        streams.push((name, stream, Format::Zlib));
+
+        streams
+    }));
+}
+
+/// Writes deflate streams bit by bit
+struct BitWriter {
+    out:    Vec<u8>,
+    buffer: u64,
+    bits:   u32
+}
+
+impl BitWriter {
+    fn new() -> BitWriter {
+        BitWriter {
+            out:    vec![],
+            buffer: 0,
+            bits:   0
+        }
+    }
+    /// Write `count` bits of `value`, least significant first
+    fn bits(&mut self, value: u32, count: u32) {
+        self.buffer |= u64::from(value) << self.bits;
+        self.bits += count;
+
+        while self.bits >= 8 {
+            self.out.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.bits -= 8;
+        }
+    }
+    /// Write a huffman code, most significant bit first
+    fn code(&mut self, (code, length): (u32, u32)) {
+        self.bits(code.reverse_bits() >> (32 - length), length);
+    }
+    /// Pad to a byte boundary
+    fn align(&mut self) {
+        if self.bits > 0 {
+            self.bits(0, 8 - self.bits);
+        }
+    }
+    fn finish(mut self) -> Vec<u8> {
+        self.align();
+        self.out
+    }
+}
+
+/// Canonical huffman codes for code lengths, as `(code, length)`
+fn canonical_codes(lengths: &[u32]) -> Vec<(u32, u32)> {
+    let mut next_code = [0; 16];
+    let mut code = 0;
+
+    for length in 1..16 {
+        let previous = lengths.iter().filter(|x| **x == length - 1).count() as u32;
+        code = (code + if length == 1 { 0 } else { previous }) << 1;
+        next_code[length as usize] = code;
+    }
+    lengths
+        .iter()
+        .map(|&length| {
+            if length == 0 {
+                return (0, 0);
+            }
+            let code = next_code[length as usize];
+            next_code[length as usize] += 1;
+            (code, length)
+        })
+        .collect()
+}
+
+/// Code lengths of a complete code where only symbols in `used` appear
+///
+/// A single symbol gets a lone one bit code
+fn complete_lengths(used: &[usize], alphabet: usize) -> Vec<u32> {
+    let mut lengths = vec![0; alphabet];
+    let n = used.len() as u32;
+
+    if n == 1 {
+        lengths[used[0]] = 1;
+        return lengths;
+    }
+    let k = n.next_power_of_two().trailing_zeros();
+    let short = (1 << k) - n;
+
+    for (position, symbol) in used.iter().enumerate() {
+        lengths[*symbol] = if (position as u32) < short { k - 1 } else { k };
+    }
+    lengths
+}
+
+const LENGTH_BASE: [u32; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258
+];
+const LENGTH_EXTRA: [u32; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0
+];
+const DIST_BASE: [u32; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577
+];
+const DIST_EXTRA: [u32; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13
+];
+
+/// Split a value into `(code, extra bit count, extra value)`
+fn split(value: u32, base: &[u32], extra: &[u32]) -> (usize, u32, u32) {
+    let code = base.iter().rposition(|x| *x <= value).unwrap();
+    (code, extra[code], value - base[code])
+}
+
+#[derive(Copy, Clone)]
+enum Token {
+    Literal(u8),
+    /// `(length, distance)`
+    Match(u32, u32)
+}
+
+fn fixed_lengths() -> (Vec<u32>, Vec<u32>) {
+    let literals = (0..288)
+        .map(|x| match x {
+            0..=143 => 8,
+            144..=255 => 9,
+            256..=279 => 7,
+            _ => 8
+        })
+        .collect();
+    (literals, vec![5; 30])
+}
+
+/// Write `tokens` and the end of block symbol
+fn write_tokens(writer: &mut BitWriter, tokens: &[Token], literals: &[u32], distances: &[u32]) {
+    let literal_codes = canonical_codes(literals);
+    let distance_codes = canonical_codes(distances);
+
+    for token in tokens {
+        match *token {
+            Token::Literal(x) => writer.code(literal_codes[usize::from(x)]),
+            Token::Match(length, distance) => {
+                let (code, extra, value) = split(length, &LENGTH_BASE, &LENGTH_EXTRA);
+                writer.code(literal_codes[257 + code]);
+                writer.bits(value, extra);
+
+                let (code, extra, value) = split(distance, &DIST_BASE, &DIST_EXTRA);
+                writer.code(distance_codes[code]);
+                writer.bits(value, extra);
+            }
+        }
+    }
+    writer.code(literal_codes[256]);
+}
+
+fn write_fixed_block(writer: &mut BitWriter, tokens: &[Token], last: bool) {
+    writer.bits(u32::from(last), 1);
+    writer.bits(1, 2);
+
+    let (literals, distances) = fixed_lengths();
+    write_tokens(writer, tokens, &literals, &distances);
+}
+
+fn write_stored_block(writer: &mut BitWriter, data: &[u8], last: bool) {
+    writer.bits(u32::from(last), 1);
+    writer.bits(0, 2);
+    writer.align();
+
+    let length = data.len() as u32;
+    writer.bits(length, 16);
+    writer.bits(!length & 0xFFFF, 16);
+    writer.out.extend_from_slice(data);
+}
+
+/// Write a dynamic block, `literals` must have at least 257 and `distances` at least one length
+fn write_dynamic_block(
+    writer: &mut BitWriter, tokens: &[Token], literals: &[u32], distances: &[u32], last: bool
+) {
+    const ORDER: [usize; 19] = [
+        16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15
+    ];
+
+    // run length encode both sets of lengths as one sequence, zero runs use 17 and 18
+    let all: Vec<u32> = literals.iter().chain(distances).copied().collect();
+    let mut precode = vec![];
+    let mut i = 0;
+
+    while i < all.len() {
+        let run = all[i..].iter().take_while(|x| **x == 0).count().min(138) as u32;
+
+        if run >= 11 {
+            precode.push((18, 7, run - 11));
+        } else if run >= 3 {
+            precode.push((17, 3, run - 3));
+        } else {
+            precode.push((all[i] as usize, 0, 0));
+            i += 1;
+            continue;
+        }
+        i += run as usize;
+    }
+    let mut used: Vec<usize> = precode.iter().map(|x| x.0).collect();
+    used.sort_unstable();
+    used.dedup();
+
+    let precode_lengths = complete_lengths(&used, 19);
+    let precode_codes = canonical_codes(&precode_lengths);
+    let hclen = ORDER
+        .iter()
+        .rposition(|x| precode_lengths[*x] != 0)
+        .unwrap()
+        .max(3)
+        + 1;
+
+    writer.bits(u32::from(last), 1);
+    writer.bits(2, 2);
+    writer.bits(literals.len() as u32 - 257, 5);
+    writer.bits(distances.len() as u32 - 1, 5);
+    writer.bits(hclen as u32 - 4, 4);
+
+    for symbol in &ORDER[..hclen] {
+        writer.bits(precode_lengths[*symbol], 3);
+    }
+    for (symbol, extra, value) in precode {
+        writer.code(precode_codes[symbol]);
+        writer.bits(value, extra);
+    }
+    write_tokens(writer, tokens, literals, distances);
+}
+
+/// Expected output of `tokens`
+fn expand(tokens: &[Token], output: &mut Vec<u8>) {
+    for token in tokens {
+        match *token {
+            Token::Literal(x) => output.push(x),
+            Token::Match(length, distance) => {
+                for _ in 0..length {
+                    output.push(output[output.len() - distance as usize]);
+                }
+            }
+        }
+    }
+}
+
+fn lcg_bytes(length: usize) -> Vec<u8> {
+    let mut state = 1_u32;
+    (0..length)
+        .map(|_| {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12345) & 0x7FFF_FFFF;
+            (state >> 16) as u8
+        })
+        .collect()
+}
+
+/// A hand assembled raw deflate stream
+struct Adversarial {
+    name:     &'static str,
+    stream:   Vec<u8>,
+    /// `None` if the stream is invalid
+    expected: Option<Vec<u8>>
+}
+
+fn adversarial_streams() -> Vec<Adversarial> {
+    let mut streams = vec![];
+
+    // matches overlapping their own output, at every short distance
+    let mut tokens = vec![
+        Token::Literal(b'x'),
+        Token::Match(258, 1),
+        Token::Match(3, 1),
+    ];
+    for distance in 2..=9 {
+        tokens.extend((0..distance).map(|x| Token::Literal(b'a' + x as u8)));
+        tokens.push(Token::Match(258, distance));
+        tokens.push(Token::Match(distance + 1, distance));
+    }
+    let mut writer = BitWriter::new();
+    write_fixed_block(&mut writer, &tokens, true);
+    let mut expected = vec![];
+    expand(&tokens, &mut expected);
+    streams.push(Adversarial {
+        name:     "overlapping matches",
+        stream:   writer.finish(),
+        expected: Some(expected)
+    });
+
+    // a dynamic block with no offset codes at all, only literals
+    let tokens: Vec<Token> = b"abbaababbbaaab"
+        .iter()
+        .map(|x| Token::Literal(*x))
+        .collect();
+    let literals = complete_lengths(&[usize::from(b'a'), usize::from(b'b'), 256], 257);
+    let mut writer = BitWriter::new();
+    write_dynamic_block(&mut writer, &tokens, &literals, &[0], true);
+    let mut expected = vec![];
+    expand(&tokens, &mut expected);
+    streams.push(Adversarial {
+        name:     "empty offset code",
+        stream:   writer.finish(),
+        expected: Some(expected)
+    });
+
+    // literals 0 and 139 are 138 zero lengths apart, the longest precode run,
+    // with a lone one bit offset code
+    let tokens = [
+        Token::Literal(0),
+        Token::Literal(139),
+        Token::Match(5, 1),
+        Token::Literal(255)
+    ];
+    let literals = complete_lengths(&[0, 139, 255, 256, 259], 260);
+    let distances = complete_lengths(&[0], 1);
+    let mut writer = BitWriter::new();
+    write_dynamic_block(&mut writer, &tokens, &literals, &distances, true);
+    let mut expected = vec![];
+    expand(&tokens, &mut expected);
+    streams.push(Adversarial {
+        name:     "maximal precode run",
+        stream:   writer.finish(),
+        expected: Some(expected)
+    });
+
+    // stored blocks of zero and maximum length followed by a match at the maximum distance
+    let data = lcg_bytes(65535);
+    let tokens = [Token::Match(258, 32768), Token::Match(258, 32768)];
+    let mut writer = BitWriter::new();
+    write_stored_block(&mut writer, &[], false);
+    write_stored_block(&mut writer, &data, false);
+    write_fixed_block(&mut writer, &tokens, true);
+    let mut expected = data;
+    expand(&tokens, &mut expected);
+    streams.push(Adversarial {
+        name:     "maximum distance",
+        stream:   writer.finish(),
+        expected: Some(expected)
+    });
+
+    // a match reaching before the start of the output
+    let mut writer = BitWriter::new();
+    write_fixed_block(&mut writer, &[Token::Literal(1), Token::Match(3, 2)], true);
+    streams.push(Adversarial {
+        name:     "distance too far",
+        stream:   writer.finish(),
+        expected: None
+    });
+
+    // more codes than a complete tree holds
+    let mut writer = BitWriter::new();
+    write_dynamic_block(&mut writer, &[], &[1; 257], &[1], true);
+    streams.push(Adversarial {
+        name:     "oversubscribed code",
+        stream:   writer.finish(),
+        expected: None
+    });
+
+    streams
+}
+
+#[test]
+fn test_adversarial_streams() {
+    let mut errors = vec![];
+
+    for Adversarial {
+        name,
+        stream,
+        expected
+    } in adversarial_streams()
+    {
+        // confirm the stream was built right before comparing against it
+        assert_eq!(
+            miniz_decode(&stream, Format::Deflate).map(|x| x.0),
+            expected,
+            "{name} was not assembled as intended"
+        );
+        if let Err(err) = check(name, &stream, Format::Deflate) {
+            errors.push(err);
+        }
+    }
+    assert!(errors.is_empty(), "Conformance errors\n{:#?}", errors);
+}
@@ -17,7 +17,13 @@ use zune_core::colorspace::ColorSpace;
 use zune_core::options::DecoderOptions;
 
 mod bmp;
+#[cfg(all(test, feature = "conformance"))]
+mod encode_conformance;
 mod inflate;
+#[cfg(test)]
+mod inflate_bitstream;
+#[cfg(test)]
+mod inflate_conformance;
 mod jpeg;
 mod png;
 mod psd;